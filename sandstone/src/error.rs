@@ -0,0 +1,96 @@
+//! The crate-wide error type. Each module has its own error type for the things that can go wrong
+//! inside of it, and [SandstoneError] brings them together so that an application can use `?` on any
+//! sandstone call and still match on what kind of failure it was.
+
+use std::io;
+
+use thiserror::Error;
+
+use crate::network::network_error::NetworkError;
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::nbt::nbt_error::NbtError;
+#[cfg(feature = "mojang")]
+use crate::util::mojang::http::HttpError;
+use crate::world::world_error::WorldError;
+
+/// The result of any sandstone operation, see [SandstoneError]
+pub type SandstoneResult<T> = Result<T, SandstoneError>;
+
+/// Any error produced by sandstone. Errors that are wrapped by a [NetworkError] are unwrapped when
+/// converted, so that IO, serialization and authentication failures always end up in the same variant
+/// no matter which API returned them.
+#[derive(Error, Debug)]
+pub enum SandstoneError {
+	/// Something went wrong with the connection or the protocol state, such as receiving an unexpected
+	/// packet or the other side disconnecting
+	#[error(transparent)]
+	Network(NetworkError),
+	/// Data could not be turned into bytes or read back from them
+	#[error(transparent)]
+	Serializing(#[from] SerializingErr),
+	#[error(transparent)]
+	Nbt(#[from] NbtError),
+	#[cfg(feature = "mojang")]
+	/// A request to the Mojang API or session server failed, or the player couldn't be authenticated
+	#[error(transparent)]
+	Auth(#[from] HttpError),
+	/// A world couldn't be loaded or saved
+	#[error(transparent)]
+	World(#[from] WorldError),
+	#[error(transparent)]
+	Io(#[from] io::Error),
+}
+
+impl SandstoneError {
+	/// Whether this error means that the connection is gone, rather than that something went wrong on
+	/// a connection that is still open
+	pub fn is_disconnect(&self) -> bool {
+		match self {
+			SandstoneError::Network(e) => matches!(e, NetworkError::NoDataReceived | NetworkError::ConnectionAbortedLocally | NetworkError::ConnectionAbortedRemotely | NetworkError::Disconnected(_)),
+			SandstoneError::Io(e) => matches!(e.kind(), io::ErrorKind::UnexpectedEof | io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted | io::ErrorKind::BrokenPipe),
+			_ => false,
+		}
+	}
+}
+
+impl From<NetworkError> for SandstoneError {
+	fn from(e: NetworkError) -> Self {
+		match e {
+			NetworkError::SerializingErr(e) => SandstoneError::Serializing(e),
+			NetworkError::IOError(e) => SandstoneError::Io(e),
+			#[cfg(feature = "mojang")]
+			NetworkError::HttpError(e) => SandstoneError::Auth(e),
+			e => SandstoneError::Network(e),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io;
+
+	use crate::error::SandstoneError;
+	use crate::network::network_error::NetworkError;
+	use crate::protocol::serialization::serializer_error::SerializingErr;
+	#[cfg(feature = "mojang")]
+	use crate::util::mojang::http::HttpError;
+
+	#[test]
+	fn test_error_flattening() {
+		let e: SandstoneError = NetworkError::SerializingErr(SerializingErr::OutOfBounds).into();
+		assert!(matches!(e, SandstoneError::Serializing(SerializingErr::OutOfBounds)));
+
+		#[cfg(feature = "mojang")]
+		{
+			let e: SandstoneError = NetworkError::HttpError(HttpError::NotAuthenticated).into();
+			assert!(matches!(e, SandstoneError::Auth(HttpError::NotAuthenticated)));
+		}
+
+		let e: SandstoneError = NetworkError::InvalidPacketState.into();
+		assert!(matches!(e, SandstoneError::Network(NetworkError::InvalidPacketState)));
+		assert!(!e.is_disconnect());
+
+		assert!(SandstoneError::from(NetworkError::NoDataReceived).is_disconnect());
+		assert!(SandstoneError::from(NetworkError::IOError(io::ErrorKind::BrokenPipe.into())).is_disconnect());
+	}
+}
@@ -7,7 +7,12 @@
 //! See the project on GitHub [dec4234/sandstone](https://www.github.com/dec4234/sandstone)
 //! or crate.io [sandstone](https://crates.io/crates/sandstone) for more info.
 
+// lets the derive macros refer to `::sandstone` from inside of this crate as well
+extern crate self as sandstone;
+
 pub mod protocol_types;
 pub mod util;
 pub mod protocol;
 pub mod network;
+pub mod world;
+pub mod error;
@@ -1,32 +1,120 @@
 //! Lists the traits used to handle packet sequences from the client. These are included so that you can
 //! override the default functionality for your own purposes.
+//!
+//! [ServerHandler] covers the whole connection in one place, while the other traits each replace a single
+//! step of it for [CraftServer::start](crate::network::server::CraftServer::start).
+//!
+//! The returned futures must be `Send` so that each connection can be handled on its own task, see
+//! [CraftServer](crate::network::server::CraftServer). Implementations can still just use `async fn`.
+
+use std::future::Future;
+use std::sync::Arc;
 
 use crate::network::client::CraftClient;
 use crate::network::network_error::NetworkError;
-use crate::protocol::packets::StatusResponseBody;
+use crate::network::server::StatusSupplier;
+use crate::protocol::commands::suggestions::Suggestions;
+use crate::protocol::packets::{HandshakingBody, LoginStartBody, Packet};
+use crate::protocol::status::status_components::StatusResponseSpec;
 
 /// The procedure required to handle a handshake. Check [DefaultHandshakeHandler] for a default implementation.
 ///
 /// If you would like to implement it yourself then check [here](https://wiki.vg/Protocol#Handshake)
 pub trait HandshakeHandler {
-	async fn handle_handshake(client: &mut CraftClient) -> Result<(), NetworkError>;
+	fn handle_handshake(client: &mut CraftClient) -> impl Future<Output = Result<(), NetworkError>> + Send;
 }
 
 /// Lists the methods required to handle a status request. Check [DefaultStatusHandler] for a default implementation.
 ///
+/// `status` builds the response from a [StatusContext](crate::protocol::status::StatusContext) describing
+/// the client, so it should be called once the status request arrives.
+///
 /// The status procedure can be found [here](https://wiki.vg/Server_List_Ping)
 pub trait StatusHandler {
-	async fn handle_status<P: PingHandler>(connection: &mut CraftClient, status_response: StatusResponseBody, ping_handler: P) -> Result<(), NetworkError>;
+	fn handle_status<P: PingHandler + Send>(connection: &mut CraftClient, status: StatusSupplier, ping_handler: P) -> impl Future<Output = Result<(), NetworkError>> + Send;
 }
 
 /// Lists the methods required to handle a ping request. Check [DefaultPingHandler] for a default implementation.
 ///
 /// The ping procedure can be found [here](https://wiki.vg/Server_List_Ping)
 pub trait PingHandler {
-	async fn handle_ping(connection: &mut CraftClient) -> Result<(), NetworkError>;
+	fn handle_ping(connection: &mut CraftClient) -> impl Future<Output = Result<(), NetworkError>> + Send;
 }
 
 /// Lists the methods required to handle a login request. Check [DefaultLoginHandler] for a default implementation.
+///
+/// [LoginFlow](crate::protocol::login::flow::LoginFlow) takes care of the login sequence, including
+/// encryption and authentication in online mode, so implementations only need to decide who may join.
 pub trait LoginHandler {
-	fn handle_login(connection: &mut CraftClient) -> Result<(), NetworkError>;
-}
\ No newline at end of file
+	fn handle_login(connection: &mut CraftClient) -> impl Future<Output = Result<(), NetworkError>> + Send;
+}
+
+/// The events of a connection, driven by [CraftServer::run](crate::network::server::CraftServer::run). The
+/// server reads the packets and moves the connection between states, so applications only implement the
+/// events they care about. Every method has a default that behaves like the default handlers.
+///
+/// Returning an error from any method drops the connection.
+pub trait ServerHandler: Send + Sync + 'static {
+	/// Called once the handshake has been received, after the connection has moved to the state that the
+	/// client asked for.
+	fn on_handshake(&self, _client: &mut CraftClient, _handshake: &HandshakingBody) -> impl Future<Output = Result<(), NetworkError>> + Send {
+		async { Ok(()) }
+	}
+
+	/// Called when the client asks for the server status. `status` comes from the server's status
+	/// supplier, and whatever is returned is sent to the client.
+	fn on_status_request(&self, _client: &mut CraftClient, status: StatusResponseSpec) -> impl Future<Output = Result<StatusResponseSpec, NetworkError>> + Send {
+		async { Ok(status) }
+	}
+
+	/// Called with the Login Start packet. This is responsible for the rest of the login sequence, which
+	/// [LoginFlow::resume](crate::protocol::login::flow::LoginFlow::resume) can take care of. If the connection
+	/// has reached the play state when it returns, every packet after that is given to
+	/// [ServerHandler::on_play_packet]. By default the client is disconnected.
+	fn on_login_start(&self, client: &mut CraftClient, _login: LoginStartBody) -> impl Future<Output = Result<(), NetworkError>> + Send {
+		async { client.disconnect("This server does not support logging in").await }
+	}
+
+	/// Called with every packet received in the play state. These are ignored by default.
+	fn on_play_packet(&self, _client: &mut CraftClient, _packet: Packet) -> impl Future<Output = Result<(), NetworkError>> + Send {
+		async { Ok(()) }
+	}
+
+	/// Called when the client asks for suggestions for the command it is typing, which it only does for
+	/// arguments that use [SuggestionType::AskServer](crate::protocol::commands::tree::SuggestionType::AskServer).
+	/// `command` is the text typed so far, and the suggestions are sent back to the client. Nothing is sent
+	/// for `None`, which is the default. These requests don't go to [ServerHandler::on_play_packet].
+	fn on_command_suggestions(&self, _client: &mut CraftClient, _command: &str) -> impl Future<Output = Result<Option<Suggestions>, NetworkError>> + Send {
+		async { Ok(None) }
+	}
+
+	/// Called once the server is done with the connection, with the error that ended it if there was one
+	fn on_disconnect(&self, _client: &CraftClient, _error: Option<&NetworkError>) {}
+}
+
+/// Lets the application keep a handle to its handler while the server is running
+impl<T: ServerHandler> ServerHandler for Arc<T> {
+	fn on_handshake(&self, client: &mut CraftClient, handshake: &HandshakingBody) -> impl Future<Output = Result<(), NetworkError>> + Send {
+		(**self).on_handshake(client, handshake)
+	}
+
+	fn on_status_request(&self, client: &mut CraftClient, status: StatusResponseSpec) -> impl Future<Output = Result<StatusResponseSpec, NetworkError>> + Send {
+		(**self).on_status_request(client, status)
+	}
+
+	fn on_login_start(&self, client: &mut CraftClient, login: LoginStartBody) -> impl Future<Output = Result<(), NetworkError>> + Send {
+		(**self).on_login_start(client, login)
+	}
+
+	fn on_play_packet(&self, client: &mut CraftClient, packet: Packet) -> impl Future<Output = Result<(), NetworkError>> + Send {
+		(**self).on_play_packet(client, packet)
+	}
+
+	fn on_command_suggestions(&self, client: &mut CraftClient, command: &str) -> impl Future<Output = Result<Option<Suggestions>, NetworkError>> + Send {
+		(**self).on_command_suggestions(client, command)
+	}
+
+	fn on_disconnect(&self, client: &CraftClient, error: Option<&NetworkError>) {
+		(**self).on_disconnect(client, error)
+	}
+}
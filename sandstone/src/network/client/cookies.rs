@@ -0,0 +1,165 @@
+//! Cookies let a server store a little data on the client, which the client keeps when it is transferred
+//! to another server. A network of servers can use them to pass along a session, so that the next server
+//! knows where the player came from. Cookies were added in 1.20.5, and can be requested during login,
+//! configuration and play but only stored during configuration and play.
+//!
+//! Every [CraftClient](crate::network::client::CraftClient) keeps the cookies it has seen in a [CookieStore]. On the server side that is what
+//! the client reported or was told to store, and for a [CraftConnector](crate::network::connector::CraftConnector)
+//! it is the cookies that servers stored, which it answers requests with.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::{ConfigCookieRequestBody, ConfigCookieResponseBody, LoginCookieRequestBody, LoginCookieResponseBody, Packet, PlayCookieRequestBody, PlayCookieResponseBody, PlayStoreCookieBody, StoreCookieBody};
+use crate::protocol::packets::packet_definer::PacketState;
+use crate::protocol_types::datatypes::identifier::Identifier;
+
+/// The largest cookie the vanilla client stores, in bytes
+pub const MAX_COOKIE_SIZE: usize = 5120;
+
+/// How long [CraftClient::request_cookie](crate::network::client::CraftClient::request_cookie) waits for the answer
+pub const DEFAULT_COOKIE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The cookies known for a connection, see the [module docs](self)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CookieStore {
+	cookies: HashMap<Identifier, Vec<u8>>,
+}
+
+impl CookieStore {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn get(&self, key: &Identifier) -> Option<&[u8]> {
+		self.cookies.get(key).map(Vec::as_slice)
+	}
+
+	/// Store a cookie, returning the one it replaced. Cookies over [MAX_COOKIE_SIZE] are rejected with
+	/// [NetworkError::PacketTooLarge].
+	pub fn insert(&mut self, key: Identifier, payload: Vec<u8>) -> Result<Option<Vec<u8>>, NetworkError> {
+		if payload.len() > MAX_COOKIE_SIZE {
+			return Err(NetworkError::PacketTooLarge);
+		}
+
+		Ok(self.cookies.insert(key, payload))
+	}
+
+	pub fn remove(&mut self, key: &Identifier) -> Option<Vec<u8>> {
+		self.cookies.remove(key)
+	}
+
+	pub fn len(&self) -> usize {
+		self.cookies.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.cookies.is_empty()
+	}
+
+	pub fn clear(&mut self) {
+		self.cookies.clear();
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = (&Identifier, &[u8])> {
+		self.cookies.iter().map(|(k, v)| (k, v.as_slice()))
+	}
+
+	/// The answer to a cookie request from the server, or `None` if the packet isn't one
+	pub fn respond(&self, request: &Packet) -> Option<Packet> {
+		let (key, state) = match request {
+			Packet::LoginCookieRequest(body) => (&body.key, PacketState::LOGIN),
+			Packet::ConfigCookieRequest(body) => (&body.key, PacketState::CONFIGURATION),
+			Packet::PlayCookieRequest(body) => (&body.key, PacketState::PLAY),
+			_ => return None,
+		};
+
+		let payload = self.get(key).map(<[u8]>::to_vec);
+
+		match state {
+			PacketState::LOGIN => Some(Packet::LoginCookieResponse(LoginCookieResponseBody::new(key.clone(), payload))),
+			PacketState::CONFIGURATION => Some(Packet::ConfigCookieResponse(ConfigCookieResponseBody::new(key.clone(), payload))),
+			_ => Some(Packet::PlayCookieResponse(PlayCookieResponseBody::new(key.clone(), payload))),
+		}
+	}
+
+	/// Keep track of the cookies in a received packet, returning the key if it had one. Responses tell
+	/// the server which cookies the client has, and stores are kept by the client.
+	pub(crate) fn record(&mut self, packet: &Packet) -> Option<Identifier> {
+		let (key, payload) = match packet {
+			Packet::LoginCookieResponse(body) => (&body.key, body.payload.as_ref()),
+			Packet::ConfigCookieResponse(body) => (&body.key, body.payload.as_ref()),
+			Packet::PlayCookieResponse(body) => (&body.key, body.payload.as_ref()),
+			Packet::StoreCookie(body) => (&body.key, Some(&body.payload)),
+			Packet::PlayStoreCookie(body) => (&body.key, Some(&body.payload)),
+			_ => return None,
+		};
+
+		match payload {
+			Some(payload) if payload.len() <= MAX_COOKIE_SIZE => {
+				self.cookies.insert(key.clone(), payload.clone());
+			}
+			_ => {
+				self.cookies.remove(key);
+			}
+		}
+
+		Some(key.clone())
+	}
+}
+
+/// The cookie request for the state that the connection is in
+pub(crate) fn request_packet(state: PacketState, key: Identifier) -> Result<Packet, NetworkError> {
+	match state {
+		PacketState::LOGIN => Ok(Packet::LoginCookieRequest(LoginCookieRequestBody::new(key))),
+		PacketState::CONFIGURATION => Ok(Packet::ConfigCookieRequest(ConfigCookieRequestBody::new(key))),
+		PacketState::PLAY => Ok(Packet::PlayCookieRequest(PlayCookieRequestBody::new(key))),
+		_ => Err(NetworkError::InvalidPacketState),
+	}
+}
+
+/// The packet that stores a cookie in the state that the connection is in
+pub(crate) fn store_packet(state: PacketState, key: Identifier, payload: Vec<u8>) -> Result<Packet, NetworkError> {
+	match state {
+		PacketState::CONFIGURATION => Ok(Packet::StoreCookie(StoreCookieBody::new(key, payload))),
+		PacketState::PLAY => Ok(Packet::PlayStoreCookie(PlayStoreCookieBody::new(key, payload))),
+		_ => Err(NetworkError::InvalidPacketState),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::network::client::cookies::{CookieStore, MAX_COOKIE_SIZE};
+	use crate::network::connector::CraftConnector;
+	use crate::network::network_error::NetworkError;
+	use crate::network::server::server_testing::configured_pair_with;
+	use crate::protocol::packets::Packet;
+
+	#[tokio::test]
+	async fn test_cookies() {
+		let prepare = |connector: &mut CraftConnector| {
+			connector.cookies().insert("test:session".parse().unwrap(), b"abc".to_vec()).unwrap();
+		};
+
+		let (mut client, mut connector) = configured_pair_with(prepare, |client| Box::pin(async move {
+			assert_eq!(client.request_cookie("test:session".parse().unwrap()).await.unwrap(), Some(b"abc".to_vec()));
+			assert_eq!(client.request_cookie("test:missing".parse().unwrap()).await.unwrap(), None);
+			assert_eq!(client.cookies.len(), 1);
+			assert_eq!(client.store_cookie("test:session".parse().unwrap(), b"def".to_vec()).await, Err(NetworkError::InvalidPacketState));
+		})).await;
+
+		client.store_cookie("test:session".parse().unwrap(), b"def".to_vec()).await.unwrap();
+		assert_eq!(client.cookies.get(&"test:session".parse().unwrap()), Some(&b"def"[..]));
+
+		assert!(matches!(connector.receive_packet().await.unwrap(), Packet::StoreCookie(_)));
+		assert_eq!(connector.cookies().get(&"test:session".parse().unwrap()), Some(&b"def"[..]));
+	}
+
+	#[test]
+	fn test_cookie_size() {
+		let mut store = CookieStore::new();
+		assert_eq!(store.insert("test:big".parse().unwrap(), vec![0; MAX_COOKIE_SIZE + 1]), Err(NetworkError::PacketTooLarge));
+		assert_eq!(store.insert("test:big".parse().unwrap(), vec![0; MAX_COOKIE_SIZE]), Ok(None));
+	}
+}
@@ -0,0 +1,308 @@
+//! Sends Keep Alive packets during the configuration and play states and checks that the client answers
+//! them. The vanilla client disconnects if it doesn't receive one for 20 seconds, and the vanilla server
+//! disconnects clients that don't answer within 30 seconds.
+//!
+//! [KeepAlive] doesn't do any IO itself apart from [KeepAlive::tick], so it can be driven from a tick loop
+//! or a `select!` with either a [CraftClient] or the halves of a split connection. [KeepAlive::keep_alive_while]
+//! does that loop for you, which is handy for holding a client in configuration while the world is prepared.
+//!
+//! Ping and Pong work like Keep Alive with an `i32` payload, but the client answers them right away and
+//! they don't time out, so they can be used to tell when the client has handled the packets before them.
+
+use std::future::Future;
+use std::time::Duration;
+
+use log::debug;
+use tokio::time::Instant;
+
+use crate::network::client::CraftClient;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::{ConfigurationPingBody, KeepAliveBody, Packet, PlayKeepAliveBody, PlayPingBody};
+use crate::protocol::packets::packet_definer::PacketState;
+use crate::protocol_types::datatypes::chat::TextComponent;
+
+/// How often vanilla sends a Keep Alive
+pub const DEFAULT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+/// How long vanilla waits for the client to answer
+pub const DEFAULT_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Keeps track of the Keep Alive sent to a single client, and how long the client took to answer the
+/// last one.
+///
+/// ```no_run
+/// # use sandstone::network::client::CraftClient;
+/// # use sandstone::network::client::keep_alive::KeepAlive;
+/// # use sandstone::network::network_error::NetworkError;
+/// # async fn run(client: &mut CraftClient) -> Result<(), NetworkError> {
+/// let mut keep_alive = KeepAlive::new();
+///
+/// loop {
+///     tokio::select! {
+///         _ = tokio::time::sleep_until(keep_alive.next_deadline()) => keep_alive.tick(client).await?,
+///         packet = client.receive_packet() => {
+///             let packet = packet?;
+///
+///             if !keep_alive.handle_packet(&packet) {
+///                 // handle the packet
+///             }
+///         }
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeepAlive {
+	interval: Duration,
+	timeout: Duration,
+	/// When the next Keep Alive should be sent, if none is waiting for an answer
+	next_send: Instant,
+	/// The ID and send time of the Keep Alive that hasn't been answered yet
+	pending: Option<(i64, Instant)>,
+	latency: Option<Duration>,
+}
+
+impl KeepAlive {
+	/// Create a tracker with the vanilla interval and timeout. The first Keep Alive is sent one interval
+	/// from now.
+	pub fn new() -> Self {
+		Self::with_timing(DEFAULT_KEEP_ALIVE_INTERVAL, DEFAULT_KEEP_ALIVE_TIMEOUT)
+	}
+
+	/// Create a tracker that sends a Keep Alive every `interval`, and fails if the client takes longer
+	/// than `timeout` to answer one.
+	pub fn with_timing(interval: Duration, timeout: Duration) -> Self {
+		Self {
+			interval,
+			timeout,
+			next_send: Instant::now() + interval,
+			pending: None,
+			latency: None,
+		}
+	}
+
+	/// Get the Keep Alive packet that should be sent now, if one is due. Only the configuration and play
+	/// states have Keep Alive packets, so in any other state this returns `None` and puts the next one off
+	/// for another interval.
+	///
+	/// This fails with [NetworkError::KeepAliveTimedOut] once the client has taken too long to answer.
+	pub fn poll(&mut self, state: PacketState) -> Result<Option<Packet>, NetworkError> {
+		let now = Instant::now();
+
+		if let Some((_, sent)) = self.pending {
+			if now.duration_since(sent) >= self.timeout {
+				return Err(NetworkError::KeepAliveTimedOut);
+			}
+
+			return Ok(None);
+		}
+
+		if now < self.next_send {
+			return Ok(None);
+		}
+
+		let id = rand::random::<i64>();
+
+		let packet = match state {
+			PacketState::CONFIGURATION => Packet::KeepAlive(KeepAliveBody::new(id)),
+			PacketState::PLAY => Packet::PlayKeepAlive(PlayKeepAliveBody::new(id)),
+			_ => {
+				self.next_send = now + self.interval;
+				return Ok(None);
+			}
+		};
+
+		self.pending = Some((id, now));
+
+		Ok(Some(packet))
+	}
+
+	/// Send a Keep Alive to the client if one is due. If the client took too long to answer the last one,
+	/// it is disconnected the same way vanilla does and [NetworkError::KeepAliveTimedOut] is returned.
+	pub async fn tick(&mut self, client: &mut CraftClient) -> Result<(), NetworkError> {
+		match self.poll(client.packet_state) {
+			Ok(Some(packet)) => client.send_packet(packet).await,
+			Ok(None) => Ok(()),
+			Err(e) => {
+				debug!("{} didn't answer a Keep Alive in time", client);
+				client.disconnect(TextComponent::translatable("disconnect.timeout", vec![])).await?;
+				Err(e)
+			}
+		}
+	}
+
+	/// Check a packet received from the client. This returns `true` if it answered the last Keep Alive,
+	/// in which case the packet doesn't need to be handled any further. Answers with the wrong ID are
+	/// ignored, so the client will time out if it never sends the right one.
+	pub fn handle_packet(&mut self, packet: &Packet) -> bool {
+		let id = match packet {
+			Packet::ServerboundKeepAlive(body) => body.keep_alive_id,
+			Packet::PlayServerboundKeepAlive(body) => body.keep_alive_id,
+			_ => return false,
+		};
+
+		match self.pending {
+			Some((pending, sent)) if pending == id => {
+				let now = Instant::now();
+
+				self.latency = Some(now.duration_since(sent));
+				self.pending = None;
+				self.next_send = sent + self.interval;
+
+				true
+			}
+			_ => false,
+		}
+	}
+
+	/// The time it took the client to answer the last Keep Alive, which is what vanilla shows as the ping
+	/// in the player list. This is `None` until the first answer is received.
+	pub fn latency(&self) -> Option<Duration> {
+		self.latency
+	}
+
+	/// Check if a Keep Alive has been sent that the client hasn't answered yet
+	pub fn is_waiting(&self) -> bool {
+		self.pending.is_some()
+	}
+
+	/// Keep the client alive until `future` finishes, answering its Keep Alives. Other packets the client
+	/// sends in the meantime, such as `ClientInformation` during configuration, are returned along with
+	/// the output so they can be handled afterwards.
+	pub async fn keep_alive_while<F: Future>(&mut self, client: &mut CraftClient, future: F) -> Result<(F::Output, Vec<Packet>), NetworkError> {
+		let mut received = vec![];
+		tokio::pin!(future);
+
+		loop {
+			tokio::select! {
+				output = &mut future => return Ok((output, received)),
+				_ = tokio::time::sleep_until(self.next_deadline()) => self.tick(client).await?,
+				packet = client.receive_packet() => {
+					let packet = packet?;
+
+					if !self.handle_packet(&packet) {
+						received.push(packet);
+					}
+				}
+			}
+		}
+	}
+
+	/// The next time that [KeepAlive::tick] has something to do, either sending a Keep Alive or timing out
+	/// the one that is waiting for an answer.
+	pub fn next_deadline(&self) -> Instant {
+		match self.pending {
+			Some((_, sent)) => sent + self.timeout,
+			None => self.next_send,
+		}
+	}
+}
+
+/// The Ping packet for the state that the connection is in. Only the configuration and play states have
+/// one.
+pub fn ping_packet(state: PacketState, payload: i32) -> Result<Packet, NetworkError> {
+	match state {
+		PacketState::CONFIGURATION => Ok(Packet::ConfigurationPing(ConfigurationPingBody::new(payload))),
+		PacketState::PLAY => Ok(Packet::PlayPing(PlayPingBody::new(payload))),
+		_ => Err(NetworkError::InvalidPacketState),
+	}
+}
+
+/// The payload of a Pong from the client, or `None` if the packet isn't one
+pub fn pong_payload(packet: &Packet) -> Option<i32> {
+	match packet {
+		Packet::ConfigurationPong(body) => Some(body.payload),
+		Packet::PlayPong(body) => Some(body.payload),
+		_ => None,
+	}
+}
+
+impl Default for KeepAlive {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use crate::network::client::keep_alive::{KeepAlive, ping_packet, pong_payload};
+	use crate::network::network_error::NetworkError;
+	use crate::network::server::server_testing::configured_pair;
+	use crate::protocol::packets::{ClientInformationBody, ConfigurationPongBody, Packet, PlayServerboundKeepAliveBody, ServerboundKeepAliveBody};
+	use crate::protocol::packets::packet_definer::PacketState;
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	#[tokio::test]
+	async fn test_keep_alive() {
+		let mut keep_alive = KeepAlive::with_timing(Duration::from_millis(50), Duration::from_millis(100));
+
+		assert_eq!(keep_alive.poll(PacketState::PLAY), Ok(None));
+		tokio::time::sleep_until(keep_alive.next_deadline()).await;
+
+		// there is no keep alive before configuration, so it waits until the next deadline
+		let deadline = keep_alive.next_deadline();
+		assert_eq!(keep_alive.poll(PacketState::LOGIN), Ok(None));
+		assert!(keep_alive.next_deadline() > deadline);
+		tokio::time::sleep_until(keep_alive.next_deadline()).await;
+
+		let id = match keep_alive.poll(PacketState::PLAY) {
+			Ok(Some(Packet::PlayKeepAlive(body))) => body.keep_alive_id,
+			other => panic!("Expected a keep alive, got {:?}", other),
+		};
+
+		assert!(keep_alive.is_waiting());
+		assert_eq!(keep_alive.poll(PacketState::PLAY), Ok(None));
+
+		tokio::time::sleep(Duration::from_millis(20)).await;
+		assert!(!keep_alive.handle_packet(&Packet::PlayServerboundKeepAlive(PlayServerboundKeepAliveBody::new(id.wrapping_add(1)))));
+		assert!(keep_alive.handle_packet(&Packet::PlayServerboundKeepAlive(PlayServerboundKeepAliveBody::new(id))));
+		assert!(keep_alive.latency().unwrap() >= Duration::from_millis(20));
+
+		// this time the client never answers
+		tokio::time::sleep_until(keep_alive.next_deadline()).await;
+		assert!(matches!(keep_alive.poll(PacketState::CONFIGURATION), Ok(Some(Packet::KeepAlive(_)))));
+		assert!(!keep_alive.handle_packet(&Packet::ServerboundKeepAlive(ServerboundKeepAliveBody::new(id))));
+
+		tokio::time::sleep_until(keep_alive.next_deadline()).await;
+		assert_eq!(keep_alive.poll(PacketState::CONFIGURATION), Err(NetworkError::KeepAliveTimedOut));
+	}
+
+	#[tokio::test]
+	async fn test_keep_alive_while_configuring() {
+		let (mut client, mut connector) = configured_pair().await;
+
+		let server = tokio::spawn(async move {
+			// the world takes longer to prepare than the client has to answer
+			let mut keep_alive = KeepAlive::with_timing(Duration::from_millis(20), Duration::from_millis(50));
+			let (output, received) = keep_alive.keep_alive_while(&mut client, async {
+				tokio::time::sleep(Duration::from_millis(200)).await;
+				5
+			}).await.unwrap();
+
+			assert_eq!(output, 5);
+			assert!(matches!(received.as_slice(), [Packet::ClientInformation(_)]));
+			assert!(keep_alive.latency().is_some());
+
+			client.send_packet(ping_packet(client.packet_state, output).unwrap()).await.unwrap();
+			let packet = client.receive_packet().await.unwrap();
+			assert_eq!(pong_payload(&packet), Some(5));
+			assert_eq!(ping_packet(PacketState::LOGIN, 0), Err(NetworkError::InvalidPacketState));
+		});
+
+		connector.send_packet(Packet::ClientInformation(ClientInformationBody::new("en_us".to_string(), 8, VarInt(0), true, 0x7F, VarInt(1), false, true))).await.unwrap();
+
+		loop {
+			match connector.receive_packet().await.unwrap() {
+				Packet::KeepAlive(body) => connector.send_packet(Packet::ServerboundKeepAlive(ServerboundKeepAliveBody::new(body.keep_alive_id))).await.unwrap(),
+				Packet::ConfigurationPing(body) => {
+					connector.send_packet(Packet::ConfigurationPong(ConfigurationPongBody::new(body.payload))).await.unwrap();
+					break;
+				}
+				p => panic!("Unexpected packet {:?}", p),
+			}
+		}
+
+		server.await.unwrap();
+	}
+}
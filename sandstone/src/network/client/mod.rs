@@ -3,25 +3,58 @@
 //! change the packet state of the connection.
 
 use std::fmt::Display;
+use std::io::ErrorKind;
 use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
 
+use bytes::BytesMut;
 use log::{debug, trace};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
-
+use tokio::time::Instant;
+use tokio_util::io::poll_read_buf;
+use uuid::Uuid;
+
+use crate::network::compression::{compress_packet, decompress_packet, DEFAULT_COMPRESSION_LEVEL};
+use crate::network::encryption::CraftCipher;
+use crate::network::client::cookies::{CookieStore, DEFAULT_COOKIE_TIMEOUT};
+use crate::network::client::resource_packs::{PackCompletion, ResourcePack, ResourcePackTracker};
+use crate::network::client::split::{CraftReader, CraftWriter};
+use crate::network::client::stats::ConnectionStats;
 use crate::network::network_error::NetworkError;
-use crate::protocol::packets::Packet;
-use crate::protocol::packets::packet_definer::{PacketDirection, PacketState};
+use crate::network::server::guard::HalfOpenPermit;
+use crate::protocol::packets::{ConfigDisconnectBody, DisconnectBody, HandshakingBody, Packet, PlayDisconnectBody, PlayTransferBody, SetCompressionBody, TransferBody};
+use crate::protocol::packets::packet_definer::{HandshakeIntent, PacketDirection, PacketState};
+use crate::protocol::play::title;
+use crate::protocol::play::title::Title;
+use crate::protocol::plugin_message::PluginMessage;
 use crate::protocol::serialization::{McDeserializer, McSerialize, McSerializer, StateBasedDeserializer};
 use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::chat::TextComponent;
+use crate::protocol_types::datatypes::identifier::Identifier;
 use crate::protocol_types::datatypes::var_types::VarInt;
 use crate::protocol_types::protocol_verison::ProtocolVerison;
 
 pub mod client_handlers;
-
-const PACKET_MAX_SIZE: usize = 2097151;  // max of 3 byte VarInt
+pub mod cookies;
+pub mod keep_alive;
+pub mod resource_packs;
+pub mod split;
+pub mod stats;
+
+pub(crate) const PACKET_MAX_SIZE: usize = 2097151;  // max of 3 byte VarInt
+/// The default limit for packets sent before the player has logged in. Nothing sent during the
+/// handshake, status or login states comes close to this, so anything bigger is most likely an attack.
+pub const DEFAULT_PRE_LOGIN_PACKET_LIMIT: usize = 32767;
+/// How much of the send buffer is kept between packets. Anything above this is freed after a packet
+/// is sent, so that a single large packet doesn't hold on to memory.
+pub(crate) const SEND_BUFFER_CAPACITY: usize = 8192;
+/// How much room is made in the read buffer before each read
+pub(crate) const READ_BUFFER_CAPACITY: usize = 4096;
 /// The bit that indicates if a VarInt is continuing into another byte.
-const CONTINUE_BIT: u8 = 0b10000000;
+pub(crate) const CONTINUE_BIT: u8 = 0b10000000;
 
 /// This represents an active connection to a Minecraft client, from the server's perspective.
 /// In other words, this is only created and held from a server context, and does NOT support clients
@@ -33,7 +66,47 @@ pub struct CraftClient {
 	pub(crate) socket_addr: SocketAddr,
 	pub packet_state: PacketState,
 	pub compression_threshold: Option<i32>,
-	pub client_version: Option<VarInt>
+	pub compression_level: u32,
+	pub client_version: Option<VarInt>,
+	/// The hostname that the client used to connect, as sent in the handshake
+	pub server_address: Option<String>,
+	/// The port that the client used to connect, as sent in the handshake
+	pub server_port: Option<u16>,
+	/// Why the client connected, as sent in the handshake. Login handlers can check this to treat
+	/// transferred players differently.
+	pub handshake_intent: Option<HandshakeIntent>,
+	pub(crate) cipher: Option<CraftCipher>,
+	/// The direction of the packets received on this connection. This is `SERVER` for connections accepted
+	/// by a server, and `CLIENT` for connections made to a server.
+	pub(crate) direction: PacketDirection,
+	/// Reused for every sent packet, so that sending doesn't allocate each time
+	pub(crate) send_buffer: McSerializer,
+	/// The longest a single call to [CraftClient::receive_packet] waits for a packet
+	read_timeout: Option<Duration>,
+	/// The longest the connection can go without receiving a packet
+	idle_timeout: Option<Duration>,
+	last_received: Instant,
+	/// The largest packet accepted in the handshake, status and login states
+	pre_login_packet_limit: usize,
+	/// Data that has been received but not made into a packet yet
+	pub(crate) read_buffer: ReadBuffer,
+	/// Packets that have been queued but not sent yet
+	pub(crate) write_buffer: BytesMut,
+	stats: ConnectionStats,
+	/// When the connection has to be out of the handshake and status states by
+	handshake_deadline: Option<Instant>,
+	/// Counts this connection as half-open until it moves past the status state
+	pub(crate) half_open_permit: Option<HalfOpenPermit>,
+	/// The compression threshold to enable during login
+	login_compression: Option<i32>,
+	/// The message ID of the next login plugin request
+	pub(crate) next_plugin_message_id: i32,
+	/// The cookies seen on this connection, see [cookies]
+	pub cookies: CookieStore,
+	/// The resource packs pushed to this client, see [resource_packs]
+	pub resource_packs: ResourcePackTracker,
+	/// The software the other side said it is running, from its `minecraft:brand` plugin message
+	pub brand: Option<String>,
 }
 
 impl CraftClient {
@@ -46,259 +119,568 @@ impl CraftClient {
 			tcp_stream,
 			packet_state: PacketState::HANDSHAKING,
 			compression_threshold: None,
-			client_version: None
+			compression_level: DEFAULT_COMPRESSION_LEVEL,
+			client_version: None,
+			server_address: None,
+			server_port: None,
+			handshake_intent: None,
+			cipher: None,
+			direction: PacketDirection::SERVER,
+			send_buffer: McSerializer::init_size(SEND_BUFFER_CAPACITY),
+			read_timeout: None,
+			idle_timeout: None,
+			last_received: Instant::now(),
+			pre_login_packet_limit: DEFAULT_PRE_LOGIN_PACKET_LIMIT,
+			read_buffer: ReadBuffer::default(),
+			write_buffer: BytesMut::with_capacity(SEND_BUFFER_CAPACITY),
+			stats: ConnectionStats::default(),
+			handshake_deadline: None,
+			login_compression: None,
+			next_plugin_message_id: 0,
+			half_open_permit: None,
+			cookies: CookieStore::new(),
+			resource_packs: ResourcePackTracker::new(),
+			brand: None,
 		})
 	}
 
-	/// Send a minecraft packet to the client. This will block until the packet is sent.
+	/// Create a new `CraftClient` for a connection made to a server, so that packets sent by the server
+	/// are received instead. See [CraftConnector](crate::network::connector::CraftConnector).
+	pub(crate) fn from_outbound_connection(tcp_stream: TcpStream) -> Result<Self, NetworkError> {
+		let mut client = Self::from_connection(tcp_stream)?;
+		client.direction = PacketDirection::CLIENT;
+
+		Ok(client)
+	}
+
+	/// Send a minecraft packet to the client. This will block until the packet is sent, along with any
+	/// packets that were queued before it.
 	pub async fn send_packet(&mut self, packet: Packet) -> Result<(), NetworkError> {
-		let mut serializer = McSerializer::new();
-		packet.mc_serialize(&mut serializer)?;
-		let output = &serializer.output;
+		self.queue_packet(packet)?;
+		self.flush().await
+	}
+
+	/// Add a packet to the write buffer without sending it. Queued packets are all sent in a single write
+	/// by [CraftClient::flush] or the next [CraftClient::send_packet], which is much cheaper than sending
+	/// a burst of packets, such as chunks, one at a time.
+	pub fn queue_packet(&mut self, packet: Packet) -> Result<(), NetworkError> {
+		let uncompressed = encode_packet(&packet, &mut self.send_buffer, self.compression_threshold, self.compression_level, &mut self.cipher)?;
+
+		trace!("Queued for {} : {:?}", self, self.send_buffer.output);
+		self.stats.record_sent(self.send_buffer.output.len(), uncompressed);
 
-		trace!("Sending to {} : {:?}", self, output);
+		self.write_buffer.extend_from_slice(&self.send_buffer.output);
+		self.send_buffer.reset(SEND_BUFFER_CAPACITY);
+		Ok(())
+	}
+
+	/// Queue a packet that was already serialized, like [CraftWriter::queue_encoded](split::CraftWriter::queue_encoded).
+	/// Only encryption is applied to it.
+	pub(crate) fn queue_encoded(&mut self, encoded: &[u8]) {
+		let start = self.write_buffer.len();
+		self.write_buffer.extend_from_slice(encoded);
 
-		// TODO: compress & encrypt here
+		if let Some(cipher) = &mut self.cipher {
+			cipher.encrypt(&mut self.write_buffer[start..]);
+		}
+	}
 
-		self.tcp_stream.write_all(output).await?;
+	/// Send every queued packet. This will block until they have all been sent.
+	pub async fn flush(&mut self) -> Result<(), NetworkError> {
+		if self.write_buffer.is_empty() {
+			return Ok(());
+		}
+
+		self.tcp_stream.write_all_buf(&mut self.write_buffer).await?;
+		shrink_write_buffer(&mut self.write_buffer);
+		self.stats.last_sent = Some(std::time::Instant::now());
 		Ok(())
 	}
 
-	// TODO: could use a good optimization pass - reduce # of copies, ideally to 0
-	/// Receive a minecraft packet from the client. This will block until a packet is received. This removes data from the TCP buffer
+	/// Receive a minecraft packet from the client. This will block until a packet is received, or until
+	/// the read or idle timeout runs out if one is set. This removes data from the TCP buffer
 	pub async fn receive_packet(&mut self) -> Result<Packet, NetworkError> {
-		let mut vec = Vec::with_capacity(3);
+		let deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
+		self.receive_packet_until(deadline).await
+	}
 
-		// read varint for length
-		loop {
-			let b = self.tcp_stream.read_u8().await?;
-
-			vec.push(b);
-			
-			if b & CONTINUE_BIT == 0 {
-				break;
-			} else if vec.len() > 3 {
-				return Err(SerializingErr::VarTypeTooLong("Packet length VarInt max bytes is 3".to_string()).into());
+	/// Receive a packet, but give up with [NetworkError::TimedOut] if it takes longer than `timeout`.
+	/// This replaces the read timeout for this call only, the idle timeout still applies.
+	///
+	/// Anything received before the timeout ran out is kept, so part of a packet is never lost and the
+	/// connection can still be used afterwards.
+	pub async fn receive_packet_timeout(&mut self, timeout: Duration) -> Result<Packet, NetworkError> {
+		self.receive_packet_until(Some(Instant::now() + timeout)).await
+	}
+
+	async fn receive_packet_until(&mut self, deadline: Option<Instant>) -> Result<Packet, NetworkError> {
+		let deadline = self.receive_deadline(deadline);
+		let max_length = max_packet_length(self.packet_state, self.pre_login_packet_limit);
+
+		let frame = loop {
+			match self.read_buffer.next_frame(&mut self.cipher, max_length) {
+				Ok(Some(frame)) => break frame,
+				Ok(None) => {}
+				Err(e) => { // the length can't be trusted, so there's no way to find the next packet
+					self.close().await;
+					return Err(e);
+				}
 			}
-		}
 
-		let vari = VarInt::from_slice(&vec)?;
+			self.fill_until(deadline).await?;
+		};
+
+		self.decode_received(frame)
+	}
+
+	/// Wait until some data has arrived and get the first byte of it, without taking it out of the read
+	/// buffer. This uses the same timeouts as [CraftClient::receive_packet].
+	pub(crate) async fn peek_byte(&mut self) -> Result<u8, NetworkError> {
+		let deadline = self.receive_deadline(self.read_timeout.map(|timeout| Instant::now() + timeout));
 
-		if vari.0 > PACKET_MAX_SIZE as i32 { // prob can't happen since it stops after 3 bytes, but check anyways
-			return Err(NetworkError::PacketTooLarge);
+		while self.read_buffer.unread().is_empty() {
+			self.fill_until(deadline).await?;
 		}
 
-		let length = vari.0 as usize + vec.len();
+		Ok(self.read_buffer.unread()[0])
+	}
 
-		// TODO: analysis needed - does this minimize copying?
-		// could define &[u8] to max packet size but that seems like too much memory usage
-		let mut buffer = vec![0; length];
+	/// The earliest of `deadline` and the deadlines set on the connection
+	pub(crate) fn receive_deadline(&self, deadline: Option<Instant>) -> Option<Instant> {
+		let idle_deadline = self.idle_timeout.map(|timeout| self.last_received + timeout);
+		let handshake_deadline = self.handshake_deadline.filter(|_| self.in_handshake());
 
-		let mut i = 0;
+		[deadline, idle_deadline, handshake_deadline].into_iter().flatten().min()
+	}
 
-		for b in &vec {
-			buffer[i] = *b;
-			i += 1;
-		}
+	/// Read more data into the read buffer, giving up with [NetworkError::TimedOut] once the deadline
+	/// passes. Anything read is kept if it times out.
+	pub(crate) async fn fill_until(&mut self, deadline: Option<Instant>) -> Result<(), NetworkError> {
+		// filling the buffer is cancel safe, so nothing is lost if the timeout runs out
+		let read = match deadline {
+			Some(deadline) => match tokio::time::timeout_at(deadline, self.read_buffer.fill(&mut self.tcp_stream)).await {
+				Ok(read) => read,
+				Err(_) => {
+					debug!("Timed out waiting for a packet from {}", self);
+					return Err(NetworkError::TimedOut);
+				}
+			},
+			None => self.read_buffer.fill(&mut self.tcp_stream).await,
+		};
 
-		let length = self.tcp_stream.read(&mut buffer[vec.len()..]).await;
-		
-		let length = match length {
-			Ok(length) => {length}
+		match read {
+			Ok(0) => { // connection closed
+				self.close().await;
+				Err(NetworkError::NoDataReceived)
+			}
+			Ok(_) => Ok(()),
 			Err(e) => {
-				if e.to_string().contains("An established connection was aborted by the software in your host machine") {
-					debug!("OS Error detected in packet receive, closing the connection: {}", e);
+				let e = read_error(e);
+
+				if e == NetworkError::ConnectionAbortedLocally {
 					self.close().await;
-					return Err(NetworkError::ConnectionAbortedLocally);
 				}
 
-				return Err(NetworkError::IOError(e));
+				Err(e)
 			}
-		};
+		}
+	}
 
-		trace!("Received from {} : {:?}", self, &buffer);
+	/// Decode a packet that was just taken out of the read buffer, keeping track of it in the stats
+	fn decode_received(&mut self, frame: BytesMut) -> Result<Packet, NetworkError> {
+		trace!("Received from {} : {:?}", self, &frame);
+		self.last_received = Instant::now();
 
-		if length == 0 { // connection closed
-			self.close().await;
-			return Err(NetworkError::NoDataReceived);
-		} else if length == PACKET_MAX_SIZE {
-			return Err(NetworkError::PacketTooLarge);
+		let (packet, uncompressed) = decode_packet_sized(frame.to_vec(), self.compression_threshold, self.packet_state, self.direction)?;
+		self.stats.record_received(frame.len(), uncompressed);
+
+		// every packet after this one is compressed, so the switch can't wait for the caller
+		if let Packet::SetCompression(body) = &packet {
+			self.enable_compression(Some(body.threshold.0));
 		}
 
-		// TODO: decompress & decrypt here
+		self.cookies.record(&packet);
+		self.resource_packs.record(&packet);
 
-		let mut deserializer = McDeserializer::new(&buffer);
-		let packet = Packet::deserialize_state(&mut deserializer, self.packet_state, PacketDirection::SERVER)?;
+		if let Some(Some(Ok(brand))) = PluginMessage::from_packet(&packet).map(|m| m.read_brand()) {
+			self.brand = Some(brand);
+		}
 
 		Ok(packet)
 	}
+
+	/// Get the traffic counters for this connection
+	pub fn stats(&self) -> &ConnectionStats {
+		&self.stats
+	}
+
+	/// Set how long a single call to [CraftClient::receive_packet] can wait for a packet before failing
+	/// with [NetworkError::TimedOut]. `None` waits forever, which is the default.
+	pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+		self.read_timeout = timeout;
+	}
+
+	/// Set the largest packet accepted before the player has logged in, meaning in the handshake, status
+	/// and login states. Longer packets close the connection with [NetworkError::PacketLengthExceeded].
+	/// This can't be raised above the protocol limit of 2097151 bytes, which applies in every state.
+	pub fn set_pre_login_packet_limit(&mut self, limit: usize) {
+		self.pre_login_packet_limit = limit;
+	}
+
+	/// Set how long the connection can go without receiving any packet. Unlike the read timeout, this
+	/// counts from the last packet received instead of from the start of the current call, so a client
+	/// that never finishes the handshake can't hold the connection open. `None` disables it, which is
+	/// the default.
+	pub fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+		self.idle_timeout = timeout;
+	}
+
+	/// Set how long the connection can stay in the handshake and status states, counting from now. Once
+	/// it runs out, receiving a packet in those states fails with [NetworkError::TimedOut], no matter how
+	/// recently data arrived, so a client that trickles in bytes can't hold the connection open.
+	/// `None` disables it, which is the default.
+	pub fn set_handshake_timeout(&mut self, timeout: Option<Duration>) {
+		self.handshake_deadline = timeout.map(|timeout| Instant::now() + timeout);
+	}
+
+	fn in_handshake(&self) -> bool {
+		matches!(self.packet_state, PacketState::HANDSHAKING | PacketState::STATUS)
+	}
 	
-	/// Try to receive a packet from the buffer without blocking. This will return 'NoDataReceived' 
-	/// if no data is available.
-	pub fn try_receive_packet(&mut self) -> Result<Packet, NetworkError> {
-		let mut vec = vec![];
+	/// Try to receive a packet without waiting. This returns `Ok(None)` if a complete packet hasn't
+	/// arrived yet, which lets a single task poll many connections, such as once per server tick.
+	/// Part of a packet that has arrived is kept until the rest of it is received.
+	///
+	/// A closed connection returns [NetworkError::NoDataReceived].
+	pub fn try_receive_packet(&mut self) -> Result<Option<Packet>, NetworkError> {
+		let max_length = max_packet_length(self.packet_state, self.pre_login_packet_limit);
 
-		// read varint for length
 		loop {
-			let var_buffer = &mut [0u8; 1];
-			let len = self.tcp_stream.try_read(var_buffer)?;
-			
-			if len == 0 {
-				return Err(NetworkError::NoDataReceived);
+			if let Some(frame) = self.read_buffer.next_frame(&mut self.cipher, max_length)? {
+				return self.decode_received(frame).map(Some);
 			}
-			
-			let b = var_buffer[0];
 
-			if b & CONTINUE_BIT == 0 {
-				vec.push(b);
-				break;
-			} else {
-				vec.push(b);
-
-				if vec.len() > 3 {
-					return Err(SerializingErr::VarTypeTooLong("Packet length VarInt max bytes is 3".to_string()).into());
-				}
+			match self.tcp_stream.try_read_buf(self.read_buffer.spare()) {
+				Ok(0) => return Err(NetworkError::NoDataReceived),
+				Ok(_) => {}
+				Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(None),
+				Err(e) => return Err(read_error(e)),
 			}
 		}
+	}
 
-		let vari = VarInt::from_slice(&vec)?;
-		let varbytes = vari.to_bytes();
+	/// Peek the next packet without removing it, so the next call to [CraftClient::receive_packet] returns
+	/// the same packet. This will block until a packet is received.
+	pub async fn peek_packet(&mut self) -> Result<Packet, NetworkError> {
+		let max_length = max_packet_length(self.packet_state, self.pre_login_packet_limit);
 
-		if vari.0 > PACKET_MAX_SIZE as i32 { // prob can't happen since it stops after 3 bytes, but check anyways
-			return Err(NetworkError::PacketTooLarge);
+		loop {
+			if let Some(frame) = self.read_buffer.peek_frame(&mut self.cipher, max_length)? {
+				trace!("Peeked from {} : {:?}", self.socket_addr, frame);
+
+				return decode_packet(frame.to_vec(), self.compression_threshold, self.packet_state, self.direction);
+			}
+
+			if self.read_buffer.fill(&mut self.tcp_stream).await.map_err(read_error)? == 0 { // connection closed
+				self.close().await;
+				return Err(NetworkError::NoDataReceived);
+			}
 		}
+	}
 
-		let length = vari.0 as usize + varbytes.len();
+	/// Split the connection into a [CraftReader] and a [CraftWriter], so that packets can be received
+	/// in one task while another task sends packets. The compression and encryption settings of the
+	/// connection are carried over to both halves.
+	pub fn split(self) -> (CraftReader, CraftWriter) {
+		let (read_half, write_half) = self.tcp_stream.into_split();
+
+		let reader = CraftReader {
+			read_half,
+			socket_addr: self.socket_addr,
+			packet_state: self.packet_state,
+			compression_threshold: self.compression_threshold,
+			cipher: self.cipher.clone(),
+			direction: self.direction,
+			pre_login_packet_limit: self.pre_login_packet_limit,
+			read_buffer: self.read_buffer,
+		};
 
-		// TODO: analysis needed - does this minimize copying?
-		// could define &[u8] to max packet size but that seems like too much memory usage
-		let mut buffer = vec![0; length];
+		let writer = CraftWriter {
+			write_half,
+			socket_addr: self.socket_addr,
+			compression_threshold: self.compression_threshold,
+			compression_level: self.compression_level,
+			cipher: self.cipher,
+			send_buffer: self.send_buffer,
+			write_buffer: self.write_buffer,
+		};
 
-		let mut i = 0;
+		(reader, writer)
+	}
 
-		for b in &varbytes {
-			buffer[i] = *b;
-			i += 1;
+	/// Change the internal Packet State. This is used to categorize what kind of packets are being sent/received.
+	/// See [PacketState] for more information.
+	pub fn change_state(&mut self, state: PacketState) {
+		self.packet_state = state;
+
+		if !self.in_handshake() {
+			self.half_open_permit = None;
 		}
+	}
 
-		let length = self.tcp_stream.try_read(&mut buffer[varbytes.len()..]);
+	/// Enable compression on the connection. This will compress packets that are larger than the threshold.
+	/// A threshold of `None` or a negative threshold disables compression entirely.
+	///
+	/// This should be called directly after the `SetCompression` packet has been sent, since every packet
+	/// after it must use the compressed packet format. [CraftClient::set_compression] does both.
+	pub fn enable_compression(&mut self, threshold: Option<i32>) {
+		self.compression_threshold = threshold.filter(|t| *t >= 0);
+	}
 
-		if let Err(e) = length {
-			return Err(NetworkError::IOError(e));
-		}
+	/// Send `SetCompression` and switch to the compressed format right after it, so that no packet can be
+	/// sent in the wrong format. Received packets switch over when the client's `SetCompression` is read.
+	/// `None` or a negative threshold sends nothing.
+	pub async fn set_compression(&mut self, threshold: Option<i32>) -> Result<(), NetworkError> {
+		let Some(threshold) = threshold.filter(|t| *t >= 0) else {
+			return Ok(());
+		};
 
-		let length = length.unwrap();
+		self.queue_packet(Packet::SetCompression(SetCompressionBody::new(VarInt(threshold))))?;
+		self.enable_compression(Some(threshold));
+		self.flush().await
+	}
 
-		trace!("Received from {} : {:?}", self, &buffer);
+	/// Set the compression threshold that [LoginFlow](crate::protocol::login::flow::LoginFlow) enables
+	/// during login. Connections accepted by a [CraftServer](crate::network::server::CraftServer) get the
+	/// threshold from its settings.
+	pub fn set_login_compression(&mut self, threshold: Option<i32>) {
+		self.login_compression = threshold.filter(|t| *t >= 0);
+	}
 
-		if length == 0 { // connection closed
-			return Err(NetworkError::NoDataReceived);
-		} else if length == PACKET_MAX_SIZE {
-			return Err(NetworkError::PacketTooLarge);
-		}
+	pub fn login_compression(&self) -> Option<i32> {
+		self.login_compression
+	}
 
-		// TODO: decompress & decrypt here
+	/// Set the zlib compression level used for packets above the compression threshold. This ranges from
+	/// 0 (no compression) to 9 (best compression), and is 6 by default.
+	pub fn set_compression_level(&mut self, level: u32) {
+		self.compression_level = level.min(9);
+	}
 
-		let mut deserializer = McDeserializer::new(&buffer);
-		let packet = Packet::deserialize_state(&mut deserializer, self.packet_state, PacketDirection::SERVER)?;
+	/// Enable AES/CFB8 encryption on the connection using the shared secret decrypted from the client's
+	/// `EncryptionResponse`. All data sent or received after this point will be encrypted, so this must
+	/// be called right after the `EncryptionResponse` is received.
+	///
+	/// See [ServerKey](crate::network::encryption::ServerKey) for the key exchange.
+	pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), NetworkError> {
+		self.cipher = Some(CraftCipher::new(shared_secret)?);
+		self.read_buffer.encryption_enabled();
 
-		Ok(packet)
-		
+		Ok(())
 	}
 
-	/// Peek the next packet in the queue without removing it. This will block until a packet is received.
-	pub async fn peek_packet(&mut self) -> Result<Packet, NetworkError> {
-		// read varint for length
-		let mut i = 1usize;
-		let vari: VarInt;
+	/// Check if encryption has been enabled on this connection
+	pub fn is_encrypted(&self) -> bool {
+		self.cipher.is_some()
+	}
 
-		/*
-		So we have to use this weird loop because we need to peek the data slowly to understand the byte length of the varint
-		 */
-		loop {
-			let mut b = vec![0; i];
-			if self.tcp_stream.peek(&mut b).await? == 0 {
-				return Err(NetworkError::NoDataReceived);
-			}
+	/// Disconnect the client with a reason that is shown on their screen. This sends the Disconnect packet
+	/// for the current state, along with any queued packets, and then closes the connection, so the client
+	/// doesn't just see "Connection reset".
+	///
+	/// The handshaking and status states have no Disconnect packet, and only servers can send one, so in
+	/// those cases the connection is just closed.
+	pub async fn disconnect<T: Into<TextComponent>>(&mut self, reason: T) -> Result<(), NetworkError> {
+		let reason = reason.into();
+		debug!("Disconnecting {}: {}", self, reason);
+
+		let packet = match (self.direction, self.packet_state) {
+			(PacketDirection::SERVER, PacketState::LOGIN) => Some(Packet::Disconnect(DisconnectBody::new(reason))),
+			(PacketDirection::SERVER, PacketState::CONFIGURATION) => Some(Packet::ConfigDisconnect(ConfigDisconnectBody::new(reason.into()))),
+			(PacketDirection::SERVER, PacketState::PLAY) => Some(Packet::PlayDisconnect(PlayDisconnectBody::new(reason.into()))),
+			_ => None,
+		};
 
-			// this indicates if the varint has ended
-			if b[i - 1] & CONTINUE_BIT == 0 {
-				vari = VarInt::from_slice(&b)?;
-				break;
-			} else {
-				if i > 3 { // any varint over 3 bytes is either broken or too big for a packet
-					return Err(SerializingErr::VarTypeTooLong("Packet length VarInt max bytes is 3".to_string()).into());
-				}
-			}
+		let sent = match packet {
+			Some(packet) => self.send_packet(packet).await,
+			None => self.flush().await,
+		};
 
-			i += 1;
-		}
+		self.close().await;
 
-		let varbytes = vari.to_bytes();
+		sent
+	}
 
-		if vari.0 > PACKET_MAX_SIZE as i32 { // prob can't happen since it stops after 3 bytes, but check anyways
-			return Err(NetworkError::PacketTooLarge);
-		}
+	/// Shutdown the connection as soon as possible
+	pub async fn close(&mut self) -> bool {
+		debug!("Closing connection to {}", self);
+		self.tcp_stream.shutdown().await.is_ok()
+	}
 
-		let length = vari.0 as usize + varbytes.len();
+	/// Get the address of the other side of the connection. For players behind a proxy with forwarding,
+	/// this is the forwarded address of the player.
+	pub fn remote_addr(&self) -> SocketAddr {
+		self.socket_addr
+	}
+
+	/// Whether the client was transferred here from another server
+	pub fn is_transfer(&self) -> bool {
+		self.handshake_intent == Some(HandshakeIntent::TRANSFER)
+	}
 
-		// TODO: analysis needed - does this minimize copying?
-		// could define &[u8] to max packet size but that seems like too much memory usage
-		let mut buffer = vec![0; length];
+	/// Send the client to another server with the Transfer packet, which was added in 1.20.5. The client
+	/// connects to `host` with a [HandshakeIntent::TRANSFER] handshake and disconnects from here.
+	///
+	/// The `cookies` are stored first, so the next server can read them to learn where the player came
+	/// from. Older clients fail with [NetworkError::UnsupportedByClient] before anything is sent.
+	pub async fn transfer_client<S: Into<String>>(&mut self, host: S, port: u16, cookies: Vec<(Identifier, Vec<u8>)>) -> Result<(), NetworkError> {
+		if self.get_client_version().is_some_and(|v| !v.has_transfers()) {
+			return Err(NetworkError::UnsupportedByClient("transfers".to_string()));
+		}
 
-		let mut i = 0;
+		let host = host.into();
+		let port = VarInt(port as i32);
 
-		for b in &varbytes {
-			buffer[i] = *b;
-			i += 1;
+		let transfer = match self.packet_state {
+			PacketState::CONFIGURATION => Packet::Transfer(TransferBody::new(host, port)),
+			PacketState::PLAY => Packet::PlayTransfer(PlayTransferBody::new(host, port)),
+			_ => return Err(NetworkError::InvalidPacketState),
+		};
+
+		for (key, payload) in cookies {
+			let packet = cookies::store_packet(self.packet_state, key.clone(), payload.clone())?;
+			self.cookies.insert(key, payload)?;
+			self.queue_packet(packet)?;
 		}
 
-		let length = self.tcp_stream.peek(&mut buffer[varbytes.len()..]).await;
+		self.send_packet(transfer).await
+	}
 
-		if let Err(e) = length {
-			if e.to_string().contains("An established connection was aborted by the software in your host machine") {
-				debug!("OS Error detected in packet receive, closing the connection: {}", e);
-				self.close().await;
-				return Err(NetworkError::ConnectionAbortedLocally);
-			}
+	/// Queue a cookie request for the state the connection is in. The answer is recorded in
+	/// [CraftClient::cookies] when it is received.
+	pub fn queue_cookie_request(&mut self, key: Identifier) -> Result<(), NetworkError> {
+		let packet = cookies::request_packet(self.packet_state, key)?;
+		self.queue_packet(packet)
+	}
 
-			return Err(NetworkError::IOError(e));
+	/// Ask the client for a cookie and wait for the answer. Clients from before 1.20.5 don't have cookies,
+	/// so `None` is returned for them without asking.
+	///
+	/// Any other packet that arrives first is an error, so once the client may send packets of its own,
+	/// use [CraftClient::queue_cookie_request] and handle the answer with the rest of the packets instead.
+	pub async fn request_cookie(&mut self, key: Identifier) -> Result<Option<Vec<u8>>, NetworkError> {
+		if self.get_client_version().is_some_and(|v| !v.has_transfers()) {
+			return Ok(None);
 		}
 
-		let length = length.unwrap();
+		self.queue_cookie_request(key.clone())?;
+		self.flush().await?;
+
+		let deadline = Instant::now() + DEFAULT_COOKIE_TIMEOUT;
 
-		trace!("Peeked from {} : {:?}", self, &buffer);
+		loop {
+			let packet = self.receive_packet_timeout(deadline.saturating_duration_since(Instant::now())).await?;
 
-		if length == 0 { // connection closed
-			self.close().await;
-			return Err(NetworkError::NoDataReceived);
-		} else if length == PACKET_MAX_SIZE {
-			return Err(NetworkError::PacketTooLarge);
+			match self.cookies.record(&packet) {
+				Some(received) if received == key => return Ok(self.cookies.get(&key).map(<[u8]>::to_vec)),
+				Some(_) => {} // the answer to an earlier request
+				None => return Err(NetworkError::ExpectedDifferentPacket("Expected a cookie response".to_string())),
+			}
 		}
+	}
 
-		// TODO: decompress & decrypt here
+	/// Store a cookie on the client, which it keeps when it is transferred to another server. Cookies can
+	/// only be stored during the configuration and play states.
+	pub async fn store_cookie(&mut self, key: Identifier, payload: Vec<u8>) -> Result<(), NetworkError> {
+		let packet = cookies::store_packet(self.packet_state, key.clone(), payload.clone())?;
+		self.cookies.insert(key, payload)?;
+		self.send_packet(packet).await
+	}
 
-		let mut deserializer = McDeserializer::new(&buffer);
-		let packet = Packet::deserialize_state(&mut deserializer, self.packet_state, PacketDirection::SERVER)?;
+	/// Push a resource pack to a client in the configuration or play state. The returned future finishes
+	/// once the client has loaded the pack or given up on it, as long as its responses are received with
+	/// [CraftClient::receive_packet].
+	pub async fn push_resource_pack(&mut self, pack: ResourcePack) -> Result<PackCompletion, NetworkError> {
+		let packet = pack.packet(self.packet_state)?;
+		self.send_packet(packet).await?;
 
-		Ok(packet)
+		self.resource_packs.track(pack.uuid);
+		Ok(self.resource_packs.completion(pack.uuid).expect("the pack was just tracked"))
 	}
 
-	/// Change the internal Packet State. This is used to categorize what kind of packets are being sent/received.
-	/// See [PacketState] for more information.
-	pub fn change_state(&mut self, state: PacketState) {
-		self.packet_state = state;
+	/// Remove a resource pack from the client, or every pack if `uuid` is `None`
+	pub async fn pop_resource_pack(&mut self, uuid: Option<Uuid>) -> Result<(), NetworkError> {
+		let packet = resource_packs::remove_packet(self.packet_state, uuid)?;
+		self.send_packet(packet).await?;
+
+		self.resource_packs.remove(uuid);
+		Ok(())
 	}
 
-	/// Enable compression on the connection. This will compress packets that are larger than the threshold.
-	pub fn enable_compression(&mut self, threshold: Option<i32>) {
-		self.compression_threshold = threshold;
+	/// Send a plugin message to the other side, in the packet for the state the connection is in
+	pub async fn send_plugin_message(&mut self, message: &PluginMessage) -> Result<(), NetworkError> {
+		let direction = match self.direction {
+			PacketDirection::SERVER => PacketDirection::CLIENT,
+			_ => PacketDirection::SERVER,
+		};
+
+		let packet = message.to_packet(self.packet_state, direction)?;
+		self.send_packet(packet).await
 	}
 
-	/// Shutdown the connection as soon as possible
-	pub async fn close(&mut self) -> bool {
-		debug!("Closing connection to {}", self);
-		self.tcp_stream.shutdown().await.is_ok()
+	/// Tell the other side which software this is, as vanilla does at the start of configuration
+	pub async fn send_brand(&mut self, brand: &str) -> Result<(), NetworkError> {
+		self.send_plugin_message(&PluginMessage::brand(brand)).await
+	}
+
+	/// Show a message in the player's chat. This is only possible in the play state.
+	pub async fn send_message(&mut self, message: TextComponent) -> Result<(), NetworkError> {
+		self.require_play()?;
+		self.send_packet(title::message_packet(message)).await
+	}
+
+	/// Show text above the player's hotbar. This is only possible in the play state.
+	pub async fn send_action_bar(&mut self, text: TextComponent) -> Result<(), NetworkError> {
+		self.require_play()?;
+		self.send_packet(title::action_bar_packet(text)).await
+	}
+
+	/// Show a title in the middle of the player's screen. This is only possible in the play state.
+	pub async fn show_title(&mut self, title: &Title) -> Result<(), NetworkError> {
+		self.require_play()?;
+
+		for packet in title.packets() {
+			self.queue_packet(packet)?;
+		}
+
+		self.flush().await
+	}
+
+	/// Hide the title, see [title::clear_packet]
+	pub async fn clear_title(&mut self, reset: bool) -> Result<(), NetworkError> {
+		self.require_play()?;
+		self.send_packet(title::clear_packet(reset)).await
+	}
+
+	fn require_play(&self) -> Result<(), NetworkError> {
+		match self.packet_state {
+			PacketState::PLAY => Ok(()),
+			_ => Err(NetworkError::InvalidPacketState),
+		}
+	}
+
+	/// Store the details of a received handshake and move to the state the client asked for
+	pub(crate) fn accept_handshake(&mut self, handshake: &HandshakingBody) -> Result<HandshakeIntent, NetworkError> {
+		let intent = HandshakeIntent::from_id(handshake.next_state.0)
+			.ok_or_else(|| NetworkError::InvalidNextState(format!("Invalid next state detected, got \"{}\"", handshake.next_state.0)))?;
+
+		self.client_version = Some(handshake.protocol_version);
+		self.server_address = Some(handshake.server_address.clone());
+		self.server_port = Some(handshake.port);
+		self.handshake_intent = Some(intent);
+		self.change_state(intent.state());
+
+		Ok(intent)
 	}
 
 	/// Get the protocol version of this client as a `ProtocolVersion` enum. This will return 'None' if the
@@ -310,12 +692,455 @@ impl CraftClient {
 
 impl Display for CraftClient {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		let s = if let Ok(addr) = self.tcp_stream.peer_addr() {
-			format!("{}", addr)
-		} else {
-			"Unknown".to_string()
-		};
+		write!(f, "CraftConnection: {}", self.socket_addr)
+	}
+}
+
+/// Serialize a packet into the bytes that are sent over the network, compressing and encrypting it
+/// if enabled. The bytes replace whatever was in `serializer`, which lets the caller reuse one buffer
+/// for every packet. Returns the size of the packet before it was compressed.
+pub(crate) fn encode_packet(packet: &Packet, serializer: &mut McSerializer, compression_threshold: Option<i32>, compression_level: u32, cipher: &mut Option<CraftCipher>) -> Result<usize, NetworkError> {
+	serializer.clear();
+	packet.mc_serialize(serializer)?;
+	let uncompressed = serializer.output.len();
+
+	if let Some(threshold) = compression_threshold {
+		let compressed = compress_packet(&serializer.output, threshold, compression_level)?;
+		serializer.clear();
+		serializer.serialize_bytes(&compressed);
+	}
+
+	if let Some(cipher) = cipher {
+		cipher.encrypt(&mut serializer.output);
+	}
+
+	Ok(uncompressed)
+}
+
+/// Free the write buffer once it is empty if a large burst of packets made it grow past the usual size,
+/// the same as the send buffer
+pub(crate) fn shrink_write_buffer(buffer: &mut BytesMut) {
+	if buffer.is_empty() && buffer.capacity() > SEND_BUFFER_CAPACITY {
+		*buffer = BytesMut::with_capacity(SEND_BUFFER_CAPACITY);
+	}
+}
+
+/// Data received from a connection that hasn't been made into a packet yet. Packets can be split across
+/// several reads, or several packets can arrive in one read, so everything goes through this buffer
+/// and a packet is only taken out once all of it has arrived.
+#[derive(Debug, Default)]
+pub(crate) struct ReadBuffer {
+	buffer: BytesMut,
+	/// How many bytes at the start of the buffer have already been decrypted
+	decrypted: usize,
+}
+
+impl ReadBuffer {
+	/// Read more data from the stream into the buffer, returning the number of bytes read. `0` means that
+	/// the connection was closed. This is cancel safe, since nothing is read if the future is dropped.
+	pub(crate) async fn fill<R: AsyncRead + Unpin>(&mut self, stream: &mut R) -> std::io::Result<usize> {
+		stream.read_buf(self.spare()).await
+	}
+
+	/// The polling version of [ReadBuffer::fill], for use in `poll` functions such as [Stream](futures::Stream)
+	pub(crate) fn poll_fill<R: AsyncRead + Unpin>(&mut self, stream: &mut R, cx: &mut Context<'_>) -> Poll<std::io::Result<usize>> {
+		poll_read_buf(Pin::new(stream), cx, self.spare())
+	}
+
+	/// Make room for the next read and get the buffer to read into
+	pub(crate) fn spare(&mut self) -> &mut BytesMut {
+		self.buffer.reserve(READ_BUFFER_CAPACITY);
+		&mut self.buffer
+	}
+
+	/// Take the next complete packet out of the buffer, including its length
+	pub(crate) fn next_frame(&mut self, cipher: &mut Option<CraftCipher>, max_length: usize) -> Result<Option<BytesMut>, NetworkError> {
+		next_frame(&mut self.buffer, &mut self.decrypted, cipher, max_length)
+	}
+
+	/// Get the next complete packet without taking it out of the buffer
+	pub(crate) fn peek_frame(&mut self, cipher: &mut Option<CraftCipher>, max_length: usize) -> Result<Option<&[u8]>, NetworkError> {
+		Ok(frame_length(&mut self.buffer, &mut self.decrypted, cipher, max_length)?.map(|length| &self.buffer[..length]))
+	}
+
+	/// The data in the buffer that hasn't been made into a packet yet. This is only readable before
+	/// encryption has been turned on.
+	pub(crate) fn unread(&self) -> &[u8] {
+		&self.buffer
+	}
+
+	/// Everything left in the buffer came after the packet that turned encryption on, so it is all
+	/// still encrypted
+	pub(crate) fn encryption_enabled(&mut self) {
+		self.decrypted = 0;
+	}
+}
+
+/// Take the next complete packet out of `buffer`, including its length, decrypting any new bytes first.
+/// Returns `None` if the whole packet hasn't arrived yet.
+pub(crate) fn next_frame(buffer: &mut BytesMut, decrypted: &mut usize, cipher: &mut Option<CraftCipher>, max_length: usize) -> Result<Option<BytesMut>, NetworkError> {
+	let frame = match frame_length(buffer, decrypted, cipher, max_length)? {
+		Some(length) => buffer.split_to(length),
+		None => return Ok(None),
+	};
+
+	*decrypted = decrypted.saturating_sub(frame.len());
+
+	Ok(Some(frame))
+}
+
+/// The length of the next packet in `buffer` including its length prefix, if all of it has arrived
+fn frame_length(buffer: &mut BytesMut, decrypted: &mut usize, cipher: &mut Option<CraftCipher>, max_length: usize) -> Result<Option<usize>, NetworkError> {
+	if let Some(cipher) = cipher {
+		cipher.decrypt(&mut buffer[*decrypted..]);
+		*decrypted = buffer.len();
+	}
+
+	let (length, header) = match read_length(buffer, max_length)? {
+		Some(length) => length,
+		None => return Ok(None),
+	};
+
+	if buffer.len() < header + length {
+		buffer.reserve(header + length - buffer.len());
+		return Ok(None);
+	}
+
+	Ok(Some(header + length))
+}
+
+/// Read the packet length from the start of the buffer. Returns the length and the number of bytes it
+/// took up, or `None` if the whole VarInt hasn't arrived yet.
+fn read_length(src: &[u8], max_length: usize) -> Result<Option<(usize, usize)>, NetworkError> {
+	for (i, b) in src.iter().enumerate() {
+		if b & CONTINUE_BIT == 0 {
+			let length = VarInt::from_slice(&src[..=i])?;
+			check_packet_length(length, max_length)?;
+
+			return Ok(Some((length.0 as usize, i + 1)));
+		} else if i >= 2 {
+			return Err(SerializingErr::VarTypeTooLong("Packet length VarInt max bytes is 3".to_string()).into());
+		}
+	}
+
+	Ok(None)
+}
+
+/// Convert an error from reading the stream, picking out the OS error for a connection that was closed
+/// on this side
+pub(crate) fn read_error(e: std::io::Error) -> NetworkError {
+	if e.to_string().contains("An established connection was aborted by the software in your host machine") {
+		debug!("OS Error detected in packet receive, closing the connection: {}", e);
+		return NetworkError::ConnectionAbortedLocally;
+	}
+
+	NetworkError::IOError(e)
+}
+
+/// The largest packet accepted in the given state
+pub(crate) fn max_packet_length(state: PacketState, pre_login_limit: usize) -> usize {
+	match state {
+		PacketState::HANDSHAKING | PacketState::STATUS | PacketState::LOGIN => pre_login_limit.min(PACKET_MAX_SIZE),
+		PacketState::CONFIGURATION | PacketState::PLAY => PACKET_MAX_SIZE,
+	}
+}
+
+/// Make sure that a packet length read from the stream is within the limit, before anything is
+/// allocated for it
+pub(crate) fn check_packet_length(length: VarInt, max_length: usize) -> Result<(), NetworkError> {
+	if length.0 < 0 || length.0 as usize > max_length {
+		return Err(NetworkError::PacketLengthExceeded { length: length.0 as i64, max: max_length });
+	}
+
+	Ok(())
+}
+
+/// Decompress the raw bytes of a packet if compression is enabled and deserialize it
+pub(crate) fn decode_packet(buffer: Vec<u8>, compression_threshold: Option<i32>, state: PacketState, direction: PacketDirection) -> Result<Packet, NetworkError> {
+	decode_packet_sized(buffer, compression_threshold, state, direction).map(|(packet, _)| packet)
+}
+
+/// The same as [decode_packet], but also returns the size of the packet after it was decompressed
+pub(crate) fn decode_packet_sized(buffer: Vec<u8>, compression_threshold: Option<i32>, state: PacketState, direction: PacketDirection) -> Result<(Packet, usize), NetworkError> {
+	let buffer = match compression_threshold {
+		Some(threshold) => decompress_packet(&buffer, threshold)?,
+		None => buffer
+	};
+
+	let mut deserializer = McDeserializer::new(&buffer);
+	let packet = Packet::deserialize_state(&mut deserializer, state, direction)?;
+
+	Ok((packet, buffer.len()))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use tokio::io::AsyncWriteExt;
+	use tokio::net::{TcpListener, TcpStream};
+
+	use crate::network::client::{CraftClient, encode_packet};
+	use crate::network::compression::DEFAULT_COMPRESSION_LEVEL;
+	use crate::network::encryption::CraftCipher;
+	use crate::network::network_error::NetworkError;
+	use crate::protocol::packets::{HandshakingBody, KeepAliveBody, Packet, ServerboundPluginMessageBody};
+	use crate::protocol::packets::packet_definer::PacketState;
+	use crate::protocol::play::title::{Title, TitleTimes};
+	use crate::protocol::serialization::McSerializer;
+	use crate::protocol_types::datatypes::chat::TextComponent;
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	async fn connect() -> (TcpStream, CraftClient) {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let stream = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+		let client = CraftClient::from_connection(listener.accept().await.unwrap().0).unwrap();
+
+		(stream, client)
+	}
+
+	fn plugin_message(data: Vec<u8>) -> Packet {
+		Packet::ServerboundPluginMessage(ServerboundPluginMessageBody::new("minecraft:brand".parse().unwrap(), data.into()))
+	}
+
+	#[tokio::test]
+	async fn test_receive_split_packets() {
+		let secret = [3u8; 16];
+		let (mut stream, mut client) = connect().await;
+		client.change_state(PacketState::CONFIGURATION);
+		client.enable_compression(Some(256));
+		client.enable_encryption(&secret).unwrap();
+
+		// well over a single read, and random so that compression doesn't shrink it
+		let big = plugin_message((0..20000).map(|_| rand::random()).collect());
+		let small = plugin_message(vec![1, 2, 3]);
+
+		let mut cipher = Some(CraftCipher::new(&secret).unwrap());
+		let mut serializer = McSerializer::new();
+		let mut wire = vec![];
+
+		for packet in [&big, &small, &small] {
+			encode_packet(packet, &mut serializer, Some(256), DEFAULT_COMPRESSION_LEVEL, &mut cipher).unwrap();
+			wire.extend_from_slice(&serializer.output);
+		}
+
+		assert!(wire.len() > 10000);
+
+		// the packets arrive in uneven pieces that don't line up with where each packet starts
+		let writer = tokio::spawn(async move {
+			for chunk in wire.chunks(777) {
+				stream.write_all(chunk).await.unwrap();
+				tokio::time::sleep(Duration::from_millis(2)).await;
+			}
+
+			stream
+		});
+
+		assert_eq!(client.receive_packet().await.unwrap(), big);
+		assert_eq!(client.receive_packet().await.unwrap(), small);
+		assert_eq!(client.receive_packet().await.unwrap(), small);
+
+		drop(writer.await.unwrap());
+		assert_eq!(client.receive_packet().await, Err(NetworkError::NoDataReceived));
+	}
+
+	#[tokio::test]
+	async fn test_queue_and_flush() {
+		let (stream, mut client) = connect().await;
+		client.change_state(PacketState::CONFIGURATION);
+
+		let mut server = CraftClient::from_outbound_connection(stream).unwrap();
+		server.change_state(PacketState::CONFIGURATION);
+
+		for i in 0..3 {
+			client.queue_packet(Packet::KeepAlive(KeepAliveBody::new(i))).unwrap();
+		}
+
+		// nothing is sent until the queue is flushed
+		assert_eq!(server.receive_packet_timeout(Duration::from_millis(100)).await, Err(NetworkError::TimedOut));
+
+		client.flush().await.unwrap();
+
+		for i in 0..3 {
+			assert_eq!(server.receive_packet().await.unwrap(), Packet::KeepAlive(KeepAliveBody::new(i)));
+		}
+
+		// sending a packet sends the queue first
+		client.queue_packet(Packet::KeepAlive(KeepAliveBody::new(3))).unwrap();
+		client.send_packet(Packet::KeepAlive(KeepAliveBody::new(4))).await.unwrap();
+
+		assert_eq!(server.receive_packet().await.unwrap(), Packet::KeepAlive(KeepAliveBody::new(3)));
+		assert_eq!(server.receive_packet().await.unwrap(), Packet::KeepAlive(KeepAliveBody::new(4)));
+	}
+
+	#[tokio::test]
+	async fn test_connection_stats() {
+		let (stream, mut client) = connect().await;
+		let mut server = CraftClient::from_outbound_connection(stream).unwrap();
+
+		for connection in [&mut client, &mut server] {
+			connection.change_state(PacketState::CONFIGURATION);
+			connection.enable_compression(Some(64));
+		}
+
+		assert_eq!(server.stats().last_activity(), None);
+		assert_eq!(server.stats().sent_compression_ratio(), None);
+
+		let packet = plugin_message(vec![0; 4000]);
+		server.send_packet(packet.clone()).await.unwrap();
+		server.send_packet(plugin_message(vec![1])).await.unwrap();
+
+		assert_eq!(client.receive_packet().await.unwrap(), packet);
+		client.receive_packet().await.unwrap();
+
+		let sent = server.stats();
+		let received = client.stats();
+
+		assert_eq!(sent.packets_sent, 2);
+		assert_eq!(received.packets_received, 2);
+		assert_eq!(sent.bytes_sent, received.bytes_received);
+		assert_eq!(sent.uncompressed_bytes_sent, received.uncompressed_bytes_received);
+		assert!(sent.uncompressed_bytes_sent > 4000);
+		assert!(sent.sent_compression_ratio().unwrap() < 0.1);
+		assert!(sent.last_activity().is_some() && received.last_received.is_some());
+	}
+
+	#[tokio::test]
+	async fn test_disconnect() {
+		for state in [PacketState::LOGIN, PacketState::CONFIGURATION, PacketState::PLAY] {
+			let (stream, mut client) = connect().await;
+			let mut server = CraftClient::from_outbound_connection(stream).unwrap();
+			client.change_state(state);
+			server.change_state(state);
+
+			// anything queued is sent first
+			if state == PacketState::CONFIGURATION {
+				client.queue_packet(Packet::KeepAlive(KeepAliveBody::new(1))).unwrap();
+			}
+
+			client.disconnect("Server closed").await.unwrap();
+
+			if state == PacketState::CONFIGURATION {
+				assert_eq!(server.receive_packet().await.unwrap(), Packet::KeepAlive(KeepAliveBody::new(1)));
+			}
+
+			let reason = match server.receive_packet().await.unwrap() {
+				Packet::Disconnect(body) => body.reason,
+				Packet::ConfigDisconnect(body) => body.reason.into_inner(),
+				Packet::PlayDisconnect(body) => body.reason.into_inner(),
+				packet => panic!("Expected a disconnect in {:?}, got {:?}", state, packet),
+			};
+
+			assert_eq!(reason, TextComponent::new("Server closed"));
+			assert_eq!(server.receive_packet().await, Err(NetworkError::NoDataReceived));
+		}
+	}
+
+	#[tokio::test]
+	async fn test_timeout_keeps_partial_packet() {
+		let (mut stream, mut client) = connect().await;
+		client.change_state(PacketState::CONFIGURATION);
+
+		let packet = plugin_message(vec![9; 3000]);
+		let mut serializer = McSerializer::new();
+		encode_packet(&packet, &mut serializer, None, DEFAULT_COMPRESSION_LEVEL, &mut None).unwrap();
+
+		let (first, rest) = serializer.output.split_at(1500);
+		stream.write_all(first).await.unwrap();
+		assert_eq!(client.receive_packet_timeout(Duration::from_millis(100)).await, Err(NetworkError::TimedOut));
+
+		stream.write_all(rest).await.unwrap();
+		assert_eq!(client.receive_packet().await.unwrap(), packet);
+	}
+
+	#[tokio::test]
+	async fn test_try_receive_packet() {
+		let (mut stream, mut client) = connect().await;
+
+		assert_eq!(client.try_receive_packet().unwrap(), None);
+
+		let packet = Packet::Handshaking(HandshakingBody::new(VarInt(767), "localhost".to_string(), 25565, VarInt(1)));
+		let mut serializer = McSerializer::new();
+		encode_packet(&packet, &mut serializer, None, DEFAULT_COMPRESSION_LEVEL, &mut None).unwrap();
+
+		// only part of the packet has arrived, which has to be kept for the next call
+		let (first, rest) = serializer.output.split_at(5);
+		stream.write_all(first).await.unwrap();
+		client.tcp_stream.readable().await.unwrap();
+		assert_eq!(client.try_receive_packet().unwrap(), None);
+
+		stream.write_all(rest).await.unwrap();
+		client.tcp_stream.readable().await.unwrap();
+		assert_eq!(client.try_receive_packet().unwrap(), Some(packet));
+		assert_eq!(client.try_receive_packet().unwrap(), None);
+
+		drop(stream);
+		client.tcp_stream.readable().await.unwrap();
+		assert_eq!(client.try_receive_packet(), Err(NetworkError::NoDataReceived));
+	}
+
+	#[tokio::test]
+	async fn test_transfer_client() {
+		let (stream, mut client) = connect().await;
+		let mut server = CraftClient::from_outbound_connection(stream).unwrap();
+		client.change_state(PacketState::PLAY);
+		server.change_state(PacketState::PLAY);
+
+		client.transfer_client("lobby.example.com", 25566, vec![("test:origin".parse().unwrap(), b"survival".to_vec())]).await.unwrap();
+
+		assert!(matches!(server.receive_packet().await.unwrap(), Packet::PlayStoreCookie(_)));
+		match server.receive_packet().await.unwrap() {
+			Packet::PlayTransfer(body) => assert_eq!((body.host.as_str(), body.port), ("lobby.example.com", VarInt(25566))),
+			p => panic!("Expected a transfer, got {:?}", p),
+		}
+		assert_eq!(server.cookies.get(&"test:origin".parse().unwrap()), Some(&b"survival"[..]));
+
+		client.client_version = Some(VarInt(765)); // 1.20.4
+		assert!(matches!(client.transfer_client("lobby.example.com", 25566, vec![]).await, Err(NetworkError::UnsupportedByClient(_))));
+	}
+
+	#[tokio::test]
+	async fn test_brand() {
+		let (stream, mut client) = connect().await;
+		let mut server = CraftClient::from_outbound_connection(stream).unwrap();
+		client.change_state(PacketState::CONFIGURATION);
+		server.change_state(PacketState::CONFIGURATION);
+
+		client.send_brand("sandstone").await.unwrap();
+		assert!(matches!(server.receive_packet().await.unwrap(), Packet::PluginMessage(_)));
+		assert_eq!(server.brand.as_deref(), Some("sandstone"));
+
+		server.change_state(PacketState::PLAY);
+		client.change_state(PacketState::PLAY);
+		server.send_brand("vanilla").await.unwrap();
+		assert!(matches!(client.receive_packet().await.unwrap(), Packet::PlayServerboundPluginMessage(_)));
+		assert_eq!(client.brand.as_deref(), Some("vanilla"));
+	}
+
+	#[tokio::test]
+	async fn test_messages_and_titles() {
+		let (stream, mut client) = connect().await;
+		let mut server = CraftClient::from_outbound_connection(stream).unwrap();
+
+		assert_eq!(client.send_message(TextComponent::new("too early")).await, Err(NetworkError::InvalidPacketState));
+
+		client.change_state(PacketState::PLAY);
+		server.change_state(PacketState::PLAY);
+
+		client.send_message(TextComponent::new("Welcome!")).await.unwrap();
+		match server.receive_packet().await.unwrap() {
+			Packet::SystemChatMessage(body) => assert_eq!((body.content.0, body.overlay), (TextComponent::new("Welcome!"), false)),
+			p => panic!("Expected a system message, got {:?}", p),
+		}
+
+		client.send_action_bar(TextComponent::new("+5 xp")).await.unwrap();
+		assert!(matches!(server.receive_packet().await.unwrap(), Packet::SetActionBarText(_)));
+
+		client.show_title(&Title::new(TextComponent::new("Victory")).times(TitleTimes::new(5, 40, 5))).await.unwrap();
+		assert!(matches!(server.receive_packet().await.unwrap(), Packet::SetTitleAnimationTimes(_)));
+		assert!(matches!(server.receive_packet().await.unwrap(), Packet::SetTitleText(_)));
 
-		write!(f, "{}", format!("CraftConnection: {}", s))
+		client.clear_title(true).await.unwrap();
+		assert!(matches!(server.receive_packet().await.unwrap(), Packet::ClearTitles(_)));
 	}
 }
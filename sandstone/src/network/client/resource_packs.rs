@@ -0,0 +1,311 @@
+//! Resource packs are pushed to the client with `AddResourcePack` and taken away with `RemoveResourcePack`,
+//! during configuration or play. Since 1.20.3 each pack has a UUID, so a client can have several at once,
+//! and it reports on each of them with `ResourcePackResponse` as it is accepted, downloaded and loaded.
+//!
+//! A [ResourcePackTracker] matches those responses to the packs that were pushed, and hands out a
+//! [PackCompletion] for each one that finishes once the client is done with the pack.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::{AddResourcePackBody, Packet, PlayAddResourcePackBody, PlayRemoveResourcePackBody, RemoveResourcePackBody};
+use crate::protocol::packets::packet_definer::PacketState;
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::chat::{NbtTextComponent, TextComponent};
+
+/// What the client reports about a resource pack in `ResourcePackResponse`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourcePackStatus {
+	SuccessfullyLoaded = 0,
+	Declined = 1,
+	FailedDownload = 2,
+	Accepted = 3,
+	Downloaded = 4,
+	InvalidUrl = 5,
+	FailedReload = 6,
+	Discarded = 7,
+}
+
+impl ResourcePackStatus {
+	/// Whether the client is done with the pack, so no more responses will follow
+	pub fn is_final(&self) -> bool {
+		!matches!(self, ResourcePackStatus::Accepted | ResourcePackStatus::Downloaded)
+	}
+}
+
+impl TryFrom<i32> for ResourcePackStatus {
+	type Error = SerializingErr;
+
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		let status = match value {
+			0 => ResourcePackStatus::SuccessfullyLoaded,
+			1 => ResourcePackStatus::Declined,
+			2 => ResourcePackStatus::FailedDownload,
+			3 => ResourcePackStatus::Accepted,
+			4 => ResourcePackStatus::Downloaded,
+			5 => ResourcePackStatus::InvalidUrl,
+			6 => ResourcePackStatus::FailedReload,
+			7 => ResourcePackStatus::Discarded,
+			_ => return Err(SerializingErr::UniqueFailure(format!("Unknown resource pack status {}", value))),
+		};
+
+		Ok(status)
+	}
+}
+
+/// A resource pack to push to the client
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResourcePack {
+	pub uuid: Uuid,
+	pub url: String,
+	/// The SHA-1 of the pack as 40 hex characters, which lets the client reuse a download. Can be empty.
+	pub hash: String,
+	/// Whether the client is disconnected if it declines the pack
+	pub forced: bool,
+	pub prompt_message: Option<TextComponent>,
+}
+
+impl ResourcePack {
+	pub fn new<U: Into<String>, H: Into<String>>(uuid: Uuid, url: U, hash: H) -> Self {
+		Self {
+			uuid,
+			url: url.into(),
+			hash: hash.into(),
+			forced: false,
+			prompt_message: None,
+		}
+	}
+
+	pub fn forced(mut self, forced: bool) -> Self {
+		self.forced = forced;
+		self
+	}
+
+	pub fn prompt_message<T: Into<TextComponent>>(mut self, message: T) -> Self {
+		self.prompt_message = Some(message.into());
+		self
+	}
+
+	/// The packet that pushes this pack in the state that the connection is in
+	pub fn packet(&self, state: PacketState) -> Result<Packet, NetworkError> {
+		match state {
+			PacketState::CONFIGURATION => Ok(Packet::AddResourcePack(AddResourcePackBody::new(self.uuid, self.url.clone(), self.hash.clone(), self.forced, self.prompt_message.clone().map(NbtTextComponent::from)))),
+			PacketState::PLAY => Ok(Packet::PlayAddResourcePack(PlayAddResourcePackBody::new(self.uuid, self.url.clone(), self.hash.clone(), self.forced, self.prompt_message.clone().map(NbtTextComponent::from)))),
+			_ => Err(NetworkError::InvalidPacketState),
+		}
+	}
+}
+
+/// The packet that removes a pack, or every pack if `uuid` is `None`, in the state that the connection is in
+pub fn remove_packet(state: PacketState, uuid: Option<Uuid>) -> Result<Packet, NetworkError> {
+	match state {
+		PacketState::CONFIGURATION => Ok(Packet::RemoveResourcePack(RemoveResourcePackBody::new(uuid))),
+		PacketState::PLAY => Ok(Packet::PlayRemoveResourcePack(PlayRemoveResourcePackBody::new(uuid))),
+		_ => Err(NetworkError::InvalidPacketState),
+	}
+}
+
+#[derive(Debug, Default)]
+struct TrackedPack {
+	status: Option<ResourcePackStatus>,
+	waiters: Vec<oneshot::Sender<ResourcePackStatus>>,
+}
+
+/// The resource packs pushed to a client and the last status it reported for each, see the
+/// [module docs](self)
+#[derive(Debug, Default)]
+pub struct ResourcePackTracker {
+	packs: HashMap<Uuid, TrackedPack>,
+}
+
+impl ResourcePackTracker {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Start tracking a pack that was pushed to the client. Pushing a pack with the same UUID again replaces
+	/// it, so its status is reset, but anything waiting on it keeps waiting for the new one.
+	pub fn track(&mut self, uuid: Uuid) {
+		self.packs.entry(uuid).or_default().status = None;
+	}
+
+	pub fn is_tracked(&self, uuid: &Uuid) -> bool {
+		self.packs.contains_key(uuid)
+	}
+
+	/// The last status the client reported for a pack, or `None` if it hasn't answered yet
+	pub fn status(&self, uuid: &Uuid) -> Option<ResourcePackStatus> {
+		self.packs.get(uuid)?.status
+	}
+
+	/// The packs that the client hasn't finished with yet
+	pub fn pending(&self) -> impl Iterator<Item = &Uuid> {
+		self.packs.iter()
+			.filter(|(_, pack)| !pack.status.is_some_and(|s| s.is_final()))
+			.map(|(uuid, _)| uuid)
+	}
+
+	/// The packs that the client has loaded
+	pub fn loaded(&self) -> impl Iterator<Item = &Uuid> {
+		self.packs.iter()
+			.filter(|(_, pack)| pack.status == Some(ResourcePackStatus::SuccessfullyLoaded))
+			.map(|(uuid, _)| uuid)
+	}
+
+	/// A future that finishes with the final status of a pack, or `None` if the pack isn't tracked
+	pub fn completion(&mut self, uuid: Uuid) -> Option<PackCompletion> {
+		let pack = self.packs.get_mut(&uuid)?;
+		let (sender, receiver) = oneshot::channel();
+
+		match pack.status {
+			Some(status) if status.is_final() => {
+				let _ = sender.send(status);
+			}
+			_ => pack.waiters.push(sender),
+		}
+
+		Some(PackCompletion { uuid, receiver })
+	}
+
+	/// Keep track of a `ResourcePackResponse`, returning the pack and its status if the packet was one.
+	/// Responses for packs that weren't pushed through the tracker are returned but not kept.
+	pub fn record(&mut self, packet: &Packet) -> Option<(Uuid, ResourcePackStatus)> {
+		let (uuid, result) = match packet {
+			Packet::ResourcePackResponse(body) => (body.uuid, body.result.0),
+			Packet::PlayResourcePackResponse(body) => (body.uuid, body.result.0),
+			_ => return None,
+		};
+
+		let status = ResourcePackStatus::try_from(result).ok()?;
+
+		if let Some(pack) = self.packs.get_mut(&uuid) {
+			pack.status = Some(status);
+
+			if status.is_final() {
+				for waiter in pack.waiters.drain(..) {
+					let _ = waiter.send(status);
+				}
+			}
+		}
+
+		Some((uuid, status))
+	}
+
+	/// Stop tracking a pack that was removed, or every pack if `uuid` is `None`. Anything still waiting on
+	/// them finishes with [ResourcePackStatus::Discarded].
+	pub fn remove(&mut self, uuid: Option<Uuid>) {
+		let removed: Vec<TrackedPack> = match uuid {
+			Some(uuid) => self.packs.remove(&uuid).into_iter().collect(),
+			None => self.packs.drain().map(|(_, pack)| pack).collect(),
+		};
+
+		for waiter in removed.into_iter().flat_map(|pack| pack.waiters) {
+			let _ = waiter.send(ResourcePackStatus::Discarded);
+		}
+	}
+}
+
+/// Finishes with the final status of a resource pack, see [ResourcePackTracker::completion]. It fails
+/// with [NetworkError::ConnectionAbortedLocally] if the tracker is dropped first, such as when the
+/// connection closes.
+#[derive(Debug)]
+pub struct PackCompletion {
+	uuid: Uuid,
+	receiver: oneshot::Receiver<ResourcePackStatus>,
+}
+
+impl PackCompletion {
+	pub fn uuid(&self) -> Uuid {
+		self.uuid
+	}
+}
+
+impl Future for PackCompletion {
+	type Output = Result<ResourcePackStatus, NetworkError>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		Pin::new(&mut self.receiver).poll(cx).map(|result| result.map_err(|_| NetworkError::ConnectionAbortedLocally))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use uuid::Uuid;
+
+	use crate::network::client::resource_packs::{ResourcePack, ResourcePackStatus, ResourcePackTracker};
+	use crate::network::network_error::NetworkError;
+	use crate::network::server::server_testing::configured_pair;
+	use crate::protocol::packets::{Packet, ResourcePackResponseBody};
+	use crate::protocol::packets::packet_definer::PacketState;
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	fn response(uuid: Uuid, status: ResourcePackStatus) -> Packet {
+		Packet::ResourcePackResponse(ResourcePackResponseBody::new(uuid, VarInt(status as i32)))
+	}
+
+	#[tokio::test]
+	async fn test_resource_pack_tracker() {
+		let a = Uuid::from_u128(1);
+		let b = Uuid::from_u128(2);
+
+		let mut tracker = ResourcePackTracker::new();
+		tracker.track(a);
+		tracker.track(b);
+
+		let completion = tracker.completion(a).unwrap();
+		let removed = tracker.completion(b).unwrap();
+		assert!(tracker.completion(Uuid::from_u128(3)).is_none());
+
+		assert_eq!(tracker.record(&response(a, ResourcePackStatus::Accepted)), Some((a, ResourcePackStatus::Accepted)));
+		assert_eq!(tracker.pending().count(), 2);
+
+		tracker.record(&response(a, ResourcePackStatus::SuccessfullyLoaded));
+		assert_eq!(completion.await, Ok(ResourcePackStatus::SuccessfullyLoaded));
+		assert_eq!(tracker.loaded().collect::<Vec<_>>(), vec![&a]);
+		assert_eq!(tracker.completion(a).unwrap().await, Ok(ResourcePackStatus::SuccessfullyLoaded));
+
+		tracker.remove(Some(b));
+		assert_eq!(removed.await, Ok(ResourcePackStatus::Discarded));
+		assert!(!tracker.is_tracked(&b));
+
+		tracker.track(b);
+		let dropped = tracker.completion(b).unwrap();
+		drop(tracker);
+		assert_eq!(dropped.await, Err(NetworkError::ConnectionAbortedLocally));
+	}
+
+	#[tokio::test]
+	async fn test_push_resource_pack() {
+		let uuid = Uuid::from_u128(0x1234);
+		let (mut client, mut connector) = configured_pair().await;
+
+		let server = tokio::spawn(async move {
+			let completion = client.push_resource_pack(ResourcePack::new(uuid, "https://example.com/pack.zip", "").forced(true)).await.unwrap();
+
+			while client.resource_packs.pending().count() > 0 {
+				client.receive_packet().await.unwrap();
+			}
+
+			assert_eq!(completion.await, Ok(ResourcePackStatus::Declined));
+			assert_eq!(client.pop_resource_pack(Some(uuid)).await, Ok(()));
+			client.change_state(PacketState::STATUS);
+			assert_eq!(client.pop_resource_pack(None).await, Err(NetworkError::InvalidPacketState));
+		});
+
+		match connector.receive_packet().await.unwrap() {
+			Packet::AddResourcePack(body) => assert!(body.forced),
+			p => panic!("Expected a resource pack, got {:?}", p),
+		}
+		connector.send_packet(response(uuid, ResourcePackStatus::Accepted)).await.unwrap();
+		connector.send_packet(response(uuid, ResourcePackStatus::Declined)).await.unwrap();
+
+		assert!(matches!(connector.receive_packet().await.unwrap(), Packet::RemoveResourcePack(_)));
+		server.await.unwrap();
+	}
+}
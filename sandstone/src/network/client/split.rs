@@ -0,0 +1,322 @@
+//! The owned read and write halves of a [CraftClient](super::CraftClient), created by
+//! [CraftClient::split](super::CraftClient::split). This allows a connection to receive packets in one
+//! task while another task sends packets, instead of forcing all IO through a single task.
+//!
+//! The reader is also a [Stream] of packets and the writer is a [Sink] for packets, so the halves work
+//! with `select!`, `forward` and the other combinators from [futures].
+
+use std::fmt::Display;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+
+use bytes::BytesMut;
+use futures::{Sink, Stream};
+use log::trace;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio_util::io::poll_write_buf;
+
+use crate::network::client::{decode_packet, encode_packet, max_packet_length, read_error, ReadBuffer, SEND_BUFFER_CAPACITY, shrink_write_buffer};
+use crate::network::encryption::CraftCipher;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::Packet;
+use crate::protocol::packets::packet_definer::{PacketDirection, PacketState};
+use crate::protocol::serialization::McSerializer;
+
+/// The receiving half of a [CraftClient](super::CraftClient). This keeps track of the packet state, since
+/// the state of a connection is changed by the packets that are received.
+#[derive(Debug)]
+pub struct CraftReader {
+	pub(crate) read_half: OwnedReadHalf,
+	pub(crate) socket_addr: SocketAddr,
+	pub packet_state: PacketState,
+	pub compression_threshold: Option<i32>,
+	pub(crate) cipher: Option<CraftCipher>,
+	pub(crate) direction: PacketDirection,
+	pub(crate) pre_login_packet_limit: usize,
+	pub(crate) read_buffer: ReadBuffer,
+}
+
+impl CraftReader {
+	/// Receive a minecraft packet. This will block until a packet is received.
+	pub async fn receive_packet(&mut self) -> Result<Packet, NetworkError> {
+		let max_length = max_packet_length(self.packet_state, self.pre_login_packet_limit);
+
+		let frame = loop {
+			if let Some(frame) = self.read_buffer.next_frame(&mut self.cipher, max_length)? {
+				break frame;
+			}
+
+			if self.read_buffer.fill(&mut self.read_half).await.map_err(read_error)? == 0 { // connection closed
+				return Err(NetworkError::NoDataReceived);
+			}
+		};
+
+		trace!("Received from {} : {:?}", self, &frame);
+
+		decode_packet(frame.to_vec(), self.compression_threshold, self.packet_state, self.direction)
+	}
+
+	/// Change the internal Packet State. See [CraftClient::change_state](super::CraftClient::change_state).
+	pub fn change_state(&mut self, state: PacketState) {
+		self.packet_state = state;
+	}
+
+	/// Enable compression for received packets. This must also be done on the [CraftWriter].
+	/// See [CraftClient::enable_compression](super::CraftClient::enable_compression).
+	pub fn enable_compression(&mut self, threshold: Option<i32>) {
+		self.compression_threshold = threshold.filter(|t| *t >= 0);
+	}
+
+	/// Enable decryption of received packets. This must also be done on the [CraftWriter].
+	/// See [CraftClient::enable_encryption](super::CraftClient::enable_encryption).
+	pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), NetworkError> {
+		self.cipher = Some(CraftCipher::new(shared_secret)?);
+		self.read_buffer.encryption_enabled();
+
+		Ok(())
+	}
+
+	/// Get the address of the other side of the connection
+	pub fn peer_addr(&self) -> SocketAddr {
+		self.socket_addr
+	}
+}
+
+/// Receives packets until the connection is closed, which ends the stream
+impl Stream for CraftReader {
+	type Item = Result<Packet, NetworkError>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		let this = self.get_mut();
+		let max_length = max_packet_length(this.packet_state, this.pre_login_packet_limit);
+
+		loop {
+			match this.read_buffer.next_frame(&mut this.cipher, max_length) {
+				Ok(Some(frame)) => {
+					trace!("Received from {} : {:?}", this, &frame);
+					return Poll::Ready(Some(decode_packet(frame.to_vec(), this.compression_threshold, this.packet_state, this.direction)));
+				}
+				Ok(None) => {}
+				Err(e) => return Poll::Ready(Some(Err(e))),
+			}
+
+			match ready!(this.read_buffer.poll_fill(&mut this.read_half, cx)) {
+				Ok(0) => return Poll::Ready(None),
+				Ok(_) => {}
+				Err(e) => return Poll::Ready(Some(Err(read_error(e)))),
+			}
+		}
+	}
+}
+
+impl Display for CraftReader {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "CraftReader: {}", self.socket_addr)
+	}
+}
+
+/// The sending half of a [CraftClient](super::CraftClient)
+#[derive(Debug)]
+pub struct CraftWriter {
+	pub(crate) write_half: OwnedWriteHalf,
+	pub(crate) socket_addr: SocketAddr,
+	pub compression_threshold: Option<i32>,
+	pub compression_level: u32,
+	pub(crate) cipher: Option<CraftCipher>,
+	pub(crate) send_buffer: McSerializer,
+	/// Packets that have been queued but not sent yet, including those given to the [Sink]
+	pub(crate) write_buffer: BytesMut,
+}
+
+impl CraftWriter {
+	/// Send a minecraft packet. This will block until the packet is sent, along with any packets that
+	/// were queued before it.
+	pub async fn send_packet(&mut self, packet: Packet) -> Result<(), NetworkError> {
+		self.queue_packet(packet)?;
+		self.flush().await
+	}
+
+	/// Add a packet to the write buffer without sending it. See [CraftClient::queue_packet](super::CraftClient::queue_packet).
+	pub fn queue_packet(&mut self, packet: Packet) -> Result<(), NetworkError> {
+		encode_packet(&packet, &mut self.send_buffer, self.compression_threshold, self.compression_level, &mut self.cipher)?;
+
+		trace!("Queued for {} : {:?}", self, self.send_buffer.output);
+
+		self.write_buffer.extend_from_slice(&self.send_buffer.output);
+		self.send_buffer.reset(SEND_BUFFER_CAPACITY);
+		Ok(())
+	}
+
+	/// Queue a packet that was already serialized, and compressed if compression is enabled, so that
+	/// the same bytes can be sent to many connections. Only encryption is left to do, since it is different
+	/// for every connection.
+	pub(crate) fn queue_encoded(&mut self, encoded: &[u8]) {
+		let start = self.write_buffer.len();
+		self.write_buffer.extend_from_slice(encoded);
+
+		if let Some(cipher) = &mut self.cipher {
+			cipher.encrypt(&mut self.write_buffer[start..]);
+		}
+	}
+
+	/// Send every queued packet. This will block until they have all been sent.
+	pub async fn flush(&mut self) -> Result<(), NetworkError> {
+		self.write_half.write_all_buf(&mut self.write_buffer).await?;
+		shrink_write_buffer(&mut self.write_buffer);
+		Ok(())
+	}
+
+	/// Enable compression for sent packets. This must also be done on the [CraftReader].
+	/// See [CraftClient::enable_compression](super::CraftClient::enable_compression).
+	pub fn enable_compression(&mut self, threshold: Option<i32>) {
+		self.compression_threshold = threshold.filter(|t| *t >= 0);
+	}
+
+	/// Set the zlib compression level. See [CraftClient::set_compression_level](super::CraftClient::set_compression_level).
+	pub fn set_compression_level(&mut self, level: u32) {
+		self.compression_level = level.min(9);
+	}
+
+	/// Enable encryption of sent packets. This must also be done on the [CraftReader].
+	/// See [CraftClient::enable_encryption](super::CraftClient::enable_encryption).
+	pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), NetworkError> {
+		self.cipher = Some(CraftCipher::new(shared_secret)?);
+
+		Ok(())
+	}
+
+	/// Get the address of the other side of the connection
+	pub fn peer_addr(&self) -> SocketAddr {
+		self.socket_addr
+	}
+
+	/// Shutdown the write side of the connection
+	pub async fn close(&mut self) -> bool {
+		self.write_half.shutdown().await.is_ok()
+	}
+}
+
+/// Packets are encoded as soon as they are given to the sink, but are only written once it is flushed
+/// or once enough of them have built up
+impl Sink<Packet> for CraftWriter {
+	type Error = NetworkError;
+
+	fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		if self.write_buffer.len() >= SEND_BUFFER_CAPACITY {
+			return self.poll_flush(cx);
+		}
+
+		Poll::Ready(Ok(()))
+	}
+
+	fn start_send(self: Pin<&mut Self>, item: Packet) -> Result<(), Self::Error> {
+		self.get_mut().queue_packet(item)
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		let this = self.get_mut();
+
+		while !this.write_buffer.is_empty() {
+			if ready!(poll_write_buf(Pin::new(&mut this.write_half), cx, &mut this.write_buffer))? == 0 {
+				return Poll::Ready(Err(std::io::Error::from(ErrorKind::WriteZero).into()));
+			}
+		}
+
+		shrink_write_buffer(&mut this.write_buffer);
+
+		Poll::Ready(ready!(Pin::new(&mut this.write_half).poll_flush(cx)).map_err(NetworkError::from))
+	}
+
+	fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		ready!(self.as_mut().poll_flush(cx))?;
+
+		Poll::Ready(ready!(Pin::new(&mut self.write_half).poll_shutdown(cx)).map_err(NetworkError::from))
+	}
+}
+
+impl Display for CraftWriter {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "CraftWriter: {}", self.socket_addr)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use futures::{SinkExt, stream, StreamExt};
+	use tokio::net::{TcpListener, TcpStream};
+
+	use crate::network::client::CraftClient;
+	use crate::network::connector::CraftConnector;
+	use crate::protocol::packets::{KeepAliveBody, Packet, ServerboundKeepAliveBody};
+	use crate::protocol::packets::packet_definer::PacketState;
+
+	#[tokio::test]
+	async fn test_split_halves() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+
+		let mut connector = CraftConnector::connect("127.0.0.1", port).await.unwrap();
+		connector.change_state(PacketState::CONFIGURATION);
+
+		let (socket, _) = listener.accept().await.unwrap();
+		let mut client = CraftClient::from_connection(socket).unwrap();
+		client.change_state(PacketState::CONFIGURATION);
+		client.enable_compression(Some(0));
+		client.enable_encryption(&[7; 16]).unwrap();
+
+		connector.connection().enable_compression(Some(0));
+		connector.connection().enable_encryption(&[7; 16]).unwrap();
+
+		let (mut reader, mut writer) = client.split();
+
+		// the writer can be moved to another task while the reader keeps receiving
+		let handle = tokio::spawn(async move {
+			for i in 0..3 {
+				writer.send_packet(Packet::KeepAlive(KeepAliveBody::new(i))).await.unwrap();
+			}
+		});
+
+		for i in 0..3 {
+			assert_eq!(connector.receive_packet().await.unwrap(), Packet::KeepAlive(KeepAliveBody::new(i)));
+			connector.send_packet(Packet::ServerboundKeepAlive(ServerboundKeepAliveBody::new(i))).await.unwrap();
+		}
+
+		for i in 0..3 {
+			assert_eq!(reader.receive_packet().await.unwrap(), Packet::ServerboundKeepAlive(ServerboundKeepAliveBody::new(i)));
+		}
+
+		handle.await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn test_stream_and_sink() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+
+		let mut connector = CraftConnector::connect("127.0.0.1", port).await.unwrap();
+		connector.change_state(PacketState::CONFIGURATION);
+
+		let (socket, _) = listener.accept().await.unwrap();
+		let mut client = CraftClient::from_connection(socket).unwrap();
+		client.change_state(PacketState::CONFIGURATION);
+
+		let (mut reader, mut writer) = client.split();
+
+		writer.send_all(&mut stream::iter((0..3).map(|i| Ok(Packet::KeepAlive(KeepAliveBody::new(i)))))).await.unwrap();
+
+		for i in 0..3 {
+			assert_eq!(connector.receive_packet().await.unwrap(), Packet::KeepAlive(KeepAliveBody::new(i)));
+			connector.send_packet(Packet::ServerboundKeepAlive(ServerboundKeepAliveBody::new(i))).await.unwrap();
+		}
+
+		connector.connection().close().await;
+
+		let received: Vec<Packet> = (&mut reader).map(|p| p.unwrap()).collect().await;
+		assert_eq!(received, (0..3).map(|i| Packet::ServerboundKeepAlive(ServerboundKeepAliveBody::new(i))).collect::<Vec<_>>());
+
+		SinkExt::close(&mut writer).await.unwrap();
+	}
+}
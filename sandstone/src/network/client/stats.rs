@@ -0,0 +1,60 @@
+//! Counters for the traffic on a single connection, so that servers can monitor connections without
+//! wrapping the stream themselves.
+
+use std::time::Instant;
+
+/// Traffic counters for a [CraftClient](super::CraftClient), see [CraftClient::stats](super::CraftClient::stats).
+///
+/// Sent packets are counted once they have been queued, since that is when they are compressed. Wire
+/// bytes include the length prefix and compression header, while uncompressed bytes are the size of the
+/// packet before it was compressed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectionStats {
+	pub packets_sent: u64,
+	pub packets_received: u64,
+	pub bytes_sent: u64,
+	pub bytes_received: u64,
+	pub uncompressed_bytes_sent: u64,
+	pub uncompressed_bytes_received: u64,
+	pub last_sent: Option<Instant>,
+	pub last_received: Option<Instant>,
+}
+
+impl ConnectionStats {
+	pub(crate) fn record_sent(&mut self, wire_bytes: usize, uncompressed_bytes: usize) {
+		self.packets_sent += 1;
+		self.bytes_sent += wire_bytes as u64;
+		self.uncompressed_bytes_sent += uncompressed_bytes as u64;
+	}
+
+	pub(crate) fn record_received(&mut self, wire_bytes: usize, uncompressed_bytes: usize) {
+		self.packets_received += 1;
+		self.bytes_received += wire_bytes as u64;
+		self.uncompressed_bytes_received += uncompressed_bytes as u64;
+		self.last_received = Some(Instant::now());
+	}
+
+	/// The last time a packet was sent or received, if either has happened yet
+	pub fn last_activity(&self) -> Option<Instant> {
+		self.last_sent.max(self.last_received)
+	}
+
+	/// How much smaller sent packets are on the wire, as wire bytes over uncompressed bytes. This is 1
+	/// when compression is off.
+	pub fn sent_compression_ratio(&self) -> Option<f64> {
+		ratio(self.bytes_sent, self.uncompressed_bytes_sent)
+	}
+
+	/// The same as [ConnectionStats::sent_compression_ratio], for received packets
+	pub fn received_compression_ratio(&self) -> Option<f64> {
+		ratio(self.bytes_received, self.uncompressed_bytes_received)
+	}
+}
+
+fn ratio(wire: u64, uncompressed: u64) -> Option<f64> {
+	if uncompressed == 0 {
+		return None;
+	}
+
+	Some(wire as f64 / uncompressed as f64)
+}
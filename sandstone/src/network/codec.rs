@@ -0,0 +1,209 @@
+//! A [tokio_util::codec] implementation of the packet format, so that any byte stream can be turned into
+//! a stream of packets with `Framed<T, McCodec>`. This handles framing by the length prefix, along with
+//! compression and encryption, the same way that [CraftClient](crate::network::client::CraftClient) does.
+
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::network::client::{decode_packet, DEFAULT_PRE_LOGIN_PACKET_LIMIT, encode_packet, max_packet_length, next_frame, SEND_BUFFER_CAPACITY};
+use crate::network::compression::DEFAULT_COMPRESSION_LEVEL;
+use crate::network::encryption::CraftCipher;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::Packet;
+use crate::protocol::packets::packet_definer::{PacketDirection, PacketState};
+use crate::protocol::serialization::McSerializer;
+
+/// Frames packets by their VarInt length prefix. Decoding only returns a packet once all of its bytes
+/// have arrived, so it is safe to cancel a read at any point.
+///
+/// Like a [CraftClient](crate::network::client::CraftClient), the codec has to be told when the packet
+/// state changes and when compression or encryption is turned on.
+#[derive(Debug)]
+pub struct McCodec {
+	pub packet_state: PacketState,
+	/// The direction of the packets being decoded
+	direction: PacketDirection,
+	compression_threshold: Option<i32>,
+	compression_level: u32,
+	cipher: Option<CraftCipher>,
+	/// How many bytes at the start of the read buffer have already been decrypted
+	decrypted: usize,
+	send_buffer: McSerializer,
+	pre_login_packet_limit: usize,
+}
+
+impl McCodec {
+	/// A codec for the server side of a connection, which decodes packets sent by the client
+	pub fn server() -> Self {
+		Self::new(PacketDirection::SERVER)
+	}
+
+	/// A codec for the client side of a connection, which decodes packets sent by the server
+	pub fn client() -> Self {
+		Self::new(PacketDirection::CLIENT)
+	}
+
+	fn new(direction: PacketDirection) -> Self {
+		Self {
+			packet_state: PacketState::HANDSHAKING,
+			direction,
+			compression_threshold: None,
+			compression_level: DEFAULT_COMPRESSION_LEVEL,
+			cipher: None,
+			decrypted: 0,
+			send_buffer: McSerializer::init_size(SEND_BUFFER_CAPACITY),
+			pre_login_packet_limit: DEFAULT_PRE_LOGIN_PACKET_LIMIT,
+		}
+	}
+
+	pub fn change_state(&mut self, state: PacketState) {
+		self.packet_state = state;
+	}
+
+	/// Enable compression for both directions. See [CraftClient::enable_compression](crate::network::client::CraftClient::enable_compression).
+	pub fn enable_compression(&mut self, threshold: Option<i32>) {
+		self.compression_threshold = threshold.filter(|t| *t >= 0);
+	}
+
+	/// Set the zlib compression level, from 0 to 9
+	pub fn set_compression_level(&mut self, level: u32) {
+		self.compression_level = level.min(9);
+	}
+
+	/// Enable encryption for both directions. Any bytes that were already read but not decoded yet are
+	/// treated as encrypted, since they came after the packet that turned encryption on.
+	pub fn enable_encryption(&mut self, shared_secret: &[u8]) -> Result<(), NetworkError> {
+		self.cipher = Some(CraftCipher::new(shared_secret)?);
+		self.decrypted = 0;
+
+		Ok(())
+	}
+
+	pub fn is_encrypted(&self) -> bool {
+		self.cipher.is_some()
+	}
+
+	/// Set the largest packet accepted before login. See [CraftClient::set_pre_login_packet_limit](crate::network::client::CraftClient::set_pre_login_packet_limit).
+	pub fn set_pre_login_packet_limit(&mut self, limit: usize) {
+		self.pre_login_packet_limit = limit;
+	}
+}
+
+impl Decoder for McCodec {
+	type Item = Packet;
+	type Error = NetworkError;
+
+	fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+		let max_length = max_packet_length(self.packet_state, self.pre_login_packet_limit);
+
+		let frame = match next_frame(src, &mut self.decrypted, &mut self.cipher, max_length)? {
+			Some(frame) => frame,
+			None => return Ok(None),
+		};
+
+		decode_packet(frame.to_vec(), self.compression_threshold, self.packet_state, self.direction).map(Some)
+	}
+}
+
+impl Encoder<Packet> for McCodec {
+	type Error = NetworkError;
+
+	fn encode(&mut self, item: Packet, dst: &mut BytesMut) -> Result<(), Self::Error> {
+		encode_packet(&item, &mut self.send_buffer, self.compression_threshold, self.compression_level, &mut self.cipher)?;
+		dst.extend_from_slice(&self.send_buffer.output);
+		self.send_buffer.reset(SEND_BUFFER_CAPACITY);
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use bytes::BytesMut;
+	use tokio_util::codec::{Decoder, Encoder};
+
+	use crate::network::codec::McCodec;
+	use crate::network::network_error::NetworkError;
+	use crate::protocol::packets::{HandshakingBody, Packet, PingRequestBody, ServerboundPluginMessageBody};
+	use crate::protocol::packets::packet_definer::PacketState;
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	fn handshake() -> Packet {
+		Packet::Handshaking(HandshakingBody::new(VarInt(767), "localhost".to_string(), 25565, VarInt(1)))
+	}
+
+	#[test]
+	fn test_codec_framing() {
+		let mut client = McCodec::client();
+		let mut server = McCodec::server();
+
+		let mut wire = BytesMut::new();
+		client.encode(handshake(), &mut wire).unwrap();
+		client.change_state(PacketState::STATUS);
+		client.encode(Packet::PingRequest(PingRequestBody::new(42)), &mut wire).unwrap();
+
+		// feed the bytes one at a time, like a slow connection would
+		let mut src = BytesMut::new();
+		let mut packets = vec![];
+
+		for b in wire {
+			src.extend_from_slice(&[b]);
+
+			if let Some(packet) = server.decode(&mut src).unwrap() {
+				server.change_state(PacketState::STATUS);
+				packets.push(packet);
+			}
+		}
+
+		assert_eq!(packets, vec![handshake(), Packet::PingRequest(PingRequestBody::new(42))]);
+		assert!(src.is_empty());
+		assert!(matches!(server.decode(&mut BytesMut::from(&[0xFF, 0xFF, 0xFF, 0x01][..])), Err(NetworkError::SerializingErr(_))));
+	}
+
+	#[test]
+	fn test_codec_packet_limit() {
+		let mut server = McCodec::server();
+		let header = [0xA0, 0x8D, 0x06]; // 100000 bytes
+
+		assert_eq!(server.decode(&mut BytesMut::from(&header[..])), Err(NetworkError::PacketLengthExceeded { length: 100000, max: 32767 }));
+
+		server.change_state(PacketState::PLAY);
+		assert_eq!(server.decode(&mut BytesMut::from(&header[..])), Ok(None)); // fine once logged in
+
+		server.change_state(PacketState::LOGIN);
+		server.set_pre_login_packet_limit(200000);
+		assert_eq!(server.decode(&mut BytesMut::from(&header[..])), Ok(None));
+	}
+
+	#[test]
+	fn test_codec_compression_and_encryption() {
+		let secret = [7u8; 16];
+		let mut client = McCodec::client();
+		let mut server = McCodec::server();
+
+		for codec in [&mut client, &mut server] {
+			codec.change_state(PacketState::CONFIGURATION);
+			codec.enable_compression(Some(64));
+		}
+
+		client.enable_encryption(&secret).unwrap();
+
+		let big = Packet::ServerboundPluginMessage(ServerboundPluginMessageBody::new("minecraft:brand".parse().unwrap(), vec![1; 500].into()));
+		let small = Packet::ServerboundPluginMessage(ServerboundPluginMessageBody::new("minecraft:brand".parse().unwrap(), vec![2; 5].into()));
+
+		let mut wire = BytesMut::new();
+		client.encode(big.clone(), &mut wire).unwrap();
+		client.encode(small.clone(), &mut wire).unwrap();
+		assert!(wire.len() < 500);
+
+		// only the start of the first packet has arrived so far
+		let mut src = wire.split_to(3);
+		server.enable_encryption(&secret).unwrap();
+		assert!(server.decode(&mut src).unwrap().is_none());
+
+		src.extend_from_slice(&wire);
+		assert_eq!(server.decode(&mut src).unwrap(), Some(big));
+		assert_eq!(server.decode(&mut src).unwrap(), Some(small));
+		assert_eq!(server.decode(&mut src).unwrap(), None);
+	}
+}
@@ -0,0 +1,185 @@
+//! Packet compression utilities. Once a `SetCompression` packet has been sent during login, every
+//! packet on the connection uses the compressed packet format, even if the packet itself is not compressed.
+//!
+//! The compressed format is as follows:
+//! ```text
+//! +----------------+----------------+--------------------------------+
+//! | Packet Length  | Data Length    | Packet ID + Packet Data        |
+//! | (VarInt)       | (VarInt)       | (zlib compressed if Data > 0)  |
+//! +----------------+----------------+--------------------------------+
+//! ```
+//!
+//! `Data Length` is the length of the uncompressed packet ID and data, or 0 if the packet is smaller
+//! than the compression threshold and was sent uncompressed.
+//!
+//! See [https://wiki.vg/Protocol#With_compression](https://wiki.vg/Protocol#With_compression)
+
+use std::io::{Read, Write};
+
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+
+use crate::network::network_error::NetworkError;
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+/// The maximum uncompressed length of a packet that the Notchian client and server will accept.
+pub const MAX_UNCOMPRESSED_LENGTH: usize = 8388608; // 2^23
+
+/// The default zlib compression level. This is the same level used by zlib itself when no level is specified.
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 6;
+
+/// Convert a serialized packet in the uncompressed format (length, id, data) into the compressed format.
+/// Packets where the packet ID and data are smaller than `threshold` are left uncompressed, with a data
+/// length of 0.
+///
+/// `level` is the zlib compression level, from 0 (none) to 9 (best).
+pub fn compress_packet(packet: &[u8], threshold: i32, level: u32) -> Result<Vec<u8>, NetworkError> {
+	let mut deserializer = McDeserializer::new(packet);
+	let _ = VarInt::mc_deserialize(&mut deserializer)?; // skip the uncompressed length
+	let data = deserializer.collect_remaining();
+
+	let mut serializer = McSerializer::init_size(packet.len() + 3);
+
+	if (data.len() as i32) < threshold {
+		let data_length = VarInt(0);
+
+		VarInt((data.len() + data_length.encoded_len()) as i32).write_to(&mut serializer);
+		data_length.write_to(&mut serializer);
+		serializer.serialize_bytes(data);
+
+		return Ok(serializer.output);
+	}
+
+	let mut encoder = ZlibEncoder::new(Vec::with_capacity(data.len()), Compression::new(level.min(9)));
+	encoder.write_all(data)?;
+	let compressed = encoder.finish()?;
+
+	let data_length = VarInt(data.len() as i32);
+
+	VarInt((compressed.len() + data_length.encoded_len()) as i32).write_to(&mut serializer);
+	data_length.write_to(&mut serializer);
+	serializer.serialize_bytes(&compressed);
+
+	Ok(serializer.output)
+}
+
+/// Convert a packet in the compressed format (length, data length, compressed id and data) back into
+/// the uncompressed format (length, id, data) so that it can be deserialized.
+///
+/// `threshold` is used to reject packets that were compressed even though they should not have been,
+/// which is what the Notchian server does as well.
+pub fn decompress_packet(packet: &[u8], threshold: i32) -> Result<Vec<u8>, NetworkError> {
+	let mut deserializer = McDeserializer::new(packet);
+	let _ = VarInt::mc_deserialize(&mut deserializer)?; // skip the packet length
+	let data_length = VarInt::mc_deserialize(&mut deserializer)?.0;
+	let data = deserializer.collect_remaining();
+
+	if data_length < 0 {
+		return Err(NetworkError::CompressionError(format!("Negative data length {}", data_length)));
+	}
+
+	let data_length = data_length as usize;
+	let mut serializer = McSerializer::init_size(data_length.max(data.len()) + 3);
+
+	if data_length == 0 { // sent uncompressed
+		VarInt(data.len() as i32).mc_serialize(&mut serializer)?;
+		serializer.serialize_bytes(data);
+
+		return Ok(serializer.output);
+	}
+
+	if data_length < threshold.max(0) as usize {
+		return Err(NetworkError::CompressionError(format!("Badly compressed packet - size of {} is below server threshold of {}", data_length, threshold)));
+	}
+
+	if data_length > MAX_UNCOMPRESSED_LENGTH {
+		return Err(NetworkError::CompressionError(format!("Badly compressed packet - size of {} is larger than protocol maximum of {}", data_length, MAX_UNCOMPRESSED_LENGTH)));
+	}
+
+	let mut decompressed = Vec::with_capacity(data_length);
+	// take one extra byte so that a packet lying about its length can be detected
+	ZlibDecoder::new(data).take(data_length as u64 + 1).read_to_end(&mut decompressed)?;
+
+	if decompressed.len() != data_length {
+		return Err(NetworkError::CompressionError(format!("Data length mismatch, expected {} but got {}", data_length, decompressed.len())));
+	}
+
+	VarInt(data_length as i32).mc_serialize(&mut serializer)?;
+	serializer.serialize_bytes(&decompressed);
+
+	Ok(serializer.output)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::network::compression::{compress_packet, decompress_packet, DEFAULT_COMPRESSION_LEVEL};
+	use crate::network::network_error::NetworkError;
+	use crate::protocol::packets::{HandshakingBody, Packet, PluginMessageBody};
+	use crate::protocol::packets::packet_definer::{PacketDirection, PacketState};
+	use crate::protocol::serialization::{McDeserializer, McSerialize, McSerializer, StateBasedDeserializer};
+	use crate::protocol_types::datatypes::identifier::Identifier;
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	#[test]
+	fn test_below_threshold() {
+		let packet = Packet::Handshaking(HandshakingBody {
+			protocol_version: VarInt(766),
+			server_address: "localhost".to_string(),
+			port: 25565,
+			next_state: VarInt(2),
+		});
+
+		let mut serializer = McSerializer::new();
+		packet.mc_serialize(&mut serializer).unwrap();
+
+		let compressed = compress_packet(&serializer.output, 256, DEFAULT_COMPRESSION_LEVEL).unwrap();
+		assert_eq!(compressed[0] as usize, serializer.output.len()); // one extra byte for the data length
+		assert_eq!(compressed[1], 0); // data length of 0 means uncompressed
+		assert_eq!(&compressed[2..], &serializer.output[1..]);
+
+		let decompressed = decompress_packet(&compressed, 256).unwrap();
+		assert_eq!(decompressed, serializer.output);
+	}
+
+	#[test]
+	fn test_above_threshold() {
+		let packet = Packet::PluginMessage(PluginMessageBody {
+			channel: Identifier::minecraft("brand").unwrap(),
+			data: vec![7; 1000].into(),
+		});
+
+		let mut serializer = McSerializer::new();
+		packet.mc_serialize(&mut serializer).unwrap();
+
+		let compressed = compress_packet(&serializer.output, 256, DEFAULT_COMPRESSION_LEVEL).unwrap();
+		assert!(compressed.len() < serializer.output.len());
+
+		let decompressed = decompress_packet(&compressed, 256).unwrap();
+		assert_eq!(decompressed, serializer.output);
+
+		let mut deserializer = McDeserializer::new(&decompressed);
+		let out = Packet::deserialize_state(&mut deserializer, PacketState::CONFIGURATION, PacketDirection::CLIENT).unwrap();
+		assert_eq!(packet, out);
+	}
+
+	#[test]
+	fn test_badly_compressed() {
+		let packet = Packet::PluginMessage(PluginMessageBody {
+			channel: Identifier::minecraft("brand").unwrap(),
+			data: vec![7; 100].into(),
+		});
+
+		let mut serializer = McSerializer::new();
+		packet.mc_serialize(&mut serializer).unwrap();
+
+		// compressed with a lower threshold than the receiving side expects
+		let compressed = compress_packet(&serializer.output, 64, DEFAULT_COMPRESSION_LEVEL).unwrap();
+
+		match decompress_packet(&compressed, 256) {
+			Err(NetworkError::CompressionError(_)) => {}
+			other => panic!("Expected compression error, got {:?}", other)
+		}
+	}
+}
@@ -0,0 +1,325 @@
+//! This file defines the client side of the network API, which is a connection made *to* a server. This
+//! can be used to build bots, server list pingers, or clients for testing your own server.
+//!
+//! The [CraftConnector] uses the same packet definitions as the server side, the only difference is that
+//! it receives the packets sent by the server instead.
+
+use std::fmt::Display;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use log::debug;
+use tokio::net::TcpStream;
+use uuid::Uuid;
+
+use crate::network::client::CraftClient;
+use crate::network::client::cookies::CookieStore;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::{HandshakingBody, LoginAcknowledgedBody, LoginPluginResponseBody, LoginStartBody, Packet, PingRequestBody, StatusRequestBody};
+use crate::protocol::packets::packet_definer::{HandshakeIntent, PacketState};
+use crate::protocol::status::status_components::StatusResponseSpec;
+use crate::protocol_types::datatypes::game_profile::GameProfile;
+use crate::protocol_types::datatypes::bounded_string::Username;
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+/// The port servers listen on if the address doesn't include one
+pub const DEFAULT_PORT: u16 = 25565;
+/// How long [ping] waits for the whole exchange by default
+const DEFAULT_PING_TIMEOUT: Duration = Duration::from_secs(5);
+/// The protocol version sent by [ping]. By convention this means that the client is only asking which
+/// version the server runs.
+const PING_PROTOCOL_VERSION: i32 = -1;
+
+/// An outbound connection to a Minecraft server, from the client's perspective.
+#[derive(Debug)]
+pub struct CraftConnector {
+	connection: CraftClient,
+	host: String,
+	port: u16,
+}
+
+impl CraftConnector {
+	/// Connect to the server at the given host and port. The host and port are also sent to the server in
+	/// the handshake.
+	pub async fn connect<S: Into<String>>(host: S, port: u16) -> Result<Self, NetworkError> {
+		let host = host.into();
+		let tcp_stream = TcpStream::connect((host.as_str(), port)).await?;
+
+		Ok(Self {
+			connection: CraftClient::from_outbound_connection(tcp_stream)?,
+			host,
+			port,
+		})
+	}
+
+	/// Send the handshake to the server and switch to the requested state. `next_state` must be either
+	/// `STATUS` or `LOGIN`.
+	pub async fn handshake(&mut self, protocol_version: i32, next_state: PacketState) -> Result<(), NetworkError> {
+		let intent = next_state.get_id().and_then(|id| HandshakeIntent::from_id(id as i32))
+			.ok_or_else(|| NetworkError::InvalidNextState(format!("Cannot switch to {:?} from a handshake", next_state)))?;
+
+		self.handshake_intent(protocol_version, intent).await
+	}
+
+	/// Send the handshake with the given intent, such as [HandshakeIntent::TRANSFER] after being
+	/// transferred here by another server
+	pub async fn handshake_intent(&mut self, protocol_version: i32, intent: HandshakeIntent) -> Result<(), NetworkError> {
+		if self.connection.packet_state != PacketState::HANDSHAKING {
+			return Err(NetworkError::InvalidPacketState);
+		}
+
+		let packet = Packet::Handshaking(HandshakingBody {
+			protocol_version: VarInt(protocol_version),
+			server_address: self.host.clone(),
+			port: self.port,
+			next_state: VarInt(intent.get_id()),
+		});
+
+		self.connection.send_packet(packet).await?;
+		self.connection.handshake_intent = Some(intent);
+		self.connection.change_state(intent.state());
+
+		Ok(())
+	}
+
+	/// Request the status of the server, which is what is displayed in the server list.
+	/// The handshake must have been sent with the `STATUS` state first.
+	pub async fn status(&mut self) -> Result<StatusResponseSpec, NetworkError> {
+		if self.connection.packet_state != PacketState::STATUS {
+			return Err(NetworkError::InvalidPacketState);
+		}
+
+		self.connection.send_packet(Packet::StatusRequest(StatusRequestBody::new())).await?;
+
+		match self.connection.receive_packet().await? {
+			Packet::StatusResponse(body) => Ok(body.into()),
+			_ => Err(NetworkError::ExpectedDifferentPacket("Expected status response".to_string()))
+		}
+	}
+
+	/// Send a ping to the server and measure how long it takes to receive the response.
+	/// This is usually done after [CraftConnector::status].
+	pub async fn ping(&mut self) -> Result<Duration, NetworkError> {
+		if self.connection.packet_state != PacketState::STATUS {
+			return Err(NetworkError::InvalidPacketState);
+		}
+
+		let payload = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as i64;
+		let start = Instant::now();
+
+		self.connection.send_packet(Packet::PingRequest(PingRequestBody::new(payload))).await?;
+
+		match self.connection.receive_packet().await? {
+			Packet::PingResponse(_) => Ok(start.elapsed()),
+			_ => Err(NetworkError::ExpectedDifferentPacket("Expected ping response".to_string()))
+		}
+	}
+
+	/// Log in to an offline-mode server. This handles compression, login plugin requests and cookie requests, acknowledges the
+	/// login and moves the connection to the `CONFIGURATION` state. The handshake must have been sent with the
+	/// `LOGIN` state first.
+	///
+	/// Online-mode servers are not supported, since that would require a Minecraft account.
+	pub async fn login<S: Into<String>>(&mut self, username: S, uuid: Uuid) -> Result<GameProfile, NetworkError> {
+		if self.connection.packet_state != PacketState::LOGIN {
+			return Err(NetworkError::InvalidPacketState);
+		}
+
+		self.connection.send_packet(Packet::LoginStart(LoginStartBody::new(Username::new(username)?, uuid))).await?;
+
+		loop {
+			match self.connection.receive_packet().await? {
+				Packet::SetCompression(_) => {} // the connection switches to compression as it reads this
+				request @ Packet::LoginCookieRequest(_) => {
+					let response = self.connection.cookies.respond(&request).expect("a cookie request");
+					self.connection.send_packet(response).await?;
+				}
+				Packet::LoginPluginRequest(body) => { // we don't understand any plugin channels
+					debug!("Ignoring login plugin request on channel {}", body.channel);
+
+					let response = LoginPluginResponseBody::new(body.message_id, None);
+
+					self.connection.send_packet(Packet::LoginPluginResponse(response)).await?;
+				}
+				Packet::LoginSuccess(body) => {
+					self.connection.send_packet(Packet::LoginAcknowledged(LoginAcknowledgedBody::new())).await?;
+					self.connection.change_state(PacketState::CONFIGURATION);
+
+					return Ok(body.spec.profile);
+				}
+				Packet::EncryptionRequest(_) => {
+					return Err(NetworkError::EncryptionError("Online-mode servers are not supported".to_string()));
+				}
+				Packet::Disconnect(body) => {
+					return Err(NetworkError::Disconnected(body.reason.text));
+				}
+				_ => {
+					return Err(NetworkError::ExpectedDifferentPacket("Unexpected packet during login".to_string()));
+				}
+			}
+		}
+	}
+
+	/// Send a minecraft packet to the server. This will block until the packet is sent.
+	pub async fn send_packet(&mut self, packet: Packet) -> Result<(), NetworkError> {
+		self.connection.send_packet(packet).await
+	}
+
+	/// Receive a minecraft packet from the server. This will block until a packet is received.
+	pub async fn receive_packet(&mut self) -> Result<Packet, NetworkError> {
+		self.connection.receive_packet().await
+	}
+
+	/// Change the internal Packet State. This is done automatically by [CraftConnector::handshake] and
+	/// [CraftConnector::login].
+	pub fn change_state(&mut self, state: PacketState) {
+		self.connection.change_state(state);
+	}
+
+	/// Get the current Packet State of the connection
+	pub fn packet_state(&self) -> PacketState {
+		self.connection.packet_state
+	}
+
+	/// The cookies that servers stored on this client, which are sent back when a server asks for them
+	/// during login. Carry them over to the connection after a transfer to keep them.
+	pub fn cookies(&mut self) -> &mut CookieStore {
+		&mut self.connection.cookies
+	}
+
+	/// Get the underlying connection for anything not covered by the connector itself
+	pub fn connection(&mut self) -> &mut CraftClient {
+		&mut self.connection
+	}
+
+	/// Shutdown the connection as soon as possible
+	pub async fn close(&mut self) -> bool {
+		self.connection.close().await
+	}
+}
+
+/// The result of pinging a server with [ping]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerPing {
+	pub status: StatusResponseSpec,
+	/// The round trip time of the ping request
+	pub latency: Duration,
+}
+
+/// Get the status of a server and measure its latency, the same way the server list does. The address is
+/// a hostname or IP with an optional port, such as `mc.example.com`, `127.0.0.1:25566` or `[::1]:25565`.
+/// SRV records are not looked up.
+///
+/// This gives up with [NetworkError::TimedOut] after 5 seconds, see [ping_timeout] to change that.
+pub async fn ping(addr: &str) -> Result<ServerPing, NetworkError> {
+	ping_timeout(addr, DEFAULT_PING_TIMEOUT).await
+}
+
+/// Like [ping], but gives up once `timeout` has passed, counting from before the connection is made
+pub async fn ping_timeout(addr: &str, timeout: Duration) -> Result<ServerPing, NetworkError> {
+	let (host, port) = split_address(addr)?;
+
+	let exchange = async {
+		let mut connector = CraftConnector::connect(host, port).await?;
+		connector.handshake(PING_PROTOCOL_VERSION, PacketState::STATUS).await?;
+
+		let status = connector.status().await?;
+		let latency = connector.ping().await?;
+		connector.close().await;
+
+		Ok(ServerPing {
+			status,
+			latency,
+		})
+	};
+
+	tokio::time::timeout(timeout, exchange).await.map_err(|_| NetworkError::TimedOut)?
+}
+
+/// Split an address into its host and port, using the default port if there isn't one
+fn split_address(addr: &str) -> Result<(&str, u16), NetworkError> {
+	let invalid = || NetworkError::IOError(std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("Invalid server address {}", addr)));
+
+	if let Some(rest) = addr.strip_prefix('[') { // an IPv6 address, which has colons of its own
+		let (host, port) = rest.split_once(']').ok_or_else(invalid)?;
+
+		return match port.strip_prefix(':') {
+			Some(port) => Ok((host, port.parse().map_err(|_| invalid())?)),
+			None if port.is_empty() => Ok((host, DEFAULT_PORT)),
+			None => Err(invalid()),
+		};
+	}
+
+	match addr.split_once(':') {
+		Some((host, port)) if !port.contains(':') => Ok((host, port.parse().map_err(|_| invalid())?)),
+		_ => Ok((addr, DEFAULT_PORT)), // no port, or a bare IPv6 address
+	}
+}
+
+impl Display for CraftConnector {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "CraftConnector: {}:{}", self.host, self.port)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use uuid::Uuid;
+
+	use crate::network::connector::{CraftConnector, DEFAULT_PORT, ping, split_address};
+	use crate::network::network_error::NetworkError;
+	use crate::network::server::CraftServer;
+	use crate::protocol::login::DefaultLoginHandler;
+	use crate::protocol::packets::packet_definer::{HandshakeIntent, PacketState};
+	use crate::protocol::status::{DefaultHandshakeHandler, DefaultPingHandler, DefaultStatusHandler};
+	use crate::protocol::status::status_components::StatusResponseSpec;
+	use crate::protocol_types::protocol_verison::ProtocolVerison;
+
+	async fn start_server() -> u16 {
+		let status = StatusResponseSpec::new(ProtocolVerison::V1_20, "Hello World");
+		let server = CraftServer::bind("127.0.0.1:0", status).await.unwrap();
+		let port = server.local_addr().unwrap().port();
+
+		tokio::spawn(server.start::<DefaultHandshakeHandler, DefaultStatusHandler, DefaultPingHandler, DefaultLoginHandler>());
+
+		port
+	}
+
+	#[tokio::test]
+	async fn test_status_and_ping() {
+		let port = start_server().await;
+
+		let mut connector = CraftConnector::connect("127.0.0.1", port).await.unwrap();
+		connector.handshake(ProtocolVerison::V1_20.get_version_number() as i32, PacketState::STATUS).await.unwrap();
+
+		let status = connector.status().await.unwrap();
+		assert_eq!(status, StatusResponseSpec::new(ProtocolVerison::V1_20, "Hello World"));
+
+		connector.ping().await.unwrap();
+	}
+
+	#[tokio::test]
+	async fn test_ping_utility() {
+		let port = start_server().await;
+
+		let result = ping(&format!("127.0.0.1:{}", port)).await.unwrap();
+		assert_eq!(result.status, StatusResponseSpec::new(ProtocolVerison::V1_20, "Hello World"));
+		assert!(result.latency.as_secs() < 5);
+
+		assert_eq!(split_address("mc.example.com").unwrap(), ("mc.example.com", DEFAULT_PORT));
+		assert_eq!(split_address("mc.example.com:25566").unwrap(), ("mc.example.com", 25566));
+		assert_eq!(split_address("[::1]:25566").unwrap(), ("::1", 25566));
+		assert_eq!(split_address("::1").unwrap(), ("::1", DEFAULT_PORT));
+		assert!(split_address("mc.example.com:port").is_err());
+	}
+
+	#[tokio::test]
+	async fn test_login_rejected() {
+		let port = start_server().await;
+
+		let mut connector = CraftConnector::connect("127.0.0.1", port).await.unwrap();
+		connector.handshake(ProtocolVerison::V1_20.get_version_number() as i32, PacketState::LOGIN).await.unwrap();
+
+		let result = connector.login("dec4234", Uuid::new_v4()).await;
+		assert_eq!(result, Err(NetworkError::Disconnected("This server does not support logging in".to_string())));
+	}
+}
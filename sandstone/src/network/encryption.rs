@@ -0,0 +1,255 @@
+//! Protocol encryption utilities. https://wiki.vg/Protocol_Encryption
+//!
+//! Encryption is negotiated during login. The server sends its RSA public key and a verify token in an
+//! `EncryptionRequest`, the client responds with a shared secret and the verify token, both encrypted with
+//! that public key. From then on, every byte sent in either direction is encrypted with AES/CFB8, using
+//! the shared secret as both the key and the IV.
+
+use std::fmt::{Debug, Formatter};
+
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes::cipher::generic_array::GenericArray;
+use rand::RngCore;
+use rsa::{Pkcs1v15Encrypt, RsaPrivateKey, RsaPublicKey};
+use rsa::pkcs8::{DecodePublicKey, EncodePublicKey};
+
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::{EncryptionRequestBody, EncryptionResponseBody};
+use crate::protocol::packets::packet_component::{EncryptionRequestSpec, EncryptionResponseSpec};
+
+type Aes128Cfb8Enc = cfb8::Encryptor<aes::Aes128>;
+type Aes128Cfb8Dec = cfb8::Decryptor<aes::Aes128>;
+
+/// The size of the RSA key used by the Notchian server
+pub const RSA_KEY_BITS: usize = 1024;
+/// The length of the shared secret in bytes, which is used as an AES-128 key
+pub const SHARED_SECRET_LENGTH: usize = 16;
+/// The length of the verify token in bytes sent by the Notchian server
+pub const VERIFY_TOKEN_LENGTH: usize = 4;
+
+/// The RSA keypair of the server. This should be generated once when the server starts and then
+/// shared between all connections.
+#[derive(Clone)]
+pub struct ServerKey {
+	private_key: RsaPrivateKey,
+	public_key_der: Vec<u8>,
+}
+
+impl ServerKey {
+	/// Generate a new 1024-bit RSA keypair. This is fairly slow, so it should only be done once.
+	pub fn generate() -> Result<Self, NetworkError> {
+		let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), RSA_KEY_BITS)
+			.map_err(|e| NetworkError::EncryptionError(format!("Could not generate RSA keypair: {}", e)))?;
+
+		let public_key_der = RsaPublicKey::from(&private_key).to_public_key_der()
+			.map_err(|e| NetworkError::EncryptionError(format!("Could not encode public key: {}", e)))?
+			.to_vec();
+
+		Ok(Self {
+			private_key,
+			public_key_der,
+		})
+	}
+
+	/// Get the public key encoded in ASN.1 DER format, as it is sent to the client
+	pub fn public_key_der(&self) -> &[u8] {
+		&self.public_key_der
+	}
+
+	/// Decrypt data that the client encrypted using this server's public key
+	pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, NetworkError> {
+		self.private_key.decrypt(Pkcs1v15Encrypt, data)
+			.map_err(|e| NetworkError::EncryptionError(format!("Could not decrypt data: {}", e)))
+	}
+
+	/// Build the `EncryptionRequest` packet sent to the client. `server_id` is an empty string on
+	/// the Notchian server since 1.7.
+	pub fn encryption_request<S: Into<String>>(&self, server_id: S, verify_token: &[u8], should_authenticate: bool) -> EncryptionRequestBody {
+		EncryptionRequestBody::new(EncryptionRequestSpec {
+			server_id: server_id.into(),
+			public_key: self.public_key_der.clone(),
+			verify_token: verify_token.to_vec(),
+			should_authenticate,
+		})
+	}
+
+	/// Decrypt the `EncryptionResponse` sent by the client, check that the verify token matches the one
+	/// sent in the `EncryptionRequest` and return the shared secret.
+	pub fn decrypt_response(&self, response: &EncryptionResponseBody, verify_token: &[u8]) -> Result<[u8; SHARED_SECRET_LENGTH], NetworkError> {
+		let decrypted_token = self.decrypt(&response.spec.verify_token)?;
+
+		if decrypted_token != verify_token {
+			return Err(NetworkError::EncryptionError("Verify token does not match".to_string()));
+		}
+
+		let shared_secret = self.decrypt(&response.spec.shared_secret)?;
+
+		shared_secret.as_slice().try_into()
+			.map_err(|_| NetworkError::EncryptionError(format!("Shared secret must be {} bytes, got {}", SHARED_SECRET_LENGTH, shared_secret.len())))
+	}
+}
+
+impl Debug for ServerKey {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "ServerKey {{ public_key_der: {} }}", hex::encode(&self.public_key_der))
+	}
+}
+
+/// Generate a random verify token to be sent in the `EncryptionRequest`
+pub fn generate_verify_token() -> [u8; VERIFY_TOKEN_LENGTH] {
+	let mut token = [0u8; VERIFY_TOKEN_LENGTH];
+	rand::thread_rng().fill_bytes(&mut token);
+	token
+}
+
+/// Generate a random shared secret. This is done by the client.
+pub fn generate_shared_secret() -> [u8; SHARED_SECRET_LENGTH] {
+	let mut secret = [0u8; SHARED_SECRET_LENGTH];
+	rand::thread_rng().fill_bytes(&mut secret);
+	secret
+}
+
+/// Build the `EncryptionResponse` packet from the client's perspective, encrypting the shared secret
+/// and verify token with the public key received in the `EncryptionRequest`.
+pub fn encryption_response(public_key_der: &[u8], shared_secret: &[u8], verify_token: &[u8]) -> Result<EncryptionResponseBody, NetworkError> {
+	let public_key = RsaPublicKey::from_public_key_der(public_key_der)
+		.map_err(|e| NetworkError::EncryptionError(format!("Could not decode public key: {}", e)))?;
+
+	let mut rng = rand::thread_rng();
+
+	let shared_secret = public_key.encrypt(&mut rng, Pkcs1v15Encrypt, shared_secret)
+		.map_err(|e| NetworkError::EncryptionError(format!("Could not encrypt shared secret: {}", e)))?;
+	let verify_token = public_key.encrypt(&mut rng, Pkcs1v15Encrypt, verify_token)
+		.map_err(|e| NetworkError::EncryptionError(format!("Could not encrypt verify token: {}", e)))?;
+
+	Ok(EncryptionResponseBody::new(EncryptionResponseSpec {
+		shared_secret,
+		verify_token,
+	}))
+}
+
+/// The AES/CFB8 stream cipher used to encrypt and decrypt all traffic on a connection once encryption
+/// has been enabled. CFB8 works on single bytes, so the data does not need to be aligned in any way and
+/// the cipher state carries over between calls.
+#[derive(Clone)]
+pub struct CraftCipher {
+	encryptor: Aes128Cfb8Enc,
+	decryptor: Aes128Cfb8Dec,
+}
+
+impl CraftCipher {
+	/// Create a new cipher from the shared secret, which is used as both the key and the IV
+	pub fn new(shared_secret: &[u8]) -> Result<Self, NetworkError> {
+		if shared_secret.len() != SHARED_SECRET_LENGTH {
+			return Err(NetworkError::EncryptionError(format!("Shared secret must be {} bytes, got {}", SHARED_SECRET_LENGTH, shared_secret.len())));
+		}
+
+		let key = GenericArray::from_slice(shared_secret);
+
+		Ok(Self {
+			encryptor: Aes128Cfb8Enc::new(key, key),
+			decryptor: Aes128Cfb8Dec::new(key, key),
+		})
+	}
+
+	/// Encrypt outgoing data in place
+	pub fn encrypt(&mut self, data: &mut [u8]) {
+		for b in data.iter_mut() {
+			self.encryptor.encrypt_block_mut(GenericArray::from_mut_slice(std::slice::from_mut(b)));
+		}
+	}
+
+	/// Decrypt incoming data in place
+	pub fn decrypt(&mut self, data: &mut [u8]) {
+		for b in data.iter_mut() {
+			self.decryptor.decrypt_block_mut(GenericArray::from_mut_slice(std::slice::from_mut(b)));
+		}
+	}
+
+	/// Decrypt incoming data in place without advancing the state of the cipher. Used to peek at data
+	/// that will be read again later.
+	pub fn decrypt_peek(&self, data: &mut [u8]) {
+		let mut decryptor = self.decryptor.clone();
+
+		for b in data.iter_mut() {
+			decryptor.decrypt_block_mut(GenericArray::from_mut_slice(std::slice::from_mut(b)));
+		}
+	}
+}
+
+impl Debug for CraftCipher {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "CraftCipher") // don't leak the key
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use aes::cipher::{AsyncStreamCipher, KeyIvInit};
+
+	use crate::network::encryption::{Aes128Cfb8Dec, Aes128Cfb8Enc, CraftCipher, encryption_response, generate_shared_secret, generate_verify_token, ServerKey};
+	use crate::network::network_error::NetworkError;
+
+	#[test]
+	fn encryption_testing() {
+		let key = [0x42; 16];
+		let iv = [0x24; 16];
+		let text = *b"HELLO WORLD ABCDEFGHIJKLMNOPQRSTUV";
+
+		let mut buf = text.to_vec();
+		Aes128Cfb8Enc::new(&key.into(), &iv.into()).encrypt(&mut buf);
+
+		assert_ne!(buf[..], text[..]); // encrypted should not be the same as original
+
+		Aes128Cfb8Dec::new(&key.into(), &iv.into()).decrypt(&mut buf);
+		assert_eq!(buf[..], text[..]);
+
+		let mut buf = [0u8; 34];
+		Aes128Cfb8Enc::new(&key.into(), &iv.into()).encrypt_b2b(&text, &mut buf).unwrap();
+
+		let mut out_buf = [0u8; 34];
+		Aes128Cfb8Dec::new(&key.into(), &iv.into()).decrypt_b2b(&buf, &mut out_buf).unwrap();
+		assert_eq!(out_buf[..], text[..]);
+	}
+
+	#[test]
+	fn test_stream_cipher_matches_one_shot() {
+		let secret = [0x42; 16];
+		let text = *b"HELLO WORLD ABCDEFGHIJKLMNOPQRSTUV";
+
+		let mut expected = text.to_vec();
+		Aes128Cfb8Enc::new(&secret.into(), &secret.into()).encrypt(&mut expected);
+
+		// encrypting in multiple chunks must produce the same output as encrypting it all at once
+		let mut cipher = CraftCipher::new(&secret).unwrap();
+		let mut buf = text.to_vec();
+		let (first, second) = buf.split_at_mut(5);
+		cipher.encrypt(first);
+		cipher.encrypt(second);
+		assert_eq!(buf, expected);
+
+		let mut peeked = buf.clone();
+		cipher.decrypt_peek(&mut peeked);
+		assert_eq!(peeked[..], text[..]);
+
+		let (first, second) = buf.split_at_mut(20);
+		cipher.decrypt(first);
+		cipher.decrypt(second);
+		assert_eq!(buf[..], text[..]);
+	}
+
+	#[test]
+	fn test_key_exchange() {
+		let server_key = ServerKey::generate().unwrap();
+		let verify_token = generate_verify_token();
+		let request = server_key.encryption_request("", &verify_token, true);
+
+		let shared_secret = generate_shared_secret();
+		let response = encryption_response(&request.spec.public_key, &shared_secret, &request.spec.verify_token).unwrap();
+
+		assert_eq!(server_key.decrypt_response(&response, &verify_token).unwrap(), shared_secret);
+
+		let wrong_token = [verify_token[0].wrapping_add(1), 0, 0, 0];
+		assert_eq!(server_key.decrypt_response(&response, &wrong_token), Err(NetworkError::EncryptionError("Verify token does not match".to_string())));
+	}
+}
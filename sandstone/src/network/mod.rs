@@ -6,4 +6,8 @@
 
 pub mod network_error;
 pub mod client;
+pub mod codec;
+pub mod compression;
+pub mod connector;
+pub mod encryption;
 pub mod server;
\ No newline at end of file
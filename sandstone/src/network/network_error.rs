@@ -4,6 +4,8 @@ use std::io;
 use thiserror::Error;
 
 use crate::protocol::serialization::serializer_error::SerializingErr;
+#[cfg(feature = "mojang")]
+use crate::util::mojang::http::HttpError;
 
 /// Any sort of error that could occur while performing or processing a network request.
 #[derive(Error, Debug)]
@@ -22,13 +24,38 @@ pub enum NetworkError {
 	InvalidPacketDirection,
 	#[error("Packet too large")]
 	PacketTooLarge,
+	#[error("Packet length of {length} bytes is over the limit of {max} for this state")]
+	PacketLengthExceeded { length: i64, max: usize },
 	#[error("Expected different packet: {0}")]
 	ExpectedDifferentPacket(String),
+	#[error("Compression error: {0}")]
+	CompressionError(String),
+	#[error("Encryption error: {0}")]
+	EncryptionError(String),
+	#[error("Disconnected by the other side: {0}")]
+	Disconnected(String),
+	#[error("Timed out waiting for a packet")]
+	TimedOut,
+	#[error("The client didn't answer a Keep Alive in time")]
+	KeepAliveTimedOut,
+	#[error("Player info forwarding failed: {0}")]
+	ForwardingFailed(String),
+	#[error("Login rejected: {0}")]
+	LoginRejected(String),
+	#[error("Invalid signature: {0}")]
+	InvalidSignature(String),
+	#[error("Unknown entry {entry} in registry {registry}")]
+	UnknownRegistryEntry { registry: String, entry: String },
+	#[error("The client's version doesn't support {0}")]
+	UnsupportedByClient(String),
 	
 	#[error(transparent)]
 	SerializingErr(#[from] SerializingErr),
 	#[error(transparent)]
 	IOError(#[from] io::Error),
+	#[cfg(feature = "mojang")]
+	#[error(transparent)]
+	HttpError(#[from] HttpError),
 }
 
 impl PartialEq for NetworkError {
@@ -41,10 +68,23 @@ impl PartialEq for NetworkError {
 			(NetworkError::InvalidNextState(a), NetworkError::InvalidNextState(b)) => a == b,
 			(NetworkError::InvalidPacketDirection, NetworkError::InvalidPacketDirection) => true,
 			(NetworkError::PacketTooLarge, NetworkError::PacketTooLarge) => true,
+			(NetworkError::PacketLengthExceeded { length: a, max: c }, NetworkError::PacketLengthExceeded { length: b, max: d }) => a == b && c == d,
 			(NetworkError::ExpectedDifferentPacket(a), NetworkError::ExpectedDifferentPacket(b)) => a == b,
+			(NetworkError::CompressionError(a), NetworkError::CompressionError(b)) => a == b,
+			(NetworkError::EncryptionError(a), NetworkError::EncryptionError(b)) => a == b,
+			(NetworkError::Disconnected(a), NetworkError::Disconnected(b)) => a == b,
+			(NetworkError::TimedOut, NetworkError::TimedOut) => true,
+			(NetworkError::KeepAliveTimedOut, NetworkError::KeepAliveTimedOut) => true,
+			(NetworkError::ForwardingFailed(a), NetworkError::ForwardingFailed(b)) => a == b,
+			(NetworkError::LoginRejected(a), NetworkError::LoginRejected(b)) => a == b,
+			(NetworkError::InvalidSignature(a), NetworkError::InvalidSignature(b)) => a == b,
+			(NetworkError::UnknownRegistryEntry { registry: a, entry: c }, NetworkError::UnknownRegistryEntry { registry: b, entry: d }) => a == b && c == d,
+			(NetworkError::UnsupportedByClient(a), NetworkError::UnsupportedByClient(b)) => a == b,
 			
 			(NetworkError::SerializingErr(a), NetworkError::SerializingErr(b)) => a == b,
 			(NetworkError::IOError(a), NetworkError::IOError(b)) => a.to_string() == b.to_string(),
+			#[cfg(feature = "mojang")]
+			(NetworkError::HttpError(a), NetworkError::HttpError(b)) => a.to_string() == b.to_string(),
 			_ => false
 		}
 	}
@@ -0,0 +1,266 @@
+//! A builder for [CraftServer], so that all of the server settings are configured in one place instead
+//! of on each connection.
+
+use std::time::Duration;
+
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use crate::network::client::DEFAULT_PRE_LOGIN_PACKET_LIMIT;
+use crate::network::network_error::NetworkError;
+use crate::network::server::{CraftServer, DEFAULT_IDLE_TIMEOUT, DEFAULT_SHUTDOWN_TIMEOUT, StatusSupplier};
+use crate::network::server::guard::{HandshakeGuard, HandshakeGuardConfig};
+use crate::network::server::throttle::{ConnectionThrottle, ThrottleConfig};
+use crate::protocol::status::status_components::StatusResponseSpec;
+use crate::protocol::status::StatusContext;
+use crate::protocol_types::datatypes::chat::TextComponent;
+use crate::protocol_types::protocol_verison::ProtocolVerison;
+
+/// The address vanilla servers listen on by default
+pub const DEFAULT_BIND_ADDRESS: &str = "0.0.0.0:25565";
+/// The MOTD used when no status response is given, the same as vanilla
+pub const DEFAULT_MOTD: &str = "A Minecraft Server";
+/// The maximum number of players shown when no status response is given, the same as vanilla
+pub const DEFAULT_MAX_PLAYERS: i32 = 20;
+
+/// The settings of a [CraftServer]. The timeouts and packet limit are given to every accepted
+/// [CraftClient](crate::network::client::CraftClient), while the rest are for the login handler to use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+	/// Whether players are authenticated with Mojang during login
+	pub online_mode: bool,
+	/// The compression threshold sent with Set Compression during login, `None` to disable compression
+	pub compression_threshold: Option<i32>,
+	/// The maximum number of players, which replaces the maximum in the status response if set
+	pub max_players: Option<i32>,
+	pub read_timeout: Option<Duration>,
+	pub idle_timeout: Option<Duration>,
+	/// See [CraftClient::set_pre_login_packet_limit](crate::network::client::CraftClient::set_pre_login_packet_limit)
+	pub pre_login_packet_limit: usize,
+	/// The reason shown to clients that are disconnected when the server shuts down
+	pub shutdown_message: TextComponent,
+	/// How long a shutdown waits for connections to close, `None` to wait forever
+	pub shutdown_timeout: Option<Duration>,
+	/// Limits how often each address can connect, see [ConnectionThrottle]. This is off by default.
+	pub connection_throttle: Option<ThrottleConfig>,
+	/// Limits how long and how many connections each address can keep in the handshake and status
+	/// states, see [HandshakeGuard]
+	pub handshake_guard: Option<HandshakeGuardConfig>,
+	/// Whether chat messages must be signed, which replaces the value in the status response if set.
+	/// Vanilla enforces it by default, but it is left to the status response here since the library
+	/// doesn't verify signed chat.
+	pub enforce_secure_chat: Option<bool>,
+	/// Advertise that chat messages can't be reported, like the No Chat Reports mod does. This replaces
+	/// the value in the status response if set.
+	pub prevents_chat_reports: Option<bool>,
+	/// Reuse each status response for this long instead of building it for every ping, see
+	/// [StatusSupplier::cached]. This is off by default.
+	pub status_cache: Option<Duration>,
+}
+
+impl Default for ServerConfig {
+	/// The same settings as a vanilla server
+	fn default() -> Self {
+		Self {
+			online_mode: true,
+			compression_threshold: Some(256),
+			max_players: None,
+			read_timeout: None,
+			idle_timeout: Some(DEFAULT_IDLE_TIMEOUT),
+			pre_login_packet_limit: DEFAULT_PRE_LOGIN_PACKET_LIMIT,
+			shutdown_message: TextComponent::translatable("multiplayer.disconnect.server_shutdown", vec![]),
+			shutdown_timeout: Some(DEFAULT_SHUTDOWN_TIMEOUT),
+			connection_throttle: None,
+			handshake_guard: Some(HandshakeGuardConfig::default()),
+			enforce_secure_chat: None,
+			prevents_chat_reports: None,
+			status_cache: None,
+		}
+	}
+}
+
+/// Configures and binds a [CraftServer]. Anything that isn't set uses the vanilla default.
+///
+/// ```no_run
+/// # use sandstone::network::server::CraftServer;
+/// # async fn run() {
+/// let server = CraftServer::builder()
+///     .bind_address("127.0.0.1:25565")
+///     .online_mode(false)
+///     .max_players(100)
+///     .motd("&aHello World")
+///     .build().await.unwrap();
+/// # }
+/// ```
+#[derive(Default)]
+pub struct ServerBuilder {
+	address: Option<String>,
+	config: ServerConfig,
+	motd: Option<String>,
+	status: Option<StatusSupplier>,
+}
+
+impl ServerBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The address to listen on, such as `0.0.0.0:25565`
+	pub fn bind_address<T: Into<String>>(mut self, address: T) -> Self {
+		self.address = Some(address.into());
+		self
+	}
+
+	pub fn online_mode(mut self, online_mode: bool) -> Self {
+		self.config.online_mode = online_mode;
+		self
+	}
+
+	/// Packets at least this long are compressed. `None` or a negative threshold disables compression.
+	pub fn compression_threshold(mut self, threshold: Option<i32>) -> Self {
+		self.config.compression_threshold = threshold.filter(|t| *t >= 0);
+		self
+	}
+
+	/// The maximum number of players. This replaces the maximum in the status response.
+	pub fn max_players(mut self, max_players: i32) -> Self {
+		self.config.max_players = Some(max_players);
+		self
+	}
+
+	/// The description shown in the server list. This replaces the description in the status response,
+	/// and has its color codes translated from '&' to '§'.
+	pub fn motd<T: Into<String>>(mut self, motd: T) -> Self {
+		self.motd = Some(motd.into());
+		self
+	}
+
+	/// The status response returned to every client that requests the server status
+	pub fn status(self, status: StatusResponseSpec) -> Self {
+		self.status_supplier(move |_| status.clone())
+	}
+
+	/// Build the status response each time it is requested, so that it can change while the server is
+	/// running, such as to show the current player count. The [StatusContext] describes the client that
+	/// asked, so the response can also depend on its version or the hostname it connected with.
+	pub fn status_supplier<F: Fn(&StatusContext) -> StatusResponseSpec + Send + Sync + 'static>(mut self, supplier: F) -> Self {
+		self.status = Some(StatusSupplier::new(supplier));
+		self
+	}
+
+	/// See [CraftClient::set_read_timeout](crate::network::client::CraftClient::set_read_timeout)
+	pub fn read_timeout(mut self, timeout: Option<Duration>) -> Self {
+		self.config.read_timeout = timeout;
+		self
+	}
+
+	/// See [CraftClient::set_idle_timeout](crate::network::client::CraftClient::set_idle_timeout)
+	pub fn idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+		self.config.idle_timeout = timeout;
+		self
+	}
+
+	/// The largest packet accepted before login, see [ServerConfig::pre_login_packet_limit]
+	pub fn pre_login_packet_limit(mut self, limit: usize) -> Self {
+		self.config.pre_login_packet_limit = limit;
+		self
+	}
+
+	/// The reason shown to clients when the server shuts down. This is "Server closed" by default.
+	pub fn shutdown_message<T: Into<TextComponent>>(mut self, message: T) -> Self {
+		self.config.shutdown_message = message.into();
+		self
+	}
+
+	/// How long a shutdown waits for connections to close before giving up, 10 seconds by default
+	pub fn shutdown_timeout(mut self, timeout: Option<Duration>) -> Self {
+		self.config.shutdown_timeout = timeout;
+		self
+	}
+
+	/// Limit how often each address can connect. Connections over the limit are closed before anything
+	/// is read from them.
+	pub fn connection_throttle(mut self, throttle: ThrottleConfig) -> Self {
+		self.config.connection_throttle = Some(throttle);
+		self
+	}
+
+	/// Limit how long connections can take to get through the handshake and status states, and how many
+	/// of them each address can have open at once. This is on by default, `None` turns it off.
+	pub fn handshake_guard(mut self, guard: Option<HandshakeGuardConfig>) -> Self {
+		self.config.handshake_guard = guard;
+		self
+	}
+
+	/// Require chat messages to be signed. This replaces the value in the status response, so clients
+	/// show the right warning in the server list.
+	pub fn enforce_secure_chat(mut self, enforce: bool) -> Self {
+		self.config.enforce_secure_chat = Some(enforce);
+		self
+	}
+
+	/// Tell clients that chat messages can't be reported. Like [ServerBuilder::enforce_secure_chat],
+	/// this replaces the value in the status response.
+	pub fn prevents_chat_reports(mut self, prevents: bool) -> Self {
+		self.config.prevents_chat_reports = Some(prevents);
+		self
+	}
+
+	/// Build the status response once and reuse it until it is `ttl` old, so that a server being scanned
+	/// doesn't rebuild it for every ping. Use [CraftServer::status_supplier] to throw it away sooner.
+	pub fn cache_status(mut self, ttl: Duration) -> Self {
+		self.config.status_cache = Some(ttl);
+		self
+	}
+
+	/// Bind to the address and create the server
+	pub async fn build(self) -> Result<CraftServer, NetworkError> {
+		let listener = TcpListener::bind(self.address.as_deref().unwrap_or(DEFAULT_BIND_ADDRESS)).await?;
+
+		let base = self.status.unwrap_or_else(|| {
+			let mut status = StatusResponseSpec::new(ProtocolVerison::V1_20, DEFAULT_MOTD);
+			status.set_max_players(DEFAULT_MAX_PLAYERS);
+			StatusSupplier::fixed(status)
+		});
+
+		let motd = self.motd;
+		let max_players = self.config.max_players;
+		let secure_chat = self.config.enforce_secure_chat;
+		let prevents_chat_reports = self.config.prevents_chat_reports;
+
+		let mut status = StatusSupplier::new(move |context| {
+			let mut status = base.build(context);
+
+			if let Some(motd) = &motd {
+				status.set_description(motd.clone());
+			}
+
+			if let Some(max_players) = max_players {
+				status.set_max_players(max_players);
+			}
+
+			if let Some(secure_chat) = secure_chat {
+				status.set_secure_chat(secure_chat);
+			}
+
+			if let Some(prevents_chat_reports) = prevents_chat_reports {
+				status.set_prevents_chat_reports(prevents_chat_reports);
+			}
+
+			status
+		});
+
+		if let Some(ttl) = self.config.status_cache {
+			status = status.cached(Some(ttl));
+		}
+
+		Ok(CraftServer {
+			listener,
+			status,
+			throttle: self.config.connection_throttle.clone().map(ConnectionThrottle::new),
+			guard: self.config.handshake_guard.clone().map(HandshakeGuard::new),
+			config: self.config,
+			shutdown: CancellationToken::new(),
+		})
+	}
+}
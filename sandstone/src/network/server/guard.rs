@@ -0,0 +1,129 @@
+//! Protects the server from connections that open a socket and never finish the handshake, such as a
+//! handshake flood or a slowloris attack that sends a byte every few seconds to hold the connection open.
+//!
+//! Every connection gets a deadline to leave the `HANDSHAKING` and `STATUS` states, and each address can
+//! only have a limited number of connections in those states at once.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The settings of a [HandshakeGuard]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandshakeGuardConfig {
+	/// How long a connection can stay in the `HANDSHAKING` and `STATUS` states. Trickling in bytes doesn't
+	/// extend this, so a slow client is dropped once it runs out.
+	pub timeout: Duration,
+	/// How many connections a single address can have in the `HANDSHAKING` and `STATUS` states at once
+	pub max_per_address: u32,
+}
+
+impl HandshakeGuardConfig {
+	pub fn new(timeout: Duration, max_per_address: u32) -> Self {
+		Self {
+			timeout,
+			max_per_address,
+		}
+	}
+}
+
+impl Default for HandshakeGuardConfig {
+	/// A real client finishes the handshake and ping in well under a second
+	fn default() -> Self {
+		Self::new(Duration::from_secs(10), 8)
+	}
+}
+
+/// Counts the half-open connections of each address, meaning the ones that haven't made it past the
+/// `STATUS` state yet
+#[derive(Debug)]
+pub struct HandshakeGuard {
+	config: HandshakeGuardConfig,
+	half_open: Arc<Mutex<HashMap<IpAddr, u32>>>,
+}
+
+impl HandshakeGuard {
+	pub fn new(config: HandshakeGuardConfig) -> Self {
+		Self {
+			config,
+			half_open: Arc::new(Mutex::new(HashMap::new())),
+		}
+	}
+
+	/// Start tracking a new connection from `addr`, returning `None` if the address already has too many
+	/// half-open connections. The connection counts until the permit is dropped.
+	pub fn try_acquire(&self, addr: IpAddr) -> Option<HalfOpenPermit> {
+		let mut half_open = self.half_open.lock().unwrap();
+		let count = half_open.entry(addr).or_insert(0);
+
+		if *count >= self.config.max_per_address {
+			return None;
+		}
+
+		*count += 1;
+
+		Some(HalfOpenPermit {
+			addr,
+			half_open: self.half_open.clone(),
+		})
+	}
+
+	/// The number of half-open connections from `addr`
+	pub fn half_open(&self, addr: IpAddr) -> u32 {
+		self.half_open.lock().unwrap().get(&addr).copied().unwrap_or(0)
+	}
+
+	pub fn config(&self) -> &HandshakeGuardConfig {
+		&self.config
+	}
+}
+
+/// Marks a connection as half-open until it is dropped. A [CraftClient](crate::network::client::CraftClient)
+/// drops its permit once it moves past the `STATUS` state or is closed.
+#[derive(Debug)]
+pub struct HalfOpenPermit {
+	addr: IpAddr,
+	half_open: Arc<Mutex<HashMap<IpAddr, u32>>>,
+}
+
+impl Drop for HalfOpenPermit {
+	fn drop(&mut self) {
+		let mut half_open = self.half_open.lock().unwrap();
+
+		if let Some(count) = half_open.get_mut(&self.addr) {
+			*count -= 1;
+
+			if *count == 0 {
+				half_open.remove(&self.addr);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::{IpAddr, Ipv4Addr};
+	use std::time::Duration;
+
+	use crate::network::server::guard::{HandshakeGuard, HandshakeGuardConfig};
+
+	#[test]
+	fn test_half_open_limit() {
+		let addr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+		let guard = HandshakeGuard::new(HandshakeGuardConfig::new(Duration::from_secs(1), 2));
+
+		let first = guard.try_acquire(addr).unwrap();
+		let second = guard.try_acquire(addr).unwrap();
+		assert!(guard.try_acquire(addr).is_none());
+		assert!(guard.try_acquire(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2))).is_some());
+		assert_eq!(guard.half_open(addr), 2);
+
+		drop(first);
+		assert_eq!(guard.half_open(addr), 1);
+		assert!(guard.try_acquire(addr).is_some());
+
+		drop(second);
+		assert_eq!(guard.half_open(addr), 0);
+	}
+}
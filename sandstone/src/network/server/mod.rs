@@ -0,0 +1,321 @@
+//! This file defines the server side of the network API. The [CraftServer] accepts new connections and
+//! routes each one through the handshake, status and login handlers on its own task, so that you don't
+//! have to write the accept loop and state dispatch yourself.
+//!
+//! Handlers are registered by type, see [client_handlers](crate::network::client::client_handlers) for the
+//! traits that need to be implemented and [crate::protocol::status] for the default implementations.
+
+use std::fmt::{Debug, Formatter};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{debug, error, trace};
+use tokio::net::{TcpListener, ToSocketAddrs};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+use crate::network::client::client_handlers::{HandshakeHandler, LoginHandler, PingHandler, ServerHandler, StatusHandler};
+use crate::network::client::CraftClient;
+use crate::network::network_error::NetworkError;
+use crate::network::server::builder::{ServerBuilder, ServerConfig};
+use crate::network::server::guard::HandshakeGuard;
+use crate::network::server::pipeline::{ConnectionHandler, Pipeline, StaticHandlers};
+use crate::network::server::throttle::ConnectionThrottle;
+use crate::protocol::packets::packet_definer::PacketState;
+use crate::protocol::status::status_components::StatusResponseSpec;
+use crate::protocol::status::legacy::{detect_legacy_ping, respond_legacy_ping};
+use crate::protocol::status::StatusContext;
+
+pub mod builder;
+pub mod guard;
+mod pipeline;
+pub mod registry;
+pub mod status;
+pub mod throttle;
+#[cfg(test)]
+pub(crate) mod server_testing;
+
+/// Vanilla drops connections that haven't sent anything in 30 seconds
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long a shutdown waits for connections to close by default
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub use status::StatusSupplier;
+
+/// Listens for new connections from Minecraft clients. Every accepted connection is turned into a
+/// [CraftClient] and handled on its own task. Use [CraftServer::builder] to configure the server.
+///
+/// ```no_run
+/// # use sandstone::network::server::CraftServer;
+/// # use sandstone::protocol::login::DefaultLoginHandler;
+/// # use sandstone::protocol::status::{DefaultHandshakeHandler, DefaultPingHandler, DefaultStatusHandler};
+/// # use sandstone::protocol::status::status_components::StatusResponseSpec;
+/// # use sandstone::protocol_types::protocol_verison::ProtocolVerison;
+/// # async fn run() {
+/// let status = StatusResponseSpec::new(ProtocolVerison::V1_20, "&aHello World");
+/// let server = CraftServer::bind("127.0.0.1:25565", status).await.unwrap();
+///
+/// server.start::<DefaultHandshakeHandler, DefaultStatusHandler, DefaultPingHandler, DefaultLoginHandler>().await;
+/// # }
+/// ```
+pub struct CraftServer {
+	listener: TcpListener,
+	status: StatusSupplier,
+	config: ServerConfig,
+	throttle: Option<ConnectionThrottle>,
+	guard: Option<HandshakeGuard>,
+	shutdown: CancellationToken,
+}
+
+impl CraftServer {
+	/// Bind the server to the given address. `status_response` is returned to every client that requests
+	/// the server status. The rest of the settings are the same as [ServerConfig::default].
+	pub async fn bind<A: ToSocketAddrs>(addr: A, status_response: StatusResponseSpec) -> Result<Self, NetworkError> {
+		let listener = TcpListener::bind(addr).await?;
+		let config = ServerConfig::default();
+
+		Ok(Self {
+			listener,
+			status: StatusSupplier::fixed(status_response),
+			throttle: None,
+			guard: config.handshake_guard.clone().map(HandshakeGuard::new),
+			config,
+			shutdown: CancellationToken::new(),
+		})
+	}
+
+	/// Start configuring a server, see [ServerBuilder]
+	pub fn builder() -> ServerBuilder {
+		ServerBuilder::new()
+	}
+
+	/// Get the settings of the server
+	pub fn config(&self) -> &ServerConfig {
+		&self.config
+	}
+
+	/// Get the address that the server is bound to. Useful if the server was bound to port 0.
+	pub fn local_addr(&self) -> Result<SocketAddr, NetworkError> {
+		Ok(self.listener.local_addr()?)
+	}
+
+	/// Change the status response returned to clients. This only affects connections accepted after the change.
+	pub fn set_status_response(&mut self, status_response: StatusResponseSpec) {
+		self.status = StatusSupplier::fixed(status_response);
+	}
+
+	/// Get the supplier of the status response. Clones of it share its cache, so a clone taken before
+	/// the server starts can be used to [invalidate](StatusSupplier::invalidate) the cached response.
+	pub fn status_supplier(&self) -> &StatusSupplier {
+		&self.status
+	}
+
+	/// Set the read timeout given to accepted connections, see [CraftClient::set_read_timeout]. There
+	/// is none by default.
+	pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+		self.config.read_timeout = timeout;
+	}
+
+	/// Set the idle timeout given to accepted connections, see [CraftClient::set_idle_timeout]. This is
+	/// 30 seconds by default, like vanilla.
+	pub fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+		self.config.idle_timeout = timeout;
+	}
+
+	/// Wait for the next connection and wrap it into a [CraftClient]. Use this if you would like to handle
+	/// the connections yourself instead of using [CraftServer::start].
+	///
+	/// Connections rejected by the connection throttle or the handshake guard are closed straight away and
+	/// never returned.
+	pub async fn accept(&self) -> Result<CraftClient, NetworkError> {
+		let (socket, permit) = loop {
+			let (socket, addr) = self.listener.accept().await?;
+
+			if let Some(throttle) = &self.throttle {
+				if !throttle.allow(addr.ip()) {
+					debug!("Throttled connection from {}", addr);
+					continue;
+				}
+			}
+
+			match &self.guard {
+				Some(guard) => match guard.try_acquire(addr.ip()) {
+					Some(permit) => break (socket, Some(permit)),
+					None => debug!("Too many half-open connections from {}", addr),
+				},
+				None => break (socket, None),
+			}
+		};
+
+		let mut client = CraftClient::from_connection(socket)?;
+		client.set_read_timeout(self.config.read_timeout);
+		client.set_idle_timeout(self.config.idle_timeout);
+		client.set_pre_login_packet_limit(self.config.pre_login_packet_limit);
+		client.set_login_compression(self.config.compression_threshold);
+
+		if let Some(guard) = &self.guard {
+			client.set_handshake_timeout(Some(guard.config().timeout));
+			client.half_open_permit = permit;
+		}
+
+		Ok(client)
+	}
+
+	/// Get a handle that can stop the server from another task, see [ShutdownHandle::shutdown]
+	pub fn shutdown_handle(&self) -> ShutdownHandle {
+		ShutdownHandle {
+			token: self.shutdown.clone(),
+		}
+	}
+
+	/// Accept connections until the server is shut down, handling each one on a new task with the provided
+	/// handlers. The handshake handler decides if the connection moves on to the status handler or the
+	/// login handler. Legacy server list pings from pre-1.7 clients are answered before the handlers are
+	/// called, see [legacy](crate::protocol::status::legacy).
+	///
+	/// Once [ShutdownHandle::shutdown] is called, no more connections are accepted and every connection
+	/// that is still being handled is disconnected with the shutdown message. This returns once they have
+	/// all finished, or once the shutdown timeout runs out.
+	pub async fn start<H, S, P, L>(self)
+	where
+		H: HandshakeHandler + 'static,
+		S: StatusHandler + 'static,
+		P: PingHandler + Default + Send + 'static,
+		L: LoginHandler + 'static,
+	{
+		self.serve(StaticHandlers::<H, S, P, L>::new()).await
+	}
+
+	/// Accept connections until the server is shut down, driving each one through the handshake, status
+	/// and login states and calling the events of `handler` along the way. Shutting down works the same
+	/// as [CraftServer::start].
+	pub async fn run<T: ServerHandler>(self, handler: T) {
+		self.serve(Pipeline(handler)).await
+	}
+
+	async fn serve<C: ConnectionHandler>(self, handler: C) {
+		let handler = Arc::new(handler);
+		let tasks = TaskTracker::new();
+
+		loop {
+			let accepted = tokio::select! {
+				accepted = self.accept() => accepted,
+				_ = self.shutdown.cancelled() => break,
+			};
+
+			let client = match accepted {
+				Ok(client) => client,
+				Err(e) => { // accept errors are usually temporary, such as running out of file descriptors
+					error!("Failed to accept connection: {}", e);
+					continue;
+				}
+			};
+
+			trace!("Accepted connection from {}", client);
+
+			let status = self.status.clone();
+			let shutdown = self.shutdown.clone();
+			let shutdown_message = self.config.shutdown_message.clone();
+			let handler = handler.clone();
+
+			tasks.spawn(async move {
+				let mut client = client;
+
+				let handled = {
+					let handle = async {
+						if let Some(ping) = detect_legacy_ping(&mut client).await? {
+							let status = status.build(&StatusContext::from_client(&client));
+							return respond_legacy_ping(&mut client, ping, &status).await;
+						}
+
+						handler.handle(&mut client, status).await
+					};
+
+					tokio::select! {
+						handled = handle => Some(handled),
+						_ = shutdown.cancelled() => None,
+					}
+				};
+
+				match handled {
+					Some(Err(e)) => debug!("Error while handling {}: {}", client, e),
+					Some(Ok(())) => {}
+					None => {
+						let _ = client.disconnect(shutdown_message).await;
+						handler.interrupted(&client);
+					}
+				}
+			});
+		}
+
+		debug!("Shutting down, waiting for {} connections to close", tasks.len());
+		tasks.close();
+
+		if let Some(timeout) = self.config.shutdown_timeout {
+			if tokio::time::timeout(timeout, tasks.wait()).await.is_err() {
+				error!("{} connections were still open when the shutdown timed out", tasks.len());
+			}
+		} else {
+			tasks.wait().await;
+		}
+	}
+}
+
+/// Stops a running [CraftServer], see [CraftServer::shutdown_handle]. This can be cloned and sent to
+/// other tasks.
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+	token: CancellationToken,
+}
+
+impl ShutdownHandle {
+	/// Stop accepting connections and disconnect every client. [CraftServer::start] returns once they have
+	/// all been disconnected.
+	pub fn shutdown(&self) {
+		self.token.cancel();
+	}
+
+	/// Check if the server has been told to shut down
+	pub fn is_shutdown(&self) -> bool {
+		self.token.is_cancelled()
+	}
+
+	/// Wait until the server has been told to shut down
+	pub async fn cancelled(&self) {
+		self.token.cancelled().await
+	}
+}
+
+impl Debug for CraftServer {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("CraftServer")
+			.field("listener", &self.listener)
+			.field("config", &self.config)
+			.finish_non_exhaustive()
+	}
+}
+
+/// Route a single connection through the handshake and then the status or login handler, depending on
+/// the state requested by the client.
+async fn handle_connection<H, S, P, L>(client: &mut CraftClient, status: StatusSupplier) -> Result<(), NetworkError>
+where
+	H: HandshakeHandler,
+	S: StatusHandler,
+	P: PingHandler + Default + Send,
+	L: LoginHandler,
+{
+	H::handle_handshake(client).await?;
+
+	match client.packet_state {
+		PacketState::STATUS => {
+			S::handle_status(client, status, P::default()).await
+		}
+		PacketState::LOGIN => {
+			L::handle_login(client).await
+		}
+		_ => {
+			Err(NetworkError::InvalidPacketState)
+		}
+	}
+}
@@ -0,0 +1,114 @@
+//! Drives a [ServerHandler] through every state of a connection, see [CraftServer::run](super::CraftServer::run).
+
+use std::future::Future;
+use std::marker::PhantomData;
+
+use log::debug;
+
+use crate::network::client::client_handlers::{HandshakeHandler, LoginHandler, PingHandler, ServerHandler, StatusHandler};
+use crate::network::client::CraftClient;
+use crate::network::network_error::NetworkError;
+use crate::network::server::{handle_connection, StatusSupplier};
+use crate::protocol::packets::{Packet, PingResponseBody, StatusResponseBody};
+use crate::protocol::packets::packet_definer::PacketState;
+use crate::protocol::status::StatusContext;
+
+/// Handles a single accepted connection, so that [CraftServer::start](super::CraftServer::start) and
+/// [CraftServer::run](super::CraftServer::run) can share the accept loop
+pub(super) trait ConnectionHandler: Send + Sync + 'static {
+	fn handle(&self, client: &mut CraftClient, status: StatusSupplier) -> impl Future<Output = Result<(), NetworkError>> + Send;
+
+	/// Called when the server shuts down while the connection is still being handled
+	fn interrupted(&self, _client: &CraftClient) {}
+}
+
+/// Only used for its type parameters, and a function pointer is always `Send` and `Sync`
+type HandlerTypes<H, S, P, L> = PhantomData<fn() -> (H, S, P, L)>;
+
+/// The separate handler traits used by [CraftServer::start](super::CraftServer::start)
+pub(super) struct StaticHandlers<H, S, P, L>(HandlerTypes<H, S, P, L>);
+
+impl<H, S, P, L> StaticHandlers<H, S, P, L> {
+	pub(super) fn new() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<H, S, P, L> ConnectionHandler for StaticHandlers<H, S, P, L>
+where
+	H: HandshakeHandler + 'static,
+	S: StatusHandler + 'static,
+	P: PingHandler + Default + Send + 'static,
+	L: LoginHandler + 'static,
+{
+	async fn handle(&self, client: &mut CraftClient, status: StatusSupplier) -> Result<(), NetworkError> {
+		handle_connection::<H, S, P, L>(client, status).await
+	}
+}
+
+/// A [ServerHandler] used by [CraftServer::run](super::CraftServer::run)
+pub(super) struct Pipeline<T>(pub(super) T);
+
+impl<T: ServerHandler> ConnectionHandler for Pipeline<T> {
+	async fn handle(&self, client: &mut CraftClient, status: StatusSupplier) -> Result<(), NetworkError> {
+		let result = drive(&self.0, client, status).await;
+		self.0.on_disconnect(client, result.as_ref().err());
+
+		result
+	}
+
+	fn interrupted(&self, client: &CraftClient) {
+		self.0.on_disconnect(client, None);
+	}
+}
+
+async fn drive<T: ServerHandler>(handler: &T, client: &mut CraftClient, status: StatusSupplier) -> Result<(), NetworkError> {
+	let handshake = match client.receive_packet().await? {
+		Packet::Handshaking(handshake) => handshake,
+		_ => return Err(NetworkError::ExpectedDifferentPacket("Invalid packet received, expected handshake".to_string())),
+	};
+
+	client.accept_handshake(&handshake)?;
+	handler.on_handshake(client, &handshake).await?;
+
+	match client.packet_state {
+		PacketState::STATUS => loop {
+			match client.receive_packet().await? {
+				Packet::StatusRequest(_) => {
+					let response = handler.on_status_request(client, status.build(&StatusContext::from_client(client))).await?;
+					client.send_packet(Packet::StatusResponse(StatusResponseBody::from(response))).await?;
+				}
+				Packet::PingRequest(ping) => {
+					client.send_packet(Packet::PingResponse(PingResponseBody::new(ping.payload as u64))).await?;
+					client.close().await;
+
+					return Ok(());
+				}
+				_ => return Err(NetworkError::ExpectedDifferentPacket("Invalid packet received, expected status request or ping request".to_string())),
+			}
+		},
+		PacketState::LOGIN => {
+			let login = match client.receive_packet().await? {
+				Packet::LoginStart(login) => login,
+				_ => return Err(NetworkError::ExpectedDifferentPacket("Invalid packet received, expected login start".to_string())),
+			};
+
+			debug!("Login started by {} for {}", client, login.username);
+			handler.on_login_start(client, login).await?;
+
+			while client.packet_state == PacketState::PLAY {
+				match client.receive_packet().await? {
+					Packet::CommandSuggestionsRequest(request) => {
+						if let Some(suggestions) = handler.on_command_suggestions(client, &request.text).await? {
+							client.send_packet(suggestions.response(request.transaction_id)).await?;
+						}
+					}
+					packet => handler.on_play_packet(client, packet).await?,
+				}
+			}
+
+			Ok(())
+		}
+		_ => Err(NetworkError::InvalidPacketState),
+	}
+}
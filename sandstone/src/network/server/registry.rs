@@ -0,0 +1,199 @@
+//! Keeps track of the connections to players that are online, so that packets can be sent to all of
+//! them at once.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+use futures::future::join_all;
+use log::debug;
+use uuid::Uuid;
+
+use crate::network::client::split::CraftWriter;
+use crate::network::compression::compress_packet;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::Packet;
+use crate::protocol::serialization::{McSerialize, McSerializer};
+
+/// A [CraftWriter] that can be shared between the registry and the task handling the connection
+pub type SharedWriter = Arc<tokio::sync::Mutex<CraftWriter>>;
+
+/// The connections to every player that is online, keyed by their UUID. Connections are registered by
+/// their [CraftWriter], so a connection has to be [split](crate::network::client::CraftClient::split)
+/// first and the reader stays with the task that handles it.
+///
+/// Cloning the registry gives another handle to the same connections.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionRegistry {
+	connections: Arc<RwLock<HashMap<Uuid, SharedWriter>>>,
+}
+
+impl ConnectionRegistry {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a connection, returning the writer so that the caller can still send packets to it. A connection
+	/// that was already registered with the same UUID is replaced.
+	pub fn register(&self, uuid: Uuid, writer: CraftWriter) -> SharedWriter {
+		let writer = Arc::new(tokio::sync::Mutex::new(writer));
+		self.connections.write().unwrap().insert(uuid, writer.clone());
+
+		writer
+	}
+
+	/// Remove a connection, such as after the player has disconnected
+	pub fn unregister(&self, uuid: &Uuid) -> Option<SharedWriter> {
+		self.connections.write().unwrap().remove(uuid)
+	}
+
+	pub fn get(&self, uuid: &Uuid) -> Option<SharedWriter> {
+		self.connections.read().unwrap().get(uuid).cloned()
+	}
+
+	pub fn contains(&self, uuid: &Uuid) -> bool {
+		self.connections.read().unwrap().contains_key(uuid)
+	}
+
+	pub fn len(&self) -> usize {
+		self.connections.read().unwrap().len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// The UUIDs of every registered connection
+	pub fn uuids(&self) -> Vec<Uuid> {
+		self.connections.read().unwrap().keys().copied().collect()
+	}
+
+	/// Send a packet to every connection. See [ConnectionRegistry::broadcast_filter].
+	pub async fn broadcast(&self, packet: &Packet) -> Result<Vec<Uuid>, NetworkError> {
+		self.broadcast_filter(packet, |_| true).await
+	}
+
+	/// Send a packet to every connection whose UUID matches the filter. The packet is only serialized once,
+	/// and compressed once for each compression threshold in use, so only encryption is done per connection.
+	/// Anything already queued on a connection is sent along with it.
+	///
+	/// Connections that fail to send are unregistered, and their UUIDs are returned.
+	pub async fn broadcast_filter<F: Fn(&Uuid) -> bool>(&self, packet: &Packet, filter: F) -> Result<Vec<Uuid>, NetworkError> {
+		let mut serializer = McSerializer::new();
+		packet.mc_serialize(&mut serializer)?;
+		let uncompressed = serializer.output;
+
+		// the same threshold always compresses to the same bytes, so each one is only compressed once
+		let compressed: Mutex<HashMap<i32, Arc<Vec<u8>>>> = Mutex::new(HashMap::new());
+
+		let targets: Vec<(Uuid, SharedWriter)> = self.connections.read().unwrap().iter()
+			.filter(|(uuid, _)| filter(uuid))
+			.map(|(uuid, writer)| (*uuid, writer.clone()))
+			.collect();
+
+		let sends = targets.into_iter().map(|(uuid, writer)| {
+			let uncompressed = &uncompressed;
+			let compressed = &compressed;
+
+			async move {
+				let mut writer = writer.lock().await;
+
+				let result = match writer.compression_threshold {
+					Some(threshold) => {
+						let cached = compressed.lock().unwrap().get(&threshold).cloned();
+
+						let bytes = match cached {
+							Some(bytes) => Ok(bytes),
+							None => compress_packet(uncompressed, threshold, writer.compression_level).map(|bytes| {
+								let bytes = Arc::new(bytes);
+								compressed.lock().unwrap().insert(threshold, bytes.clone());
+								bytes
+							}),
+						};
+
+						bytes.map(|bytes| writer.queue_encoded(&bytes))
+					}
+					None => {
+						writer.queue_encoded(uncompressed);
+						Ok(())
+					}
+				};
+
+				let result = match result {
+					Ok(()) => writer.flush().await,
+					Err(e) => Err(e),
+				};
+
+				(uuid, result)
+			}
+		});
+
+		let mut failed = vec![];
+
+		for (uuid, result) in join_all(sends).await {
+			if let Err(e) = result {
+				debug!("Failed to broadcast to {}: {}", uuid, e);
+				self.unregister(&uuid);
+				failed.push(uuid);
+			}
+		}
+
+		Ok(failed)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use tokio::net::{TcpListener, TcpStream};
+	use uuid::Uuid;
+
+	use crate::network::client::CraftClient;
+	use crate::network::server::registry::ConnectionRegistry;
+	use crate::protocol::packets::{KeepAliveBody, Packet};
+	use crate::protocol::packets::packet_definer::PacketState;
+
+	#[tokio::test]
+	async fn test_broadcast() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let registry = ConnectionRegistry::new();
+		let mut players = vec![];
+
+		// one plain, one compressed and one compressed and encrypted connection
+		for (i, (compression, encryption)) in [(None, false), (Some(0), false), (Some(0), true)].into_iter().enumerate() {
+			let mut player = CraftClient::from_outbound_connection(TcpStream::connect(listener.local_addr().unwrap()).await.unwrap()).unwrap();
+			let mut client = CraftClient::from_connection(listener.accept().await.unwrap().0).unwrap();
+
+			for connection in [&mut player, &mut client] {
+				connection.change_state(PacketState::CONFIGURATION);
+				connection.enable_compression(compression);
+
+				if encryption {
+					connection.enable_encryption(&[i as u8; 16]).unwrap();
+				}
+			}
+
+			let uuid = Uuid::new_v4();
+			registry.register(uuid, client.split().1);
+			players.push((uuid, player));
+		}
+
+		assert_eq!(registry.len(), 3);
+
+		let failed = registry.broadcast(&Packet::KeepAlive(KeepAliveBody::new(1))).await.unwrap();
+		assert!(failed.is_empty());
+
+		for (_, player) in &mut players {
+			assert_eq!(player.receive_packet().await.unwrap(), Packet::KeepAlive(KeepAliveBody::new(1)));
+		}
+
+		let skipped = players[0].0;
+		registry.broadcast_filter(&Packet::KeepAlive(KeepAliveBody::new(2)), |uuid| *uuid != skipped).await.unwrap();
+
+		for (_, player) in &mut players[1..] {
+			assert_eq!(player.receive_packet().await.unwrap(), Packet::KeepAlive(KeepAliveBody::new(2)));
+		}
+
+		assert!(registry.unregister(&skipped).is_some());
+		assert!(!registry.contains(&skipped));
+		assert_eq!(registry.len(), 2);
+	}
+}
@@ -0,0 +1,582 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use uuid::Uuid;
+
+use crate::network::client::client_handlers::{HandshakeHandler, ServerHandler};
+use crate::network::client::CraftClient;
+use crate::network::connector::CraftConnector;
+use crate::network::network_error::NetworkError;
+use crate::network::server::CraftServer;
+use crate::network::server::guard::HandshakeGuardConfig;
+use crate::network::server::throttle::ThrottleConfig;
+use crate::protocol::commands::suggestions::Suggestions;
+use crate::protocol::login::DefaultLoginHandler;
+use crate::protocol::login::flow::LoginFlow;
+use crate::protocol::packets::{CommandSuggestionsRequestBody, HandshakingBody, LoginStartBody, LoginSuccessBody, Packet, StatusRequestBody};
+use crate::protocol::packets::packet_component::LoginSuccessSpec;
+use crate::protocol::packets::packet_definer::{PacketDirection, PacketState};
+use crate::protocol::serialization::{McDeserializer, McSerialize, McSerializer, StateBasedDeserializer};
+use crate::protocol::status::{DefaultHandshakeHandler, DefaultPingHandler, DefaultStatusHandler};
+use crate::protocol::status::status_components::StatusResponseSpec;
+use crate::protocol::status::StatusContext;
+use crate::protocol_types::datatypes::bounded_string::Username;
+use crate::protocol_types::datatypes::game_profile::GameProfile;
+use crate::protocol_types::datatypes::var_types::VarInt;
+use crate::protocol_types::protocol_verison::ProtocolVerison;
+
+async fn write_packet(stream: &mut TcpStream, packet: Packet) {
+	let mut serializer = McSerializer::new();
+	packet.mc_serialize(&mut serializer).unwrap();
+	stream.write_all(&serializer.output).await.unwrap();
+}
+
+async fn read_packet(stream: &mut TcpStream, state: PacketState) -> Packet {
+	let mut buffer = vec![];
+
+	loop {
+		let b = stream.read_u8().await.unwrap();
+		buffer.push(b);
+
+		if b & 0x80 == 0 {
+			break;
+		}
+	}
+
+	let length = VarInt::from_slice(&buffer).unwrap().0 as usize;
+	let start = buffer.len();
+	buffer.resize(start + length, 0);
+	stream.read_exact(&mut buffer[start..]).await.unwrap();
+
+	let mut deserializer = McDeserializer::new(&buffer);
+	Packet::deserialize_state(&mut deserializer, state, PacketDirection::CLIENT).unwrap()
+}
+
+fn handshake(next_state: i32) -> Packet {
+	Packet::Handshaking(HandshakingBody {
+		protocol_version: VarInt(ProtocolVerison::V1_20.get_version_number() as i32),
+		server_address: "localhost".to_string(),
+		port: 25565,
+		next_state: VarInt(next_state),
+	})
+}
+
+async fn start_server() -> std::net::SocketAddr {
+	let status = StatusResponseSpec::new(ProtocolVerison::V1_20, "Hello World");
+	let server = CraftServer::bind("127.0.0.1:0", status).await.unwrap();
+	let addr = server.local_addr().unwrap();
+
+	tokio::spawn(server.start::<DefaultHandshakeHandler, DefaultStatusHandler, DefaultPingHandler, DefaultLoginHandler>());
+
+	addr
+}
+
+/// Log a [CraftConnector] in to a [CraftClient] as `dec4234` over a local socket, and return both of them
+/// in configuration
+pub(crate) async fn configured_pair() -> (CraftClient, CraftConnector) {
+	configured_pair_with(|_| {}, |_| Box::pin(async {})).await
+}
+
+/// Like [configured_pair], with `prepare` run on the connector before its handshake, and `during_login`
+/// run on the client after `LoginStart` while the connector waits for `LoginSuccess`
+pub(crate) async fn configured_pair_with<P, L>(prepare: P, during_login: L) -> (CraftClient, CraftConnector)
+where
+	P: FnOnce(&mut CraftConnector),
+	L: for<'a> FnOnce(&'a mut CraftClient) -> BoxFuture<'a, ()> + Send + 'static,
+{
+	let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let port = listener.local_addr().unwrap().port();
+
+	let server = tokio::spawn(async move {
+		let (socket, _) = listener.accept().await.unwrap();
+		let mut client = CraftClient::from_connection(socket).unwrap();
+		DefaultHandshakeHandler::handle_handshake(&mut client).await.unwrap();
+		assert!(matches!(client.receive_packet().await.unwrap(), Packet::LoginStart(_)));
+
+		during_login(&mut client).await;
+
+		client.send_packet(Packet::LoginSuccess(LoginSuccessBody::new(LoginSuccessSpec::new(GameProfile::offline("dec4234"))))).await.unwrap();
+		assert!(matches!(client.receive_packet().await.unwrap(), Packet::LoginAcknowledged(_)));
+		client.change_state(PacketState::CONFIGURATION);
+		client
+	});
+
+	let mut connector = CraftConnector::connect("127.0.0.1", port).await.unwrap();
+	prepare(&mut connector);
+	connector.handshake(ProtocolVerison::V1_21.get_version_number() as i32, PacketState::LOGIN).await.unwrap();
+	connector.login("dec4234", Uuid::from_u128(7)).await.unwrap();
+
+	(server.await.unwrap(), connector)
+}
+
+#[tokio::test]
+async fn test_server_status() {
+	let addr = start_server().await;
+
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	write_packet(&mut stream, handshake(1)).await;
+	write_packet(&mut stream, Packet::StatusRequest(StatusRequestBody::new())).await;
+
+	match read_packet(&mut stream, PacketState::STATUS).await {
+		Packet::StatusResponse(body) => {
+			assert_eq!(*body.response, StatusResponseSpec::new(ProtocolVerison::V1_20, "Hello World"));
+		}
+		p => panic!("Expected status response, got {:?}", p)
+	}
+}
+
+#[tokio::test]
+async fn test_server_status_context() {
+	let server = CraftServer::builder()
+		.bind_address("127.0.0.1:0")
+		.status_supplier(|context: &StatusContext| {
+			let description = match context.version() {
+				Some(version) if version == ProtocolVerison::V1_20 => format!("Welcome to {}", context.hostname()),
+				_ => "Outdated client".to_string(),
+			};
+
+			StatusResponseSpec::new(ProtocolVerison::V1_20, description)
+		})
+		.build().await.unwrap();
+	let addr = server.local_addr().unwrap();
+
+	tokio::spawn(server.start::<DefaultHandshakeHandler, DefaultStatusHandler, DefaultPingHandler, DefaultLoginHandler>());
+
+	let mut modded = handshake(1);
+	if let Packet::Handshaking(body) = &mut modded {
+		body.server_address = "play.example.com.\0FML3\0".to_string();
+	}
+
+	let mut old = handshake(1);
+	if let Packet::Handshaking(body) = &mut old {
+		body.protocol_version = VarInt(47);
+	}
+
+	for (handshake, description) in [(modded, "Welcome to play.example.com"), (old, "Outdated client")] {
+		let mut stream = TcpStream::connect(addr).await.unwrap();
+		write_packet(&mut stream, handshake).await;
+		write_packet(&mut stream, Packet::StatusRequest(StatusRequestBody::new())).await;
+
+		match read_packet(&mut stream, PacketState::STATUS).await {
+			Packet::StatusResponse(body) => {
+				assert_eq!(*body.response, StatusResponseSpec::new(ProtocolVerison::V1_20, description));
+			}
+			p => panic!("Expected status response, got {:?}", p)
+		}
+	}
+}
+
+#[tokio::test]
+async fn test_server_legacy_ping() {
+	let addr = start_server().await;
+
+	// 1.6 follows the ping with a plugin message, which is ignored
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	stream.write_all(&[0xFE, 0x01, 0xFA, 0x00, 0x0B]).await.unwrap();
+
+	let mut response = vec![];
+	tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut response)).await.unwrap().unwrap();
+
+	let chars: Vec<u16> = response[3..].chunks(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+	let fields: Vec<String> = String::from_utf16(&chars).unwrap().split('\0').map(str::to_string).collect();
+	assert_eq!(response[0], 0xFF);
+	assert_eq!(fields[0], "§1");
+	assert_eq!(fields[3], "Hello World");
+
+	// the normal status ping still works afterwards
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	write_packet(&mut stream, handshake(1)).await;
+	write_packet(&mut stream, Packet::StatusRequest(StatusRequestBody::new())).await;
+	assert!(matches!(read_packet(&mut stream, PacketState::STATUS).await, Packet::StatusResponse(_)));
+}
+
+#[tokio::test]
+async fn test_server_rejects_login() {
+	let addr = start_server().await;
+
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	write_packet(&mut stream, handshake(2)).await;
+	write_packet(&mut stream, Packet::LoginStart(LoginStartBody::new(Username::new("dec4234").unwrap(), Uuid::new_v4()))).await;
+
+	match read_packet(&mut stream, PacketState::LOGIN).await {
+		Packet::Disconnect(_) => {}
+		p => panic!("Expected disconnect, got {:?}", p)
+	}
+}
+
+#[tokio::test]
+async fn test_server_idle_timeout() {
+	let status = StatusResponseSpec::new(ProtocolVerison::V1_20, "Hello World");
+	let mut server = CraftServer::bind("127.0.0.1:0", status).await.unwrap();
+	server.set_idle_timeout(Some(Duration::from_millis(100)));
+	let addr = server.local_addr().unwrap();
+
+	tokio::spawn(server.start::<DefaultHandshakeHandler, DefaultStatusHandler, DefaultPingHandler, DefaultLoginHandler>());
+
+	// never send the handshake, the server should give up and close the connection
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	let mut buffer = [0u8; 1];
+	let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buffer)).await.expect("Server kept the idle connection open");
+	assert!(matches!(read, Ok(0) | Err(_)));
+}
+
+#[tokio::test]
+async fn test_server_rejects_oversized_packet() {
+	let addr = start_server().await;
+
+	// a handshake that claims to be 100000 bytes long
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	stream.write_all(&[0xA0, 0x8D, 0x06, 0x00]).await.unwrap();
+
+	let mut buffer = [0u8; 1];
+	let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buffer)).await.expect("Server kept the connection open");
+	assert!(matches!(read, Ok(0) | Err(_)));
+}
+
+#[tokio::test]
+async fn test_server_builder() {
+	let online = Arc::new(AtomicI32::new(0));
+	let counter = online.clone();
+
+	let server = CraftServer::builder()
+		.bind_address("127.0.0.1:0")
+		.online_mode(false)
+		.max_players(50)
+		.motd("&aBuilt")
+		.enforce_secure_chat(true)
+		.status_supplier(move |_| {
+			let mut status = StatusResponseSpec::new(ProtocolVerison::V1_20, "Replaced");
+			status.set_player_info(0, counter.fetch_add(1, Ordering::SeqCst) + 1, vec![]);
+			status
+		})
+		.build().await.unwrap();
+
+	assert!(!server.config().online_mode);
+	assert_eq!(server.config().compression_threshold, Some(256));
+
+	let addr = server.local_addr().unwrap();
+	tokio::spawn(server.start::<DefaultHandshakeHandler, DefaultStatusHandler, DefaultPingHandler, DefaultLoginHandler>());
+
+	// the status is built again for every request
+	for online in 1..=2 {
+		let mut stream = TcpStream::connect(addr).await.unwrap();
+		write_packet(&mut stream, handshake(1)).await;
+		write_packet(&mut stream, Packet::StatusRequest(StatusRequestBody::new())).await;
+
+		let mut expected = StatusResponseSpec::new(ProtocolVerison::V1_20, "&aBuilt");
+		expected.set_player_info(50, online, vec![]);
+		expected.set_secure_chat(true);
+
+		match read_packet(&mut stream, PacketState::STATUS).await {
+			Packet::StatusResponse(body) => assert_eq!(*body.response, expected),
+			p => panic!("Expected status response, got {:?}", p)
+		}
+	}
+
+	assert_eq!(online.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_server_builder_keeps_status() {
+	let mut spec = StatusResponseSpec::new(ProtocolVerison::V1_20, "Unchanged");
+	spec.set_player_info(7, 3, vec![]);
+	spec.set_secure_chat(true);
+	spec.set_prevents_chat_reports(true);
+
+	// nothing is replaced unless it is set on the builder
+	let server = CraftServer::builder()
+		.bind_address("127.0.0.1:0")
+		.status(spec.clone())
+		.build().await.unwrap();
+
+	let addr = server.local_addr().unwrap();
+	tokio::spawn(server.start::<DefaultHandshakeHandler, DefaultStatusHandler, DefaultPingHandler, DefaultLoginHandler>());
+
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	write_packet(&mut stream, handshake(1)).await;
+	write_packet(&mut stream, Packet::StatusRequest(StatusRequestBody::new())).await;
+
+	match read_packet(&mut stream, PacketState::STATUS).await {
+		Packet::StatusResponse(body) => assert_eq!(*body.response, spec),
+		p => panic!("Expected status response, got {:?}", p)
+	}
+}
+
+#[tokio::test]
+async fn test_server_status_cache() {
+	let builds = Arc::new(AtomicI32::new(0));
+	let counter = builds.clone();
+
+	let server = CraftServer::builder()
+		.bind_address("127.0.0.1:0")
+		.cache_status(Duration::from_secs(60))
+		.status_supplier(move |_| {
+			counter.fetch_add(1, Ordering::SeqCst);
+			StatusResponseSpec::new(ProtocolVerison::V1_20, "Cached")
+		})
+		.build().await.unwrap();
+
+	let addr = server.local_addr().unwrap();
+	let status = server.status_supplier().clone();
+	tokio::spawn(server.start::<DefaultHandshakeHandler, DefaultStatusHandler, DefaultPingHandler, DefaultLoginHandler>());
+
+	for i in 0..4 {
+		// the cached response is only built again once it is invalidated
+		if i == 3 {
+			status.invalidate();
+		}
+
+		let mut stream = TcpStream::connect(addr).await.unwrap();
+		write_packet(&mut stream, handshake(1)).await;
+		write_packet(&mut stream, Packet::StatusRequest(StatusRequestBody::new())).await;
+
+		match read_packet(&mut stream, PacketState::STATUS).await {
+			Packet::StatusResponse(body) => assert_eq!(body.response.description().text, "Cached"),
+			p => panic!("Expected status response, got {:?}", p)
+		}
+	}
+
+	assert_eq!(builds.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_server_shutdown() {
+	let server = CraftServer::builder().bind_address("127.0.0.1:0").build().await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let shutdown = server.shutdown_handle();
+
+	let running = tokio::spawn(server.start::<DefaultHandshakeHandler, DefaultStatusHandler, DefaultPingHandler, DefaultLoginHandler>());
+
+	// a connection that is stuck waiting in the handshake
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	tokio::time::sleep(Duration::from_millis(50)).await;
+
+	shutdown.shutdown();
+	assert!(shutdown.is_shutdown());
+
+	tokio::time::timeout(Duration::from_secs(5), running).await.expect("Server didn't shut down").unwrap();
+
+	let mut buffer = [0u8; 1];
+	let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buffer)).await.expect("Server kept the connection open");
+	assert!(matches!(read, Ok(0) | Err(_)));
+
+	assert!(TcpStream::connect(addr).await.is_err());
+}
+
+#[tokio::test]
+async fn test_server_throttle() {
+	let server = CraftServer::builder()
+		.bind_address("127.0.0.1:0")
+		.connection_throttle(ThrottleConfig::new(1, Duration::from_secs(60)))
+		.build().await.unwrap();
+	let addr = server.local_addr().unwrap();
+
+	tokio::spawn(server.start::<DefaultHandshakeHandler, DefaultStatusHandler, DefaultPingHandler, DefaultLoginHandler>());
+
+	let mut first = TcpStream::connect(addr).await.unwrap();
+	let mut second = TcpStream::connect(addr).await.unwrap();
+
+	// the second connection is closed without the server reading anything
+	let mut buffer = [0u8; 1];
+	let read = tokio::time::timeout(Duration::from_secs(5), second.read(&mut buffer)).await.expect("Server kept the throttled connection open");
+	assert!(matches!(read, Ok(0) | Err(_)));
+
+	write_packet(&mut first, handshake(1)).await;
+	write_packet(&mut first, Packet::StatusRequest(StatusRequestBody::new())).await;
+	assert!(matches!(read_packet(&mut first, PacketState::STATUS).await, Packet::StatusResponse(_)));
+}
+
+#[tokio::test]
+async fn test_server_handshake_guard() {
+	let server = CraftServer::builder()
+		.bind_address("127.0.0.1:0")
+		.handshake_guard(Some(HandshakeGuardConfig::new(Duration::from_millis(300), 1)))
+		.build().await.unwrap();
+	let addr = server.local_addr().unwrap();
+
+	tokio::spawn(server.start::<DefaultHandshakeHandler, DefaultStatusHandler, DefaultPingHandler, DefaultLoginHandler>());
+
+	let mut slow = TcpStream::connect(addr).await.unwrap();
+
+	// the address already has a half-open connection
+	let mut rejected = TcpStream::connect(addr).await.unwrap();
+	let mut buffer = [0u8; 1];
+	let read = tokio::time::timeout(Duration::from_secs(5), rejected.read(&mut buffer)).await.expect("Server kept the extra connection open");
+	assert!(matches!(read, Ok(0) | Err(_)));
+
+	// trickling in the handshake a byte at a time doesn't keep the connection open past the deadline
+	let mut serializer = McSerializer::new();
+	handshake(1).mc_serialize(&mut serializer).unwrap();
+
+	let trickle = async {
+		for b in serializer.output.iter().cycle() {
+			if slow.write_all(&[*b]).await.is_err() {
+				break;
+			}
+
+			tokio::time::sleep(Duration::from_millis(50)).await;
+		}
+	};
+
+	tokio::time::timeout(Duration::from_secs(5), trickle).await.expect("Server kept the slow connection open");
+	tokio::time::sleep(Duration::from_millis(50)).await;
+
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	write_packet(&mut stream, handshake(1)).await;
+	write_packet(&mut stream, Packet::StatusRequest(StatusRequestBody::new())).await;
+	assert!(matches!(read_packet(&mut stream, PacketState::STATUS).await, Packet::StatusResponse(_)));
+}
+
+#[derive(Default)]
+struct CountingHandler {
+	handshakes: AtomicI32,
+	disconnects: AtomicI32,
+}
+
+impl ServerHandler for CountingHandler {
+	async fn on_handshake(&self, _client: &mut CraftClient, _handshake: &HandshakingBody) -> Result<(), NetworkError> {
+		self.handshakes.fetch_add(1, Ordering::SeqCst);
+		Ok(())
+	}
+
+	async fn on_status_request(&self, _client: &mut CraftClient, mut status: StatusResponseSpec) -> Result<StatusResponseSpec, NetworkError> {
+		status.set_description(format!("Handshakes: {}", self.handshakes.load(Ordering::SeqCst)));
+		Ok(status)
+	}
+
+	async fn on_login_start(&self, client: &mut CraftClient, login: LoginStartBody) -> Result<(), NetworkError> {
+		if client.is_transfer() {
+			return client.disconnect(format!("Welcome back {}", login.username)).await;
+		}
+
+		client.disconnect(format!("Hello {}", login.username)).await
+	}
+
+	fn on_disconnect(&self, _client: &CraftClient, _error: Option<&NetworkError>) {
+		self.disconnects.fetch_add(1, Ordering::SeqCst);
+	}
+}
+
+#[tokio::test]
+async fn test_server_handler() {
+	let server = CraftServer::bind("127.0.0.1:0", StatusResponseSpec::new(ProtocolVerison::V1_20, "Hello World")).await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let shutdown = server.shutdown_handle();
+
+	let handler = Arc::new(CountingHandler::default());
+	let running = tokio::spawn(server.run(handler.clone()));
+
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	write_packet(&mut stream, handshake(1)).await;
+	write_packet(&mut stream, Packet::StatusRequest(StatusRequestBody::new())).await;
+
+	match read_packet(&mut stream, PacketState::STATUS).await {
+		Packet::StatusResponse(body) => assert_eq!(*body.response, StatusResponseSpec::new(ProtocolVerison::V1_20, "Handshakes: 1")),
+		p => panic!("Expected status response, got {:?}", p)
+	}
+
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	write_packet(&mut stream, handshake(2)).await;
+	write_packet(&mut stream, Packet::LoginStart(LoginStartBody::new(Username::new("dec4234").unwrap(), Uuid::new_v4()))).await;
+
+	match read_packet(&mut stream, PacketState::LOGIN).await {
+		Packet::Disconnect(body) => assert_eq!(body.reason.text, "Hello dec4234"),
+		p => panic!("Expected disconnect, got {:?}", p)
+	}
+
+	// a transferred client logs in like any other, and the handler can tell it was transferred
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	write_packet(&mut stream, handshake(3)).await;
+	write_packet(&mut stream, Packet::LoginStart(LoginStartBody::new(Username::new("dec4234").unwrap(), Uuid::new_v4()))).await;
+
+	match read_packet(&mut stream, PacketState::LOGIN).await {
+		Packet::Disconnect(body) => assert_eq!(body.reason.text, "Welcome back dec4234"),
+		p => panic!("Expected disconnect, got {:?}", p)
+	}
+
+	shutdown.shutdown();
+	running.await.unwrap();
+
+	assert_eq!(handler.handshakes.load(Ordering::SeqCst), 3);
+	assert_eq!(handler.disconnects.load(Ordering::SeqCst), 3);
+}
+
+struct OfflineHandler;
+
+impl ServerHandler for OfflineHandler {
+	async fn on_login_start(&self, client: &mut CraftClient, login: LoginStartBody) -> Result<(), NetworkError> {
+		LoginFlow::offline().resume(client, login, &()).await?;
+		client.disconnect("Logged in").await
+	}
+}
+
+#[tokio::test]
+async fn test_server_login_compression() {
+	let server = CraftServer::builder()
+		.bind_address("127.0.0.1:0")
+		.online_mode(false)
+		.compression_threshold(Some(0))
+		.build().await.unwrap();
+	let port = server.local_addr().unwrap().port();
+
+	tokio::spawn(server.run(OfflineHandler));
+
+	let mut connector = CraftConnector::connect("127.0.0.1", port).await.unwrap();
+	connector.handshake(ProtocolVerison::V1_20.get_version_number() as i32, PacketState::LOGIN).await.unwrap();
+
+	// everything after Set Compression is compressed, including Login Success
+	let profile = connector.login("dec4234", Uuid::from_u128(1)).await.unwrap();
+	assert_eq!(profile.name, "dec4234");
+	assert_eq!(connector.connection().compression_threshold, Some(0));
+
+	match connector.receive_packet().await {
+		Ok(Packet::ConfigDisconnect(_)) => {}
+		p => panic!("Expected disconnect, got {:?}", p)
+	}
+}
+
+struct SuggestingHandler;
+
+impl ServerHandler for SuggestingHandler {
+	async fn on_login_start(&self, client: &mut CraftClient, _login: LoginStartBody) -> Result<(), NetworkError> {
+		client.change_state(PacketState::PLAY);
+		Ok(())
+	}
+
+	async fn on_command_suggestions(&self, _client: &mut CraftClient, command: &str) -> Result<Option<Suggestions>, NetworkError> {
+		if command.starts_with("/warp ") {
+			Ok(Some(Suggestions::complete(command, ["spawn", "arena"])))
+		} else {
+			Ok(None)
+		}
+	}
+}
+
+#[tokio::test]
+async fn test_server_command_suggestions() {
+	let server = CraftServer::bind("127.0.0.1:0", StatusResponseSpec::new(ProtocolVerison::V1_20, "Hello World")).await.unwrap();
+	let addr = server.local_addr().unwrap();
+	tokio::spawn(server.run(SuggestingHandler));
+
+	let mut stream = TcpStream::connect(addr).await.unwrap();
+	write_packet(&mut stream, handshake(2)).await;
+	write_packet(&mut stream, Packet::LoginStart(LoginStartBody::new(Username::new("dec4234").unwrap(), Uuid::new_v4()))).await;
+
+	// nothing is sent for the first one, so the response is for the second
+	write_packet(&mut stream, Packet::CommandSuggestionsRequest(CommandSuggestionsRequestBody::new(VarInt(1), "/tp ".to_string()))).await;
+	write_packet(&mut stream, Packet::CommandSuggestionsRequest(CommandSuggestionsRequestBody::new(VarInt(2), "/warp ar".to_string()))).await;
+
+	match read_packet(&mut stream, PacketState::PLAY).await {
+		Packet::CommandSuggestionsResponse(body) => {
+			assert_eq!(body.transaction_id, VarInt(2));
+			assert_eq!((body.start, body.length), (VarInt(6), VarInt(2)));
+			assert_eq!(body.matches.len(), 1);
+			assert_eq!(body.matches[0].text, "arena");
+		}
+		p => panic!("Expected command suggestions, got {:?}", p)
+	}
+}
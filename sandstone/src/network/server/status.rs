@@ -0,0 +1,191 @@
+//! Builds the status response for each client that asks for it, optionally keeping the serialized response
+//! around so that a server being scanned doesn't rebuild and serialize it for every ping.
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::network::client::CraftClient;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::{Packet, StatusResponseBody};
+use crate::protocol::serialization::{McSerialize, McSerializer};
+use crate::protocol::status::status_components::StatusResponseSpec;
+use crate::protocol::status::StatusContext;
+
+/// Once this many different responses are cached, the cache is cleared. Scanners connect with all sorts
+/// of hostnames, so this keeps them from growing it forever.
+const MAX_CACHED_RESPONSES: usize = 64;
+
+/// Builds the status response each time a client requests it, see [ServerBuilder::status_supplier](super::builder::ServerBuilder::status_supplier).
+/// Cloning it is cheap, and clones share the same cache.
+#[derive(Clone)]
+pub struct StatusSupplier {
+	build: Arc<dyn Fn(&StatusContext) -> StatusResponseSpec + Send + Sync>,
+	cache: Option<Arc<StatusCache>>,
+}
+
+/// Responses are cached by the protocol version and hostname of the client, since those are what a
+/// response usually depends on
+type CacheKey = (i32, String);
+
+struct StatusCache {
+	ttl: Option<Duration>,
+	entries: RwLock<HashMap<CacheKey, CachedStatus>>,
+}
+
+#[derive(Clone)]
+struct CachedStatus {
+	built: Instant,
+	spec: Arc<StatusResponseSpec>,
+	/// The whole Status Response packet, including its length
+	packet: Arc<Vec<u8>>,
+}
+
+impl StatusSupplier {
+	pub fn new<F: Fn(&StatusContext) -> StatusResponseSpec + Send + Sync + 'static>(build: F) -> Self {
+		Self {
+			build: Arc::new(build),
+			cache: None,
+		}
+	}
+
+	/// Always return the same response
+	pub fn fixed(status: StatusResponseSpec) -> Self {
+		Self::new(move |_| status.clone())
+	}
+
+	/// Keep each response after it is built, and reuse it until it is older than `ttl` or until
+	/// [StatusSupplier::invalidate] is called. `None` keeps it until it is invalidated.
+	///
+	/// Responses are cached for each protocol version and hostname, so a response that depends on
+	/// anything else in the [StatusContext], such as the client's address, shouldn't be cached.
+	pub fn cached(mut self, ttl: Option<Duration>) -> Self {
+		self.cache = Some(Arc::new(StatusCache {
+			ttl,
+			entries: RwLock::new(HashMap::new()),
+		}));
+		self
+	}
+
+	pub fn is_cached(&self) -> bool {
+		self.cache.is_some()
+	}
+
+	/// Throw away the cached responses, so that the next request builds a new one. This does nothing if
+	/// the responses aren't cached.
+	pub fn invalidate(&self) {
+		if let Some(cache) = &self.cache {
+			cache.entries.write().unwrap().clear();
+		}
+	}
+
+	/// Get the response for a client, from the cache if there is one
+	pub fn build(&self, context: &StatusContext) -> StatusResponseSpec {
+		match &self.cache {
+			Some(cache) => match self.cached_entry(cache, context) {
+				Ok(entry) => (*entry.spec).clone(),
+				Err(_) => (self.build)(context),
+			},
+			None => (self.build)(context),
+		}
+	}
+
+	/// Send the response to a client in the `STATUS` state. When responses are cached, the already
+	/// serialized packet is sent as is.
+	pub async fn send(&self, client: &mut CraftClient) -> Result<(), NetworkError> {
+		let context = StatusContext::from_client(client);
+
+		match &self.cache {
+			Some(cache) if client.compression_threshold.is_none() => {
+				let entry = self.cached_entry(cache, &context)?;
+				client.queue_encoded(&entry.packet);
+				client.flush().await
+			}
+			_ => client.send_packet(Packet::StatusResponse(StatusResponseBody::from(self.build(&context)))).await,
+		}
+	}
+
+	fn cached_entry(&self, cache: &StatusCache, context: &StatusContext) -> Result<CachedStatus, NetworkError> {
+		let key = (context.protocol_version, context.hostname().to_string());
+
+		if let Some(entry) = cache.entries.read().unwrap().get(&key) {
+			if cache.ttl.is_none_or(|ttl| entry.built.elapsed() < ttl) {
+				return Ok(entry.clone());
+			}
+		}
+
+		let spec = (self.build)(context);
+
+		let mut serializer = McSerializer::new();
+		Packet::StatusResponse(StatusResponseBody::from(spec.clone())).mc_serialize(&mut serializer)?;
+
+		let entry = CachedStatus {
+			built: Instant::now(),
+			spec: Arc::new(spec),
+			packet: Arc::new(serializer.output),
+		};
+
+		let mut entries = cache.entries.write().unwrap();
+
+		if entries.len() >= MAX_CACHED_RESPONSES {
+			entries.clear();
+		}
+
+		entries.insert(key, entry.clone());
+
+		Ok(entry)
+	}
+}
+
+impl Debug for StatusSupplier {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("StatusSupplier")
+			.field("cached", &self.is_cached())
+			.finish_non_exhaustive()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::SocketAddr;
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicI32, Ordering};
+	use std::time::Duration;
+
+	use crate::network::server::status::StatusSupplier;
+	use crate::protocol::status::status_components::StatusResponseSpec;
+	use crate::protocol::status::StatusContext;
+	use crate::protocol_types::protocol_verison::ProtocolVerison;
+
+	fn context(hostname: &str) -> StatusContext {
+		StatusContext {
+			protocol_version: 766,
+			server_address: hostname.to_string(),
+			server_port: 25565,
+			remote_address: SocketAddr::from(([127, 0, 0, 1], 50000)),
+		}
+	}
+
+	#[test]
+	fn test_status_cache() {
+		let builds = Arc::new(AtomicI32::new(0));
+		let counter = builds.clone();
+
+		let supplier = StatusSupplier::new(move |context| {
+			let count = counter.fetch_add(1, Ordering::SeqCst) + 1;
+			StatusResponseSpec::new(ProtocolVerison::V1_20, format!("{} {}", context.hostname(), count))
+		}).cached(Some(Duration::from_millis(100)));
+
+		assert_eq!(supplier.build(&context("a.example.com")).description().text, "a.example.com 1");
+		assert_eq!(supplier.build(&context("a.example.com")).description().text, "a.example.com 1");
+		assert_eq!(supplier.build(&context("b.example.com")).description().text, "b.example.com 2");
+
+		supplier.clone().invalidate();
+		assert_eq!(supplier.build(&context("a.example.com")).description().text, "a.example.com 3");
+
+		std::thread::sleep(Duration::from_millis(120));
+		assert_eq!(supplier.build(&context("a.example.com")).description().text, "a.example.com 4");
+		assert_eq!(builds.load(Ordering::SeqCst), 4);
+	}
+}
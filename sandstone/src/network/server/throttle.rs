@@ -0,0 +1,116 @@
+//! Limits how often a single IP address can connect, like the connection throttle of Bukkit based
+//! servers. Connections over the limit are closed as soon as they are accepted, before anything is read.
+
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Once this many addresses are being tracked, the ones that haven't connected recently are removed
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// The limits used by a [ConnectionThrottle]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThrottleConfig {
+	/// How many connections an address can make in each window
+	pub max_connections: u32,
+	pub window: Duration,
+	/// Addresses that are never throttled, such as a proxy in front of the server
+	pub exempt: HashSet<IpAddr>,
+}
+
+impl ThrottleConfig {
+	pub fn new(max_connections: u32, window: Duration) -> Self {
+		Self {
+			max_connections,
+			window,
+			exempt: HashSet::new(),
+		}
+	}
+
+	/// Never throttle connections from this address
+	pub fn exempt(mut self, addr: IpAddr) -> Self {
+		self.exempt.insert(addr);
+		self
+	}
+}
+
+impl Default for ThrottleConfig {
+	/// One connection every 4 seconds, the Bukkit default
+	fn default() -> Self {
+		Self::new(1, Duration::from_millis(4000))
+	}
+}
+
+/// Counts the connections made by each address within a fixed window
+#[derive(Debug)]
+pub struct ConnectionThrottle {
+	config: ThrottleConfig,
+	/// The start of the current window for each address, and how many connections were made in it
+	attempts: Mutex<HashMap<IpAddr, (Instant, u32)>>,
+}
+
+impl ConnectionThrottle {
+	pub fn new(config: ThrottleConfig) -> Self {
+		Self {
+			config,
+			attempts: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Record a connection from `addr`, returning `false` if it is over the limit and should be rejected
+	pub fn allow(&self, addr: IpAddr) -> bool {
+		if self.config.exempt.contains(&addr) {
+			return true;
+		}
+
+		let now = Instant::now();
+		let mut attempts = self.attempts.lock().unwrap();
+
+		if attempts.len() >= PRUNE_THRESHOLD {
+			attempts.retain(|_, (start, _)| now.duration_since(*start) < self.config.window);
+		}
+
+		let (start, count) = attempts.entry(addr).or_insert((now, 0));
+
+		if now.duration_since(*start) >= self.config.window {
+			*start = now;
+			*count = 0;
+		}
+
+		*count += 1;
+
+		*count <= self.config.max_connections
+	}
+
+	pub fn config(&self) -> &ThrottleConfig {
+		&self.config
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::net::{IpAddr, Ipv4Addr};
+	use std::time::Duration;
+
+	use crate::network::server::throttle::{ConnectionThrottle, ThrottleConfig};
+
+	#[test]
+	fn test_throttle() {
+		let proxy = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+		let player = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+		let throttle = ConnectionThrottle::new(ThrottleConfig::new(2, Duration::from_millis(50)).exempt(proxy));
+
+		assert!(throttle.allow(player));
+		assert!(throttle.allow(player));
+		assert!(!throttle.allow(player));
+		assert!(throttle.allow(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 3))));
+
+		for _ in 0..10 {
+			assert!(throttle.allow(proxy));
+		}
+
+		std::thread::sleep(Duration::from_millis(60));
+		assert!(throttle.allow(player));
+	}
+}
@@ -0,0 +1,6 @@
+//! Chat between players. Since 1.19 players can sign their chat messages with a key that Mojang
+//! certifies, so that messages can be reported and can't be changed by the server. Since 1.19.3 the key
+//! is sent in a chat session once the player is in game, rather than during login.
+
+pub mod session;
+pub mod signed;
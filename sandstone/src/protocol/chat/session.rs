@@ -0,0 +1,165 @@
+//! The player's chat session, sent with the `PlayerSession` packet after joining. It holds the key that
+//! the player signs their messages with, which Mojang signs in turn so that the server can tell it
+//! belongs to the player. See [here](https://wiki.vg/Protocol#Player_Session) for more.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rsa::RsaPublicKey;
+use rsa::pkcs8::DecodePublicKey;
+use sandstone_derive::{McDeserialize, McSerialize};
+use uuid::Uuid;
+
+use crate::network::network_error::NetworkError;
+use crate::protocol::login::yggdrasil::YggdrasilVerifier;
+use crate::protocol::packets::PlayerSessionBody;
+
+/// The longest public key that vanilla accepts, in bytes
+pub const MAX_KEY_LENGTH: usize = 512;
+/// The longest key signature that vanilla accepts, in bytes
+pub const MAX_KEY_SIGNATURE_LENGTH: usize = 4096;
+
+/// A player's chat key, certified by Mojang
+#[derive(McSerialize, McDeserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PlayerPublicKey {
+	/// When the key expires, in milliseconds since the epoch
+	pub expires_at: i64,
+	/// The RSA key as a DER `SubjectPublicKeyInfo`
+	pub public_key: Vec<u8>,
+	/// Mojang's signature of the key, see [PlayerPublicKey::signed_payload]
+	pub key_signature: Vec<u8>,
+}
+
+impl PlayerPublicKey {
+	pub fn expires_at(&self) -> SystemTime {
+		UNIX_EPOCH + Duration::from_millis(self.expires_at.max(0) as u64)
+	}
+
+	pub fn is_expired(&self) -> bool {
+		self.is_expired_at(SystemTime::now())
+	}
+
+	pub fn is_expired_at(&self, time: SystemTime) -> bool {
+		time > self.expires_at()
+	}
+
+	/// Parse the key, so that it can check the player's message signatures
+	pub fn rsa_key(&self) -> Result<RsaPublicKey, NetworkError> {
+		RsaPublicKey::from_public_key_der(&self.public_key)
+			.map_err(|e| NetworkError::InvalidSignature(format!("Invalid chat key: {}", e)))
+	}
+
+	/// The data that Mojang signs: the player's UUID, the expiry and the key. Tying the key to the UUID
+	/// stops a player from using someone else's key.
+	pub fn signed_payload(&self, player: Uuid) -> Vec<u8> {
+		let mut payload = Vec::with_capacity(24 + self.public_key.len());
+		payload.extend_from_slice(player.as_bytes());
+		payload.extend_from_slice(&self.expires_at.to_be_bytes());
+		payload.extend_from_slice(&self.public_key);
+		payload
+	}
+
+	/// Check that the key belongs to the player and hasn't expired. `verifier` should trust Mojang's
+	/// player certificate keys.
+	pub fn validate(&self, player: Uuid, verifier: &YggdrasilVerifier) -> Result<(), NetworkError> {
+		if self.public_key.len() > MAX_KEY_LENGTH || self.key_signature.len() > MAX_KEY_SIGNATURE_LENGTH {
+			return Err(NetworkError::InvalidSignature("Chat key is too long".to_string()));
+		}
+
+		if self.is_expired() {
+			return Err(NetworkError::InvalidSignature("Chat key has expired".to_string()));
+		}
+
+		self.rsa_key()?;
+
+		if !verifier.verify_signature(&self.signed_payload(player), &self.key_signature) {
+			return Err(NetworkError::InvalidSignature("Chat key was not signed by Mojang".to_string()));
+		}
+
+		Ok(())
+	}
+}
+
+/// A chat session, which the player's signed messages refer to by its ID
+#[derive(McSerialize, McDeserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChatSession {
+	pub session_id: Uuid,
+	pub public_key: PlayerPublicKey,
+}
+
+impl ChatSession {
+	/// Check the session's key, see [PlayerPublicKey::validate]
+	pub fn validate(&self, player: Uuid, verifier: &YggdrasilVerifier) -> Result<(), NetworkError> {
+		self.public_key.validate(player, verifier)
+	}
+}
+
+impl From<PlayerSessionBody> for ChatSession {
+	fn from(body: PlayerSessionBody) -> Self {
+		Self {
+			session_id: body.session_id,
+			public_key: body.public_key,
+		}
+	}
+}
+
+impl From<ChatSession> for PlayerSessionBody {
+	fn from(session: ChatSession) -> Self {
+		PlayerSessionBody::new(session.session_id, session.public_key)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+	use rsa::{RsaPrivateKey, RsaPublicKey};
+	use rsa::pkcs8::EncodePublicKey;
+	use uuid::Uuid;
+
+	use crate::network::network_error::NetworkError;
+	use crate::protocol::chat::session::{ChatSession, PlayerPublicKey};
+	use crate::protocol::login::yggdrasil::tests::sign_bytes;
+	use crate::protocol::login::yggdrasil::YggdrasilVerifier;
+	use crate::protocol::packets::{Packet, PlayerSessionBody};
+	use crate::protocol::packets::packet_definer::PacketDirection;
+	use crate::protocol::serialization::{McDeserializer, McSerialize, McSerializer, StateBasedDeserializer};
+
+	#[test]
+	fn test_chat_session() {
+		let mojang = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+		let verifier = YggdrasilVerifier::from_der(RsaPublicKey::from(&mojang).to_public_key_der().unwrap().as_bytes()).unwrap();
+
+		let player_key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+		let player = Uuid::from_u128(0x1234);
+		let expires = SystemTime::now() + Duration::from_secs(3600);
+
+		let mut key = PlayerPublicKey {
+			expires_at: expires.duration_since(UNIX_EPOCH).unwrap().as_millis() as i64,
+			public_key: RsaPublicKey::from(&player_key).to_public_key_der().unwrap().to_vec(),
+			key_signature: vec![],
+		};
+		key.key_signature = sign_bytes(&mojang, &key.signed_payload(player));
+
+		let session = ChatSession { session_id: Uuid::from_u128(7), public_key: key.clone() };
+		assert_eq!(session.validate(player, &verifier), Ok(()));
+		assert_eq!(session.public_key.rsa_key().unwrap(), RsaPublicKey::from(&player_key));
+
+		// someone else's key
+		assert!(matches!(session.validate(Uuid::from_u128(0x4321), &verifier), Err(NetworkError::InvalidSignature(_))));
+
+		let mut expired = key.clone();
+		expired.expires_at = 1000;
+		assert!(expired.is_expired());
+		assert!(matches!(expired.validate(player, &verifier), Err(NetworkError::InvalidSignature(_))));
+
+		let packet = Packet::PlayerSession(PlayerSessionBody::from(session.clone()));
+		let mut serializer = McSerializer::new();
+		packet.mc_serialize(&mut serializer).unwrap();
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		match Packet::deserialize_state(&mut deserializer, packet.state(), PacketDirection::SERVER).unwrap() {
+			Packet::PlayerSession(body) => assert_eq!(ChatSession::from(body), session),
+			p => panic!("Expected player session, got {:?}", p),
+		}
+	}
+}
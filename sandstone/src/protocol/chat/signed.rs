@@ -0,0 +1,498 @@
+//! Signed chat messages and commands. Each message the player sends is signed with the key of their
+//! [ChatSession], over the message, when it was sent and the last messages the player saw, so that a
+//! reported message can be shown in context. Messages form a chain: each one has the next index in the
+//! session, which stops the server from leaving messages out.
+//!
+//! [ChatValidator] checks the chain and signatures of one player's messages the way vanilla does. See
+//! [here](https://wiki.vg/Chat#Signed_chat) for more.
+
+use rsa::Pkcs1v15Sign;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::network::network_error::NetworkError;
+use crate::protocol::chat::session::ChatSession;
+use crate::protocol::packets::{ChatMessageBody, Packet, PlayerChatBody, SignedChatCommandBody};
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::bitset::{BitSet, FixedBitSet};
+use crate::protocol_types::datatypes::chat::{NbtTextComponent, TextComponent};
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+/// The length of a message signature, made with the 2048 bit keys that Mojang issues
+pub const SIGNATURE_LENGTH: usize = 256;
+/// How many of the last messages a player acknowledges
+pub const LAST_SEEN_COUNT: usize = 20;
+/// The longest message a player can send
+pub const MAX_MESSAGE_LENGTH: usize = 256;
+
+/// The version of the signed payload, which vanilla has kept at 1 since 1.19.3
+const SIGNATURE_VERSION: i32 = 1;
+
+/// The set of messages a player acknowledges in `ChatMessage` and `SignedChatCommand`
+pub type Acknowledged = FixedBitSet<LAST_SEEN_COUNT>;
+
+/// The signature of a chat message, or of an argument of a command. It is boxed so that packets holding
+/// one stay small.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MessageSignature(pub Box<[u8; SIGNATURE_LENGTH]>);
+
+impl MessageSignature {
+	pub fn new(bytes: [u8; SIGNATURE_LENGTH]) -> Self {
+		Self(Box::new(bytes))
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+		bytes.try_into().ok().map(Self::new)
+	}
+}
+
+impl McSerialize for MessageSignature {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		serializer.serialize_bytes(self.0.as_slice());
+		Ok(())
+	}
+}
+
+impl McDeserialize for MessageSignature {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		Ok(Self::new(<[u8; SIGNATURE_LENGTH]>::mc_deserialize(deserializer)?))
+	}
+}
+
+/// The signature of one argument of a signed command, such as the message of `/msg`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArgumentSignature {
+	pub name: String,
+	pub signature: MessageSignature,
+}
+
+impl McSerialize for ArgumentSignature {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.name.mc_serialize(serializer)?;
+		self.signature.mc_serialize(serializer)
+	}
+}
+
+impl McDeserialize for ArgumentSignature {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let name = String::mc_deserialize(deserializer)?;
+		let signature = MessageSignature::mc_deserialize(deserializer)?;
+
+		Ok(Self {
+			name,
+			signature,
+		})
+	}
+}
+
+/// A message that came before a `PlayerChat` message, either by the ID that the client cached it under or
+/// by its full signature
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PreviousMessage {
+	Id(i32),
+	Signature(MessageSignature),
+}
+
+impl McSerialize for PreviousMessage {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		match self {
+			PreviousMessage::Id(id) => VarInt(id + 1).mc_serialize(serializer),
+			PreviousMessage::Signature(signature) => {
+				VarInt(0).mc_serialize(serializer)?;
+				signature.mc_serialize(serializer)
+			}
+		}
+	}
+}
+
+impl McDeserialize for PreviousMessage {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		match VarInt::mc_deserialize(deserializer)?.0 {
+			0 => Ok(PreviousMessage::Signature(MessageSignature::mc_deserialize(deserializer)?)),
+			id => Ok(PreviousMessage::Id(id - 1)),
+		}
+	}
+}
+
+/// Which parts of a `PlayerChat` message were filtered by the server
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum FilterMask {
+	#[default]
+	PassThrough,
+	FullyFiltered,
+	/// The characters that were filtered, which the client shows as `#`
+	PartiallyFiltered(BitSet),
+}
+
+impl McSerialize for FilterMask {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		match self {
+			FilterMask::PassThrough => VarInt(0).mc_serialize(serializer),
+			FilterMask::FullyFiltered => VarInt(1).mc_serialize(serializer),
+			FilterMask::PartiallyFiltered(mask) => {
+				VarInt(2).mc_serialize(serializer)?;
+				mask.mc_serialize(serializer)
+			}
+		}
+	}
+}
+
+impl McDeserialize for FilterMask {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		match VarInt::mc_deserialize(deserializer)?.0 {
+			0 => Ok(FilterMask::PassThrough),
+			1 => Ok(FilterMask::FullyFiltered),
+			2 => Ok(FilterMask::PartiallyFiltered(BitSet::mc_deserialize(deserializer)?)),
+			kind => Err(SerializingErr::UniqueFailure(format!("Unknown filter type {}", kind))),
+		}
+	}
+}
+
+/// What a player signs for each message. The timestamp is in milliseconds since the epoch, as in the
+/// packets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SignedMessage {
+	pub sender: Uuid,
+	pub session_id: Uuid,
+	/// The position of the message in the session's chain
+	pub index: i32,
+	pub content: String,
+	pub timestamp: i64,
+	pub salt: i64,
+	/// The signatures of the messages the player acknowledged, oldest first
+	pub last_seen: Vec<MessageSignature>,
+}
+
+impl SignedMessage {
+	/// The data that the player signs
+	pub fn signed_payload(&self) -> Vec<u8> {
+		let mut payload = Vec::with_capacity(64 + self.content.len() + self.last_seen.len() * SIGNATURE_LENGTH);
+		payload.extend_from_slice(&SIGNATURE_VERSION.to_be_bytes());
+		payload.extend_from_slice(self.sender.as_bytes());
+		payload.extend_from_slice(self.session_id.as_bytes());
+		payload.extend_from_slice(&self.index.to_be_bytes());
+		payload.extend_from_slice(&self.salt.to_be_bytes());
+		payload.extend_from_slice(&(self.timestamp / 1000).to_be_bytes()); // signed in seconds
+		payload.extend_from_slice(&(self.content.len() as i32).to_be_bytes());
+		payload.extend_from_slice(self.content.as_bytes());
+		payload.extend_from_slice(&(self.last_seen.len() as i32).to_be_bytes());
+
+		for signature in &self.last_seen {
+			payload.extend_from_slice(signature.0.as_slice());
+		}
+
+		payload
+	}
+
+	/// The `PlayerChat` packet that passes this message on to other players. `chat_type` is the ID of the
+	/// chat type in the `minecraft:chat_type` registry, and the messages the player had seen are sent by
+	/// their full signatures.
+	pub fn player_chat_packet(&self, signature: Option<MessageSignature>, chat_type: i32, sender_name: TextComponent) -> Packet {
+		Packet::PlayerChat(PlayerChatBody::new(
+			self.sender,
+			VarInt(self.index),
+			signature,
+			self.content.clone(),
+			self.timestamp,
+			self.salt,
+			self.last_seen.iter().cloned().map(PreviousMessage::Signature).collect(),
+			None,
+			FilterMask::PassThrough,
+			VarInt(chat_type + 1),
+			Box::new(NbtTextComponent::new(sender_name)),
+			None,
+		))
+	}
+
+	/// Check that the session's key made a SHA256withRSA signature of this message
+	pub fn verify(&self, session: &ChatSession, signature: &MessageSignature) -> Result<(), NetworkError> {
+		let hash = Sha256::digest(self.signed_payload());
+
+		session.public_key.rsa_key()?
+			.verify(Pkcs1v15Sign::new::<Sha256>(), &hash, signature.0.as_slice())
+			.map_err(|_| NetworkError::InvalidSignature("Chat message was not signed by the player's chat key".to_string()))
+	}
+}
+
+/// Tracks the messages sent to a player, so that the signatures they acknowledge can be worked out. This
+/// follows vanilla's `LastSeenMessagesValidator`: the player reports how many new messages it has seen
+/// since the last update, and which of the last [LAST_SEEN_COUNT] it acknowledges.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastSeenTracker {
+	/// Each message and whether it is still pending, meaning that the player hasn't acknowledged it yet
+	tracked: Vec<Option<(MessageSignature, bool)>>,
+}
+
+impl LastSeenTracker {
+	pub fn new() -> Self {
+		Self {
+			tracked: vec![None; LAST_SEEN_COUNT],
+		}
+	}
+
+	/// Track a signed message that was sent to the player
+	pub fn add_pending(&mut self, signature: MessageSignature) {
+		self.tracked.push(Some((signature, true)));
+	}
+
+	/// The number of messages sent that the player hasn't reported seeing
+	pub fn pending(&self) -> usize {
+		self.tracked.len() - LAST_SEEN_COUNT
+	}
+
+	/// Drop the `offset` oldest messages, which the player reports with `MessageAcknowledgment` and at the
+	/// start of every signed message
+	pub fn apply_offset(&mut self, offset: i32) -> Result<(), NetworkError> {
+		if offset < 0 || offset as usize > self.pending() {
+			return Err(NetworkError::InvalidSignature(format!("Invalid acknowledgement offset {}", offset)));
+		}
+
+		self.tracked.drain(..offset as usize);
+		Ok(())
+	}
+
+	/// Apply a player's acknowledgements, returning the signatures of the messages it acknowledged
+	pub fn apply_update(&mut self, offset: i32, acknowledged: &Acknowledged) -> Result<Vec<MessageSignature>, NetworkError> {
+		self.apply_offset(offset)?;
+		let mut last_seen = vec![];
+
+		for i in 0..LAST_SEEN_COUNT {
+			let entry = &mut self.tracked[i];
+
+			if acknowledged.get(i) {
+				let Some((signature, pending)) = entry else {
+					return Err(NetworkError::InvalidSignature(format!("Acknowledged a missing message at {}", i)));
+				};
+
+				*pending = false;
+				last_seen.push(signature.clone());
+			} else {
+				if matches!(entry, Some((_, false))) {
+					return Err(NetworkError::InvalidSignature(format!("Stopped acknowledging the message at {}", i)));
+				}
+
+				*entry = None;
+			}
+		}
+
+		Ok(last_seen)
+	}
+}
+
+impl Default for LastSeenTracker {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Checks the signed messages and commands of one player, see the [module docs](self). A message that
+/// fails is an [NetworkError::InvalidSignature], after which vanilla disconnects the player.
+#[derive(Debug, Clone)]
+pub struct ChatValidator {
+	player: Uuid,
+	session: Option<ChatSession>,
+	next_index: i32,
+	last_timestamp: i64,
+	/// The messages sent to the player
+	pub last_seen: LastSeenTracker,
+}
+
+impl ChatValidator {
+	pub fn new(player: Uuid) -> Self {
+		Self {
+			player,
+			session: None,
+			next_index: 0,
+			last_timestamp: i64::MIN,
+			last_seen: LastSeenTracker::new(),
+		}
+	}
+
+	/// Start a new chat session, which restarts the chain. The session should be
+	/// [validated](ChatSession::validate) first.
+	pub fn set_session(&mut self, session: ChatSession) {
+		self.session = Some(session);
+		self.next_index = 0;
+	}
+
+	pub fn session(&self) -> Option<&ChatSession> {
+		self.session.as_ref()
+	}
+
+	/// Check a `ChatMessage` from the player. Unsigned messages are returned as `None`, which servers that
+	/// enforce secure chat should reject. Signed messages are returned with everything needed to pass
+	/// them on in `PlayerChat`.
+	pub fn validate_message(&mut self, body: &ChatMessageBody) -> Result<Option<SignedMessage>, NetworkError> {
+		let last_seen = self.last_seen.apply_update(body.message_count.0, &body.acknowledged)?;
+
+		let Some(signature) = &body.signature else {
+			return Ok(None);
+		};
+
+		let message = self.next_message(body.message.clone(), body.timestamp, body.salt, last_seen)?;
+		self.verify(&message, signature)?;
+
+		Ok(Some(message))
+	}
+
+	/// Check a `SignedChatCommand` from the player. `arguments` gives the value of each signable argument
+	/// by name, from parsing the command. Each signature uses up an index in the chain, and an argument
+	/// that was signed but isn't in `arguments` fails.
+	pub fn validate_command(&mut self, body: &SignedChatCommandBody, arguments: &[(&str, &str)]) -> Result<Vec<SignedMessage>, NetworkError> {
+		let last_seen = self.last_seen.apply_update(body.message_count.0, &body.acknowledged)?;
+		let mut messages = vec![];
+
+		for argument in &body.argument_signatures {
+			let (_, value) = arguments.iter().find(|(name, _)| *name == argument.name)
+				.ok_or_else(|| NetworkError::InvalidSignature(format!("Signed an unknown argument {}", argument.name)))?;
+
+			let message = self.next_message(value.to_string(), body.timestamp, body.salt, last_seen.clone())?;
+			self.verify(&message, &argument.signature)?;
+			messages.push(message);
+		}
+
+		Ok(messages)
+	}
+
+	fn next_message(&mut self, content: String, timestamp: i64, salt: i64, last_seen: Vec<MessageSignature>) -> Result<SignedMessage, NetworkError> {
+		let session = self.session.as_ref()
+			.ok_or_else(|| NetworkError::InvalidSignature("Signed a message without a chat session".to_string()))?;
+
+		if content.chars().count() > MAX_MESSAGE_LENGTH {
+			return Err(NetworkError::InvalidSignature("Chat message is too long".to_string()));
+		}
+
+		if timestamp < self.last_timestamp {
+			return Err(NetworkError::InvalidSignature("Chat message is older than the last one".to_string()));
+		}
+
+		let message = SignedMessage {
+			sender: self.player,
+			session_id: session.session_id,
+			index: self.next_index,
+			content,
+			timestamp,
+			salt,
+			last_seen,
+		};
+
+		self.next_index += 1;
+		self.last_timestamp = timestamp;
+
+		Ok(message)
+	}
+
+	fn verify(&self, message: &SignedMessage, signature: &MessageSignature) -> Result<(), NetworkError> {
+		let session = self.session.as_ref().expect("next_message checks the session");
+
+		if session.public_key.is_expired() {
+			return Err(NetworkError::InvalidSignature("Chat key has expired".to_string()));
+		}
+
+		message.verify(session, signature)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+	use rsa::{Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+	use rsa::pkcs8::EncodePublicKey;
+	use sha2::{Digest, Sha256};
+	use uuid::Uuid;
+
+	use crate::network::network_error::NetworkError;
+	use crate::protocol::chat::session::{ChatSession, PlayerPublicKey};
+	use crate::protocol::chat::signed::{Acknowledged, ArgumentSignature, ChatValidator, LastSeenTracker, MessageSignature, SignedMessage};
+	use crate::protocol::packets::{ChatMessageBody, SignedChatCommandBody};
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	fn signature(byte: u8) -> MessageSignature {
+		MessageSignature::new([byte; 256])
+	}
+
+	fn acknowledged(bits: &[usize]) -> Acknowledged {
+		let mut set = Acknowledged::new();
+		for bit in bits {
+			set.set(*bit, true);
+		}
+		set
+	}
+
+	#[test]
+	fn test_last_seen_tracker() {
+		let mut tracker = LastSeenTracker::new();
+		tracker.add_pending(signature(1));
+		tracker.add_pending(signature(2));
+		assert_eq!(tracker.pending(), 2);
+
+		// saw both, the window moved up by 2
+		assert_eq!(tracker.apply_update(2, &acknowledged(&[18, 19])), Ok(vec![signature(1), signature(2)]));
+		assert_eq!(tracker.pending(), 0);
+		assert!(tracker.apply_offset(1).is_err());
+
+		tracker.add_pending(signature(3));
+		assert_eq!(tracker.apply_update(1, &acknowledged(&[17, 18, 19])), Ok(vec![signature(1), signature(2), signature(3)]));
+
+		// nothing new, but a message that was acknowledged is dropped
+		assert!(matches!(tracker.apply_update(0, &acknowledged(&[18, 19])), Err(NetworkError::InvalidSignature(_))));
+		// a message that was never sent
+		assert!(matches!(LastSeenTracker::new().apply_update(0, &acknowledged(&[0])), Err(NetworkError::InvalidSignature(_))));
+	}
+
+	#[test]
+	fn test_chat_validator() {
+		let key = RsaPrivateKey::new(&mut rand::thread_rng(), 2048).unwrap();
+		let player = Uuid::from_u128(0x1234);
+		let session = ChatSession {
+			session_id: Uuid::from_u128(0x5678),
+			public_key: PlayerPublicKey {
+				expires_at: (SystemTime::now() + Duration::from_secs(3600)).duration_since(UNIX_EPOCH).unwrap().as_millis() as i64,
+				public_key: RsaPublicKey::from(&key).to_public_key_der().unwrap().to_vec(),
+				key_signature: vec![],
+			},
+		};
+
+		let sign = |message: &SignedMessage| {
+			let hash = Sha256::digest(message.signed_payload());
+			MessageSignature::from_bytes(&key.sign(Pkcs1v15Sign::new::<Sha256>(), &hash).unwrap()).unwrap()
+		};
+
+		let mut validator = ChatValidator::new(player);
+		validator.last_seen.add_pending(signature(9));
+
+		let mut message = SignedMessage {
+			sender: player,
+			session_id: session.session_id,
+			index: 0,
+			content: "hello".to_string(),
+			timestamp: 1_700_000_000_123,
+			salt: 42,
+			last_seen: vec![signature(9)],
+		};
+		let body = ChatMessageBody::new(message.content.clone(), message.timestamp, message.salt, Some(sign(&message)), VarInt(1), acknowledged(&[19]));
+
+		// no session yet
+		assert!(validator.clone().validate_message(&body).is_err());
+
+		validator.set_session(session.clone());
+		assert_eq!(validator.validate_message(&body), Ok(Some(message.clone())));
+
+		// the same message again has the wrong index
+		let body = ChatMessageBody::new(message.content.clone(), message.timestamp, message.salt, Some(sign(&message)), VarInt(0), acknowledged(&[19]));
+		assert!(matches!(validator.clone().validate_message(&body), Err(NetworkError::InvalidSignature(_))));
+
+		message.index = 1;
+		message.content = "give it to me".to_string();
+		let arguments = vec![ArgumentSignature { name: "message".to_string(), signature: sign(&message) }];
+		let command = SignedChatCommandBody::new("msg Steve give it to me".to_string(), message.timestamp, message.salt, arguments, VarInt(0), acknowledged(&[19]));
+		assert_eq!(validator.clone().validate_command(&command, &[("targets", "Steve"), ("message", "give it to me")]), Ok(vec![message.clone()]));
+		assert!(validator.validate_command(&command, &[("targets", "Steve")]).is_err());
+
+		// unsigned messages aren't checked
+		let body = ChatMessageBody::new("hi".to_string(), 0, 0, None, VarInt(0), acknowledged(&[19]));
+		assert_eq!(validator.validate_message(&body), Ok(None));
+	}
+}
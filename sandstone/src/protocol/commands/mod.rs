@@ -0,0 +1,5 @@
+//! Commands, as the client sees them. The server sends its commands as a graph of Brigadier nodes in the
+//! `Commands` packet, which the client uses to parse, highlight and complete commands as they are typed.
+
+pub mod tree;
+pub mod suggestions;
@@ -0,0 +1,135 @@
+//! Tab completion for arguments that ask the server, see
+//! [SuggestionType::AskServer](crate::protocol::commands::tree::SuggestionType::AskServer). The client sends
+//! the command typed so far in a `CommandSuggestionsRequest`, and the server answers with the text to put
+//! in place of part of it. [ServerHandler::on_command_suggestions](crate::network::client::client_handlers::ServerHandler::on_command_suggestions)
+//! is called for each request.
+
+use sandstone_derive::{McDeserialize, McSerialize};
+
+use crate::protocol::packets::{CommandSuggestionsResponseBody, Packet};
+use crate::protocol_types::datatypes::chat::{NbtTextComponent, TextComponent};
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+/// A single suggestion, with the text shown when it is hovered
+#[derive(McSerialize, McDeserialize, Debug, Clone, PartialEq)]
+pub struct Suggestion {
+	pub text: String,
+	pub tooltip: Option<NbtTextComponent>,
+}
+
+/// The suggestions for a request, which all replace the same part of the command
+///
+/// ```
+/// # use sandstone::protocol::commands::suggestions::Suggestions;
+/// let suggestions = Suggestions::complete("/warp sp", ["spawn", "Spleef", "arena"]);
+///
+/// assert_eq!(suggestions.start(), 6);
+/// assert_eq!(suggestions.length(), 2);
+/// assert_eq!(suggestions.matches().len(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Suggestions {
+	start: i32,
+	length: i32,
+	matches: Vec<Suggestion>,
+}
+
+impl Suggestions {
+	/// Suggestions that replace `length` characters of the command from `start`. The client counts these in
+	/// UTF-16 code units, including the leading `/`.
+	pub fn new(start: i32, length: i32) -> Self {
+		Self {
+			start,
+			length,
+			matches: vec![],
+		}
+	}
+
+	/// Suggestions that replace the last word of the command, which is the text of the request
+	pub fn last_word(command: &str) -> Self {
+		let start = last_word_start(command);
+		Self::new(utf16_len(&command[..start]), utf16_len(&command[start..]))
+	}
+
+	/// Suggest the candidates that start with the last word of the command, ignoring case
+	pub fn complete<I: IntoIterator<Item = S>, S: Into<String>>(command: &str, candidates: I) -> Self {
+		let mut suggestions = Self::last_word(command);
+		let word = command[last_word_start(command)..].to_lowercase();
+
+		for candidate in candidates {
+			let candidate = candidate.into();
+
+			if candidate.to_lowercase().starts_with(&word) {
+				suggestions = suggestions.suggest(candidate);
+			}
+		}
+
+		suggestions
+	}
+
+	pub fn suggest<T: Into<String>>(mut self, text: T) -> Self {
+		self.matches.push(Suggestion { text: text.into(), tooltip: None });
+		self
+	}
+
+	pub fn suggest_with_tooltip<T: Into<String>>(mut self, text: T, tooltip: TextComponent) -> Self {
+		self.matches.push(Suggestion { text: text.into(), tooltip: Some(NbtTextComponent::new(tooltip)) });
+		self
+	}
+
+	pub fn start(&self) -> i32 {
+		self.start
+	}
+
+	pub fn length(&self) -> i32 {
+		self.length
+	}
+
+	pub fn matches(&self) -> &[Suggestion] {
+		&self.matches
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.matches.is_empty()
+	}
+
+	/// The `CommandSuggestionsResponse` packet, with the transaction id of the request
+	pub fn response(self, transaction_id: VarInt) -> Packet {
+		Packet::CommandSuggestionsResponse(CommandSuggestionsResponseBody::new(transaction_id, VarInt(self.start), VarInt(self.length), self.matches))
+	}
+}
+
+/// The byte index that the last word starts at, after the last space or the leading `/`
+fn last_word_start(command: &str) -> usize {
+	match command.rfind(' ') {
+		Some(i) => i + 1,
+		None if command.starts_with('/') => 1,
+		None => 0,
+	}
+}
+
+fn utf16_len(s: &str) -> i32 {
+	s.encode_utf16().count() as i32
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::commands::suggestions::Suggestions;
+
+	#[test]
+	fn test_suggestion_ranges() {
+		let suggestions = Suggestions::complete("/gi", ["give", "gamemode", "Gift"]);
+		assert_eq!((suggestions.start(), suggestions.length()), (1, 2));
+		assert_eq!(suggestions.matches().iter().map(|s| s.text.as_str()).collect::<Vec<_>>(), vec!["give", "Gift"]);
+
+		let suggestions = Suggestions::complete("/msg ", ["Alice", "Bob"]);
+		assert_eq!((suggestions.start(), suggestions.length()), (5, 0));
+		assert_eq!(suggestions.matches().len(), 2);
+
+		// counted in UTF-16, like the client does
+		let suggestions = Suggestions::complete("/say 🎉 hé", ["héllo", "hello"]);
+		assert_eq!((suggestions.start(), suggestions.length()), (8, 2));
+		assert_eq!(suggestions.matches()[0].text, "héllo");
+		assert!(Suggestions::complete("/warp x", ["spawn"]).is_empty());
+	}
+}
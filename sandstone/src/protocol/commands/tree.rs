@@ -0,0 +1,613 @@
+//! The command graph sent in the `Commands` packet. Each node is the root, a literal word or an argument
+//! with a parser, and points to the nodes that can follow it by their index in the packet. A node can also
+//! redirect to another node, so that `/tp` can use the same arguments as `/teleport`.
+//!
+//! [CommandTree] builds the graph from nested [literal] and [argument] nodes, the same way that
+//! Brigadier does. See [here](https://wiki.vg/Command_Data) for more.
+
+use std::collections::HashMap;
+
+use crate::protocol::packets::{CommandsBody, Packet};
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::identifier::Identifier;
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+const NODE_TYPE_MASK: u8 = 0x03;
+const EXECUTABLE: u8 = 0x04;
+const HAS_REDIRECT: u8 = 0x08;
+const HAS_SUGGESTIONS: u8 = 0x10;
+
+/// The smallest and largest value of a number argument, which are only sent if they are set
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Bounds<T> {
+	pub min: Option<T>,
+	pub max: Option<T>,
+}
+
+impl<T> Bounds<T> {
+	pub fn new(min: Option<T>, max: Option<T>) -> Self {
+		Self {
+			min,
+			max,
+		}
+	}
+}
+
+impl<T: McSerialize> McSerialize for Bounds<T> {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		let flags = self.min.is_some() as u8 | (self.max.is_some() as u8) << 1;
+		flags.mc_serialize(serializer)?;
+
+		if let Some(min) = &self.min {
+			min.mc_serialize(serializer)?;
+		}
+
+		if let Some(max) = &self.max {
+			max.mc_serialize(serializer)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl<T: McDeserialize> McDeserialize for Bounds<T> {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let flags = u8::mc_deserialize(deserializer)?;
+
+		let min = if flags & 0x01 != 0 { Some(T::mc_deserialize(deserializer)?) } else { None };
+		let max = if flags & 0x02 != 0 { Some(T::mc_deserialize(deserializer)?) } else { None };
+
+		Ok(Self { min, max })
+	}
+}
+
+/// How much of the input a string argument takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StringKind {
+	/// A single word
+	#[default]
+	SingleWord = 0,
+	/// A single word, or a phrase in quotes
+	QuotablePhrase = 1,
+	/// The rest of the input
+	GreedyPhrase = 2,
+}
+
+impl TryFrom<i32> for StringKind {
+	type Error = SerializingErr;
+
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(StringKind::SingleWord),
+			1 => Ok(StringKind::QuotablePhrase),
+			2 => Ok(StringKind::GreedyPhrase),
+			_ => Err(SerializingErr::UniqueFailure(format!("Unknown string argument kind {}", value))),
+		}
+	}
+}
+
+/// How the client parses an argument, with the properties that some parsers have. These are sent as their
+/// id in the `command_argument_type` registry, which is fixed for each version.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentParser {
+	Bool,
+	Float(Bounds<f32>),
+	Double(Bounds<f64>),
+	Integer(Bounds<i32>),
+	Long(Bounds<i64>),
+	String(StringKind),
+	Entity { single: bool, players_only: bool },
+	GameProfile,
+	BlockPos,
+	ColumnPos,
+	Vec3,
+	Vec2,
+	BlockState,
+	BlockPredicate,
+	ItemStack,
+	ItemPredicate,
+	Color,
+	Component,
+	Style,
+	Message,
+	NbtCompound,
+	NbtTag,
+	NbtPath,
+	Objective,
+	ObjectiveCriteria,
+	Operation,
+	Particle,
+	Angle,
+	Rotation,
+	ScoreboardSlot,
+	ScoreHolder { allow_multiple: bool },
+	Swizzle,
+	Team,
+	ItemSlot,
+	ItemSlots,
+	ResourceLocation,
+	Function,
+	EntityAnchor,
+	IntRange,
+	FloatRange,
+	Dimension,
+	Gamemode,
+	/// A duration, with the fewest ticks allowed
+	Time { min: i32 },
+	/// An entry or a tag of the registry
+	ResourceOrTag(Identifier),
+	ResourceOrTagKey(Identifier),
+	/// An entry of the registry
+	Resource(Identifier),
+	ResourceKey(Identifier),
+	TemplateMirror,
+	TemplateRotation,
+	Heightmap,
+	LootTable,
+	LootPredicate,
+	LootModifier,
+	Uuid,
+}
+
+impl ArgumentParser {
+	/// The id of the parser in the `command_argument_type` registry
+	pub fn id(&self) -> i32 {
+		match self {
+			ArgumentParser::Bool => 0,
+			ArgumentParser::Float(_) => 1,
+			ArgumentParser::Double(_) => 2,
+			ArgumentParser::Integer(_) => 3,
+			ArgumentParser::Long(_) => 4,
+			ArgumentParser::String(_) => 5,
+			ArgumentParser::Entity { .. } => 6,
+			ArgumentParser::GameProfile => 7,
+			ArgumentParser::BlockPos => 8,
+			ArgumentParser::ColumnPos => 9,
+			ArgumentParser::Vec3 => 10,
+			ArgumentParser::Vec2 => 11,
+			ArgumentParser::BlockState => 12,
+			ArgumentParser::BlockPredicate => 13,
+			ArgumentParser::ItemStack => 14,
+			ArgumentParser::ItemPredicate => 15,
+			ArgumentParser::Color => 16,
+			ArgumentParser::Component => 17,
+			ArgumentParser::Style => 18,
+			ArgumentParser::Message => 19,
+			ArgumentParser::NbtCompound => 20,
+			ArgumentParser::NbtTag => 21,
+			ArgumentParser::NbtPath => 22,
+			ArgumentParser::Objective => 23,
+			ArgumentParser::ObjectiveCriteria => 24,
+			ArgumentParser::Operation => 25,
+			ArgumentParser::Particle => 26,
+			ArgumentParser::Angle => 27,
+			ArgumentParser::Rotation => 28,
+			ArgumentParser::ScoreboardSlot => 29,
+			ArgumentParser::ScoreHolder { .. } => 30,
+			ArgumentParser::Swizzle => 31,
+			ArgumentParser::Team => 32,
+			ArgumentParser::ItemSlot => 33,
+			ArgumentParser::ItemSlots => 34,
+			ArgumentParser::ResourceLocation => 35,
+			ArgumentParser::Function => 36,
+			ArgumentParser::EntityAnchor => 37,
+			ArgumentParser::IntRange => 38,
+			ArgumentParser::FloatRange => 39,
+			ArgumentParser::Dimension => 40,
+			ArgumentParser::Gamemode => 41,
+			ArgumentParser::Time { .. } => 42,
+			ArgumentParser::ResourceOrTag(_) => 43,
+			ArgumentParser::ResourceOrTagKey(_) => 44,
+			ArgumentParser::Resource(_) => 45,
+			ArgumentParser::ResourceKey(_) => 46,
+			ArgumentParser::TemplateMirror => 47,
+			ArgumentParser::TemplateRotation => 48,
+			ArgumentParser::Heightmap => 49,
+			ArgumentParser::LootTable => 50,
+			ArgumentParser::LootPredicate => 51,
+			ArgumentParser::LootModifier => 52,
+			ArgumentParser::Uuid => 53,
+		}
+	}
+}
+
+impl McSerialize for ArgumentParser {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		VarInt(self.id()).mc_serialize(serializer)?;
+
+		match self {
+			ArgumentParser::Float(bounds) => bounds.mc_serialize(serializer),
+			ArgumentParser::Double(bounds) => bounds.mc_serialize(serializer),
+			ArgumentParser::Integer(bounds) => bounds.mc_serialize(serializer),
+			ArgumentParser::Long(bounds) => bounds.mc_serialize(serializer),
+			ArgumentParser::String(kind) => VarInt(*kind as i32).mc_serialize(serializer),
+			ArgumentParser::Entity { single, players_only } => (*single as u8 | (*players_only as u8) << 1).mc_serialize(serializer),
+			ArgumentParser::ScoreHolder { allow_multiple } => (*allow_multiple as u8).mc_serialize(serializer),
+			ArgumentParser::Time { min } => min.mc_serialize(serializer),
+			ArgumentParser::ResourceOrTag(registry) | ArgumentParser::ResourceOrTagKey(registry)
+			| ArgumentParser::Resource(registry) | ArgumentParser::ResourceKey(registry) => registry.mc_serialize(serializer),
+			_ => Ok(()),
+		}
+	}
+}
+
+impl McDeserialize for ArgumentParser {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let parser = match VarInt::mc_deserialize(deserializer)?.0 {
+			0 => ArgumentParser::Bool,
+			1 => ArgumentParser::Float(Bounds::mc_deserialize(deserializer)?),
+			2 => ArgumentParser::Double(Bounds::mc_deserialize(deserializer)?),
+			3 => ArgumentParser::Integer(Bounds::mc_deserialize(deserializer)?),
+			4 => ArgumentParser::Long(Bounds::mc_deserialize(deserializer)?),
+			5 => ArgumentParser::String(VarInt::mc_deserialize(deserializer)?.0.try_into()?),
+			6 => {
+				let flags = u8::mc_deserialize(deserializer)?;
+				ArgumentParser::Entity { single: flags & 0x01 != 0, players_only: flags & 0x02 != 0 }
+			}
+			7 => ArgumentParser::GameProfile,
+			8 => ArgumentParser::BlockPos,
+			9 => ArgumentParser::ColumnPos,
+			10 => ArgumentParser::Vec3,
+			11 => ArgumentParser::Vec2,
+			12 => ArgumentParser::BlockState,
+			13 => ArgumentParser::BlockPredicate,
+			14 => ArgumentParser::ItemStack,
+			15 => ArgumentParser::ItemPredicate,
+			16 => ArgumentParser::Color,
+			17 => ArgumentParser::Component,
+			18 => ArgumentParser::Style,
+			19 => ArgumentParser::Message,
+			20 => ArgumentParser::NbtCompound,
+			21 => ArgumentParser::NbtTag,
+			22 => ArgumentParser::NbtPath,
+			23 => ArgumentParser::Objective,
+			24 => ArgumentParser::ObjectiveCriteria,
+			25 => ArgumentParser::Operation,
+			26 => ArgumentParser::Particle,
+			27 => ArgumentParser::Angle,
+			28 => ArgumentParser::Rotation,
+			29 => ArgumentParser::ScoreboardSlot,
+			30 => ArgumentParser::ScoreHolder { allow_multiple: u8::mc_deserialize(deserializer)? & 0x01 != 0 },
+			31 => ArgumentParser::Swizzle,
+			32 => ArgumentParser::Team,
+			33 => ArgumentParser::ItemSlot,
+			34 => ArgumentParser::ItemSlots,
+			35 => ArgumentParser::ResourceLocation,
+			36 => ArgumentParser::Function,
+			37 => ArgumentParser::EntityAnchor,
+			38 => ArgumentParser::IntRange,
+			39 => ArgumentParser::FloatRange,
+			40 => ArgumentParser::Dimension,
+			41 => ArgumentParser::Gamemode,
+			42 => ArgumentParser::Time { min: i32::mc_deserialize(deserializer)? },
+			43 => ArgumentParser::ResourceOrTag(Identifier::mc_deserialize(deserializer)?),
+			44 => ArgumentParser::ResourceOrTagKey(Identifier::mc_deserialize(deserializer)?),
+			45 => ArgumentParser::Resource(Identifier::mc_deserialize(deserializer)?),
+			46 => ArgumentParser::ResourceKey(Identifier::mc_deserialize(deserializer)?),
+			47 => ArgumentParser::TemplateMirror,
+			48 => ArgumentParser::TemplateRotation,
+			49 => ArgumentParser::Heightmap,
+			50 => ArgumentParser::LootTable,
+			51 => ArgumentParser::LootPredicate,
+			52 => ArgumentParser::LootModifier,
+			53 => ArgumentParser::Uuid,
+			id => return Err(SerializingErr::UniqueFailure(format!("Unknown argument parser {}", id))),
+		};
+
+		Ok(parser)
+	}
+}
+
+/// Where the client gets suggestions for an argument from, instead of the parser's own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SuggestionType {
+	/// Send a `CommandSuggestionsRequest` to the server
+	AskServer,
+	AllRecipes,
+	AvailableSounds,
+	SummonableEntities,
+}
+
+impl SuggestionType {
+	pub fn identifier(&self) -> &'static str {
+		match self {
+			SuggestionType::AskServer => "minecraft:ask_server",
+			SuggestionType::AllRecipes => "minecraft:all_recipes",
+			SuggestionType::AvailableSounds => "minecraft:available_sounds",
+			SuggestionType::SummonableEntities => "minecraft:summonable_entities",
+		}
+	}
+}
+
+impl McSerialize for SuggestionType {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.identifier().to_string().mc_serialize(serializer)
+	}
+}
+
+impl McDeserialize for SuggestionType {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let identifier = Identifier::mc_deserialize(deserializer)?.to_string();
+
+		[SuggestionType::AskServer, SuggestionType::AllRecipes, SuggestionType::AvailableSounds, SuggestionType::SummonableEntities]
+			.into_iter()
+			.find(|t| t.identifier() == identifier)
+			.ok_or_else(|| SerializingErr::UniqueFailure(format!("Unknown suggestion type {}", identifier)))
+	}
+}
+
+/// What a node matches
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeKind {
+	/// The start of every command, there is one in each graph
+	Root,
+	/// A fixed word
+	Literal(String),
+	Argument {
+		name: String,
+		parser: ArgumentParser,
+		suggestions: Option<SuggestionType>,
+	},
+}
+
+/// A node as it is sent in the `Commands` packet
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandNode {
+	pub kind: NodeKind,
+	/// Whether the command can be run if it ends at this node
+	pub executable: bool,
+	/// The indices of the nodes that can follow this one
+	pub children: Vec<VarInt>,
+	/// The index of the node whose children follow this one instead
+	pub redirect: Option<VarInt>,
+}
+
+impl CommandNode {
+	/// The name of a literal or an argument
+	pub fn name(&self) -> Option<&str> {
+		match &self.kind {
+			NodeKind::Root => None,
+			NodeKind::Literal(name) | NodeKind::Argument { name, .. } => Some(name),
+		}
+	}
+}
+
+impl McSerialize for CommandNode {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		let mut flags = match &self.kind {
+			NodeKind::Root => 0,
+			NodeKind::Literal(_) => 1,
+			NodeKind::Argument { suggestions, .. } => if suggestions.is_some() { 2 | HAS_SUGGESTIONS } else { 2 },
+		};
+
+		if self.executable {
+			flags |= EXECUTABLE;
+		}
+
+		if self.redirect.is_some() {
+			flags |= HAS_REDIRECT;
+		}
+
+		flags.mc_serialize(serializer)?;
+		self.children.mc_serialize(serializer)?;
+
+		if let Some(redirect) = &self.redirect {
+			redirect.mc_serialize(serializer)?;
+		}
+
+		match &self.kind {
+			NodeKind::Root => Ok(()),
+			NodeKind::Literal(name) => name.mc_serialize(serializer),
+			NodeKind::Argument { name, parser, suggestions } => {
+				name.mc_serialize(serializer)?;
+				parser.mc_serialize(serializer)?;
+
+				if let Some(suggestions) = suggestions {
+					suggestions.mc_serialize(serializer)?;
+				}
+
+				Ok(())
+			}
+		}
+	}
+}
+
+impl McDeserialize for CommandNode {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let flags = u8::mc_deserialize(deserializer)?;
+		let children = Vec::mc_deserialize(deserializer)?;
+		let redirect = if flags & HAS_REDIRECT != 0 { Some(VarInt::mc_deserialize(deserializer)?) } else { None };
+
+		let kind = match flags & NODE_TYPE_MASK {
+			0 => NodeKind::Root,
+			1 => NodeKind::Literal(String::mc_deserialize(deserializer)?),
+			2 => NodeKind::Argument {
+				name: String::mc_deserialize(deserializer)?,
+				parser: ArgumentParser::mc_deserialize(deserializer)?,
+				suggestions: if flags & HAS_SUGGESTIONS != 0 { Some(SuggestionType::mc_deserialize(deserializer)?) } else { None },
+			},
+			_ => return Err(SerializingErr::UniqueFailure("Unknown command node type 3".to_string())),
+		};
+
+		Ok(Self {
+			kind,
+			executable: flags & EXECUTABLE != 0,
+			children,
+			redirect,
+		})
+	}
+}
+
+/// A literal or argument node with the nodes that follow it, see [literal] and [argument]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandBuilder {
+	kind: NodeKind,
+	executable: bool,
+	children: Vec<CommandBuilder>,
+	redirect: Option<String>,
+}
+
+/// A node that matches a fixed word
+pub fn literal<T: Into<String>>(name: T) -> CommandBuilder {
+	CommandBuilder::new(NodeKind::Literal(name.into()))
+}
+
+/// A node that matches an argument, which the client parses with the parser
+pub fn argument<T: Into<String>>(name: T, parser: ArgumentParser) -> CommandBuilder {
+	CommandBuilder::new(NodeKind::Argument { name: name.into(), parser, suggestions: None })
+}
+
+impl CommandBuilder {
+	fn new(kind: NodeKind) -> Self {
+		Self {
+			kind,
+			executable: false,
+			children: vec![],
+			redirect: None,
+		}
+	}
+
+	/// Add a node that can follow this one
+	pub fn then(mut self, child: CommandBuilder) -> Self {
+		self.children.push(child);
+		self
+	}
+
+	/// Let the command run if it ends at this node
+	pub fn executes(mut self) -> Self {
+		self.executable = true;
+		self
+	}
+
+	/// Continue with the children of another node, given as the names of the nodes from the root separated
+	/// by spaces. An empty path goes back to the root, like `/execute run` does.
+	pub fn redirect<T: Into<String>>(mut self, path: T) -> Self {
+		self.redirect = Some(path.into());
+		self
+	}
+
+	/// Where the client gets suggestions for this argument from. This does nothing for a literal.
+	pub fn suggests(mut self, suggestions: SuggestionType) -> Self {
+		if let NodeKind::Argument { suggestions: s, .. } = &mut self.kind {
+			*s = Some(suggestions);
+		}
+
+		self
+	}
+}
+
+/// The commands of a server, see the [module docs](self)
+///
+/// ```
+/// # use sandstone::protocol::commands::tree::{argument, literal, ArgumentParser, Bounds, CommandTree};
+/// let tree = CommandTree::new()
+///     .command(literal("teleport")
+///         .then(argument("target", ArgumentParser::Entity { single: true, players_only: false })
+///             .then(argument("location", ArgumentParser::Vec3).executes())))
+///     .command(literal("tp").redirect("teleport"))
+///     .command(literal("heal")
+///         .executes()
+///         .then(argument("amount", ArgumentParser::Integer(Bounds::new(Some(1), Some(20)))).executes()));
+///
+/// let nodes = tree.nodes().unwrap();
+/// assert_eq!(nodes.len(), 7); // with the root
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CommandTree {
+	commands: Vec<CommandBuilder>,
+}
+
+impl CommandTree {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add a command, which should start with a literal
+	pub fn command(mut self, command: CommandBuilder) -> Self {
+		self.commands.push(command);
+		self
+	}
+
+	/// Flatten the tree into the nodes of the `Commands` packet, with the root first. Fails if two nodes
+	/// with the same parent have the same name, or if a redirect doesn't lead to a node.
+	pub fn nodes(&self) -> Result<Vec<CommandNode>, SerializingErr> {
+		let mut nodes = vec![CommandNode { kind: NodeKind::Root, executable: false, children: vec![], redirect: None }];
+		let mut paths = HashMap::from([(String::new(), 0)]);
+		let mut redirects = vec![];
+
+		let children = self.commands.iter()
+			.map(|command| flatten(command, "", &mut nodes, &mut paths, &mut redirects).map(|i| VarInt(i as i32)))
+			.collect::<Result<_, _>>()?;
+		nodes[0].children = children;
+
+		for (index, path) in redirects {
+			let target = paths.get(&path).ok_or_else(|| SerializingErr::UniqueFailure(format!("Redirect to unknown command node '{}'", path)))?;
+			nodes[index].redirect = Some(VarInt(*target as i32));
+		}
+
+		Ok(nodes)
+	}
+
+	/// The `Commands` packet
+	pub fn packet(&self) -> Result<Packet, SerializingErr> {
+		Ok(Packet::Commands(CommandsBody::new(self.nodes()?, VarInt(0))))
+	}
+}
+
+/// Add a node and its children depth first, and return its index
+fn flatten(builder: &CommandBuilder, parent: &str, nodes: &mut Vec<CommandNode>, paths: &mut HashMap<String, usize>, redirects: &mut Vec<(usize, String)>) -> Result<usize, SerializingErr> {
+	let name = match &builder.kind {
+		NodeKind::Root => return Err(SerializingErr::UniqueFailure("A command can't contain a root node".to_string())),
+		NodeKind::Literal(name) | NodeKind::Argument { name, .. } => name,
+	};
+
+	let path = if parent.is_empty() { name.clone() } else { format!("{} {}", parent, name) };
+	let index = nodes.len();
+
+	if paths.insert(path.clone(), index).is_some() {
+		return Err(SerializingErr::UniqueFailure(format!("Duplicate command node '{}'", path)));
+	}
+
+	nodes.push(CommandNode { kind: builder.kind.clone(), executable: builder.executable, children: vec![], redirect: None });
+
+	if let Some(redirect) = &builder.redirect {
+		redirects.push((index, redirect.clone()));
+	}
+
+	let children = builder.children.iter()
+		.map(|child| flatten(child, &path, nodes, paths, redirects).map(|i| VarInt(i as i32)))
+		.collect::<Result<_, _>>()?;
+	nodes[index].children = children;
+
+	Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::commands::tree::{argument, literal, ArgumentParser, CommandTree, NodeKind, StringKind, SuggestionType};
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	#[test]
+	fn test_command_tree() {
+		let nodes = CommandTree::new()
+			.command(literal("msg")
+				.then(argument("target", ArgumentParser::GameProfile).suggests(SuggestionType::AskServer)
+					.then(argument("message", ArgumentParser::String(StringKind::GreedyPhrase)).executes())))
+			.command(literal("w").redirect("msg"))
+			.command(literal("execute").then(literal("run").redirect("")))
+			.nodes()
+			.unwrap();
+
+		assert_eq!(nodes[0].kind, NodeKind::Root);
+		assert_eq!(nodes[0].children, vec![VarInt(1), VarInt(4), VarInt(5)]);
+		assert_eq!(nodes[2].name(), Some("target"));
+		assert_eq!(nodes[2].children, vec![VarInt(3)]);
+		assert!(nodes[3].executable);
+		assert_eq!(nodes[4].redirect, Some(VarInt(1)));
+		assert_eq!(nodes[6].redirect, Some(VarInt(0)));
+
+		assert!(CommandTree::new().command(literal("tp").redirect("teleport")).nodes().is_err());
+		assert!(CommandTree::new().command(literal("a")).command(literal("a")).nodes().is_err());
+	}
+}
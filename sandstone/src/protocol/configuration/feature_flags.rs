@@ -0,0 +1,53 @@
+//! Feature flags turn on experimental content, such as the items from an upcoming update. The server
+//! lists the enabled flags in `FeatureFlags` during configuration, and the client hides anything behind a
+//! flag that isn't listed. Vanilla always sends [VANILLA].
+
+use crate::protocol::packets::FeatureFlagsBody;
+use crate::protocol_types::datatypes::identifier::Identifier;
+
+/// The base game, which every server should enable
+pub const VANILLA: &str = "minecraft:vanilla";
+/// The experimental bundle item
+pub const BUNDLE: &str = "minecraft:bundle";
+/// The experimental villager trade changes
+pub const TRADE_REBALANCE: &str = "minecraft:trade_rebalance";
+/// The experimental redstone changes
+pub const REDSTONE_EXPERIMENTS: &str = "minecraft:redstone_experiments";
+/// The experimental minecart changes
+pub const MINECART_IMPROVEMENTS: &str = "minecraft:minecart_improvements";
+
+impl FeatureFlagsBody {
+	/// Just the [VANILLA] flag
+	pub fn vanilla() -> Self {
+		Self::new(vec![VANILLA.parse().expect("the vanilla flag is a valid identifier")])
+	}
+
+	/// Enable another flag, if it isn't already
+	pub fn with_flag(mut self, flag: Identifier) -> Self {
+		if !self.flags.contains(&flag) {
+			self.flags.push(flag);
+		}
+
+		self
+	}
+
+	pub fn is_enabled(&self, flag: &Identifier) -> bool {
+		self.flags.contains(flag)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::configuration::feature_flags::{BUNDLE, VANILLA};
+	use crate::protocol::packets::FeatureFlagsBody;
+
+	#[test]
+	fn test_feature_flags() {
+		let flags = FeatureFlagsBody::vanilla()
+			.with_flag(BUNDLE.parse().unwrap())
+			.with_flag(VANILLA.parse().unwrap());
+
+		assert_eq!(flags.flags.len(), 2);
+		assert!(flags.is_enabled(&BUNDLE.parse().unwrap()));
+	}
+}
@@ -0,0 +1,148 @@
+//! Since 1.20.5, the server starts configuration by listing the data packs that its registry entries come
+//! from in `ClientboundKnownPacks`, and the client answers with the ones it has too. Entries from a shared
+//! pack are sent without their data, since the client already has it, which saves sending the bulk of the
+//! vanilla registries to every player.
+//!
+//! [negotiate] does the exchange, and [RegistryDataBuilder::send_for](crate::protocol::configuration::registry::RegistryDataBuilder::send_for)
+//! leaves out what the client knows.
+
+use crate::network::client::CraftClient;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::{ClientboundKnownPacksBody, Packet};
+use crate::protocol::packets::packet_component::KnownPack;
+use crate::protocol::packets::packet_definer::PacketState;
+
+/// The namespace of the vanilla data packs
+pub const CORE_PACK_NAMESPACE: &str = "minecraft";
+/// The vanilla data pack that every registry entry comes from
+pub const CORE_PACK_ID: &str = "core";
+/// The version of the vanilla data pack, which matches the version of the packets in this library
+pub const CORE_PACK_VERSION: &str = "1.21";
+
+/// The most packs that the vanilla client lists in its answer
+pub const MAX_KNOWN_PACKS: usize = 64;
+
+impl KnownPack {
+	pub fn new<N: Into<String>, I: Into<String>, V: Into<String>>(namespace: N, id: I, version: V) -> Self {
+		Self {
+			namespace: namespace.into(),
+			id: id.into(),
+			version: version.into(),
+		}
+	}
+
+	/// The vanilla `minecraft:core` pack
+	pub fn core() -> Self {
+		Self::new(CORE_PACK_NAMESPACE, CORE_PACK_ID, CORE_PACK_VERSION)
+	}
+}
+
+/// The outcome of [negotiate]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KnownPackNegotiation {
+	/// The packs that both sides know, so entries from them can be sent without their data
+	pub shared: Vec<KnownPack>,
+	/// Other packets that the client sent before its answer, such as `ClientInformation`, which still need
+	/// to be handled
+	pub deferred: Vec<Packet>,
+}
+
+/// The packs that the server offered and the client reported, in the server's order. A pack has to match
+/// in namespace, id and version, since its data may change between versions.
+pub fn shared_packs(offered: &[KnownPack], reported: &[KnownPack]) -> Vec<KnownPack> {
+	offered.iter()
+		.filter(|pack| reported.contains(pack))
+		.cloned()
+		.collect()
+}
+
+/// Offer the packs to a client in the `CONFIGURATION` state and wait for its answer. Clients from before
+/// 1.20.5 don't take part, so nothing is sent to them and no packs are shared.
+pub async fn negotiate(client: &mut CraftClient, offered: Vec<KnownPack>) -> Result<KnownPackNegotiation, NetworkError> {
+	if client.packet_state != PacketState::CONFIGURATION {
+		return Err(NetworkError::InvalidPacketState);
+	}
+
+	if client.get_client_version().is_some_and(|v| !v.has_known_packs()) {
+		return Ok(KnownPackNegotiation::default());
+	}
+
+	client.send_packet(Packet::ClientboundKnownPacks(ClientboundKnownPacksBody::new(offered.clone()))).await?;
+
+	let mut deferred = vec![];
+
+	loop {
+		match client.receive_packet().await? {
+			Packet::ServerboundKnownPacks(body) => {
+				if body.packs.len() > MAX_KNOWN_PACKS {
+					return Err(NetworkError::ExpectedDifferentPacket(format!("Client reported {} known packs, the limit is {}", body.packs.len(), MAX_KNOWN_PACKS)));
+				}
+
+				return Ok(KnownPackNegotiation {
+					shared: shared_packs(&offered, &body.packs),
+					deferred,
+				});
+			}
+			packet => deferred.push(packet),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::network::server::server_testing::configured_pair;
+	use crate::protocol::configuration::known_packs::{negotiate, shared_packs};
+	use crate::protocol::configuration::registry::{DIMENSION_TYPE, RegistryDataBuilder};
+	use crate::protocol::packets::{ClientInformationBody, Packet, ServerboundKnownPacksBody};
+	use crate::protocol::packets::packet_component::KnownPack;
+	use crate::protocol_types::datatypes::nbt::nbt::NbtCompound;
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	#[test]
+	fn test_shared_packs() {
+		let other = KnownPack::new("example", "pack", "1");
+		let old_core = KnownPack::new("minecraft", "core", "1.20.6");
+
+		assert_eq!(shared_packs(&[KnownPack::core(), other.clone()], &[other.clone(), old_core]), vec![other]);
+	}
+
+	#[test]
+	fn test_build_for_known_packs() {
+		let registry = DIMENSION_TYPE.parse().unwrap();
+		let builder = RegistryDataBuilder::new()
+			.pack_entry(DIMENSION_TYPE.parse().unwrap(), "minecraft:overworld".parse().unwrap(), NbtCompound::new::<String>(None), KnownPack::core())
+			.entry(DIMENSION_TYPE.parse().unwrap(), "example:flat".parse().unwrap(), NbtCompound::new::<String>(None));
+
+		assert_eq!(builder.packs(), vec![KnownPack::core()]);
+		assert_eq!(builder.pack_of(&registry, &"example:flat".parse().unwrap()), None);
+
+		let entries = |packets: Vec<Packet>| match packets.into_iter().next() {
+			Some(Packet::RegistryData(body)) => body.entries.into_iter().map(|e| e.data.is_some()).collect::<Vec<_>>(),
+			p => panic!("Expected registry data, got {:?}", p),
+		};
+
+		assert_eq!(entries(builder.build()), vec![true, true]);
+		assert_eq!(entries(builder.build_for(&[KnownPack::core()])), vec![false, true]);
+	}
+
+	#[tokio::test]
+	async fn test_negotiate() {
+		let (mut client, mut connector) = configured_pair().await;
+
+		let server = tokio::spawn(async move {
+			let negotiation = negotiate(&mut client, vec![KnownPack::core(), KnownPack::new("example", "pack", "1")]).await.unwrap();
+			assert_eq!(negotiation.shared, vec![KnownPack::core()]);
+			assert!(matches!(negotiation.deferred.as_slice(), [Packet::ClientInformation(_)]));
+		});
+
+		connector.send_packet(Packet::ClientInformation(ClientInformationBody::new("en_us".to_string(), 8, VarInt(0), true, 0x7F, VarInt(1), false, true))).await.unwrap();
+
+		match connector.receive_packet().await.unwrap() {
+			Packet::ClientboundKnownPacks(body) => assert_eq!(body.packs.len(), 2),
+			p => panic!("Expected known packs, got {:?}", p),
+		}
+		connector.send_packet(Packet::ServerboundKnownPacks(ServerboundKnownPacksBody::new(vec![KnownPack::core()]))).await.unwrap();
+
+		server.await.unwrap();
+	}
+}
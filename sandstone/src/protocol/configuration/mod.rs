@@ -0,0 +1,8 @@
+//! Helpers for the configuration state, added in 1.20.2, where the server sends the client everything it
+//! needs to know before joining the world: the contents of the registries, their tags, the enabled
+//! feature flags and which data packs both sides share.
+
+pub mod feature_flags;
+pub mod known_packs;
+pub mod registry;
+pub mod tags;
@@ -0,0 +1,290 @@
+//! Builds the `RegistryData` packets sent during configuration. Every registry that the client syncs from
+//! the server, such as dimension types and biomes, is sent in its own packet, and an entry's numeric id
+//! is its position in that packet.
+//!
+//! With the `vanilla-registries` feature, [RegistryDataBuilder::vanilla] starts from the smallest set of
+//! vanilla entries that a 1.21 client needs to join a world. Those entries come from the vanilla `core`
+//! data pack, so their data can be left out for clients that report knowing it, see
+//! [known_packs](crate::protocol::configuration::known_packs).
+
+use std::collections::HashMap;
+
+use crate::network::client::CraftClient;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::{Packet, RegistryDataBody};
+use crate::protocol::packets::packet_component::{KnownPack, RegistryEntry};
+use crate::protocol::packets::packet_definer::PacketState;
+use crate::protocol_types::datatypes::identifier::Identifier;
+use crate::protocol_types::datatypes::nbt::nbt::NbtCompound;
+
+pub const DIMENSION_TYPE: &str = "minecraft:dimension_type";
+pub const BIOME: &str = "minecraft:worldgen/biome";
+pub const DAMAGE_TYPE: &str = "minecraft:damage_type";
+pub const CHAT_TYPE: &str = "minecraft:chat_type";
+pub const WOLF_VARIANT: &str = "minecraft:wolf_variant";
+pub const PAINTING_VARIANT: &str = "minecraft:painting_variant";
+
+/// The registries and their entries, in the order they are sent
+///
+/// ```
+/// # use sandstone::protocol::configuration::registry::{RegistryDataBuilder, DIMENSION_TYPE};
+/// # use sandstone::protocol_types::datatypes::nbt::nbt::NbtCompound;
+/// let registries = RegistryDataBuilder::new()
+///     .entry(DIMENSION_TYPE.parse().unwrap(), "example:flat".parse().unwrap(), NbtCompound::new::<String>(None));
+///
+/// assert_eq!(registries.id_of(&DIMENSION_TYPE.parse().unwrap(), &"example:flat".parse().unwrap()), Some(0));
+/// assert_eq!(registries.build().len(), 1);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegistryDataBuilder {
+	registries: Vec<(Identifier, Vec<RegistryEntry>)>,
+	/// The data pack that each entry's data came from, keyed by registry and entry
+	packs: HashMap<(Identifier, Identifier), KnownPack>,
+}
+
+impl RegistryDataBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The vanilla dimension types, damage types and chat types, the plains and void biomes, and a wolf and
+	/// painting variant. The data matches 1.21, the version of the packets in this library.
+	#[cfg(feature = "vanilla-registries")]
+	pub fn vanilla() -> Self {
+		let mut builder = Self::new();
+
+		for (registry, snbt) in vanilla::REGISTRIES {
+			builder.load_from_pack(registry, snbt, KnownPack::core()).expect("the bundled registry data is valid");
+		}
+
+		builder
+	}
+
+	/// Add an entry, replacing any entry with the same id
+	pub fn entry(mut self, registry: Identifier, id: Identifier, data: NbtCompound) -> Self {
+		self.insert(registry, RegistryEntry { id, data: Some(data) });
+		self
+	}
+
+	/// Add an entry whose data also ships in a data pack, so that it is only sent to clients that don't
+	/// know the pack
+	pub fn pack_entry(mut self, registry: Identifier, id: Identifier, data: NbtCompound, pack: KnownPack) -> Self {
+		self.insert_from_pack(registry, RegistryEntry { id, data: Some(data) }, Some(pack));
+		self
+	}
+
+	/// Add an entry without its data, which the client takes from a data pack that both sides know. See
+	/// [KnownPack](crate::protocol::packets::packet_component::KnownPack).
+	pub fn known_entry(mut self, registry: Identifier, id: Identifier) -> Self {
+		self.insert(registry, RegistryEntry { id, data: None });
+		self
+	}
+
+	/// Add an entry, replacing any entry with the same id
+	pub fn insert(&mut self, registry: Identifier, entry: RegistryEntry) {
+		self.insert_from_pack(registry, entry, None);
+	}
+
+	/// Add an entry that came from a data pack, or from no pack if `pack` is `None`, replacing any entry
+	/// with the same id
+	pub fn insert_from_pack(&mut self, registry: Identifier, entry: RegistryEntry, pack: Option<KnownPack>) {
+		let key = (registry.clone(), entry.id.clone());
+		match pack {
+			Some(pack) => self.packs.insert(key, pack),
+			None => self.packs.remove(&key),
+		};
+
+		let entries = match self.registries.iter().position(|(r, _)| *r == registry) {
+			Some(i) => &mut self.registries[i].1,
+			None => {
+				self.registries.push((registry, vec![]));
+				&mut self.registries.last_mut().unwrap().1
+			}
+		};
+
+		match entries.iter_mut().find(|e| e.id == entry.id) {
+			Some(existing) => *existing = entry,
+			None => entries.push(entry),
+		}
+	}
+
+	/// Remove an entry, which changes the ids of the entries after it
+	pub fn remove(&mut self, registry: &Identifier, id: &Identifier) -> Option<RegistryEntry> {
+		let entries = self.entries_mut(registry)?;
+		let index = entries.iter().position(|e| e.id == *id)?;
+		let entry = entries.remove(index);
+
+		self.packs.remove(&(registry.clone(), id.clone()));
+		Some(entry)
+	}
+
+	/// Add the entries from an SNBT list of `{name: "...", element: {...}}` compounds, the same layout as the
+	/// registry codec sent before 1.20.2
+	pub fn load(&mut self, registry: &str, snbt: &str) -> Result<(), NetworkError> {
+		self.load_entries(registry, snbt, None)
+	}
+
+	/// Like [RegistryDataBuilder::load], for entries that come from a data pack
+	pub fn load_from_pack(&mut self, registry: &str, snbt: &str, pack: KnownPack) -> Result<(), NetworkError> {
+		self.load_entries(registry, snbt, Some(pack))
+	}
+
+	fn load_entries(&mut self, registry: &str, snbt: &str, pack: Option<KnownPack>) -> Result<(), NetworkError> {
+		let registry: Identifier = registry.parse()?;
+		let list = NbtCompound::from_snbt(&format!("{{entries:{}}}", snbt))
+			.and_then(|c| c.get_list("entries").cloned())
+			.map_err(|e| NetworkError::ExpectedDifferentPacket(format!("Invalid registry data for {}: {}", registry, e)))?;
+
+		for tag in list.iter() {
+			let compound = NbtCompound::from(tag.clone());
+			let name = compound.get_string("name")
+				.map_err(|e| NetworkError::ExpectedDifferentPacket(format!("Invalid registry entry in {}: {}", registry, e)))?;
+			let data = compound.get_compound("element")
+				.map_err(|e| NetworkError::ExpectedDifferentPacket(format!("Invalid registry entry {} in {}: {}", name, registry, e)))?;
+
+			self.insert_from_pack(registry.clone(), RegistryEntry { id: name.parse()?, data: Some(data.clone()) }, pack.clone());
+		}
+
+		Ok(())
+	}
+
+	pub fn entries(&self, registry: &Identifier) -> Option<&[RegistryEntry]> {
+		self.registries.iter().find(|(r, _)| r == registry).map(|(_, e)| e.as_slice())
+	}
+
+	fn entries_mut(&mut self, registry: &Identifier) -> Option<&mut Vec<RegistryEntry>> {
+		self.registries.iter_mut().find(|(r, _)| r == registry).map(|(_, e)| e)
+	}
+
+	/// The registries in the order they are sent
+	pub fn registries(&self) -> impl Iterator<Item = &Identifier> {
+		self.registries.iter().map(|(r, _)| r)
+	}
+
+	/// The data pack that an entry came from, if any
+	pub fn pack_of(&self, registry: &Identifier, id: &Identifier) -> Option<&KnownPack> {
+		self.packs.get(&(registry.clone(), id.clone()))
+	}
+
+	/// Every data pack that an entry came from, which is what the server offers in `ClientboundKnownPacks`
+	pub fn packs(&self) -> Vec<KnownPack> {
+		let mut packs: Vec<KnownPack> = vec![];
+
+		for (registry, entries) in &self.registries {
+			for entry in entries {
+				if let Some(pack) = self.pack_of(registry, &entry.id) {
+					if !packs.contains(pack) {
+						packs.push(pack.clone());
+					}
+				}
+			}
+		}
+
+		packs
+	}
+
+	/// The numeric id of an entry, which is how other packets refer to it, such as the dimension type in
+	/// `LoginPlay` or the entries of a tag
+	pub fn id_of(&self, registry: &Identifier, id: &Identifier) -> Option<i32> {
+		self.entries(registry)?.iter().position(|e| e.id == *id).map(|i| i as i32)
+	}
+
+	/// One `RegistryData` packet for each registry
+	pub fn build(&self) -> Vec<Packet> {
+		self.build_for(&[])
+	}
+
+	/// One `RegistryData` packet for each registry, leaving out the data of entries from the packs that the
+	/// client knows. The ids of the entries don't change, the client looks up their data by name.
+	pub fn build_for(&self, known: &[KnownPack]) -> Vec<Packet> {
+		self.registries.iter()
+			.map(|(registry, entries)| {
+				let entries = entries.iter()
+					.map(|entry| match self.pack_of(registry, &entry.id) {
+						Some(pack) if known.contains(pack) => RegistryEntry { id: entry.id.clone(), data: None },
+						_ => entry.clone(),
+					})
+					.collect();
+
+				Packet::RegistryData(RegistryDataBody::new(registry.clone(), entries))
+			})
+			.collect()
+	}
+
+	/// Send every registry to a client in the `CONFIGURATION` state
+	pub async fn send(&self, client: &mut CraftClient) -> Result<(), NetworkError> {
+		self.send_for(client, &[]).await
+	}
+
+	/// Send every registry to a client in the `CONFIGURATION` state, leaving out what it already knows from
+	/// the `known` packs, which usually come from [negotiate](crate::protocol::configuration::known_packs::negotiate)
+	pub async fn send_for(&self, client: &mut CraftClient, known: &[KnownPack]) -> Result<(), NetworkError> {
+		if client.packet_state != PacketState::CONFIGURATION {
+			return Err(NetworkError::InvalidPacketState);
+		}
+
+		for packet in self.build_for(known) {
+			client.queue_packet(packet)?;
+		}
+
+		client.flush().await
+	}
+}
+
+#[cfg(feature = "vanilla-registries")]
+mod vanilla {
+	use crate::protocol::configuration::registry::{BIOME, CHAT_TYPE, DAMAGE_TYPE, DIMENSION_TYPE, PAINTING_VARIANT, WOLF_VARIANT};
+
+	pub(super) const REGISTRIES: [(&str, &str); 6] = [
+		(DIMENSION_TYPE, include_str!("vanilla/dimension_type.snbt")),
+		(BIOME, include_str!("vanilla/worldgen_biome.snbt")),
+		(DAMAGE_TYPE, include_str!("vanilla/damage_type.snbt")),
+		(CHAT_TYPE, include_str!("vanilla/chat_type.snbt")),
+		(WOLF_VARIANT, include_str!("vanilla/wolf_variant.snbt")),
+		(PAINTING_VARIANT, include_str!("vanilla/painting_variant.snbt")),
+	];
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::configuration::registry::{DIMENSION_TYPE, RegistryDataBuilder};
+	use crate::protocol::packets::Packet;
+	use crate::protocol::serialization::{McDeserializer, McSerialize, McSerializer, StateBasedDeserializer};
+	use crate::protocol::packets::packet_definer::PacketDirection;
+	use crate::protocol_types::datatypes::identifier::Identifier;
+	use crate::protocol_types::datatypes::nbt::nbt::NbtCompound;
+
+	#[test]
+	fn test_registry_builder() {
+		let registry: Identifier = DIMENSION_TYPE.parse().unwrap();
+		let mut builder = RegistryDataBuilder::new()
+			.entry(registry.clone(), "test:a".parse().unwrap(), NbtCompound::new::<String>(None))
+			.known_entry(registry.clone(), "test:b".parse().unwrap())
+			.entry(registry.clone(), "test:a".parse().unwrap(), NbtCompound::new::<String>(None));
+
+		assert_eq!(builder.entries(&registry).unwrap().len(), 2);
+		assert_eq!(builder.id_of(&registry, &"test:b".parse().unwrap()), Some(1));
+
+		builder.remove(&registry, &"test:a".parse().unwrap()).unwrap();
+		assert_eq!(builder.id_of(&registry, &"test:b".parse().unwrap()), Some(0));
+	}
+
+	#[cfg(feature = "vanilla-registries")]
+	#[test]
+	fn test_vanilla_registries() {
+		let builder = RegistryDataBuilder::vanilla();
+
+		assert_eq!(builder.registries().count(), 6);
+		assert_eq!(builder.id_of(&DIMENSION_TYPE.parse().unwrap(), &"minecraft:overworld".parse().unwrap()), Some(0));
+		assert!(builder.id_of(&"minecraft:damage_type".parse().unwrap(), &"minecraft:generic_kill".parse().unwrap()).is_some());
+
+		for packet in builder.build() {
+			let mut serializer = McSerializer::new();
+			packet.mc_serialize(&mut serializer).unwrap();
+
+			let mut deserializer = McDeserializer::new(&serializer.output);
+			let out = Packet::deserialize_state(&mut deserializer, packet.state(), PacketDirection::CLIENT).unwrap();
+			assert_eq!(out, packet);
+		}
+	}
+}
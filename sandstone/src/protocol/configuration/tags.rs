@@ -0,0 +1,191 @@
+//! Builds the `UpdateTags` packet. A tag is a named group of registry entries, such as the damage types
+//! that bypass armor, and the client refers to them by their numeric ids. Modern clients disconnect if a
+//! tag that they rely on is missing, so every tag that vanilla sends for a synced registry should be sent
+//! too, even if it is empty.
+//!
+//! Entries of the registries sent in `RegistryData` are given by name and looked up in a
+//! [RegistryDataBuilder]. The ids of the built-in registries, such as blocks and items, are fixed by the
+//! game and are given directly.
+
+use crate::network::network_error::NetworkError;
+use crate::protocol::configuration::registry::RegistryDataBuilder;
+use crate::protocol::packets::{Packet, UpdateTagsBody};
+use crate::protocol::packets::packet_component::{RegistryTagsSpec, TagSpec};
+use crate::protocol_types::datatypes::identifier::Identifier;
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TagEntry {
+	Name(Identifier),
+	Id(i32),
+}
+
+/// The tags of one registry and their entries
+type RegistryTags = Vec<(Identifier, Vec<TagEntry>)>;
+
+/// The tags of each registry, in the order they are sent
+///
+/// ```
+/// # use sandstone::protocol::configuration::registry::{RegistryDataBuilder, DAMAGE_TYPE};
+/// # use sandstone::protocol::configuration::tags::TagsBuilder;
+/// # use sandstone::protocol_types::datatypes::nbt::nbt::NbtCompound;
+/// let registries = RegistryDataBuilder::new()
+///     .entry(DAMAGE_TYPE.parse().unwrap(), "minecraft:in_fire".parse().unwrap(), NbtCompound::new::<String>(None));
+///
+/// let tags = TagsBuilder::new()
+///     .tag(DAMAGE_TYPE.parse().unwrap(), "minecraft:is_fire".parse().unwrap(), vec!["minecraft:in_fire".parse().unwrap()]);
+/// assert!(tags.build(&registries).is_ok());
+///
+/// // not in the registry
+/// let tags = tags.tag(DAMAGE_TYPE.parse().unwrap(), "minecraft:is_fire".parse().unwrap(), vec!["minecraft:lava".parse().unwrap()]);
+/// assert!(tags.build(&registries).is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagsBuilder {
+	registries: Vec<(Identifier, RegistryTags)>,
+}
+
+impl TagsBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Add entries to a tag by name, creating the tag if needed. The names are looked up when the tags are
+	/// built.
+	pub fn tag(mut self, registry: Identifier, name: Identifier, entries: Vec<Identifier>) -> Self {
+		self.tag_mut(registry, name).extend(entries.into_iter().map(TagEntry::Name));
+		self
+	}
+
+	/// Add entries to a tag by their numeric ids, for the built-in registries that aren't sent in
+	/// `RegistryData`
+	pub fn tag_ids(mut self, registry: Identifier, name: Identifier, ids: Vec<i32>) -> Self {
+		self.tag_mut(registry, name).extend(ids.into_iter().map(TagEntry::Id));
+		self
+	}
+
+	/// Add a tag without any entries, for tags the client expects to exist
+	pub fn empty_tag(mut self, registry: Identifier, name: Identifier) -> Self {
+		self.tag_mut(registry, name);
+		self
+	}
+
+	/// Add a single entry to a tag by name
+	pub fn add(&mut self, registry: Identifier, name: Identifier, entry: Identifier) {
+		self.tag_mut(registry, name).push(TagEntry::Name(entry));
+	}
+
+	/// Remove a tag, returning whether it existed
+	pub fn remove(&mut self, registry: &Identifier, name: &Identifier) -> bool {
+		let Some((_, tags)) = self.registries.iter_mut().find(|(r, _)| r == registry) else {
+			return false;
+		};
+
+		let before = tags.len();
+		tags.retain(|(n, _)| n != name);
+		tags.len() != before
+	}
+
+	/// The tags of a registry
+	pub fn tags(&self, registry: &Identifier) -> impl Iterator<Item = &Identifier> {
+		self.registries.iter()
+			.find(|(r, _)| r == registry)
+			.into_iter()
+			.flat_map(|(_, tags)| tags.iter().map(|(name, _)| name))
+	}
+
+	fn tag_mut(&mut self, registry: Identifier, name: Identifier) -> &mut Vec<TagEntry> {
+		let tags = match self.registries.iter().position(|(r, _)| *r == registry) {
+			Some(i) => &mut self.registries[i].1,
+			None => {
+				self.registries.push((registry, vec![]));
+				&mut self.registries.last_mut().unwrap().1
+			}
+		};
+
+		let index = match tags.iter().position(|(n, _)| *n == name) {
+			Some(i) => i,
+			None => {
+				tags.push((name, vec![]));
+				tags.len() - 1
+			}
+		};
+
+		&mut tags[index].1
+	}
+
+	/// Build the packet, turning names into the ids they have in `registries`. A name that isn't in the
+	/// registry is an [NetworkError::UnknownRegistryEntry], since the client would reject the tag.
+	pub fn build(&self, registries: &RegistryDataBuilder) -> Result<UpdateTagsBody, NetworkError> {
+		let mut out = Vec::with_capacity(self.registries.len());
+
+		for (registry, tags) in &self.registries {
+			let mut specs = Vec::with_capacity(tags.len());
+
+			for (name, entries) in tags {
+				let mut ids = Vec::with_capacity(entries.len());
+
+				for entry in entries {
+					let id = match entry {
+						TagEntry::Id(id) => *id,
+						TagEntry::Name(entry) => registries.id_of(registry, entry)
+							.ok_or_else(|| NetworkError::UnknownRegistryEntry { registry: registry.to_string(), entry: entry.to_string() })?,
+					};
+
+					if !ids.contains(&VarInt(id)) {
+						ids.push(VarInt(id));
+					}
+				}
+
+				specs.push(TagSpec { name: name.clone(), entries: ids });
+			}
+
+			out.push(RegistryTagsSpec { registry: registry.clone(), tags: specs });
+		}
+
+		Ok(UpdateTagsBody::new(out))
+	}
+
+	/// Build the `UpdateTags` packet, see [TagsBuilder::build]
+	pub fn build_packet(&self, registries: &RegistryDataBuilder) -> Result<Packet, NetworkError> {
+		Ok(Packet::UpdateTags(self.build(registries)?))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::network::network_error::NetworkError;
+	use crate::protocol::configuration::registry::{DAMAGE_TYPE, RegistryDataBuilder};
+	use crate::protocol::configuration::tags::TagsBuilder;
+	use crate::protocol_types::datatypes::identifier::Identifier;
+	use crate::protocol_types::datatypes::nbt::nbt::NbtCompound;
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	#[test]
+	fn test_tags_builder() {
+		let damage: Identifier = DAMAGE_TYPE.parse().unwrap();
+		let block: Identifier = "minecraft:block".parse().unwrap();
+
+		let registries = RegistryDataBuilder::new()
+			.entry(damage.clone(), "minecraft:in_fire".parse().unwrap(), NbtCompound::new::<String>(None))
+			.entry(damage.clone(), "minecraft:lava".parse().unwrap(), NbtCompound::new::<String>(None));
+
+		let mut builder = TagsBuilder::new()
+			.tag(damage.clone(), "minecraft:is_fire".parse().unwrap(), vec!["minecraft:lava".parse().unwrap(), "minecraft:in_fire".parse().unwrap()])
+			.tag_ids(block.clone(), "minecraft:logs".parse().unwrap(), vec![46, 47])
+			.empty_tag(damage.clone(), "minecraft:is_drowning".parse().unwrap());
+		builder.add(damage.clone(), "minecraft:is_fire".parse().unwrap(), "minecraft:lava".parse().unwrap());
+
+		let body = builder.build(&registries).unwrap();
+		assert_eq!(body.registries.len(), 2);
+		assert_eq!(body.registries[0].tags[0].entries, vec![VarInt(1), VarInt(0)]);
+		assert!(body.registries[0].tags[1].entries.is_empty());
+		assert_eq!(body.registries[1].tags[0].entries, vec![VarInt(46), VarInt(47)]);
+
+		builder.add(damage.clone(), "minecraft:is_fire".parse().unwrap(), "minecraft:drown".parse().unwrap());
+		assert_eq!(builder.build(&registries), Err(NetworkError::UnknownRegistryEntry { registry: DAMAGE_TYPE.to_string(), entry: "minecraft:drown".to_string() }));
+
+		assert!(builder.remove(&damage, &"minecraft:is_fire".parse().unwrap()));
+		assert_eq!(builder.tags(&damage).count(), 1);
+	}
+}
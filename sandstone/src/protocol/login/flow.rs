@@ -0,0 +1,305 @@
+//! Drives the whole login sequence, so that login handlers only decide who may join instead of having to
+//! get the order of the packets right. The sequence is:
+//!
+//! 1. The client sends `LoginStart`
+//! 2. In online mode, the server sends an `EncryptionRequest`, enables encryption once the client answers
+//!    and checks the player with Mojang. Behind a Velocity proxy, the player info is requested from the
+//!    proxy instead.
+//! 3. If compression is enabled, the server sends `SetCompression`
+//! 4. The server sends `LoginSuccess` and the client answers with `LoginAcknowledged`, moving the
+//!    connection to the `CONFIGURATION` state
+//!
+//! See [here](https://wiki.vg/Protocol_FAQ#What.27s_the_normal_login_sequence_for_a_client.3F) for more.
+
+use std::future::Future;
+#[cfg(feature = "mojang")]
+use std::sync::Arc;
+
+use log::debug;
+
+use crate::network::client::CraftClient;
+#[cfg(feature = "mojang")]
+use crate::network::encryption::{generate_verify_token, ServerKey};
+use crate::network::network_error::NetworkError;
+use crate::network::server::builder::ServerConfig;
+use crate::protocol::login::velocity::VelocityForwarding;
+use crate::protocol::packets::{LoginStartBody, LoginSuccessBody, Packet};
+use crate::protocol::packets::packet_component::LoginSuccessSpec;
+use crate::protocol::packets::packet_definer::PacketState;
+use crate::protocol_types::datatypes::chat::TextComponent;
+use crate::protocol_types::datatypes::game_profile::GameProfile;
+#[cfg(feature = "mojang")]
+use crate::util::mojang::authenticate_player;
+
+/// How a [LoginFlow] finds out who the player is
+#[derive(Debug, Clone)]
+pub enum LoginAuth {
+	/// Trust the username that the client sends, and give the player the same [offline UUID](crate::protocol::login::offline_uuid)
+	/// as vanilla. Anyone can join with any name, so this should only be used on a LAN or behind a proxy
+	/// that authenticates players itself.
+	Offline,
+	/// Enable encryption and check with Mojang's session server that the player owns the account
+	#[cfg(feature = "mojang")]
+	Online(Arc<ServerKey>),
+	/// Take the player's profile from a Velocity proxy, see [VelocityForwarding]
+	Velocity(VelocityForwarding),
+}
+
+/// Decides who is allowed to join during a [LoginFlow]. Every method returns `Some(reason)` to turn the
+/// player away, which disconnects them with that reason. Both allow everyone by default.
+pub trait LoginHooks: Send + Sync {
+	/// Called with `LoginStart`, before anything is sent back, such as to turn players away when the server
+	/// is full. The username hasn't been verified at this point.
+	fn check_login(&self, _client: &mut CraftClient, _login: &LoginStartBody) -> impl Future<Output = Result<Option<TextComponent>, NetworkError>> + Send {
+		async { Ok(None) }
+	}
+
+	/// Called with the player's verified profile right before `LoginSuccess`, such as to check a whitelist
+	/// or bans. Changes to the profile are sent to the client.
+	fn check_profile(&self, _client: &mut CraftClient, _profile: &mut GameProfile) -> impl Future<Output = Result<Option<TextComponent>, NetworkError>> + Send {
+		async { Ok(None) }
+	}
+}
+
+/// Lets everyone join
+impl LoginHooks for () {}
+
+/// Logs a player in and leaves the connection in the `CONFIGURATION` state, or in `PLAY` for clients from
+/// before 1.20.2.
+///
+/// ```no_run
+/// # use sandstone::network::client::CraftClient;
+/// # use sandstone::network::network_error::NetworkError;
+/// # use sandstone::protocol::login::flow::LoginFlow;
+/// # async fn login(client: &mut CraftClient) -> Result<(), NetworkError> {
+/// let profile = LoginFlow::offline()
+///     .compression(Some(256))
+///     .run(client).await?;
+///
+/// println!("{} joined", profile.name);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LoginFlow {
+	auth: LoginAuth,
+	/// `None` uses the connection's [login compression](CraftClient::login_compression)
+	compression_threshold: Option<Option<i32>>,
+}
+
+impl LoginFlow {
+	/// A flow that enables the connection's [login compression](CraftClient::login_compression), which
+	/// comes from the server settings for connections accepted by a server
+	pub fn new(auth: LoginAuth) -> Self {
+		Self {
+			auth,
+			compression_threshold: None,
+		}
+	}
+
+	/// See [LoginAuth::Offline]
+	pub fn offline() -> Self {
+		Self::new(LoginAuth::Offline)
+	}
+
+	/// A flow that follows the server settings. Online mode generates a new key for the server, so this
+	/// should be called once rather than for every player. Online mode needs the `mojang` feature.
+	pub fn from_config(config: &ServerConfig) -> Result<Self, NetworkError> {
+		let auth = match config.online_mode {
+			#[cfg(feature = "mojang")]
+			true => LoginAuth::Online(Arc::new(ServerKey::generate()?)),
+			#[cfg(not(feature = "mojang"))]
+			true => return Err(NetworkError::EncryptionError("Online mode needs the mojang feature".to_string())),
+			false => LoginAuth::Offline,
+		};
+
+		Ok(Self::new(auth).compression(config.compression_threshold))
+	}
+
+	/// Packets at least this long are compressed once the player has logged in, instead of using the
+	/// connection's setting. `None` or a negative threshold leaves compression off.
+	pub fn compression(mut self, threshold: Option<i32>) -> Self {
+		self.compression_threshold = Some(threshold.filter(|t| *t >= 0));
+		self
+	}
+
+	pub fn auth(&self) -> &LoginAuth {
+		&self.auth
+	}
+
+	/// Log in a client that has just finished the handshake, letting everyone join
+	pub async fn run(&self, client: &mut CraftClient) -> Result<GameProfile, NetworkError> {
+		self.run_with(client, &()).await
+	}
+
+	/// Log in a client that has just finished the handshake, asking `hooks` who may join
+	pub async fn run_with<H: LoginHooks>(&self, client: &mut CraftClient, hooks: &H) -> Result<GameProfile, NetworkError> {
+		if client.packet_state != PacketState::LOGIN {
+			return Err(NetworkError::InvalidPacketState);
+		}
+
+		let login = match client.receive_packet().await? {
+			Packet::LoginStart(login) => login,
+			_ => return Err(NetworkError::ExpectedDifferentPacket("Expected login start".to_string())),
+		};
+
+		self.resume(client, login, hooks).await
+	}
+
+	/// Finish logging in a client whose `LoginStart` has already been read, such as in
+	/// [ServerHandler::on_login_start](crate::network::client::client_handlers::ServerHandler::on_login_start)
+	pub async fn resume<H: LoginHooks>(&self, client: &mut CraftClient, login: LoginStartBody, hooks: &H) -> Result<GameProfile, NetworkError> {
+		if client.packet_state != PacketState::LOGIN {
+			return Err(NetworkError::InvalidPacketState);
+		}
+
+		debug!("Logging in {} as {}", client, login.username);
+
+		if let Some(reason) = hooks.check_login(client, &login).await? {
+			return Err(reject(client, reason).await);
+		}
+
+		let mut profile = match &self.auth {
+			LoginAuth::Offline => GameProfile::offline(login.username.to_string()),
+			#[cfg(feature = "mojang")]
+			LoginAuth::Online(key) => authenticate(client, key, &login).await?,
+			LoginAuth::Velocity(forwarding) => match forwarding.forward(client).await {
+				Ok(player) => player.profile,
+				Err(e) => {
+					debug!("Forwarding failed for {}: {}", client, e);
+					reject(client, "This server requires you to connect through its proxy").await;
+					return Err(e);
+				}
+			},
+		};
+
+		if let Some(reason) = hooks.check_profile(client, &mut profile).await? {
+			return Err(reject(client, reason).await);
+		}
+
+		// compression starts right before LoginSuccess, like vanilla
+		let threshold = self.compression_threshold.unwrap_or(client.login_compression());
+		client.set_compression(threshold).await?;
+
+		client.send_packet(Packet::LoginSuccess(LoginSuccessBody::new(LoginSuccessSpec::new(profile.clone())))).await?;
+
+		// clients from before 1.20.2 go straight to play, and unknown versions are assumed to be newer
+		if client.get_client_version().is_some_and(|v| !v.has_configuration_state()) {
+			client.change_state(PacketState::PLAY);
+		} else {
+			match client.receive_packet().await? {
+				Packet::LoginAcknowledged(_) => client.change_state(PacketState::CONFIGURATION),
+				_ => return Err(NetworkError::ExpectedDifferentPacket("Expected login acknowledged".to_string())),
+			}
+		}
+
+		debug!("{} logged in as {} ({})", client, profile.name, profile.uuid);
+
+		Ok(profile)
+	}
+}
+
+/// Enable encryption and check the player with Mojang
+#[cfg(feature = "mojang")]
+async fn authenticate(client: &mut CraftClient, key: &ServerKey, login: &LoginStartBody) -> Result<GameProfile, NetworkError> {
+	let verify_token = generate_verify_token();
+
+	client.send_packet(Packet::EncryptionRequest(key.encryption_request("", &verify_token, true))).await?;
+
+	let response = match client.receive_packet().await? {
+		Packet::EncryptionResponse(response) => response,
+		_ => return Err(NetworkError::ExpectedDifferentPacket("Expected encryption response".to_string())),
+	};
+
+	let shared_secret = key.decrypt_response(&response, &verify_token)?;
+	client.enable_encryption(&shared_secret)?;
+
+	match authenticate_player(&login.username.to_string(), "", &shared_secret, key.public_key_der(), None).await {
+		Ok(profile) => Ok(profile),
+		Err(e) => {
+			debug!("Could not authenticate {} for {}: {}", login.username, client, e);
+			Err(reject(client, TextComponent::translatable("multiplayer.disconnect.unverified_username", vec![])).await)
+		}
+	}
+}
+
+/// Disconnect the client with the reason, returning the error that ends the login
+async fn reject<T: Into<TextComponent>>(client: &mut CraftClient, reason: T) -> NetworkError {
+	let reason = reason.into();
+	let text = reason.to_plain_text();
+
+	if let Err(e) = client.disconnect(reason).await {
+		debug!("Could not send the disconnect to {}: {}", client, e);
+	}
+
+	NetworkError::LoginRejected(text)
+}
+
+#[cfg(test)]
+mod tests {
+	use tokio::net::TcpListener;
+	use uuid::Uuid;
+
+	use crate::network::client::client_handlers::HandshakeHandler;
+	use crate::network::client::CraftClient;
+	use crate::network::connector::CraftConnector;
+	use crate::network::network_error::NetworkError;
+	use crate::protocol::login::flow::{LoginAuth, LoginFlow, LoginHooks};
+	use crate::protocol::login::offline_uuid;
+	use crate::protocol::login::velocity::VelocityForwarding;
+	use crate::protocol::packets::LoginStartBody;
+	use crate::protocol::packets::packet_definer::PacketState;
+	use crate::protocol::status::DefaultHandshakeHandler;
+	use crate::protocol_types::datatypes::chat::TextComponent;
+	use crate::protocol_types::protocol_verison::ProtocolVerison;
+
+	struct NoSteve;
+
+	impl LoginHooks for NoSteve {
+		async fn check_login(&self, _client: &mut CraftClient, login: &LoginStartBody) -> Result<Option<TextComponent>, NetworkError> {
+			Ok((login.username.to_string() == "Steve").then(|| TextComponent::new("No Steves allowed")))
+		}
+	}
+
+	/// Run the flow on the server side of a new connection, and log in to it as `username`
+	async fn login(flow: LoginFlow, username: &str) -> (Result<PacketState, NetworkError>, Result<(), NetworkError>) {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+
+		let server = tokio::spawn(async move {
+			let (socket, _) = listener.accept().await.unwrap();
+			let mut client = CraftClient::from_connection(socket).unwrap();
+			DefaultHandshakeHandler::handle_handshake(&mut client).await.unwrap();
+
+			flow.run_with(&mut client, &NoSteve).await.map(|_| client.packet_state)
+		});
+
+		let mut connector = CraftConnector::connect("127.0.0.1", port).await.unwrap();
+		connector.handshake(ProtocolVerison::V1_20.get_version_number() as i32, PacketState::LOGIN).await.unwrap();
+		let logged_in = connector.login(username, Uuid::from_u128(7)).await.map(|profile| {
+			assert_eq!(profile.name, username);
+			assert_eq!(profile.uuid, offline_uuid(username));
+		});
+
+		(server.await.unwrap(), logged_in)
+	}
+
+	#[tokio::test]
+	async fn test_login_flow() {
+		let (server, client) = login(LoginFlow::offline().compression(Some(64)), "dec4234").await;
+		assert_eq!(server, Ok(PacketState::CONFIGURATION));
+		assert_eq!(client, Ok(()));
+	}
+
+	#[tokio::test]
+	async fn test_login_flow_rejects() {
+		let (server, client) = login(LoginFlow::offline(), "Steve").await;
+		assert_eq!(server, Err(NetworkError::LoginRejected("No Steves allowed".to_string())));
+		assert_eq!(client, Err(NetworkError::Disconnected("No Steves allowed".to_string())));
+
+		// the connector doesn't answer the proxy's request, so forwarding fails
+		let (server, client) = login(LoginFlow::new(LoginAuth::Velocity(VelocityForwarding::new("secret"))), "dec4234").await;
+		assert!(matches!(server, Err(NetworkError::ForwardingFailed(_))));
+		assert!(matches!(client, Err(NetworkError::Disconnected(_))));
+	}
+}
@@ -0,0 +1,54 @@
+//! The purpose of this file is to provide default implementations for the login handler.
+
+use log::debug;
+use md5::{Digest, Md5};
+use uuid::{Builder, Uuid};
+
+use crate::network::client::client_handlers::LoginHandler;
+use crate::network::client::CraftClient;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::packet_definer::PacketState;
+
+pub mod flow;
+pub mod plugin;
+pub mod velocity;
+pub mod yggdrasil;
+
+/// The UUID that offline-mode servers give a player, the same as vanilla. This is a version 3 UUID made
+/// from `OfflinePlayer:<username>`, like Java's `UUID.nameUUIDFromBytes`, so it doesn't match the
+/// player's real UUID.
+pub fn offline_uuid(username: &str) -> Uuid {
+	let hash: [u8; 16] = Md5::digest(format!("OfflinePlayer:{}", username)).into();
+	Builder::from_md5_bytes(hash).into_uuid()
+}
+
+/// The default login handler. Logging in needs a decision about who may join, so this disconnects the
+/// client with a message explaining that instead of leaving them hanging. Use [flow::LoginFlow] to let
+/// players log in.
+pub struct DefaultLoginHandler;
+
+impl LoginHandler for DefaultLoginHandler {
+	async fn handle_login(connection: &mut CraftClient) -> Result<(), NetworkError> {
+		if connection.packet_state != PacketState::LOGIN {
+			return Err(NetworkError::InvalidPacketState);
+		}
+
+		debug!("Rejecting login for {}", connection);
+
+		connection.disconnect("This server does not support logging in").await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use uuid::Uuid;
+
+	use crate::protocol::login::offline_uuid;
+
+	#[test]
+	fn test_offline_uuid() {
+		assert_eq!(offline_uuid("Notch"), Uuid::parse_str("b50ad385-829d-3141-a216-7e7d7539ba7f").unwrap());
+		assert_eq!(offline_uuid("Notch").get_version_num(), 3);
+		assert_ne!(offline_uuid("notch"), offline_uuid("Notch"));
+	}
+}
@@ -0,0 +1,199 @@
+//! Login plugin messages let the server talk to mods and proxies before the player has logged in, such as
+//! to ask a Velocity proxy for the player's info or check which mods a modded client has. The server sends
+//! a request on a channel, and the client answers each one with the same message ID, with no data if it
+//! doesn't know the channel.
+//!
+//! See [here](https://wiki.vg/Protocol#Login_Plugin_Request) for more.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::network::client::CraftClient;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::{LoginPluginRequestBody, Packet};
+use crate::protocol::packets::packet_definer::PacketState;
+use crate::protocol_types::datatypes::identifier::Identifier;
+use crate::protocol_types::datatypes::var_types::VarInt;
+use crate::protocol_types::datatypes::wrappers::ByteArrayRemainder;
+
+/// How long to wait for the answers by default. Vanilla clients answer straight away, this only has to
+/// cover the round trip and anything a proxy does to answer.
+pub const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The answer to a login plugin request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginPluginResponse {
+	pub message_id: i32,
+	/// The channel of the request this answers
+	pub channel: Identifier,
+	/// `None` if the client didn't understand the request
+	pub data: Option<Vec<u8>>,
+}
+
+impl LoginPluginResponse {
+	/// Whether the client knew the channel
+	pub fn understood(&self) -> bool {
+		self.data.is_some()
+	}
+}
+
+/// Sends login plugin requests on a connection and matches up the answers by their message ID. Several
+/// requests can be sent before waiting, and they are all answered in one round trip.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use sandstone::network::client::CraftClient;
+/// # use sandstone::network::network_error::NetworkError;
+/// # use sandstone::protocol::login::plugin::LoginPlugins;
+/// # async fn ask(client: &mut CraftClient) -> Result<(), NetworkError> {
+/// let mut plugins = LoginPlugins::new(client);
+/// plugins.send("example:hello".parse()?, b"hi".to_vec())?;
+/// plugins.send("example:mods".parse()?, vec![])?;
+///
+/// for response in plugins.collect(Duration::from_secs(5)).await? {
+///     println!("{} answered: {}", response.channel, response.understood());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct LoginPlugins<'a> {
+	client: &'a mut CraftClient,
+	/// The channel of each request that hasn't been answered yet
+	pending: HashMap<i32, Identifier>,
+}
+
+impl<'a> LoginPlugins<'a> {
+	pub fn new(client: &'a mut CraftClient) -> Self {
+		Self {
+			client,
+			pending: HashMap::new(),
+		}
+	}
+
+	/// Queue a request, returning its message ID. The requests are sent by [LoginPlugins::collect].
+	pub fn send(&mut self, channel: Identifier, data: Vec<u8>) -> Result<i32, NetworkError> {
+		if self.client.packet_state != PacketState::LOGIN {
+			return Err(NetworkError::InvalidPacketState);
+		}
+
+		let message_id = self.client.next_plugin_message_id;
+		self.client.next_plugin_message_id = message_id.wrapping_add(1);
+
+		self.client.queue_packet(Packet::LoginPluginRequest(LoginPluginRequestBody::new(VarInt(message_id), channel.clone(), ByteArrayRemainder::new(data))))?;
+		self.pending.insert(message_id, channel);
+
+		Ok(message_id)
+	}
+
+	/// The number of requests that haven't been answered yet
+	pub fn pending(&self) -> usize {
+		self.pending.len()
+	}
+
+	/// Send the queued requests and wait for every one of them to be answered, failing with
+	/// [NetworkError::TimedOut] if that takes longer than `timeout`. The answers are in the order they
+	/// arrived, which clients keep the same as the requests.
+	pub async fn collect(&mut self, timeout: Duration) -> Result<Vec<LoginPluginResponse>, NetworkError> {
+		self.client.flush().await?;
+
+		let deadline = Instant::now() + timeout;
+		let mut responses = Vec::with_capacity(self.pending.len());
+
+		while !self.pending.is_empty() {
+			let remaining = deadline.saturating_duration_since(Instant::now());
+
+			let response = match self.client.receive_packet_timeout(remaining).await? {
+				Packet::LoginPluginResponse(body) => body,
+				_ => return Err(NetworkError::ExpectedDifferentPacket("Expected a login plugin response".to_string())),
+			};
+
+			let channel = self.pending.remove(&response.message_id.0)
+				.ok_or_else(|| NetworkError::ExpectedDifferentPacket(format!("Login plugin response to unknown message {}", response.message_id.0)))?;
+
+			responses.push(LoginPluginResponse {
+				message_id: response.message_id.0,
+				channel,
+				data: response.data.map(ByteArrayRemainder::into_inner),
+			});
+		}
+
+		Ok(responses)
+	}
+
+	/// Send a single request and wait for its answer
+	pub async fn request(client: &mut CraftClient, channel: Identifier, data: Vec<u8>, timeout: Duration) -> Result<LoginPluginResponse, NetworkError> {
+		let mut plugins = LoginPlugins::new(client);
+		plugins.send(channel, data)?;
+
+		let mut responses = plugins.collect(timeout).await?;
+		Ok(responses.remove(0))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use tokio::net::TcpListener;
+	use uuid::Uuid;
+
+	use crate::network::client::client_handlers::HandshakeHandler;
+	use crate::network::client::CraftClient;
+	use crate::network::connector::CraftConnector;
+	use crate::network::network_error::NetworkError;
+	use crate::protocol::login::plugin::LoginPlugins;
+	use crate::protocol::packets::{LoginPluginResponseBody, LoginStartBody, Packet};
+	use crate::protocol::packets::packet_definer::PacketState;
+	use crate::protocol::status::DefaultHandshakeHandler;
+	use crate::protocol_types::datatypes::bounded_string::Username;
+	use crate::protocol_types::datatypes::var_types::VarInt;
+	use crate::protocol_types::protocol_verison::ProtocolVerison;
+
+	#[tokio::test]
+	async fn test_login_plugin_requests() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+
+		let server = tokio::spawn(async move {
+			let (socket, _) = listener.accept().await.unwrap();
+			let mut client = CraftClient::from_connection(socket).unwrap();
+			DefaultHandshakeHandler::handle_handshake(&mut client).await.unwrap();
+			client.receive_packet().await.unwrap();
+
+			let mut plugins = LoginPlugins::new(&mut client);
+			let echo = plugins.send("test:echo".parse().unwrap(), b"hello".to_vec()).unwrap();
+			let unknown = plugins.send("test:unknown".parse().unwrap(), vec![]).unwrap();
+			assert_eq!(plugins.pending(), 2);
+
+			let responses = plugins.collect(Duration::from_secs(5)).await.unwrap();
+			assert_eq!(responses[0].message_id, echo);
+			assert_eq!(responses[0].data.as_deref(), Some(&b"hello"[..]));
+			assert_eq!(responses[1].message_id, unknown);
+			assert!(!responses[1].understood());
+
+			// nothing answers this one
+			LoginPlugins::request(&mut client, "test:echo".parse().unwrap(), vec![], Duration::from_millis(100)).await
+		});
+
+		let mut connector = CraftConnector::connect("127.0.0.1", port).await.unwrap();
+		connector.handshake(ProtocolVerison::V1_20.get_version_number() as i32, PacketState::LOGIN).await.unwrap();
+		connector.send_packet(Packet::LoginStart(LoginStartBody::new(Username::new("dec4234").unwrap(), Uuid::new_v4()))).await.unwrap();
+
+		// a client that answers the echo channel and nothing else
+		for _ in 0..2 {
+			let request = match connector.receive_packet().await.unwrap() {
+				Packet::LoginPluginRequest(request) => request,
+				p => panic!("Expected login plugin request, got {:?}", p),
+			};
+
+			let data = (request.channel.to_string() == "test:echo").then(|| request.data.clone());
+			connector.send_packet(Packet::LoginPluginResponse(LoginPluginResponseBody::new(request.message_id, data))).await.unwrap();
+		}
+
+		assert_eq!(server.await.unwrap(), Err(NetworkError::TimedOut));
+		assert!(matches!(connector.receive_packet().await.unwrap(), Packet::LoginPluginRequest(request) if request.message_id == VarInt(2)));
+	}
+}
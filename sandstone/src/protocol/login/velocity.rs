@@ -0,0 +1,237 @@
+//! Support for Velocity's modern player info forwarding. When a server sits behind a Velocity proxy,
+//! the proxy handles authentication and the server only ever sees the proxy's address. During login the
+//! server asks the proxy for the real player info with a login plugin request, and the proxy answers with
+//! the player's address, UUID and profile, signed with a secret that both sides share.
+
+use std::fmt::{Debug, Formatter};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use hmac::{Hmac, Mac};
+use log::debug;
+use sha2::Sha256;
+use crate::network::client::CraftClient;
+use crate::network::network_error::NetworkError;
+use crate::protocol::login::plugin::{DEFAULT_RESPONSE_TIMEOUT, LoginPlugins};
+use crate::protocol::login::yggdrasil::YggdrasilVerifier;
+use crate::protocol::packets::{LoginPluginRequestBody, LoginPluginResponseBody};
+use crate::protocol::packets::packet_component::LoginSuccessSpec;
+use crate::protocol::packets::packet_definer::PacketState;
+use crate::protocol::serialization::{McDeserialize, McDeserializer};
+use crate::protocol_types::datatypes::game_profile::GameProfile;
+use crate::protocol_types::datatypes::var_types::VarInt;
+use crate::protocol_types::datatypes::wrappers::ByteArrayRemainder;
+
+/// The login plugin channel that Velocity listens on for forwarding requests
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+
+/// The forwarding version that carries the address, UUID and profile. Later versions also include the
+/// player's chat signing key, which is not needed for 1.20.2 and above.
+pub const MODERN_FORWARDING_DEFAULT: u8 = 1;
+
+/// The length of an HMAC-SHA256 signature, which comes before the forwarded data
+const SIGNATURE_LENGTH: usize = 32;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The player info that a Velocity proxy forwarded to the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForwardedPlayer {
+	/// The player's real address, rather than the proxy's
+	pub address: IpAddr,
+	/// The player's profile, including properties such as their skin
+	pub profile: GameProfile,
+}
+
+impl ForwardedPlayer {
+	/// The `LoginSuccess` body for this player, using the profile from the proxy
+	pub fn login_success(&self) -> LoginSuccessSpec {
+		LoginSuccessSpec::new(self.profile.clone())
+	}
+}
+
+/// Asks a Velocity proxy for the real player info and checks that the answer was signed with the
+/// forwarding secret from the proxy's `forwarding.secret` file.
+#[derive(Clone)]
+pub struct VelocityForwarding {
+	secret: Vec<u8>,
+	textures: Option<Arc<YggdrasilVerifier>>,
+}
+
+impl VelocityForwarding {
+	pub fn new<S: Into<Vec<u8>>>(secret: S) -> Self {
+		Self {
+			secret: secret.into(),
+			textures: None,
+		}
+	}
+
+	/// Also check that the forwarded skin was signed by Mojang, so that a compromised proxy can't hand
+	/// out any skin it likes
+	pub fn verify_textures(mut self, verifier: Arc<YggdrasilVerifier>) -> Self {
+		self.textures = Some(verifier);
+		self
+	}
+
+	/// The login plugin request that asks the proxy to forward the player info
+	pub fn request(&self, message_id: i32) -> LoginPluginRequestBody {
+		LoginPluginRequestBody::new(VarInt(message_id), VELOCITY_CHANNEL.parse().expect("valid identifier"), ByteArrayRemainder::new(vec![MODERN_FORWARDING_DEFAULT]))
+	}
+
+	/// Check the signature on the proxy's response and read the player info out of it. A response
+	/// without any data means the client didn't come through a proxy with modern forwarding turned on.
+	pub fn verify(&self, response: &LoginPluginResponseBody) -> Result<ForwardedPlayer, NetworkError> {
+		self.verify_data(response.data.as_deref().map(Vec::as_slice))
+	}
+
+	fn verify_data(&self, data: Option<&[u8]>) -> Result<ForwardedPlayer, NetworkError> {
+		let data = match data {
+			Some(data) => data,
+			None => return Err(NetworkError::ForwardingFailed("The proxy did not answer, is modern forwarding enabled?".to_string())),
+		};
+
+		if data.len() < SIGNATURE_LENGTH {
+			return Err(NetworkError::ForwardingFailed("Response is too short to be signed".to_string()));
+		}
+
+		let (signature, payload) = data.split_at(SIGNATURE_LENGTH);
+
+		let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+		mac.update(payload);
+		mac.verify_slice(signature).map_err(|_| NetworkError::ForwardingFailed("Invalid signature, the forwarding secrets don't match".to_string()))?;
+
+		let mut deserializer = McDeserializer::new(payload);
+
+		let version = VarInt::mc_deserialize(&mut deserializer)?.0;
+
+		if version != MODERN_FORWARDING_DEFAULT as i32 {
+			return Err(NetworkError::ForwardingFailed(format!("Unsupported forwarding version {}", version)));
+		}
+
+		let address = String::mc_deserialize(&mut deserializer)?;
+		let address = address.trim_start_matches('[').trim_end_matches(']').parse()
+			.map_err(|_| NetworkError::ForwardingFailed(format!("Invalid forwarded address {}", address)))?;
+
+		let profile = GameProfile::mc_deserialize(&mut deserializer)?;
+
+		if let Some(verifier) = &self.textures {
+			verifier.verify_profile(&profile).map_err(|e| NetworkError::ForwardingFailed(e.to_string()))?;
+		}
+
+		Ok(ForwardedPlayer {
+			address,
+			profile,
+		})
+	}
+
+	/// Ask the proxy for the player info and wait for the answer, see [LoginPlugins]. This should be done
+	/// after the client sends `LoginStart` and before `LoginSuccess`. The connection's address is replaced with the
+	/// forwarded one, so that logs and anything else using it see the player rather than the proxy.
+	pub async fn forward(&self, client: &mut CraftClient) -> Result<ForwardedPlayer, NetworkError> {
+		if client.packet_state != PacketState::LOGIN {
+			return Err(NetworkError::InvalidPacketState);
+		}
+
+		let channel = VELOCITY_CHANNEL.parse().expect("valid identifier");
+		let response = LoginPlugins::request(client, channel, vec![MODERN_FORWARDING_DEFAULT], DEFAULT_RESPONSE_TIMEOUT).await?;
+
+		let player = self.verify_data(response.data.as_deref())?;
+
+		debug!("Velocity forwarded {} ({}) from {}", player.profile.name, player.profile.uuid, player.address);
+		client.socket_addr = SocketAddr::new(player.address, client.socket_addr.port());
+
+		Ok(player)
+	}
+}
+
+impl Debug for VelocityForwarding {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("VelocityForwarding").finish_non_exhaustive() // don't leak the secret into logs
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+
+	use hmac::Mac;
+	use rsa::{RsaPrivateKey, RsaPublicKey};
+	use rsa::pkcs8::EncodePublicKey;
+	use uuid::Uuid;
+
+	use crate::network::network_error::NetworkError;
+	use crate::protocol::login::velocity::{ForwardedPlayer, HmacSha256, VelocityForwarding};
+	use crate::protocol::login::yggdrasil::tests::sign;
+	use crate::protocol::login::yggdrasil::YggdrasilVerifier;
+	use crate::protocol::packets::LoginPluginResponseBody;
+	use crate::protocol::serialization::{McSerialize, McSerializer};
+	use crate::protocol_types::datatypes::game_profile::{GameProfile, ProfileProperty};
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	fn player() -> ForwardedPlayer {
+		ForwardedPlayer {
+			address: "203.0.113.7".parse().unwrap(),
+			profile: GameProfile {
+				uuid: Uuid::from_u128(0x1234),
+				name: "dec4234".to_string(),
+				properties: vec![ProfileProperty::new("textures", "abc", Some("sig".to_string()))],
+			},
+		}
+	}
+
+	/// Build a response the way Velocity does
+	fn response(secret: &[u8], version: i32, player: &ForwardedPlayer) -> LoginPluginResponseBody {
+		let mut serializer = McSerializer::new();
+		VarInt(version).mc_serialize(&mut serializer).unwrap();
+		player.address.to_string().mc_serialize(&mut serializer).unwrap();
+		player.profile.uuid.mc_serialize(&mut serializer).unwrap();
+		player.profile.name.mc_serialize(&mut serializer).unwrap();
+		player.profile.properties.mc_serialize(&mut serializer).unwrap();
+
+		let mut mac = HmacSha256::new_from_slice(secret).unwrap();
+		mac.update(&serializer.output);
+
+		let mut data = mac.finalize().into_bytes().to_vec();
+		data.extend_from_slice(&serializer.output);
+
+		LoginPluginResponseBody::new(VarInt(1), Some(data.into()))
+	}
+
+	#[test]
+	fn test_velocity_verify() {
+		let forwarding = VelocityForwarding::new("hunter2");
+
+		assert_eq!(forwarding.request(5).data.0, vec![1]);
+		assert_eq!(forwarding.verify(&response(b"hunter2", 1, &player())), Ok(player()));
+		assert_eq!(player().login_success().profile.name, "dec4234");
+	}
+
+	#[test]
+	fn test_velocity_rejects_bad_responses() {
+		let forwarding = VelocityForwarding::new("hunter2");
+
+		assert!(matches!(forwarding.verify(&response(b"wrong", 1, &player())), Err(NetworkError::ForwardingFailed(_))));
+		assert!(matches!(forwarding.verify(&response(b"hunter2", 4, &player())), Err(NetworkError::ForwardingFailed(_))));
+		assert!(matches!(forwarding.verify(&LoginPluginResponseBody::new(VarInt(1), None)), Err(NetworkError::ForwardingFailed(_))));
+
+		// change a byte of the username after signing
+		let mut tampered = response(b"hunter2", 1, &player());
+		let last = tampered.data.as_ref().unwrap().len() - 20;
+		tampered.data.as_mut().unwrap().0[last] ^= 1;
+		assert!(matches!(forwarding.verify(&tampered), Err(NetworkError::ForwardingFailed(_))));
+	}
+
+	#[test]
+	fn test_velocity_verify_textures() {
+		let key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+		let der = RsaPublicKey::from(&key).to_public_key_der().unwrap();
+		let forwarding = VelocityForwarding::new("hunter2").verify_textures(Arc::new(YggdrasilVerifier::from_der(der.as_bytes()).unwrap()));
+
+		// signed by the proxy, but not by Mojang
+		assert!(matches!(forwarding.verify(&response(b"hunter2", 1, &player())), Err(NetworkError::ForwardingFailed(_))));
+
+		let mut signed = player();
+		signed.profile.properties[0].signature = Some(sign(&key, "abc"));
+		assert_eq!(forwarding.verify(&response(b"hunter2", 1, &signed)), Ok(signed));
+	}
+}
@@ -0,0 +1,183 @@
+//! Checks the signatures that Mojang's session server (Yggdrasil) puts on profile properties. The
+//! `textures` property holds the URLs of a player's skin and cape, and it is signed so that a proxy or a
+//! modified client can't hand out someone else's profile with a different skin.
+//!
+//! The signature is SHA1withRSA over the property's value, made with one of Mojang's profile property
+//! keys. Vanilla ships the key as `yggdrasil_session_pubkey.der`, and the current keys are also served at
+//! `https://api.minecraftservices.com/publickeys`, along with the keys that sign players' chat keys.
+
+use std::fmt::{Debug, Formatter};
+
+use base64::Engine;
+use base64::engine::general_purpose;
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
+use rsa::pkcs8::DecodePublicKey;
+use sha1::{Digest, Sha1};
+
+use crate::network::network_error::NetworkError;
+use crate::protocol_types::datatypes::game_profile::{GameProfile, ProfileProperty};
+#[cfg(feature = "mojang")]
+use crate::util::mojang::http::HttpError;
+
+/// Verifies profile property signatures against a set of trusted keys. A signature is accepted if any of
+/// the keys made it, since Mojang may sign with a new key while old signatures are still around.
+#[derive(Clone, Default)]
+pub struct YggdrasilVerifier {
+	keys: Vec<RsaPublicKey>,
+}
+
+impl YggdrasilVerifier {
+	/// A verifier that doesn't trust any key yet
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Trust a key given as a DER `SubjectPublicKeyInfo`, such as `yggdrasil_session_pubkey.der` from the
+	/// client jar
+	pub fn from_der(der: &[u8]) -> Result<Self, NetworkError> {
+		let mut verifier = Self::new();
+		verifier.add_der_key(der)?;
+		Ok(verifier)
+	}
+
+	/// Trust the profile property keys that Mojang currently publishes
+	#[cfg(feature = "mojang")]
+	pub async fn fetch() -> Result<Self, HttpError> {
+		let keys = crate::util::mojang::get_public_keys().await?;
+		Self::from_entries(&keys.profilePropertyKeys)
+	}
+
+	/// Trust the keys that Mojang currently signs players' chat keys with, for checking a
+	/// [PlayerPublicKey](crate::protocol::chat::session::PlayerPublicKey)
+	#[cfg(feature = "mojang")]
+	pub async fn fetch_player_certificate_keys() -> Result<Self, HttpError> {
+		let keys = crate::util::mojang::get_public_keys().await?;
+		Self::from_entries(&keys.playerCertificateKeys)
+	}
+
+	#[cfg(feature = "mojang")]
+	fn from_entries(entries: &[crate::util::mojang::PublicKeyEntry]) -> Result<Self, HttpError> {
+		let mut verifier = Self::new();
+
+		for entry in entries {
+			verifier.add_base64_key(&entry.publicKey)
+				.map_err(|e| HttpError::StatusCode(format!("Invalid public key from Mojang: {}", e)))?;
+		}
+
+		Ok(verifier)
+	}
+
+	pub fn add_key(&mut self, key: RsaPublicKey) {
+		self.keys.push(key);
+	}
+
+	/// Trust a key given as a DER `SubjectPublicKeyInfo`
+	pub fn add_der_key(&mut self, der: &[u8]) -> Result<(), NetworkError> {
+		let key = RsaPublicKey::from_public_key_der(der)
+			.map_err(|e| NetworkError::InvalidSignature(format!("Invalid public key: {}", e)))?;
+
+		self.add_key(key);
+		Ok(())
+	}
+
+	/// Trust a key given as base64 DER, the format used by the `publickeys` endpoint
+	pub fn add_base64_key(&mut self, key: &str) -> Result<(), NetworkError> {
+		let der = general_purpose::STANDARD.decode(key)
+			.map_err(|e| NetworkError::InvalidSignature(format!("Invalid public key: {}", e)))?;
+
+		self.add_der_key(&der)
+	}
+
+	/// The number of trusted keys
+	pub fn key_count(&self) -> usize {
+		self.keys.len()
+	}
+
+	/// Check that a property was signed by one of the trusted keys, failing if it isn't signed at all
+	pub fn verify_property(&self, property: &ProfileProperty) -> Result<(), NetworkError> {
+		let signature = property.signature.as_deref()
+			.ok_or_else(|| NetworkError::InvalidSignature(format!("Property '{}' is not signed", property.name)))?;
+
+		let signature = general_purpose::STANDARD.decode(signature)
+			.map_err(|e| NetworkError::InvalidSignature(format!("Property '{}' has an invalid signature: {}", property.name, e)))?;
+
+		if self.verify_signature(property.value.as_bytes(), &signature) {
+			Ok(())
+		} else {
+			Err(NetworkError::InvalidSignature(format!("Property '{}' was not signed by a trusted key", property.name)))
+		}
+	}
+
+	/// Whether one of the trusted keys made a SHA1withRSA signature of the data
+	pub fn verify_signature(&self, data: &[u8], signature: &[u8]) -> bool {
+		let hash = Sha1::digest(data);
+		self.keys.iter().any(|key| key.verify(Pkcs1v15Sign::new::<Sha1>(), &hash, signature).is_ok())
+	}
+
+	/// Check the signature on a profile's `textures` property. A profile without textures passes, since
+	/// the player just has the default skin, but textures without a valid signature don't.
+	pub fn verify_profile(&self, profile: &GameProfile) -> Result<(), NetworkError> {
+		match profile.textures() {
+			Some(textures) => self.verify_property(textures),
+			None => Ok(()),
+		}
+	}
+}
+
+impl Debug for YggdrasilVerifier {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("YggdrasilVerifier")
+			.field("keys", &self.keys.len())
+			.finish()
+	}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use base64::Engine;
+	use base64::engine::general_purpose;
+	use rsa::{Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+	use rsa::pkcs8::EncodePublicKey;
+	use sha1::{Digest, Sha1};
+	use uuid::Uuid;
+
+	use crate::network::network_error::NetworkError;
+	use crate::protocol::login::yggdrasil::YggdrasilVerifier;
+	use crate::protocol_types::datatypes::game_profile::{GameProfile, ProfileProperty};
+
+	/// Sign data the way Mojang does
+	pub(crate) fn sign_bytes(key: &RsaPrivateKey, data: &[u8]) -> Vec<u8> {
+		key.sign(Pkcs1v15Sign::new::<Sha1>(), &Sha1::digest(data)).unwrap()
+	}
+
+	/// Sign a property value the way the session server does
+	pub(crate) fn sign(key: &RsaPrivateKey, value: &str) -> String {
+		general_purpose::STANDARD.encode(sign_bytes(key, value.as_bytes()))
+	}
+
+	#[test]
+	fn test_yggdrasil_verify() {
+		let key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+		let other = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+
+		let der = RsaPublicKey::from(&key).to_public_key_der().unwrap();
+		let verifier = YggdrasilVerifier::from_der(der.as_bytes()).unwrap();
+		assert_eq!(verifier.key_count(), 1);
+
+		let mut profile = GameProfile::new(Uuid::from_u128(0x1234), "dec4234");
+		assert_eq!(verifier.verify_profile(&profile), Ok(()));
+
+		profile.properties.push(ProfileProperty::new("textures", "abc", Some(sign(&key, "abc"))));
+		assert_eq!(verifier.verify_profile(&profile), Ok(()));
+
+		// a different skin with the original signature
+		profile.properties[0].value = "abd".to_string();
+		assert!(matches!(verifier.verify_profile(&profile), Err(NetworkError::InvalidSignature(_))));
+
+		profile.properties[0] = ProfileProperty::new("textures", "abc", Some(sign(&other, "abc")));
+		assert!(matches!(verifier.verify_profile(&profile), Err(NetworkError::InvalidSignature(_))));
+
+		profile.properties[0].signature = None;
+		assert!(matches!(verifier.verify_profile(&profile), Err(NetworkError::InvalidSignature(_))));
+	}
+}
@@ -1,5 +1,12 @@
 pub mod packets;
 pub mod status;
+pub mod login;
+pub mod chat;
+pub mod commands;
+pub mod configuration;
+pub mod plugin_message;
+pub mod play;
 pub mod serialization;
 
+#[cfg(test)]
 mod testing;
\ No newline at end of file
@@ -8,16 +8,27 @@
 use uuid::Uuid;
 
 use crate::packets;
-use crate::protocol::packets::packet_component::{AddResourcePackSpec, LoginCookieResponseSpec, LoginPluginSpec, RegistryEntry, RemoveResourcePackSpec};
-use crate::protocol::packets::packet_component::LoginPropertyElement;
+use crate::protocol::chat::session::PlayerPublicKey;
+use crate::protocol::chat::signed::{Acknowledged, ArgumentSignature, FilterMask, MessageSignature, PreviousMessage};
+use crate::protocol::commands::suggestions::Suggestion;
+use crate::protocol::commands::tree::CommandNode;
+use crate::protocol::packets::packet_component::{ChunkDataSpec, EncryptionRequestSpec, EncryptionResponseSpec, KnownPack, LoginPlaySpec, LoginSuccessSpec, PlayerInfoUpdateSpec, RegistryEntry, RegistryTagsSpec, ReportDetail, ServerLink};
 use crate::protocol::packets::packet_definer::{PacketDirection, PacketState};
+use crate::protocol::play::boss_bar::BossBarAction;
+use crate::protocol::play::scoreboard::{NumberFormat, ObjectiveAction, TeamAction};
 use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
 use crate::protocol::serialization::serializer_error::SerializingErr;
 use crate::protocol::serialization::SerializingResult;
 use crate::protocol::serialization::StateBasedDeserializer;
 use crate::protocol::status::status_components::StatusResponseSpec;
-use crate::protocol_types::datatypes::chat::TextComponent;
+use crate::protocol_types::datatypes::angle::{Angle, PositionDelta};
+use crate::protocol_types::datatypes::bounded_string::Username;
+use crate::protocol_types::datatypes::chat::{NbtTextComponent, TextComponent};
+use crate::protocol_types::datatypes::entity_metadata::EntityMetadata;
+use crate::protocol_types::datatypes::identifier::Identifier;
+use crate::protocol_types::datatypes::position::Position;
 use crate::protocol_types::datatypes::var_types::VarInt;
+use crate::protocol_types::datatypes::wrappers::{ByteArrayRemainder, Json};
 
 pub mod packet_component;
 pub mod packet_definer;
@@ -38,7 +49,7 @@ packets!(v1_21 => { // version name is for reference only, has no effect
 	STATUS => {
 		CLIENT => {
 			StatusResponse, StatusResponseBody, 0x00 => {
-				response: StatusResponseSpec
+				response: Json<StatusResponseSpec>
 			},
 			PingResponse, PingResponseBody, 0x01 => {
 				payload: u64
@@ -59,64 +70,55 @@ packets!(v1_21 => { // version name is for reference only, has no effect
 				reason: TextComponent
 			},
 			EncryptionRequest, EncryptionRequestBody, 0x01 => {
-				server_id: String,
-				public_key_length: VarInt,
-				public_key: Vec<u8>,
-				verify_token_length: VarInt, // always 4 for Notchian servers
-				verify_token: Vec<u8>
+				spec: EncryptionRequestSpec
 			},
 			LoginSuccess, LoginSuccessBody, 0x02 => {
-				uuid: String,
-				username: String,
-				num_properties: VarInt,
-				array: Vec<LoginPropertyElement>,
-				strict_error_handling: bool
+				spec: LoginSuccessSpec
 			},
 			SetCompression, SetCompressionBody, 0x03 => {
 				threshold: VarInt
 			},
 			LoginPluginRequest, LoginPluginRequestBody, 0x04 => {
 				message_id: VarInt,
-				channel: String,
-				data: Vec<u8>
+				channel: Identifier,
+				data: ByteArrayRemainder
 			},
 			LoginCookieRequest, LoginCookieRequestBody, 0x05 => {
-				key: String
+				key: Identifier
 			}
 		},
 		SERVER => {
 			LoginStart, LoginStartBody, 0x00 => {
-				username: String,
+				username: Username,
 				uuid: Uuid
 			},
 			EncryptionResponse, EncryptionResponseBody, 0x01 => {
-				shared_secret_length: VarInt,
-				shared_secret: Vec<u8>,
-				verify_token_length: VarInt,
-				verify_token: Vec<u8>
+				spec: EncryptionResponseSpec
 			},
 			LoginPluginResponse, LoginPluginResponseBody, 0x02 => {
-				response: LoginPluginSpec
+				message_id: VarInt,
+				data: Option<ByteArrayRemainder> // absent if the client didn't understand the request
 			},
 			LoginAcknowledged, LoginAcknowledgedBody, 0x03 => {
 				// none
 			},
 			LoginCookieResponse, LoginCookieResponseBody, 0x04 => {
-				spec: LoginCookieResponseSpec
+				key: Identifier,
+				payload: Option<Vec<u8>>
 			}
 		}
 	},
 	CONFIGURATION => {
 		CLIENT => {
 			ConfigCookieRequest, ConfigCookieRequestBody, 0x00 => {
-				key: String
+				key: Identifier
 			},
 			PluginMessage, PluginMessageBody, 0x01 => {
-				channel: String,
-				data: Vec<u8>
+				channel: Identifier,
+				data: ByteArrayRemainder
 			},
 			ConfigDisconnect, ConfigDisconnectBody, 0x02 => {
-				reason: TextComponent
+				reason: NbtTextComponent
 			},
 			FinishConfiguration, FinishConfigurationBody, 0x03 => {
 				// none
@@ -131,22 +133,350 @@ packets!(v1_21 => { // version name is for reference only, has no effect
 				// none
 			},
 			RegistryData, RegistryDataBody, 0x07 => {
-				registry_id: String,
-				entry_count: VarInt,
+				registry_id: Identifier,
 				entries: Vec<RegistryEntry>
 			},
 			RemoveResourcePack, RemoveResourcePackBody, 0x08 => {
-				spec: RemoveResourcePackSpec
+				uuid: Option<Uuid> // removes every pack if absent
 			},
 			AddResourcePack, AddResourcePackBody, 0x09 => {
-				spec: AddResourcePackSpec
+				uuid: Uuid,
+				url: String,
+				hash: String,
+				forced: bool,
+				prompt_message: Option<NbtTextComponent>
+			},
+			StoreCookie, StoreCookieBody, 0x0A => {
+				key: Identifier,
+				payload: Vec<u8>
+			},
+			Transfer, TransferBody, 0x0B => {
+				host: String,
+				port: VarInt
 			},
-			
-			// TODO: others here
-			
 			FeatureFlags, FeatureFlagsBody, 0x0C => {
-				total: VarInt,
-				flags: Vec<String>
+				flags: Vec<Identifier>
+			},
+			UpdateTags, UpdateTagsBody, 0x0D => {
+				registries: Vec<RegistryTagsSpec>
+			},
+			ClientboundKnownPacks, ClientboundKnownPacksBody, 0x0E => {
+				packs: Vec<KnownPack>
+			},
+			CustomReportDetails, CustomReportDetailsBody, 0x0F => {
+				details: Vec<ReportDetail>
+			},
+			ServerLinks, ServerLinksBody, 0x10 => {
+				links: Vec<ServerLink>
+			}
+		},
+		SERVER => {
+			ClientInformation, ClientInformationBody, 0x00 => {
+				locale: String,
+				view_distance: i8,
+				chat_mode: VarInt,
+				chat_colors: bool,
+				displayed_skin_parts: u8,
+				main_hand: VarInt,
+				enable_text_filtering: bool,
+				allow_server_listings: bool
+			},
+			ConfigCookieResponse, ConfigCookieResponseBody, 0x01 => {
+				key: Identifier,
+				payload: Option<Vec<u8>>
+			},
+			ServerboundPluginMessage, ServerboundPluginMessageBody, 0x02 => {
+				channel: Identifier,
+				data: ByteArrayRemainder
+			},
+			AcknowledgeFinishConfiguration, AcknowledgeFinishConfigurationBody, 0x03 => {
+				// none
+			},
+			ServerboundKeepAlive, ServerboundKeepAliveBody, 0x04 => {
+				keep_alive_id: i64
+			},
+			ConfigurationPong, ConfigurationPongBody, 0x05 => {
+				payload: i32
+			},
+			ResourcePackResponse, ResourcePackResponseBody, 0x06 => {
+				uuid: Uuid,
+				result: VarInt
+			},
+			ServerboundKnownPacks, ServerboundKnownPacksBody, 0x07 => {
+				packs: Vec<KnownPack>
+			}
+		}
+	},
+	PLAY => {
+		CLIENT => {
+			SpawnEntity, SpawnEntityBody, 0x01 => {
+				entity_id: VarInt,
+				entity_uuid: Uuid,
+				entity_type: VarInt,
+				x: f64,
+				y: f64,
+				z: f64,
+				pitch: Angle,
+				yaw: Angle,
+				head_yaw: Angle,
+				data: VarInt,
+				velocity_x: i16,
+				velocity_y: i16,
+				velocity_z: i16
+			},
+			BossBar, BossBarBody, 0x0A => {
+				uuid: Uuid,
+				action: BossBarAction
+			},
+			ClearTitles, ClearTitlesBody, 0x0F => {
+				reset: bool // also resets the title times
+			},
+			CommandSuggestionsResponse, CommandSuggestionsResponseBody, 0x10 => {
+				transaction_id: VarInt,
+				start: VarInt, // in UTF-16 code units of the request text
+				length: VarInt,
+				matches: Vec<Suggestion>
+			},
+			Commands, CommandsBody, 0x11 => {
+				nodes: Vec<CommandNode>,
+				root_index: VarInt
+			},
+			PlayCookieRequest, PlayCookieRequestBody, 0x16 => {
+				key: Identifier
+			},
+			PlayPluginMessage, PlayPluginMessageBody, 0x19 => {
+				channel: Identifier,
+				data: ByteArrayRemainder
+			},
+			PlayDisconnect, PlayDisconnectBody, 0x1D => {
+				reason: NbtTextComponent
+			},
+			GameEvent, GameEventBody, 0x22 => {
+				event: u8,
+				value: f32
+			},
+			PlayKeepAlive, PlayKeepAliveBody, 0x26 => {
+				keep_alive_id: i64
+			},
+			ChunkDataAndUpdateLight, ChunkDataAndUpdateLightBody, 0x27 => {
+				spec: ChunkDataSpec
+			},
+			LoginPlay, LoginPlayBody, 0x2B => {
+				spec: LoginPlaySpec
+			},
+			UpdateEntityPosition, UpdateEntityPositionBody, 0x2E => {
+				entity_id: VarInt,
+				delta_x: PositionDelta,
+				delta_y: PositionDelta,
+				delta_z: PositionDelta,
+				on_ground: bool
+			},
+			UpdateEntityPositionAndRotation, UpdateEntityPositionAndRotationBody, 0x2F => {
+				entity_id: VarInt,
+				delta_x: PositionDelta,
+				delta_y: PositionDelta,
+				delta_z: PositionDelta,
+				yaw: Angle,
+				pitch: Angle,
+				on_ground: bool
+			},
+			UpdateEntityRotation, UpdateEntityRotationBody, 0x30 => {
+				entity_id: VarInt,
+				yaw: Angle,
+				pitch: Angle,
+				on_ground: bool
+			},
+			PlayPing, PlayPingBody, 0x35 => {
+				payload: i32
+			},
+			PlayerAbilities, PlayerAbilitiesBody, 0x38 => {
+				flags: i8,
+				flying_speed: f32,
+				fov_modifier: f32
+			},
+			PlayerChat, PlayerChatBody, 0x39 => {
+				sender: Uuid,
+				index: VarInt,
+				signature: Option<MessageSignature>,
+				message: String,
+				timestamp: i64,
+				salt: i64,
+				previous_messages: Vec<PreviousMessage>,
+				unsigned_content: Option<Box<NbtTextComponent>>, // boxed to keep the Packet enum small
+				filter: FilterMask,
+				chat_type: VarInt, // the ID in the minecraft:chat_type registry, plus 1
+				sender_name: Box<NbtTextComponent>,
+				target_name: Option<Box<NbtTextComponent>>
+			},
+			PlayerInfoRemove, PlayerInfoRemoveBody, 0x3D => {
+				uuids: Vec<Uuid>
+			},
+			PlayerInfoUpdate, PlayerInfoUpdateBody, 0x3E => {
+				spec: PlayerInfoUpdateSpec
+			},
+			SynchronizePlayerPosition, SynchronizePlayerPositionBody, 0x40 => {
+				x: f64,
+				y: f64,
+				z: f64,
+				yaw: f32,
+				pitch: f32,
+				flags: i8,
+				teleport_id: VarInt
+			},
+			ResetScore, ResetScoreBody, 0x44 => {
+				entity_name: String,
+				objective_name: Option<String> // every objective if not present
+			},
+			PlayRemoveResourcePack, PlayRemoveResourcePackBody, 0x45 => {
+				uuid: Option<Uuid> // removes every pack if absent
+			},
+			PlayAddResourcePack, PlayAddResourcePackBody, 0x46 => {
+				uuid: Uuid,
+				url: String,
+				hash: String,
+				forced: bool,
+				prompt_message: Option<NbtTextComponent>
+			},
+			SetHeadRotation, SetHeadRotationBody, 0x48 => {
+				entity_id: VarInt,
+				head_yaw: Angle
+			},
+			SetActionBarText, SetActionBarTextBody, 0x4C => {
+				text: NbtTextComponent
+			},
+			SetDefaultSpawnPosition, SetDefaultSpawnPositionBody, 0x56 => {
+				location: Position,
+				angle: f32
+			},
+			DisplayObjective, DisplayObjectiveBody, 0x57 => {
+				position: VarInt, // see DisplaySlot
+				score_name: String // empty to clear the slot
+			},
+			SetEntityMetadata, SetEntityMetadataBody, 0x58 => {
+				entity_id: VarInt,
+				metadata: EntityMetadata
+			},
+			UpdateObjectives, UpdateObjectivesBody, 0x5E => {
+				objective_name: String,
+				action: ObjectiveAction
+			},
+			UpdateTeams, UpdateTeamsBody, 0x60 => {
+				team_name: String,
+				action: TeamAction
+			},
+			UpdateScore, UpdateScoreBody, 0x61 => {
+				entity_name: String,
+				objective_name: String,
+				value: VarInt,
+				display_name: Option<NbtTextComponent>,
+				number_format: Option<NumberFormat>
+			},
+			SetSubtitleText, SetSubtitleTextBody, 0x63 => {
+				text: NbtTextComponent
+			},
+			SetTitleText, SetTitleTextBody, 0x65 => {
+				text: NbtTextComponent
+			},
+			SetTitleAnimationTimes, SetTitleAnimationTimesBody, 0x66 => {
+				fade_in: i32, // all in ticks
+				stay: i32,
+				fade_out: i32
+			},
+			PlayStoreCookie, PlayStoreCookieBody, 0x6B => {
+				key: Identifier,
+				payload: Vec<u8>
+			},
+			SystemChatMessage, SystemChatMessageBody, 0x6C => {
+				content: NbtTextComponent,
+				overlay: bool // shown above the hotbar instead of in chat
+			},
+			PlayTransfer, PlayTransferBody, 0x73 => {
+				host: String,
+				port: VarInt
+			},
+			PlayCustomReportDetails, PlayCustomReportDetailsBody, 0x7A => {
+				details: Vec<ReportDetail>
+			},
+			PlayServerLinks, PlayServerLinksBody, 0x7B => {
+				links: Vec<ServerLink>
+			}
+		},
+		SERVER => {
+			ConfirmTeleportation, ConfirmTeleportationBody, 0x00 => {
+				teleport_id: VarInt
+			},
+			MessageAcknowledgment, MessageAcknowledgmentBody, 0x03 => {
+				message_count: VarInt
+			},
+			ChatCommand, ChatCommandBody, 0x04 => {
+				command: String
+			},
+			SignedChatCommand, SignedChatCommandBody, 0x05 => {
+				command: String,
+				timestamp: i64,
+				salt: i64,
+				argument_signatures: Vec<ArgumentSignature>,
+				message_count: VarInt,
+				acknowledged: Acknowledged
+			},
+			ChatMessage, ChatMessageBody, 0x06 => {
+				message: String,
+				timestamp: i64,
+				salt: i64,
+				signature: Option<MessageSignature>,
+				message_count: VarInt,
+				acknowledged: Acknowledged
+			},
+			PlayerSession, PlayerSessionBody, 0x07 => {
+				session_id: Uuid,
+				public_key: PlayerPublicKey
+			},
+			CommandSuggestionsRequest, CommandSuggestionsRequestBody, 0x0B => {
+				transaction_id: VarInt,
+				text: String // the command typed so far, with the leading '/'
+			},
+			PlayCookieResponse, PlayCookieResponseBody, 0x11 => {
+				key: Identifier,
+				payload: Option<Vec<u8>>
+			},
+			PlayServerboundPluginMessage, PlayServerboundPluginMessageBody, 0x12 => {
+				channel: Identifier,
+				data: ByteArrayRemainder
+			},
+			PlayServerboundKeepAlive, PlayServerboundKeepAliveBody, 0x18 => {
+				keep_alive_id: i64
+			},
+			SetPlayerPosition, SetPlayerPositionBody, 0x1A => {
+				x: f64,
+				feet_y: f64,
+				z: f64,
+				on_ground: bool
+			},
+			SetPlayerPositionAndRotation, SetPlayerPositionAndRotationBody, 0x1B => {
+				x: f64,
+				feet_y: f64,
+				z: f64,
+				yaw: f32,
+				pitch: f32,
+				on_ground: bool
+			},
+			SetPlayerRotation, SetPlayerRotationBody, 0x1C => {
+				yaw: f32,
+				pitch: f32,
+				on_ground: bool
+			},
+			SetPlayerOnGround, SetPlayerOnGroundBody, 0x1D => {
+				on_ground: bool
+			},
+			ServerboundPlayerAbilities, ServerboundPlayerAbilitiesBody, 0x23 => {
+				flags: i8
+			},
+			PlayPong, PlayPongBody, 0x27 => {
+				payload: i32
+			},
+			PlayResourcePackResponse, PlayResourcePackResponseBody, 0x2B => {
+				uuid: Uuid,
+				result: VarInt
 			}
 		}
 	}
@@ -1,177 +1,518 @@
 //! Defines a lot of random components of network packets. This is separate from packet.rs to reduce
 //! clutter.
 
-use sandstone_derive::McSerialize;
+use sandstone_derive::{McDeserialize, McSerialize};
 use uuid::Uuid;
 
+use crate::protocol::chat::session::ChatSession;
 use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
 use crate::protocol::serialization::serializer_error::SerializingErr;
-use crate::protocol_types::datatypes::nbt::nbt::NbtCompound;
+use crate::protocol_types::datatypes::bitset::BitSet;
+use crate::protocol_types::datatypes::chat::NbtTextComponent;
+use crate::protocol_types::datatypes::game_profile::{GameProfile, ProfileProperty};
+use crate::protocol_types::datatypes::identifier::Identifier;
+use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtFlavor};
+use crate::protocol_types::datatypes::position::Position;
 use crate::protocol_types::datatypes::var_types::VarInt;
 
-// TODO: maybe we can make a derive tag for options? At the very least only the option section needs to
-// be in a special body struct.
-#[derive(McSerialize, Debug, Clone, PartialEq, Eq, Hash)]
-pub struct LoginPropertyElement {
-	pub(crate) name: String,
-	pub(crate) value: String,
-	pub(crate) is_signed: bool,
-	pub(crate) signature: Option<String>,
+/// The body of the `EncryptionRequest` packet
+#[derive(McSerialize, McDeserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EncryptionRequestSpec {
+	pub server_id: String,
+	pub public_key: Vec<u8>,
+	pub verify_token: Vec<u8>, // always 4 bytes for Notchian servers
+	pub should_authenticate: bool,
 }
 
-impl McDeserialize for LoginPropertyElement {
-	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
-		let name = String::mc_deserialize(deserializer)?;
-		let value = String::mc_deserialize(deserializer)?;
-		let is_signed = bool::mc_deserialize(deserializer)?;
-
+/// The body of the `EncryptionResponse` packet. Both the shared secret and verify token are encrypted
+/// with the server's public key.
+#[derive(McSerialize, McDeserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EncryptionResponseSpec {
+	pub shared_secret: Vec<u8>,
+	pub verify_token: Vec<u8>,
+}
 
-		let signature = if is_signed {
-			Some(String::mc_deserialize(deserializer)?)
-		} else {
-			None
-		};
+/// The body of the `LoginSuccess` packet
+#[derive(McSerialize, McDeserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LoginSuccessSpec {
+	pub profile: GameProfile,
+	pub strict_error_handling: bool,
+}
 
-		Ok(Self {
-			name,
-			value,
-			is_signed,
-			signature,
-		})
+impl LoginSuccessSpec {
+	pub fn new(profile: GameProfile) -> Self {
+		Self {
+			profile,
+			strict_error_handling: false,
+		}
 	}
 }
 
-#[derive(McSerialize, Debug, Clone, PartialEq, Eq, Hash)]
-pub struct LoginPluginSpec {
-	pub(crate) message_id: VarInt,
-	pub(crate) success: bool,
-	pub(crate) data: Option<Vec<u8>>,
+/// A single entry in the `RegistryData` packet. The data is left out if the client should use the entry
+/// from a data pack that both sides know.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryEntry {
+	pub id: Identifier,
+	pub data: Option<NbtCompound>, // network NBT, without a root name
 }
 
-impl McDeserialize for LoginPluginSpec {
-	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
-		let message_id = VarInt::mc_deserialize(deserializer)?;
-		let success = bool::mc_deserialize(deserializer)?;
+impl McSerialize for RegistryEntry {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.id.mc_serialize(serializer)?;
+		self.data.is_some().mc_serialize(serializer)?;
+
+		if let Some(data) = &self.data {
+			data.serialize_flavor(serializer, NbtFlavor::Network)?;
+		}
+
+		Ok(())
+	}
+}
 
-		let data = if success {
-			Some(Vec::<u8>::mc_deserialize(deserializer)?)
+impl McDeserialize for RegistryEntry {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let id = Identifier::mc_deserialize(deserializer)?;
+		let data = if bool::mc_deserialize(deserializer)? {
+			Some(NbtCompound::from_network(deserializer)?)
 		} else {
 			None
 		};
 
 		Ok(Self {
-			message_id,
-			success,
+			id,
 			data,
 		})
 	}
 }
 
-#[derive(McSerialize, Debug, Clone, PartialEq, Eq, Hash)]
-pub struct RemoveResourcePackSpec {
-	pub(crate) has_uuid: bool,
-	pub(crate) uuid: Option<Uuid>,
+/// A data pack known by either side, sent in the `KnownPacks` packets during configuration
+#[derive(McSerialize, McDeserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KnownPack {
+	pub namespace: String,
+	pub id: String,
+	pub version: String,
+}
+
+/// A single tag in the `UpdateTags` packet. The entries are the numeric ids of the registry entries
+/// that belong to the tag.
+#[derive(McSerialize, McDeserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TagSpec {
+	pub name: Identifier,
+	pub entries: Vec<VarInt>,
+}
+
+/// All of the tags for a single registry in the `UpdateTags` packet
+#[derive(McSerialize, McDeserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RegistryTagsSpec {
+	pub registry: Identifier,
+	pub tags: Vec<TagSpec>,
+}
+
+/// The body of the `LoginPlay` packet, which is sent by the server to move the client into the world.
+#[derive(McSerialize, McDeserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LoginPlaySpec {
+	pub entity_id: i32,
+	pub is_hardcore: bool,
+	pub dimension_names: Vec<String>,
+	pub max_players: VarInt,
+	pub view_distance: VarInt,
+	pub simulation_distance: VarInt,
+	pub reduced_debug_info: bool,
+	pub enable_respawn_screen: bool,
+	pub do_limited_crafting: bool,
+	pub dimension_type: VarInt,
+	pub dimension_name: String,
+	pub hashed_seed: i64,
+	pub game_mode: u8,
+	pub previous_game_mode: i8,
+	pub is_debug: bool,
+	pub is_flat: bool,
+	/// The dimension and position where the player last died
+	pub death_location: Option<(String, Position)>,
+	pub portal_cooldown: VarInt,
+	pub enforces_secure_chat: bool,
+}
+
+/// A line of extra information that the client adds to its crash and disconnect reports, sent in the
+/// `CustomReportDetails` packet since 1.21. The client keeps at most [ReportDetail::MAX_DETAILS].
+#[derive(McSerialize, McDeserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReportDetail {
+	pub title: String,
+	pub description: String,
+}
+
+impl ReportDetail {
+	pub const MAX_DETAILS: usize = 32;
+	pub const MAX_TITLE_LENGTH: usize = 128;
+	pub const MAX_DESCRIPTION_LENGTH: usize = 4096;
+
+	/// Create a detail, cutting the title and description down to the lengths the client accepts
+	pub fn new<T: Into<String>, D: Into<String>>(title: T, description: D) -> Self {
+		Self {
+			title: truncate(title.into(), Self::MAX_TITLE_LENGTH),
+			description: truncate(description.into(), Self::MAX_DESCRIPTION_LENGTH),
+		}
+	}
+}
+
+/// Cut a string down to `max` characters
+fn truncate(mut s: String, max: usize) -> String {
+	if let Some((index, _)) = s.char_indices().nth(max) {
+		s.truncate(index);
+	}
+
+	s
+}
+
+/// The links that the client shows in its pause menu, sent in the `ServerLinks` packet since 1.21. The
+/// client has its own labels for the built-in kinds, and reports made with
+/// [ServerLinkType::BugReport] also list the link.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServerLink {
+	pub label: ServerLinkLabel,
+	pub url: String,
+}
+
+impl ServerLink {
+	pub fn built_in<S: Into<String>>(kind: ServerLinkType, url: S) -> Self {
+		Self {
+			label: ServerLinkLabel::BuiltIn(kind),
+			url: url.into(),
+		}
+	}
+
+	pub fn custom<L: Into<NbtTextComponent>, S: Into<String>>(label: L, url: S) -> Self {
+		Self {
+			label: ServerLinkLabel::Custom(Box::new(label.into())),
+			url: url.into(),
+		}
+	}
+}
+
+impl McSerialize for ServerLink {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		match &self.label {
+			ServerLinkLabel::BuiltIn(kind) => {
+				true.mc_serialize(serializer)?;
+				VarInt(*kind as i32).mc_serialize(serializer)?;
+			}
+			ServerLinkLabel::Custom(label) => {
+				false.mc_serialize(serializer)?;
+				label.mc_serialize(serializer)?;
+			}
+		}
+
+		self.url.mc_serialize(serializer)
+	}
 }
 
-impl McDeserialize for RemoveResourcePackSpec {
+impl McDeserialize for ServerLink {
 	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
-		let has_uuid = bool::mc_deserialize(deserializer)?;
-		let uuid = if has_uuid {
-			Some(Uuid::mc_deserialize(deserializer)?)
+		let label = if bool::mc_deserialize(deserializer)? {
+			ServerLinkLabel::BuiltIn(ServerLinkType::try_from(VarInt::mc_deserialize(deserializer)?.0)?)
 		} else {
-			None
+			ServerLinkLabel::Custom(Box::new(NbtTextComponent::mc_deserialize(deserializer)?))
 		};
+		let url = String::mc_deserialize(deserializer)?;
 
 		Ok(Self {
-			has_uuid,
-			uuid,
+			label,
+			url,
 		})
 	}
 }
 
-#[derive(McSerialize, Debug, Clone, PartialEq, Eq, Hash)]
-pub struct AddResourcePackSpec {
-	pub(crate) uuid: Uuid,
-	pub(crate) url: String,
-	pub(crate) hash: String,
-	pub(crate) forced: bool,
-	pub(crate) has_prompt_message: bool,
-	pub(crate) prompt_message: Option<String>,
+/// The label of a [ServerLink]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ServerLinkLabel {
+	/// One of the kinds that the client has a translated label for
+	BuiltIn(ServerLinkType),
+	Custom(Box<NbtTextComponent>),
 }
 
-impl McDeserialize for AddResourcePackSpec {
-	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
-		let uuid = Uuid::mc_deserialize(deserializer)?;
-		let url = String::mc_deserialize(deserializer)?;
-		let hash = String::mc_deserialize(deserializer)?;
-		let forced = bool::mc_deserialize(deserializer)?;
-		let has_prompt_message = bool::mc_deserialize(deserializer)?;
-		let prompt_message = if has_prompt_message {
-			Some(String::mc_deserialize(deserializer)?)
-		} else {
-			None
+/// The kinds of [ServerLink] that the client has its own label for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ServerLinkType {
+	BugReport = 0,
+	CommunityGuidelines = 1,
+	Support = 2,
+	Status = 3,
+	Feedback = 4,
+	Community = 5,
+	Website = 6,
+	Forums = 7,
+	News = 8,
+	Announcements = 9,
+}
+
+impl TryFrom<i32> for ServerLinkType {
+	type Error = SerializingErr;
+
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		let kind = match value {
+			0 => ServerLinkType::BugReport,
+			1 => ServerLinkType::CommunityGuidelines,
+			2 => ServerLinkType::Support,
+			3 => ServerLinkType::Status,
+			4 => ServerLinkType::Feedback,
+			5 => ServerLinkType::Community,
+			6 => ServerLinkType::Website,
+			7 => ServerLinkType::Forums,
+			8 => ServerLinkType::News,
+			9 => ServerLinkType::Announcements,
+			_ => return Err(SerializingErr::UniqueFailure(format!("Unknown server link type {}", value))),
 		};
 
+		Ok(kind)
+	}
+}
+
+/// A block entity inside of a chunk, as sent in the `ChunkDataAndUpdateLight` packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkBlockEntity {
+	pub packed_xz: u8, // ((x & 15) << 4) | (z & 15)
+	pub y: i16,
+	pub block_entity_type: VarInt,
+	pub data: NbtCompound,
+}
+
+impl McSerialize for ChunkBlockEntity {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.packed_xz.mc_serialize(serializer)?;
+		self.y.mc_serialize(serializer)?;
+		self.block_entity_type.mc_serialize(serializer)?;
+		self.data.serialize_flavor(serializer, NbtFlavor::Network)?;
+
+		Ok(())
+	}
+}
+
+impl McDeserialize for ChunkBlockEntity {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let packed_xz = u8::mc_deserialize(deserializer)?;
+		let y = i16::mc_deserialize(deserializer)?;
+		let block_entity_type = VarInt::mc_deserialize(deserializer)?;
+		let data = NbtCompound::from_network(deserializer)?;
+
 		Ok(Self {
-			uuid,
-			url,
-			hash,
-			forced,
-			has_prompt_message,
-			prompt_message,
+			packed_xz,
+			y,
+			block_entity_type,
+			data,
 		})
 	}
 }
 
-#[derive(McSerialize, Debug, Clone, PartialEq, Eq)]
-pub struct LoginCookieResponseSpec {
-	key: String,
-	has_payload: bool,
-	payload_length: VarInt,
-	payload: Option<Vec<u8>>,
+/// The body of the `ChunkDataAndUpdateLight` packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkDataSpec {
+	pub chunk_x: i32,
+	pub chunk_z: i32,
+	pub heightmaps: NbtCompound, // network NBT, without a root name
+	pub data: Vec<u8>,
+	pub block_entities: Vec<ChunkBlockEntity>,
+	pub sky_light_mask: BitSet,
+	pub block_light_mask: BitSet,
+	pub empty_sky_light_mask: BitSet,
+	pub empty_block_light_mask: BitSet,
+	pub sky_light_arrays: Vec<Vec<u8>>, // each array is 2048 bytes
+	pub block_light_arrays: Vec<Vec<u8>>,
 }
 
-impl McDeserialize for LoginCookieResponseSpec {
+impl McSerialize for ChunkDataSpec {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.chunk_x.mc_serialize(serializer)?;
+		self.chunk_z.mc_serialize(serializer)?;
+		self.heightmaps.serialize_flavor(serializer, NbtFlavor::Network)?;
+		self.data.mc_serialize(serializer)?;
+		self.block_entities.mc_serialize(serializer)?;
+		self.sky_light_mask.mc_serialize(serializer)?;
+		self.block_light_mask.mc_serialize(serializer)?;
+		self.empty_sky_light_mask.mc_serialize(serializer)?;
+		self.empty_block_light_mask.mc_serialize(serializer)?;
+		self.sky_light_arrays.mc_serialize(serializer)?;
+		self.block_light_arrays.mc_serialize(serializer)?;
+
+		Ok(())
+	}
+}
+
+impl McDeserialize for ChunkDataSpec {
 	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
-		let key = String::mc_deserialize(deserializer)?;
-		let has_payload = bool::mc_deserialize(deserializer)?;
-		let payload_length = VarInt::mc_deserialize(deserializer)?;
-		let payload = if has_payload {
-			Some(Vec::<u8>::mc_deserialize(deserializer)?)
-		} else {
-			None
-		};
+		let chunk_x = i32::mc_deserialize(deserializer)?;
+		let chunk_z = i32::mc_deserialize(deserializer)?;
+		let heightmaps = NbtCompound::from_network(deserializer)?;
+		let data = Vec::<u8>::mc_deserialize(deserializer)?;
+		let block_entities = Vec::<ChunkBlockEntity>::mc_deserialize(deserializer)?;
+		let sky_light_mask = BitSet::mc_deserialize(deserializer)?;
+		let block_light_mask = BitSet::mc_deserialize(deserializer)?;
+		let empty_sky_light_mask = BitSet::mc_deserialize(deserializer)?;
+		let empty_block_light_mask = BitSet::mc_deserialize(deserializer)?;
+		let sky_light_arrays = Vec::<Vec<u8>>::mc_deserialize(deserializer)?;
+		let block_light_arrays = Vec::<Vec<u8>>::mc_deserialize(deserializer)?;
 
 		Ok(Self {
-			key,
-			has_payload,
-			payload_length,
-			payload,
+			chunk_x,
+			chunk_z,
+			heightmaps,
+			data,
+			block_entities,
+			sky_light_mask,
+			block_light_mask,
+			empty_sky_light_mask,
+			empty_block_light_mask,
+			sky_light_arrays,
+			block_light_arrays,
 		})
 	}
 }
 
-#[derive(McSerialize, Debug, Clone, PartialEq, Eq)]
-pub struct RegistryEntry {
-	pub id: String,
-	pub has_data: bool,
-	pub data: Option<NbtCompound>,
+/// The actions of a `PlayerInfoUpdate` packet, each of which adds a field to every entry in the packet.
+/// The fields are sent in the order of these bits.
+pub const PLAYER_INFO_ADD_PLAYER: u8 = 0x01;
+pub const PLAYER_INFO_INITIALIZE_CHAT: u8 = 0x02;
+pub const PLAYER_INFO_UPDATE_GAME_MODE: u8 = 0x04;
+pub const PLAYER_INFO_UPDATE_LISTED: u8 = 0x08;
+pub const PLAYER_INFO_UPDATE_LATENCY: u8 = 0x10;
+pub const PLAYER_INFO_UPDATE_DISPLAY_NAME: u8 = 0x20;
+
+/// A player in the `PlayerInfoUpdate` packet. Each field belongs to an action, and is only sent if the
+/// packet has that action.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PlayerInfoEntry {
+	pub uuid: Uuid,
+	/// The name and properties, for [PLAYER_INFO_ADD_PLAYER]
+	pub profile: Option<GameProfile>,
+	/// The chat session, or `None` inside to remove it, for [PLAYER_INFO_INITIALIZE_CHAT]
+	pub chat_session: Option<Option<ChatSession>>,
+	/// For [PLAYER_INFO_UPDATE_GAME_MODE]
+	pub game_mode: Option<VarInt>,
+	/// Whether the player is shown in the tab list, for [PLAYER_INFO_UPDATE_LISTED]
+	pub listed: Option<bool>,
+	/// The latency in milliseconds, for [PLAYER_INFO_UPDATE_LATENCY]
+	pub latency: Option<VarInt>,
+	/// The name shown instead of the player's name, or `None` inside to show the name again, for
+	/// [PLAYER_INFO_UPDATE_DISPLAY_NAME]
+	pub display_name: Option<Option<NbtTextComponent>>,
 }
 
-impl McDeserialize for RegistryEntry {
+impl PlayerInfoEntry {
+	pub fn new(uuid: Uuid) -> Self {
+		Self {
+			uuid,
+			..Default::default()
+		}
+	}
+
+	/// The actions for the fields that are set
+	pub fn actions(&self) -> u8 {
+		let mut actions = 0;
+
+		for (set, action) in [
+			(self.profile.is_some(), PLAYER_INFO_ADD_PLAYER),
+			(self.chat_session.is_some(), PLAYER_INFO_INITIALIZE_CHAT),
+			(self.game_mode.is_some(), PLAYER_INFO_UPDATE_GAME_MODE),
+			(self.listed.is_some(), PLAYER_INFO_UPDATE_LISTED),
+			(self.latency.is_some(), PLAYER_INFO_UPDATE_LATENCY),
+			(self.display_name.is_some(), PLAYER_INFO_UPDATE_DISPLAY_NAME),
+		] {
+			if set {
+				actions |= action;
+			}
+		}
+
+		actions
+	}
+}
+
+/// The body of the `PlayerInfoUpdate` packet. Every entry must have the fields for `actions`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerInfoUpdateSpec {
+	pub actions: u8,
+	pub entries: Vec<PlayerInfoEntry>,
+}
+
+/// Get the field of an entry for an action, failing if it wasn't set
+fn player_info_field<'a, T>(field: &'a Option<T>, uuid: &Uuid, action: &str) -> Result<&'a T, SerializingErr> {
+	field.as_ref().ok_or_else(|| SerializingErr::UniqueFailure(format!("Player {} is missing the {} of the packet", uuid, action)))
+}
+
+impl McSerialize for PlayerInfoUpdateSpec {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.actions.mc_serialize(serializer)?;
+		VarInt(self.entries.len() as i32).mc_serialize(serializer)?;
+
+		for entry in &self.entries {
+			entry.uuid.mc_serialize(serializer)?;
+
+			if self.actions & PLAYER_INFO_ADD_PLAYER != 0 {
+				let profile = player_info_field(&entry.profile, &entry.uuid, "profile")?;
+				profile.name.mc_serialize(serializer)?;
+				profile.properties.mc_serialize(serializer)?;
+			}
+			if self.actions & PLAYER_INFO_INITIALIZE_CHAT != 0 {
+				player_info_field(&entry.chat_session, &entry.uuid, "chat session")?.mc_serialize(serializer)?;
+			}
+			if self.actions & PLAYER_INFO_UPDATE_GAME_MODE != 0 {
+				player_info_field(&entry.game_mode, &entry.uuid, "game mode")?.mc_serialize(serializer)?;
+			}
+			if self.actions & PLAYER_INFO_UPDATE_LISTED != 0 {
+				player_info_field(&entry.listed, &entry.uuid, "listed flag")?.mc_serialize(serializer)?;
+			}
+			if self.actions & PLAYER_INFO_UPDATE_LATENCY != 0 {
+				player_info_field(&entry.latency, &entry.uuid, "latency")?.mc_serialize(serializer)?;
+			}
+			if self.actions & PLAYER_INFO_UPDATE_DISPLAY_NAME != 0 {
+				player_info_field(&entry.display_name, &entry.uuid, "display name")?.mc_serialize(serializer)?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl McDeserialize for PlayerInfoUpdateSpec {
 	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
-		let id = String::mc_deserialize(deserializer)?;
-		let has_data = bool::mc_deserialize(deserializer)?;
-		let data = if has_data {
-			Some(NbtCompound::mc_deserialize(deserializer)?)
-		} else {
-			None
-		};
+		let actions = u8::mc_deserialize(deserializer)?;
+		let count = VarInt::mc_deserialize(deserializer)?.0;
+
+		if count < 0 {
+			return Err(SerializingErr::UniqueFailure(format!("Negative number of players: {}", count)));
+		}
+
+		let mut entries = vec![];
+
+		for _ in 0..count {
+			let mut entry = PlayerInfoEntry::new(Uuid::mc_deserialize(deserializer)?);
+
+			if actions & PLAYER_INFO_ADD_PLAYER != 0 {
+				let name = String::mc_deserialize(deserializer)?;
+				let mut profile = GameProfile::new(entry.uuid, name);
+				profile.properties = Vec::<ProfileProperty>::mc_deserialize(deserializer)?;
+				entry.profile = Some(profile);
+			}
+			if actions & PLAYER_INFO_INITIALIZE_CHAT != 0 {
+				entry.chat_session = Some(Option::<ChatSession>::mc_deserialize(deserializer)?);
+			}
+			if actions & PLAYER_INFO_UPDATE_GAME_MODE != 0 {
+				entry.game_mode = Some(VarInt::mc_deserialize(deserializer)?);
+			}
+			if actions & PLAYER_INFO_UPDATE_LISTED != 0 {
+				entry.listed = Some(bool::mc_deserialize(deserializer)?);
+			}
+			if actions & PLAYER_INFO_UPDATE_LATENCY != 0 {
+				entry.latency = Some(VarInt::mc_deserialize(deserializer)?);
+			}
+			if actions & PLAYER_INFO_UPDATE_DISPLAY_NAME != 0 {
+				entry.display_name = Some(Option::<NbtTextComponent>::mc_deserialize(deserializer)?);
+			}
+
+			entries.push(entry);
+		}
 
 		Ok(Self {
-			id,
-			has_data,
-			data,
+			actions,
+			entries,
 		})
 	}
-}
\ No newline at end of file
+}
@@ -24,7 +24,7 @@ impl PacketState {
     pub fn from_id(id: u8) -> Option<PacketState> {
         match id {
             1 => Some(PacketState::STATUS),
-            2 => Some(PacketState::LOGIN),
+            2 | 3 => Some(PacketState::LOGIN), // transfers log in like any other client
             _ => None // others are unknown at this time
         }
     }
@@ -39,6 +39,43 @@ impl PacketState {
     }
 }
 
+/// The reason a client gives for connecting in its handshake, sent as the `next_state` field
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
+pub enum HandshakeIntent {
+	STATUS,
+	LOGIN,
+	/// The client was sent here by another server with a Transfer packet, added in 1.20.5
+	TRANSFER
+}
+
+impl HandshakeIntent {
+	/// Converts the `next_state` of a handshake to an intent. Returns None if the id is unknown.
+	pub fn from_id(id: i32) -> Option<HandshakeIntent> {
+		match id {
+			1 => Some(HandshakeIntent::STATUS),
+			2 => Some(HandshakeIntent::LOGIN),
+			3 => Some(HandshakeIntent::TRANSFER),
+			_ => None
+		}
+	}
+
+	pub fn get_id(&self) -> i32 {
+		match self {
+			HandshakeIntent::STATUS => 1,
+			HandshakeIntent::LOGIN => 2,
+			HandshakeIntent::TRANSFER => 3,
+		}
+	}
+
+	/// The state the connection moves to after the handshake
+	pub fn state(&self) -> PacketState {
+		match self {
+			HandshakeIntent::STATUS => PacketState::STATUS,
+			HandshakeIntent::LOGIN | HandshakeIntent::TRANSFER => PacketState::LOGIN,
+		}
+	}
+}
+
 #[macro_use]
 mod macros {
     /// Used to define the minecraft packet protocol. This includes, the name, packet ID, state and
@@ -58,12 +95,13 @@ mod macros {
             $(
                 $(
                     $(
-                        #[derive(Debug, Clone, PartialEq, Eq)]
+                        #[derive(Debug, Clone, PartialEq)] // not Eq, since some packets contain floats
                         pub struct $name_body { // The body struct of the packet
                             $(pub(crate) $field: $t),*
                         }
                         
                         impl $name_body {
+                            #[allow(clippy::too_many_arguments)] // some packets just have a lot of fields
                             pub fn new($($field: $t),*) -> Self {
                                 Self {
                                     $($field),*
@@ -75,7 +113,7 @@ mod macros {
                         impl McDeserialize for $name_body {
                             fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
                                 let s = Self {
-                                    $($field: <$t>::mc_deserialize(deserializer)?,)*
+                                    $($field: <$t>::mc_deserialize(deserializer).map_err(|e| e.while_reading(concat!(stringify!($name_body), ".", stringify!($field)), deserializer))?,)*
                                 };
         
                                 Ok(s)
@@ -110,7 +148,7 @@ mod macros {
             )*
             
             $crate::as_item!( // weird workaround from mcproto-rs
-                #[derive(Debug, Clone, PartialEq, Eq)]
+                #[derive(Debug, Clone, PartialEq)]
                 pub enum Packet {
                     $($($($name($name_body),)*)*)*
                 }
@@ -145,10 +183,8 @@ mod macros {
                     
                     let packet_id = self.packet_id();
                     
-                    let bytes = packet_id.to_bytes(); // getting the bytes is kind of expensive, so cache it
-                    
-                    VarInt(length_serializer.output.len() as i32 + bytes.len() as i32).mc_serialize(serializer)?;
-                    bytes.mc_serialize(serializer)?;
+                    VarInt(length_serializer.output.len() as i32 + packet_id.encoded_len() as i32).write_to(serializer);
+                    packet_id.write_to(serializer);
                     serializer.merge(length_serializer);
                     
             
@@ -173,11 +209,10 @@ mod macros {
                                     match packet_id.0 {
                                         $(
                                             $packetID => {
-                                                let a = $name_body::mc_deserialize(&mut sub);
-
-                                                if let Ok(a) = a {
-                                                    return Ok(Packet::$name(a));
-                                                }
+                                                return match $name_body::mc_deserialize(&mut sub) {
+                                                    Ok(a) => Ok(Packet::$name(a)),
+                                                    Err(e) => Err(e.in_packet(state, packet_direction, packet_id.0, &sub)),
+                                                };
                                             }
                                         )*
                                         
@@ -188,7 +223,7 @@ mod macros {
                         }
                     )*
                     
-                    return Err(SerializingErr::UniqueFailure("Could not find matching type.".to_string()));
+                    Err(SerializingErr::UniqueFailure("Unknown packet id".to_string()).in_packet(state, packet_direction, packet_id.0, &sub))
                 }
             }
         };
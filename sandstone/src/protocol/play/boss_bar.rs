@@ -0,0 +1,362 @@
+//! Boss bars at the top of the screen. Each bar has a UUID that every later packet refers to, so a
+//! [BossBar] keeps the state of a bar and turns changes into the packets that update it. One bar can be
+//! shown to any number of players. See [here](https://wiki.vg/Protocol#Boss_Bar) for more.
+
+use uuid::Uuid;
+
+use crate::network::client::CraftClient;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::{BossBarBody, Packet};
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::chat::{NbtTextComponent, TextComponent};
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+/// Darkens the sky, like the Wither
+pub const DARKEN_SKY: u8 = 0x01;
+/// Plays the end music, like the Ender Dragon
+pub const PLAY_BOSS_MUSIC: u8 = 0x02;
+/// Adds fog around the player
+pub const CREATE_FOG: u8 = 0x04;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BossBarColor {
+	#[default]
+	Pink = 0,
+	Blue = 1,
+	Red = 2,
+	Green = 3,
+	Yellow = 4,
+	Purple = 5,
+	White = 6,
+}
+
+impl TryFrom<i32> for BossBarColor {
+	type Error = SerializingErr;
+
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		let color = match value {
+			0 => BossBarColor::Pink,
+			1 => BossBarColor::Blue,
+			2 => BossBarColor::Red,
+			3 => BossBarColor::Green,
+			4 => BossBarColor::Yellow,
+			5 => BossBarColor::Purple,
+			6 => BossBarColor::White,
+			_ => return Err(SerializingErr::UniqueFailure(format!("Unknown boss bar color {}", value))),
+		};
+
+		Ok(color)
+	}
+}
+
+/// How many notches the bar is split into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BossBarDivision {
+	#[default]
+	None = 0,
+	Notches6 = 1,
+	Notches10 = 2,
+	Notches12 = 3,
+	Notches20 = 4,
+}
+
+impl TryFrom<i32> for BossBarDivision {
+	type Error = SerializingErr;
+
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		let division = match value {
+			0 => BossBarDivision::None,
+			1 => BossBarDivision::Notches6,
+			2 => BossBarDivision::Notches10,
+			3 => BossBarDivision::Notches12,
+			4 => BossBarDivision::Notches20,
+			_ => return Err(SerializingErr::UniqueFailure(format!("Unknown boss bar division {}", value))),
+		};
+
+		Ok(division)
+	}
+}
+
+/// What a `BossBar` packet does to the bar with its UUID
+#[derive(Debug, Clone, PartialEq)]
+pub enum BossBarAction {
+	Add {
+		title: NbtTextComponent,
+		progress: f32,
+		color: BossBarColor,
+		division: BossBarDivision,
+		flags: u8,
+	},
+	Remove,
+	UpdateProgress(f32),
+	UpdateTitle(NbtTextComponent),
+	UpdateStyle(BossBarColor, BossBarDivision),
+	UpdateFlags(u8),
+}
+
+impl McSerialize for BossBarAction {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		match self {
+			BossBarAction::Add { title, progress, color, division, flags } => {
+				VarInt(0).mc_serialize(serializer)?;
+				title.mc_serialize(serializer)?;
+				progress.mc_serialize(serializer)?;
+				VarInt(*color as i32).mc_serialize(serializer)?;
+				VarInt(*division as i32).mc_serialize(serializer)?;
+				flags.mc_serialize(serializer)
+			}
+			BossBarAction::Remove => VarInt(1).mc_serialize(serializer),
+			BossBarAction::UpdateProgress(progress) => {
+				VarInt(2).mc_serialize(serializer)?;
+				progress.mc_serialize(serializer)
+			}
+			BossBarAction::UpdateTitle(title) => {
+				VarInt(3).mc_serialize(serializer)?;
+				title.mc_serialize(serializer)
+			}
+			BossBarAction::UpdateStyle(color, division) => {
+				VarInt(4).mc_serialize(serializer)?;
+				VarInt(*color as i32).mc_serialize(serializer)?;
+				VarInt(*division as i32).mc_serialize(serializer)
+			}
+			BossBarAction::UpdateFlags(flags) => {
+				VarInt(5).mc_serialize(serializer)?;
+				flags.mc_serialize(serializer)
+			}
+		}
+	}
+}
+
+impl McDeserialize for BossBarAction {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let action = match VarInt::mc_deserialize(deserializer)?.0 {
+			0 => BossBarAction::Add {
+				title: NbtTextComponent::mc_deserialize(deserializer)?,
+				progress: f32::mc_deserialize(deserializer)?,
+				color: VarInt::mc_deserialize(deserializer)?.0.try_into()?,
+				division: VarInt::mc_deserialize(deserializer)?.0.try_into()?,
+				flags: u8::mc_deserialize(deserializer)?,
+			},
+			1 => BossBarAction::Remove,
+			2 => BossBarAction::UpdateProgress(f32::mc_deserialize(deserializer)?),
+			3 => BossBarAction::UpdateTitle(NbtTextComponent::mc_deserialize(deserializer)?),
+			4 => BossBarAction::UpdateStyle(VarInt::mc_deserialize(deserializer)?.0.try_into()?, VarInt::mc_deserialize(deserializer)?.0.try_into()?),
+			5 => BossBarAction::UpdateFlags(u8::mc_deserialize(deserializer)?),
+			action => return Err(SerializingErr::UniqueFailure(format!("Unknown boss bar action {}", action))),
+		};
+
+		Ok(action)
+	}
+}
+
+/// A boss bar and the changes that haven't been sent yet, see the [module docs](self)
+///
+/// ```no_run
+/// # use sandstone::network::client::CraftClient;
+/// # use sandstone::network::network_error::NetworkError;
+/// # use sandstone::protocol::play::boss_bar::{BossBar, BossBarColor, BossBarDivision};
+/// # use sandstone::protocol_types::datatypes::chat::TextComponent;
+/// # async fn run(players: &mut [CraftClient]) -> Result<(), NetworkError> {
+/// let mut bar = BossBar::new(TextComponent::new("Wave 1"), BossBarColor::Red, BossBarDivision::Notches10);
+///
+/// for player in players.iter_mut() {
+///     bar.show(player).await?;
+/// }
+///
+/// bar.set_progress(0.5);
+/// bar.set_title(TextComponent::new("Wave 1 - half way"));
+/// bar.update(players.iter_mut()).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BossBar {
+	uuid: Uuid,
+	title: TextComponent,
+	progress: f32,
+	color: BossBarColor,
+	division: BossBarDivision,
+	flags: u8,
+	/// The changes since the last update, in the order the packets are sent
+	dirty_progress: bool,
+	dirty_title: bool,
+	dirty_style: bool,
+	dirty_flags: bool,
+}
+
+impl BossBar {
+	/// A full bar with a random UUID
+	pub fn new(title: TextComponent, color: BossBarColor, division: BossBarDivision) -> Self {
+		Self::with_uuid(Uuid::from_u128(rand::random()), title, color, division)
+	}
+
+	pub fn with_uuid(uuid: Uuid, title: TextComponent, color: BossBarColor, division: BossBarDivision) -> Self {
+		Self {
+			uuid,
+			title,
+			progress: 1.0,
+			color,
+			division,
+			flags: 0,
+			dirty_progress: false,
+			dirty_title: false,
+			dirty_style: false,
+			dirty_flags: false,
+		}
+	}
+
+	pub fn uuid(&self) -> Uuid {
+		self.uuid
+	}
+
+	pub fn title(&self) -> &TextComponent {
+		&self.title
+	}
+
+	pub fn progress(&self) -> f32 {
+		self.progress
+	}
+
+	pub fn color(&self) -> BossBarColor {
+		self.color
+	}
+
+	pub fn division(&self) -> BossBarDivision {
+		self.division
+	}
+
+	pub fn flags(&self) -> u8 {
+		self.flags
+	}
+
+	/// How full the bar is, from 0 to 1
+	pub fn set_progress(&mut self, progress: f32) {
+		let progress = progress.clamp(0.0, 1.0);
+		self.dirty_progress |= progress != self.progress;
+		self.progress = progress;
+	}
+
+	pub fn set_title(&mut self, title: TextComponent) {
+		self.dirty_title |= title != self.title;
+		self.title = title;
+	}
+
+	pub fn set_style(&mut self, color: BossBarColor, division: BossBarDivision) {
+		self.dirty_style |= (color, division) != (self.color, self.division);
+		self.color = color;
+		self.division = division;
+	}
+
+	/// Set the flags, which are [DARKEN_SKY], [PLAY_BOSS_MUSIC] and [CREATE_FOG]
+	pub fn set_flags(&mut self, flags: u8) {
+		self.dirty_flags |= flags != self.flags;
+		self.flags = flags;
+	}
+
+	fn packet(&self, action: BossBarAction) -> Packet {
+		Packet::BossBar(BossBarBody::new(self.uuid, action))
+	}
+
+	/// The packet that shows the bar as it is now
+	pub fn add_packet(&self) -> Packet {
+		self.packet(BossBarAction::Add {
+			title: NbtTextComponent::new(self.title.clone()),
+			progress: self.progress,
+			color: self.color,
+			division: self.division,
+			flags: self.flags,
+		})
+	}
+
+	/// The packet that hides the bar
+	pub fn remove_packet(&self) -> Packet {
+		self.packet(BossBarAction::Remove)
+	}
+
+	/// The packets for everything that changed since the last call, which should be sent to every player
+	/// who can see the bar
+	pub fn take_updates(&mut self) -> Vec<Packet> {
+		let mut packets = vec![];
+
+		if std::mem::take(&mut self.dirty_progress) {
+			packets.push(self.packet(BossBarAction::UpdateProgress(self.progress)));
+		}
+
+		if std::mem::take(&mut self.dirty_title) {
+			packets.push(self.packet(BossBarAction::UpdateTitle(NbtTextComponent::new(self.title.clone()))));
+		}
+
+		if std::mem::take(&mut self.dirty_style) {
+			packets.push(self.packet(BossBarAction::UpdateStyle(self.color, self.division)));
+		}
+
+		if std::mem::take(&mut self.dirty_flags) {
+			packets.push(self.packet(BossBarAction::UpdateFlags(self.flags)));
+		}
+
+		packets
+	}
+
+	/// Show the bar to a player, as it is now
+	pub async fn show(&self, client: &mut CraftClient) -> Result<(), NetworkError> {
+		client.send_packet(self.add_packet()).await
+	}
+
+	/// Hide the bar from a player
+	pub async fn hide(&self, client: &mut CraftClient) -> Result<(), NetworkError> {
+		client.send_packet(self.remove_packet()).await
+	}
+
+	/// Send the changes since the last update to every player who can see the bar
+	pub async fn update<'a, I: IntoIterator<Item = &'a mut CraftClient>>(&mut self, clients: I) -> Result<(), NetworkError> {
+		let packets = self.take_updates();
+
+		if packets.is_empty() {
+			return Ok(());
+		}
+
+		for client in clients {
+			for packet in &packets {
+				client.queue_packet(packet.clone())?;
+			}
+
+			client.flush().await?;
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use uuid::Uuid;
+
+	use crate::protocol::packets::Packet;
+	use crate::protocol::play::boss_bar::{BossBar, BossBarAction, BossBarColor, BossBarDivision, DARKEN_SKY};
+	use crate::protocol_types::datatypes::chat::TextComponent;
+
+	fn action(packet: &Packet) -> &BossBarAction {
+		match packet {
+			Packet::BossBar(body) => &body.action,
+			p => panic!("Expected a boss bar packet, got {:?}", p),
+		}
+	}
+
+	#[test]
+	fn test_boss_bar() {
+		let mut bar = BossBar::with_uuid(Uuid::from_u128(1), TextComponent::new("Wither"), BossBarColor::Purple, BossBarDivision::None);
+		assert!(matches!(action(&bar.add_packet()), BossBarAction::Add { progress, .. } if *progress == 1.0));
+		assert!(bar.take_updates().is_empty());
+
+		bar.set_progress(1.5); // clamped, so nothing changed
+		bar.set_flags(DARKEN_SKY);
+		bar.set_progress(0.25);
+		bar.set_style(BossBarColor::Purple, BossBarDivision::None);
+
+		let updates = bar.take_updates();
+		assert_eq!(updates.iter().map(action).collect::<Vec<_>>(), vec![&BossBarAction::UpdateProgress(0.25), &BossBarAction::UpdateFlags(DARKEN_SKY)]);
+		assert!(bar.take_updates().is_empty());
+		assert_eq!(action(&bar.remove_packet()), &BossBarAction::Remove);
+	}
+}
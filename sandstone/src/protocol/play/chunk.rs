@@ -0,0 +1,453 @@
+//! Builds the `ChunkDataAndUpdateLight` packet, which sends a 16 block wide column of the world. The
+//! column is split into 16 block tall sections from the bottom of the world up, each with a
+//! [PalettedContainer] of block states and one of biomes. Light is sent per section too, with an extra
+//! section below and above the world so that light can spill over the edges.
+//!
+//! See [here](https://wiki.vg/Chunk_Format) for more.
+
+use crate::protocol::packets::{ChunkDataAndUpdateLightBody, Packet};
+use crate::protocol::packets::packet_component::{ChunkBlockEntity, ChunkDataSpec};
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::bitset::BitSet;
+use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtLongArray};
+use crate::protocol_types::datatypes::paletted_container::{ContainerKind, longs_needed, PalettedContainer, pack, unpack};
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+/// The block state id of air
+pub const AIR: u32 = 0;
+
+/// The number of sections in an overworld column, from y = -64 up to y = 320
+pub const OVERWORLD_SECTIONS: usize = 24;
+
+/// The bytes in the light array of a section, half a byte for each block
+pub const LIGHT_ARRAY_LENGTH: usize = 2048;
+
+/// The heightmap of the highest block that blocks motion or contains a fluid, which the client uses for
+/// rain and snow
+pub const MOTION_BLOCKING: &str = "MOTION_BLOCKING";
+/// The heightmap of the highest block that isn't air
+pub const WORLD_SURFACE: &str = "WORLD_SURFACE";
+
+/// A 16x16x16 section of a column
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChunkSection {
+	/// The number of blocks that aren't air, the client skips ticking sections without any
+	pub block_count: i16,
+	pub block_states: PalettedContainer,
+	pub biomes: PalettedContainer,
+}
+
+impl ChunkSection {
+	/// A section made of one block and one biome
+	pub fn filled(block_state: u32, biome: u32) -> Self {
+		Self {
+			block_count: if block_state == AIR { 0 } else { 4096 },
+			block_states: PalettedContainer::single(ContainerKind::BLOCK_STATES, block_state),
+			biomes: PalettedContainer::single(ContainerKind::BIOMES, biome),
+		}
+	}
+
+	/// A section of air in a biome
+	pub fn empty(biome: u32) -> Self {
+		Self::filled(AIR, biome)
+	}
+
+	pub fn block(&self, x: usize, y: usize, z: usize) -> u32 {
+		self.block_states.get(x, y, z)
+	}
+
+	/// Set the block state at a position in the section, keeping the block count up to date. Only
+	/// [AIR] counts as air, use [ChunkSection::recount] if other states should too.
+	pub fn set_block(&mut self, x: usize, y: usize, z: usize, block_state: u32) -> u32 {
+		let old = self.block_states.set(x, y, z, block_state);
+		self.block_count += (block_state != AIR) as i16 - (old != AIR) as i16;
+		old
+	}
+
+	/// The biome at a position in the section, each biome covers 4x4x4 blocks
+	pub fn biome(&self, x: usize, y: usize, z: usize) -> u32 {
+		self.biomes.get(x / 4, y / 4, z / 4)
+	}
+
+	/// Set the biome of the 4x4x4 cell that a position is in, returning the biome that was there
+	pub fn set_biome(&mut self, x: usize, y: usize, z: usize, biome: u32) -> u32 {
+		self.biomes.set(x / 4, y / 4, z / 4, biome)
+	}
+
+	/// Set the biome of the whole section
+	pub fn fill_biome(&mut self, biome: u32) {
+		self.biomes.fill(biome);
+	}
+
+	/// Count the blocks again, for block states like cave air that also count as air
+	pub fn recount<F: Fn(u32) -> bool>(&mut self, is_air: F) {
+		self.block_count = self.block_states.count(|id| !is_air(id)) as i16;
+	}
+
+	pub fn read(deserializer: &mut McDeserializer) -> Result<Self, SerializingErr> {
+		let block_count = i16::mc_deserialize(deserializer)?;
+		let block_states = PalettedContainer::read(deserializer, ContainerKind::BLOCK_STATES)?;
+		let biomes = PalettedContainer::read(deserializer, ContainerKind::BIOMES)?;
+
+		Ok(Self {
+			block_count,
+			block_states,
+			biomes,
+		})
+	}
+}
+
+impl McSerialize for ChunkSection {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.block_count.mc_serialize(serializer)?;
+		self.block_states.mc_serialize(serializer)?;
+		self.biomes.mc_serialize(serializer)?;
+
+		Ok(())
+	}
+}
+
+/// Read the sections from the `data` of a [ChunkDataSpec]. The number of sections depends on the height
+/// of the dimension, which the packet doesn't include.
+pub fn read_sections(data: &[u8], count: usize) -> Result<Vec<ChunkSection>, SerializingErr> {
+	let mut deserializer = McDeserializer::new(data);
+	let sections = (0..count).map(|_| ChunkSection::read(&mut deserializer)).collect::<Result<Vec<_>, _>>()?;
+
+	if !deserializer.is_at_end() {
+		return Err(SerializingErr::UniqueFailure(format!("{} bytes left over after {} chunk sections", data.len() - deserializer.index, count)));
+	}
+
+	Ok(sections)
+}
+
+/// Pack a heightmap into the long array that the client expects. `heights` has one entry for each column
+/// of the chunk, in `z * 16 + x` order, counted up from the bottom of the world.
+pub fn pack_heightmap(heights: &[u32; 256], world_height: u32) -> Result<NbtLongArray, SerializingErr> {
+	let bits = heightmap_bits(world_height)?;
+
+	if let Some(height) = heights.iter().find(|h| **h > world_height) {
+		return Err(SerializingErr::UniqueFailure(format!("Height {} is above the world height of {}", height, world_height)));
+	}
+
+	Ok(NbtLongArray::new(pack(heights, bits)))
+}
+
+/// Read a heightmap back out of its long array, see [pack_heightmap]
+pub fn unpack_heightmap(packed: &NbtLongArray, world_height: u32) -> Result<[u32; 256], SerializingErr> {
+	let bits = heightmap_bits(world_height)?;
+
+	if packed.len() != longs_needed(256, bits) {
+		return Err(SerializingErr::UniqueFailure(format!("Expected {} longs in a heightmap of {} bit heights, got {}", longs_needed(256, bits), bits, packed.len())));
+	}
+
+	let mut heights = [0; 256];
+	heights.copy_from_slice(&unpack(&packed.list, bits, 256));
+	Ok(heights)
+}
+
+/// Enough bits for every height from 0 up to and including the height of the world
+fn heightmap_bits(world_height: u32) -> Result<u8, SerializingErr> {
+	if world_height == 0 {
+		return Err(SerializingErr::UniqueFailure("A heightmap needs a world height above 0".to_string()));
+	}
+
+	Ok((u32::BITS - world_height.leading_zeros()) as u8)
+}
+
+/// The light of one kind for each section of a column, plus one section below and one above. Sections
+/// can be left out, in which case the client keeps the light it already had for them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChunkLight {
+	sections: Vec<Option<Vec<u8>>>,
+}
+
+impl ChunkLight {
+	/// Light for a column of `sections`, without any sections set
+	pub fn new(sections: usize) -> Self {
+		Self {
+			sections: vec![None; sections + 2],
+		}
+	}
+
+	/// Full brightness everywhere, as sky light above ground in the day
+	pub fn full(sections: usize) -> Self {
+		Self {
+			sections: vec![Some(vec![0xFF; LIGHT_ARRAY_LENGTH]); sections + 2],
+		}
+	}
+
+	/// Set the light of a section, where 0 is the section below the world. Each block has 4 bits, in the
+	/// same order as block states.
+	pub fn set(&mut self, section: usize, light: Option<Vec<u8>>) -> Result<(), SerializingErr> {
+		if let Some(light) = &light {
+			if light.len() != LIGHT_ARRAY_LENGTH {
+				return Err(SerializingErr::UniqueFailure(format!("A light array has {} bytes, not {}", light.len(), LIGHT_ARRAY_LENGTH)));
+			}
+		}
+
+		let len = self.sections.len();
+		*self.sections.get_mut(section)
+			.ok_or_else(|| SerializingErr::UniqueFailure(format!("Light section {} is out of bounds for {} sections", section, len)))? = light;
+
+		Ok(())
+	}
+
+	pub fn get(&self, section: usize) -> Option<&[u8]> {
+		self.sections.get(section).and_then(|light| light.as_deref())
+	}
+
+	/// The number of light sections, 2 more than the sections of the column
+	pub fn len(&self) -> usize {
+		self.sections.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.sections.is_empty()
+	}
+
+	/// The light mask, the empty light mask and the arrays for the sections in the light mask. Sections
+	/// that are completely dark go in the empty mask instead of being sent.
+	pub fn masks(&self) -> (BitSet, BitSet, Vec<Vec<u8>>) {
+		let mut mask = BitSet::new();
+		let mut empty = BitSet::new();
+		let mut arrays = vec![];
+
+		for (i, light) in self.sections.iter().enumerate() {
+			match light {
+				Some(light) if light.iter().all(|b| *b == 0) => empty.set(i, true),
+				Some(light) => {
+					mask.set(i, true);
+					arrays.push(light.clone());
+				}
+				None => {}
+			}
+		}
+
+		(mask, empty, arrays)
+	}
+
+	/// Read the light back out of the masks and arrays of a [ChunkDataSpec]
+	pub fn from_masks(sections: usize, mask: &BitSet, empty: &BitSet, arrays: &[Vec<u8>]) -> Result<Self, SerializingErr> {
+		let mut light = Self::new(sections);
+		let mut arrays = arrays.iter();
+
+		for i in 0..light.len() {
+			if mask.get(i) {
+				let array = arrays.next()
+					.ok_or_else(|| SerializingErr::UniqueFailure("There are fewer light arrays than bits in the light mask".to_string()))?;
+				light.set(i, Some(array.clone()))?;
+			} else if empty.get(i) {
+				light.set(i, Some(vec![0; LIGHT_ARRAY_LENGTH]))?;
+			}
+		}
+
+		Ok(light)
+	}
+}
+
+/// Builds the [ChunkDataSpec] of a column
+///
+/// ```
+/// # use sandstone::protocol::play::chunk::{ChunkDataBuilder, ChunkLight, ChunkSection, MOTION_BLOCKING, OVERWORLD_SECTIONS};
+/// let mut sections = vec![ChunkSection::empty(0); OVERWORLD_SECTIONS];
+/// sections[0] = ChunkSection::filled(1, 0); // stone
+///
+/// let spec = ChunkDataBuilder::new(0, 0)
+///     .sections(sections)
+///     .heightmap(MOTION_BLOCKING, &[16; 256], 384)
+///     .sky_light(ChunkLight::full(OVERWORLD_SECTIONS))
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(spec.sky_light_arrays.len(), OVERWORLD_SECTIONS + 2);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkDataBuilder {
+	chunk_x: i32,
+	chunk_z: i32,
+	sections: Vec<ChunkSection>,
+	heightmaps: NbtCompound,
+	/// Heightmaps that are packed when the spec is built, along with the height of the world
+	unpacked_heightmaps: Vec<(String, Box<[u32; 256]>, u32)>,
+	block_entities: Vec<ChunkBlockEntity>,
+	sky_light: Option<ChunkLight>,
+	block_light: Option<ChunkLight>,
+}
+
+impl ChunkDataBuilder {
+	pub fn new(chunk_x: i32, chunk_z: i32) -> Self {
+		Self {
+			chunk_x,
+			chunk_z,
+			sections: vec![],
+			heightmaps: NbtCompound::new::<String>(None),
+			unpacked_heightmaps: vec![],
+			block_entities: vec![],
+			sky_light: None,
+			block_light: None,
+		}
+	}
+
+	/// The sections from the bottom of the world up. There must be one for every section of the
+	/// dimension.
+	pub fn sections(mut self, sections: Vec<ChunkSection>) -> Self {
+		self.sections = sections;
+		self
+	}
+
+	pub fn section(mut self, section: ChunkSection) -> Self {
+		self.sections.push(section);
+		self
+	}
+
+	/// Add a heightmap, see [pack_heightmap]. The client only uses [MOTION_BLOCKING] and
+	/// [WORLD_SURFACE]. Heights that can't be packed fail when the spec is built.
+	pub fn heightmap(mut self, name: &str, heights: &[u32; 256], world_height: u32) -> Self {
+		self.unpacked_heightmaps.push((name.to_string(), Box::new(*heights), world_height));
+		self
+	}
+
+	/// Replace every heightmap, such as with [Heightmaps::to_nbt](crate::world::heightmap::Heightmaps::to_nbt)
+	pub fn heightmaps(mut self, heightmaps: NbtCompound) -> Self {
+		self.heightmaps = heightmaps;
+		self.unpacked_heightmaps.clear();
+		self
+	}
+
+	/// Add a block entity, with a position relative to the column and `y` in world coordinates
+	pub fn block_entity(mut self, x: u8, y: i16, z: u8, block_entity_type: i32, data: NbtCompound) -> Self {
+		self.block_entities.push(ChunkBlockEntity {
+			packed_xz: ((x & 15) << 4) | (z & 15),
+			y,
+			block_entity_type: VarInt(block_entity_type),
+			data,
+		});
+		self
+	}
+
+	pub fn sky_light(mut self, light: ChunkLight) -> Self {
+		self.sky_light = Some(light);
+		self
+	}
+
+	pub fn block_light(mut self, light: ChunkLight) -> Self {
+		self.block_light = Some(light);
+		self
+	}
+
+	/// Build the spec. Light that doesn't cover 2 more sections than the column fails, since the client
+	/// would put it at the wrong height.
+	pub fn build(mut self) -> Result<ChunkDataSpec, SerializingErr> {
+		for (name, heights, world_height) in &self.unpacked_heightmaps {
+			self.heightmaps.add(name.as_str(), pack_heightmap(heights, *world_height)?);
+		}
+
+		let mut serializer = McSerializer::new();
+		for section in &self.sections {
+			section.mc_serialize(&mut serializer)?;
+		}
+
+		let expected = self.sections.len() + 2;
+		let light = |light: Option<ChunkLight>| -> Result<(BitSet, BitSet, Vec<Vec<u8>>), SerializingErr> {
+			match light {
+				Some(light) if light.len() != expected => {
+					Err(SerializingErr::UniqueFailure(format!("Light covers {} sections, expected {}", light.len(), expected)))
+				}
+				Some(light) => Ok(light.masks()),
+				None => Ok((BitSet::new(), BitSet::new(), vec![])),
+			}
+		};
+
+		let (sky_light_mask, empty_sky_light_mask, sky_light_arrays) = light(self.sky_light)?;
+		let (block_light_mask, empty_block_light_mask, block_light_arrays) = light(self.block_light)?;
+
+		Ok(ChunkDataSpec {
+			chunk_x: self.chunk_x,
+			chunk_z: self.chunk_z,
+			heightmaps: self.heightmaps,
+			data: serializer.output,
+			block_entities: self.block_entities,
+			sky_light_mask,
+			block_light_mask,
+			empty_sky_light_mask,
+			empty_block_light_mask,
+			sky_light_arrays,
+			block_light_arrays,
+		})
+	}
+
+	/// Build the `ChunkDataAndUpdateLight` packet
+	pub fn packet(self) -> Result<Packet, SerializingErr> {
+		Ok(Packet::ChunkDataAndUpdateLight(ChunkDataAndUpdateLightBody::new(self.build()?)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::play::chunk::{ChunkDataBuilder, ChunkLight, ChunkSection, LIGHT_ARRAY_LENGTH, MOTION_BLOCKING, pack_heightmap, read_sections, unpack_heightmap};
+	use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtTag};
+
+	#[test]
+	fn test_chunk_data_builder() {
+		let mut section = ChunkSection::empty(2);
+		section.set_block(1, 2, 3, 9);
+		section.set_block(4, 5, 6, 10);
+		section.set_block(4, 5, 6, 0);
+		section.set_biome(15, 15, 15, 3);
+		assert_eq!(section.block_count, 1);
+		assert_eq!(section.biome(12, 12, 12), 3);
+
+		let mut sky = ChunkLight::new(2);
+		sky.set(0, Some(vec![0; LIGHT_ARRAY_LENGTH])).unwrap();
+		sky.set(2, Some(vec![0xFF; LIGHT_ARRAY_LENGTH])).unwrap();
+		assert!(sky.set(4, None).is_err());
+		assert!(sky.set(1, Some(vec![0; 10])).is_err());
+
+		let mut sign = NbtCompound::new::<String>(None);
+		sign.add("is_waxed", NbtTag::Byte(1));
+
+		let spec = ChunkDataBuilder::new(-1, 4)
+			.sections(vec![ChunkSection::filled(1, 2), section.clone()])
+			.heightmap(MOTION_BLOCKING, &[20; 256], 384)
+			.block_entity(15, -60, 3, 7, sign)
+			.sky_light(sky.clone())
+			.build()
+			.unwrap();
+
+		assert_eq!(read_sections(&spec.data, 2).unwrap(), vec![ChunkSection::filled(1, 2), section]);
+		assert!(read_sections(&spec.data, 1).is_err());
+		assert_eq!(spec.block_entities[0].packed_xz, 0xF3);
+		assert_eq!(spec.heightmaps.get_long_array(MOTION_BLOCKING).unwrap().len(), 37);
+
+		assert_eq!(spec.sky_light_mask.iter().collect::<Vec<_>>(), vec![2]);
+		assert_eq!(spec.empty_sky_light_mask.iter().collect::<Vec<_>>(), vec![0]);
+		assert_eq!(ChunkLight::from_masks(2, &spec.sky_light_mask, &spec.empty_sky_light_mask, &spec.sky_light_arrays).unwrap(), sky);
+		assert!(spec.block_light_mask.is_empty());
+
+		// light for the wrong number of sections
+		assert!(ChunkDataBuilder::new(0, 0).section(ChunkSection::empty(0)).sky_light(ChunkLight::full(2)).build().is_err());
+	}
+
+	#[test]
+	fn test_pack_heightmap() {
+		let mut heights = [0; 256];
+		heights[0] = 384;
+		heights[6] = 1;
+		heights[7] = 2;
+
+		// 9 bits, 7 to a long
+		let packed = pack_heightmap(&heights, 384).unwrap();
+		assert_eq!(packed.len(), 37);
+		assert_eq!(packed.list[0], 384 | (1 << 54));
+		assert_eq!(packed.list[1], 2);
+		assert_eq!(unpack_heightmap(&packed, 384).unwrap(), heights);
+		assert!(unpack_heightmap(&packed, 128).is_err());
+		assert!(unpack_heightmap(&packed, 0).is_err());
+
+		// too high for the world, or no world at all
+		assert!(pack_heightmap(&heights, 383).is_err());
+		assert!(pack_heightmap(&[0; 256], 0).is_err());
+		assert!(ChunkDataBuilder::new(0, 0).heightmap(MOTION_BLOCKING, &heights, 100).build().is_err());
+	}
+}
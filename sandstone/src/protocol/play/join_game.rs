@@ -0,0 +1,263 @@
+//! Builds the `LoginPlay` packet, also known as Join Game, which moves the client into the world once
+//! configuration is finished. Its fields change in almost every version, so [JoinGameBuilder] fills in
+//! the vanilla defaults and checks the parts that depend on each other, such as the dimension being in
+//! the list of dimensions and its type being in the registry.
+
+use sha2::{Digest, Sha256};
+
+use crate::network::network_error::NetworkError;
+use crate::protocol::configuration::registry::{DIMENSION_TYPE, RegistryDataBuilder};
+use crate::protocol::packets::{LoginPlayBody, Packet};
+use crate::protocol::packets::packet_component::LoginPlaySpec;
+use crate::protocol_types::datatypes::game_mode::GameMode;
+use crate::protocol_types::datatypes::identifier::Identifier;
+use crate::protocol_types::datatypes::position::Position;
+use crate::protocol_types::datatypes::var_types::VarInt;
+use crate::protocol_types::protocol_verison::ProtocolVerison;
+
+/// The dimension vanilla players spawn in
+pub const OVERWORLD: &str = "minecraft:overworld";
+
+/// The closest and furthest view distances that vanilla allows
+pub const MIN_VIEW_DISTANCE: i32 = 2;
+pub const MAX_VIEW_DISTANCE: i32 = 32;
+
+/// Hash a world seed the way vanilla does before sending it, so that the client can use it for biome
+/// noise without learning the seed
+pub fn hash_seed(seed: i64) -> i64 {
+	let hash = Sha256::digest(seed.to_le_bytes());
+	i64::from_le_bytes(hash[..8].try_into().expect("a SHA-256 hash is 32 bytes"))
+}
+
+/// Builds the [LoginPlaySpec]. Everything starts at what a vanilla server sends to a survival player in
+/// the overworld, with the dimension type at id 0.
+///
+/// ```
+/// # use sandstone::protocol::play::join_game::JoinGameBuilder;
+/// # use sandstone::protocol_types::datatypes::game_mode::GameMode;
+/// let spec = JoinGameBuilder::new(1)
+///     .game_mode(GameMode::Creative)
+///     .seed(1234)
+///     .view_distance(12)
+///     .build();
+///
+/// assert_eq!(spec.game_mode, 1);
+/// assert_eq!(spec.dimension_names, vec!["minecraft:overworld".to_string()]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JoinGameBuilder {
+	spec: LoginPlaySpec,
+}
+
+impl JoinGameBuilder {
+	pub fn new(entity_id: i32) -> Self {
+		Self {
+			spec: LoginPlaySpec {
+				entity_id,
+				is_hardcore: false,
+				dimension_names: vec![OVERWORLD.to_string()],
+				max_players: VarInt(20),
+				view_distance: VarInt(10),
+				simulation_distance: VarInt(10),
+				reduced_debug_info: false,
+				enable_respawn_screen: true,
+				do_limited_crafting: false,
+				dimension_type: VarInt(0),
+				dimension_name: OVERWORLD.to_string(),
+				hashed_seed: 0,
+				game_mode: GameMode::Survival as u8,
+				previous_game_mode: -1,
+				is_debug: false,
+				is_flat: false,
+				death_location: None,
+				portal_cooldown: VarInt(0),
+				enforces_secure_chat: false,
+			},
+		}
+	}
+
+	pub fn game_mode(mut self, mode: GameMode) -> Self {
+		self.spec.game_mode = mode as u8;
+		self
+	}
+
+	/// The game mode that F3+F4 switches back to
+	pub fn previous_game_mode(mut self, mode: Option<GameMode>) -> Self {
+		self.spec.previous_game_mode = GameMode::optional_id(mode);
+		self
+	}
+
+	pub fn hardcore(mut self, hardcore: bool) -> Self {
+		self.spec.is_hardcore = hardcore;
+		self
+	}
+
+	/// Every dimension on the server, which the client suggests in commands
+	pub fn dimensions(mut self, dimensions: Vec<Identifier>) -> Self {
+		self.spec.dimension_names = dimensions.into_iter().map(|d| d.to_string()).collect();
+		self
+	}
+
+	/// The dimension the player spawns in and the id of its type in the `minecraft:dimension_type` registry
+	pub fn dimension(mut self, name: Identifier, dimension_type: i32) -> Self {
+		self.spec.dimension_name = name.to_string();
+		self.spec.dimension_type = VarInt(dimension_type);
+		self
+	}
+
+	/// The same as [JoinGameBuilder::dimension], looking up the id of the dimension type in the registries
+	/// that were sent during configuration
+	pub fn dimension_of(self, name: Identifier, dimension_type: &Identifier, registries: &RegistryDataBuilder) -> Result<Self, NetworkError> {
+		let registry: Identifier = DIMENSION_TYPE.parse()?;
+		let id = registries.id_of(&registry, dimension_type)
+			.ok_or_else(|| NetworkError::UnknownRegistryEntry { registry: registry.to_string(), entry: dimension_type.to_string() })?;
+
+		Ok(self.dimension(name, id))
+	}
+
+	/// The world seed, which is hashed with [hash_seed]
+	pub fn seed(mut self, seed: i64) -> Self {
+		self.spec.hashed_seed = hash_seed(seed);
+		self
+	}
+
+	/// A seed that was already hashed
+	pub fn hashed_seed(mut self, hashed_seed: i64) -> Self {
+		self.spec.hashed_seed = hashed_seed;
+		self
+	}
+
+	/// Ignored by the client, but still sent
+	pub fn max_players(mut self, max_players: i32) -> Self {
+		self.spec.max_players = VarInt(max_players);
+		self
+	}
+
+	/// Clamped to [MIN_VIEW_DISTANCE] and [MAX_VIEW_DISTANCE]
+	pub fn view_distance(mut self, chunks: i32) -> Self {
+		self.spec.view_distance = VarInt(chunks.clamp(MIN_VIEW_DISTANCE, MAX_VIEW_DISTANCE));
+		self
+	}
+
+	/// Clamped to [MIN_VIEW_DISTANCE] and [MAX_VIEW_DISTANCE]
+	pub fn simulation_distance(mut self, chunks: i32) -> Self {
+		self.spec.simulation_distance = VarInt(chunks.clamp(MIN_VIEW_DISTANCE, MAX_VIEW_DISTANCE));
+		self
+	}
+
+	pub fn reduced_debug_info(mut self, reduced: bool) -> Self {
+		self.spec.reduced_debug_info = reduced;
+		self
+	}
+
+	/// Whether the death screen is shown, the `doImmediateRespawn` game rule turns it off
+	pub fn respawn_screen(mut self, enabled: bool) -> Self {
+		self.spec.enable_respawn_screen = enabled;
+		self
+	}
+
+	/// Whether players can only craft recipes they have unlocked, the `doLimitedCrafting` game rule
+	pub fn limited_crafting(mut self, limited: bool) -> Self {
+		self.spec.do_limited_crafting = limited;
+		self
+	}
+
+	/// Whether the world is a debug world, which can't be modified
+	pub fn debug(mut self, debug: bool) -> Self {
+		self.spec.is_debug = debug;
+		self
+	}
+
+	/// Whether the world is superflat, which moves the horizon and void fog down
+	pub fn flat(mut self, flat: bool) -> Self {
+		self.spec.is_flat = flat;
+		self
+	}
+
+	/// Where the player last died, for the recovery compass
+	pub fn death_location(mut self, dimension: Identifier, position: Position) -> Self {
+		self.spec.death_location = Some((dimension.to_string(), position));
+		self
+	}
+
+	/// How many ticks until the player can use a portal again
+	pub fn portal_cooldown(mut self, ticks: i32) -> Self {
+		self.spec.portal_cooldown = VarInt(ticks);
+		self
+	}
+
+	/// Whether the server requires signed chat. Clients warn the player about unsigned chat if this isn't
+	/// set, so it should only be set if the server checks [chat sessions](crate::protocol::chat::session).
+	pub fn enforces_secure_chat(mut self, enforces: bool) -> Self {
+		self.spec.enforces_secure_chat = enforces;
+		self
+	}
+
+	/// Build the spec, adding the player's dimension to the list of dimensions if it's missing
+	pub fn build(mut self) -> LoginPlaySpec {
+		if !self.spec.dimension_names.contains(&self.spec.dimension_name) {
+			self.spec.dimension_names.push(self.spec.dimension_name.clone());
+		}
+
+		self.spec
+	}
+
+	/// Build the spec for a client on `version`. The packets in this library use the layout from 1.20.5,
+	/// where the dimension type is a registry id and `enforces_secure_chat` was added. Older clients
+	/// fail with [NetworkError::UnsupportedByClient].
+	pub fn build_for(self, version: ProtocolVerison) -> Result<LoginPlaySpec, NetworkError> {
+		if version < ProtocolVerison::V1_20_5 {
+			return Err(NetworkError::UnsupportedByClient(format!("the LoginPlay layout of 1.20.5, the client is on {}", version.get_fancy_name())));
+		}
+
+		Ok(self.build())
+	}
+
+	/// Build the `LoginPlay` packet
+	pub fn packet(self) -> Packet {
+		Packet::LoginPlay(LoginPlayBody::new(self.build()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::network::network_error::NetworkError;
+	use crate::protocol::configuration::registry::{DIMENSION_TYPE, RegistryDataBuilder};
+	use crate::protocol::play::join_game::{hash_seed, JoinGameBuilder, MAX_VIEW_DISTANCE};
+	use crate::protocol_types::datatypes::game_mode::GameMode;
+	use crate::protocol_types::datatypes::nbt::nbt::NbtCompound;
+	use crate::protocol_types::datatypes::position::Position;
+	use crate::protocol_types::protocol_verison::ProtocolVerison;
+
+	#[test]
+	fn test_join_game_builder() {
+		let registries = RegistryDataBuilder::new()
+			.entry(DIMENSION_TYPE.parse().unwrap(), "minecraft:overworld".parse().unwrap(), NbtCompound::new::<String>(None))
+			.entry(DIMENSION_TYPE.parse().unwrap(), "minecraft:the_nether".parse().unwrap(), NbtCompound::new::<String>(None));
+
+		let spec = JoinGameBuilder::new(7)
+			.dimension_of("example:nether".parse().unwrap(), &"minecraft:the_nether".parse().unwrap(), &registries).unwrap()
+			.game_mode(GameMode::Adventure)
+			.previous_game_mode(Some(GameMode::Spectator))
+			.view_distance(64)
+			.death_location("minecraft:overworld".parse().unwrap(), Position::new(1, 2, 3))
+			.build_for(ProtocolVerison::V1_21)
+			.unwrap();
+
+		assert_eq!(spec.dimension_type.0, 1);
+		assert_eq!(spec.dimension_names, vec!["minecraft:overworld".to_string(), "example:nether".to_string()]);
+		assert_eq!((spec.game_mode, spec.previous_game_mode), (2, 3));
+		assert_eq!(spec.view_distance.0, MAX_VIEW_DISTANCE);
+
+		assert!(matches!(JoinGameBuilder::new(7).dimension_of("example:end".parse().unwrap(), &"minecraft:the_end".parse().unwrap(), &registries), Err(NetworkError::UnknownRegistryEntry { .. })));
+		assert!(matches!(JoinGameBuilder::new(7).build_for(ProtocolVerison::V1_20_4), Err(NetworkError::UnsupportedByClient(_))));
+	}
+
+	#[test]
+	fn test_hash_seed() {
+		// the same seed always hashes the same, and the hash doesn't give the seed away
+		assert_eq!(hash_seed(1234), hash_seed(1234));
+		assert_ne!(hash_seed(1234), hash_seed(1235));
+		assert_ne!(hash_seed(1234), 1234);
+	}
+}
@@ -0,0 +1,10 @@
+//! Helpers for the play state, where the player is in the world. These build the packets that are easy to
+//! get wrong by hand, and keep track of what the client has been told.
+
+pub mod join_game;
+pub mod chunk;
+pub mod teleport;
+pub mod player_info;
+pub mod title;
+pub mod boss_bar;
+pub mod scoreboard;
@@ -0,0 +1,223 @@
+//! Builds the `PlayerInfoUpdate` and `PlayerInfoRemove` packets, which manage the tab list and the
+//! profiles that the client knows about. An update packet has a set of actions that applies to every
+//! player in it, so [PlayerInfoBuilder] collects the changes for each player and splits them into as few
+//! packets as possible.
+//!
+//! A player needs to be added before the client can render them, even if they aren't listed.
+
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::protocol::chat::session::ChatSession;
+use crate::protocol::packets::{Packet, PlayerInfoRemoveBody, PlayerInfoUpdateBody};
+use crate::protocol::packets::packet_component::{PlayerInfoEntry, PlayerInfoUpdateSpec};
+use crate::protocol_types::datatypes::chat::{NbtTextComponent, TextComponent};
+use crate::protocol_types::datatypes::game_mode::GameMode;
+use crate::protocol_types::datatypes::game_profile::GameProfile;
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+/// A player to add to the tab list. Players are listed in survival with no latency by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerListEntry {
+	entry: PlayerInfoEntry,
+}
+
+impl PlayerListEntry {
+	pub fn new(profile: GameProfile) -> Self {
+		Self {
+			entry: PlayerInfoEntry {
+				uuid: profile.uuid,
+				profile: Some(profile),
+				chat_session: None,
+				game_mode: Some(VarInt(GameMode::Survival as i32)),
+				listed: Some(true),
+				latency: Some(VarInt(0)),
+				display_name: Some(None),
+			},
+		}
+	}
+
+	pub fn game_mode(mut self, mode: GameMode) -> Self {
+		self.entry.game_mode = Some(VarInt(mode as i32));
+		self
+	}
+
+	/// Whether the player shows up in the tab list
+	pub fn listed(mut self, listed: bool) -> Self {
+		self.entry.listed = Some(listed);
+		self
+	}
+
+	/// The latency shown as bars next to the player's name
+	pub fn latency(mut self, latency: Duration) -> Self {
+		self.entry.latency = Some(latency_millis(latency));
+		self
+	}
+
+	/// The name shown in the tab list instead of the player's name
+	pub fn display_name(mut self, name: TextComponent) -> Self {
+		self.entry.display_name = Some(Some(NbtTextComponent::new(name)));
+		self
+	}
+
+	/// The player's chat session, which the client needs to verify their signed messages
+	pub fn chat_session(mut self, session: ChatSession) -> Self {
+		self.entry.chat_session = Some(Some(session));
+		self
+	}
+}
+
+fn latency_millis(latency: Duration) -> VarInt {
+	VarInt(latency.as_millis().min(i32::MAX as u128) as i32)
+}
+
+/// Collects tab list changes into `PlayerInfoUpdate` packets, see the [module docs](self)
+///
+/// ```
+/// # use std::time::Duration;
+/// # use sandstone::protocol::play::player_info::{PlayerInfoBuilder, PlayerListEntry};
+/// # use sandstone::protocol_types::datatypes::game_mode::GameMode;
+/// # use sandstone::protocol_types::datatypes::game_profile::GameProfile;
+/// let alice = GameProfile::offline("Alice");
+/// let bob = GameProfile::offline("Bob");
+///
+/// let packets = PlayerInfoBuilder::new()
+///     .add_player(PlayerListEntry::new(alice).game_mode(GameMode::Creative))
+///     .latency(bob.uuid, Duration::from_millis(40))
+///     .packets();
+///
+/// // one packet adds Alice, another updates Bob
+/// assert_eq!(packets.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlayerInfoBuilder {
+	entries: Vec<PlayerInfoEntry>,
+}
+
+impl PlayerInfoBuilder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	fn entry(&mut self, uuid: Uuid) -> &mut PlayerInfoEntry {
+		let index = match self.entries.iter().position(|e| e.uuid == uuid) {
+			Some(i) => i,
+			None => {
+				self.entries.push(PlayerInfoEntry::new(uuid));
+				self.entries.len() - 1
+			}
+		};
+
+		&mut self.entries[index]
+	}
+
+	/// Add a player. Changes already made to the player in this builder are replaced.
+	pub fn add_player(mut self, player: PlayerListEntry) -> Self {
+		let uuid = player.entry.uuid;
+		*self.entry(uuid) = player.entry;
+		self
+	}
+
+	pub fn game_mode(mut self, uuid: Uuid, mode: GameMode) -> Self {
+		self.entry(uuid).game_mode = Some(VarInt(mode as i32));
+		self
+	}
+
+	pub fn listed(mut self, uuid: Uuid, listed: bool) -> Self {
+		self.entry(uuid).listed = Some(listed);
+		self
+	}
+
+	pub fn latency(mut self, uuid: Uuid, latency: Duration) -> Self {
+		self.entry(uuid).latency = Some(latency_millis(latency));
+		self
+	}
+
+	/// Change the name shown in the tab list, or show the player's name again with `None`
+	pub fn display_name(mut self, uuid: Uuid, name: Option<TextComponent>) -> Self {
+		self.entry(uuid).display_name = Some(name.map(NbtTextComponent::new));
+		self
+	}
+
+	/// Change the player's chat session, or remove it with `None`
+	pub fn chat_session(mut self, uuid: Uuid, session: Option<ChatSession>) -> Self {
+		self.entry(uuid).chat_session = Some(session);
+		self
+	}
+
+	/// Check if there are no changes
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// Build the specs, with one for each set of actions in the order that the players were first changed
+	pub fn build(self) -> Vec<PlayerInfoUpdateSpec> {
+		let mut specs: Vec<PlayerInfoUpdateSpec> = vec![];
+
+		for entry in self.entries {
+			let actions = entry.actions();
+
+			match specs.iter_mut().find(|spec| spec.actions == actions) {
+				Some(spec) => spec.entries.push(entry),
+				None => specs.push(PlayerInfoUpdateSpec { actions, entries: vec![entry] }),
+			}
+		}
+
+		specs
+	}
+
+	/// Build the `PlayerInfoUpdate` packets, see [PlayerInfoBuilder::build]
+	pub fn packets(self) -> Vec<Packet> {
+		self.build().into_iter()
+			.map(|spec| Packet::PlayerInfoUpdate(PlayerInfoUpdateBody::new(spec)))
+			.collect()
+	}
+}
+
+/// The `PlayerInfoRemove` packet, which takes players off the tab list and makes the client forget their
+/// profiles
+pub fn remove_packet(uuids: Vec<Uuid>) -> Packet {
+	Packet::PlayerInfoRemove(PlayerInfoRemoveBody::new(uuids))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use uuid::Uuid;
+
+	use crate::protocol::packets::packet_component::{PLAYER_INFO_ADD_PLAYER, PLAYER_INFO_UPDATE_DISPLAY_NAME, PLAYER_INFO_UPDATE_GAME_MODE, PLAYER_INFO_UPDATE_LATENCY, PLAYER_INFO_UPDATE_LISTED};
+	use crate::protocol::play::player_info::{PlayerInfoBuilder, PlayerListEntry};
+	use crate::protocol_types::datatypes::chat::TextComponent;
+	use crate::protocol_types::datatypes::game_mode::GameMode;
+	use crate::protocol_types::datatypes::game_profile::GameProfile;
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	#[test]
+	fn test_player_info_builder() {
+		let alice = GameProfile::offline("Alice");
+		let bob = Uuid::from_u128(2);
+		let carol = Uuid::from_u128(3);
+
+		let specs = PlayerInfoBuilder::new()
+			.latency(bob, Duration::from_millis(150))
+			.add_player(PlayerListEntry::new(alice.clone()).listed(false).display_name(TextComponent::new("[Admin] Alice")))
+			.display_name(carol, None)
+			.latency(carol, Duration::from_millis(20))
+			.game_mode(alice.uuid, GameMode::Spectator)
+			.build();
+
+		assert_eq!(specs.len(), 3);
+		assert_eq!(specs[0].actions, PLAYER_INFO_UPDATE_LATENCY);
+		assert_eq!(specs[0].entries[0].latency, Some(VarInt(150)));
+
+		assert_eq!(specs[1].actions, PLAYER_INFO_ADD_PLAYER | PLAYER_INFO_UPDATE_GAME_MODE | PLAYER_INFO_UPDATE_LISTED | PLAYER_INFO_UPDATE_LATENCY | PLAYER_INFO_UPDATE_DISPLAY_NAME);
+		assert_eq!(specs[1].entries[0].profile, Some(alice));
+		assert_eq!(specs[1].entries[0].game_mode, Some(VarInt(GameMode::Spectator as i32)));
+		assert_eq!(specs[1].entries[0].listed, Some(false));
+
+		assert_eq!(specs[2].actions, PLAYER_INFO_UPDATE_LATENCY | PLAYER_INFO_UPDATE_DISPLAY_NAME);
+		assert!(PlayerInfoBuilder::new().is_empty());
+	}
+}
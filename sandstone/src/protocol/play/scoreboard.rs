@@ -0,0 +1,869 @@
+//! Scoreboards and teams. Objectives hold a score for each entity (a player name or an entity UUID) and
+//! can be shown in the sidebar, the tab list or below player names. Teams group entities together and
+//! change how their names look, with prefixes, suffixes and colors.
+//!
+//! The client keeps its own copy of all of this, so a [Scoreboard] keeps the state that the players were
+//! sent and only sends the packets for what changed. See [here](https://wiki.vg/Protocol#Update_Objectives)
+//! for more on the packets.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::network::client::CraftClient;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::{DisplayObjectiveBody, Packet, ResetScoreBody, UpdateObjectivesBody, UpdateScoreBody, UpdateTeamsBody};
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::chat::{NbtTextComponent, TextComponent};
+use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtFlavor};
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+/// Lets players on the team hurt each other
+pub const FRIENDLY_FIRE: u8 = 0x01;
+/// Lets players on the team see each other while invisible
+pub const SEE_INVISIBLE_TEAMMATES: u8 = 0x02;
+
+/// How the scores of an objective are drawn in the tab list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RenderType {
+	#[default]
+	Integer = 0,
+	Hearts = 1,
+}
+
+impl TryFrom<i32> for RenderType {
+	type Error = SerializingErr;
+
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(RenderType::Integer),
+			1 => Ok(RenderType::Hearts),
+			_ => Err(SerializingErr::UniqueFailure(format!("Unknown objective render type {}", value))),
+		}
+	}
+}
+
+/// How a score's number is shown, which replaces the default red number
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumberFormat {
+	/// Don't show the number at all
+	Blank,
+	/// Show the number with a style, which is a compound with the style fields of a text component
+	Styled(NbtCompound),
+	/// Show this text instead of the number
+	Fixed(NbtTextComponent),
+}
+
+impl McSerialize for NumberFormat {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		match self {
+			NumberFormat::Blank => VarInt(0).mc_serialize(serializer),
+			NumberFormat::Styled(style) => {
+				VarInt(1).mc_serialize(serializer)?;
+				style.serialize_flavor(serializer, NbtFlavor::Network)
+			}
+			NumberFormat::Fixed(text) => {
+				VarInt(2).mc_serialize(serializer)?;
+				text.mc_serialize(serializer)
+			}
+		}
+	}
+}
+
+impl McDeserialize for NumberFormat {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		match VarInt::mc_deserialize(deserializer)?.0 {
+			0 => Ok(NumberFormat::Blank),
+			1 => Ok(NumberFormat::Styled(NbtCompound::deserialize_flavor(deserializer, NbtFlavor::Network)?)),
+			2 => Ok(NumberFormat::Fixed(NbtTextComponent::mc_deserialize(deserializer)?)),
+			format => Err(SerializingErr::UniqueFailure(format!("Unknown number format {}", format))),
+		}
+	}
+}
+
+/// The color of a team, which colors the names of its players and picks its sidebar slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum TeamColor {
+	Black = 0,
+	DarkBlue = 1,
+	DarkGreen = 2,
+	DarkAqua = 3,
+	DarkRed = 4,
+	DarkPurple = 5,
+	Gold = 6,
+	Gray = 7,
+	DarkGray = 8,
+	Blue = 9,
+	Green = 10,
+	Aqua = 11,
+	Red = 12,
+	LightPurple = 13,
+	Yellow = 14,
+	White = 15,
+	/// No color
+	#[default]
+	Reset = 21,
+}
+
+impl TryFrom<i32> for TeamColor {
+	type Error = SerializingErr;
+
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		let color = match value {
+			0 => TeamColor::Black,
+			1 => TeamColor::DarkBlue,
+			2 => TeamColor::DarkGreen,
+			3 => TeamColor::DarkAqua,
+			4 => TeamColor::DarkRed,
+			5 => TeamColor::DarkPurple,
+			6 => TeamColor::Gold,
+			7 => TeamColor::Gray,
+			8 => TeamColor::DarkGray,
+			9 => TeamColor::Blue,
+			10 => TeamColor::Green,
+			11 => TeamColor::Aqua,
+			12 => TeamColor::Red,
+			13 => TeamColor::LightPurple,
+			14 => TeamColor::Yellow,
+			15 => TeamColor::White,
+			21 => TeamColor::Reset,
+			_ => return Err(SerializingErr::UniqueFailure(format!("Unknown team color {}", value))),
+		};
+
+		Ok(color)
+	}
+}
+
+/// Where an objective is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DisplaySlot {
+	List,
+	Sidebar,
+	BelowName,
+	/// The sidebar for players on a team with this color. [TeamColor::Reset] has no sidebar of its own.
+	TeamSidebar(TeamColor),
+}
+
+impl DisplaySlot {
+	pub fn id(&self) -> i32 {
+		match self {
+			DisplaySlot::List => 0,
+			DisplaySlot::Sidebar => 1,
+			DisplaySlot::BelowName => 2,
+			DisplaySlot::TeamSidebar(color) => 3 + *color as i32,
+		}
+	}
+}
+
+impl TryFrom<i32> for DisplaySlot {
+	type Error = SerializingErr;
+
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(DisplaySlot::List),
+			1 => Ok(DisplaySlot::Sidebar),
+			2 => Ok(DisplaySlot::BelowName),
+			3..=18 => Ok(DisplaySlot::TeamSidebar(TeamColor::try_from(value - 3)?)),
+			_ => Err(SerializingErr::UniqueFailure(format!("Unknown display slot {}", value))),
+		}
+	}
+}
+
+/// Whose name tags the players on a team can see
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum NameTagVisibility {
+	#[default]
+	Always,
+	HideForOtherTeams,
+	HideForOwnTeam,
+	Never,
+}
+
+impl NameTagVisibility {
+	pub fn name(&self) -> &'static str {
+		match self {
+			NameTagVisibility::Always => "always",
+			NameTagVisibility::HideForOtherTeams => "hideForOtherTeams",
+			NameTagVisibility::HideForOwnTeam => "hideForOwnTeam",
+			NameTagVisibility::Never => "never",
+		}
+	}
+}
+
+impl McSerialize for NameTagVisibility {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.name().to_string().mc_serialize(serializer)
+	}
+}
+
+impl McDeserialize for NameTagVisibility {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		match String::mc_deserialize(deserializer)?.as_str() {
+			"always" => Ok(NameTagVisibility::Always),
+			"hideForOtherTeams" => Ok(NameTagVisibility::HideForOtherTeams),
+			"hideForOwnTeam" => Ok(NameTagVisibility::HideForOwnTeam),
+			"never" => Ok(NameTagVisibility::Never),
+			s => Err(SerializingErr::UniqueFailure(format!("Unknown name tag visibility {}", s))),
+		}
+	}
+}
+
+/// Who the players on a team push around
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CollisionRule {
+	#[default]
+	Always,
+	PushOtherTeams,
+	PushOwnTeam,
+	Never,
+}
+
+impl CollisionRule {
+	pub fn name(&self) -> &'static str {
+		match self {
+			CollisionRule::Always => "always",
+			CollisionRule::PushOtherTeams => "pushOtherTeams",
+			CollisionRule::PushOwnTeam => "pushOwnTeam",
+			CollisionRule::Never => "never",
+		}
+	}
+}
+
+impl McSerialize for CollisionRule {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.name().to_string().mc_serialize(serializer)
+	}
+}
+
+impl McDeserialize for CollisionRule {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		match String::mc_deserialize(deserializer)?.as_str() {
+			"always" => Ok(CollisionRule::Always),
+			"pushOtherTeams" => Ok(CollisionRule::PushOtherTeams),
+			"pushOwnTeam" => Ok(CollisionRule::PushOwnTeam),
+			"never" => Ok(CollisionRule::Never),
+			s => Err(SerializingErr::UniqueFailure(format!("Unknown collision rule {}", s))),
+		}
+	}
+}
+
+/// The settings of an objective
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Objective {
+	display_name: TextComponent,
+	render_type: RenderType,
+	number_format: Option<NumberFormat>,
+}
+
+impl Objective {
+	pub fn new(display_name: TextComponent) -> Self {
+		Self {
+			display_name,
+			..Default::default()
+		}
+	}
+
+	pub fn render_type(mut self, render_type: RenderType) -> Self {
+		self.render_type = render_type;
+		self
+	}
+
+	/// The format for every score of this objective that doesn't have its own
+	pub fn number_format(mut self, format: NumberFormat) -> Self {
+		self.number_format = Some(format);
+		self
+	}
+
+	pub fn display_name(&self) -> &TextComponent {
+		&self.display_name
+	}
+}
+
+impl McSerialize for Objective {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		NbtTextComponent::new(self.display_name.clone()).mc_serialize(serializer)?;
+		VarInt(self.render_type as i32).mc_serialize(serializer)?;
+		self.number_format.mc_serialize(serializer)
+	}
+}
+
+impl McDeserialize for Objective {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		Ok(Self {
+			display_name: NbtTextComponent::mc_deserialize(deserializer)?.into_inner(),
+			render_type: VarInt::mc_deserialize(deserializer)?.0.try_into()?,
+			number_format: Option::mc_deserialize(deserializer)?,
+		})
+	}
+}
+
+/// What an `UpdateObjectives` packet does to the objective with its name
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectiveAction {
+	Create(Objective),
+	Remove,
+	Update(Objective),
+}
+
+impl McSerialize for ObjectiveAction {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		match self {
+			ObjectiveAction::Create(objective) => {
+				0u8.mc_serialize(serializer)?;
+				objective.mc_serialize(serializer)
+			}
+			ObjectiveAction::Remove => 1u8.mc_serialize(serializer),
+			ObjectiveAction::Update(objective) => {
+				2u8.mc_serialize(serializer)?;
+				objective.mc_serialize(serializer)
+			}
+		}
+	}
+}
+
+impl McDeserialize for ObjectiveAction {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		match u8::mc_deserialize(deserializer)? {
+			0 => Ok(ObjectiveAction::Create(Objective::mc_deserialize(deserializer)?)),
+			1 => Ok(ObjectiveAction::Remove),
+			2 => Ok(ObjectiveAction::Update(Objective::mc_deserialize(deserializer)?)),
+			mode => Err(SerializingErr::UniqueFailure(format!("Unknown objective mode {}", mode))),
+		}
+	}
+}
+
+/// A score in an objective. An `i32` converts into a score with no display name or format.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Score {
+	value: i32,
+	display_name: Option<TextComponent>,
+	number_format: Option<NumberFormat>,
+}
+
+impl Score {
+	pub fn new(value: i32) -> Self {
+		Self {
+			value,
+			..Default::default()
+		}
+	}
+
+	/// The name shown instead of the entity's name
+	pub fn display_name(mut self, name: TextComponent) -> Self {
+		self.display_name = Some(name);
+		self
+	}
+
+	pub fn number_format(mut self, format: NumberFormat) -> Self {
+		self.number_format = Some(format);
+		self
+	}
+
+	pub fn value(&self) -> i32 {
+		self.value
+	}
+}
+
+impl From<i32> for Score {
+	fn from(value: i32) -> Self {
+		Self::new(value)
+	}
+}
+
+/// The settings of a team
+///
+/// ```
+/// # use sandstone::protocol::play::scoreboard::{NameTagVisibility, Team, TeamColor};
+/// # use sandstone::protocol_types::datatypes::chat::TextComponent;
+/// let team = Team::new(TextComponent::new("Red"))
+///     .color(TeamColor::Red)
+///     .prefix(TextComponent::new("[Red] "))
+///     .name_tag_visibility(NameTagVisibility::HideForOtherTeams);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Team {
+	display_name: TextComponent,
+	flags: u8,
+	name_tag_visibility: NameTagVisibility,
+	collision_rule: CollisionRule,
+	color: TeamColor,
+	prefix: TextComponent,
+	suffix: TextComponent,
+}
+
+impl Team {
+	pub fn new(display_name: TextComponent) -> Self {
+		Self {
+			display_name,
+			..Default::default()
+		}
+	}
+
+	/// Set the flags, which are [FRIENDLY_FIRE] and [SEE_INVISIBLE_TEAMMATES]
+	pub fn flags(mut self, flags: u8) -> Self {
+		self.flags = flags;
+		self
+	}
+
+	pub fn name_tag_visibility(mut self, visibility: NameTagVisibility) -> Self {
+		self.name_tag_visibility = visibility;
+		self
+	}
+
+	pub fn collision_rule(mut self, rule: CollisionRule) -> Self {
+		self.collision_rule = rule;
+		self
+	}
+
+	pub fn color(mut self, color: TeamColor) -> Self {
+		self.color = color;
+		self
+	}
+
+	/// The text in front of the names of players on the team
+	pub fn prefix(mut self, prefix: TextComponent) -> Self {
+		self.prefix = prefix;
+		self
+	}
+
+	/// The text after the names of players on the team
+	pub fn suffix(mut self, suffix: TextComponent) -> Self {
+		self.suffix = suffix;
+		self
+	}
+
+	pub fn display_name(&self) -> &TextComponent {
+		&self.display_name
+	}
+
+	pub fn team_color(&self) -> TeamColor {
+		self.color
+	}
+}
+
+impl McSerialize for Team {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		NbtTextComponent::new(self.display_name.clone()).mc_serialize(serializer)?;
+		self.flags.mc_serialize(serializer)?;
+		self.name_tag_visibility.mc_serialize(serializer)?;
+		self.collision_rule.mc_serialize(serializer)?;
+		VarInt(self.color as i32).mc_serialize(serializer)?;
+		NbtTextComponent::new(self.prefix.clone()).mc_serialize(serializer)?;
+		NbtTextComponent::new(self.suffix.clone()).mc_serialize(serializer)
+	}
+}
+
+impl McDeserialize for Team {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		Ok(Self {
+			display_name: NbtTextComponent::mc_deserialize(deserializer)?.into_inner(),
+			flags: u8::mc_deserialize(deserializer)?,
+			name_tag_visibility: NameTagVisibility::mc_deserialize(deserializer)?,
+			collision_rule: CollisionRule::mc_deserialize(deserializer)?,
+			color: VarInt::mc_deserialize(deserializer)?.0.try_into()?,
+			prefix: NbtTextComponent::mc_deserialize(deserializer)?.into_inner(),
+			suffix: NbtTextComponent::mc_deserialize(deserializer)?.into_inner(),
+		})
+	}
+}
+
+/// What an `UpdateTeams` packet does to the team with its name
+#[derive(Debug, Clone, PartialEq)]
+pub enum TeamAction {
+	/// Create the team with these entities in it
+	Create(Box<Team>, Vec<String>),
+	Remove,
+	Update(Box<Team>),
+	AddEntities(Vec<String>),
+	RemoveEntities(Vec<String>),
+}
+
+impl McSerialize for TeamAction {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		match self {
+			TeamAction::Create(team, entities) => {
+				0u8.mc_serialize(serializer)?;
+				team.mc_serialize(serializer)?;
+				entities.mc_serialize(serializer)
+			}
+			TeamAction::Remove => 1u8.mc_serialize(serializer),
+			TeamAction::Update(team) => {
+				2u8.mc_serialize(serializer)?;
+				team.mc_serialize(serializer)
+			}
+			TeamAction::AddEntities(entities) => {
+				3u8.mc_serialize(serializer)?;
+				entities.mc_serialize(serializer)
+			}
+			TeamAction::RemoveEntities(entities) => {
+				4u8.mc_serialize(serializer)?;
+				entities.mc_serialize(serializer)
+			}
+		}
+	}
+}
+
+impl McDeserialize for TeamAction {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		match u8::mc_deserialize(deserializer)? {
+			0 => Ok(TeamAction::Create(Box::new(Team::mc_deserialize(deserializer)?), Vec::mc_deserialize(deserializer)?)),
+			1 => Ok(TeamAction::Remove),
+			2 => Ok(TeamAction::Update(Box::new(Team::mc_deserialize(deserializer)?))),
+			3 => Ok(TeamAction::AddEntities(Vec::mc_deserialize(deserializer)?)),
+			4 => Ok(TeamAction::RemoveEntities(Vec::mc_deserialize(deserializer)?)),
+			mode => Err(SerializingErr::UniqueFailure(format!("Unknown team mode {}", mode))),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ObjectiveEntry {
+	objective: Objective,
+	scores: BTreeMap<String, Score>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct TeamEntry {
+	team: Team,
+	entities: BTreeSet<String>,
+}
+
+/// The objectives, scores and teams that the players were sent, see the [module docs](self)
+///
+/// ```no_run
+/// # use sandstone::network::client::CraftClient;
+/// # use sandstone::network::network_error::NetworkError;
+/// # use sandstone::protocol::play::scoreboard::{DisplaySlot, Objective, Scoreboard};
+/// # use sandstone::protocol_types::datatypes::chat::TextComponent;
+/// # async fn run(players: &mut [CraftClient]) -> Result<(), NetworkError> {
+/// let mut scoreboard = Scoreboard::new();
+/// scoreboard.set_objective("kills", Objective::new(TextComponent::new("Kills")));
+/// scoreboard.set_display_slot(DisplaySlot::Sidebar, Some("kills"));
+///
+/// for player in players.iter_mut() {
+///     scoreboard.show(player).await?;
+/// }
+///
+/// scoreboard.set_score("kills", "Alice", 3);
+/// scoreboard.update(players.iter_mut()).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Scoreboard {
+	objectives: BTreeMap<String, ObjectiveEntry>,
+	display_slots: BTreeMap<DisplaySlot, String>,
+	teams: BTreeMap<String, TeamEntry>,
+	/// The packets for the changes since the last update
+	pending: Vec<Packet>,
+}
+
+impl Scoreboard {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Create an objective, or change the settings of an existing one and keep its scores
+	pub fn set_objective<T: Into<String>>(&mut self, name: T, objective: Objective) {
+		let name = name.into();
+
+		match self.objectives.get_mut(&name) {
+			Some(entry) if entry.objective == objective => {}
+			Some(entry) => {
+				entry.objective = objective.clone();
+				self.pending.push(objective_packet(&name, ObjectiveAction::Update(objective)));
+			}
+			None => {
+				self.pending.push(objective_packet(&name, ObjectiveAction::Create(objective.clone())));
+				self.objectives.insert(name, ObjectiveEntry { objective, scores: BTreeMap::new() });
+			}
+		}
+	}
+
+	/// Remove an objective with its scores. The client also takes it out of every display slot.
+	pub fn remove_objective(&mut self, name: &str) {
+		if self.objectives.remove(name).is_some() {
+			self.display_slots.retain(|_, objective| objective != name);
+			self.pending.push(objective_packet(name, ObjectiveAction::Remove));
+		}
+	}
+
+	pub fn objective(&self, name: &str) -> Option<&Objective> {
+		self.objectives.get(name).map(|entry| &entry.objective)
+	}
+
+	/// Show an objective in a slot, or clear the slot with `None`. Returns false if the objective doesn't
+	/// exist.
+	pub fn set_display_slot(&mut self, slot: DisplaySlot, objective: Option<&str>) -> bool {
+		let current = self.display_slots.get(&slot).map(|s| s.as_str());
+
+		if current == objective {
+			return true;
+		}
+
+		match objective {
+			Some(name) if !self.objectives.contains_key(name) => return false,
+			Some(name) => {
+				self.display_slots.insert(slot, name.to_string());
+			}
+			None => {
+				self.display_slots.remove(&slot);
+			}
+		}
+
+		self.pending.push(display_packet(slot, objective.unwrap_or_default()));
+		true
+	}
+
+	/// Set the score of an entity, which is a player name or an entity UUID. Returns false if the
+	/// objective doesn't exist.
+	pub fn set_score<S: Into<Score>>(&mut self, objective: &str, entity: &str, score: S) -> bool {
+		let Some(entry) = self.objectives.get_mut(objective) else {
+			return false;
+		};
+
+		let score = score.into();
+
+		if entry.scores.get(entity) != Some(&score) {
+			self.pending.push(score_packet(objective, entity, &score));
+			entry.scores.insert(entity.to_string(), score);
+		}
+
+		true
+	}
+
+	pub fn score(&self, objective: &str, entity: &str) -> Option<&Score> {
+		self.objectives.get(objective)?.scores.get(entity)
+	}
+
+	/// Remove the score of an entity from one objective
+	pub fn reset_score(&mut self, objective: &str, entity: &str) {
+		if let Some(entry) = self.objectives.get_mut(objective) {
+			if entry.scores.remove(entity).is_some() {
+				self.pending.push(Packet::ResetScore(ResetScoreBody::new(entity.to_string(), Some(objective.to_string()))));
+			}
+		}
+	}
+
+	/// Remove the scores of an entity from every objective
+	pub fn reset_scores(&mut self, entity: &str) {
+		let mut removed = false;
+
+		for entry in self.objectives.values_mut() {
+			removed |= entry.scores.remove(entity).is_some();
+		}
+
+		if removed {
+			self.pending.push(Packet::ResetScore(ResetScoreBody::new(entity.to_string(), None)));
+		}
+	}
+
+	/// Create a team, or change the settings of an existing one and keep its entities
+	pub fn set_team<T: Into<String>>(&mut self, name: T, team: Team) {
+		let name = name.into();
+
+		match self.teams.get_mut(&name) {
+			Some(entry) if entry.team == team => {}
+			Some(entry) => {
+				entry.team = team.clone();
+				self.pending.push(team_packet(&name, TeamAction::Update(Box::new(team))));
+			}
+			None => {
+				self.pending.push(team_packet(&name, TeamAction::Create(Box::new(team.clone()), vec![])));
+				self.teams.insert(name, TeamEntry { team, entities: BTreeSet::new() });
+			}
+		}
+	}
+
+	pub fn remove_team(&mut self, name: &str) {
+		if self.teams.remove(name).is_some() {
+			self.pending.push(team_packet(name, TeamAction::Remove));
+		}
+	}
+
+	pub fn team(&self, name: &str) -> Option<&Team> {
+		self.teams.get(name).map(|entry| &entry.team)
+	}
+
+	/// The name of the team that an entity is on
+	pub fn team_of(&self, entity: &str) -> Option<&str> {
+		self.teams.iter()
+			.find(|(_, entry)| entry.entities.contains(entity))
+			.map(|(name, _)| name.as_str())
+	}
+
+	/// Put entities on a team. An entity can only be on one team, so the client takes them off any team
+	/// they were on before. Returns false if the team doesn't exist.
+	pub fn join_team<'a, I: IntoIterator<Item = &'a str>>(&mut self, name: &str, entities: I) -> bool {
+		if !self.teams.contains_key(name) {
+			return false;
+		}
+
+		let mut added = vec![];
+
+		for entity in entities {
+			if self.team_of(entity) == Some(name) || added.iter().any(|e| e == entity) {
+				continue;
+			}
+
+			for entry in self.teams.values_mut() {
+				entry.entities.remove(entity);
+			}
+
+			added.push(entity.to_string());
+		}
+
+		if !added.is_empty() {
+			self.teams.get_mut(name).unwrap().entities.extend(added.iter().cloned());
+			self.pending.push(team_packet(name, TeamAction::AddEntities(added)));
+		}
+
+		true
+	}
+
+	/// Take entities off a team, ignoring the ones that aren't on it
+	pub fn leave_team<'a, I: IntoIterator<Item = &'a str>>(&mut self, name: &str, entities: I) {
+		let Some(entry) = self.teams.get_mut(name) else {
+			return;
+		};
+
+		let removed: Vec<String> = entities.into_iter()
+			.filter(|entity| entry.entities.remove(*entity))
+			.map(|entity| entity.to_string())
+			.collect();
+
+		if !removed.is_empty() {
+			self.pending.push(team_packet(name, TeamAction::RemoveEntities(removed)));
+		}
+	}
+
+	/// The packets that send the whole scoreboard to a player who hasn't seen it yet
+	pub fn packets(&self) -> Vec<Packet> {
+		let mut packets = vec![];
+
+		for (name, entry) in &self.objectives {
+			packets.push(objective_packet(name, ObjectiveAction::Create(entry.objective.clone())));
+
+			for (entity, score) in &entry.scores {
+				packets.push(score_packet(name, entity, score));
+			}
+		}
+
+		for (slot, name) in &self.display_slots {
+			packets.push(display_packet(*slot, name));
+		}
+
+		for (name, entry) in &self.teams {
+			packets.push(team_packet(name, TeamAction::Create(Box::new(entry.team.clone()), entry.entities.iter().cloned().collect())));
+		}
+
+		packets
+	}
+
+	/// The packets for everything that changed since the last call, which should be sent to every player
+	/// who can see the scoreboard
+	pub fn take_updates(&mut self) -> Vec<Packet> {
+		std::mem::take(&mut self.pending)
+	}
+
+	/// Send the whole scoreboard to a player who hasn't seen it yet
+	pub async fn show(&self, client: &mut CraftClient) -> Result<(), NetworkError> {
+		for packet in self.packets() {
+			client.queue_packet(packet)?;
+		}
+
+		client.flush().await
+	}
+
+	/// Send the changes since the last update to every player who can see the scoreboard
+	pub async fn update<'a, I: IntoIterator<Item = &'a mut CraftClient>>(&mut self, clients: I) -> Result<(), NetworkError> {
+		let packets = self.take_updates();
+
+		if packets.is_empty() {
+			return Ok(());
+		}
+
+		for client in clients {
+			for packet in &packets {
+				client.queue_packet(packet.clone())?;
+			}
+
+			client.flush().await?;
+		}
+
+		Ok(())
+	}
+}
+
+fn objective_packet(name: &str, action: ObjectiveAction) -> Packet {
+	Packet::UpdateObjectives(UpdateObjectivesBody::new(name.to_string(), action))
+}
+
+fn display_packet(slot: DisplaySlot, objective: &str) -> Packet {
+	Packet::DisplayObjective(DisplayObjectiveBody::new(VarInt(slot.id()), objective.to_string()))
+}
+
+fn score_packet(objective: &str, entity: &str, score: &Score) -> Packet {
+	Packet::UpdateScore(UpdateScoreBody::new(entity.to_string(), objective.to_string(), VarInt(score.value),
+		score.display_name.clone().map(NbtTextComponent::new), score.number_format.clone()))
+}
+
+fn team_packet(name: &str, action: TeamAction) -> Packet {
+	Packet::UpdateTeams(UpdateTeamsBody::new(name.to_string(), action))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::packets::Packet;
+	use crate::protocol::play::scoreboard::{DisplaySlot, Objective, ObjectiveAction, Scoreboard, Team, TeamAction, TeamColor};
+	use crate::protocol_types::datatypes::chat::TextComponent;
+
+	#[test]
+	fn test_scoreboard_diffs() {
+		let mut scoreboard = Scoreboard::new();
+		scoreboard.set_objective("kills", Objective::new(TextComponent::new("Kills")));
+		assert!(scoreboard.set_display_slot(DisplaySlot::Sidebar, Some("kills")));
+		assert!(!scoreboard.set_display_slot(DisplaySlot::List, Some("deaths")));
+		assert!(scoreboard.set_score("kills", "Alice", 2));
+		assert!(!scoreboard.set_score("deaths", "Alice", 1));
+		assert_eq!(scoreboard.take_updates().len(), 3);
+
+		// nothing changed
+		scoreboard.set_objective("kills", Objective::new(TextComponent::new("Kills")));
+		scoreboard.set_display_slot(DisplaySlot::Sidebar, Some("kills"));
+		scoreboard.set_score("kills", "Alice", 2);
+		assert!(scoreboard.take_updates().is_empty());
+
+		scoreboard.set_objective("kills", Objective::new(TextComponent::new("Total kills")));
+		scoreboard.reset_score("kills", "Bob");
+		scoreboard.reset_scores("Alice");
+		let updates = scoreboard.take_updates();
+		assert_eq!(updates.len(), 2);
+		assert!(matches!(&updates[0], Packet::UpdateObjectives(body) if matches!(body.action, ObjectiveAction::Update(_))));
+		assert!(matches!(&updates[1], Packet::ResetScore(body) if body.objective_name.is_none()));
+
+		scoreboard.remove_objective("kills");
+		assert_eq!(scoreboard.take_updates().len(), 1);
+		assert!(scoreboard.packets().is_empty());
+	}
+
+	#[test]
+	fn test_teams() {
+		let mut scoreboard = Scoreboard::new();
+		scoreboard.set_team("red", Team::new(TextComponent::new("Red")).color(TeamColor::Red));
+		scoreboard.set_team("blue", Team::new(TextComponent::new("Blue")).color(TeamColor::Blue));
+		assert!(scoreboard.join_team("red", ["Alice", "Bob", "Alice"]));
+		assert!(!scoreboard.join_team("green", ["Carol"]));
+		scoreboard.join_team("blue", ["Bob"]);
+		scoreboard.leave_team("red", ["Bob", "Carol"]);
+
+		let updates = scoreboard.take_updates();
+		assert_eq!(updates.len(), 4);
+		assert!(matches!(&updates[2], Packet::UpdateTeams(body) if body.action == TeamAction::AddEntities(vec!["Alice".to_string(), "Bob".to_string()])));
+		assert_eq!(scoreboard.team_of("Bob"), Some("blue"));
+		assert_eq!(scoreboard.team_of("Alice"), Some("red"));
+
+		// a new player gets both teams with their entities
+		let packets = scoreboard.packets();
+		assert!(matches!(&packets[1], Packet::UpdateTeams(body) if matches!(&body.action, TeamAction::Create(_, entities) if entities == &vec!["Alice".to_string()])));
+	}
+}
@@ -0,0 +1,292 @@
+//! Keeps the server's idea of where the player is in sync with the client's. The server moves the player
+//! with `SynchronizePlayerPosition`, which carries a teleport ID that the client echoes back in
+//! `ConfirmTeleportation` once it has moved. Until then the client may still send movement from before
+//! the teleport, so vanilla ignores all movement while a teleport is unconfirmed and [TeleportTracker]
+//! does the same.
+//!
+//! Like [KeepAlive](crate::network::client::keep_alive::KeepAlive), the tracker doesn't do any IO itself
+//! apart from [TeleportTracker::send], so it can be driven from any loop.
+
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::network::client::CraftClient;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::{Packet, SynchronizePlayerPositionBody};
+use crate::protocol::packets::packet_definer::PacketState;
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+/// The flags of `SynchronizePlayerPosition`. Each field that has its flag set is added to the player's
+/// current value instead of replacing it.
+pub const RELATIVE_X: i8 = 0x01;
+pub const RELATIVE_Y: i8 = 0x02;
+pub const RELATIVE_Z: i8 = 0x04;
+pub const RELATIVE_YAW: i8 = 0x08;
+pub const RELATIVE_PITCH: i8 = 0x10;
+
+/// How long vanilla waits for a teleport to be confirmed before sending it again, 20 ticks
+pub const DEFAULT_RESEND_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Where a player is and which way they are looking
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PlayerPosition {
+	pub x: f64,
+	pub y: f64,
+	pub z: f64,
+	pub yaw: f32,
+	pub pitch: f32,
+}
+
+impl PlayerPosition {
+	pub fn new(x: f64, y: f64, z: f64, yaw: f32, pitch: f32) -> Self {
+		Self {
+			x,
+			y,
+			z,
+			yaw,
+			pitch,
+		}
+	}
+
+	/// Apply a teleport to this position, adding the fields that are relative in `flags`
+	pub fn apply(&self, target: &PlayerPosition, flags: i8) -> PlayerPosition {
+		let pick = |flag: i8, current: f64, value: f64| if flags & flag != 0 { current + value } else { value };
+
+		PlayerPosition {
+			x: pick(RELATIVE_X, self.x, target.x),
+			y: pick(RELATIVE_Y, self.y, target.y),
+			z: pick(RELATIVE_Z, self.z, target.z),
+			yaw: pick(RELATIVE_YAW, self.yaw as f64, target.yaw as f64) as f32,
+			pitch: pick(RELATIVE_PITCH, self.pitch as f64, target.pitch as f64) as f32,
+		}
+	}
+
+	fn is_finite(&self) -> bool {
+		self.x.is_finite() && self.y.is_finite() && self.z.is_finite() && self.yaw.is_finite() && self.pitch.is_finite()
+	}
+}
+
+/// What a packet from the client did to its position, see [TeleportTracker::handle_packet]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Movement {
+	/// The client confirmed the last teleport and is now at this position
+	Confirmed(PlayerPosition),
+	/// The client moved to this position
+	Moved(PlayerPosition),
+	/// The packet was stale, for a teleport that was replaced, or had coordinates that aren't finite
+	Ignored,
+}
+
+/// Tracks the teleports sent to one client and its position, see the [module docs](self)
+///
+/// ```
+/// # use sandstone::protocol::packets::{ConfirmTeleportationBody, Packet, SetPlayerPositionBody};
+/// # use sandstone::protocol::play::teleport::{Movement, PlayerPosition, TeleportTracker};
+/// # use sandstone::protocol_types::datatypes::var_types::VarInt;
+/// let mut tracker = TeleportTracker::new();
+/// let _packet = tracker.teleport(PlayerPosition::new(0.5, 64.0, 0.5, 0.0, 0.0), 0);
+///
+/// // sent before the client saw the teleport
+/// let stale = Packet::SetPlayerPosition(SetPlayerPositionBody::new(100.0, 70.0, 100.0, true));
+/// assert_eq!(tracker.handle_packet(&stale), Some(Movement::Ignored));
+///
+/// let confirm = Packet::ConfirmTeleportation(ConfirmTeleportationBody::new(VarInt(1)));
+/// assert!(matches!(tracker.handle_packet(&confirm), Some(Movement::Confirmed(_))));
+/// assert_eq!(tracker.position().unwrap().y, 64.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TeleportTracker {
+	last_id: i32,
+	/// The ID, absolute target and send time of the teleport that hasn't been confirmed
+	pending: Option<(i32, PlayerPosition, Instant)>,
+	position: Option<PlayerPosition>,
+	on_ground: bool,
+	resend_interval: Duration,
+}
+
+impl TeleportTracker {
+	pub fn new() -> Self {
+		Self {
+			last_id: 0,
+			pending: None,
+			position: None,
+			on_ground: false,
+			resend_interval: DEFAULT_RESEND_INTERVAL,
+		}
+	}
+
+	/// Set how long to wait for a confirmation before [TeleportTracker::poll_resend] sends the teleport
+	/// again
+	pub fn set_resend_interval(&mut self, interval: Duration) {
+		self.resend_interval = interval;
+	}
+
+	/// The next teleport ID. IDs count up from 1 and wrap back to 0 like vanilla.
+	fn next_id(&mut self) -> i32 {
+		self.last_id = if self.last_id == i32::MAX - 1 { 0 } else { self.last_id + 1 };
+		self.last_id
+	}
+
+	/// Get the packet that teleports the client, replacing any teleport that hasn't been confirmed. Fields
+	/// with a relative flag are added to the last known position, or to 0 if there isn't one.
+	pub fn teleport(&mut self, target: PlayerPosition, flags: i8) -> Packet {
+		let absolute = self.position.unwrap_or_default().apply(&target, flags);
+		let id = self.next_id();
+		self.pending = Some((id, absolute, Instant::now()));
+
+		Packet::SynchronizePlayerPosition(SynchronizePlayerPositionBody::new(target.x, target.y, target.z, target.yaw, target.pitch, flags, VarInt(id)))
+	}
+
+	/// Teleport the client, see [TeleportTracker::teleport]. This is only possible in the play state.
+	pub async fn send(&mut self, client: &mut CraftClient, target: PlayerPosition, flags: i8) -> Result<(), NetworkError> {
+		if client.packet_state != PacketState::PLAY {
+			return Err(NetworkError::InvalidPacketState);
+		}
+
+		client.send_packet(self.teleport(target, flags)).await
+	}
+
+	/// Get the teleport again with a new ID if the client hasn't confirmed it in time, which vanilla does
+	/// in case the packet was missed
+	pub fn poll_resend(&mut self) -> Option<Packet> {
+		let (_, target, sent) = self.pending?;
+
+		if Instant::now().duration_since(sent) < self.resend_interval {
+			return None;
+		}
+
+		Some(self.teleport(target, 0))
+	}
+
+	/// Check a packet received from the client. Returns `None` if it isn't a movement packet or a teleport
+	/// confirmation.
+	///
+	/// Movement is ignored while a teleport is waiting to be confirmed, and before the first teleport
+	/// since the client can't have a position until then.
+	pub fn handle_packet(&mut self, packet: &Packet) -> Option<Movement> {
+		if let Packet::ConfirmTeleportation(body) = packet {
+			return match self.pending {
+				Some((id, target, _)) if id == body.teleport_id.0 => {
+					self.pending = None;
+					self.position = Some(target);
+					Some(Movement::Confirmed(target))
+				}
+				_ => Some(Movement::Ignored),
+			};
+		}
+
+		let (x, y, z, yaw, pitch, on_ground) = match packet {
+			Packet::SetPlayerPosition(body) => (Some(body.x), Some(body.feet_y), Some(body.z), None, None, body.on_ground),
+			Packet::SetPlayerPositionAndRotation(body) => (Some(body.x), Some(body.feet_y), Some(body.z), Some(body.yaw), Some(body.pitch), body.on_ground),
+			Packet::SetPlayerRotation(body) => (None, None, None, Some(body.yaw), Some(body.pitch), body.on_ground),
+			Packet::SetPlayerOnGround(body) => (None, None, None, None, None, body.on_ground),
+			_ => return None,
+		};
+
+		let current = match self.position {
+			Some(position) if self.pending.is_none() => position,
+			_ => return Some(Movement::Ignored),
+		};
+
+		let moved = PlayerPosition {
+			x: x.unwrap_or(current.x),
+			y: y.unwrap_or(current.y),
+			z: z.unwrap_or(current.z),
+			yaw: yaw.unwrap_or(current.yaw),
+			pitch: pitch.unwrap_or(current.pitch),
+		};
+
+		if !moved.is_finite() {
+			return Some(Movement::Ignored);
+		}
+
+		self.position = Some(moved);
+		self.on_ground = on_ground;
+
+		Some(Movement::Moved(moved))
+	}
+
+	/// The last position the client confirmed or moved to
+	pub fn position(&self) -> Option<PlayerPosition> {
+		self.position
+	}
+
+	/// Whether the client said it was on the ground in its last movement packet
+	pub fn on_ground(&self) -> bool {
+		self.on_ground
+	}
+
+	/// Check if a teleport has been sent that the client hasn't confirmed yet
+	pub fn is_awaiting(&self) -> bool {
+		self.pending.is_some()
+	}
+
+	/// The ID of the teleport that hasn't been confirmed yet
+	pub fn pending_id(&self) -> Option<i32> {
+		self.pending.map(|(id, _, _)| id)
+	}
+}
+
+impl Default for TeleportTracker {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+
+	use crate::protocol::packets::{ConfirmTeleportationBody, Packet, PlayPongBody, SetPlayerOnGroundBody, SetPlayerPositionBody, SetPlayerRotationBody};
+	use crate::protocol::play::teleport::{Movement, PlayerPosition, RELATIVE_X, RELATIVE_YAW, TeleportTracker};
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	fn confirm(id: i32) -> Packet {
+		Packet::ConfirmTeleportation(ConfirmTeleportationBody::new(VarInt(id)))
+	}
+
+	#[test]
+	fn test_teleport_tracker() {
+		let mut tracker = TeleportTracker::new();
+
+		// nothing to move from yet
+		assert_eq!(tracker.handle_packet(&Packet::SetPlayerOnGround(SetPlayerOnGroundBody::new(true))), Some(Movement::Ignored));
+
+		tracker.teleport(PlayerPosition::new(1.0, 64.0, 1.0, 90.0, 0.0), 0);
+		tracker.teleport(PlayerPosition::new(2.0, 64.0, 2.0, 90.0, 0.0), 0);
+		assert_eq!(tracker.pending_id(), Some(2));
+
+		// confirms the teleport that was replaced
+		assert_eq!(tracker.handle_packet(&confirm(1)), Some(Movement::Ignored));
+		assert!(tracker.is_awaiting());
+		assert_eq!(tracker.handle_packet(&confirm(2)), Some(Movement::Confirmed(PlayerPosition::new(2.0, 64.0, 2.0, 90.0, 0.0))));
+
+		let moved = tracker.handle_packet(&Packet::SetPlayerRotation(SetPlayerRotationBody::new(45.0, 10.0, true)));
+		assert_eq!(moved, Some(Movement::Moved(PlayerPosition::new(2.0, 64.0, 2.0, 45.0, 10.0))));
+		assert!(tracker.on_ground());
+
+		assert_eq!(tracker.handle_packet(&Packet::SetPlayerPosition(SetPlayerPositionBody::new(f64::NAN, 64.0, 2.0, true))), Some(Movement::Ignored));
+		assert_eq!(tracker.handle_packet(&confirm(3)), Some(Movement::Ignored));
+		assert_eq!(tracker.handle_packet(&Packet::PlayPong(PlayPongBody::new(1))), None);
+
+		// relative fields are resolved against the last position
+		let packet = tracker.teleport(PlayerPosition::new(3.0, 70.0, 0.0, 15.0, 0.0), RELATIVE_X | RELATIVE_YAW);
+		let Packet::SynchronizePlayerPosition(body) = packet else { panic!() };
+		assert_eq!((body.x, body.flags), (3.0, RELATIVE_X | RELATIVE_YAW));
+		assert_eq!(tracker.handle_packet(&confirm(3)), Some(Movement::Confirmed(PlayerPosition::new(5.0, 70.0, 0.0, 60.0, 0.0))));
+	}
+
+	#[test]
+	fn test_teleport_resend() {
+		let mut tracker = TeleportTracker::new();
+		assert!(tracker.poll_resend().is_none());
+
+		tracker.teleport(PlayerPosition::new(1.0, 64.0, 1.0, 0.0, 0.0), RELATIVE_X);
+		assert!(tracker.poll_resend().is_none());
+
+		tracker.set_resend_interval(Duration::ZERO);
+		let Some(Packet::SynchronizePlayerPosition(body)) = tracker.poll_resend() else { panic!() };
+		assert_eq!((body.flags, body.teleport_id), (0, VarInt(2)));
+	}
+}
@@ -0,0 +1,111 @@
+//! Messages from the server rather than from a player: system chat, the action bar above the hotbar and
+//! titles in the middle of the screen. These are all unsigned, so they can say anything. The
+//! [CraftClient](crate::network::client::CraftClient) has `send_message`, `send_action_bar` and
+//! `show_title` for sending them.
+
+use crate::protocol::packets::{ClearTitlesBody, Packet, SetActionBarTextBody, SetSubtitleTextBody, SetTitleAnimationTimesBody, SetTitleTextBody, SystemChatMessageBody};
+use crate::protocol_types::datatypes::chat::{NbtTextComponent, TextComponent};
+
+/// How long a title fades in, stays and fades out, in ticks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TitleTimes {
+	pub fade_in: i32,
+	pub stay: i32,
+	pub fade_out: i32,
+}
+
+impl TitleTimes {
+	/// The times the client uses until it is told otherwise
+	pub const DEFAULT: TitleTimes = TitleTimes { fade_in: 10, stay: 70, fade_out: 20 };
+
+	pub fn new(fade_in: i32, stay: i32, fade_out: i32) -> Self {
+		Self {
+			fade_in,
+			stay,
+			fade_out,
+		}
+	}
+
+	pub fn packet(&self) -> Packet {
+		Packet::SetTitleAnimationTimes(SetTitleAnimationTimesBody::new(self.fade_in, self.stay, self.fade_out))
+	}
+}
+
+impl Default for TitleTimes {
+	fn default() -> Self {
+		Self::DEFAULT
+	}
+}
+
+/// A title with an optional subtitle and times
+///
+/// ```
+/// # use sandstone::protocol::play::title::{Title, TitleTimes};
+/// # use sandstone::protocol_types::datatypes::chat::TextComponent;
+/// let title = Title::new(TextComponent::new("Round 2"))
+///     .subtitle(TextComponent::new("Fight!"))
+///     .times(TitleTimes::new(5, 40, 5));
+///
+/// // times, subtitle and then the title, which shows them
+/// assert_eq!(title.packets().len(), 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Title {
+	title: TextComponent,
+	subtitle: Option<TextComponent>,
+	times: Option<TitleTimes>,
+}
+
+impl Title {
+	pub fn new(title: TextComponent) -> Self {
+		Self {
+			title,
+			subtitle: None,
+			times: None,
+		}
+	}
+
+	pub fn subtitle(mut self, subtitle: TextComponent) -> Self {
+		self.subtitle = Some(subtitle);
+		self
+	}
+
+	/// The times for this title and every title after it. The client keeps the last times it was sent if
+	/// these aren't set.
+	pub fn times(mut self, times: TitleTimes) -> Self {
+		self.times = Some(times);
+		self
+	}
+
+	/// The packets that show this title. The title packet goes last, since it is what starts the
+	/// animation with the subtitle and times that were set before it.
+	pub fn packets(&self) -> Vec<Packet> {
+		let mut packets = vec![];
+
+		if let Some(times) = &self.times {
+			packets.push(times.packet());
+		}
+
+		if let Some(subtitle) = &self.subtitle {
+			packets.push(Packet::SetSubtitleText(SetSubtitleTextBody::new(NbtTextComponent::new(subtitle.clone()))));
+		}
+
+		packets.push(Packet::SetTitleText(SetTitleTextBody::new(NbtTextComponent::new(self.title.clone()))));
+		packets
+	}
+}
+
+/// The `SystemChatMessage` packet that shows a message in chat
+pub fn message_packet(message: TextComponent) -> Packet {
+	Packet::SystemChatMessage(SystemChatMessageBody::new(NbtTextComponent::new(message), false))
+}
+
+/// The packet that shows text above the hotbar. `SystemChatMessage` with the overlay flag does the same.
+pub fn action_bar_packet(text: TextComponent) -> Packet {
+	Packet::SetActionBarText(SetActionBarTextBody::new(NbtTextComponent::new(text)))
+}
+
+/// The packet that hides the title, and also resets the times to [TitleTimes::DEFAULT] if `reset` is set
+pub fn clear_packet(reset: bool) -> Packet {
+	Packet::ClearTitles(ClearTitlesBody::new(reset))
+}
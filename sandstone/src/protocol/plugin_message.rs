@@ -0,0 +1,255 @@
+//! Plugin messages (custom payloads) carry data on a named channel during configuration and play, for
+//! anything that the protocol doesn't cover, such as talking to mods or a proxy. Vanilla only uses
+//! [BRAND], which each side sends once to name the software it is running.
+//!
+//! [PluginChannels] keeps a handler for each channel and passes received messages to the right one. See
+//! [here](https://wiki.vg/Plugin_channels) for more.
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::{Packet, PlayPluginMessageBody, PlayServerboundPluginMessageBody, PluginMessageBody, ServerboundPluginMessageBody};
+use crate::protocol::packets::packet_definer::{PacketDirection, PacketState};
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
+use crate::protocol_types::datatypes::identifier::Identifier;
+
+/// The channel that each side names its software on, such as `vanilla` or `fabric`
+pub const BRAND: &str = "minecraft:brand";
+/// The channel that announces which channels a side listens on, as names separated by `\0`
+pub const REGISTER: &str = "minecraft:register";
+/// The channel that takes back channels announced on [REGISTER]
+pub const UNREGISTER: &str = "minecraft:unregister";
+
+/// The largest message that the server can send
+pub const MAX_CLIENTBOUND_SIZE: usize = 1048576;
+/// The largest message that the client can send
+pub const MAX_SERVERBOUND_SIZE: usize = 32767;
+
+/// A plugin message sent by either side, in either state
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PluginMessage {
+	pub channel: Identifier,
+	pub data: Vec<u8>,
+}
+
+impl PluginMessage {
+	pub fn new(channel: Identifier, data: Vec<u8>) -> Self {
+		Self {
+			channel,
+			data,
+		}
+	}
+
+	/// A [BRAND] message
+	pub fn brand(brand: &str) -> Self {
+		let mut serializer = McSerializer::new();
+		brand.to_string().mc_serialize(&mut serializer).expect("a string always serializes");
+
+		Self::new(BRAND.parse().expect("the brand channel is a valid identifier"), serializer.output)
+	}
+
+	/// A [REGISTER] message announcing the channels
+	pub fn register<'a, I: IntoIterator<Item = &'a Identifier>>(channels: I) -> Self {
+		let names: Vec<String> = channels.into_iter().map(Identifier::to_string).collect();
+		Self::new(REGISTER.parse().expect("the register channel is a valid identifier"), names.join("\0").into_bytes())
+	}
+
+	/// The message in a packet, or `None` if the packet isn't a plugin message
+	pub fn from_packet(packet: &Packet) -> Option<Self> {
+		let (channel, data) = match packet {
+			Packet::PluginMessage(body) => (&body.channel, &body.data),
+			Packet::ServerboundPluginMessage(body) => (&body.channel, &body.data),
+			Packet::PlayPluginMessage(body) => (&body.channel, &body.data),
+			Packet::PlayServerboundPluginMessage(body) => (&body.channel, &body.data),
+			_ => return None,
+		};
+
+		Some(Self::new(channel.clone(), data.to_vec()))
+	}
+
+	/// The packet that sends this message in a state and direction. Messages over [MAX_CLIENTBOUND_SIZE]
+	/// or [MAX_SERVERBOUND_SIZE] fail with [NetworkError::PacketTooLarge].
+	pub fn to_packet(&self, state: PacketState, direction: PacketDirection) -> Result<Packet, NetworkError> {
+		let max = match direction {
+			PacketDirection::CLIENT => MAX_CLIENTBOUND_SIZE,
+			_ => MAX_SERVERBOUND_SIZE,
+		};
+
+		if self.data.len() > max {
+			return Err(NetworkError::PacketTooLarge);
+		}
+
+		let channel = self.channel.clone();
+		let data = self.data.clone().into();
+
+		match (state, direction) {
+			(PacketState::CONFIGURATION, PacketDirection::CLIENT) => Ok(Packet::PluginMessage(PluginMessageBody::new(channel, data))),
+			(PacketState::CONFIGURATION, _) => Ok(Packet::ServerboundPluginMessage(ServerboundPluginMessageBody::new(channel, data))),
+			(PacketState::PLAY, PacketDirection::CLIENT) => Ok(Packet::PlayPluginMessage(PlayPluginMessageBody::new(channel, data))),
+			(PacketState::PLAY, _) => Ok(Packet::PlayServerboundPluginMessage(PlayServerboundPluginMessageBody::new(channel, data))),
+			_ => Err(NetworkError::InvalidPacketState),
+		}
+	}
+
+	/// Read the name from a [BRAND] message, or `None` if this is a different channel
+	pub fn read_brand(&self) -> Option<Result<String, NetworkError>> {
+		if self.channel.to_string() != BRAND {
+			return None;
+		}
+
+		let mut deserializer = McDeserializer::new(&self.data);
+		Some(String::mc_deserialize(&mut deserializer).map_err(NetworkError::from))
+	}
+
+	/// The channels in a [REGISTER] or [UNREGISTER] message. Names that aren't valid identifiers are skipped.
+	pub fn read_channels(&self) -> Vec<Identifier> {
+		String::from_utf8_lossy(&self.data)
+			.split('\0')
+			.filter_map(|name| name.parse().ok())
+			.collect()
+	}
+}
+
+/// Handles the messages on a channel
+pub type ChannelHandler = Arc<dyn Fn(&PluginMessage) + Send + Sync>;
+
+/// A handler for each channel, see the [module docs](self). Cloning gives another handle to the same
+/// handlers.
+///
+/// ```
+/// # use std::sync::{Arc, Mutex};
+/// # use sandstone::protocol::plugin_message::{PluginChannels, PluginMessage};
+/// # use sandstone::protocol::packets::packet_definer::{PacketDirection, PacketState};
+/// let brand = Arc::new(Mutex::new(None));
+/// let seen = brand.clone();
+///
+/// let mut channels = PluginChannels::new();
+/// channels.register("minecraft:brand".parse().unwrap(), move |message| {
+///     *seen.lock().unwrap() = message.read_brand().and_then(Result::ok);
+/// });
+///
+/// let packet = PluginMessage::brand("vanilla").to_packet(PacketState::PLAY, PacketDirection::SERVER).unwrap();
+/// assert!(channels.dispatch(&packet));
+/// assert_eq!(brand.lock().unwrap().as_deref(), Some("vanilla"));
+/// ```
+#[derive(Clone, Default)]
+pub struct PluginChannels {
+	handlers: HashMap<Identifier, ChannelHandler>,
+}
+
+impl PluginChannels {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Handle the messages on a channel, replacing any handler it already had
+	pub fn register<F: Fn(&PluginMessage) + Send + Sync + 'static>(&mut self, channel: Identifier, handler: F) {
+		self.handlers.insert(channel, Arc::new(handler));
+	}
+
+	/// Stop handling a channel, returning whether it had a handler
+	pub fn unregister(&mut self, channel: &Identifier) -> bool {
+		self.handlers.remove(channel).is_some()
+	}
+
+	pub fn is_registered(&self, channel: &Identifier) -> bool {
+		self.handlers.contains_key(channel)
+	}
+
+	/// The channels with a handler
+	pub fn channels(&self) -> impl Iterator<Item = &Identifier> {
+		self.handlers.keys()
+	}
+
+	/// A [REGISTER] message announcing every channel with a handler, so that the other side knows what it
+	/// can send
+	pub fn register_message(&self) -> PluginMessage {
+		let mut channels: Vec<&Identifier> = self.channels().collect();
+		channels.sort();
+
+		PluginMessage::register(channels)
+	}
+
+	/// Pass a plugin message to the handler for its channel. Returns `false` if the packet isn't a plugin
+	/// message or nothing handles its channel.
+	pub fn dispatch(&self, packet: &Packet) -> bool {
+		let Some(message) = PluginMessage::from_packet(packet) else {
+			return false;
+		};
+
+		match self.handlers.get(&message.channel) {
+			Some(handler) => {
+				handler(&message);
+				true
+			}
+			None => false,
+		}
+	}
+}
+
+impl Debug for PluginChannels {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("PluginChannels")
+			.field("channels", &self.handlers.keys().collect::<Vec<_>>())
+			.finish()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, Mutex};
+
+	use crate::network::network_error::NetworkError;
+	use crate::protocol::packets::Packet;
+	use crate::protocol::packets::packet_definer::{PacketDirection, PacketState};
+	use crate::protocol::plugin_message::{MAX_SERVERBOUND_SIZE, PluginChannels, PluginMessage};
+	use crate::protocol::serialization::{McDeserializer, McSerialize, McSerializer, StateBasedDeserializer};
+
+	#[test]
+	fn test_plugin_messages() {
+		let brand = PluginMessage::brand("sandstone");
+		assert_eq!(brand.read_brand(), Some(Ok("sandstone".to_string())));
+
+		for state in [PacketState::CONFIGURATION, PacketState::PLAY] {
+			for direction in [PacketDirection::CLIENT, PacketDirection::SERVER] {
+				let packet = brand.to_packet(state, direction).unwrap();
+
+				let mut serializer = McSerializer::new();
+				packet.mc_serialize(&mut serializer).unwrap();
+				let mut deserializer = McDeserializer::new(&serializer.output);
+				let out = Packet::deserialize_state(&mut deserializer, state, direction).unwrap();
+
+				assert_eq!(PluginMessage::from_packet(&out), Some(brand.clone()));
+			}
+		}
+
+		let big = PluginMessage::new("example:big".parse().unwrap(), vec![0; MAX_SERVERBOUND_SIZE + 1]);
+		assert_eq!(big.to_packet(PacketState::PLAY, PacketDirection::SERVER), Err(NetworkError::PacketTooLarge));
+		assert!(big.to_packet(PacketState::PLAY, PacketDirection::CLIENT).is_ok());
+		assert_eq!(brand.to_packet(PacketState::LOGIN, PacketDirection::CLIENT), Err(NetworkError::InvalidPacketState));
+	}
+
+	#[test]
+	fn test_plugin_channels() {
+		let received = Arc::new(Mutex::new(vec![]));
+		let handled = received.clone();
+
+		let mut channels = PluginChannels::new();
+		channels.register("example:b".parse().unwrap(), move |message| handled.lock().unwrap().push(message.data.clone()));
+		channels.register("example:a".parse().unwrap(), |_| {});
+
+		let message = PluginMessage::new("example:b".parse().unwrap(), vec![1, 2, 3]);
+		assert!(channels.dispatch(&message.to_packet(PacketState::CONFIGURATION, PacketDirection::SERVER).unwrap()));
+		assert!(!channels.dispatch(&PluginMessage::brand("vanilla").to_packet(PacketState::CONFIGURATION, PacketDirection::SERVER).unwrap()));
+		assert_eq!(*received.lock().unwrap(), vec![vec![1, 2, 3]]);
+
+		let register = channels.register_message();
+		assert_eq!(register.data, b"example:a\0example:b");
+		assert_eq!(register.read_channels(), vec!["example:a".parse().unwrap(), "example:b".parse().unwrap()]);
+
+		assert!(channels.unregister(&"example:a".parse().unwrap()));
+		assert!(!channels.is_registered(&"example:a".parse().unwrap()));
+	}
+}
@@ -6,6 +6,9 @@
 
 use std::cmp::min;
 
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
 use crate::protocol::packets::packet_definer::{PacketDirection, PacketState};
 use crate::protocol::serialization::serializer_error::SerializingErr;
 
@@ -39,29 +42,40 @@ impl McSerializer {
 		}
 	}
 	
-	/// Set the size of the internal buffer. This will resize the buffer to the size specified.
+	/// Make sure the internal buffer can hold at least `size` bytes in total without reallocating.
 	/// The provided size must be greater than the current length of the buffer.
 	pub fn set_size(&mut self, size: usize) -> SerializingResult<()> {
 		if size < self.output.len() {
 			return Err(SerializingErr::UniqueFailure("Cannot set size to less than current length".to_string()));
 		}
 		
-		self.output.reserve(size);
+		self.output.reserve(size - self.output.len());
 		
 		Ok(())
 	}
 
-	/// Clear the existing serialized data from the internal buffer
+	/// Clear the existing serialized data from the internal buffer. The allocated capacity is kept, so
+	/// the serializer can be reused without allocating again.
 	pub fn clear(&mut self) {
 		self.output.clear();
 	}
 
-	/// Add a slice of bytes to the internal buffer. Reallocates the buffer
-	/// for the new amount of space required before pushing.
+	/// Clear the buffer so that it can be reused for the next packet, like [McSerializer::clear], but
+	/// give back memory above `max_capacity`. This stops one huge packet from holding on to its
+	/// buffer for the rest of the connection.
+	pub fn reset(&mut self, max_capacity: usize) {
+		self.output.clear();
+		self.output.shrink_to(max_capacity);
+	}
+
+	/// The number of bytes the internal buffer can hold before it has to reallocate
+	pub fn capacity(&self) -> usize {
+		self.output.capacity()
+	}
+
+	/// Add a slice of bytes to the internal buffer, growing it first if needed
 	pub fn serialize_bytes(&mut self, input: &[u8]) {
-		for b in input {
-			self.output.push(*b);
-		}
+		self.output.extend_from_slice(input);
 	}
 
 	pub fn serialize_vec(&mut self, vec: Vec<u8>) {
@@ -77,6 +91,11 @@ impl McSerializer {
 		self.serialize_bytes(s.as_bytes());
 	}
 
+	/// Serialize a value as a JSON string, prefixed by its length like any other string
+	pub fn serialize_json<T: Serialize>(&mut self, value: &T) -> SerializingResult<()> {
+		serde_json::to_string(value).map_err(|e| SerializingErr::UniqueFailure(format!("Failed to serialize JSON: {}", e)))?.mc_serialize(self)
+	}
+
 	pub fn get_last(&self) -> Option<&u8> {
 		self.output.last()
 	}
@@ -90,20 +109,50 @@ impl McSerializer {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct McDeserializer<'a> {
 	pub data: &'a [u8],
-	pub index: usize
+	pub index: usize,
+	/// The index that [McDeserializer::reset] goes back to
+	mark: usize,
 }
 
 impl <'a> McDeserializer<'a> {
 	pub fn new(data: &'a [u8]) -> Self {
 		Self {
 			data,
-			index: 0
+			index: 0,
+			mark: 0,
 		}
 	}
 
 	/// Collect the remaining data into a sub-slice
-	pub fn collect_remaining(&self) -> &[u8] {
-		&self.data[self.index..]
+	pub fn collect_remaining(&self) -> &'a [u8] {
+		&self.data[self.index.min(self.data.len())..]
+	}
+
+	/// The number of bytes that haven't been read yet
+	pub fn remaining(&self) -> usize {
+		self.data.len().saturating_sub(self.index)
+	}
+
+	/// Look at the next byte without consuming it
+	pub fn peek_u8(&self) -> Option<u8> {
+		self.data.get(self.index).copied()
+	}
+
+	/// Look at the next `amount` bytes without consuming them, if there are that many left
+	pub fn peek_bytes(&self, amount: usize) -> Option<&'a [u8]> {
+		self.data.get(self.index..self.index.checked_add(amount)?)
+	}
+
+	/// Deserialize the next value without consuming it, such as checking a VarInt before deciding how
+	/// to read what follows
+	pub fn peek<T: McDeserialize>(&self) -> SerializingResult<T> {
+		let mut lookahead = self.clone();
+		T::mc_deserialize(&mut lookahead)
+	}
+
+	/// Remember the current position, so that [McDeserializer::reset] can come back to it
+	pub fn mark(&mut self) {
+		self.mark = self.index;
 	}
 
 	/// Slice the internal buffer, starting at the current index and up to the bound provided. Will
@@ -129,6 +178,12 @@ impl <'a> McDeserializer<'a> {
 		Some(slice)
 	}
 
+	/// Deserialize a length prefixed JSON string into a value
+	pub fn deserialize_json<T: DeserializeOwned>(&mut self) -> SerializingResult<T> {
+		let json = String::mc_deserialize(self)?;
+		serde_json::from_str(&json).map_err(|e| SerializingErr::UniqueFailure(format!("Failed to deserialize JSON: {}", e)))
+	}
+
 	pub fn pop(&mut self) -> Option<u8> {
 		if self.index < self.data.len() {
 			let u = self.data[self.index];
@@ -156,21 +211,24 @@ impl <'a> McDeserializer<'a> {
 		self.index >= self.data.len()
 	}
 
+	/// Go back to the position saved by [McDeserializer::mark], or the start of the data if nothing
+	/// was marked
 	pub fn reset(&mut self) {
-		self.index = 0;
+		self.index = self.mark;
 	}
 
 	/// Creates a new McDeserializer only including the remaining unused data.
 	/// Used in conjunction with reset()
-	pub fn create_sub_deserializer(&self) -> McDeserializer {
-		McDeserializer::new(&self.data[self.index..])
+	pub fn create_sub_deserializer(&self) -> McDeserializer<'a> {
+		McDeserializer::new(self.collect_remaining())
 	}
 
 	/// Create a new McDeserializer with a start at `index` and an end at `index + end`.
 	/// Basically reserves the number of bytes you specify for the sub-deserializer.
-	/// Also increments the parent McDeserializer's index by `end`
-	pub fn sub_deserializer_length(&mut self, end: usize) -> SerializingResult<McDeserializer> {
-		if self.index + end > self.data.len() {
+	/// Also increments the parent McDeserializer's index by `end`. The sub-deserializer can't read past
+	/// its end, which keeps a length-prefixed section from running into the data after it.
+	pub fn sub_deserializer_length(&mut self, end: usize) -> SerializingResult<'a, McDeserializer<'a>> {
+		if end > self.remaining() {
 			return Err(SerializingErr::UniqueFailure("Sub-deserializer length exceeds data length".to_string()));
 		}
 
@@ -2,11 +2,17 @@
 //! for serialization and deserialization operations. This is useful for debugging and error handling.
 
 use std::array::TryFromSliceError;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display, Formatter};
 use std::string::FromUtf8Error;
 
 use thiserror::Error;
 
+use crate::protocol::packets::packet_definer::{PacketDirection, PacketState};
+use crate::protocol::serialization::McDeserializer;
+
+/// How many bytes on either side of the failure are included in [ErrorContext::excerpt]
+const EXCERPT_RADIUS: usize = 8;
+
 /// A type that describes common errors encountered while serializing or deserializing network data.
 /// Each error either provides a description of the error or transparently passes the internal error,
 /// usually another error type.
@@ -32,6 +38,130 @@ pub enum SerializingErr {
 	UniqueFailure(String),
 	#[error("The current packet state does not match what is needed to deserialize this packet")]
 	InvalidPacketState,
+	#[error("NBT limit exceeded: {0}")]
+	NbtLimitExceeded(String),
+	#[error("Invalid identifier: {0}")]
+	InvalidIdentifier(String),
+	#[error("String of length {length} is longer than the maximum of {max}")]
+	StringTooLong { length: usize, max: usize },
+	/// Another error, along with where in the input it happened. See [SerializingErr::root] to get
+	/// the original error.
+	#[error("{error} ({context})")]
+	WithContext { error: Box<SerializingErr>, context: Box<ErrorContext> },
+}
+
+impl SerializingErr {
+	/// Attach the current position of the deserializer and what was being read to this error. If the
+	/// error already has context, it is kept as is, since the innermost field is the most useful one.
+	pub fn while_reading(self, expected: &str, deserializer: &McDeserializer) -> Self {
+		match self {
+			e @ SerializingErr::WithContext { .. } => e,
+			e => SerializingErr::WithContext {
+				error: Box::new(e),
+				context: Box::new(ErrorContext::new(Some(expected.to_string()), deserializer)),
+			}
+		}
+	}
+
+	/// Record which packet was being read when this error happened
+	pub fn in_packet(self, state: PacketState, direction: PacketDirection, packet_id: i32, deserializer: &McDeserializer) -> Self {
+		let packet = Some(PacketContext { state, direction, packet_id });
+
+		match self {
+			SerializingErr::WithContext { error, mut context } => {
+				context.packet = context.packet.or(packet);
+				SerializingErr::WithContext { error, context }
+			}
+			e => {
+				let mut context = ErrorContext::new(None, deserializer);
+				context.packet = packet;
+
+				SerializingErr::WithContext { error: Box::new(e), context: Box::new(context) }
+			}
+		}
+	}
+
+	/// The error without any context attached
+	pub fn root(&self) -> &SerializingErr {
+		match self {
+			SerializingErr::WithContext { error, .. } => error.root(),
+			e => e,
+		}
+	}
+
+	/// Where the error happened, if that is known
+	pub fn context(&self) -> Option<&ErrorContext> {
+		match self {
+			SerializingErr::WithContext { context, .. } => Some(context),
+			_ => None,
+		}
+	}
+}
+
+/// Where in the input a deserialization error happened. This is attached to errors as they pass up
+/// through packet and struct fields, see [SerializingErr::WithContext].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ErrorContext {
+	/// The index into the data being read. For packets, this is counted from the start of the packet id.
+	pub offset: usize,
+	/// What was being read, usually a field in the form `Struct.field`
+	pub expected: Option<String>,
+	pub packet: Option<PacketContext>,
+	/// The bytes around the offset in hex, with the byte at the offset in brackets
+	pub excerpt: String,
+}
+
+impl ErrorContext {
+	pub fn new(expected: Option<String>, deserializer: &McDeserializer) -> Self {
+		let offset = deserializer.index.min(deserializer.data.len());
+		let start = offset.saturating_sub(EXCERPT_RADIUS);
+		let end = (offset + EXCERPT_RADIUS).min(deserializer.data.len());
+
+		let excerpt = (start..end).map(|i| {
+			if i == offset {
+				format!("[{:02x}]", deserializer.data[i])
+			} else {
+				format!("{:02x}", deserializer.data[i])
+			}
+		}).collect::<Vec<String>>().join(" ");
+
+		Self {
+			offset,
+			expected,
+			packet: None,
+			excerpt,
+		}
+	}
+}
+
+impl Display for ErrorContext {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "at byte {}", self.offset)?;
+
+		if let Some(expected) = &self.expected {
+			write!(f, " reading {}", expected)?;
+		}
+
+		if let Some(packet) = &self.packet {
+			write!(f, " in {}", packet)?;
+		}
+
+		write!(f, ", near [{}]", self.excerpt)
+	}
+}
+
+/// The packet that was being read when an error happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PacketContext {
+	pub state: PacketState,
+	pub direction: PacketDirection,
+	pub packet_id: i32,
+}
+
+impl Display for PacketContext {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{:?} packet 0x{:02X} to {:?}", self.state, self.packet_id, self.direction)
+	}
 }
 
 impl PartialEq for SerializingErr {
@@ -47,6 +177,10 @@ impl PartialEq for SerializingErr {
 			(Self::UnknownFailure, Self::UnknownFailure) => true,
 			(Self::UniqueFailure(a), Self::UniqueFailure(b)) => a == b,
 			(Self::InvalidPacketState, Self::InvalidPacketState) => true,
+			(Self::NbtLimitExceeded(a), Self::NbtLimitExceeded(b)) => a == b,
+			(Self::InvalidIdentifier(a), Self::InvalidIdentifier(b)) => a == b,
+			(Self::StringTooLong { length: a, max: c }, Self::StringTooLong { length: b, max: d }) => a == b && c == d,
+			(Self::WithContext { error: a, context: c }, Self::WithContext { error: b, context: d }) => a == b && c == d,
 			_ => false,
 		}
 	}
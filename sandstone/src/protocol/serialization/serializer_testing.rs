@@ -130,11 +130,15 @@ impl McDeserialize for StringMix {
 
 #[cfg(test)]
 mod tests {
+	use sandstone_derive::{McDeserialize, McSerialize};
+	use serde::{Deserialize, Serialize};
+
 	use crate::protocol::packets::{HandshakingBody, Packet};
 	use crate::protocol::packets::packet_definer::{PacketDirection, PacketState};
 	use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, StateBasedDeserializer};
 	use crate::protocol::serialization::serializer_testing::{Group, StringMix, VarIntMix};
 	use crate::protocol_types::datatypes::var_types::{VarInt, VarLong};
+	use crate::protocol_types::datatypes::wrappers::ByteArrayRemainder;
 
 	#[test]
 	fn struct_serialization() {
@@ -236,4 +240,151 @@ mod tests {
 		assert_eq!([7, 8, 9], deserializer.slice(3));
 		assert_eq!([10], deserializer.slice(3));
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn test_lookahead() {
+		let v: Vec<u8> = vec![172, 2, 5, 6, 7, 8];
+		let mut deserializer = McDeserializer::new(&v);
+
+		assert_eq!(deserializer.peek_u8(), Some(172));
+		assert_eq!(deserializer.peek::<VarInt>().unwrap(), VarInt(300));
+		assert_eq!(deserializer.peek_bytes(3), Some(&[172, 2, 5][..]));
+		assert_eq!(deserializer.peek_bytes(7), None);
+		assert_eq!(deserializer.remaining(), 6);
+
+		assert_eq!(VarInt::mc_deserialize(&mut deserializer).unwrap(), VarInt(300));
+		deserializer.mark();
+		assert_eq!(u8::mc_deserialize(&mut deserializer).unwrap(), 5);
+		deserializer.reset();
+		assert_eq!(deserializer.remaining(), 4);
+
+		let mut sub = deserializer.sub_deserializer_length(2).unwrap();
+		assert_eq!(u8::mc_deserialize(&mut deserializer).unwrap(), 7); // both can be used at once
+		assert_eq!(sub.collect_remaining(), &[5, 6]);
+		assert!(i32::mc_deserialize(&mut sub).is_err());
+		assert!(deserializer.sub_deserializer_length(2).is_err());
+
+		deserializer.increment(10);
+		assert_eq!(deserializer.remaining(), 0);
+		assert_eq!(deserializer.peek_u8(), None);
+		assert!(deserializer.collect_remaining().is_empty());
+	}
+
+	#[derive(McSerialize, McDeserialize, Debug, PartialEq)]
+	struct DerivedPlain {
+		first: u8,
+		second: String,
+		third: VarInt,
+	}
+
+	#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+	struct JsonPart {
+		name: String,
+		count: u32,
+	}
+
+	#[derive(McSerialize, McDeserialize, Debug, PartialEq)]
+	struct DerivedAttributes {
+		#[varint]
+		id: i32,
+		#[length_prefixed]
+		names: Vec<String>,
+		#[json]
+		part: JsonPart,
+		trailing: ByteArrayRemainder,
+	}
+
+	#[derive(McSerialize, McDeserialize, Debug, PartialEq)]
+	struct DerivedTuple(#[varint] u16, bool, #[remainder] Vec<u8>);
+
+	#[test]
+	fn test_derive_plain() {
+		let a = DerivedPlain {
+			first: 16,
+			second: "hello".to_string(),
+			third: VarInt(300),
+		};
+
+		let mut serializer = McSerializer::new();
+		a.mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, vec![16, 5, 104, 101, 108, 108, 111, 172, 2]);
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(a, DerivedPlain::mc_deserialize(&mut deserializer).unwrap());
+	}
+
+	#[test]
+	fn test_derive_attributes() {
+		let a = DerivedAttributes {
+			id: 300,
+			names: vec!["a".to_string(), "bc".to_string()],
+			part: JsonPart {
+				name: "x".to_string(),
+				count: 2,
+			},
+			trailing: vec![9, 8, 7].into(),
+		};
+
+		let mut serializer = McSerializer::new();
+		a.mc_serialize(&mut serializer).unwrap();
+
+		let json = r#"{"name":"x","count":2}"#;
+		let mut expected = vec![172, 2, 2, 1, 97, 2, 98, 99, json.len() as u8];
+		expected.extend_from_slice(json.as_bytes());
+		expected.extend_from_slice(&[9, 8, 7]);
+		assert_eq!(serializer.output, expected);
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(a, DerivedAttributes::mc_deserialize(&mut deserializer).unwrap());
+
+		let a = DerivedTuple(1000, true, vec![4, 5]);
+
+		serializer.clear();
+		a.mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, vec![232, 7, 1, 4, 5]);
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(a, DerivedTuple::mc_deserialize(&mut deserializer).unwrap());
+	}
+
+	#[test]
+	fn test_serializer_reuse() {
+		let mut serializer = McSerializer::init_size(16);
+		serializer.serialize_bytes(&[1, 2, 3]);
+		serializer.set_size(64).unwrap();
+		assert!(serializer.capacity() >= 64);
+		assert!(serializer.set_size(2).is_err());
+
+		serializer.clear();
+		assert!(serializer.output.is_empty());
+		assert!(serializer.capacity() >= 64);
+
+		serializer.serialize_bytes(&[0; 1000]);
+		serializer.reset(32);
+		assert!(serializer.output.is_empty());
+		assert!(serializer.capacity() < 1000);
+	}
+
+	#[test]
+	fn test_container_serialization() {
+		let mut serializer = McSerializer::new();
+		vec![VarInt(1), VarInt(300)].mc_serialize(&mut serializer).unwrap();
+		Some("a".to_string()).mc_serialize(&mut serializer).unwrap();
+		None::<u8>.mc_serialize(&mut serializer).unwrap();
+		[1u8, 2, 3].mc_serialize(&mut serializer).unwrap();
+		(true, 5i16).mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, vec![2, 1, 172, 2, 1, 1, 97, 0, 1, 2, 3, 1, 0, 5]);
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(Vec::<VarInt>::mc_deserialize(&mut deserializer).unwrap(), vec![VarInt(1), VarInt(300)]);
+		assert_eq!(Option::<String>::mc_deserialize(&mut deserializer).unwrap(), Some("a".to_string()));
+		assert_eq!(Option::<u8>::mc_deserialize(&mut deserializer).unwrap(), None);
+		assert_eq!(<[u8; 3]>::mc_deserialize(&mut deserializer).unwrap(), [1, 2, 3]);
+		assert_eq!(<(bool, i16)>::mc_deserialize(&mut deserializer).unwrap(), (true, 5));
+		assert!(deserializer.is_at_end());
+
+		assert!(Vec::<u8>::mc_deserialize(&mut McDeserializer::new(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F])).is_err()); // negative length
+		assert!(Vec::<u8>::mc_deserialize(&mut McDeserializer::new(&[0x7F])).is_err()); // 127 elements that aren't there
+		assert!(Option::<u8>::mc_deserialize(&mut McDeserializer::new(&[2])).is_err());
+	}
+}
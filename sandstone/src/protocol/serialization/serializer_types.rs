@@ -99,8 +99,13 @@ mod macros {
     }
 }
 
+/// Vectors are prefixed by their number of elements as a VarInt. Use
+/// [ByteArrayRemainder](crate::protocol_types::datatypes::wrappers::ByteArrayRemainder) for bytes that
+/// take up the rest of a packet instead.
 impl<T: McSerialize> McSerialize for Vec<T> {
 	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> where T: McSerialize {
+		VarInt(self.len() as i32).mc_serialize(serializer)?;
+
 		for item in self {
 			item.mc_serialize(serializer)?;
 		}
@@ -111,9 +116,15 @@ impl<T: McSerialize> McSerialize for Vec<T> {
 
 impl<T: McDeserialize> McDeserialize for Vec<T> {
 	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> where Self: Sized, T: McDeserialize {
-		let mut vec = vec![];
+		let length = VarInt::mc_deserialize(deserializer)?.0;
+
+		if length < 0 {
+			return Err(SerializingErr::UniqueFailure(format!("Negative length {}", length)));
+		}
+
+		let mut vec = vec![]; // don't trust the length for the allocation
 
-		while !deserializer.is_at_end() {
+		for _ in 0..length {
 			vec.push(T::mc_deserialize(deserializer)?);
 		}
 
@@ -121,14 +132,14 @@ impl<T: McDeserialize> McDeserialize for Vec<T> {
 	}
 }
 
-
+/// Options are prefixed by a boolean that is true if the value is present, which is how the protocol
+/// sends almost every optional field.
 impl<T: McSerialize> McSerialize for Option<T> {
 	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> where T: McSerialize {
-		match self {
-			Some(item) => {
-				item.mc_serialize(serializer)?;
-			}
-			None => {}
+		self.is_some().mc_serialize(serializer)?;
+
+		if let Some(item) = self {
+			item.mc_serialize(serializer)?;
 		}
 
 		Ok(())
@@ -136,19 +147,65 @@ impl<T: McSerialize> McSerialize for Option<T> {
 }
 
 impl<T: McDeserialize> McDeserialize for Option<T> {
-	/// Special Deserialization note for Options: The protocol uses Options in some places, usually preceded
-	/// by a boolean indicating if the option is present. In order to handle these cases in a packet,
-	/// you must make a custom struct for at least the Option field and the accompanying boolean field, and
-	/// place them in the correct order in the packet. See [crate::protocol::packets::LoginPropertyElement] as an example.
 	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> where Self: Sized, T: McDeserialize {
-		if deserializer.is_at_end() {
+		if !bool::mc_deserialize(deserializer)? {
 			return Ok(None);
 		}
-		
+
 		Ok(Some(T::mc_deserialize(deserializer)?))
 	}
 }
 
+/// Fixed size arrays are sent as their elements one after another, without a length.
+impl<T: McSerialize, const N: usize> McSerialize for [T; N] {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		for item in self {
+			item.mc_serialize(serializer)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl<T: McDeserialize, const N: usize> McDeserialize for [T; N] {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let mut vec = Vec::with_capacity(N);
+
+		for _ in 0..N {
+			vec.push(T::mc_deserialize(deserializer)?);
+		}
+
+		vec.try_into().map_err(|_| SerializingErr::UnknownFailure) // always has N elements
+	}
+}
+
+/// Implements McSerialize and McDeserialize for tuples, which are sent as each element in order. This
+/// covers the `(bool, T)` shape as well as groups of fields that are only present together, such as
+/// `Option<(String, Position)>`.
+macro_rules! serialize_tuples {
+	($(($($name: ident),+)),*) => {
+		$(
+		impl<$($name: McSerialize),+> McSerialize for ($($name,)+) {
+			#[allow(non_snake_case)]
+			fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+				let ($($name,)+) = self;
+				$($name.mc_serialize(serializer)?;)+
+
+				Ok(())
+			}
+		}
+
+		impl<$($name: McDeserialize),+> McDeserialize for ($($name,)+) {
+			fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+				Ok(($($name::mc_deserialize(deserializer)?,)+))
+			}
+		}
+		)*
+	};
+}
+
+serialize_tuples!((A, B), (A, B, C), (A, B, C, D), (A, B, C, D, E));
+
 // not sure if this will ever be needed, but it's nice to have
 impl<T: McSerialize> McSerialize for Box<T> {
 	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> where T: McSerialize {
@@ -0,0 +1,136 @@
+//! Answers the server list ping of clients from before 1.7, which don't use the packet format at all.
+//! Some monitoring tools still send it, so the server answers it with the usual status response instead of
+//! failing to read a handshake.
+//!
+//! See [here](https://wiki.vg/Server_List_Ping#1.6) for the format.
+
+use std::time::Duration;
+
+use log::debug;
+use tokio::io::AsyncWriteExt;
+use tokio::time::Instant;
+
+use crate::network::client::CraftClient;
+use crate::network::network_error::NetworkError;
+use crate::protocol::packets::packet_definer::PacketState;
+use crate::protocol::status::motd::component_to_legacy;
+use crate::protocol::status::status_components::StatusResponseSpec;
+
+/// The first byte of a legacy ping. No handshake starts with it, since it would be the length of a
+/// packet far longer than a handshake.
+pub const LEGACY_PING: u8 = 0xFE;
+/// The legacy kick packet, which carries the response
+const LEGACY_KICK: u8 = 0xFF;
+/// The protocol number in the response. This is newer than every legacy client, so they show the
+/// version name as incompatible instead of letting the player try to join.
+const LEGACY_PROTOCOL: i32 = 127;
+/// How long to wait for the second byte, which tells 1.4 to 1.6 clients apart from older ones
+const VERSION_BYTE_WAIT: Duration = Duration::from_millis(100);
+
+/// The kind of legacy ping sent by a client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyPing {
+	/// Beta 1.8 to 1.3 only send `0xFE`, and show the MOTD and player count
+	Beta,
+	/// 1.4 to 1.6 send `0xFE 0x01`, which 1.6 follows with an `MC|PingHost` plugin message. These also
+	/// show the version name.
+	V1_4,
+}
+
+impl LegacyPing {
+	/// Build the kick packet that answers this ping, using the description and player count of `status`
+	pub fn response(&self, status: &StatusResponseSpec) -> Vec<u8> {
+		let motd = component_to_legacy(status.description()).replace('\n', " ");
+		let players = status.players();
+
+		let message = match self {
+			LegacyPing::Beta => format!("{}§{}§{}", strip_color_codes(&motd), players.online(), players.max()),
+			LegacyPing::V1_4 => format!("§1\0{}\0{}\0{}\0{}\0{}", LEGACY_PROTOCOL, status.version().name(), motd, players.online(), players.max()),
+		};
+
+		let chars: Vec<u16> = message.encode_utf16().collect();
+
+		let mut response = Vec::with_capacity(3 + chars.len() * 2);
+		response.push(LEGACY_KICK);
+		response.extend_from_slice(&(chars.len() as u16).to_be_bytes());
+
+		for c in chars {
+			response.extend_from_slice(&c.to_be_bytes());
+		}
+
+		response
+	}
+}
+
+/// Check if a new connection started with a legacy ping. This waits for the first data from the client,
+/// which is left in place to be read as the handshake if it isn't a legacy ping.
+pub async fn detect_legacy_ping(client: &mut CraftClient) -> Result<Option<LegacyPing>, NetworkError> {
+	if client.packet_state != PacketState::HANDSHAKING || client.peek_byte().await? != LEGACY_PING {
+		return Ok(None);
+	}
+
+	// the version byte is normally sent along with the first one, but it could be split off
+	if client.read_buffer.unread().len() < 2 {
+		match client.fill_until(Some(Instant::now() + VERSION_BYTE_WAIT)).await {
+			Ok(()) | Err(NetworkError::TimedOut) => {}
+			Err(e) => return Err(e),
+		}
+	}
+
+	Ok(Some(match client.read_buffer.unread().get(1) {
+		Some(1) => LegacyPing::V1_4,
+		_ => LegacyPing::Beta,
+	}))
+}
+
+/// Answer a legacy ping and close the connection, since legacy clients open a new one for each ping
+pub async fn respond_legacy_ping(client: &mut CraftClient, ping: LegacyPing, status: &StatusResponseSpec) -> Result<(), NetworkError> {
+	debug!("Answering {:?} legacy ping from {}", ping, client);
+
+	client.tcp_stream.write_all(&ping.response(status)).await?;
+	client.close().await;
+
+	Ok(())
+}
+
+/// Remove the `§` color codes, which beta clients would mistake for the separator between fields
+fn strip_color_codes(text: &str) -> String {
+	let mut out = String::with_capacity(text.len());
+	let mut chars = text.chars();
+
+	while let Some(c) = chars.next() {
+		if c == '§' {
+			chars.next();
+		} else {
+			out.push(c);
+		}
+	}
+
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::status::legacy::LegacyPing;
+	use crate::protocol::status::status_components::StatusResponseSpec;
+	use crate::protocol_types::protocol_verison::ProtocolVerison;
+
+	fn decode(response: &[u8]) -> String {
+		assert_eq!(response[0], 0xFF);
+		let length = u16::from_be_bytes([response[1], response[2]]) as usize;
+
+		let chars: Vec<u16> = response[3..].chunks(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+		assert_eq!(chars.len(), length);
+
+		String::from_utf16(&chars).unwrap()
+	}
+
+	#[test]
+	fn test_legacy_ping_response() {
+		let mut status = StatusResponseSpec::new(ProtocolVerison::V1_20, "&aHello World");
+		status.set_player_info(20, 3, vec![]);
+
+		assert_eq!(decode(&LegacyPing::Beta.response(&status)), "Hello World§3§20");
+		assert_eq!(decode(&LegacyPing::V1_4.response(&status)), format!("§1\0127\0{}\0§aHello World\03\020", ProtocolVerison::V1_20.get_fancy_name()));
+	}
+}
@@ -2,6 +2,7 @@
 //! There is no conceivable reason why you would want to override these, but if you do, you can implement
 //! them yourself and use them.
 
+use std::net::SocketAddr;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use log::{debug, trace};
@@ -9,17 +10,56 @@ use log::{debug, trace};
 use crate::network::client::client_handlers::{HandshakeHandler, PingHandler, StatusHandler};
 use crate::network::client::CraftClient;
 use crate::network::network_error::NetworkError;
-use crate::protocol::packets::{Packet, PingResponseBody, StatusResponseBody};
+use crate::network::server::StatusSupplier;
+use crate::protocol::packets::{Packet, PingResponseBody};
 use crate::protocol::packets::packet_definer::PacketState;
-use crate::protocol_types::datatypes::var_types::VarInt;
+use crate::protocol_types::protocol_verison::ProtocolVerison;
 
+pub mod legacy;
+pub mod motd;
 pub mod status_components;
 
+/// Describes the client that requested the server status, so that the response can depend on who is
+/// asking, such as telling old clients that they are outdated or showing a different MOTD per domain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusContext {
+	/// The protocol version number sent in the handshake
+	pub protocol_version: i32,
+	/// The hostname the client connected with, exactly as sent in the handshake
+	pub server_address: String,
+	pub server_port: u16,
+	pub remote_address: SocketAddr,
+}
+
+impl StatusContext {
+	/// Collect the context from a client that has finished the handshake
+	pub fn from_client(client: &CraftClient) -> Self {
+		Self {
+			protocol_version: client.client_version.map(|v| v.0).unwrap_or(-1),
+			server_address: client.server_address.clone().unwrap_or_default(),
+			server_port: client.server_port.unwrap_or(0),
+			remote_address: client.remote_addr(),
+		}
+	}
+
+	/// The client's version, or `None` if the library doesn't know the protocol number
+	pub fn version(&self) -> Option<ProtocolVerison> {
+		ProtocolVerison::from(self.protocol_version as i16)
+	}
+
+	/// The hostname without the markers that modded clients append after a null byte, such as Forge's
+	/// `\0FML3\0`, or the trailing dot of a fully qualified domain name
+	pub fn hostname(&self) -> &str {
+		let host = self.server_address.split('\0').next().unwrap_or_default();
+		host.strip_suffix('.').unwrap_or(host)
+	}
+}
+
 /// The default server-list status handler. Not sure why you wouldn't want to use it, but it's here.
 pub struct DefaultStatusHandler;
 
 impl StatusHandler for DefaultStatusHandler {
-	async fn handle_status<P: PingHandler>(connection: &mut CraftClient, status_response: StatusResponseBody, _ping_handler: P) -> Result<(), NetworkError> {
+	async fn handle_status<P: PingHandler + Send>(connection: &mut CraftClient, status: StatusSupplier, _ping_handler: P) -> Result<(), NetworkError> {
 		if connection.packet_state != PacketState::STATUS {
 			return Err(NetworkError::InvalidPacketState);
 		}
@@ -32,9 +72,7 @@ impl StatusHandler for DefaultStatusHandler {
 			Packet::StatusRequest(_) => {
 				trace!("Received status request from {}", connection);
 
-				let packed = Packet::StatusResponse(status_response);
-
-				connection.send_packet(packed).await?;
+				status.send(connection).await?;
 			}
 			Packet::PingRequest(b) => {
 				let packed = Packet::PingResponse(PingResponseBody {
@@ -59,6 +97,7 @@ impl StatusHandler for DefaultStatusHandler {
 }
 
 /// The default ping handler. Not sure why you wouldn't want to use it, but it's here.
+#[derive(Default)]
 pub struct DefaultPingHandler;
 
 impl PingHandler for DefaultPingHandler {
@@ -104,13 +143,7 @@ impl HandshakeHandler for DefaultHandshakeHandler {
 
 		match packet {
 			Packet::Handshaking(handshake) => {
-				if handshake.next_state == VarInt(1) {
-					client.change_state(PacketState::STATUS);
-				} else if handshake.next_state == VarInt(2) {
-					client.change_state(PacketState::LOGIN);
-				} else {
-					return Err(NetworkError::InvalidNextState(format!("Invalid next state detected, got \"{}\"", handshake.next_state.0)));
-				}
+				client.accept_handshake(&handshake)?;
 			}
 			_ => {
 				return Err(NetworkError::ExpectedDifferentPacket("Invalid packet received, expected handshake".to_string()));
@@ -0,0 +1,369 @@
+//! Helpers for building the description shown in the server list. The client shows at most two lines of
+//! it, so [Motd] takes care of splitting, truncating and centering the lines, and the legacy `§` color
+//! codes can be converted to and from [TextComponent]s.
+
+use crate::protocol_types::datatypes::chat::TextComponent;
+
+/// The width of the description in the server list, in pixels of the default font. This is approximate,
+/// since it depends on the client's GUI scale and resource pack.
+pub const MOTD_WIDTH: u32 = 270;
+/// The number of description lines shown in the server list
+pub const MOTD_LINES: usize = 2;
+
+/// The legacy color codes and the names used for them in text components
+const COLORS: [(char, &str); 16] = [
+	('0', "black"),
+	('1', "dark_blue"),
+	('2', "dark_green"),
+	('3', "dark_aqua"),
+	('4', "dark_red"),
+	('5', "dark_purple"),
+	('6', "gold"),
+	('7', "gray"),
+	('8', "dark_gray"),
+	('9', "blue"),
+	('a', "green"),
+	('b', "aqua"),
+	('c', "red"),
+	('d', "light_purple"),
+	('e', "yellow"),
+	('f', "white"),
+];
+
+/// The formatting that a legacy code can turn on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Style {
+	color: Option<&'static str>,
+	obfuscated: bool,
+	bold: bool,
+	strikethrough: bool,
+	underlined: bool,
+	italic: bool,
+}
+
+impl Style {
+	fn of(component: &TextComponent, parent: &Style) -> Self {
+		Self {
+			color: component.color.as_deref().map(color_name).unwrap_or(parent.color),
+			obfuscated: component.obfuscated.unwrap_or(parent.obfuscated),
+			bold: component.bold.unwrap_or(parent.bold),
+			strikethrough: component.strikethrough.unwrap_or(parent.strikethrough),
+			underlined: component.underlined.unwrap_or(parent.underlined),
+			italic: component.italic.unwrap_or(parent.italic),
+		}
+	}
+
+	fn apply(&self, text: String) -> TextComponent {
+		let mut component = TextComponent::new(text);
+		component.color = self.color.map(str::to_string);
+		component.obfuscated = self.obfuscated.then_some(true);
+		component.bold = self.bold.then_some(true);
+		component.strikethrough = self.strikethrough.then_some(true);
+		component.underlined = self.underlined.then_some(true);
+		component.italic = self.italic.then_some(true);
+		component
+	}
+
+	/// The codes that switch to this style. A color code clears the formatting, so anything without a
+	/// color starts with a reset instead.
+	fn write_codes(&self, out: &mut String) {
+		let color = self.color.and_then(|name| COLORS.iter().find(|(_, n)| *n == name)).map(|(code, _)| *code);
+
+		out.push('§');
+		out.push(color.unwrap_or('r'));
+
+		for (on, code) in [(self.obfuscated, 'k'), (self.bold, 'l'), (self.strikethrough, 'm'), (self.underlined, 'n'), (self.italic, 'o')] {
+			if on {
+				out.push('§');
+				out.push(code);
+			}
+		}
+	}
+}
+
+/// The static name of a color, or `None` for hex colors that have no legacy code
+fn color_name(color: &str) -> Option<&'static str> {
+	COLORS.iter().find(|(_, name)| *name == color).map(|(_, name)| *name)
+}
+
+/// Turn text with legacy formatting codes into a text component. Codes can start with either `§` or `&`.
+pub fn legacy_to_component(text: &str) -> TextComponent {
+	let mut parts = vec![];
+	let mut style = Style::default();
+	let mut current = String::new();
+	let mut chars = text.chars().peekable();
+
+	while let Some(c) = chars.next() {
+		let code = match chars.peek() {
+			Some(code) if c == '§' || c == '&' => code.to_ascii_lowercase(),
+			_ => {
+				current.push(c);
+				continue;
+			}
+		};
+
+		let mut next = style.clone();
+
+		match code {
+			'k' => next.obfuscated = true,
+			'l' => next.bold = true,
+			'm' => next.strikethrough = true,
+			'n' => next.underlined = true,
+			'o' => next.italic = true,
+			'r' => next = Style::default(),
+			code => match COLORS.iter().find(|(c, _)| *c == code) {
+				Some((_, name)) => next = Style { color: Some(name), ..Style::default() },
+				None => { // not a code, such as a lone '&'
+					current.push(c);
+					continue;
+				}
+			},
+		}
+
+		chars.next();
+
+		if !current.is_empty() {
+			parts.push(style.apply(std::mem::take(&mut current)));
+		}
+
+		style = next;
+	}
+
+	if !current.is_empty() {
+		parts.push(style.apply(current));
+	}
+
+	match parts.len() {
+		0 => TextComponent::new(""),
+		1 => parts.remove(0),
+		_ => {
+			let mut root = TextComponent::new("");
+			root.set_extra(parts);
+			root
+		}
+	}
+}
+
+/// Turn a text component into text with legacy `§` codes, for clients and tools that only understand
+/// those. Hex colors, hover text and click events have no legacy form and are left out.
+pub fn component_to_legacy(component: &TextComponent) -> String {
+	let mut out = String::new();
+	let mut last = Style::default();
+
+	write_legacy(component, &Style::default(), &mut last, &mut out);
+
+	out
+}
+
+fn write_legacy(component: &TextComponent, parent: &Style, last: &mut Style, out: &mut String) {
+	let style = Style::of(component, parent);
+
+	let text = match &component.translate {
+		Some(key) if component.text.is_empty() => key,
+		_ => &component.text,
+	};
+
+	if !text.is_empty() {
+		if style != *last {
+			style.write_codes(out);
+			*last = style.clone();
+		}
+
+		out.push_str(text);
+	}
+
+	for child in component.extra.iter().flatten() {
+		write_legacy(child, &style, last, out);
+	}
+}
+
+/// How many pixels a character takes up in the default font, including the gap after it
+fn char_width(c: char, bold: bool) -> u32 {
+	let width = match c {
+		'i' | '!' | ',' | '.' | ':' | ';' | '|' | '\'' => 1,
+		'l' | '`' => 2,
+		'I' | 't' | '[' | ']' | ' ' | '"' => 3,
+		'f' | 'k' | '<' | '>' | '(' | ')' | '{' | '}' | '*' => 4,
+		'@' => 6,
+		_ => 5,
+	};
+
+	width + 1 + bold as u32
+}
+
+/// Walk through the visible characters of a line with legacy codes, along with the byte index where each
+/// character ends and the width of the line up to that point
+fn visible_widths(line: &str) -> impl Iterator<Item = (usize, u32)> + '_ {
+	let mut bold = false;
+	let mut width = 0;
+	let mut chars = line.char_indices();
+
+	std::iter::from_fn(move || {
+		while let Some((i, c)) = chars.next() {
+			if c == '§' {
+				if let Some((_, code)) = chars.next() {
+					match code.to_ascii_lowercase() {
+						'l' => bold = true,
+						'k' | 'm' | 'n' | 'o' => {}
+						_ => bold = false, // colors and resets clear the formatting
+					}
+				}
+
+				continue;
+			}
+
+			width += char_width(c, bold);
+			return Some((i + c.len_utf8(), width));
+		}
+
+		None
+	})
+}
+
+/// The width of a line in pixels of the default font, not counting its `§` formatting codes
+pub fn text_width(line: &str) -> u32 {
+	visible_widths(line).last().map(|(_, width)| width).unwrap_or(0)
+}
+
+/// Cut a line down so that it fits within `width` pixels, keeping its formatting codes
+pub fn truncate(line: &str, width: u32) -> &str {
+	let mut end = 0;
+
+	for (char_end, line_width) in visible_widths(line) {
+		if line_width > width {
+			return &line[..end];
+		}
+
+		end = char_end;
+	}
+
+	line
+}
+
+/// Pad a line with spaces so that it sits in the middle of `width` pixels
+pub fn center(line: &str, width: u32) -> String {
+	let padding = width.saturating_sub(text_width(line)) / 2 / char_width(' ', false);
+
+	format!("{}{}", " ".repeat(padding as usize), line)
+}
+
+/// Builds a server list description out of up to two lines, which are cut to fit the width of the server
+/// list and can be centered
+///
+/// ```
+/// # use sandstone::protocol::status::motd::Motd;
+/// # use sandstone::protocol_types::datatypes::chat::TextComponent;
+/// let motd = Motd::new()
+///     .line("&6&lA Minecraft Server")
+///     .line_component(TextComponent::new("Now with more blocks").color("gray"))
+///     .centered(true);
+///
+/// let description: TextComponent = motd.into();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Motd {
+	/// Each line in legacy format
+	lines: Vec<String>,
+	centered: bool,
+}
+
+impl Motd {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Read a description in legacy format, which may already have two lines
+	pub fn from_legacy(text: &str) -> Self {
+		text.lines().fold(Self::new(), |motd, line| motd.line(line))
+	}
+
+	/// Add a line with legacy formatting codes, starting with either `§` or `&`. Lines past the second
+	/// are dropped, since the client doesn't show them.
+	pub fn line<T: Into<String>>(mut self, line: T) -> Self {
+		let line = component_to_legacy(&legacy_to_component(&line.into()));
+		self.lines.push(line);
+		self
+	}
+
+	/// Add a line made from a text component. See [component_to_legacy] for the formatting that is kept.
+	pub fn line_component(mut self, line: TextComponent) -> Self {
+		self.lines.push(component_to_legacy(&line));
+		self
+	}
+
+	/// Center each line in the server list
+	pub fn centered(mut self, centered: bool) -> Self {
+		self.centered = centered;
+		self
+	}
+
+	/// The description in legacy format, with the lines fitted to the server list
+	pub fn to_legacy(&self) -> String {
+		self.lines.iter().take(MOTD_LINES).map(|line| {
+			let line = truncate(line, MOTD_WIDTH);
+
+			if self.centered {
+				center(line, MOTD_WIDTH)
+			} else {
+				line.to_string()
+			}
+		}).collect::<Vec<_>>().join("\n")
+	}
+
+	pub fn to_component(&self) -> TextComponent {
+		legacy_to_component(&self.to_legacy())
+	}
+}
+
+impl From<Motd> for TextComponent {
+	fn from(motd: Motd) -> Self {
+		motd.to_component()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::status::motd::{center, component_to_legacy, legacy_to_component, Motd, MOTD_WIDTH, text_width, truncate};
+	use crate::protocol_types::datatypes::chat::TextComponent;
+
+	#[test]
+	fn test_legacy_conversion() {
+		let component = legacy_to_component("&6Gold &lbold§r plain &zkept");
+
+		assert_eq!(component.extra.as_ref().unwrap()[0], TextComponent::new("Gold ").color("gold"));
+		assert_eq!(component.extra.as_ref().unwrap()[1], TextComponent::new("bold").color("gold").bold(true));
+		assert_eq!(component.extra.as_ref().unwrap()[2], TextComponent::new(" plain &zkept"));
+		assert_eq!(component_to_legacy(&component), "§6Gold §6§lbold§r plain &zkept");
+
+		let nested = TextComponent::new("Hello ").color("red").append(TextComponent::new("World").italic(true)).append(TextComponent::new("#").color("#123456"));
+		assert_eq!(component_to_legacy(&nested), "§cHello §c§oWorld§r#");
+		assert_eq!(legacy_to_component("plain"), TextComponent::new("plain"));
+	}
+
+	#[test]
+	fn test_width_and_truncation() {
+		assert_eq!(text_width("Hi!"), 6 + 2 + 2);
+		assert_eq!(text_width("§lHi"), 7 + 3);
+		assert_eq!(text_width("§aHi"), text_width("Hi"));
+
+		let long = format!("§a{}", "W".repeat(100));
+		let cut = truncate(&long, MOTD_WIDTH);
+		assert_eq!(cut, &long[.."§a".len() + 45]);
+		assert!(text_width(cut) <= MOTD_WIDTH);
+		assert_eq!(truncate("short", MOTD_WIDTH), "short");
+
+		assert_eq!(center("§aHi", 30), "  §aHi");
+	}
+
+	#[test]
+	fn test_motd_lines() {
+		let motd = Motd::from_legacy("&aFirst\n&bSecond\nThird").centered(true);
+		let legacy = motd.to_legacy();
+		let lines: Vec<&str> = legacy.lines().collect();
+
+		assert_eq!(lines.len(), 2);
+		assert!(lines[0].trim_start().starts_with("§aFirst"));
+		assert!(lines[0].starts_with(' '));
+		assert_eq!(motd.to_component().to_plain_text(), legacy.replace("§a", "").replace("§b", ""));
+	}
+}
@@ -7,21 +7,27 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::protocol::packets::StatusResponseBody;
-use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol_types::datatypes::chat::{deserialize_component, TextComponent};
 use crate::protocol_types::protocol_verison::ProtocolVerison;
 
 /// A prepared response to a status request from a client. This provides useful functions for building
-/// the complicated nested structure of the status response.
+/// the complicated nested structure of the status response, see [StatusResponseSpec::builder].
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 #[allow(non_snake_case)]
 pub struct StatusResponseSpec {
 	version: VersionInfo,
+	#[serde(default)]
 	players: PlayerInfo,
-	description: DescriptionInfo,
+	#[serde(default, deserialize_with = "deserialize_component")]
+	description: TextComponent,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	favicon: Option<String>,
+	#[serde(default)]
 	enforcesSecureChat: bool,
+	#[serde(default)]
 	previewsChat: bool,
+	#[serde(default, skip_serializing_if = "is_false")]
+	preventsChatReports: bool,
 }
 
 impl StatusResponseSpec {
@@ -29,21 +35,34 @@ impl StatusResponseSpec {
 	/// will have its color codes translated from the symbol '&' to the symbol '§'.
 	pub fn new<T: Into<String>>(protocol_version: ProtocolVerison, description: T) -> Self {
 		Self {
-			version: VersionInfo {
-				name: protocol_version.get_fancy_name(),
-				protocol: protocol_version.get_version_number(),
-			},
-			players: PlayerInfo {
-				max: 0,
-				online: 0,
-				sample: Vec::new(),
-			},
-			description: DescriptionInfo {
-				text: description.into().replace("&", "§")
-			},
+			version: VersionInfo::from(protocol_version),
+			players: PlayerInfo::default(),
+			description: TextComponent::new(description.into().replace("&", "§")),
 			favicon: None,
 			enforcesSecureChat: false,
 			previewsChat: false,
+			preventsChatReports: false,
+		}
+	}
+
+	/// Start building a status response for the given protocol version
+	///
+	/// ```
+	/// # use sandstone::protocol::status::status_components::{PlayerSample, StatusResponseSpec};
+	/// # use sandstone::protocol_types::datatypes::chat::TextComponent;
+	/// # use sandstone::protocol_types::protocol_verison::ProtocolVerison;
+	/// let status = StatusResponseSpec::builder(ProtocolVerison::V1_20)
+	///     .description(TextComponent::new("A Minecraft Server").color("gold"))
+	///     .players(100, 1)
+	///     .sample(PlayerSample::new_random("dec4234"))
+	///     .enforces_secure_chat(true)
+	///     .build();
+	///
+	/// assert_eq!(status.players().max(), 100);
+	/// ```
+	pub fn builder(protocol_version: ProtocolVerison) -> StatusResponseBuilder {
+		StatusResponseBuilder {
+			spec: Self::new(protocol_version, ""),
 		}
 	}
 
@@ -53,18 +72,31 @@ impl StatusResponseSpec {
 		let mut image_data: Vec<u8> = Vec::new();
 		image.write_to(&mut Cursor::new(&mut image_data), ImageFormat::Png)
 			.unwrap();
-		let res_base64 = general_purpose::STANDARD.encode(image_data);
+
+		self.set_favicon_png(&image_data);
+	}
+
+	/// Set the server logo from the bytes of a 64x64 PNG file, such as a `server-icon.png`
+	pub fn set_favicon_png(&mut self, png: &[u8]) {
+		let res_base64 = general_purpose::STANDARD.encode(png);
 		let s = format!("data:image/png;base64,{}", res_base64);
 
 		self.favicon = Some(s);
 	}
 
-	/// Unknown purpose. Might be related to post 1.18 chat security.
+	/// Tell the client whether the server requires signed chat messages. Clients show a warning on
+	/// servers that don't, and since 1.20.5 only clients with a chat session can join servers that do.
 	pub fn set_secure_chat(&mut self, secure: bool) {
 		self.enforcesSecureChat = secure;
 	}
 
-	/// Unknown purpose. Might be related to post 1.18 chat security.
+	/// Tell the client that chat messages can't be reported. This isn't part of vanilla, but is shown by
+	/// clients with the No Chat Reports mod and similar mods. It is only sent when set.
+	pub fn set_prevents_chat_reports(&mut self, prevents: bool) {
+		self.preventsChatReports = prevents;
+	}
+
+	/// Only used by 1.19 to 1.19.2 clients, which could preview chat messages styled by the server
 	pub fn set_preview_chat(&mut self, preview: bool) {
 		self.previewsChat = preview;
 	}
@@ -72,9 +104,13 @@ impl StatusResponseSpec {
 	/// Set the description/MOTD of the server, which is displayed in the server list.
 	/// The description will have its color codes translated from the symbol '&' to the symbol '§'.
 	pub fn set_description(&mut self, description: String) {
-		self.description = DescriptionInfo {
-			text: description
-		};
+		self.description = TextComponent::new(description.replace("&", "§"));
+	}
+
+	/// Set the description/MOTD of the server to a text component, which allows for hover text and
+	/// more colors than the legacy color codes
+	pub fn set_description_component<T: Into<TextComponent>>(&mut self, description: T) {
+		self.description = description.into();
 	}
 
 	/// Set the player list preview response, seen when the user hovers over the player count.
@@ -86,6 +122,11 @@ impl StatusResponseSpec {
 		};
 	}
 
+	/// Set the maximum number of players shown in the server list, keeping the online count and sample
+	pub fn set_max_players(&mut self, max: i32) {
+		self.players.max = max;
+	}
+
 	/// `version` can really be anything you want, but `protocol_version` must be a valid protocol version number
 	pub fn set_protocol_version(&mut self, version: String, protocol_version: i16) {
 		self.version = VersionInfo {
@@ -93,40 +134,129 @@ impl StatusResponseSpec {
 			protocol: protocol_version,
 		};
 	}
-}
 
-impl McSerialize for StatusResponseSpec {
-	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
-		let serialized = serde_json::to_string(self).unwrap();
+	pub fn version(&self) -> &VersionInfo {
+		&self.version
+	}
 
-		serialized.mc_serialize(serializer)?;
+	pub fn players(&self) -> &PlayerInfo {
+		&self.players
+	}
 
-		Ok(())
+	pub fn players_mut(&mut self) -> &mut PlayerInfo {
+		&mut self.players
 	}
-}
 
-impl McDeserialize for StatusResponseSpec {
-	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> where Self: Sized {
-		let raw = serde_json::from_str(&String::mc_deserialize(deserializer)?).unwrap(); // TODO: test
+	pub fn description(&self) -> &TextComponent {
+		&self.description
+	}
+
+	/// The server logo as a `data:image/png;base64,` URI, if there is one
+	pub fn favicon(&self) -> Option<&str> {
+		self.favicon.as_deref()
+	}
+
+	pub fn enforces_secure_chat(&self) -> bool {
+		self.enforcesSecureChat
+	}
 
-		Ok(raw)
+	pub fn prevents_chat_reports(&self) -> bool {
+		self.preventsChatReports
 	}
 }
 
+fn is_false(b: &bool) -> bool {
+	!*b
+}
+
 impl From<StatusResponseBody> for StatusResponseSpec {
 	fn from(p: StatusResponseBody) -> Self {
-		p.response
+		p.response.into_inner()
 	}
 }
 
 impl From<StatusResponseSpec> for StatusResponseBody {
 	fn from(p: StatusResponseSpec) -> Self {
 		StatusResponseBody {
-			response: p
+			response: p.into()
 		}
 	}
 }
 
+/// Builds a [StatusResponseSpec], see [StatusResponseSpec::builder]
+#[derive(Debug, Clone)]
+pub struct StatusResponseBuilder {
+	spec: StatusResponseSpec,
+}
+
+impl StatusResponseBuilder {
+	/// The version name shown to clients with a different protocol version, such as "Paper 1.21"
+	pub fn version_name<T: Into<String>>(mut self, name: T) -> Self {
+		self.spec.version.name = name.into();
+		self
+	}
+
+	/// The protocol version of the server. Clients with a different version are shown as outdated.
+	pub fn protocol(mut self, protocol: i16) -> Self {
+		self.spec.version.protocol = protocol;
+		self
+	}
+
+	/// The description shown in the server list. Plain strings have their color codes translated from
+	/// '&' to '§', like [StatusResponseSpec::set_description].
+	pub fn motd<T: Into<String>>(mut self, motd: T) -> Self {
+		self.spec.set_description(motd.into());
+		self
+	}
+
+	/// The description shown in the server list, as a text component
+	pub fn description<T: Into<TextComponent>>(mut self, description: T) -> Self {
+		self.spec.description = description.into();
+		self
+	}
+
+	/// The maximum and current number of players
+	pub fn players(mut self, max: i32, online: i32) -> Self {
+		self.spec.players.max = max;
+		self.spec.players.online = online;
+		self
+	}
+
+	/// Add a player to the list shown when hovering over the player count
+	pub fn sample(mut self, player: PlayerSample) -> Self {
+		self.spec.players.sample.push(player);
+		self
+	}
+
+	/// The server logo as a PNG image, see [StatusResponseSpec::set_favicon_image]
+	pub fn favicon_image(mut self, image: DynamicImage) -> Self {
+		self.spec.set_favicon_image(image);
+		self
+	}
+
+	/// The server logo from the bytes of a PNG file, see [StatusResponseSpec::set_favicon_png]
+	pub fn favicon_png(mut self, png: &[u8]) -> Self {
+		self.spec.set_favicon_png(png);
+		self
+	}
+
+	/// See [StatusResponseSpec::set_secure_chat]
+	pub fn enforces_secure_chat(mut self, secure: bool) -> Self {
+		self.spec.enforcesSecureChat = secure;
+		self
+	}
+
+	/// See [StatusResponseSpec::set_prevents_chat_reports]
+	pub fn prevents_chat_reports(mut self, prevents: bool) -> Self {
+		self.spec.preventsChatReports = prevents;
+		self
+	}
+
+	pub fn build(self) -> StatusResponseSpec {
+		self.spec
+	}
+}
+
 /// Represents the version information for the server. The `name` of the version can be anything you want.
 /// The `protocol` must be a valid protocol version number, and must match the protocol version of the
 /// connecting client.
@@ -136,10 +266,31 @@ pub struct VersionInfo {
 	protocol: i16,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+impl VersionInfo {
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn protocol(&self) -> i16 {
+		self.protocol
+	}
+}
+
+impl From<ProtocolVerison> for VersionInfo {
+	fn from(version: ProtocolVerison) -> Self {
+		Self {
+			name: version.get_fancy_name(),
+			protocol: version.get_version_number(),
+		}
+	}
+}
+
+/// The player count and the sample of players shown when hovering over it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub struct PlayerInfo {
 	max: i32,
 	online: i32,
+	#[serde(default)]
 	sample: Vec<PlayerSample>,
 }
 
@@ -151,19 +302,29 @@ impl PlayerInfo {
 	pub fn set_player_sample(&mut self, players: Vec<PlayerSample>) {
 		self.sample = players;
 	}
-}
 
-/// Represents the description/MOTD of the server, which is displayed in the server list.
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
-pub struct DescriptionInfo { // TODO: update to chat thing?
-	text: String,
+	pub fn set_online(&mut self, online: i32) {
+		self.online = online;
+	}
+
+	pub fn max(&self) -> i32 {
+		self.max
+	}
+
+	pub fn online(&self) -> i32 {
+		self.online
+	}
+
+	pub fn sample(&self) -> &[PlayerSample] {
+		&self.sample
+	}
 }
 
 /// Represents a single entry in the player list sample response, seen when the user hovers over the player count.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PlayerSample {
 	name: String,
-	id: String,
+	id: Uuid,
 }
 
 impl PlayerSample {
@@ -172,17 +333,69 @@ impl PlayerSample {
 	pub fn new<S: Into<String>>(name: S, id: Uuid) -> Self {
 		Self {
 			name: name.into().replace("&", "§"),
-			id: id.to_string(),
+			id,
 		}
 	}
 
 	/// Create a new player sample with the given name and a random UUID.
 	/// The name will have its color codes translated from the symbol '&' to the symbol '§'.
 	pub fn new_random<S: Into<String>>(name: S) -> Self {
-		Self {
-			name: name.into().replace("&", "§"),
-			id: Uuid::new_v4().to_string(), // TODO: no-std support?
-		}
+		Self::new(name, Uuid::new_v4()) // TODO: no-std support?
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn id(&self) -> Uuid {
+		self.id
 	}
 }
 
+#[cfg(test)]
+mod tests {
+	use uuid::Uuid;
+
+	use crate::protocol::status::status_components::{PlayerSample, StatusResponseSpec};
+	use crate::protocol_types::datatypes::chat::TextComponent;
+	use crate::protocol_types::protocol_verison::ProtocolVerison;
+
+	#[test]
+	fn test_status_response_json() {
+		let id = Uuid::from_u128(0x0123456789abcdef);
+		let status = StatusResponseSpec::builder(ProtocolVerison::V1_20)
+			.version_name("Sandstone")
+			.description(TextComponent::new("Hello").color("gold"))
+			.players(20, 1)
+			.sample(PlayerSample::new("dec4234", id))
+			.enforces_secure_chat(true)
+			.build();
+
+		let json = serde_json::to_value(&status).unwrap();
+		assert!(json.get("preventsChatReports").is_none());
+		assert_eq!(json["version"]["name"], "Sandstone");
+		assert_eq!(json["description"]["color"], "gold");
+		assert_eq!(json["players"]["sample"][0]["id"], id.to_string());
+		assert_eq!(json["enforcesSecureChat"], true);
+		assert!(json.get("favicon").is_none());
+
+		assert_eq!(serde_json::from_value::<StatusResponseSpec>(json).unwrap(), status);
+	}
+
+	#[test]
+	fn test_status_response_from_other_servers() {
+		// older servers send the description as a plain string and leave out the optional fields
+		let json = r#"{"version":{"name":"1.8.9","protocol":47},"players":{"max":10,"online":0},"description":"§aOld server"}"#;
+		let status: StatusResponseSpec = serde_json::from_str(json).unwrap();
+
+		assert_eq!(status.version().protocol(), 47);
+		assert_eq!(status.description().to_plain_text(), "§aOld server");
+		assert!(status.players().sample().is_empty());
+		assert!(!status.enforces_secure_chat());
+		assert!(!status.prevents_chat_reports());
+
+		let json = r#"{"version":{"name":"1.20.6","protocol":766},"description":"","enforcesSecureChat":true,"preventsChatReports":true}"#;
+		let status: StatusResponseSpec = serde_json::from_str(json).unwrap();
+		assert!(status.enforces_secure_chat() && status.prevents_chat_reports());
+	}
+}
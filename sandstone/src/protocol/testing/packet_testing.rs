@@ -1,8 +1,27 @@
-use crate::protocol::packets::{DisconnectBody, LoginPluginResponseBody, Packet};
-use crate::protocol::packets::packet_component::LoginPluginSpec;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::protocol::chat::signed::{Acknowledged, ArgumentSignature, FilterMask, MessageSignature, PreviousMessage};
+use crate::protocol::commands::suggestions::Suggestions;
+use crate::protocol::commands::tree::{argument, literal, ArgumentParser, Bounds, CommandTree, StringKind, SuggestionType};
+use crate::protocol::packets::{BossBarBody, CommandSuggestionsRequestBody, DisplayObjectiveBody, ChatMessageBody, ChunkDataAndUpdateLightBody, ClientInformationBody, ClientboundKnownPacksBody, CustomReportDetailsBody, DisconnectBody, LoginPlayBody, LoginPluginResponseBody, Packet, PlayAddResourcePackBody, PlayCustomReportDetailsBody, PlayerChatBody, PlayerInfoRemoveBody, PlayerInfoUpdateBody, PlayServerLinksBody, ResetScoreBody, ServerboundKnownPacksBody, SetEntityMetadataBody, ServerLinksBody, SetPlayerPositionAndRotationBody, SignedChatCommandBody, SpawnEntityBody, SynchronizePlayerPositionBody, UpdateObjectivesBody, UpdateScoreBody, UpdateTagsBody, UpdateTeamsBody};
+use crate::protocol::packets::packet_component::{ChunkBlockEntity, ChunkDataSpec, KnownPack, LoginPlaySpec, PLAYER_INFO_UPDATE_LATENCY, PlayerInfoEntry, PlayerInfoUpdateSpec, RegistryTagsSpec, ReportDetail, ServerLink, ServerLinkType, TagSpec};
 use crate::protocol::packets::packet_definer::{PacketDirection, PacketState};
+use crate::protocol::play::boss_bar::{BossBarAction, BossBarColor, BossBarDivision, CREATE_FOG};
+use crate::protocol::play::player_info::{PlayerInfoBuilder, PlayerListEntry};
+use crate::protocol::play::scoreboard::{CollisionRule, DisplaySlot, FRIENDLY_FIRE, NumberFormat, Objective, ObjectiveAction, RenderType, Team, TeamAction, TeamColor};
 use crate::protocol::serialization::{McDeserializer, McSerialize, McSerializer, StateBasedDeserializer};
-use crate::protocol_types::datatypes::chat::TextComponent;
+use crate::protocol::serialization::serializer_error::{PacketContext, SerializingErr};
+use crate::protocol_types::datatypes::angle::Angle;
+use crate::protocol_types::datatypes::bitset::BitSet;
+use crate::protocol_types::datatypes::chat::{NbtTextComponent, TextComponent};
+use crate::protocol_types::datatypes::entity_metadata::{EntityMetadata, MetadataValue, Pose};
+use crate::protocol_types::datatypes::game_profile::{GameProfile, ProfileProperty};
+use crate::protocol_types::datatypes::identifier::Identifier;
+use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtLongArray, NbtTag};
+use crate::protocol_types::datatypes::position::Position;
+use crate::protocol_types::datatypes::var_types::VarInt;
 
 #[test]
 pub fn test_basic_deserialization() {
@@ -27,16 +46,35 @@ pub fn test_basic_deserialization() {
 	}
 }
 
+#[test]
+pub fn test_deserialization_error_context() {
+	let vec: Vec<u8> = vec![5, 0, 254, 5, 9, 108]; // Handshake with the address cut off
+
+	let mut deserializer = McDeserializer::new(&vec);
+	let err = Packet::deserialize_state(&mut deserializer, PacketState::HANDSHAKING, PacketDirection::SERVER).unwrap_err();
+	assert_eq!(err.root(), &SerializingErr::OutOfBounds);
+
+	let context = err.context().unwrap();
+	assert_eq!(context.expected.as_deref(), Some("HandshakingBody.server_address"));
+	assert_eq!(context.offset, 4);
+	assert_eq!(context.excerpt, "00 fe 05 09 [6c]");
+	assert_eq!(context.packet, Some(PacketContext { state: PacketState::HANDSHAKING, direction: PacketDirection::SERVER, packet_id: 0 }));
+	assert!(err.to_string().contains("HANDSHAKING packet 0x00"));
+
+	let vec: Vec<u8> = vec![1, 0x7F]; // unknown id
+	
+	let mut deserializer = McDeserializer::new(&vec);
+	let err = Packet::deserialize_state(&mut deserializer, PacketState::STATUS, PacketDirection::SERVER).unwrap_err();
+	assert_eq!(err.context().unwrap().packet.unwrap().packet_id, 0x7F);
+}
+
 #[test]
 pub fn test_optional_vec_serialization() {
 	let mut serializer = McSerializer::new();
 	
 	let packet = Packet::LoginPluginResponse(LoginPluginResponseBody {
-		response: LoginPluginSpec {
-			message_id: 0.into(),
-			success: true,
-			data: Some(vec![1, 2, 3])
-		}
+		message_id: 0.into(),
+		data: Some(vec![1, 2, 3].into())
 	});
 	
 	packet.mc_serialize(&mut serializer).unwrap();
@@ -50,11 +88,8 @@ pub fn test_optional_vec_serialization() {
 	serializer.clear();
 	
 	let packet = Packet::LoginPluginResponse(LoginPluginResponseBody {
-		response: LoginPluginSpec {
-			message_id: 0.into(),
-			success: false,
-			data: None
-		}
+		message_id: 0.into(),
+		data: None
 	});
 	
 	packet.mc_serialize(&mut serializer).unwrap();
@@ -83,4 +118,232 @@ pub fn test_cross_serialization() {
 	assert_eq!(packet, out);
 	
 	serializer.clear();
-}
\ No newline at end of file
+}
+
+fn round_trip(packet: &Packet) -> Packet {
+	let mut serializer = McSerializer::new();
+	packet.mc_serialize(&mut serializer).unwrap();
+
+	let mut deserializer = McDeserializer::new(&serializer.output);
+	Packet::deserialize_state(&mut deserializer, packet.state(), packet.direction()).unwrap()
+}
+
+#[test]
+pub fn test_configuration_serialization() {
+	let packet = Packet::ClientInformation(ClientInformationBody::new("en_us".to_string(), 12, VarInt(0), true, 0x7F, VarInt(1), false, true));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packs = vec![KnownPack {
+		namespace: "minecraft".to_string(),
+		id: "core".to_string(),
+		version: "1.20.6".to_string(),
+	}];
+
+	let packet = Packet::ClientboundKnownPacks(ClientboundKnownPacksBody::new(packs.clone()));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Packet::ServerboundKnownPacks(ServerboundKnownPacksBody::new(packs));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Packet::UpdateTags(UpdateTagsBody::new(vec![
+		RegistryTagsSpec {
+			registry: Identifier::try_from("minecraft:block").unwrap(),
+			tags: vec![
+				TagSpec { name: Identifier::try_from("minecraft:logs").unwrap(), entries: vec![VarInt(40), VarInt(41), VarInt(300)] },
+				TagSpec { name: Identifier::try_from("minecraft:empty").unwrap(), entries: vec![] },
+			],
+		},
+		RegistryTagsSpec {
+			registry: Identifier::try_from("minecraft:item").unwrap(),
+			tags: vec![],
+		},
+	]));
+	assert_eq!(packet, round_trip(&packet));
+
+	let links = vec![
+		ServerLink::built_in(ServerLinkType::BugReport, "https://example.com/issues"),
+		ServerLink::custom("Map", "https://example.com/map"),
+	];
+
+	let packet = Packet::ServerLinks(ServerLinksBody::new(links.clone()));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Packet::PlayServerLinks(PlayServerLinksBody::new(links));
+	assert_eq!(packet, round_trip(&packet));
+
+	let details = vec![ReportDetail::new("World", "survival"), ReportDetail::new("é".repeat(200), "")];
+	assert_eq!(details[1].title.chars().count(), ReportDetail::MAX_TITLE_LENGTH);
+
+	let packet = Packet::CustomReportDetails(CustomReportDetailsBody::new(details.clone()));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Packet::PlayCustomReportDetails(PlayCustomReportDetailsBody::new(details));
+	assert_eq!(packet, round_trip(&packet));
+}
+
+#[test]
+pub fn test_play_serialization() {
+	let packet = Packet::LoginPlay(LoginPlayBody::new(LoginPlaySpec {
+		entity_id: 42,
+		is_hardcore: false,
+		dimension_names: vec!["minecraft:overworld".to_string(), "minecraft:the_nether".to_string()],
+		max_players: VarInt(20),
+		view_distance: VarInt(10),
+		simulation_distance: VarInt(8),
+		reduced_debug_info: false,
+		enable_respawn_screen: true,
+		do_limited_crafting: false,
+		dimension_type: VarInt(0),
+		dimension_name: "minecraft:overworld".to_string(),
+		hashed_seed: -1234567890,
+		game_mode: 1,
+		previous_game_mode: -1,
+		is_debug: false,
+		is_flat: true,
+		death_location: Some(("minecraft:the_nether".to_string(), Position::new(-100, 64, 2500))),
+		portal_cooldown: VarInt(0),
+		enforces_secure_chat: false,
+	}));
+	assert_eq!(packet, round_trip(&packet));
+
+	let mut heightmaps = NbtCompound::new::<String>(None);
+	heightmaps.add("MOTION_BLOCKING", NbtLongArray::new(vec![0; 37]));
+
+	let mut sign = NbtCompound::new::<String>(None);
+	sign.add("is_waxed", NbtTag::Byte(1));
+
+	let packet = Packet::ChunkDataAndUpdateLight(ChunkDataAndUpdateLightBody::new(ChunkDataSpec {
+		chunk_x: -3,
+		chunk_z: 7,
+		heightmaps,
+		data: vec![1, 2, 3, 4, 5],
+		block_entities: vec![ChunkBlockEntity {
+			packed_xz: 0x3F,
+			y: -20,
+			block_entity_type: VarInt(7),
+			data: sign,
+		}],
+		sky_light_mask: BitSet::from_longs(vec![0b110]),
+		block_light_mask: BitSet::new(),
+		empty_sky_light_mask: [0, 100].into_iter().collect(),
+		empty_block_light_mask: BitSet::from_longs(vec![-1]),
+		sky_light_arrays: vec![vec![0xFF; 2048], vec![0; 2048]],
+		block_light_arrays: vec![],
+	}));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Packet::SynchronizePlayerPosition(SynchronizePlayerPositionBody::new(0.5, 64.0, -0.5, 90.0, 0.0, 0, VarInt(1)));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Packet::SetPlayerPositionAndRotation(SetPlayerPositionAndRotationBody::new(0.5, 64.0, -0.5, 90.0, 0.0, true));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Packet::SpawnEntity(SpawnEntityBody::new(VarInt(7), Uuid::from_u128(7), VarInt(128), 1.5, 70.0, -3.25, Angle::from_degrees(45.0), Angle(0), Angle(64), VarInt(0), 0, -100, 0));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Packet::SetEntityMetadata(SetEntityMetadataBody::new(VarInt(7), EntityMetadata::new()
+		.with(0, MetadataValue::Byte(0x20))
+		.with(2, MetadataValue::OptionalTextComponent(Some(TextComponent::new("Dinnerbone"))))
+		.with(6, MetadataValue::Pose(Pose::Sleeping))));
+	assert_eq!(packet, round_trip(&packet));
+
+	let mut profile = GameProfile::offline("Alice");
+	profile.properties.push(ProfileProperty { name: "textures".to_string(), value: "e30=".to_string(), signature: None });
+
+	let packets = PlayerInfoBuilder::new()
+		.add_player(PlayerListEntry::new(profile).display_name(TextComponent::new("Alice")))
+		.latency(Uuid::from_u128(2), Duration::from_millis(40))
+		.packets();
+	for packet in packets {
+		assert_eq!(packet, round_trip(&packet));
+	}
+
+	let packet = Packet::PlayerInfoRemove(PlayerInfoRemoveBody::new(vec![Uuid::from_u128(1), Uuid::from_u128(2)]));
+	assert_eq!(packet, round_trip(&packet));
+
+	// an entry without the fields for the actions of the packet
+	let spec = PlayerInfoUpdateSpec { actions: PLAYER_INFO_UPDATE_LATENCY, entries: vec![PlayerInfoEntry::new(Uuid::from_u128(1))] };
+	assert!(Packet::PlayerInfoUpdate(PlayerInfoUpdateBody::new(spec)).mc_serialize(&mut McSerializer::new()).is_err());
+
+	let mut acknowledged = Acknowledged::new();
+	acknowledged.set(19, true);
+
+	let packet = Packet::ChatMessage(ChatMessageBody::new("hello".to_string(), 1_700_000_000_000, -5, Some(MessageSignature::new([7; 256])), VarInt(1), acknowledged.clone()));
+	assert_eq!(packet, round_trip(&packet));
+
+	let arguments = vec![ArgumentSignature { name: "message".to_string(), signature: MessageSignature::new([3; 256]) }];
+	let packet = Packet::SignedChatCommand(SignedChatCommandBody::new("msg Steve hi".to_string(), 0, 1, arguments, VarInt(0), acknowledged));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Packet::PlayerChat(PlayerChatBody::new(Uuid::from_u128(1), VarInt(3), None, "hi".to_string(), 0, 1,
+		vec![PreviousMessage::Id(4), PreviousMessage::Signature(MessageSignature::new([1; 256]))],
+		Some(Box::new(NbtTextComponent::new(TextComponent::new("hi!")))), FilterMask::PartiallyFiltered([0, 1].into_iter().collect()), VarInt(1),
+		Box::new(NbtTextComponent::new(TextComponent::new("Alice"))), None));
+	assert_eq!(packet, round_trip(&packet));
+
+	let actions = vec![
+		BossBarAction::Add { title: NbtTextComponent::new(TextComponent::new("Ender Dragon")), progress: 0.75, color: BossBarColor::Pink, division: BossBarDivision::Notches12, flags: CREATE_FOG },
+		BossBarAction::Remove,
+		BossBarAction::UpdateProgress(0.5),
+		BossBarAction::UpdateTitle(NbtTextComponent::new(TextComponent::new("Wither"))),
+		BossBarAction::UpdateStyle(BossBarColor::White, BossBarDivision::Notches20),
+		BossBarAction::UpdateFlags(0),
+	];
+	for action in actions {
+		let packet = Packet::BossBar(BossBarBody::new(Uuid::from_u128(9), action));
+		assert_eq!(packet, round_trip(&packet));
+	}
+
+	let mut style = NbtCompound::new::<String>(None);
+	style.add("color", NbtTag::String("gold".to_string()));
+
+	let objective = Objective::new(TextComponent::new("Health")).render_type(RenderType::Hearts).number_format(NumberFormat::Styled(style));
+	for action in [ObjectiveAction::Create(objective.clone()), ObjectiveAction::Remove, ObjectiveAction::Update(objective)] {
+		let packet = Packet::UpdateObjectives(UpdateObjectivesBody::new("health".to_string(), action));
+		assert_eq!(packet, round_trip(&packet));
+	}
+
+	let packet = Packet::DisplayObjective(DisplayObjectiveBody::new(VarInt(DisplaySlot::TeamSidebar(TeamColor::Gold).id()), "health".to_string()));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Packet::UpdateScore(UpdateScoreBody::new("Alice".to_string(), "health".to_string(), VarInt(-3),
+		Some(NbtTextComponent::new(TextComponent::new("Alice the Great"))), Some(NumberFormat::Fixed(NbtTextComponent::new(TextComponent::new("low"))))));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Packet::ResetScore(ResetScoreBody::new("Alice".to_string(), Some("health".to_string())));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Packet::PlayAddResourcePack(PlayAddResourcePackBody::new(Uuid::from_u128(5), "https://example.com/pack.zip".to_string(), String::new(), true,
+		Some(NbtTextComponent::new(TextComponent::new("Please accept")))));
+	assert_eq!(packet, round_trip(&packet));
+
+	let team = Team::new(TextComponent::new("Red")).flags(FRIENDLY_FIRE).collision_rule(CollisionRule::PushOwnTeam).color(TeamColor::Red).prefix(TextComponent::new("[R] "));
+	let entities = vec!["Alice".to_string(), "Bob".to_string()];
+	for action in [TeamAction::Create(Box::new(team.clone()), entities.clone()), TeamAction::Remove, TeamAction::Update(Box::new(team)), TeamAction::AddEntities(entities.clone()), TeamAction::RemoveEntities(entities)] {
+		let packet = Packet::UpdateTeams(UpdateTeamsBody::new("red".to_string(), action));
+		assert_eq!(packet, round_trip(&packet));
+	}
+
+	let packet = CommandTree::new()
+		.command(literal("give")
+			.then(argument("target", ArgumentParser::Entity { single: false, players_only: true })
+				.then(argument("item", ArgumentParser::ItemStack)
+					.executes()
+					.then(argument("count", ArgumentParser::Integer(Bounds::new(Some(1), None))).executes()))))
+		.command(literal("say").then(argument("message", ArgumentParser::String(StringKind::GreedyPhrase)).suggests(SuggestionType::AskServer).executes()))
+		.command(literal("effect").then(argument("effect", ArgumentParser::Resource(Identifier::minecraft("mob_effect").unwrap())).executes()))
+		.command(literal("speed").then(argument("value", ArgumentParser::Double(Bounds::new(Some(0.0), Some(10.0)))).executes()))
+		.command(literal("s").redirect("say"))
+		.packet()
+		.unwrap();
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Packet::CommandSuggestionsRequest(CommandSuggestionsRequestBody::new(VarInt(7), "/give @p dia".to_string()));
+	assert_eq!(packet, round_trip(&packet));
+
+	let packet = Suggestions::last_word("/give @p dia")
+		.suggest("diamond")
+		.suggest_with_tooltip("diamond_sword", TextComponent::new("A sharp sword"))
+		.response(VarInt(7));
+	assert_eq!(packet, round_trip(&packet));
+}
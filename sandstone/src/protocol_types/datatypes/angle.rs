@@ -0,0 +1,194 @@
+//! Small fixed-point number types used by entity packets. [Angle] holds a rotation in a single byte, and
+//! [PositionDelta] holds how far an entity moved in a short.
+//! See more details here: https://wiki.vg/Protocol#Angle and https://wiki.vg/Protocol#Update_Entity_Position
+
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+
+/// A rotation in steps of 1/256th of a full turn. Any angle in degrees can be converted into this, since
+/// it wraps around, but some precision is lost along the way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Angle(pub u8);
+
+impl Angle {
+	/// Convert from degrees, rounding down the same way vanilla does
+	pub fn from_degrees(degrees: f32) -> Self {
+		Angle(((degrees * 256.0 / 360.0).floor() as i64).rem_euclid(256) as u8)
+	}
+
+	pub fn from_radians(radians: f32) -> Self {
+		Angle::from_degrees(radians.to_degrees())
+	}
+
+	/// Convert to degrees, from 0 up to (but not including) 360
+	pub fn to_degrees(&self) -> f32 {
+		self.0 as f32 * 360.0 / 256.0
+	}
+
+	pub fn to_radians(&self) -> f32 {
+		self.to_degrees().to_radians()
+	}
+}
+
+impl From<f32> for Angle {
+	/// Convert from degrees
+	fn from(degrees: f32) -> Self {
+		Angle::from_degrees(degrees)
+	}
+}
+
+impl From<f64> for Angle {
+	/// Convert from degrees
+	fn from(degrees: f64) -> Self {
+		Angle::from_degrees(degrees as f32)
+	}
+}
+
+impl From<Angle> for f32 {
+	/// Convert to degrees
+	fn from(angle: Angle) -> Self {
+		angle.to_degrees()
+	}
+}
+
+impl Display for Angle {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}°", self.to_degrees())
+	}
+}
+
+impl McSerialize for Angle {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.0.mc_serialize(serializer)
+	}
+}
+
+impl McDeserialize for Angle {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		Ok(Angle(u8::mc_deserialize(deserializer)?))
+	}
+}
+
+/// How far an entity moved along one axis, in 1/4096ths of a block. This only covers movements of up to
+/// 8 blocks in either direction; anything further has to be sent as a teleport instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PositionDelta(pub i16);
+
+impl PositionDelta {
+	/// The number of steps in a single block
+	pub const UNITS_PER_BLOCK: f64 = 4096.0;
+
+	/// Calculate the delta for an entity moving from `previous` to `current`. Both positions are rounded
+	/// to the nearest step first, like vanilla does, so adding up deltas never drifts from the real position.
+	pub fn between(previous: f64, current: f64) -> Result<Self, SerializingErr> {
+		let delta = (current * Self::UNITS_PER_BLOCK).round() - (previous * Self::UNITS_PER_BLOCK).round();
+
+		Self::from_units(delta)
+	}
+
+	/// Create a delta from a distance in blocks
+	pub fn from_blocks(blocks: f64) -> Result<Self, SerializingErr> {
+		Self::from_units((blocks * Self::UNITS_PER_BLOCK).round())
+	}
+
+	/// The distance moved, in blocks
+	pub fn to_blocks(&self) -> f64 {
+		self.0 as f64 / Self::UNITS_PER_BLOCK
+	}
+
+	fn from_units(units: f64) -> Result<Self, SerializingErr> {
+		if units.is_nan() || units < i16::MIN as f64 || units > i16::MAX as f64 {
+			return Err(SerializingErr::UniqueFailure(format!("Movement of {} blocks is too far for a delta", units / Self::UNITS_PER_BLOCK)));
+		}
+
+		Ok(PositionDelta(units as i16))
+	}
+}
+
+impl TryFrom<f64> for PositionDelta {
+	type Error = SerializingErr;
+
+	/// Convert from a distance in blocks
+	fn try_from(blocks: f64) -> Result<Self, Self::Error> {
+		PositionDelta::from_blocks(blocks)
+	}
+}
+
+impl TryFrom<f32> for PositionDelta {
+	type Error = SerializingErr;
+
+	/// Convert from a distance in blocks
+	fn try_from(blocks: f32) -> Result<Self, Self::Error> {
+		PositionDelta::from_blocks(blocks as f64)
+	}
+}
+
+impl From<PositionDelta> for f64 {
+	/// Convert to a distance in blocks
+	fn from(delta: PositionDelta) -> Self {
+		delta.to_blocks()
+	}
+}
+
+impl McSerialize for PositionDelta {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.0.mc_serialize(serializer)
+	}
+}
+
+impl McDeserialize for PositionDelta {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		Ok(PositionDelta(i16::mc_deserialize(deserializer)?))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
+	use crate::protocol_types::datatypes::angle::{Angle, PositionDelta};
+
+	#[test]
+	fn test_angle() {
+		assert_eq!(Angle::from_degrees(0.0), Angle(0));
+		assert_eq!(Angle::from_degrees(90.0), Angle(64));
+		assert_eq!(Angle::from_degrees(-90.0), Angle(192));
+		assert_eq!(Angle::from_degrees(360.0), Angle(0));
+		assert_eq!(Angle::from_degrees(719.0), Angle(255));
+		assert_eq!(Angle::from(180.0f64), Angle(128));
+		assert_eq!(Angle(64).to_degrees(), 90.0);
+		assert!((Angle::from_radians(std::f32::consts::PI).to_radians() - std::f32::consts::PI).abs() < 0.001);
+
+		let mut serializer = McSerializer::new();
+		Angle(200).mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, vec![200]);
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(Angle::mc_deserialize(&mut deserializer).unwrap(), Angle(200));
+	}
+
+	#[test]
+	fn test_position_delta() {
+		assert_eq!(PositionDelta::from_blocks(1.0).unwrap(), PositionDelta(4096));
+		assert_eq!(PositionDelta::from_blocks(-0.5).unwrap().to_blocks(), -0.5);
+		assert_eq!(PositionDelta::between(10.0, 12.25).unwrap(), PositionDelta(9216));
+		assert_eq!(PositionDelta::try_from(7.99f32).unwrap().to_blocks(), 32727.0 / 4096.0);
+
+		assert!(PositionDelta::from_blocks(8.0).is_err());
+		assert!(PositionDelta::from_blocks(-8.0).is_ok());
+		assert!(PositionDelta::between(0.0, 100.0).is_err());
+		assert!(PositionDelta::from_blocks(f64::NAN).is_err());
+
+		let mut serializer = McSerializer::new();
+		PositionDelta(-2).mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, vec![0xFF, 0xFE]);
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(PositionDelta::mc_deserialize(&mut deserializer).unwrap(), PositionDelta(-2));
+	}
+}
@@ -0,0 +1,270 @@
+//! Bit sets as they are sent over the network. [BitSet] grows as needed and is used for things like the
+//! light masks of a chunk, while [FixedBitSet] always has the same number of bits, such as for the
+//! acknowledged messages in signed chat.
+//! See more details here: https://wiki.vg/Protocol#BitSet
+
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+/// A set of bits of any length, sent as a VarInt-prefixed array of longs. Bit `i` is stored in long
+/// `i / 64` at position `i % 64`.
+///
+/// Trailing longs that are 0 are never stored, so two sets with the same bits set are always equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BitSet {
+	data: Vec<i64>,
+}
+
+impl BitSet {
+	pub fn new() -> Self {
+		Self {
+			data: vec![],
+		}
+	}
+
+	/// Create a bit set from its raw longs
+	pub fn from_longs(longs: Vec<i64>) -> Self {
+		let mut set = Self {
+			data: longs,
+		};
+
+		set.trim();
+		set
+	}
+
+	/// The raw longs of this bit set, as they are sent over the network
+	pub fn as_longs(&self) -> &[i64] {
+		&self.data
+	}
+
+	pub fn get(&self, index: usize) -> bool {
+		match self.data.get(index / 64) {
+			Some(long) => (long >> (index % 64)) & 1 == 1,
+			None => false,
+		}
+	}
+
+	/// Set or clear a bit, growing the set if needed
+	pub fn set(&mut self, index: usize, value: bool) {
+		let long = index / 64;
+
+		if value {
+			if long >= self.data.len() {
+				self.data.resize(long + 1, 0);
+			}
+
+			self.data[long] |= 1 << (index % 64);
+		} else if long < self.data.len() {
+			self.data[long] &= !(1 << (index % 64));
+			self.trim();
+		}
+	}
+
+	/// True if no bits are set
+	pub fn is_empty(&self) -> bool {
+		self.data.is_empty()
+	}
+
+	/// The number of bits that are set
+	pub fn count_ones(&self) -> usize {
+		self.data.iter().map(|long| long.count_ones() as usize).sum()
+	}
+
+	/// Iterate over the indices of every set bit, from lowest to highest
+	pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+		(0..self.data.len() * 64).filter(|i| self.get(*i))
+	}
+
+	fn trim(&mut self) {
+		while self.data.last() == Some(&0) {
+			self.data.pop();
+		}
+	}
+}
+
+impl FromIterator<usize> for BitSet {
+	/// Create a bit set with each of the given bits set
+	fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+		let mut set = BitSet::new();
+
+		for i in iter {
+			set.set(i, true);
+		}
+
+		set
+	}
+}
+
+impl McSerialize for BitSet {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		VarInt(self.data.len() as i32).mc_serialize(serializer)?;
+
+		for long in &self.data {
+			long.mc_serialize(serializer)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl McDeserialize for BitSet {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let length = VarInt::mc_deserialize(deserializer)?.0;
+
+		if length < 0 {
+			return Err(SerializingErr::UniqueFailure(format!("Negative length {}", length)));
+		}
+
+		let mut longs = vec![]; // don't trust the length for the allocation
+
+		for _ in 0..length {
+			longs.push(i64::mc_deserialize(deserializer)?);
+		}
+
+		Ok(BitSet::from_longs(longs))
+	}
+}
+
+/// A set of exactly `N` bits, sent as `ceil(N / 8)` bytes without a length prefix. Bit `i` is stored in
+/// byte `i / 8` at position `i % 8`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FixedBitSet<const N: usize> {
+	data: Vec<u8>,
+}
+
+impl<const N: usize> FixedBitSet<N> {
+	/// The number of bytes used to send this bit set
+	pub const BYTES: usize = N.div_ceil(8);
+
+	pub fn new() -> Self {
+		Self {
+			data: vec![0; Self::BYTES],
+		}
+	}
+
+	/// The raw bytes of this bit set, as they are sent over the network
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.data
+	}
+
+	/// Get a bit, which is always false past the end of the set
+	pub fn get(&self, index: usize) -> bool {
+		index < N && (self.data[index / 8] >> (index % 8)) & 1 == 1
+	}
+
+	/// Set or clear a bit.
+	///
+	/// # Panics
+	/// If `index` is not less than `N`
+	pub fn set(&mut self, index: usize, value: bool) {
+		assert!(index < N, "Bit {} is out of bounds for a FixedBitSet of {} bits", index, N);
+
+		if value {
+			self.data[index / 8] |= 1 << (index % 8);
+		} else {
+			self.data[index / 8] &= !(1 << (index % 8));
+		}
+	}
+
+	/// True if no bits are set
+	pub fn is_empty(&self) -> bool {
+		self.data.iter().all(|b| *b == 0)
+	}
+
+	/// The number of bits that are set
+	pub fn count_ones(&self) -> usize {
+		self.data.iter().map(|b| b.count_ones() as usize).sum()
+	}
+
+	/// Iterate over the indices of every set bit, from lowest to highest
+	pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+		(0..N).filter(|i| self.get(*i))
+	}
+}
+
+impl<const N: usize> Default for FixedBitSet<N> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<const N: usize> McSerialize for FixedBitSet<N> {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		serializer.serialize_bytes(&self.data);
+		Ok(())
+	}
+}
+
+impl<const N: usize> McDeserialize for FixedBitSet<N> {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let mut data = deserializer.slice_option(Self::BYTES).ok_or(SerializingErr::InputEnded)?.to_vec();
+
+		if !N.is_multiple_of(8) { // ignore any bits past the end
+			data[Self::BYTES - 1] &= (1 << (N % 8)) - 1;
+		}
+
+		Ok(Self {
+			data,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
+	use crate::protocol_types::datatypes::bitset::{BitSet, FixedBitSet};
+
+	#[test]
+	fn test_bitset() {
+		let mut set = BitSet::new();
+		set.set(1, true);
+		set.set(2, true);
+		set.set(70, true);
+
+		assert!(set.get(1) && set.get(70));
+		assert!(!set.get(0) && !set.get(1000));
+		assert_eq!(set.as_longs(), &[0b110, 0b1000000]);
+		assert_eq!(set.iter().collect::<Vec<usize>>(), vec![1, 2, 70]);
+		assert_eq!(set.count_ones(), 3);
+
+		set.set(70, false);
+		assert_eq!(set, BitSet::from_longs(vec![0b110, 0, 0]));
+		assert_eq!(set, [1, 2].into_iter().collect());
+
+		let mut serializer = McSerializer::new();
+		set.mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, vec![1, 0, 0, 0, 0, 0, 0, 0, 0b110]);
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(BitSet::mc_deserialize(&mut deserializer).unwrap(), set);
+	}
+
+	#[test]
+	fn test_fixed_bitset() {
+		let mut set = FixedBitSet::<20>::new();
+		set.set(0, true);
+		set.set(19, true);
+
+		assert_eq!(FixedBitSet::<20>::BYTES, 3);
+		assert_eq!(set.as_bytes(), &[1, 0, 0b1000]);
+		assert_eq!(set.iter().collect::<Vec<usize>>(), vec![0, 19]);
+		assert!(!set.get(20));
+
+		let mut serializer = McSerializer::new();
+		set.mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output.len(), 3);
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(FixedBitSet::<20>::mc_deserialize(&mut deserializer).unwrap(), set);
+
+		let mut deserializer = McDeserializer::new(&[0xFF, 0xFF, 0xFF]);
+		assert_eq!(FixedBitSet::<20>::mc_deserialize(&mut deserializer).unwrap().count_ones(), 20);
+	}
+
+	#[test]
+	#[should_panic]
+	fn test_fixed_bitset_out_of_bounds() {
+		FixedBitSet::<20>::new().set(20, true);
+	}
+}
@@ -0,0 +1,177 @@
+//! Strings with a maximum length. Most string fields in the protocol have a limit on how many characters
+//! they can hold, and vanilla rejects anything longer, so these are checked on both ends.
+//! See more details here: https://wiki.vg/Protocol#Type:String
+
+use std::fmt::{Display, Formatter};
+use std::ops::Deref;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+/// The limit for strings that don't have a more specific one
+pub const DEFAULT_MAX_LENGTH: usize = 32767;
+/// The limit for player usernames
+pub const USERNAME_MAX_LENGTH: usize = 16;
+/// The limit for JSON chat components
+pub const CHAT_MAX_LENGTH: usize = 262144;
+
+/// A string that holds at most `MAX` characters. Like vanilla, the length is counted in UTF-16 code units,
+/// so some characters such as emoji count twice.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct BoundedString<const MAX: usize = DEFAULT_MAX_LENGTH>(String);
+
+/// A player's username
+pub type Username = BoundedString<USERNAME_MAX_LENGTH>;
+
+impl<const MAX: usize> BoundedString<MAX> {
+	/// Create a bounded string, failing if the string is longer than `MAX`
+	pub fn new<S: Into<String>>(s: S) -> Result<Self, SerializingErr> {
+		let s = s.into();
+		check_length(&s, MAX)?;
+
+		Ok(Self(s))
+	}
+
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+
+	pub fn into_inner(self) -> String {
+		self.0
+	}
+}
+
+fn check_length(s: &str, max: usize) -> Result<(), SerializingErr> {
+	// a char is at most 2 UTF-16 units, so short strings can skip counting
+	if s.len() > max || s.chars().count() * 2 > max {
+		let length = s.encode_utf16().count();
+
+		if length > max {
+			return Err(SerializingErr::StringTooLong { length, max });
+		}
+	}
+
+	Ok(())
+}
+
+impl<const MAX: usize> Deref for BoundedString<MAX> {
+	type Target = str;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl<const MAX: usize> Display for BoundedString<MAX> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+impl<const MAX: usize> FromStr for BoundedString<MAX> {
+	type Err = SerializingErr;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		BoundedString::new(s)
+	}
+}
+
+impl<const MAX: usize> TryFrom<String> for BoundedString<MAX> {
+	type Error = SerializingErr;
+
+	fn try_from(s: String) -> Result<Self, Self::Error> {
+		BoundedString::new(s)
+	}
+}
+
+impl<const MAX: usize> TryFrom<&str> for BoundedString<MAX> {
+	type Error = SerializingErr;
+
+	fn try_from(s: &str) -> Result<Self, Self::Error> {
+		BoundedString::new(s)
+	}
+}
+
+impl<const MAX: usize> From<BoundedString<MAX>> for String {
+	fn from(s: BoundedString<MAX>) -> Self {
+		s.0
+	}
+}
+
+impl<const MAX: usize> PartialEq<str> for BoundedString<MAX> {
+	fn eq(&self, other: &str) -> bool {
+		self.0 == other
+	}
+}
+
+impl<const MAX: usize> PartialEq<&str> for BoundedString<MAX> {
+	fn eq(&self, other: &&str) -> bool {
+		self.0 == *other
+	}
+}
+
+impl<const MAX: usize> McSerialize for BoundedString<MAX> {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		check_length(&self.0, MAX)?; // the field is private, but be safe anyway
+		self.0.mc_serialize(serializer)
+	}
+}
+
+impl<const MAX: usize> McDeserialize for BoundedString<MAX> {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let length = VarInt::mc_deserialize(deserializer)?.0;
+
+		// each UTF-16 unit takes at most 3 bytes, so check before reading anything
+		if length < 0 || length as usize > MAX * 3 {
+			return Err(SerializingErr::StringTooLong { length: length.max(0) as usize, max: MAX });
+		}
+
+		let length = length as usize;
+
+		if deserializer.index + length > deserializer.data.len() {
+			return Err(SerializingErr::OutOfBounds);
+		}
+
+		let s = String::from_utf8(deserializer.data[deserializer.index..deserializer.index + length].to_vec())?;
+		deserializer.increment(length);
+
+		BoundedString::new(s)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
+	use crate::protocol::serialization::serializer_error::SerializingErr;
+	use crate::protocol_types::datatypes::bounded_string::{BoundedString, Username};
+
+	#[test]
+	fn test_bounded_string() {
+		assert_eq!(Username::new("dec4234").unwrap(), "dec4234");
+		assert!(Username::new("a".repeat(16)).is_ok());
+		assert_eq!(Username::new("a".repeat(17)), Err(SerializingErr::StringTooLong { length: 17, max: 16 }));
+
+		// counted in UTF-16 units, not bytes or chars
+		assert!(BoundedString::<4>::new("éééé").is_ok());
+		assert!(BoundedString::<4>::new("😀😀").is_ok());
+		assert!(BoundedString::<4>::new("😀😀a").is_err());
+
+		let mut serializer = McSerializer::new();
+		Username::new("abc").unwrap().mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, vec![3, 97, 98, 99]);
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(Username::mc_deserialize(&mut deserializer).unwrap(), "abc");
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(BoundedString::<2>::mc_deserialize(&mut deserializer), Err(SerializingErr::StringTooLong { length: 3, max: 2 }));
+
+		let mut deserializer = McDeserializer::new(&[0xFF, 0x01]); // rejected from the length alone
+		assert!(BoundedString::<2>::mc_deserialize(&mut deserializer).is_err());
+	}
+}
@@ -1,10 +1,16 @@
 //! This file defines the TextComponent type in the Minecraft network API.
 //! Seen in books, disconnect messages, chat messages, action bar, etc.
 
-use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::ops::Deref;
+
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
 use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::nbt::nbt::NbtTag;
+use crate::protocol_types::datatypes::nbt::nbt_error::NbtError;
+use crate::protocol_types::datatypes::nbt::nbt_serde;
 
 /// A TextComponent is a fancy way to display text inside the game. This is most commonly seen
 /// in chat messages and book messages. The only thing that is required to be included is a String
@@ -12,16 +18,23 @@ use crate::protocol::serialization::serializer_error::SerializingErr;
 ///
 /// See [https://wiki.vg/Text_formatting#Text_components](https://wiki.vg/Text_formatting#Text_components)
 /// for more information.
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
-#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub struct TextComponent {
+	#[serde(default)]
 	pub text: String,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	#[serde(rename = "type")]
-	pub typ: Option<String>, // TODO: replace with ComponentType enum
+	pub typ: Option<ComponentType>,
 	#[serde(skip_serializing_if = "Option::is_none")]
+	#[serde(default, deserialize_with = "deserialize_children")]
 	pub extra: Option<Vec<TextComponent>>,
 	
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub translate: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[serde(default, deserialize_with = "deserialize_children")]
+	pub with: Option<Vec<TextComponent>>,
+	
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub color: Option<String>,
 	#[serde(skip_serializing_if = "Option::is_none")]
@@ -40,32 +53,167 @@ pub struct TextComponent {
 	
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub insertion: Option<String>,
+	
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[serde(rename = "clickEvent")]
+	pub click_event: Option<ClickEvent>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	#[serde(rename = "hoverEvent")]
+	pub hover_event: Option<HoverEvent>,
 }
 
 impl TextComponent {
 	pub fn new<T: Into<String>>(text: T) -> Self {
 		Self {
 			text: text.into(),
-			typ: None,
-			extra: None,
-			color: None,
-			bold: None,
-			italic: None,
-			underlined: None,
-			strikethrough: None,
-			obfuscated: None,
-			font: None,
-			insertion: None,
+			..Default::default()
 		}
 	}
 	
-	pub fn set_type<T: Into<String>>(&mut self, typ: T) {
-		self.typ = Some(typ.into());
+	/// Create a component that the client translates using its language file, filling in the
+	/// placeholders of the translation with `with`.
+	pub fn translatable<T: Into<String>>(key: T, with: Vec<TextComponent>) -> Self {
+		Self {
+			typ: Some(ComponentType::Translatable),
+			translate: Some(key.into()),
+			with: if with.is_empty() { None } else { Some(with) },
+			..Default::default()
+		}
+	}
+	
+	pub fn set_type(&mut self, typ: ComponentType) {
+		self.typ = Some(typ);
 	}
 	
 	pub fn set_extra(&mut self, extra: Vec<TextComponent>) {
 		self.extra = Some(extra);
 	}
+	
+	/// Add a component to be displayed after this one. It inherits any formatting from this component
+	/// that it doesn't override.
+	pub fn append<T: Into<TextComponent>>(mut self, child: T) -> Self {
+		self.extra.get_or_insert_with(Vec::new).push(child.into());
+		self
+	}
+	
+	/// Set the color to either a named color such as `red` or a hex color such as `#FF0000`
+	pub fn color<T: Into<String>>(mut self, color: T) -> Self {
+		self.color = Some(color.into());
+		self
+	}
+	
+	pub fn bold(mut self, bold: bool) -> Self {
+		self.bold = Some(bold);
+		self
+	}
+	
+	pub fn italic(mut self, italic: bool) -> Self {
+		self.italic = Some(italic);
+		self
+	}
+	
+	pub fn underlined(mut self, underlined: bool) -> Self {
+		self.underlined = Some(underlined);
+		self
+	}
+	
+	pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+		self.strikethrough = Some(strikethrough);
+		self
+	}
+	
+	pub fn obfuscated(mut self, obfuscated: bool) -> Self {
+		self.obfuscated = Some(obfuscated);
+		self
+	}
+	
+	pub fn on_click(mut self, event: ClickEvent) -> Self {
+		self.click_event = Some(event);
+		self
+	}
+	
+	pub fn on_hover(mut self, event: HoverEvent) -> Self {
+		self.hover_event = Some(event);
+		self
+	}
+	
+	/// The text of this component and all of its children without any formatting. Translatable
+	/// components show their translation key, since the translation is only known to the client.
+	pub fn to_plain_text(&self) -> String {
+		let mut out = String::new();
+		self.write_plain_text(&mut out);
+		out
+	}
+	
+	/// Convert this component into NBT, which is how components are sent over the network since 1.20.3.
+	/// A component with only text is sent as a plain String tag.
+	pub fn to_nbt(&self) -> Result<NbtTag, NbtError> {
+		if *self == TextComponent::new(self.text.clone()) {
+			return Ok(NbtTag::String(self.text.clone()));
+		}
+		
+		nbt_serde::to_nbt(self)
+	}
+	
+	/// Read a component sent as NBT, which may also be a plain String tag or a List of components
+	pub fn from_nbt(tag: NbtTag) -> Result<Self, NbtError> {
+		match tag {
+			NbtTag::String(s) => Ok(TextComponent::new(s)),
+			NbtTag::Compound(_) => nbt_serde::from_nbt(tag),
+			tag => Ok(nbt_serde::from_nbt::<ComponentOrString>(tag)?.into()),
+		}
+	}
+	
+	fn write_plain_text(&self, out: &mut String) {
+		match &self.translate {
+			Some(key) if self.text.is_empty() => out.push_str(key),
+			_ => out.push_str(&self.text),
+		}
+		
+		for child in self.extra.iter().flatten() {
+			child.write_plain_text(out);
+		}
+	}
+}
+
+/// Vanilla often sends children as plain strings or puts a lone string in place of a component, so
+/// accept both forms wherever a list of components is expected.
+fn deserialize_children<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<TextComponent>>, D::Error> {
+	let children: Option<Vec<ComponentOrString>> = Option::deserialize(deserializer)?;
+	
+	Ok(children.map(|children| children.into_iter().map(TextComponent::from).collect()))
+}
+
+/// Like [deserialize_children], but for a single component, such as the description of a status response
+pub(crate) fn deserialize_component<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TextComponent, D::Error> {
+	Ok(ComponentOrString::deserialize(deserializer)?.into())
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ComponentOrString {
+	String(String),
+	List(Vec<ComponentOrString>),
+	Component(Box<TextComponent>),
+}
+
+impl From<ComponentOrString> for TextComponent {
+	fn from(value: ComponentOrString) -> Self {
+		match value {
+			ComponentOrString::String(s) => TextComponent::new(s),
+			ComponentOrString::List(list) => { // the first element is the parent of the rest
+				let mut iter = list.into_iter().map(TextComponent::from);
+				let mut first = iter.next().unwrap_or_default();
+				
+				for child in iter {
+					first = first.append(child);
+				}
+				
+				first
+			}
+			ComponentOrString::Component(c) => *c,
+		}
+	}
 }
 
 impl McSerialize for TextComponent {
@@ -79,9 +227,9 @@ impl McSerialize for TextComponent {
 impl McDeserialize for TextComponent {
 	fn mc_deserialize(deserializer: &mut McDeserializer) -> Result<Self, SerializingErr> {
 		let json = String::mc_deserialize(deserializer)?;
-		let deserialized = serde_json::from_str(&json).map_err(|e| SerializingErr::UniqueFailure(format!("Failed to deserialize JSON: {}", e)))?;
+		let deserialized: ComponentOrString = serde_json::from_str(&json).map_err(|e| SerializingErr::UniqueFailure(format!("Failed to deserialize JSON: {}", e)))?;
 		
-		Ok(deserialized)
+		Ok(deserialized.into())
 	}
 }
 
@@ -91,7 +239,69 @@ impl From<String> for TextComponent {
 	}
 }
 
-// TODO: continue writing component type
+impl From<&str> for TextComponent {
+	fn from(s: &str) -> Self {
+		Self::new(s)
+	}
+}
+
+impl Display for TextComponent {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.to_plain_text())
+	}
+}
+
+/// A [TextComponent] that is sent as NBT instead of JSON. Packets in the configuration and play states
+/// use this format since 1.20.3, while the login state still uses JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct NbtTextComponent(pub TextComponent);
+
+impl NbtTextComponent {
+	pub fn new(component: TextComponent) -> Self {
+		Self(component)
+	}
+
+	pub fn into_inner(self) -> TextComponent {
+		self.0
+	}
+}
+
+impl Deref for NbtTextComponent {
+	type Target = TextComponent;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl From<TextComponent> for NbtTextComponent {
+	fn from(component: TextComponent) -> Self {
+		Self(component)
+	}
+}
+
+impl From<&str> for NbtTextComponent {
+	fn from(s: &str) -> Self {
+		Self(TextComponent::new(s))
+	}
+}
+
+impl McSerialize for NbtTextComponent {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.0.to_nbt().map_err(|e| SerializingErr::UniqueFailure(format!("Failed to serialize NBT: {}", e)))?.serialize_network(serializer)
+	}
+}
+
+impl McDeserialize for NbtTextComponent {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let tag = NbtTag::from_network(deserializer)?;
+
+		Ok(Self(TextComponent::from_nbt(tag).map_err(|e| SerializingErr::UniqueFailure(format!("Failed to deserialize NBT: {}", e)))?))
+	}
+}
+
+/// What kind of content a component holds. This is optional, since the client can work it out from
+/// which fields are present.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ComponentType {
 	#[serde(rename = "text")]
@@ -183,7 +393,7 @@ impl HoverEvent {
 		Self::new("show_text", HoverComponent::String(text.into()))
 	}
 	
-	pub fn show_item<T: Into<String>>(id: String, count: i32, tag: Option<String>) -> Self {
+	pub fn show_item<T: Into<String>>(id: T, count: i32, tag: Option<String>) -> Self {
 		let s = {
 			if let Some(compound) = tag {
 				compound
@@ -193,7 +403,7 @@ impl HoverEvent {
 		};
 		
 		let item = ItemHover {
-			id,
+			id: id.into(),
 			count,
 			tag: Some(s),
 		};
@@ -206,7 +416,7 @@ impl HoverEvent {
 	}
 	
 	pub fn show_text_component(text: TextComponent) -> Self {
-		Self::new("show_text", HoverComponent::TextComponent(text))
+		Self::new("show_text", HoverComponent::TextComponent(Box::new(text)))
 	}
 	
 	pub fn show_achievement<T: Into<String>>(entity: T) -> Self {
@@ -232,9 +442,10 @@ impl McDeserialize for HoverEvent {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(untagged)]
 pub enum HoverComponent {
 	String(String),
-	TextComponent(TextComponent),
+	TextComponent(Box<TextComponent>),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
@@ -242,4 +453,63 @@ pub struct ItemHover {
 	pub id: String,
 	pub count: i32,
 	pub tag: Option<String>,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+	use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
+	use crate::protocol_types::datatypes::chat::{ClickEvent, ComponentType, HoverEvent, NbtTextComponent, TextComponent};
+
+	#[test]
+	fn test_component_json() {
+		let component = TextComponent::new("Hello")
+			.color("gold")
+			.bold(true)
+			.on_click(ClickEvent::open_url("https://example.com"))
+			.on_hover(HoverEvent::show_text("Click me"))
+			.append(", world!");
+
+		let json = serde_json::to_string(&component).unwrap();
+		assert_eq!(json, r#"{"text":"Hello","extra":[{"text":", world!"}],"color":"gold","bold":true,"clickEvent":{"action":"open_url","value":"https://example.com"},"hoverEvent":{"action":"show_text","contents":"Click me"}}"#);
+		assert_eq!(serde_json::from_str::<TextComponent>(&json).unwrap(), component);
+		assert_eq!(component.to_plain_text(), "Hello, world!");
+
+		let translated = TextComponent::translatable("chat.type.text", vec!["Steve".into(), "hi".into()]);
+		assert_eq!(serde_json::to_string(&translated).unwrap(), r#"{"text":"","type":"translatable","translate":"chat.type.text","with":[{"text":"Steve"},{"text":"hi"}]}"#);
+		assert_eq!(translated.typ, Some(ComponentType::Translatable));
+	}
+
+	#[test]
+	fn test_component_shorthand() {
+		let json = r#"["", "Plain ", {"text": "red", "color": "red", "extra": ["!"]}]"#;
+
+		let mut serializer = McSerializer::new();
+		json.to_string().mc_serialize(&mut serializer).unwrap();
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		let component = TextComponent::mc_deserialize(&mut deserializer).unwrap();
+
+		assert_eq!(component.to_string(), "Plain red!");
+		assert_eq!(component.extra.as_ref().unwrap()[1].color.as_deref(), Some("red"));
+
+		let mut serializer = McSerializer::new();
+		"\"Just a string\"".to_string().mc_serialize(&mut serializer).unwrap();
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(TextComponent::mc_deserialize(&mut deserializer).unwrap(), TextComponent::new("Just a string"));
+	}
+
+	#[test]
+	fn test_nbt_text_component() {
+		for component in [TextComponent::new("Kicked"), TextComponent::new("Kicked").color("red").append(" for spam")] {
+			let component = NbtTextComponent::from(component);
+			let mut serializer = McSerializer::new();
+			component.mc_serialize(&mut serializer).unwrap();
+
+			// a string tag for plain text, a compound otherwise
+			assert_eq!(serializer.output[0], if component.extra.is_none() { 8 } else { 10 });
+
+			let mut deserializer = McDeserializer::new(&serializer.output);
+			assert_eq!(NbtTextComponent::mc_deserialize(&mut deserializer).unwrap(), component);
+			assert!(deserializer.is_at_end());
+		}
+	}
+}
@@ -0,0 +1,454 @@
+//! Entity metadata is a list of values that describe the state of an entity, such as whether it is on
+//! fire, its custom name or the item it is holding. Each value is sent as its index, its type and then
+//! the value itself, and the list ends with an index of 0xFF.
+//! See more details here: https://wiki.vg/Entity_metadata
+
+use std::collections::BTreeMap;
+
+use uuid::Uuid;
+
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::chat::TextComponent;
+use crate::protocol_types::datatypes::identifier::Identifier;
+use crate::protocol_types::datatypes::nbt::nbt::NbtTag;
+use crate::protocol_types::datatypes::position::Position;
+use crate::protocol_types::datatypes::slot::Slot;
+use crate::protocol_types::datatypes::var_types::{VarInt, VarLong};
+
+/// The index that marks the end of the metadata
+const END_INDEX: u8 = 0xFF;
+
+/// The metadata of an entity, ordered by index. Only the values that changed need to be sent.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct EntityMetadata {
+	values: BTreeMap<u8, MetadataValue>,
+}
+
+impl EntityMetadata {
+	pub fn new() -> Self {
+		Self {
+			values: BTreeMap::new(),
+		}
+	}
+
+	/// Set the value at an index, replacing any previous value.
+	///
+	/// # Panics
+	/// If `index` is 0xFF, which is reserved to mark the end of the metadata
+	pub fn set(&mut self, index: u8, value: MetadataValue) {
+		assert_ne!(index, END_INDEX, "Metadata index 0xFF is reserved");
+		self.values.insert(index, value);
+	}
+
+	/// Set the value at an index and return the metadata, for chaining
+	pub fn with(mut self, index: u8, value: MetadataValue) -> Self {
+		self.set(index, value);
+		self
+	}
+
+	pub fn get(&self, index: u8) -> Option<&MetadataValue> {
+		self.values.get(&index)
+	}
+
+	pub fn remove(&mut self, index: u8) -> Option<MetadataValue> {
+		self.values.remove(&index)
+	}
+
+	pub fn len(&self) -> usize {
+		self.values.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.values.is_empty()
+	}
+
+	/// Iterate over every index and value, from the lowest index to the highest
+	pub fn iter(&self) -> impl Iterator<Item = (u8, &MetadataValue)> {
+		self.values.iter().map(|(index, value)| (*index, value))
+	}
+}
+
+impl McSerialize for EntityMetadata {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		for (index, value) in &self.values {
+			index.mc_serialize(serializer)?;
+			value.mc_serialize(serializer)?;
+		}
+
+		END_INDEX.mc_serialize(serializer)
+	}
+}
+
+impl McDeserialize for EntityMetadata {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let mut metadata = EntityMetadata::new();
+
+		loop {
+			let index = u8::mc_deserialize(deserializer)?;
+
+			if index == END_INDEX {
+				break;
+			}
+
+			metadata.values.insert(index, MetadataValue::mc_deserialize(deserializer)?);
+		}
+
+		Ok(metadata)
+	}
+}
+
+/// A single metadata value along with its type, as of 1.21 (protocol 767).
+///
+/// Particles are not supported yet, since their encoding depends on the type of particle. Reading
+/// metadata that holds particles fails.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataValue {
+	Byte(i8),
+	VarInt(VarInt),
+	VarLong(VarLong),
+	Float(f32),
+	String(String),
+	TextComponent(TextComponent),
+	OptionalTextComponent(Option<TextComponent>),
+	Slot(Slot),
+	Boolean(bool),
+	/// Rotation around the x, y and z axis in degrees
+	Rotations(f32, f32, f32),
+	Position(Position),
+	OptionalPosition(Option<Position>),
+	Direction(Direction),
+	OptionalUuid(Option<Uuid>),
+	/// An id in the block state registry
+	BlockState(VarInt),
+	/// An id in the block state registry, where air is the same as no block
+	OptionalBlockState(Option<VarInt>),
+	Nbt(NbtTag),
+	VillagerData {
+		villager_type: VarInt,
+		profession: VarInt,
+		level: VarInt,
+	},
+	OptionalVarInt(Option<VarInt>),
+	Pose(Pose),
+	CatVariant(VarInt),
+	WolfVariant(VarInt),
+	FrogVariant(VarInt),
+	/// A dimension and a position in it
+	OptionalGlobalPosition(Option<(Identifier, Position)>),
+	/// An id in the `minecraft:painting_variant` registry
+	PaintingVariant(VarInt),
+	SnifferState(VarInt),
+	ArmadilloState(VarInt),
+	Vector3(f32, f32, f32),
+	Quaternion(f32, f32, f32, f32),
+}
+
+impl MetadataValue {
+	/// The id of the type of this value, which is sent before the value
+	pub fn type_id(&self) -> i32 {
+		match self {
+			MetadataValue::Byte(_) => 0,
+			MetadataValue::VarInt(_) => 1,
+			MetadataValue::VarLong(_) => 2,
+			MetadataValue::Float(_) => 3,
+			MetadataValue::String(_) => 4,
+			MetadataValue::TextComponent(_) => 5,
+			MetadataValue::OptionalTextComponent(_) => 6,
+			MetadataValue::Slot(_) => 7,
+			MetadataValue::Boolean(_) => 8,
+			MetadataValue::Rotations(..) => 9,
+			MetadataValue::Position(_) => 10,
+			MetadataValue::OptionalPosition(_) => 11,
+			MetadataValue::Direction(_) => 12,
+			MetadataValue::OptionalUuid(_) => 13,
+			MetadataValue::BlockState(_) => 14,
+			MetadataValue::OptionalBlockState(_) => 15,
+			MetadataValue::Nbt(_) => 16,
+			// 17 and 18 are particles
+			MetadataValue::VillagerData { .. } => 19,
+			MetadataValue::OptionalVarInt(_) => 20,
+			MetadataValue::Pose(_) => 21,
+			MetadataValue::CatVariant(_) => 22,
+			MetadataValue::WolfVariant(_) => 23,
+			MetadataValue::FrogVariant(_) => 24,
+			MetadataValue::OptionalGlobalPosition(_) => 25,
+			MetadataValue::PaintingVariant(_) => 26,
+			MetadataValue::SnifferState(_) => 27,
+			MetadataValue::ArmadilloState(_) => 28,
+			MetadataValue::Vector3(..) => 29,
+			MetadataValue::Quaternion(..) => 30,
+		}
+	}
+}
+
+impl McSerialize for MetadataValue {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		VarInt(self.type_id()).mc_serialize(serializer)?;
+
+		match self {
+			MetadataValue::Byte(b) => b.mc_serialize(serializer)?,
+			MetadataValue::VarInt(v) | MetadataValue::BlockState(v) | MetadataValue::CatVariant(v)
+			| MetadataValue::WolfVariant(v) | MetadataValue::FrogVariant(v) | MetadataValue::SnifferState(v)
+			| MetadataValue::ArmadilloState(v) => v.mc_serialize(serializer)?,
+			MetadataValue::VarLong(v) => v.mc_serialize(serializer)?,
+			MetadataValue::Float(f) => f.mc_serialize(serializer)?,
+			MetadataValue::String(s) => s.mc_serialize(serializer)?,
+			MetadataValue::TextComponent(text) => write_text(serializer, text)?,
+			MetadataValue::OptionalTextComponent(text) => {
+				text.is_some().mc_serialize(serializer)?;
+
+				if let Some(text) = text {
+					write_text(serializer, text)?;
+				}
+			}
+			MetadataValue::Slot(slot) => slot.mc_serialize(serializer)?,
+			MetadataValue::Boolean(b) => b.mc_serialize(serializer)?,
+			MetadataValue::Rotations(x, y, z) | MetadataValue::Vector3(x, y, z) => {
+				x.mc_serialize(serializer)?;
+				y.mc_serialize(serializer)?;
+				z.mc_serialize(serializer)?;
+			}
+			MetadataValue::Position(position) => position.mc_serialize(serializer)?,
+			MetadataValue::OptionalPosition(position) => position.mc_serialize(serializer)?,
+			MetadataValue::Direction(direction) => VarInt(*direction as i32).mc_serialize(serializer)?,
+			MetadataValue::OptionalUuid(uuid) => uuid.mc_serialize(serializer)?,
+			MetadataValue::OptionalBlockState(state) => {
+				VarInt(state.map(|s| s.0).unwrap_or(0)).mc_serialize(serializer)?;
+			}
+			MetadataValue::Nbt(nbt) => nbt.serialize_network(serializer)?,
+			MetadataValue::VillagerData { villager_type, profession, level } => {
+				villager_type.mc_serialize(serializer)?;
+				profession.mc_serialize(serializer)?;
+				level.mc_serialize(serializer)?;
+			}
+			MetadataValue::OptionalVarInt(v) => { // sent as the value plus one, with 0 meaning absent
+				VarInt(v.map(|v| v.0 + 1).unwrap_or(0)).mc_serialize(serializer)?;
+			}
+			MetadataValue::Pose(pose) => VarInt(*pose as i32).mc_serialize(serializer)?,
+			MetadataValue::OptionalGlobalPosition(position) => position.mc_serialize(serializer)?,
+			MetadataValue::PaintingVariant(v) => { // a registry reference is the id plus one, 0 would be an inline variant
+				VarInt(v.0 + 1).mc_serialize(serializer)?;
+			}
+			MetadataValue::Quaternion(x, y, z, w) => {
+				x.mc_serialize(serializer)?;
+				y.mc_serialize(serializer)?;
+				z.mc_serialize(serializer)?;
+				w.mc_serialize(serializer)?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl McDeserialize for MetadataValue {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let type_id = VarInt::mc_deserialize(deserializer)?.0;
+
+		let value = match type_id {
+			0 => MetadataValue::Byte(i8::mc_deserialize(deserializer)?),
+			1 => MetadataValue::VarInt(VarInt::mc_deserialize(deserializer)?),
+			2 => MetadataValue::VarLong(VarLong::mc_deserialize(deserializer)?),
+			3 => MetadataValue::Float(f32::mc_deserialize(deserializer)?),
+			4 => MetadataValue::String(String::mc_deserialize(deserializer)?),
+			5 => MetadataValue::TextComponent(read_text(deserializer)?),
+			6 => MetadataValue::OptionalTextComponent(if bool::mc_deserialize(deserializer)? {
+				Some(read_text(deserializer)?)
+			} else {
+				None
+			}),
+			7 => MetadataValue::Slot(Slot::mc_deserialize(deserializer)?),
+			8 => MetadataValue::Boolean(bool::mc_deserialize(deserializer)?),
+			9 => MetadataValue::Rotations(f32::mc_deserialize(deserializer)?, f32::mc_deserialize(deserializer)?, f32::mc_deserialize(deserializer)?),
+			10 => MetadataValue::Position(Position::mc_deserialize(deserializer)?),
+			11 => MetadataValue::OptionalPosition(Option::<Position>::mc_deserialize(deserializer)?),
+			12 => MetadataValue::Direction(Direction::try_from(VarInt::mc_deserialize(deserializer)?.0)?),
+			13 => MetadataValue::OptionalUuid(Option::<Uuid>::mc_deserialize(deserializer)?),
+			14 => MetadataValue::BlockState(VarInt::mc_deserialize(deserializer)?),
+			15 => MetadataValue::OptionalBlockState(match VarInt::mc_deserialize(deserializer)? {
+				VarInt(0) => None,
+				state => Some(state),
+			}),
+			16 => MetadataValue::Nbt(NbtTag::from_network(deserializer)?),
+			19 => MetadataValue::VillagerData {
+				villager_type: VarInt::mc_deserialize(deserializer)?,
+				profession: VarInt::mc_deserialize(deserializer)?,
+				level: VarInt::mc_deserialize(deserializer)?,
+			},
+			20 => MetadataValue::OptionalVarInt(match VarInt::mc_deserialize(deserializer)? {
+				VarInt(0) => None,
+				v => Some(VarInt(v.0 - 1)),
+			}),
+			21 => MetadataValue::Pose(Pose::try_from(VarInt::mc_deserialize(deserializer)?.0)?),
+			22 => MetadataValue::CatVariant(VarInt::mc_deserialize(deserializer)?),
+			23 => MetadataValue::WolfVariant(VarInt::mc_deserialize(deserializer)?),
+			24 => MetadataValue::FrogVariant(VarInt::mc_deserialize(deserializer)?),
+			25 => MetadataValue::OptionalGlobalPosition(Option::<(Identifier, Position)>::mc_deserialize(deserializer)?),
+			26 => match VarInt::mc_deserialize(deserializer)? {
+				VarInt(0) => return Err(SerializingErr::UniqueFailure("Inline painting variants are not supported".to_string())),
+				v => MetadataValue::PaintingVariant(VarInt(v.0 - 1)),
+			},
+			27 => MetadataValue::SnifferState(VarInt::mc_deserialize(deserializer)?),
+			28 => MetadataValue::ArmadilloState(VarInt::mc_deserialize(deserializer)?),
+			29 => MetadataValue::Vector3(f32::mc_deserialize(deserializer)?, f32::mc_deserialize(deserializer)?, f32::mc_deserialize(deserializer)?),
+			30 => MetadataValue::Quaternion(f32::mc_deserialize(deserializer)?, f32::mc_deserialize(deserializer)?, f32::mc_deserialize(deserializer)?, f32::mc_deserialize(deserializer)?),
+			_ => return Err(SerializingErr::UniqueFailure(format!("Metadata type {} is not supported", type_id))),
+		};
+
+		Ok(value)
+	}
+}
+
+/// The direction a block or entity is facing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+	Down = 0,
+	Up = 1,
+	North = 2,
+	South = 3,
+	West = 4,
+	East = 5,
+}
+
+impl TryFrom<i32> for Direction {
+	type Error = SerializingErr;
+
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(Direction::Down),
+			1 => Ok(Direction::Up),
+			2 => Ok(Direction::North),
+			3 => Ok(Direction::South),
+			4 => Ok(Direction::West),
+			5 => Ok(Direction::East),
+			_ => Err(SerializingErr::UniqueFailure(format!("Unknown direction {}", value))),
+		}
+	}
+}
+
+/// The pose of an entity, which changes its hitbox and how it is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Pose {
+	Standing = 0,
+	FallFlying = 1,
+	Sleeping = 2,
+	Swimming = 3,
+	SpinAttack = 4,
+	Sneaking = 5,
+	LongJumping = 6,
+	Dying = 7,
+	Croaking = 8,
+	UsingTongue = 9,
+	Sitting = 10,
+	Roaring = 11,
+	Sniffing = 12,
+	Emerging = 13,
+	Digging = 14,
+	Sliding = 15,
+	Shooting = 16,
+	Inhaling = 17,
+}
+
+impl TryFrom<i32> for Pose {
+	type Error = SerializingErr;
+
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		let pose = match value {
+			0 => Pose::Standing,
+			1 => Pose::FallFlying,
+			2 => Pose::Sleeping,
+			3 => Pose::Swimming,
+			4 => Pose::SpinAttack,
+			5 => Pose::Sneaking,
+			6 => Pose::LongJumping,
+			7 => Pose::Dying,
+			8 => Pose::Croaking,
+			9 => Pose::UsingTongue,
+			10 => Pose::Sitting,
+			11 => Pose::Roaring,
+			12 => Pose::Sniffing,
+			13 => Pose::Emerging,
+			14 => Pose::Digging,
+			15 => Pose::Sliding,
+			16 => Pose::Shooting,
+			17 => Pose::Inhaling,
+			_ => return Err(SerializingErr::UniqueFailure(format!("Unknown pose {}", value))),
+		};
+
+		Ok(pose)
+	}
+}
+
+fn write_text(serializer: &mut McSerializer, text: &TextComponent) -> Result<(), SerializingErr> {
+	text.to_nbt().map_err(|e| SerializingErr::UniqueFailure(e.to_string()))?.serialize_network(serializer)
+}
+
+fn read_text(deserializer: &mut McDeserializer) -> Result<TextComponent, SerializingErr> {
+	TextComponent::from_nbt(NbtTag::from_network(deserializer)?).map_err(|e| SerializingErr::UniqueFailure(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use uuid::Uuid;
+
+	use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
+	use crate::protocol_types::datatypes::chat::TextComponent;
+	use crate::protocol_types::datatypes::entity_metadata::{Direction, EntityMetadata, MetadataValue, Pose};
+	use crate::protocol_types::datatypes::identifier::Identifier;
+	use crate::protocol_types::datatypes::position::Position;
+	use crate::protocol_types::datatypes::slot::Slot;
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	#[test]
+	fn test_metadata_encoding() {
+		let metadata = EntityMetadata::new()
+			.with(0, MetadataValue::Byte(0x02))
+			.with(6, MetadataValue::Pose(Pose::Sneaking));
+
+		let mut serializer = McSerializer::new();
+		metadata.mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, vec![0, 0, 0x02, 6, 21, 5, 0xFF]);
+
+		let mut serializer = McSerializer::new();
+		EntityMetadata::new().mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, vec![0xFF]);
+	}
+
+	#[test]
+	fn test_metadata_round_trip() {
+		let metadata = EntityMetadata::new()
+			.with(0, MetadataValue::Byte(-1))
+			.with(1, MetadataValue::VarInt(VarInt(300)))
+			.with(2, MetadataValue::OptionalTextComponent(Some(TextComponent::new("Named").color("red"))))
+			.with(3, MetadataValue::OptionalTextComponent(None))
+			.with(4, MetadataValue::Slot(Slot::new(5, 1)))
+			.with(5, MetadataValue::Rotations(1.0, 2.0, 3.0))
+			.with(6, MetadataValue::OptionalPosition(Some(Position::new(1, 2, 3))))
+			.with(7, MetadataValue::Direction(Direction::West))
+			.with(8, MetadataValue::OptionalUuid(Some(Uuid::from_u128(42))))
+			.with(9, MetadataValue::OptionalBlockState(None))
+			.with(10, MetadataValue::OptionalVarInt(Some(VarInt(0))))
+			.with(11, MetadataValue::OptionalGlobalPosition(Some((Identifier::minecraft("overworld").unwrap(), Position::new(0, 64, 0)))))
+			.with(12, MetadataValue::PaintingVariant(VarInt(3)))
+			.with(13, MetadataValue::Quaternion(0.0, 0.0, 0.0, 1.0));
+
+		let mut serializer = McSerializer::new();
+		metadata.mc_serialize(&mut serializer).unwrap();
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		let deserialized = EntityMetadata::mc_deserialize(&mut deserializer).unwrap();
+		assert_eq!(deserialized, metadata);
+		assert!(deserializer.is_at_end());
+		assert_eq!(deserialized.get(7), Some(&MetadataValue::Direction(Direction::West)));
+	}
+
+	#[test]
+	fn test_metadata_errors() {
+		let mut deserializer = McDeserializer::new(&[0, 17, 1, 0xFF]); // particle
+		assert!(EntityMetadata::mc_deserialize(&mut deserializer).is_err());
+
+		let mut deserializer = McDeserializer::new(&[0, 0, 1]); // missing end
+		assert!(EntityMetadata::mc_deserialize(&mut deserializer).is_err());
+	}
+}
@@ -0,0 +1,36 @@
+//! The game modes a player can be in, as sent in `LoginPlay`, `PlayerInfoUpdate` and the `GameEvent`
+//! that changes it.
+
+use crate::protocol::serialization::serializer_error::SerializingErr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GameMode {
+	#[default]
+	Survival = 0,
+	Creative = 1,
+	Adventure = 2,
+	Spectator = 3,
+}
+
+impl GameMode {
+	/// The id of an optional game mode, where `-1` is none, as used for the previous game mode
+	pub fn optional_id(mode: Option<GameMode>) -> i8 {
+		mode.map_or(-1, |m| m as i8)
+	}
+}
+
+impl TryFrom<i32> for GameMode {
+	type Error = SerializingErr;
+
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		let mode = match value {
+			0 => GameMode::Survival,
+			1 => GameMode::Creative,
+			2 => GameMode::Adventure,
+			3 => GameMode::Spectator,
+			_ => return Err(SerializingErr::UniqueFailure(format!("Unknown game mode {}", value))),
+		};
+
+		Ok(mode)
+	}
+}
@@ -0,0 +1,101 @@
+//! A player's profile: their UUID, name and properties such as their skin. The same profile is sent in
+//! `LoginSuccess`, forwarded by proxies and returned by Mojang's session server, where the UUID is written
+//! without dashes.
+
+use serde::{Deserialize, Serialize};
+use sandstone_derive::{McDeserialize, McSerialize};
+use uuid::Uuid;
+
+use crate::protocol::login::offline_uuid;
+
+/// The name of the property that holds a player's skin and cape
+pub const TEXTURES_PROPERTY: &str = "textures";
+
+/// A player's profile, see the [module docs](self)
+#[derive(McSerialize, McDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameProfile {
+	#[serde(rename = "id", with = "uuid::serde::simple")]
+	pub uuid: Uuid,
+	pub name: String,
+	#[serde(default)]
+	pub properties: Vec<ProfileProperty>,
+}
+
+impl GameProfile {
+	pub fn new<S: Into<String>>(uuid: Uuid, name: S) -> Self {
+		Self {
+			uuid,
+			name: name.into(),
+			properties: vec![],
+		}
+	}
+
+	/// The profile that an offline-mode server gives a player, with the same UUID as vanilla and no
+	/// properties
+	pub fn offline<S: Into<String>>(name: S) -> Self {
+		let name = name.into();
+		Self::new(offline_uuid(&name), name)
+	}
+
+	/// Get a property by its name
+	pub fn property(&self, name: &str) -> Option<&ProfileProperty> {
+		self.properties.iter().find(|p| p.name == name)
+	}
+
+	/// The player's skin and cape, if they have any
+	pub fn textures(&self) -> Option<&ProfileProperty> {
+		self.property(TEXTURES_PROPERTY)
+	}
+}
+
+/// A property of a player's profile, such as their skin. The value is usually base64 and the signature is
+/// only present if the property is signed.
+#[derive(McSerialize, McDeserialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProfileProperty {
+	pub name: String,
+	pub value: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub signature: Option<String>,
+}
+
+impl ProfileProperty {
+	pub fn new<N: Into<String>, V: Into<String>>(name: N, value: V, signature: Option<String>) -> Self {
+		Self {
+			name: name.into(),
+			value: value.into(),
+			signature,
+		}
+	}
+
+	pub fn is_signed(&self) -> bool {
+		self.signature.is_some()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use uuid::Uuid;
+
+	use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
+	use crate::protocol_types::datatypes::game_profile::{GameProfile, ProfileProperty};
+
+	#[test]
+	fn test_game_profile() {
+		let mut profile = GameProfile::new(Uuid::from_u128(0x1234), "dec4234");
+		profile.properties.push(ProfileProperty::new("textures", "abc", Some("def".to_string())));
+		profile.properties.push(ProfileProperty::new("other", "ghi", None));
+
+		let mut serializer = McSerializer::new();
+		profile.mc_serialize(&mut serializer).unwrap();
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(GameProfile::mc_deserialize(&mut deserializer).unwrap(), profile);
+
+		let json = serde_json::to_string(&profile).unwrap();
+		assert!(json.contains(r#""id":"00000000000000000000000000001234""#));
+		assert!(!json.contains(r#""signature":null"#));
+		assert_eq!(serde_json::from_str::<GameProfile>(&json).unwrap(), profile);
+
+		assert!(profile.textures().unwrap().is_signed());
+		assert_eq!(GameProfile::offline("Notch").uuid.to_string(), "b50ad385-829d-3141-a216-7e7d7539ba7f");
+	}
+}
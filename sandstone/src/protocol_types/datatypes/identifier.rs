@@ -0,0 +1,168 @@
+//! Identifiers are namespaced keys such as `minecraft:stone`, used to name registries, plugin channels,
+//! sounds and many other things in the protocol.
+//! See more details here: https://wiki.vg/Identifier
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+
+/// The namespace used when an identifier doesn't specify one
+pub const DEFAULT_NAMESPACE: &str = "minecraft";
+
+/// The longest an identifier can be, since it is sent as a protocol string
+const MAX_LENGTH: usize = 32767;
+
+/// A namespaced key in the form `namespace:path`. The namespace may only contain `a-z`, `0-9`, `.`, `-`
+/// and `_`, and the path may additionally contain `/`. If no namespace is given, `minecraft` is used.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Identifier {
+	namespace: String,
+	path: String,
+}
+
+impl Identifier {
+	/// Create an identifier from its parts, checking that both only use valid characters
+	pub fn new<N: Into<String>, P: Into<String>>(namespace: N, path: P) -> Result<Self, SerializingErr> {
+		let namespace = namespace.into();
+		let path = path.into();
+
+		if namespace.is_empty() || !namespace.chars().all(is_valid_namespace_char) {
+			return Err(SerializingErr::InvalidIdentifier(format!("Invalid namespace '{}'", namespace)));
+		}
+
+		if path.is_empty() || !path.chars().all(is_valid_path_char) {
+			return Err(SerializingErr::InvalidIdentifier(format!("Invalid path '{}'", path)));
+		}
+
+		if namespace.len() + path.len() + 1 > MAX_LENGTH {
+			return Err(SerializingErr::InvalidIdentifier("Identifier is too long".to_string()));
+		}
+
+		Ok(Self {
+			namespace,
+			path,
+		})
+	}
+
+	/// Create an identifier in the `minecraft` namespace
+	pub fn minecraft<P: Into<String>>(path: P) -> Result<Self, SerializingErr> {
+		Identifier::new(DEFAULT_NAMESPACE, path)
+	}
+
+	pub fn namespace(&self) -> &str {
+		&self.namespace
+	}
+
+	pub fn path(&self) -> &str {
+		&self.path
+	}
+}
+
+fn is_valid_namespace_char(c: char) -> bool {
+	matches!(c, 'a'..='z' | '0'..='9' | '.' | '-' | '_')
+}
+
+fn is_valid_path_char(c: char) -> bool {
+	is_valid_namespace_char(c) || c == '/'
+}
+
+impl FromStr for Identifier {
+	type Err = SerializingErr;
+
+	/// Parse `namespace:path`, or just `path` for the `minecraft` namespace
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.split_once(':') {
+			Some(("", path)) => Identifier::minecraft(path), // ":path" is accepted by vanilla as well
+			Some((namespace, path)) => Identifier::new(namespace, path),
+			None => Identifier::minecraft(s),
+		}
+	}
+}
+
+impl TryFrom<String> for Identifier {
+	type Error = SerializingErr;
+
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		value.parse()
+	}
+}
+
+impl TryFrom<&str> for Identifier {
+	type Error = SerializingErr;
+
+	fn try_from(value: &str) -> Result<Self, Self::Error> {
+		value.parse()
+	}
+}
+
+impl From<Identifier> for String {
+	fn from(value: Identifier) -> Self {
+		value.to_string()
+	}
+}
+
+impl Display for Identifier {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}:{}", self.namespace, self.path)
+	}
+}
+
+impl McSerialize for Identifier {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.to_string().mc_serialize(serializer)
+	}
+}
+
+impl McDeserialize for Identifier {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		String::mc_deserialize(deserializer)?.parse()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
+	use crate::protocol_types::datatypes::identifier::Identifier;
+
+	#[test]
+	fn test_identifier_parsing() {
+		let id: Identifier = "minecraft:stone".parse().unwrap();
+		assert_eq!(id.namespace(), "minecraft");
+		assert_eq!(id.path(), "stone");
+
+		assert_eq!("stone".parse::<Identifier>().unwrap(), id);
+		assert_eq!(":stone".parse::<Identifier>().unwrap(), id);
+		assert_eq!("my_mod:blocks/ore.v2".parse::<Identifier>().unwrap().to_string(), "my_mod:blocks/ore.v2");
+
+		assert!("Minecraft:stone".parse::<Identifier>().is_err());
+		assert!("minecraft:stone block".parse::<Identifier>().is_err());
+		assert!("my/mod:stone".parse::<Identifier>().is_err());
+		assert!("minecraft:".parse::<Identifier>().is_err());
+		assert!("a:b:c".parse::<Identifier>().is_err());
+	}
+
+	#[test]
+	fn test_identifier_serialization() {
+		let id = Identifier::new("sandstone", "channel").unwrap();
+
+		let mut serializer = McSerializer::new();
+		id.mc_serialize(&mut serializer).unwrap();
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(Identifier::mc_deserialize(&mut deserializer).unwrap(), id);
+
+		let mut serializer = McSerializer::new();
+		"NOT VALID".to_string().mc_serialize(&mut serializer).unwrap();
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert!(Identifier::mc_deserialize(&mut deserializer).is_err());
+
+		assert_eq!(serde_json::to_string(&id).unwrap(), "\"sandstone:channel\"");
+		assert_eq!(serde_json::from_str::<Identifier>("\"sandstone:channel\"").unwrap(), id);
+		assert!(serde_json::from_str::<Identifier>("\"Bad\"").is_err());
+	}
+}
@@ -1,3 +1,13 @@
 pub mod var_types;
 pub mod chat;
-pub mod nbt;
\ No newline at end of file
+pub mod nbt;
+pub mod position;
+pub mod identifier;
+pub mod bitset;
+pub mod angle;
+pub mod slot;
+pub mod entity_metadata;
+pub mod wrappers;pub mod bounded_string;
+pub mod game_profile;
+pub mod game_mode;
+pub mod paletted_container;
\ No newline at end of file
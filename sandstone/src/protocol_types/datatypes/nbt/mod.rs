@@ -1,8 +1,13 @@
 pub mod nbt;
+pub mod snbt;
+#[cfg(test)]
 mod nbt_testing;
+#[cfg(test)]
 mod snbt_testing;
 pub mod nbt_error;
 pub mod nbt_reader;
+pub mod nbt_serde;
+pub mod nbt_json;
 
 #[macro_use]
 mod macros {
@@ -11,9 +16,9 @@ mod macros {
 	macro_rules! primvalue_nbtvalue {
         ($(($t: ty, $name: ident)),*) => {
             $(
-            impl Into<NbtTag> for $t {
-                fn into(self) -> NbtTag {
-                    NbtTag::$name(self)
+            impl From<$t> for NbtTag {
+                fn from(value: $t) -> Self {
+                    NbtTag::$name(value)
                 }
             }
         
@@ -36,29 +41,60 @@ mod macros {
                 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
                 pub struct $fancyname {
                     pub list: Vec<$t>,
-                    pub count: u32, // iterator
                 }
                 
                 impl $fancyname {
                     pub fn new(list: Vec<$t>) -> Self {
                         Self {
                             list,
-                            count: 0,
                         }
                     }
+                    
+                    #[inline]
+                    pub fn iter(&self) -> std::slice::Iter<'_, $t> {
+                        self.list.iter()
+                    }
+                    
+                    #[inline]
+                    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, $t> {
+                        self.list.iter_mut()
+                    }
+                    
+                    #[inline]
+                    pub fn len(&self) -> usize {
+                        self.list.len()
+                    }
+                    
+                    #[inline]
+                    pub fn is_empty(&self) -> bool {
+                        self.list.is_empty()
+                    }
                 }
             
-                impl Iterator for $fancyname {
+                impl IntoIterator for $fancyname {
                     type Item = $t;
+                    type IntoIter = std::vec::IntoIter<$t>;
                 
-                    fn next(&mut self) -> Option<Self::Item> {
-                        if self.count < self.list.len() as u32 {
-                            let tag = self.list[self.count as usize];
-                            self.count += 1;
-                            Some(tag)
-                        } else {
-                            None
-                        }
+                    fn into_iter(self) -> Self::IntoIter {
+                        self.list.into_iter()
+                    }
+                }
+                
+                impl<'a> IntoIterator for &'a $fancyname {
+                    type Item = &'a $t;
+                    type IntoIter = std::slice::Iter<'a, $t>;
+                
+                    fn into_iter(self) -> Self::IntoIter {
+                        self.list.iter()
+                    }
+                }
+                
+                impl<'a> IntoIterator for &'a mut $fancyname {
+                    type Item = &'a mut $t;
+                    type IntoIter = std::slice::IterMut<'a, $t>;
+                
+                    fn into_iter(self) -> Self::IntoIter {
+                        self.list.iter_mut()
                     }
                 }
             
@@ -91,9 +127,15 @@ mod macros {
                     }
                 }
             
-                impl Into<NbtTag> for $fancyname {
-                    fn into(self) -> NbtTag {
-                        NbtTag::$name(self)
+                impl From<$fancyname> for NbtTag {
+                    fn from(value: $fancyname) -> Self {
+                        NbtTag::$name(value)
+                    }
+                }
+                
+                impl From<Vec<$t>> for NbtTag {
+                    fn from(value: Vec<$t>) -> Self {
+                        NbtTag::$name($fancyname::new(value))
                     }
                 }
             
@@ -84,7 +84,57 @@ impl NbtTag {
 		}
 	}
 	
+	/// Deserialize the payload of a tag of the given type with the default [NbtLimits]
 	pub fn deserialize_specific<'a>(deserializer: &mut McDeserializer, ty: u8) -> SerializingResult<'a, Self> {
+		let mut budget = NbtBudget::new(deserializer, NbtLimits::default());
+		NbtTag::deserialize_budgeted(deserializer, ty, &mut budget)
+	}
+
+	/// Serialize this tag as a nameless root tag of any type, made up of the type id and then the payload.
+	/// This is used by the network since 1.20.3, such as for text components.
+	pub fn serialize_network(&self, serializer: &mut McSerializer) -> Result<(), SerializingErr> {
+		serializer.serialize_u8(self.get_type_id());
+		self.mc_serialize(serializer)?;
+		Ok(())
+	}
+
+	/// Deserialize a nameless root tag of any type, see [NbtTag::serialize_network]
+	pub fn from_network<'a>(deserializer: &mut McDeserializer) -> SerializingResult<'a, Self> {
+		let ty = u8::mc_deserialize(deserializer)?;
+
+		if ty == 0 {
+			return Ok(NbtTag::End);
+		}
+
+		NbtTag::deserialize_specific(deserializer, ty)
+	}
+
+	fn deserialize_budgeted<'a>(deserializer: &mut McDeserializer, ty: u8, budget: &mut NbtBudget) -> SerializingResult<'a, Self> {
+		match ty {
+			9 => { // List
+				budget.enter()?;
+				let list = NbtList::deserialize_budgeted(deserializer, budget)?;
+				budget.exit();
+
+				Ok(NbtTag::List(list))
+			},
+
+			10 => { // compound
+				budget.enter()?;
+				let compound = NbtCompound::deserialize_payload(deserializer, budget)?;
+				budget.exit();
+
+				Ok(NbtTag::Compound(compound))
+			}
+
+			_ => NbtTag::deserialize_leaf(deserializer, ty, budget),
+		}
+	}
+
+	/// Deserialize a tag that can't hold other tags. This is kept separate from the list and compound
+	/// cases so that each level of nesting uses as little of the stack as possible.
+	#[inline(never)]
+	fn deserialize_leaf<'a>(deserializer: &mut McDeserializer, ty: u8, budget: &NbtBudget) -> SerializingResult<'a, Self> {
 		match ty {
 			// Primitives
 			0 => Ok(NbtTag::End),
@@ -96,30 +146,22 @@ impl NbtTag {
 			6 => Ok(NbtTag::Double(f64::mc_deserialize(deserializer)?)),
 
 			8 => { // String
-				let len = u16::mc_deserialize(deserializer)?;
-				let bytes = deserializer.slice(len as usize);
-
-				Ok(NbtTag::String(String::from_utf8_lossy(bytes).to_string()))
+				Ok(NbtTag::String(read_nbt_string(deserializer)?))
 			},
 
 			7 => { // Byte array
+				budget.check_array(deserializer, 1)?;
 				Ok(NbtTag::ByteArray(NbtByteArray::mc_deserialize(deserializer)?))
 			},
 			11 => { // Int Array
+				budget.check_array(deserializer, 4)?;
 				Ok(NbtTag::IntArray(NbtIntArray::mc_deserialize(deserializer)?))
 			},
-			12 => { // Int Array
+			12 => { // Long Array
+				budget.check_array(deserializer, 8)?;
 				Ok(NbtTag::LongArray(NbtLongArray::mc_deserialize(deserializer)?))
 			},
 
-			9 => { // List
-				Ok(NbtTag::List(NbtList::mc_deserialize(deserializer)?))
-			},
-
-			10 => { // compound
-				Ok(NbtTag::Compound(NbtCompound::mc_deserialize(deserializer)?))
-			}
-
 			_ => Err(SerializingErr::UniqueFailure("Could not identify tag type".to_string())),
 		}
 	}
@@ -165,8 +207,8 @@ impl McSerialize for NbtTag {
 			NbtTag::List(b) => {
 				b.mc_serialize(serializer)?
 			}
-			NbtTag::Compound(c) => {
-				c.mc_serialize(serializer)?
+			NbtTag::Compound(c) => { // nested compounds have no name of their own, it is stored by the parent
+				c.serialize_payload(serializer)?
 			}
 		}
 
@@ -175,19 +217,62 @@ impl McSerialize for NbtTag {
 }
 
 impl McDeserialize for NbtTag {
+	/// Deserialize a root tag, which is made up of the type id, the name of the tag and then the payload.
+	/// If the root tag is a compound, the name is kept as the root name of the compound.
 	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, NbtTag> {
 		let ty = u8::mc_deserialize(deserializer)?;
 
-		NbtTag::deserialize_specific(deserializer, ty)
+		if ty == 0 { // the End tag never has a name
+			return Ok(NbtTag::End);
+		}
+
+		let name = read_nbt_string(deserializer)?;
+		let mut budget = NbtBudget::new(deserializer, NbtLimits::default());
+		let tag = NbtTag::deserialize_budgeted(deserializer, ty, &mut budget)?;
+
+		if let NbtTag::Compound(mut compound) = tag {
+			compound.root_name = Some(name);
+			return Ok(NbtTag::Compound(compound));
+		}
+
+		Ok(tag)
 	}
 }
 
+/// Read a string as it is represented in NBT, which is prefixed by its length as an u16 rather than a VarInt
+fn read_nbt_string(deserializer: &mut McDeserializer) -> Result<String, SerializingErr> {
+	let length = u16::mc_deserialize(deserializer)?;
+	let bytes = deserializer.slice_option(length as usize).ok_or(SerializingErr::InputEnded)?;
+
+	Ok(String::from_utf8_lossy(bytes).to_string())
+}
+
 impl From<&str> for NbtTag {
 	fn from(value: &str) -> Self {
 		NbtTag::String(value.to_string())
 	}
 }
 
+impl From<String> for NbtTag {
+	fn from(value: String) -> Self {
+		NbtTag::String(value)
+	}
+}
+
+/// Booleans are stored as a Byte of 0 or 1
+impl From<bool> for NbtTag {
+	fn from(value: bool) -> Self {
+		NbtTag::Byte(value as i8)
+	}
+}
+
+/// Bytes are stored as a ByteArray, reinterpreting each as signed
+impl From<&[u8]> for NbtTag {
+	fn from(value: &[u8]) -> Self {
+		NbtTag::ByteArray(NbtByteArray::new(value.iter().map(|b| *b as i8).collect()))
+	}
+}
+
 primvalue_nbtvalue!(
     (i8, Byte),
     (i16, Short),
@@ -203,6 +288,128 @@ list_nbtvalue!(
     (i64, LongArray, NbtLongArray, 12)
 );
 
+/// How a root compound is framed. Files and the network before 1.20.2 name the root compound, while
+/// the network since 1.20.2 leaves the name out entirely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NbtFlavor {
+	/// Type id, name and then the payload
+	Named,
+	/// Type id and then the payload, used by the network since 1.20.2
+	Network,
+}
+
+/// Limits applied while deserializing NBT, so that hostile input can't overflow the stack or use up
+/// all of the memory. The defaults match the limits vanilla uses for NBT sent over the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NbtLimits {
+	/// How deeply compounds and lists can be nested, the root compound counts as one level
+	pub max_depth: usize,
+	/// The most bytes that can be read for a single root tag
+	pub max_size: usize,
+	/// The most entries that can be read across all compounds and lists in a single root tag. Elements
+	/// of the array types are covered by `max_size` instead.
+	pub max_elements: usize,
+}
+
+impl Default for NbtLimits {
+	fn default() -> Self {
+		Self {
+			max_depth: 512,
+			max_size: 2 * 1024 * 1024,
+			max_elements: 512 * 1024,
+		}
+	}
+}
+
+/// Keeps track of how much of the [NbtLimits] has been used while deserializing a single root tag
+struct NbtBudget {
+	limits: NbtLimits,
+	start: usize,
+	depth: usize,
+	elements: usize,
+}
+
+impl NbtBudget {
+	fn new(deserializer: &McDeserializer, limits: NbtLimits) -> Self {
+		Self {
+			limits,
+			start: deserializer.index,
+			depth: 0,
+			elements: 0,
+		}
+	}
+
+	fn enter(&mut self) -> Result<(), SerializingErr> {
+		self.depth += 1;
+
+		if self.depth > self.limits.max_depth {
+			return Err(SerializingErr::NbtLimitExceeded(format!("Nested deeper than {} levels", self.limits.max_depth)));
+		}
+
+		Ok(())
+	}
+
+	fn exit(&mut self) {
+		self.depth -= 1;
+	}
+
+	fn add_element(&mut self) -> Result<(), SerializingErr> {
+		self.elements += 1;
+
+		if self.elements > self.limits.max_elements {
+			return Err(SerializingErr::NbtLimitExceeded(format!("More than {} elements", self.limits.max_elements)));
+		}
+
+		Ok(())
+	}
+
+	/// Check that the bytes read so far plus `upcoming` more bytes stay within the size limit
+	fn check_size(&self, deserializer: &McDeserializer, upcoming: usize) -> Result<(), SerializingErr> {
+		let size = (deserializer.index - self.start).saturating_add(upcoming);
+
+		if size > self.limits.max_size {
+			return Err(SerializingErr::NbtLimitExceeded(format!("Larger than {} bytes", self.limits.max_size)));
+		}
+
+		Ok(())
+	}
+
+	/// Check the length prefix of an array before any of it is read
+	fn check_array(&self, deserializer: &McDeserializer, element_size: usize) -> Result<(), SerializingErr> {
+		let bytes = deserializer.data.get(deserializer.index..deserializer.index + 4).ok_or(SerializingErr::InputEnded)?;
+		let length = i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]).max(0) as usize;
+
+		self.check_size(deserializer, 4 + length.saturating_mul(element_size))
+	}
+}
+
+/// Generates typed getters on [NbtCompound], which fail if the entry is missing or of another type.
+/// The `ref` form returns a reference instead of a copy.
+macro_rules! compound_getters {
+	($(($name: ident, $variant: ident, $t: ty)),*) => {
+		$(
+			#[doc = concat!("Get a ", stringify!($variant), " with the given name")]
+			pub fn $name(&self, name: &str) -> Result<$t, NbtError> {
+				match self.get_or_missing(name)? {
+					NbtTag::$variant(v) => Ok(*v),
+					tag => Err(NbtError::WrongType(name.to_string(), tag.get_name()))
+				}
+			}
+		)*
+	};
+	(ref $(($name: ident, $variant: ident, $t: ty)),*) => {
+		$(
+			#[doc = concat!("Get a reference to the ", stringify!($variant), " with the given name")]
+			pub fn $name(&self, name: &str) -> Result<&$t, NbtError> {
+				match self.get_or_missing(name)? {
+					NbtTag::$variant(v) => Ok(v),
+					tag => Err(NbtError::WrongType(name.to_string(), tag.get_name()))
+				}
+			}
+		)*
+	};
+}
+
 /// Effectively a map of NbtTagLegacys
 ///
 /// Order is not needed according to NBT specification, but I do it anyways
@@ -239,43 +446,154 @@ impl NbtCompound {
 	pub fn remove<T: Into<String>>(&mut self, name: T) {
 		self.map.remove(&name.into());
 	}
+
+	/// Get the tag with the given name, if there is one
+	#[inline]
+	pub fn get(&self, name: &str) -> Option<&NbtTag> {
+		self.map.get(name)
+	}
+
+	/// Get a mutable reference to the tag with the given name, if there is one
+	#[inline]
+	pub fn get_mut(&mut self, name: &str) -> Option<&mut NbtTag> {
+		self.map.get_mut(name)
+	}
+
+	#[inline]
+	pub fn contains_key(&self, name: &str) -> bool {
+		self.map.contains_key(name)
+	}
+
+	/// The number of entries in this compound, not counting any nested entries
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.map.len()
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.map.is_empty()
+	}
+
+	/// The names of all entries in this compound, in no particular order
+	pub fn keys(&self) -> impl Iterator<Item = &String> {
+		self.map.keys()
+	}
+
+	fn get_or_missing(&self, name: &str) -> Result<&NbtTag, NbtError> {
+		self.map.get(name).ok_or_else(|| NbtError::MissingKey(name.to_string()))
+	}
+
+	/// Get a Byte as a boolean, where anything other than 0 is true
+	pub fn get_bool(&self, name: &str) -> Result<bool, NbtError> {
+		Ok(self.get_byte(name)? != 0)
+	}
+
+	compound_getters!(
+		(get_byte, Byte, i8),
+		(get_short, Short, i16),
+		(get_int, Int, i32),
+		(get_long, Long, i64),
+		(get_float, Float, f32),
+		(get_double, Double, f64)
+	);
+
+	compound_getters!(
+		ref (get_string, String, str),
+		(get_byte_array, ByteArray, NbtByteArray),
+		(get_int_array, IntArray, NbtIntArray),
+		(get_long_array, LongArray, NbtLongArray),
+		(get_list, List, NbtList),
+		(get_compound, Compound, NbtCompound)
+	);
 	
+	/// Deserialize a compound sent over the network since 1.20.2, where the root compound has no name.
 	pub fn from_network<'a>(deserializer: &mut McDeserializer) -> SerializingResult<'a, NbtCompound> {
+		NbtCompound::deserialize_flavor(deserializer, NbtFlavor::Network)
+	}
+
+	/// Serialize this compound as a root tag in the given flavor. A compound without a root name is
+	/// written with an empty name in the [NbtFlavor::Named] flavor, and the root name is always left out
+	/// in the [NbtFlavor::Network] flavor.
+	pub fn serialize_flavor(&self, serializer: &mut McSerializer, flavor: NbtFlavor) -> Result<(), SerializingErr> {
+		10u8.mc_serialize(serializer)?;
+
+		if flavor == NbtFlavor::Named {
+			let root_name = self.root_name.as_deref().unwrap_or("");
+			(root_name.len() as u16).mc_serialize(serializer)?;
+			serializer.serialize_bytes(root_name.as_bytes());
+		}
+
+		self.serialize_payload(serializer)
+	}
+
+	/// Deserialize a root compound, including its type id, in the given flavor. The root name is only
+	/// present in the result for the [NbtFlavor::Named] flavor.
+	pub fn deserialize_flavor<'a>(deserializer: &mut McDeserializer, flavor: NbtFlavor) -> SerializingResult<'a, NbtCompound> {
+		NbtCompound::deserialize_with_limits(deserializer, flavor, NbtLimits::default())
+	}
+
+	/// Deserialize a root compound like [NbtCompound::deserialize_flavor], failing with
+	/// [SerializingErr::NbtLimitExceeded] as soon as the input goes over any of the given limits.
+	pub fn deserialize_with_limits<'a>(deserializer: &mut McDeserializer, flavor: NbtFlavor, limits: NbtLimits) -> SerializingResult<'a, NbtCompound> {
+		let mut budget = NbtBudget::new(deserializer, limits);
 		let t = u8::mc_deserialize(deserializer)?;
-		
+
 		if t != 10 {
 			return Err(SerializingErr::UniqueFailure("Expected compound tag".to_string()));
 		}
 
-		let mut compound = NbtCompound::new::<String>(None);
-
-		loop {
-			let tag = deserializer.slice(1)[0];
-			let name_length = i16::mc_deserialize(deserializer).unwrap();
-			let name = String::from_utf8_lossy(deserializer.slice(name_length as usize)).to_string();
-
-			let tag = NbtTag::deserialize_specific(deserializer, tag).unwrap();
+		match flavor {
+			NbtFlavor::Named => {
+				let name = read_nbt_string(deserializer)?;
+				budget.enter()?;
+				let mut compound = NbtCompound::deserialize_payload(deserializer, &mut budget)?;
+				compound.root_name = Some(name);
 
-			if tag == NbtTag::End {
-				break;
+				Ok(compound)
+			}
+			NbtFlavor::Network => {
+				budget.enter()?;
+				NbtCompound::deserialize_payload(deserializer, &mut budget)
 			}
-
-			compound.add(name, tag);
 		}
-
-		return Ok(compound);
 	}
 
-	fn serialize_tags(&self, serializer: &mut McSerializer) -> Result<(), SerializingErr> {
+	/// Serialize the entries of this compound followed by an End tag. This does not include the type id
+	/// or the name of the compound itself.
+	pub(crate) fn serialize_payload(&self, serializer: &mut McSerializer) -> Result<(), SerializingErr> {
 		for (name, tag) in self.map.iter() {
 			serializer.serialize_u8(tag.get_type_id());
-			(name.as_bytes().len() as u16).mc_serialize(serializer)?;
+			(name.len() as u16).mc_serialize(serializer)?;
 			serializer.serialize_bytes(name.as_bytes());
 			tag.mc_serialize(serializer)?;
 		}
 		serializer.serialize_u8(0); // end tag
 		Ok(())
 	}
+
+	/// Deserialize the entries of a compound up to and including its End tag. The resulting compound has
+	/// no root name, since nested compounds are named by their parent.
+	fn deserialize_payload<'a>(deserializer: &mut McDeserializer, budget: &mut NbtBudget) -> SerializingResult<'a, NbtCompound> {
+		let mut compound = NbtCompound::new::<String>(None);
+
+		loop {
+			let tag = deserializer.pop().ok_or_else(|| SerializingErr::UniqueFailure(NbtError::MissingEndTag.to_string()))?;
+
+			if tag == 0 { // End tag
+				break;
+			}
+
+			budget.add_element()?;
+			let name = read_nbt_string(deserializer)?;
+			let tag = NbtTag::deserialize_budgeted(deserializer, tag, budget)?;
+			budget.check_size(deserializer, 0)?;
+
+			compound.add(name, tag);
+		}
+
+		Ok(compound)
+	}
 }
 
 impl Index<&str> for NbtCompound {
@@ -287,49 +605,31 @@ impl Index<&str> for NbtCompound {
 }
 
 impl McSerialize for NbtCompound {
+	/// Serialize this compound as a root tag. Nested compounds are serialized through [NbtTag] instead.
+	/// The flavor is chosen by whether there is a root name, use [NbtCompound::serialize_flavor] to pick it explicitly.
 	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
-		if serializer.get_last().is_none() { // only serialize tag type if its the main compound
-			10u8.mc_serialize(serializer)?; // TODO: needs more investigation
-		}
-		
 		// only serialize root name if present (non-network compound tag or pre 1.20.2)
-		if let Some(root_name) = &self.root_name {
-			(root_name.len() as u16).mc_serialize(serializer)?;
-			serializer.serialize_bytes(root_name.as_bytes());
-		}
+		let flavor = if self.root_name.is_some() {
+			NbtFlavor::Named
+		} else {
+			NbtFlavor::Network
+		};
 
-		self.serialize_tags(serializer)?;
+		self.serialize_flavor(serializer, flavor)?;
 		Ok(())
 	}
 }
 
 impl McDeserialize for NbtCompound {
+	/// Deserialize a named root compound, including its type id.
 	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> where Self: Sized {
-		let name_length = u16::mc_deserialize(deserializer)?;
-		let name = String::from_utf8_lossy(deserializer.slice(name_length as usize)).to_string();
-		let mut compound = NbtCompound::new(Some(name));
-		
-		loop {
-			let tag = deserializer.pop();
-			
-			if tag.is_none() || tag.unwrap() == 0 { // END Tag
-				break;
-			}
-			
-			let name_length = u16::mc_deserialize(deserializer)?;
-			let name = String::from_utf8_lossy(deserializer.slice(name_length as usize)).to_string();
-			
-			let tag = NbtTag::deserialize_specific(deserializer, tag.unwrap())?;
-			compound.add(name, tag);
-		}
-		
-		return Ok(compound);
+		NbtCompound::deserialize_flavor(deserializer, NbtFlavor::Named)
 	}
 }
 
-impl Into<NbtTag> for NbtCompound {
-	fn into(self) -> NbtTag {
-		NbtTag::Compound(self)
+impl From<NbtCompound> for NbtTag {
+	fn from(value: NbtCompound) -> Self {
+		NbtTag::Compound(value)
 	}
 }
 
@@ -355,7 +655,6 @@ impl Eq for NbtCompound {}
 pub struct NbtList {
 	pub type_id: u8,
 	pub list: Vec<NbtTag>,
-	count: u32, // used for iterator
 }
 
 impl NbtList {
@@ -363,7 +662,6 @@ impl NbtList {
 		Self {
 			type_id: 0, // set to END by default
 			list: vec![],
-			count: 0
 		}
 	}
 	
@@ -408,19 +706,53 @@ impl NbtList {
 
 		Ok(())
 	}
+
+	#[inline]
+	pub fn iter(&self) -> std::slice::Iter<'_, NbtTag> {
+		self.list.iter()
+	}
+
+	/// Iterate over mutable references to the tags. Every tag must keep the type of the list.
+	#[inline]
+	pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, NbtTag> {
+		self.list.iter_mut()
+	}
+
+	#[inline]
+	pub fn len(&self) -> usize {
+		self.list.len()
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.list.is_empty()
+	}
 }
 
-impl Iterator for NbtList {
+impl IntoIterator for NbtList {
 	type Item = NbtTag;
+	type IntoIter = std::vec::IntoIter<NbtTag>;
 
-	fn next(&mut self) -> Option<Self::Item> {
-		if self.count < self.list.len() as u32 {
-			let tag = self.list[self.count as usize].clone();
-			self.count += 1;
-			Some(tag)
-		} else {
-			None
-		}
+	fn into_iter(self) -> Self::IntoIter {
+		self.list.into_iter()
+	}
+}
+
+impl<'a> IntoIterator for &'a NbtList {
+	type Item = &'a NbtTag;
+	type IntoIter = std::slice::Iter<'a, NbtTag>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.list.iter()
+	}
+}
+
+impl<'a> IntoIterator for &'a mut NbtList {
+	type Item = &'a mut NbtTag;
+	type IntoIter = std::slice::IterMut<'a, NbtTag>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.list.iter_mut()
 	}
 }
 
@@ -437,6 +769,13 @@ impl McSerialize for NbtList {
 
 impl McDeserialize for NbtList {
 	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, NbtList> {
+		let mut budget = NbtBudget::new(deserializer, NbtLimits::default());
+		NbtList::deserialize_budgeted(deserializer, &mut budget)
+	}
+}
+
+impl NbtList {
+	fn deserialize_budgeted<'a>(deserializer: &mut McDeserializer, budget: &mut NbtBudget) -> SerializingResult<'a, NbtList> {
 		let t = u8::mc_deserialize(deserializer)?;
 		let length = i32::mc_deserialize(deserializer)?;
 
@@ -447,7 +786,9 @@ impl McDeserialize for NbtList {
 		let mut list = NbtList::new();
 
 		for _ in 0..length {
-			let tag = NbtTag::deserialize_specific(deserializer, t)?;
+			budget.add_element()?;
+			let tag = NbtTag::deserialize_budgeted(deserializer, t, budget)?;
+			budget.check_size(deserializer, 0)?;
 
 			if tag.get_type_id() != t {
 				return Err(SerializingErr::UniqueFailure("Type must be the same as the type for the list".to_string()))
@@ -472,8 +813,8 @@ impl From<NbtTag> for NbtList {
 	}
 }
 
-impl Into<NbtTag> for NbtList {
-	fn into(self) -> NbtTag {
-		NbtTag::List(self)
+impl From<NbtList> for NbtTag {
+	fn from(value: NbtList) -> Self {
+		NbtTag::List(value)
 	}
 }
\ No newline at end of file
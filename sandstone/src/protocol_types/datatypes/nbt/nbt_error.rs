@@ -18,4 +18,16 @@ pub enum NbtError {
 	EndTagNotAllowedInList,
 	#[error("Incompatible types")]
 	IncompatibleTypes,
+	#[error("Invalid SNBT: {0}")]
+	InvalidSnbt(String),
+	#[error("IO error: {0}")]
+	IOError(String),
+	#[error("Invalid NBT data: {0}")]
+	InvalidData(String),
+	#[error("Serde error: {0}")]
+	Serde(String),
+	#[error("No entry named {0}")]
+	MissingKey(String),
+	#[error("Entry {0} is a {1}")]
+	WrongType(String, String),
 }
\ No newline at end of file
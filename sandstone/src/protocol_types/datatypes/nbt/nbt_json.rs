@@ -0,0 +1,113 @@
+//! Conversions between NBT and `serde_json::Value`, so NBT can be inspected with regular JSON tooling or
+//! built from config files.
+//!
+//! JSON has fewer types than NBT, so the conversion is lossy in both directions:
+//! - Every number type becomes a JSON number. NaN and infinite floats become `null`.
+//! - Byte, Int and Long arrays become JSON arrays of numbers, the same as a List would.
+//! - The root name of a compound is dropped.
+//!
+//! Going the other way:
+//! - Booleans become a Byte of 0 or 1.
+//! - Integers become an Int if they fit, otherwise a Long. Numbers with a fraction become a Double.
+//! - Arrays always become Lists, never one of the array types. Numbers in an array are widened to the
+//!   largest type among them, so `[1, 5000000000]` is a List of Longs and `[1, 0.5]` a List of Doubles.
+//! - `null` has no NBT equivalent and is an error.
+
+use serde_json::{Map, Number, Value};
+
+use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtList, NbtTag};
+use crate::protocol_types::datatypes::nbt::nbt_error::NbtError;
+
+impl NbtTag {
+	/// Convert this tag into JSON. See the module documentation for what is lost on the way.
+	pub fn to_json(&self) -> Value {
+		match self {
+			NbtTag::End => Value::Null,
+			NbtTag::Byte(b) => Value::from(*b),
+			NbtTag::Short(s) => Value::from(*s),
+			NbtTag::Int(i) => Value::from(*i),
+			NbtTag::Long(l) => Value::from(*l),
+			NbtTag::Float(f) => float_to_json(*f as f64),
+			NbtTag::Double(d) => float_to_json(*d),
+			NbtTag::String(s) => Value::String(s.clone()),
+			NbtTag::ByteArray(a) => Value::Array(a.iter().map(|b| Value::from(*b)).collect()),
+			NbtTag::IntArray(a) => Value::Array(a.iter().map(|i| Value::from(*i)).collect()),
+			NbtTag::LongArray(a) => Value::Array(a.iter().map(|l| Value::from(*l)).collect()),
+			NbtTag::List(list) => Value::Array(list.iter().map(NbtTag::to_json).collect()),
+			NbtTag::Compound(compound) => {
+				let mut map = Map::new();
+
+				for (name, tag) in compound.map.iter() {
+					map.insert(name.clone(), tag.to_json());
+				}
+
+				Value::Object(map)
+			}
+		}
+	}
+
+	/// Build a tag from JSON. See the module documentation for how JSON types are mapped to NBT.
+	pub fn from_json(value: &Value) -> Result<NbtTag, NbtError> {
+		match value {
+			Value::Null => Err(NbtError::InvalidData("null has no NBT equivalent".to_string())),
+			Value::Bool(b) => Ok(NbtTag::Byte(*b as i8)),
+			Value::Number(n) => number_from_json(n),
+			Value::String(s) => Ok(NbtTag::String(s.clone())),
+			Value::Array(array) => {
+				let tags = array.iter().map(NbtTag::from_json).collect::<Result<Vec<NbtTag>, NbtError>>()?;
+
+				Ok(NbtTag::List(NbtList::from_vec(widen_numbers(tags))?))
+			}
+			Value::Object(map) => {
+				let mut compound = NbtCompound::new::<String>(None);
+
+				for (name, value) in map {
+					compound.add(name.as_str(), NbtTag::from_json(value)?);
+				}
+
+				Ok(NbtTag::Compound(compound))
+			}
+		}
+	}
+}
+
+fn float_to_json(f: f64) -> Value {
+	Number::from_f64(f).map(Value::Number).unwrap_or(Value::Null)
+}
+
+fn number_from_json(n: &Number) -> Result<NbtTag, NbtError> {
+	if let Some(i) = n.as_i64() {
+		return Ok(match i32::try_from(i) {
+			Ok(i) => NbtTag::Int(i),
+			Err(_) => NbtTag::Long(i),
+		});
+	}
+
+	if n.is_u64() {
+		return Err(NbtError::InvalidData(format!("{} is too large for a Long", n)));
+	}
+
+	n.as_f64().map(NbtTag::Double).ok_or_else(|| NbtError::InvalidData(format!("Unsupported number {}", n)))
+}
+
+/// Convert every number in a list to the largest number type in it, so that the list has a single type
+fn widen_numbers(tags: Vec<NbtTag>) -> Vec<NbtTag> {
+	if !tags.iter().all(|tag| matches!(tag, NbtTag::Int(_) | NbtTag::Long(_) | NbtTag::Double(_))) {
+		return tags;
+	}
+
+	if tags.iter().any(|tag| matches!(tag, NbtTag::Double(_))) {
+		tags.into_iter().map(|tag| match tag {
+			NbtTag::Int(i) => NbtTag::Double(i as f64),
+			NbtTag::Long(l) => NbtTag::Double(l as f64),
+			tag => tag,
+		}).collect()
+	} else if tags.iter().any(|tag| matches!(tag, NbtTag::Long(_))) {
+		tags.into_iter().map(|tag| match tag {
+			NbtTag::Int(i) => NbtTag::Long(i as i64),
+			tag => tag,
+		}).collect()
+	} else {
+		tags
+	}
+}
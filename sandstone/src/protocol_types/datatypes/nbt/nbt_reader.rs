@@ -0,0 +1,101 @@
+//! Reading and writing NBT files, such as `level.dat`, player data and structure files. Unlike NBT sent
+//! over the network, files are usually compressed with gzip or zlib and always have a named root compound.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use flate2::Compression;
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+
+use crate::protocol::serialization::{McDeserializer, McSerializer};
+use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtFlavor, NbtLimits};
+use crate::protocol_types::datatypes::nbt::nbt_error::NbtError;
+
+/// The compression used for an NBT file. Most files made by the game, like `level.dat`, use gzip.
+/// Chunks inside of region files use zlib.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NbtCompression {
+	Uncompressed,
+	Gzip,
+	Zlib,
+}
+
+impl NbtCompression {
+	/// Guess the compression of NBT data from its first bytes. Uncompressed data always starts with the
+	/// compound type id (10), which can't be mistaken for either header.
+	pub fn detect(data: &[u8]) -> NbtCompression {
+		match data {
+			[0x1F, 0x8B, ..] => NbtCompression::Gzip,
+			[0x78, ..] => NbtCompression::Zlib,
+			_ => NbtCompression::Uncompressed,
+		}
+	}
+}
+
+impl NbtCompound {
+	/// Read a compound from a file, detecting the compression automatically
+	pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<NbtCompound, NbtError> {
+		let data = fs::read(path).map_err(|e| NbtError::IOError(e.to_string()))?;
+
+		NbtCompound::from_bytes(&data, NbtCompression::detect(&data))
+	}
+
+	/// Write this compound to a file with the given compression. The file is created if it does not exist
+	/// and overwritten if it does.
+	pub fn write_to_file<P: AsRef<Path>>(&self, path: P, compression: NbtCompression) -> Result<(), NbtError> {
+		let data = self.to_bytes(compression)?;
+
+		fs::write(path, data).map_err(|e| NbtError::IOError(e.to_string()))
+	}
+
+	/// Read a compound with a named root from bytes with the given compression. Files are not held to the
+	/// size and element limits used for the network, but nesting is still limited to protect the stack.
+	pub fn from_bytes(data: &[u8], compression: NbtCompression) -> Result<NbtCompound, NbtError> {
+		let mut decompressed = vec![];
+
+		let data = match compression {
+			NbtCompression::Uncompressed => data,
+			NbtCompression::Gzip => {
+				GzDecoder::new(data).read_to_end(&mut decompressed).map_err(|e| NbtError::IOError(e.to_string()))?;
+				&decompressed
+			}
+			NbtCompression::Zlib => {
+				ZlibDecoder::new(data).read_to_end(&mut decompressed).map_err(|e| NbtError::IOError(e.to_string()))?;
+				&decompressed
+			}
+		};
+
+		let mut deserializer = McDeserializer::new(data);
+		let limits = NbtLimits {
+			max_size: usize::MAX,
+			max_elements: usize::MAX,
+			..NbtLimits::default()
+		};
+
+		NbtCompound::deserialize_with_limits(&mut deserializer, NbtFlavor::Named, limits).map_err(|e| NbtError::InvalidData(e.to_string()))
+	}
+
+	/// Convert this compound into bytes with the given compression. The root name is written as an empty
+	/// string if the compound doesn't have one, since files always have a named root.
+	pub fn to_bytes(&self, compression: NbtCompression) -> Result<Vec<u8>, NbtError> {
+		let mut serializer = McSerializer::new();
+
+		self.serialize_flavor(&mut serializer, NbtFlavor::Named).map_err(|e| NbtError::InvalidData(e.to_string()))?;
+
+		match compression {
+			NbtCompression::Uncompressed => Ok(serializer.output),
+			NbtCompression::Gzip => {
+				let mut encoder = GzEncoder::new(vec![], Compression::default());
+				encoder.write_all(&serializer.output).map_err(|e| NbtError::IOError(e.to_string()))?;
+				encoder.finish().map_err(|e| NbtError::IOError(e.to_string()))
+			}
+			NbtCompression::Zlib => {
+				let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+				encoder.write_all(&serializer.output).map_err(|e| NbtError::IOError(e.to_string()))?;
+				encoder.finish().map_err(|e| NbtError::IOError(e.to_string()))
+			}
+		}
+	}
+}
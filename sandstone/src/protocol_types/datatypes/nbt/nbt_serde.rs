@@ -0,0 +1,543 @@
+//! Converts any type that implements serde's `Serialize` or `Deserialize` to and from NBT, so structs
+//! don't have to be built by hand with `compound.add(...)`.
+//!
+//! ```
+//! # use serde::{Deserialize, Serialize};
+//! # use sandstone::protocol_types::datatypes::nbt::nbt_serde::{from_nbt, to_nbt};
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Dimension {
+//!     ambient_light: f32,
+//!     has_skylight: bool,
+//!     effects: Option<String>,
+//! }
+//!
+//! let dimension = Dimension { ambient_light: 0.5, has_skylight: true, effects: None };
+//! let tag = to_nbt(&dimension).unwrap();
+//!
+//! assert_eq!(from_nbt::<Dimension>(tag).unwrap(), dimension);
+//! ```
+//!
+//! Types are mapped as follows:
+//! - `bool` is a Byte of 0 or 1, and unsigned integers are stored in the signed tag of the same size
+//! - Sequences are Lists, except for `serialize_bytes` (such as `serde_bytes`) which is a ByteArray
+//! - Structs and maps are Compounds, and map keys must be strings
+//! - `None` fields are left out of the compound entirely
+//! - Unit enum variants are Strings, other variants are a Compound with the variant name as the only key
+//!
+//! Byte, Int and Long arrays can be deserialized into any sequence, such as a `Vec<i32>`.
+
+use std::fmt::Display;
+
+use serde::{de, ser, Serialize};
+use serde::de::{DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, VariantAccess, Visitor};
+use serde::de::value::{MapDeserializer, SeqDeserializer};
+use serde::ser::{SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple, SerializeTupleStruct, SerializeTupleVariant};
+
+use crate::protocol_types::datatypes::nbt::nbt::{NbtByteArray, NbtCompound, NbtList, NbtTag};
+use crate::protocol_types::datatypes::nbt::nbt_error::NbtError;
+
+/// Convert a value into an NBT tag. Structs and maps become an [NbtCompound].
+pub fn to_nbt<T: Serialize + ?Sized>(value: &T) -> Result<NbtTag, NbtError> {
+	value.serialize(NbtSerializer)
+}
+
+/// Convert a value into an [NbtCompound]. This fails if the value is not a struct or map.
+pub fn to_compound<T: Serialize + ?Sized>(value: &T) -> Result<NbtCompound, NbtError> {
+	match to_nbt(value)? {
+		NbtTag::Compound(compound) => Ok(compound),
+		_ => Err(NbtError::MismatchedTypes)
+	}
+}
+
+/// Convert an NBT tag into a value
+pub fn from_nbt<T: DeserializeOwned>(tag: NbtTag) -> Result<T, NbtError> {
+	T::deserialize(NbtDeserializer { tag })
+}
+
+/// Convert an [NbtCompound] into a value
+pub fn from_compound<T: DeserializeOwned>(compound: NbtCompound) -> Result<T, NbtError> {
+	from_nbt(NbtTag::Compound(compound))
+}
+
+impl ser::Error for NbtError {
+	fn custom<T: Display>(msg: T) -> Self {
+		NbtError::Serde(msg.to_string())
+	}
+}
+
+impl de::Error for NbtError {
+	fn custom<T: Display>(msg: T) -> Self {
+		NbtError::Serde(msg.to_string())
+	}
+}
+
+/// Create a compound with a single entry, used for enum variants
+fn variant_compound(variant: &str, tag: NbtTag) -> NbtTag {
+	let mut compound = NbtCompound::new::<String>(None);
+	compound.add(variant, tag);
+	NbtTag::Compound(compound)
+}
+
+fn list_from(tags: Vec<NbtTag>) -> Result<NbtTag, NbtError> {
+	Ok(NbtTag::List(NbtList::from_vec(tags)?))
+}
+
+/// A serde Serializer that outputs an [NbtTag]. `None` and unit values are serialized as [NbtTag::End]
+/// so that they can be left out of compounds.
+pub struct NbtSerializer;
+
+impl ser::Serializer for NbtSerializer {
+	type Ok = NbtTag;
+	type Error = NbtError;
+	type SerializeSeq = NbtSeqSerializer;
+	type SerializeTuple = NbtSeqSerializer;
+	type SerializeTupleStruct = NbtSeqSerializer;
+	type SerializeTupleVariant = NbtSeqSerializer;
+	type SerializeMap = NbtCompoundSerializer;
+	type SerializeStruct = NbtCompoundSerializer;
+	type SerializeStructVariant = NbtCompoundSerializer;
+
+	fn serialize_bool(self, v: bool) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::Byte(v as i8))
+	}
+
+	fn serialize_i8(self, v: i8) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::Byte(v))
+	}
+
+	fn serialize_i16(self, v: i16) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::Short(v))
+	}
+
+	fn serialize_i32(self, v: i32) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::Int(v))
+	}
+
+	fn serialize_i64(self, v: i64) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::Long(v))
+	}
+
+	fn serialize_u8(self, v: u8) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::Byte(v as i8))
+	}
+
+	fn serialize_u16(self, v: u16) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::Short(v as i16))
+	}
+
+	fn serialize_u32(self, v: u32) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::Int(v as i32))
+	}
+
+	fn serialize_u64(self, v: u64) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::Long(v as i64))
+	}
+
+	fn serialize_f32(self, v: f32) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::Float(v))
+	}
+
+	fn serialize_f64(self, v: f64) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::Double(v))
+	}
+
+	fn serialize_char(self, v: char) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::String(v.to_string()))
+	}
+
+	fn serialize_str(self, v: &str) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::String(v.to_string()))
+	}
+
+	fn serialize_bytes(self, v: &[u8]) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::ByteArray(NbtByteArray::new(v.iter().map(|b| *b as i8).collect())))
+	}
+
+	fn serialize_none(self) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::End)
+	}
+
+	fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<NbtTag, NbtError> {
+		value.serialize(self)
+	}
+
+	fn serialize_unit(self) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::End)
+	}
+
+	fn serialize_unit_struct(self, _name: &'static str) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::Compound(NbtCompound::new::<String>(None)))
+	}
+
+	fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<NbtTag, NbtError> {
+		Ok(NbtTag::String(variant.to_string()))
+	}
+
+	fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<NbtTag, NbtError> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _variant_index: u32, variant: &'static str, value: &T) -> Result<NbtTag, NbtError> {
+		Ok(variant_compound(variant, value.serialize(self)?))
+	}
+
+	fn serialize_seq(self, len: Option<usize>) -> Result<NbtSeqSerializer, NbtError> {
+		Ok(NbtSeqSerializer {
+			tags: Vec::with_capacity(len.unwrap_or(0)),
+			variant: None,
+		})
+	}
+
+	fn serialize_tuple(self, len: usize) -> Result<NbtSeqSerializer, NbtError> {
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<NbtSeqSerializer, NbtError> {
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, len: usize) -> Result<NbtSeqSerializer, NbtError> {
+		Ok(NbtSeqSerializer {
+			tags: Vec::with_capacity(len),
+			variant: Some(variant),
+		})
+	}
+
+	fn serialize_map(self, _len: Option<usize>) -> Result<NbtCompoundSerializer, NbtError> {
+		Ok(NbtCompoundSerializer {
+			compound: NbtCompound::new::<String>(None),
+			next_key: None,
+			variant: None,
+		})
+	}
+
+	fn serialize_struct(self, _name: &'static str, len: usize) -> Result<NbtCompoundSerializer, NbtError> {
+		self.serialize_map(Some(len))
+	}
+
+	fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, _len: usize) -> Result<NbtCompoundSerializer, NbtError> {
+		Ok(NbtCompoundSerializer {
+			compound: NbtCompound::new::<String>(None),
+			next_key: None,
+			variant: Some(variant),
+		})
+	}
+}
+
+/// Builds an [NbtList] from a sequence. All elements must serialize to the same tag type.
+pub struct NbtSeqSerializer {
+	tags: Vec<NbtTag>,
+	variant: Option<&'static str>,
+}
+
+impl NbtSeqSerializer {
+	fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), NbtError> {
+		self.tags.push(value.serialize(NbtSerializer)?);
+		Ok(())
+	}
+
+	fn finish(self) -> Result<NbtTag, NbtError> {
+		let list = list_from(self.tags)?;
+
+		match self.variant {
+			Some(variant) => Ok(variant_compound(variant, list)),
+			None => Ok(list)
+		}
+	}
+}
+
+impl SerializeSeq for NbtSeqSerializer {
+	type Ok = NbtTag;
+	type Error = NbtError;
+
+	fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), NbtError> {
+		self.push(value)
+	}
+
+	fn end(self) -> Result<NbtTag, NbtError> {
+		self.finish()
+	}
+}
+
+impl SerializeTuple for NbtSeqSerializer {
+	type Ok = NbtTag;
+	type Error = NbtError;
+
+	fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), NbtError> {
+		self.push(value)
+	}
+
+	fn end(self) -> Result<NbtTag, NbtError> {
+		self.finish()
+	}
+}
+
+impl SerializeTupleStruct for NbtSeqSerializer {
+	type Ok = NbtTag;
+	type Error = NbtError;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), NbtError> {
+		self.push(value)
+	}
+
+	fn end(self) -> Result<NbtTag, NbtError> {
+		self.finish()
+	}
+}
+
+impl SerializeTupleVariant for NbtSeqSerializer {
+	type Ok = NbtTag;
+	type Error = NbtError;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), NbtError> {
+		self.push(value)
+	}
+
+	fn end(self) -> Result<NbtTag, NbtError> {
+		self.finish()
+	}
+}
+
+/// Builds an [NbtCompound] from a struct or map. Entries that serialize to [NbtTag::End], such as
+/// `None`, are skipped.
+pub struct NbtCompoundSerializer {
+	compound: NbtCompound,
+	next_key: Option<String>,
+	variant: Option<&'static str>,
+}
+
+impl NbtCompoundSerializer {
+	fn insert<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<(), NbtError> {
+		let tag = value.serialize(NbtSerializer)?;
+
+		if tag != NbtTag::End {
+			self.compound.add(key, tag);
+		}
+
+		Ok(())
+	}
+
+	fn finish(self) -> Result<NbtTag, NbtError> {
+		let compound = NbtTag::Compound(self.compound);
+
+		match self.variant {
+			Some(variant) => Ok(variant_compound(variant, compound)),
+			None => Ok(compound)
+		}
+	}
+}
+
+impl SerializeMap for NbtCompoundSerializer {
+	type Ok = NbtTag;
+	type Error = NbtError;
+
+	fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), NbtError> {
+		match key.serialize(NbtSerializer)? {
+			NbtTag::String(key) => {
+				self.next_key = Some(key);
+				Ok(())
+			}
+			_ => Err(NbtError::Serde("Compound keys must be strings".to_string()))
+		}
+	}
+
+	fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), NbtError> {
+		let key = self.next_key.take().ok_or_else(|| NbtError::Serde("Value serialized before key".to_string()))?;
+		self.insert(key, value)
+	}
+
+	fn end(self) -> Result<NbtTag, NbtError> {
+		self.finish()
+	}
+}
+
+impl SerializeStruct for NbtCompoundSerializer {
+	type Ok = NbtTag;
+	type Error = NbtError;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), NbtError> {
+		self.insert(key.to_string(), value)
+	}
+
+	fn end(self) -> Result<NbtTag, NbtError> {
+		self.finish()
+	}
+}
+
+impl SerializeStructVariant for NbtCompoundSerializer {
+	type Ok = NbtTag;
+	type Error = NbtError;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), NbtError> {
+		self.insert(key.to_string(), value)
+	}
+
+	fn end(self) -> Result<NbtTag, NbtError> {
+		self.finish()
+	}
+}
+
+/// A serde Deserializer that reads from an owned [NbtTag]
+pub struct NbtDeserializer {
+	tag: NbtTag,
+}
+
+impl NbtDeserializer {
+	pub fn new(tag: NbtTag) -> Self {
+		Self {
+			tag
+		}
+	}
+
+	fn mismatch(&self, expected: &str) -> NbtError {
+		NbtError::Serde(format!("Expected {}, found {}", expected, self.tag.get_name()))
+	}
+}
+
+impl<'de> IntoDeserializer<'de, NbtError> for NbtTag {
+	type Deserializer = NbtDeserializer;
+
+	fn into_deserializer(self) -> NbtDeserializer {
+		NbtDeserializer::new(self)
+	}
+}
+
+/// Unsigned integers are stored in the signed tag of the same size, so they are read back the same way
+macro_rules! deserialize_unsigned {
+	($($method: ident, $visit: ident, $variant: ident, $t: ty);*) => {
+		$(
+			fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, NbtError> {
+				match self.tag {
+					NbtTag::$variant(v) => visitor.$visit(v as $t),
+					_ => self.deserialize_any(visitor)
+				}
+			}
+		)*
+	};
+}
+
+impl<'de> de::Deserializer<'de> for NbtDeserializer {
+	type Error = NbtError;
+
+	fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, NbtError> {
+		match self.tag {
+			NbtTag::End => visitor.visit_unit(),
+			NbtTag::Byte(v) => visitor.visit_i8(v),
+			NbtTag::Short(v) => visitor.visit_i16(v),
+			NbtTag::Int(v) => visitor.visit_i32(v),
+			NbtTag::Long(v) => visitor.visit_i64(v),
+			NbtTag::Float(v) => visitor.visit_f32(v),
+			NbtTag::Double(v) => visitor.visit_f64(v),
+			NbtTag::String(v) => visitor.visit_string(v),
+			NbtTag::ByteArray(v) => visitor.visit_seq(SeqDeserializer::new(v.list.into_iter())),
+			NbtTag::IntArray(v) => visitor.visit_seq(SeqDeserializer::new(v.list.into_iter())),
+			NbtTag::LongArray(v) => visitor.visit_seq(SeqDeserializer::new(v.list.into_iter())),
+			NbtTag::List(v) => visitor.visit_seq(SeqDeserializer::new(v.list.into_iter())),
+			NbtTag::Compound(v) => visitor.visit_map(MapDeserializer::new(v.map.into_iter())),
+		}
+	}
+
+	fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, NbtError> {
+		match self.tag {
+			NbtTag::Byte(v) => visitor.visit_bool(v != 0),
+			_ => Err(self.mismatch("TAG_Byte"))
+		}
+	}
+
+	deserialize_unsigned!(
+		deserialize_u8, visit_u8, Byte, u8;
+		deserialize_u16, visit_u16, Short, u16;
+		deserialize_u32, visit_u32, Int, u32;
+		deserialize_u64, visit_u64, Long, u64
+	);
+
+	fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, NbtError> {
+		match self.tag {
+			NbtTag::ByteArray(v) => visitor.visit_byte_buf(v.list.into_iter().map(|b| b as u8).collect()),
+			_ => self.deserialize_any(visitor)
+		}
+	}
+
+	fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, NbtError> {
+		self.deserialize_bytes(visitor)
+	}
+
+	fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, NbtError> {
+		match self.tag {
+			NbtTag::End => visitor.visit_none(),
+			_ => visitor.visit_some(self)
+		}
+	}
+
+	fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, NbtError> {
+		visitor.visit_unit()
+	}
+
+	fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, NbtError> {
+		visitor.visit_newtype_struct(self)
+	}
+
+	fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, NbtError> {
+		match self.tag {
+			NbtTag::String(variant) => visitor.visit_enum(NbtEnumAccess { variant, value: None }),
+			NbtTag::Compound(compound) if compound.map.len() == 1 => {
+				let (variant, value) = compound.map.into_iter().next().unwrap();
+				visitor.visit_enum(NbtEnumAccess { variant, value: Some(value) })
+			}
+			_ => Err(self.mismatch("TAG_String or TAG_Compound with a single entry"))
+		}
+	}
+
+	serde::forward_to_deserialize_any! {
+		i8 i16 i32 i64 i128 u128 f32 f64 char str string unit seq tuple tuple_struct map struct identifier ignored_any
+	}
+}
+
+struct NbtEnumAccess {
+	variant: String,
+	value: Option<NbtTag>,
+}
+
+impl<'de> EnumAccess<'de> for NbtEnumAccess {
+	type Error = NbtError;
+	type Variant = NbtVariantAccess;
+
+	fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, NbtVariantAccess), NbtError> {
+		let variant = seed.deserialize(IntoDeserializer::<NbtError>::into_deserializer(self.variant))?;
+
+		Ok((variant, NbtVariantAccess { value: self.value }))
+	}
+}
+
+struct NbtVariantAccess {
+	value: Option<NbtTag>,
+}
+
+impl NbtVariantAccess {
+	fn value(self) -> Result<NbtDeserializer, NbtError> {
+		self.value.map(NbtDeserializer::new).ok_or_else(|| NbtError::Serde("Expected a variant with a value".to_string()))
+	}
+}
+
+impl<'de> VariantAccess<'de> for NbtVariantAccess {
+	type Error = NbtError;
+
+	fn unit_variant(self) -> Result<(), NbtError> {
+		match self.value {
+			None => Ok(()),
+			Some(_) => Err(NbtError::Serde("Expected a unit variant".to_string()))
+		}
+	}
+
+	fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, NbtError> {
+		seed.deserialize(self.value()?)
+	}
+
+	fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, NbtError> {
+		de::Deserializer::deserialize_seq(self.value()?, visitor)
+	}
+
+	fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, NbtError> {
+		de::Deserializer::deserialize_map(self.value()?, visitor)
+	}
+}
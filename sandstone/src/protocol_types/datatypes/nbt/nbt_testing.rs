@@ -1,5 +1,14 @@
+use std::io::Cursor;
+
+use quartz_nbt::io::Flavor;
+use serde::{Deserialize, Serialize};
+
 use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
-use crate::protocol_types::datatypes::nbt::nbt::{NbtByteArray, NbtCompound, NbtIntArray, NbtList, NbtLongArray, NbtTag};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::nbt::nbt::{NbtByteArray, NbtCompound, NbtFlavor, NbtIntArray, NbtLimits, NbtList, NbtLongArray, NbtTag};
+use crate::protocol_types::datatypes::nbt::nbt_error::NbtError;
+use crate::protocol_types::datatypes::nbt::nbt_reader::NbtCompression;
+use crate::protocol_types::datatypes::nbt::nbt_serde::{from_compound, from_nbt, to_compound, to_nbt};
 
 #[test]
 fn test_compound_serialization() {
@@ -15,7 +24,7 @@ fn test_compound_serialization() {
 	compound.add("long_array", NbtLongArray::new(vec![1, 2, 3, 4, 5]));
 	compound.add("list", NbtList::from_vec(vec![NbtTag::Int(1), NbtTag::Int(2), NbtTag::Int(3)]).unwrap());
 	
-	let mut compound2 = NbtCompound::new(Some("AB"));
+	let mut compound2 = NbtCompound::new::<String>(None); // nested compounds are named by their parent
 	compound2.add("byte", 13i8);
 	compound.add("compound", compound2);
 
@@ -51,11 +60,11 @@ fn test_compound_serialization() {
 #[test]
 fn test_compounds_in_compounds() {
 	let mut outer = NbtCompound::new(Some("outer"));
-	let mut mid1 = NbtCompound::new(Some("mid1"));
-	let mut mid2 = NbtCompound::new(Some("mid2"));
-	let mut inner1 = NbtCompound::new(Some("inner1"));
-	let mut inner2 = NbtCompound::new(Some("inner2"));
-	let mut inner3 = NbtCompound::new(Some("inner3"));
+	let mut mid1 = NbtCompound::new::<String>(None);
+	let mut mid2 = NbtCompound::new::<String>(None);
+	let mut inner1 = NbtCompound::new::<String>(None);
+	let mut inner2 = NbtCompound::new::<String>(None);
+	let mut inner3 = NbtCompound::new::<String>(None);
 	
 	inner1.add("i8", 123i8);
 	inner1.add("i16", 1234i16);
@@ -93,4 +102,368 @@ fn test_compounds_in_compounds() {
 		},
 		_ => panic!("Expected compound")
 	}
-}
\ No newline at end of file
+}
+
+/// Build the same structure in both sandstone and quartz_nbt, including lists of compounds
+fn build_reference() -> (NbtCompound, quartz_nbt::NbtCompound) {
+	let mut ours = NbtCompound::new(Some("Level"));
+	ours.add("byte", 12i8);
+	ours.add("long", -98765432123i64);
+	ours.add("double", 0.493128713218231f64);
+	ours.add("name", "Bananrama");
+	ours.add("ints", NbtIntArray::new(vec![1, -2, 3]));
+	
+	let mut nested = NbtCompound::new::<String>(None);
+	nested.add("short", 32767i16);
+	nested.add("float", 0.75f32);
+	ours.add("nested", nested);
+	
+	let mut list = NbtList::new();
+	for i in 0..3 {
+		let mut element = NbtCompound::new::<String>(None);
+		element.add("index", i as i32);
+		element.add("created-on", 1264099775885i64);
+		list.add(element).unwrap();
+	}
+	ours.add("compounds", list);
+	ours.add("empty", NbtList::new());
+	
+	let mut theirs = quartz_nbt::NbtCompound::new();
+	theirs.insert("byte", 12i8);
+	theirs.insert("long", -98765432123i64);
+	theirs.insert("double", 0.493128713218231f64);
+	theirs.insert("name", "Bananrama");
+	theirs.insert("ints", quartz_nbt::NbtTag::IntArray(vec![1, -2, 3]));
+	
+	let mut nested = quartz_nbt::NbtCompound::new();
+	nested.insert("short", 32767i16);
+	nested.insert("float", 0.75f32);
+	theirs.insert("nested", nested);
+	
+	let mut list = quartz_nbt::NbtList::new();
+	for i in 0..3 {
+		let mut element = quartz_nbt::NbtCompound::new();
+		element.insert("index", i as i32);
+		element.insert("created-on", 1264099775885i64);
+		list.push(element);
+	}
+	theirs.insert("compounds", list);
+	theirs.insert("empty", quartz_nbt::NbtList::new());
+	
+	(ours, theirs)
+}
+
+#[test]
+fn test_read_quartz_output() {
+	let (ours, theirs) = build_reference();
+	
+	let mut bytes = vec![];
+	quartz_nbt::io::write_nbt(&mut bytes, Some("Level"), &theirs, Flavor::Uncompressed).unwrap();
+	
+	let mut deserializer = McDeserializer::new(&bytes);
+	let deserialized = NbtCompound::mc_deserialize(&mut deserializer).unwrap();
+	
+	assert!(deserializer.is_at_end());
+	assert_eq!(ours, deserialized);
+}
+
+#[test]
+fn test_quartz_reads_our_output() {
+	let (ours, theirs) = build_reference();
+	
+	let mut serializer = McSerializer::new();
+	ours.mc_serialize(&mut serializer).unwrap();
+	
+	let (read, name) = quartz_nbt::io::read_nbt(&mut Cursor::new(serializer.output), Flavor::Uncompressed).unwrap();
+	
+	assert_eq!(name, "Level");
+	assert_eq!(read, theirs);
+}
+
+#[test]
+fn test_missing_end_tag() {
+	let (ours, _) = build_reference();
+	
+	let mut serializer = McSerializer::new();
+	ours.mc_serialize(&mut serializer).unwrap();
+	serializer.output.pop(); // remove the End tag of the root compound
+	
+	let mut deserializer = McDeserializer::new(&serializer.output);
+	assert!(NbtCompound::mc_deserialize(&mut deserializer).is_err());
+}
+
+#[test]
+fn test_compressed_bytes() {
+	let (ours, theirs) = build_reference();
+	
+	for (compression, flavor) in [(NbtCompression::Uncompressed, Flavor::Uncompressed), (NbtCompression::Gzip, Flavor::GzCompressed), (NbtCompression::Zlib, Flavor::ZlibCompressed)] {
+		let bytes = ours.to_bytes(compression).unwrap();
+		assert_eq!(NbtCompression::detect(&bytes), compression);
+		
+		let (read, name) = quartz_nbt::io::read_nbt(&mut Cursor::new(&bytes), flavor).unwrap();
+		assert_eq!(name, "Level");
+		assert_eq!(read, theirs);
+		
+		let mut bytes = vec![];
+		quartz_nbt::io::write_nbt(&mut bytes, Some("Level"), &theirs, flavor).unwrap();
+		assert_eq!(NbtCompound::from_bytes(&bytes, compression).unwrap(), ours);
+	}
+}
+
+#[test]
+fn test_file_io() {
+	let (ours, _) = build_reference();
+	let path = std::env::temp_dir().join(format!("sandstone_nbt_test_{}.dat", std::process::id()));
+	
+	ours.write_to_file(&path, NbtCompression::Gzip).unwrap();
+	let read = NbtCompound::read_from_file(&path);
+	std::fs::remove_file(&path).unwrap();
+	
+	assert_eq!(read.unwrap(), ours);
+}
+
+#[test]
+fn test_flavors() {
+	let mut compound = NbtCompound::new(Some("root"));
+	compound.add("int", 5i32);
+	
+	let mut serializer = McSerializer::new();
+	compound.serialize_flavor(&mut serializer, NbtFlavor::Network).unwrap();
+	assert_eq!(serializer.output, vec![10, 3, 0, 3, b'i', b'n', b't', 0, 0, 0, 5, 0]);
+	
+	let mut deserializer = McDeserializer::new(&serializer.output);
+	let network = NbtCompound::deserialize_flavor(&mut deserializer, NbtFlavor::Network).unwrap();
+	assert_eq!(network.root_name, None);
+	assert_eq!(network["int"], NbtTag::Int(5));
+	
+	// a compound without a name still gets an empty one in the named flavor
+	let mut serializer = McSerializer::new();
+	network.serialize_flavor(&mut serializer, NbtFlavor::Named).unwrap();
+	assert_eq!(&serializer.output[..3], &[10, 0, 0]);
+	
+	let mut deserializer = McDeserializer::new(&serializer.output);
+	let named = NbtCompound::deserialize_flavor(&mut deserializer, NbtFlavor::Named).unwrap();
+	assert_eq!(named.root_name, Some(String::new()));
+	assert_eq!(named.map, network.map);
+}
+
+#[test]
+fn test_typed_getters() {
+	let mut inner = NbtCompound::new::<String>(None);
+	inner.add("name", "minecraft:plains");
+	
+	let mut compound = NbtCompound::new::<String>(None);
+	compound.add("flag", 1i8);
+	compound.add("int", 5i32);
+	compound.add("long", -7i64);
+	compound.add("double", 0.5f64);
+	compound.add("list", NbtList::from_vec(vec![NbtTag::Int(1)]).unwrap());
+	compound.add("inner", inner);
+	
+	assert_eq!(compound.len(), 6);
+	assert!(compound.contains_key("int"));
+	assert!(!compound.contains_key("missing"));
+	
+	let mut keys: Vec<&String> = compound.keys().collect();
+	keys.sort();
+	assert_eq!(keys, vec!["double", "flag", "inner", "int", "list", "long"]);
+	
+	assert_eq!(compound.get_bool("flag"), Ok(true));
+	assert_eq!(compound.get_byte("flag"), Ok(1));
+	assert_eq!(compound.get_int("int"), Ok(5));
+	assert_eq!(compound.get_long("long"), Ok(-7));
+	assert_eq!(compound.get_double("double"), Ok(0.5));
+	assert_eq!(compound.get_list("list").unwrap().list, vec![NbtTag::Int(1)]);
+	assert_eq!(compound.get_compound("inner").and_then(|c| c.get_string("name")), Ok("minecraft:plains"));
+	
+	assert_eq!(compound.get_int("missing"), Err(NbtError::MissingKey("missing".to_string())));
+	assert_eq!(compound.get_long("int"), Err(NbtError::WrongType("int".to_string(), "TAG_Int".to_string())));
+	assert_eq!(compound.get("missing"), None);
+}
+
+#[test]
+fn test_list_iteration() {
+	let mut list = NbtList::from_vec(vec![NbtTag::Int(1), NbtTag::Int(2), NbtTag::Int(3)]).unwrap();
+	
+	for tag in &mut list {
+		if let NbtTag::Int(i) = tag {
+			*i *= 2;
+		}
+	}
+	
+	// iterating does not consume or exhaust the list
+	assert_eq!(list.iter().count(), 3);
+	assert_eq!(list.iter().count(), 3);
+	assert_eq!(list.into_iter().collect::<Vec<NbtTag>>(), vec![NbtTag::Int(2), NbtTag::Int(4), NbtTag::Int(6)]);
+	
+	let array = NbtLongArray::new(vec![1, 2, 3]);
+	assert_eq!(array.iter().sum::<i64>(), 6);
+	assert_eq!((&array).into_iter().count(), array.len());
+	assert_eq!(array.into_iter().collect::<Vec<i64>>(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_json_conversion() {
+	let json = serde_json::json!({
+		"name": "minecraft:plains",
+		"natural": true,
+		"downfall": 0.4,
+		"seed": 5000000000i64,
+		"colors": [1, 5000000000i64],
+		"mixed": [1, 0.5],
+		"effects": {"fog_color": 12638463}
+	});
+	
+	let tag = NbtTag::from_json(&json).unwrap();
+	let compound = NbtCompound::from(tag.clone());
+	
+	assert_eq!(compound["natural"], NbtTag::Byte(1));
+	assert_eq!(compound["downfall"], NbtTag::Double(0.4));
+	assert_eq!(compound["seed"], NbtTag::Long(5000000000));
+	assert_eq!(compound["colors"], NbtTag::List(NbtList::from_vec(vec![NbtTag::Long(1), NbtTag::Long(5000000000)]).unwrap()));
+	assert_eq!(compound["mixed"], NbtTag::List(NbtList::from_vec(vec![NbtTag::Double(1.0), NbtTag::Double(0.5)]).unwrap()));
+	assert_eq!(compound.get_compound("effects").unwrap()["fog_color"], NbtTag::Int(12638463));
+	
+	// only the boolean and array widening are lost on the way back
+	let back = tag.to_json();
+	assert_eq!(back["natural"], serde_json::json!(1));
+	assert_eq!(back["name"], json["name"]);
+	assert_eq!(back["effects"], json["effects"]);
+	
+	assert_eq!(NbtTag::IntArray(NbtIntArray::new(vec![1, 2])).to_json(), serde_json::json!([1, 2]));
+	assert_eq!(NbtTag::Float(f32::NAN).to_json(), serde_json::Value::Null);
+	assert!(NbtTag::from_json(&serde_json::json!([1, "a"])).is_err());
+	assert!(NbtTag::from_json(&serde_json::Value::Null).is_err());
+}
+
+#[test]
+fn test_from_conversions() {
+	let mut compound = NbtCompound::new::<String>(None);
+	compound.add("string", "owned".to_string());
+	compound.add("bool", true);
+	compound.add("bytes", vec![1i8, -1]);
+	compound.add("ints", vec![1i32, 2]);
+	compound.add("longs", vec![1i64, 2]);
+	compound.add("raw", [0u8, 255].as_slice());
+	compound.add("list", NbtList::from_vec(vec![NbtTag::Byte(1)]).unwrap());
+	compound.add("compound", NbtCompound::new::<String>(None));
+	
+	assert_eq!(compound["string"], NbtTag::String("owned".to_string()));
+	assert_eq!(compound["bool"], NbtTag::Byte(1));
+	assert_eq!(compound["bytes"], NbtTag::ByteArray(NbtByteArray::new(vec![1, -1])));
+	assert_eq!(compound["ints"], NbtTag::IntArray(NbtIntArray::new(vec![1, 2])));
+	assert_eq!(compound["longs"], NbtTag::LongArray(NbtLongArray::new(vec![1, 2])));
+	assert_eq!(compound["raw"], NbtTag::ByteArray(NbtByteArray::new(vec![0, -1])));
+	assert_eq!(compound.get_list("list").unwrap().len(), 1);
+	assert!(compound.get_compound("compound").unwrap().is_empty());
+}
+
+/// A network compound with `depth` lists nested inside of each other
+fn nested_lists(depth: usize) -> Vec<u8> {
+	let mut bytes = vec![10, 9, 0, 1, b'l'];
+	
+	for _ in 1..depth {
+		bytes.extend_from_slice(&[9, 0, 0, 0, 1]); // a list holding one list
+	}
+	
+	bytes.extend_from_slice(&[0, 0, 0, 0, 0]); // innermost list is empty
+	bytes.push(0);
+	bytes
+}
+
+#[test]
+fn test_limits() {
+	// the root compound and 511 lists is exactly the default depth
+	let bytes = nested_lists(511);
+	let mut deserializer = McDeserializer::new(&bytes);
+	NbtCompound::from_network(&mut deserializer).unwrap();
+	
+	let bytes = nested_lists(512);
+	let mut deserializer = McDeserializer::new(&bytes);
+	assert!(matches!(NbtCompound::from_network(&mut deserializer), Err(SerializingErr::NbtLimitExceeded(_))));
+	
+	let limits = NbtLimits { max_depth: 10, ..NbtLimits::default() };
+	let bytes = nested_lists(10);
+	let mut deserializer = McDeserializer::new(&bytes);
+	assert!(matches!(NbtCompound::deserialize_with_limits(&mut deserializer, NbtFlavor::Network, limits), Err(SerializingErr::NbtLimitExceeded(_))));
+	
+	// an array that claims to be far bigger than allowed is rejected before it is read
+	let mut compound = NbtCompound::new::<String>(None);
+	compound.add("array", NbtLongArray::new(vec![0; 100]));
+	let mut serializer = McSerializer::new();
+	compound.mc_serialize(&mut serializer).unwrap();
+	
+	let limits = NbtLimits { max_size: 512, ..NbtLimits::default() };
+	let mut deserializer = McDeserializer::new(&serializer.output);
+	assert!(matches!(NbtCompound::deserialize_with_limits(&mut deserializer, NbtFlavor::Network, limits), Err(SerializingErr::NbtLimitExceeded(_))));
+	
+	let mut compound = NbtCompound::new::<String>(None);
+	compound.add("list", NbtList::from_vec(vec![NbtTag::Byte(0); 100]).unwrap());
+	let mut serializer = McSerializer::new();
+	compound.mc_serialize(&mut serializer).unwrap();
+	
+	let limits = NbtLimits { max_elements: 50, ..NbtLimits::default() };
+	let mut deserializer = McDeserializer::new(&serializer.output);
+	assert!(matches!(NbtCompound::deserialize_with_limits(&mut deserializer, NbtFlavor::Network, limits), Err(SerializingErr::NbtLimitExceeded(_))));
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+enum Precipitation {
+	None,
+	Rain,
+	Snow { temperature: f32 },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Biome {
+	name: String,
+	has_precipitation: bool,
+	downfall: f64,
+	fog_color: u32,
+	precipitation: Precipitation,
+	sky_colors: Vec<i32>,
+	effects: Option<String>,
+	particle: Option<Box<Biome>>,
+}
+
+#[test]
+fn test_serde() {
+	let biome = Biome {
+		name: "minecraft:plains".to_string(),
+		has_precipitation: true,
+		downfall: 0.4,
+		fog_color: 0xC0D8FF,
+		precipitation: Precipitation::Snow { temperature: -0.5 },
+		sky_colors: vec![1, 2, 3],
+		effects: None,
+		particle: Some(Box::new(Biome {
+			name: "minecraft:ash".to_string(),
+			has_precipitation: false,
+			downfall: 0.0,
+			fog_color: u32::MAX,
+			precipitation: Precipitation::Rain,
+			sky_colors: vec![],
+			effects: Some("ash".to_string()),
+			particle: None,
+		})),
+	};
+	
+	let compound = to_compound(&biome).unwrap();
+	assert_eq!(compound["has_precipitation"], NbtTag::Byte(1));
+	assert_eq!(compound["fog_color"], NbtTag::Int(0xC0D8FF));
+	assert_eq!(compound["sky_colors"], NbtTag::List(NbtList::from_vec(vec![NbtTag::Int(1), NbtTag::Int(2), NbtTag::Int(3)]).unwrap()));
+	assert!(!compound.map.contains_key("effects"));
+	
+	// survives a trip through the binary format too
+	let mut serializer = McSerializer::new();
+	compound.mc_serialize(&mut serializer).unwrap();
+	let mut deserializer = McDeserializer::new(&serializer.output);
+	let compound = NbtCompound::from_network(&mut deserializer).unwrap();
+	
+	assert_eq!(from_compound::<Biome>(compound).unwrap(), biome);
+	
+	assert_eq!(to_nbt(&Precipitation::None).unwrap(), NbtTag::String("None".to_string()));
+	assert_eq!(from_nbt::<Vec<i32>>(NbtTag::IntArray(NbtIntArray::new(vec![4, 5]))).unwrap(), vec![4, 5]);
+	assert_eq!(to_nbt(&(1i32, "mixed")), Err(NbtError::IncompatibleTypes));
+	assert!(matches!(from_nbt::<Biome>(NbtTag::Int(1)), Err(NbtError::Serde(_))));
+}
@@ -0,0 +1,456 @@
+//! Stringified NBT (SNBT), the text representation of NBT used by commands and data packs.
+//! For example, `{name:"dec4234",level:3b,scores:[I;1,2,3]}`
+//!
+//! See https://minecraft.wiki/w/NBT_format#SNBT_format
+
+use std::fmt::{Display, Formatter, Write};
+
+use crate::protocol_types::datatypes::nbt::nbt::{NbtByteArray, NbtCompound, NbtIntArray, NbtList, NbtLongArray, NbtTag};
+use crate::protocol_types::datatypes::nbt::nbt_error::NbtError;
+
+impl NbtTag {
+	/// Parse a tag from its SNBT representation. Numbers without a suffix are parsed as an Int, or as
+	/// a Double if they contain a decimal point, just like the vanilla parser.
+	pub fn from_snbt(input: &str) -> Result<NbtTag, NbtError> {
+		let mut parser = SnbtParser::new(input);
+
+		let tag = parser.read_value()?;
+		parser.skip_whitespace();
+
+		if !parser.is_at_end() {
+			return Err(parser.error("Trailing data after tag"));
+		}
+
+		Ok(tag)
+	}
+
+	/// Convert this tag into its SNBT representation. The keys of compounds are sorted so the output is
+	/// always the same for the same tag.
+	pub fn to_snbt(&self) -> String {
+		let mut output = String::new();
+		write_snbt(self, &mut output);
+		output
+	}
+}
+
+impl NbtCompound {
+	/// Parse a compound from its SNBT representation. See [NbtTag::from_snbt]
+	pub fn from_snbt(input: &str) -> Result<NbtCompound, NbtError> {
+		match NbtTag::from_snbt(input)? {
+			NbtTag::Compound(compound) => Ok(compound),
+			_ => Err(NbtError::MismatchedTypes)
+		}
+	}
+
+	/// Convert this compound into its SNBT representation. See [NbtTag::to_snbt]
+	pub fn to_snbt(&self) -> String {
+		let mut output = String::new();
+		write_compound(self, &mut output);
+		output
+	}
+}
+
+impl Display for NbtTag {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.to_snbt())
+	}
+}
+
+impl Display for NbtCompound {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.to_snbt())
+	}
+}
+
+fn write_snbt(tag: &NbtTag, output: &mut String) {
+	match tag {
+		NbtTag::End => {}
+		NbtTag::Byte(b) => { let _ = write!(output, "{}b", b); }
+		NbtTag::Short(s) => { let _ = write!(output, "{}s", s); }
+		NbtTag::Int(i) => { let _ = write!(output, "{}", i); }
+		NbtTag::Long(l) => { let _ = write!(output, "{}L", l); }
+		NbtTag::Float(f) => write_decimal(&format!("{:?}", f), 'f', output),
+		NbtTag::Double(d) => write_decimal(&format!("{:?}", d), 'd', output),
+		NbtTag::String(s) => write_quoted(s, output),
+		NbtTag::ByteArray(a) => write_array("B", a.list.iter().map(|b| format!("{}b", b)), output),
+		NbtTag::IntArray(a) => write_array("I", a.list.iter().map(|i| i.to_string()), output),
+		NbtTag::LongArray(a) => write_array("L", a.list.iter().map(|l| format!("{}L", l)), output),
+		NbtTag::List(list) => {
+			output.push('[');
+
+			for (i, tag) in list.list.iter().enumerate() {
+				if i > 0 {
+					output.push(',');
+				}
+
+				write_snbt(tag, output);
+			}
+
+			output.push(']');
+		}
+		NbtTag::Compound(compound) => write_compound(compound, output),
+	}
+}
+
+fn write_compound(compound: &NbtCompound, output: &mut String) {
+	let mut keys: Vec<&String> = compound.map.keys().collect();
+	keys.sort();
+
+	output.push('{');
+
+	for (i, key) in keys.into_iter().enumerate() {
+		if i > 0 {
+			output.push(',');
+		}
+
+		if !key.is_empty() && key.chars().all(is_unquoted_char) {
+			output.push_str(key);
+		} else {
+			write_quoted(key, output);
+		}
+
+		output.push(':');
+		write_snbt(&compound.map[key], output);
+	}
+
+	output.push('}');
+}
+
+fn write_array<I: Iterator<Item = String>>(prefix: &str, values: I, output: &mut String) {
+	output.push('[');
+	output.push_str(prefix);
+	output.push(';');
+	output.push_str(&values.collect::<Vec<String>>().join(","));
+	output.push(']');
+}
+
+/// Write a float from its debug formatting, which has a decimal point unless it has an exponent. Values
+/// that aren't finite are spelled the way Java and so vanilla write them, such as `NaNf` and `-Infinityd`.
+fn write_decimal(debug: &str, suffix: char, output: &mut String) {
+	match debug {
+		"NaN" => output.push_str("NaN"),
+		"inf" => output.push_str("Infinity"),
+		"-inf" => output.push_str("-Infinity"),
+		_ => match debug.split_once('e') {
+			Some((mantissa, exponent)) if !mantissa.contains('.') => { let _ = write!(output, "{}.0e{}", mantissa, exponent); }
+			_ => output.push_str(debug),
+		},
+	}
+
+	output.push(suffix);
+}
+
+fn write_quoted(s: &str, output: &mut String) {
+	output.push('"');
+
+	for c in s.chars() {
+		if c == '"' || c == '\\' {
+			output.push('\\');
+		}
+
+		output.push(c);
+	}
+
+	output.push('"');
+}
+
+/// Characters that can be used in keys and strings without quotes
+fn is_unquoted_char(c: char) -> bool {
+	c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.' || c == '+'
+}
+
+struct SnbtParser<'a> {
+	input: &'a str,
+	index: usize,
+}
+
+impl<'a> SnbtParser<'a> {
+	fn new(input: &'a str) -> Self {
+		Self {
+			input,
+			index: 0,
+		}
+	}
+
+	fn error(&self, message: &str) -> NbtError {
+		NbtError::InvalidSnbt(format!("{} at position {}", message, self.index))
+	}
+
+	fn is_at_end(&self) -> bool {
+		self.index >= self.input.len()
+	}
+
+	fn peek(&self) -> Option<char> {
+		self.input[self.index..].chars().next()
+	}
+
+	fn skip_whitespace(&mut self) {
+		while let Some(c) = self.peek() {
+			if !c.is_whitespace() {
+				break;
+			}
+
+			self.index += c.len_utf8();
+		}
+	}
+
+	/// Skip whitespace and then consume the expected character
+	fn expect(&mut self, expected: char) -> Result<(), NbtError> {
+		self.skip_whitespace();
+
+		if self.peek() != Some(expected) {
+			return Err(self.error(&format!("Expected '{}'", expected)));
+		}
+
+		self.index += 1;
+		Ok(())
+	}
+
+	fn read_value(&mut self) -> Result<NbtTag, NbtError> {
+		self.skip_whitespace();
+
+		match self.peek() {
+			Some('{') => Ok(NbtTag::Compound(self.read_compound()?)),
+			Some('[') => self.read_list_or_array(),
+			Some('"') | Some('\'') => Ok(NbtTag::String(self.read_quoted()?)),
+			Some(_) => {
+				let s = self.read_unquoted()?;
+				Ok(parse_primitive(s))
+			}
+			None => Err(self.error("Expected value")),
+		}
+	}
+
+	fn read_compound(&mut self) -> Result<NbtCompound, NbtError> {
+		self.expect('{')?;
+		let mut compound = NbtCompound::new::<String>(None);
+
+		self.skip_whitespace();
+
+		if self.peek() == Some('}') {
+			self.index += 1;
+			return Ok(compound);
+		}
+
+		loop {
+			self.skip_whitespace();
+
+			let key = match self.peek() {
+				Some('"') | Some('\'') => self.read_quoted()?,
+				_ => self.read_unquoted()?.to_string(),
+			};
+
+			self.expect(':')?;
+			let value = self.read_value()?;
+			compound.add(key, value);
+
+			self.skip_whitespace();
+
+			match self.peek() {
+				Some(',') => self.index += 1,
+				Some('}') => {
+					self.index += 1;
+					return Ok(compound);
+				}
+				_ => return Err(self.error("Expected ',' or '}'")),
+			}
+		}
+	}
+
+	fn read_list_or_array(&mut self) -> Result<NbtTag, NbtError> {
+		self.expect('[')?;
+
+		// arrays start with their type, such as [I;1,2,3]
+		let rest = &self.input[self.index..];
+		let mut chars = rest.chars();
+
+		if let (Some(ty @ ('B' | 'I' | 'L')), Some(';')) = (chars.next(), chars.next()) {
+			self.index += 2;
+			return self.read_array(ty);
+		}
+
+		let mut list = NbtList::new();
+
+		for tag in self.read_elements()? {
+			list.add(tag).map_err(|_| self.error("List elements must all be the same type"))?;
+		}
+
+		Ok(NbtTag::List(list))
+	}
+
+	fn read_array(&mut self, ty: char) -> Result<NbtTag, NbtError> {
+		let elements = self.read_elements()?;
+
+		match ty {
+			'B' => {
+				let values = elements.into_iter().map(|tag| match tag {
+					NbtTag::Byte(b) => Ok(b),
+					_ => Err(self.error("Byte arrays can only contain bytes")),
+				}).collect::<Result<Vec<i8>, NbtError>>()?;
+
+				Ok(NbtTag::ByteArray(NbtByteArray::new(values)))
+			}
+			'I' => {
+				let values = elements.into_iter().map(|tag| match tag {
+					NbtTag::Byte(b) => Ok(b as i32),
+					NbtTag::Short(s) => Ok(s as i32),
+					NbtTag::Int(i) => Ok(i),
+					_ => Err(self.error("Int arrays can only contain ints")),
+				}).collect::<Result<Vec<i32>, NbtError>>()?;
+
+				Ok(NbtTag::IntArray(NbtIntArray::new(values)))
+			}
+			_ => {
+				let values = elements.into_iter().map(|tag| match tag {
+					NbtTag::Byte(b) => Ok(b as i64),
+					NbtTag::Short(s) => Ok(s as i64),
+					NbtTag::Int(i) => Ok(i as i64),
+					NbtTag::Long(l) => Ok(l),
+					_ => Err(self.error("Long arrays can only contain longs")),
+				}).collect::<Result<Vec<i64>, NbtError>>()?;
+
+				Ok(NbtTag::LongArray(NbtLongArray::new(values)))
+			}
+		}
+	}
+
+	/// Read comma separated values up to and including the closing bracket
+	fn read_elements(&mut self) -> Result<Vec<NbtTag>, NbtError> {
+		let mut elements = vec![];
+
+		self.skip_whitespace();
+
+		if self.peek() == Some(']') {
+			self.index += 1;
+			return Ok(elements);
+		}
+
+		loop {
+			elements.push(self.read_value()?);
+			self.skip_whitespace();
+
+			match self.peek() {
+				Some(',') => self.index += 1,
+				Some(']') => {
+					self.index += 1;
+					return Ok(elements);
+				}
+				_ => return Err(self.error("Expected ',' or ']'")),
+			}
+		}
+	}
+
+	fn read_quoted(&mut self) -> Result<String, NbtError> {
+		let quote = self.peek().ok_or_else(|| self.error("Expected string"))?;
+		self.index += 1;
+
+		let mut s = String::new();
+		let mut escaped = false;
+
+		while let Some(c) = self.peek() {
+			self.index += c.len_utf8();
+
+			if escaped {
+				if c != quote && c != '\\' {
+					return Err(self.error(&format!("Invalid escape sequence '\\{}'", c)));
+				}
+
+				s.push(c);
+				escaped = false;
+			} else if c == '\\' {
+				escaped = true;
+			} else if c == quote {
+				return Ok(s);
+			} else {
+				s.push(c);
+			}
+		}
+
+		Err(self.error("Unterminated string"))
+	}
+
+	fn read_unquoted(&mut self) -> Result<&'a str, NbtError> {
+		let start = self.index;
+
+		while let Some(c) = self.peek() {
+			if !is_unquoted_char(c) {
+				break;
+			}
+
+			self.index += 1;
+		}
+
+		if start == self.index {
+			return Err(self.error("Expected value"));
+		}
+
+		Ok(&self.input[start..self.index])
+	}
+}
+
+/// Figure out the type of an unquoted value from its suffix and format. Anything that is not a valid
+/// number is a string, which is also what happens to numbers that are out of range for their type.
+/// Unlike vanilla, `NaN` and `Infinity` with a float suffix are read as numbers so that they can be
+/// read back after [NbtTag::to_snbt].
+fn parse_primitive(s: &str) -> NbtTag {
+	match s {
+		"true" => return NbtTag::Byte(1),
+		"false" => return NbtTag::Byte(0),
+		_ => {}
+	}
+
+	let (body, suffix) = s.split_at(s.len() - 1);
+	let suffix = suffix.chars().next().unwrap_or_default().to_ascii_lowercase();
+
+	let tag = match suffix {
+		'b' if is_integer(body) => body.parse().ok().map(NbtTag::Byte),
+		's' if is_integer(body) => body.parse().ok().map(NbtTag::Short),
+		'l' if is_integer(body) => body.parse().ok().map(NbtTag::Long),
+		'f' if is_decimal(body) || is_integer(body) || is_non_finite(body) => body.parse().ok().map(NbtTag::Float),
+		'd' if is_decimal(body) || is_integer(body) || is_non_finite(body) => body.parse().ok().map(NbtTag::Double),
+		_ if is_integer(s) => s.parse().ok().map(NbtTag::Int),
+		_ if is_decimal(s) => s.parse().ok().map(NbtTag::Double),
+		_ => None,
+	};
+
+	tag.unwrap_or_else(|| NbtTag::String(s.to_string()))
+}
+
+/// Matches `[-+]?(0|[1-9][0-9]*)`
+fn is_integer(s: &str) -> bool {
+	let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+
+	!digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) && (digits == "0" || !digits.starts_with('0'))
+}
+
+/// Matches `NaN`, `Infinity` and `-Infinity`, which is how Java writes floats that aren't finite
+fn is_non_finite(s: &str) -> bool {
+	matches!(s, "NaN" | "Infinity" | "-Infinity")
+}
+
+/// Matches `[-+]?([0-9]+\.|[0-9]*\.[0-9]+)(e[-+]?[0-9]+)?`
+fn is_decimal(s: &str) -> bool {
+	let s = s.strip_prefix(['-', '+']).unwrap_or(s);
+
+	let (mantissa, exponent) = match s.find(['e', 'E']) {
+		Some(i) => (&s[..i], Some(&s[i + 1..])),
+		None => (s, None),
+	};
+
+	if let Some(exponent) = exponent {
+		let exponent = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+
+		if exponent.is_empty() || !exponent.chars().all(|c| c.is_ascii_digit()) {
+			return false;
+		}
+	}
+
+	match mantissa.split_once('.') {
+		Some((whole, fraction)) => {
+			(!whole.is_empty() || !fraction.is_empty())
+				&& whole.chars().all(|c| c.is_ascii_digit())
+				&& fraction.chars().all(|c| c.is_ascii_digit())
+		}
+		None => false,
+	}
+}
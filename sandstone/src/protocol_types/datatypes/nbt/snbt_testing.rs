@@ -1,4 +1,5 @@
-use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtTag};
+use crate::protocol_types::datatypes::nbt::nbt::{NbtByteArray, NbtCompound, NbtIntArray, NbtList, NbtLongArray, NbtTag};
+use crate::protocol_types::datatypes::nbt::nbt_error::NbtError;
 
 #[test]
 pub fn simple_test() {
@@ -8,6 +9,75 @@ pub fn simple_test() {
 	nbt.add("name", NbtTag::String("dec4234".to_string()));
 	
 	let nbt = NbtTag::Compound(nbt);
+	assert_eq!(nbt.to_snbt(), r#"{name:"dec4234"}"#);
+}
+
+#[test]
+pub fn test_snbt_primitives() {
+	assert_eq!(NbtTag::from_snbt("1b").unwrap(), NbtTag::Byte(1));
+	assert_eq!(NbtTag::from_snbt("-12s").unwrap(), NbtTag::Short(-12));
+	assert_eq!(NbtTag::from_snbt("300").unwrap(), NbtTag::Int(300));
+	assert_eq!(NbtTag::from_snbt("9000000000L").unwrap(), NbtTag::Long(9000000000));
+	assert_eq!(NbtTag::from_snbt("0.5f").unwrap(), NbtTag::Float(0.5));
+	assert_eq!(NbtTag::from_snbt("1.25").unwrap(), NbtTag::Double(1.25));
+	assert_eq!(NbtTag::from_snbt("3d").unwrap(), NbtTag::Double(3.0));
+	assert_eq!(NbtTag::from_snbt("true").unwrap(), NbtTag::Byte(1));
+	assert_eq!(NbtTag::from_snbt("300b").unwrap(), NbtTag::String("300b".to_string())); // out of range
+	assert!(NbtTag::from_snbt("minecraft:stone").is_err()); // ":" needs quotes
+	assert_eq!(NbtTag::from_snbt(r#""minecraft:stone""#).unwrap(), NbtTag::String("minecraft:stone".to_string()));
+	assert_eq!(NbtTag::from_snbt(r#"'say "hi" \\ \''"#).unwrap(), NbtTag::String(r#"say "hi" \ '"#.to_string()));
+}
+
+#[test]
+pub fn test_snbt_compound() {
+	let input = r#"{foo:1b, bar:[I;1,2,3], "spaced key": 'text', nested: {list: [1.5f, 2f], longs: [L; 1L, -2L], bytes: [B;]}}"#;
+	let tag = NbtTag::from_snbt(input).unwrap();
+
+	let mut nested = NbtCompound::new::<String>(None);
+	nested.add("list", NbtList::from_vec(vec![NbtTag::Float(1.5), NbtTag::Float(2.0)]).unwrap());
+	nested.add("longs", NbtLongArray::new(vec![1, -2]));
+	nested.add("bytes", NbtByteArray::new(vec![]));
+
+	let mut compound = NbtCompound::new::<String>(None);
+	compound.add("foo", NbtTag::Byte(1));
+	compound.add("bar", NbtIntArray::new(vec![1, 2, 3]));
+	compound.add("spaced key", "text");
+	compound.add("nested", nested);
+
+	assert_eq!(tag, NbtTag::Compound(compound.clone()));
+	assert_eq!(compound.to_snbt(), r#"{bar:[I;1,2,3],foo:1b,nested:{bytes:[B;],list:[1.5f,2.0f],longs:[L;1L,-2L]},"spaced key":"text"}"#);
+
+	// printing and parsing again should give the same tag
+	assert_eq!(NbtCompound::from_snbt(&compound.to_snbt()).unwrap(), compound);
+}
+
+#[test]
+pub fn test_snbt_decimals() {
+	let round_trip = |tag: NbtTag| NbtTag::from_snbt(&tag.to_snbt()).unwrap();
+
+	assert_eq!(NbtTag::Float(f32::INFINITY).to_snbt(), "Infinityf");
+	assert_eq!(NbtTag::Double(f64::NEG_INFINITY).to_snbt(), "-Infinityd");
+	assert_eq!(NbtTag::Double(f64::NAN).to_snbt(), "NaNd");
+	assert_eq!(NbtTag::Float(1e20).to_snbt(), "1.0e20f");
+	assert_eq!(NbtTag::Double(-2.5e-10).to_snbt(), "-2.5e-10d");
+
+	for tag in [NbtTag::Float(f32::INFINITY), NbtTag::Float(f32::NEG_INFINITY), NbtTag::Double(f64::INFINITY), NbtTag::Float(1e20), NbtTag::Double(1e-300), NbtTag::Float(0.1)] {
+		assert_eq!(round_trip(tag.clone()), tag);
+	}
+
+	assert!(matches!(round_trip(NbtTag::Float(f32::NAN)), NbtTag::Float(f) if f.is_nan()));
+	assert!(matches!(round_trip(NbtTag::Double(f64::NAN)), NbtTag::Double(d) if d.is_nan()));
+	assert_eq!(NbtTag::from_snbt("Infinity").unwrap(), NbtTag::String("Infinity".to_string())); // needs a suffix
+}
+
+#[test]
+pub fn test_snbt_errors() {
+	assert!(matches!(NbtTag::from_snbt("{a:1"), Err(NbtError::InvalidSnbt(_))));
+	assert!(matches!(NbtTag::from_snbt("[1, 2b]"), Err(NbtError::InvalidSnbt(_))));
+	assert!(matches!(NbtTag::from_snbt("[I; 1, 2.0]"), Err(NbtError::InvalidSnbt(_))));
+	assert!(matches!(NbtTag::from_snbt("{a:1} extra"), Err(NbtError::InvalidSnbt(_))));
+	assert!(matches!(NbtTag::from_snbt("'unterminated"), Err(NbtError::InvalidSnbt(_))));
+	assert_eq!(NbtCompound::from_snbt("[]"), Err(NbtError::MismatchedTypes));
 }
 
 /**
@@ -0,0 +1,372 @@
+//! Paletted containers hold the block states and biomes of a chunk section. The values are registry ids,
+//! which are packed into longs with as few bits as possible: a section made of one block needs no data
+//! at all, a section with a few kinds of block indexes into a palette of them, and a section with many
+//! stores the ids directly.
+//!
+//! See [here](https://wiki.vg/Chunk_Format#Paletted_Container_structure) for more.
+
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+/// How a kind of container is laid out and packed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContainerKind {
+	/// The length of a side, 16 for block states and 4 for biomes
+	pub side: usize,
+	/// The fewest bits used with a palette. The client reads anything less as this many bits.
+	pub min_indirect_bits: u8,
+	/// The most bits used with a palette, any more and the ids are stored directly
+	pub max_indirect_bits: u8,
+	/// The bits used to store an id directly, which depends on the size of the registry
+	pub direct_bits: u8,
+}
+
+impl ContainerKind {
+	/// Block states, 16x16x16 per section. 1.21 has just under 2^15 block states.
+	pub const BLOCK_STATES: ContainerKind = ContainerKind { side: 16, min_indirect_bits: 4, max_indirect_bits: 8, direct_bits: 15 };
+	/// Biomes, 4x4x4 per section. The 64 vanilla biomes of 1.21 fit in 6 bits.
+	pub const BIOMES: ContainerKind = ContainerKind { side: 4, min_indirect_bits: 1, max_indirect_bits: 3, direct_bits: 6 };
+
+	/// The number of values in a container
+	pub const fn entries(&self) -> usize {
+		self.side * self.side * self.side
+	}
+
+	/// The index of a position in the container, with each coordinate from 0 up to `side`
+	pub const fn index(&self, x: usize, y: usize, z: usize) -> usize {
+		(y * self.side + z) * self.side + x
+	}
+
+	/// The same kind, for a registry with `size` entries
+	pub fn with_registry_size(mut self, size: usize) -> Self {
+		self.direct_bits = bits_for(size).max(1);
+		self
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Storage {
+	Single(u32),
+	Values(Box<[u32]>),
+}
+
+/// The ids of every position in a section, see the [module docs](self)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PalettedContainer {
+	kind: ContainerKind,
+	storage: Storage,
+}
+
+impl PalettedContainer {
+	/// A container where every position has the same id
+	pub fn single(kind: ContainerKind, id: u32) -> Self {
+		Self {
+			kind,
+			storage: Storage::Single(id),
+		}
+	}
+
+	/// A container from the id of each position, in [ContainerKind::index] order
+	pub fn from_values(kind: ContainerKind, values: Vec<u32>) -> Result<Self, SerializingErr> {
+		if values.len() != kind.entries() {
+			return Err(SerializingErr::UniqueFailure(format!("Expected {} values in a paletted container, got {}", kind.entries(), values.len())));
+		}
+
+		let mut container = Self {
+			kind,
+			storage: Storage::Values(values.into_boxed_slice()),
+		};
+
+		container.compact();
+		Ok(container)
+	}
+
+	pub fn kind(&self) -> ContainerKind {
+		self.kind
+	}
+
+	pub fn get(&self, x: usize, y: usize, z: usize) -> u32 {
+		self.get_index(self.kind.index(x, y, z))
+	}
+
+	pub fn get_index(&self, index: usize) -> u32 {
+		match &self.storage {
+			Storage::Single(id) => *id,
+			Storage::Values(values) => values[index],
+		}
+	}
+
+	/// Set the id at a position, returning the id that was there
+	pub fn set(&mut self, x: usize, y: usize, z: usize, id: u32) -> u32 {
+		self.set_index(self.kind.index(x, y, z), id)
+	}
+
+	pub fn set_index(&mut self, index: usize, id: u32) -> u32 {
+		if let Storage::Single(current) = self.storage {
+			if current == id {
+				return id;
+			}
+
+			self.storage = Storage::Values(vec![current; self.kind.entries()].into_boxed_slice());
+		}
+
+		match &mut self.storage {
+			Storage::Values(values) => std::mem::replace(&mut values[index], id),
+			Storage::Single(_) => unreachable!("the storage was just expanded"),
+		}
+	}
+
+	/// Set every position to the same id
+	pub fn fill(&mut self, id: u32) {
+		self.storage = Storage::Single(id);
+	}
+
+	/// The id of every position, in [ContainerKind::index] order
+	pub fn values(&self) -> Vec<u32> {
+		match &self.storage {
+			Storage::Single(id) => vec![*id; self.kind.entries()],
+			Storage::Values(values) => values.to_vec(),
+		}
+	}
+
+	/// The id shared by every position, if there is one
+	pub fn single_value(&self) -> Option<u32> {
+		match &self.storage {
+			Storage::Single(id) => Some(*id),
+			Storage::Values(_) => None,
+		}
+	}
+
+	/// How many positions have an id that matches
+	pub fn count<F: Fn(u32) -> bool>(&self, filter: F) -> usize {
+		match &self.storage {
+			Storage::Single(id) => if filter(*id) { self.kind.entries() } else { 0 },
+			Storage::Values(values) => values.iter().filter(|id| filter(**id)).count(),
+		}
+	}
+
+	/// The distinct ids, in the order they first appear
+	pub fn palette(&self) -> Vec<u32> {
+		match &self.storage {
+			Storage::Single(id) => vec![*id],
+			Storage::Values(values) => {
+				let mut palette = vec![];
+
+				for id in values.iter() {
+					if !palette.contains(id) {
+						palette.push(*id);
+					}
+				}
+
+				palette
+			}
+		}
+	}
+
+	/// Switch back to a single id if every position has the same one
+	fn compact(&mut self) {
+		if let Storage::Values(values) = &self.storage {
+			if values.iter().all(|id| *id == values[0]) {
+				self.storage = Storage::Single(values[0]);
+			}
+		}
+	}
+
+	/// Read a container of a kind from the network
+	pub fn read(deserializer: &mut McDeserializer, kind: ContainerKind) -> Result<Self, SerializingErr> {
+		let bits = u8::mc_deserialize(deserializer)?;
+
+		if bits == 0 {
+			let id = VarInt::mc_deserialize(deserializer)?.0 as u32;
+			read_longs(deserializer, 0)?;
+			return Ok(Self::single(kind, id));
+		}
+
+		// ids are at most 32 bits, and more than 64 would break packing
+		if bits > kind.direct_bits.min(32) {
+			return Err(SerializingErr::UniqueFailure(format!("{} bits per entry is more than the {} of a direct container", bits, kind.direct_bits)));
+		}
+
+		let (bits, palette) = if bits <= kind.max_indirect_bits {
+			let palette = Vec::<VarInt>::mc_deserialize(deserializer)?;
+			(bits.max(kind.min_indirect_bits), Some(palette))
+		} else {
+			(bits, None)
+		};
+
+		let longs = read_longs(deserializer, longs_needed(kind.entries(), bits))?;
+		let mut values = unpack(&longs, bits, kind.entries());
+
+		if let Some(palette) = palette {
+			for value in values.iter_mut() {
+				*value = palette.get(*value as usize)
+					.ok_or_else(|| SerializingErr::UniqueFailure(format!("Palette index {} is out of bounds", value)))?.0 as u32;
+			}
+		}
+
+		Self::from_values(kind, values)
+	}
+}
+
+impl McSerialize for PalettedContainer {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		let palette = self.palette();
+
+		if palette.len() == 1 {
+			0u8.mc_serialize(serializer)?;
+			VarInt(palette[0] as i32).mc_serialize(serializer)?;
+			return VarInt(0).mc_serialize(serializer);
+		}
+
+		let bits = bits_for(palette.len()).max(self.kind.min_indirect_bits);
+		let values = self.values();
+
+		let (bits, values) = if bits <= self.kind.max_indirect_bits {
+			bits.mc_serialize(serializer)?;
+			palette.iter().map(|id| VarInt(*id as i32)).collect::<Vec<_>>().mc_serialize(serializer)?;
+
+			let indices = values.iter()
+				.map(|id| palette.iter().position(|p| p == id).expect("every value is in the palette") as u32)
+				.collect();
+
+			(bits, indices)
+		} else {
+			let bits = self.kind.direct_bits;
+
+			if let Some(id) = values.iter().find(|id| bits_for(**id as usize + 1) > bits) {
+				return Err(SerializingErr::UniqueFailure(format!("Id {} doesn't fit in {} bits", id, bits)));
+			}
+
+			bits.mc_serialize(serializer)?;
+			(bits, values)
+		};
+
+		pack(&values, bits).mc_serialize(serializer)
+	}
+}
+
+/// The number of bits needed to store `count` different values
+pub(crate) fn bits_for(count: usize) -> u8 {
+	if count <= 1 {
+		0
+	} else {
+		(usize::BITS - (count - 1).leading_zeros()) as u8
+	}
+}
+
+/// The number of longs that hold `entries` values of `bits` each. Values never span two longs.
+pub(crate) fn longs_needed(entries: usize, bits: u8) -> usize {
+	let per_long = 64 / bits as usize;
+	entries.div_ceil(per_long)
+}
+
+/// Pack values of `bits` each into longs. Values that don't fit are cut down to `bits` instead of spilling
+/// into the next value, so they have to be checked beforehand.
+pub(crate) fn pack(values: &[u32], bits: u8) -> Vec<i64> {
+	let per_long = 64 / bits as usize;
+	let mask = (1u64 << bits) - 1;
+	let mut longs = vec![0i64; longs_needed(values.len(), bits)];
+
+	for (i, value) in values.iter().enumerate() {
+		longs[i / per_long] |= ((*value as u64 & mask) << ((i % per_long) * bits as usize)) as i64;
+	}
+
+	longs
+}
+
+pub(crate) fn unpack(longs: &[i64], bits: u8, entries: usize) -> Vec<u32> {
+	let per_long = 64 / bits as usize;
+	let mask = (1u64 << bits) - 1;
+
+	(0..entries)
+		.map(|i| ((longs[i / per_long] as u64 >> ((i % per_long) * bits as usize)) & mask) as u32)
+		.collect()
+}
+
+fn read_longs(deserializer: &mut McDeserializer, expected: usize) -> Result<Vec<i64>, SerializingErr> {
+	let longs = Vec::<i64>::mc_deserialize(deserializer)?;
+
+	if longs.len() != expected {
+		return Err(SerializingErr::UniqueFailure(format!("Expected {} longs in a paletted container, got {}", expected, longs.len())));
+	}
+
+	Ok(longs)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::serialization::{McDeserializer, McSerialize, McSerializer};
+	use crate::protocol_types::datatypes::paletted_container::{bits_for, ContainerKind, pack, PalettedContainer, unpack};
+
+	fn round_trip(container: &PalettedContainer) -> (usize, PalettedContainer) {
+		let mut serializer = McSerializer::new();
+		container.mc_serialize(&mut serializer).unwrap();
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		let out = PalettedContainer::read(&mut deserializer, container.kind()).unwrap();
+		assert!(deserializer.is_at_end());
+
+		(serializer.output.len(), out)
+	}
+
+	#[test]
+	fn test_paletted_container() {
+		let kind = ContainerKind::BLOCK_STATES;
+
+		// single valued: bits, id and an empty data array
+		let mut container = PalettedContainer::single(kind, 1);
+		assert_eq!(round_trip(&container), (3, container.clone()));
+
+		// a palette of 2 still uses 4 bits
+		container.set(1, 2, 3, 9);
+		assert_eq!(container.get(1, 2, 3), 9);
+		let (len, out) = round_trip(&container);
+		assert_eq!(out, container);
+		assert_eq!(len, 1 + 3 + 2 + 256 * 8);
+
+		// too many for a palette
+		let values = (0..kind.entries() as u32).map(|i| i % 300).collect();
+		let container = PalettedContainer::from_values(kind, values).unwrap();
+		assert_eq!(round_trip(&container).1, container);
+		assert_eq!(container.count(|id| id == 0), 14);
+
+		// biomes pack into fewer bits
+		let mut biomes = PalettedContainer::single(ContainerKind::BIOMES, 0);
+		for i in 0..5 {
+			biomes.set_index(i, i as u32);
+		}
+		assert_eq!(round_trip(&biomes).1, biomes);
+
+		let compact = PalettedContainer::from_values(ContainerKind::BIOMES, vec![3; 64]).unwrap();
+		assert_eq!(compact.single_value(), Some(3));
+	}
+
+	#[test]
+	fn test_invalid_bits() {
+		for bits in [16u8, 33, 64, 200] {
+			let mut serializer = McSerializer::new();
+			bits.mc_serialize(&mut serializer).unwrap();
+			vec![0i64; 256].mc_serialize(&mut serializer).unwrap();
+
+			let mut deserializer = McDeserializer::new(&serializer.output);
+			assert!(PalettedContainer::read(&mut deserializer, ContainerKind::BLOCK_STATES).is_err());
+		}
+	}
+
+	#[test]
+	fn test_pack() {
+		// a value too wide for its bits doesn't spill into the next one
+		assert_eq!(pack(&[0xFF, 1, 2], 4), vec![0xF | 1 << 4 | 2 << 8]);
+		assert_eq!(unpack(&pack(&[u32::MAX, 7], 32), 32, 2), vec![u32::MAX, 7]);
+	}
+
+	#[test]
+	fn test_bits_for() {
+		assert_eq!(bits_for(1), 0);
+		assert_eq!(bits_for(2), 1);
+		assert_eq!(bits_for(16), 4);
+		assert_eq!(bits_for(17), 5);
+		assert_eq!(ContainerKind::BIOMES.with_registry_size(65).direct_bits, 7);
+	}
+}
@@ -0,0 +1,127 @@
+//! The block position type used by the protocol, which packs all three coordinates into a single long.
+//! See more details here: https://wiki.vg/Protocol#Position
+
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+
+const MAX_XZ: i32 = (1 << 25) - 1; // 26 bits, signed
+const MIN_XZ: i32 = -(1 << 25);
+const MAX_Y: i32 = (1 << 11) - 1; // 12 bits, signed
+const MIN_Y: i32 = -(1 << 11);
+
+/// The position of a block. X and Z are stored in 26 bits and Y in 12 bits, so each must be within
+/// -33554432 to 33554431 and -2048 to 2047 respectively to be serialized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct Position {
+	pub x: i32,
+	pub y: i32,
+	pub z: i32,
+}
+
+impl Position {
+	pub const fn new(x: i32, y: i32, z: i32) -> Self {
+		Self {
+			x,
+			y,
+			z
+		}
+	}
+
+	/// Check that every coordinate fits in the number of bits it is packed into
+	pub const fn is_valid(&self) -> bool {
+		self.x >= MIN_XZ && self.x <= MAX_XZ && self.z >= MIN_XZ && self.z <= MAX_XZ && self.y >= MIN_Y && self.y <= MAX_Y
+	}
+
+	/// Pack the position into a long, as `x` (26 bits), `z` (26 bits) and then `y` (12 bits)
+	pub fn encode(&self) -> Result<i64, SerializingErr> {
+		if !self.is_valid() {
+			return Err(SerializingErr::UniqueFailure(format!("Position {} is out of bounds", self)));
+		}
+
+		Ok(((self.x as i64 & 0x3FFFFFF) << 38) | ((self.z as i64 & 0x3FFFFFF) << 12) | (self.y as i64 & 0xFFF))
+	}
+
+	/// Unpack a position from a long. Every long is a valid position, so this can't fail.
+	pub const fn decode(value: i64) -> Self {
+		Self {
+			x: (value >> 38) as i32,
+			y: (value << 52 >> 52) as i32, // shift up and back down to sign extend
+			z: (value << 26 >> 38) as i32,
+		}
+	}
+}
+
+impl Display for Position {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "({}, {}, {})", self.x, self.y, self.z)
+	}
+}
+
+impl McSerialize for Position {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.encode()?.mc_serialize(serializer)
+	}
+}
+
+impl McDeserialize for Position {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		Ok(Position::decode(i64::mc_deserialize(deserializer)?))
+	}
+}
+
+impl TryFrom<(i32, i32, i32)> for Position {
+	type Error = SerializingErr;
+
+	/// Create a position from `(x, y, z)`, failing if any coordinate is out of bounds
+	fn try_from((x, y, z): (i32, i32, i32)) -> Result<Self, Self::Error> {
+		let position = Position::new(x, y, z);
+
+		if !position.is_valid() {
+			return Err(SerializingErr::UniqueFailure(format!("Position {} is out of bounds", position)));
+		}
+
+		Ok(position)
+	}
+}
+
+impl From<Position> for (i32, i32, i32) {
+	fn from(position: Position) -> Self {
+		(position.x, position.y, position.z)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
+	use crate::protocol_types::datatypes::position::Position;
+
+	#[test]
+	fn test_position_encoding() {
+		// example from wiki.vg
+		let position = Position::new(18357644, 831, -20882616);
+		assert_eq!(position.encode().unwrap(), 0b01000110000001110110001100_10110000010101101101001000_001100111111);
+		assert_eq!(Position::decode(position.encode().unwrap()), position);
+
+		for position in [Position::new(0, 0, 0), Position::new(-1, -1, -1), Position::new(33554431, 2047, -33554432), Position::new(-33554432, -2048, 33554431)] {
+			let mut serializer = McSerializer::new();
+			position.mc_serialize(&mut serializer).unwrap();
+
+			let mut deserializer = McDeserializer::new(&serializer.output);
+			assert_eq!(Position::mc_deserialize(&mut deserializer).unwrap(), position);
+		}
+	}
+
+	#[test]
+	fn test_position_bounds() {
+		assert!(Position::new(33554432, 0, 0).encode().is_err());
+		assert!(Position::new(0, -2049, 0).encode().is_err());
+		assert!(Position::try_from((0, 2048, 0)).is_err());
+
+		let position = Position::try_from((1, 2, 3)).unwrap();
+		assert_eq!(<(i32, i32, i32)>::from(position), (1, 2, 3));
+	}
+}
@@ -0,0 +1,577 @@
+//! Slots hold a stack of items in an inventory, container or equipment packet.
+//!
+//! Since 1.20.5 an item is sent as its id and count followed by the components that differ from the
+//! defaults of that item, see [Slot]. Older versions send the id, count and an optional NBT compound
+//! instead, see [LegacySlot].
+//! See more details here: https://wiki.vg/Slot_Data
+
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::chat::TextComponent;
+use crate::protocol_types::datatypes::identifier::Identifier;
+use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtTag};
+use crate::protocol_types::datatypes::var_types::VarInt;
+
+/// A slot which may hold an [ItemStack], in the format used since 1.20.5
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Slot {
+	pub item: Option<ItemStack>,
+}
+
+impl Slot {
+	pub fn empty() -> Self {
+		Self {
+			item: None,
+		}
+	}
+
+	/// Create a slot holding `count` of an item without any components
+	pub fn new(item_id: i32, count: i32) -> Self {
+		Self {
+			item: Some(ItemStack::new(item_id, count)),
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.item.is_none()
+	}
+}
+
+impl From<ItemStack> for Slot {
+	fn from(item: ItemStack) -> Self {
+		Self {
+			item: Some(item),
+		}
+	}
+}
+
+impl McSerialize for Slot {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		match &self.item {
+			Some(item) => item.mc_serialize(serializer),
+			None => VarInt(0).mc_serialize(serializer),
+		}
+	}
+}
+
+impl McDeserialize for Slot {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let count = VarInt::mc_deserialize(deserializer)?.0;
+
+		if count <= 0 { // vanilla treats any stack without items as empty
+			return Ok(Slot::empty());
+		}
+
+		let item_id = VarInt::mc_deserialize(deserializer)?;
+		let added_count = read_length(deserializer)?;
+		let removed_count = read_length(deserializer)?;
+
+		let mut added = vec![]; // don't trust the lengths for the allocations
+		for _ in 0..added_count {
+			added.push(ItemComponent::mc_deserialize(deserializer)?);
+		}
+
+		let mut removed = vec![];
+		for _ in 0..removed_count {
+			removed.push(VarInt::mc_deserialize(deserializer)?);
+		}
+
+		Ok(Slot::from(ItemStack {
+			item_id,
+			count,
+			added,
+			removed,
+		}))
+	}
+}
+
+/// A stack of items, along with the components that change it from the default for that item
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemStack {
+	/// The id of the item in the `minecraft:item` registry
+	pub item_id: VarInt,
+	pub count: i32,
+	/// Components that are added or replace the default component of the same type
+	pub added: Vec<ItemComponent>,
+	/// The type ids of default components that this stack doesn't have
+	pub removed: Vec<VarInt>,
+}
+
+impl ItemStack {
+	pub fn new(item_id: i32, count: i32) -> Self {
+		Self {
+			item_id: VarInt(item_id),
+			count,
+			added: vec![],
+			removed: vec![],
+		}
+	}
+
+	/// Add a component, replacing any added component of the same type
+	pub fn with_component(mut self, component: ItemComponent) -> Self {
+		self.added.retain(|c| c.type_id() != component.type_id());
+		self.added.push(component);
+		self
+	}
+
+	/// Remove a default component of the item by its type id
+	pub fn without_component(mut self, type_id: i32) -> Self {
+		self.added.retain(|c| c.type_id() != type_id);
+		self.removed.push(VarInt(type_id));
+		self
+	}
+
+	/// Get an added component by its type id
+	pub fn component(&self, type_id: i32) -> Option<&ItemComponent> {
+		self.added.iter().find(|c| c.type_id() == type_id)
+	}
+}
+
+impl McSerialize for ItemStack {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		if self.count <= 0 {
+			return Err(SerializingErr::UniqueFailure(format!("An item stack must have at least one item, found {}", self.count)));
+		}
+
+		VarInt(self.count).mc_serialize(serializer)?;
+		self.item_id.mc_serialize(serializer)?;
+		VarInt(self.added.len() as i32).mc_serialize(serializer)?;
+		VarInt(self.removed.len() as i32).mc_serialize(serializer)?;
+
+		for component in &self.added {
+			component.mc_serialize(serializer)?;
+		}
+
+		for type_id in &self.removed {
+			type_id.mc_serialize(serializer)?;
+		}
+
+		Ok(())
+	}
+}
+
+/// The rarity of an item, which sets the default color of its name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rarity {
+	Common = 0,
+	Uncommon = 1,
+	Rare = 2,
+	Epic = 3,
+}
+
+impl TryFrom<i32> for Rarity {
+	type Error = SerializingErr;
+
+	fn try_from(value: i32) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(Rarity::Common),
+			1 => Ok(Rarity::Uncommon),
+			2 => Ok(Rarity::Rare),
+			3 => Ok(Rarity::Epic),
+			_ => Err(SerializingErr::UniqueFailure(format!("Unknown rarity {}", value))),
+		}
+	}
+}
+
+/// A single enchantment, by its id in the `minecraft:enchantment` registry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Enchantment {
+	pub id: VarInt,
+	pub level: VarInt,
+}
+
+/// The data components of an item as of 1.21 (protocol 767). Only components with a simple encoding are
+/// supported so far; reading a slot with any other component fails, since there is no way to know how
+/// long an unknown component is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemComponent {
+	CustomData(NbtCompound),
+	MaxStackSize(VarInt),
+	MaxDamage(VarInt),
+	Damage(VarInt),
+	Unbreakable { show_in_tooltip: bool },
+	CustomName(TextComponent),
+	ItemName(TextComponent),
+	Lore(Vec<TextComponent>),
+	Rarity(Rarity),
+	Enchantments { enchantments: Vec<Enchantment>, show_in_tooltip: bool },
+	CustomModelData(VarInt),
+	HideAdditionalTooltip,
+	HideTooltip,
+	RepairCost(VarInt),
+	CreativeSlotLock,
+	EnchantmentGlintOverride(bool),
+	IntangibleProjectile,
+	FireResistant,
+	StoredEnchantments { enchantments: Vec<Enchantment>, show_in_tooltip: bool },
+	DyedColor { color: i32, show_in_tooltip: bool },
+	MapColor(i32),
+	MapId(VarInt),
+	MapDecorations(NbtCompound),
+	MapPostProcessing(VarInt),
+	ChargedProjectiles(Vec<Slot>),
+	BundleContents(Vec<Slot>),
+	DebugStickState(NbtCompound),
+	EntityData(NbtCompound),
+	BucketEntityData(NbtCompound),
+	BlockEntityData(NbtCompound),
+	OminousBottleAmplifier(VarInt),
+	Recipes(NbtCompound),
+	NoteBlockSound(Identifier),
+	BaseColor(VarInt),
+	PotDecorations(Vec<VarInt>),
+	Container(Vec<Slot>),
+	/// Block state properties as pairs of name and value
+	BlockState(Vec<(String, String)>),
+	Lock(NbtCompound),
+	ContainerLoot(NbtCompound),
+}
+
+impl ItemComponent {
+	/// The id of this component's type in the `minecraft:data_component_type` registry
+	pub fn type_id(&self) -> i32 {
+		match self {
+			ItemComponent::CustomData(_) => 0,
+			ItemComponent::MaxStackSize(_) => 1,
+			ItemComponent::MaxDamage(_) => 2,
+			ItemComponent::Damage(_) => 3,
+			ItemComponent::Unbreakable { .. } => 4,
+			ItemComponent::CustomName(_) => 5,
+			ItemComponent::ItemName(_) => 6,
+			ItemComponent::Lore(_) => 7,
+			ItemComponent::Rarity(_) => 8,
+			ItemComponent::Enchantments { .. } => 9,
+			ItemComponent::CustomModelData(_) => 13,
+			ItemComponent::HideAdditionalTooltip => 14,
+			ItemComponent::HideTooltip => 15,
+			ItemComponent::RepairCost(_) => 16,
+			ItemComponent::CreativeSlotLock => 17,
+			ItemComponent::EnchantmentGlintOverride(_) => 18,
+			ItemComponent::IntangibleProjectile => 19,
+			ItemComponent::FireResistant => 21,
+			ItemComponent::StoredEnchantments { .. } => 23,
+			ItemComponent::DyedColor { .. } => 24,
+			ItemComponent::MapColor(_) => 25,
+			ItemComponent::MapId(_) => 26,
+			ItemComponent::MapDecorations(_) => 27,
+			ItemComponent::MapPostProcessing(_) => 28,
+			ItemComponent::ChargedProjectiles(_) => 29,
+			ItemComponent::BundleContents(_) => 30,
+			ItemComponent::DebugStickState(_) => 36,
+			ItemComponent::EntityData(_) => 37,
+			ItemComponent::BucketEntityData(_) => 38,
+			ItemComponent::BlockEntityData(_) => 39,
+			ItemComponent::OminousBottleAmplifier(_) => 41,
+			ItemComponent::Recipes(_) => 43,
+			ItemComponent::NoteBlockSound(_) => 48,
+			ItemComponent::BaseColor(_) => 50,
+			ItemComponent::PotDecorations(_) => 51,
+			ItemComponent::Container(_) => 52,
+			ItemComponent::BlockState(_) => 53,
+			ItemComponent::Lock(_) => 55,
+			ItemComponent::ContainerLoot(_) => 56,
+		}
+	}
+}
+
+impl McSerialize for ItemComponent {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		VarInt(self.type_id()).mc_serialize(serializer)?;
+
+		match self {
+			ItemComponent::CustomData(nbt) | ItemComponent::MapDecorations(nbt) | ItemComponent::DebugStickState(nbt)
+			| ItemComponent::EntityData(nbt) | ItemComponent::BucketEntityData(nbt) | ItemComponent::BlockEntityData(nbt)
+			| ItemComponent::Recipes(nbt) | ItemComponent::Lock(nbt) | ItemComponent::ContainerLoot(nbt) => {
+				write_nbt(serializer, nbt)?;
+			}
+			ItemComponent::MaxStackSize(v) | ItemComponent::MaxDamage(v) | ItemComponent::Damage(v)
+			| ItemComponent::CustomModelData(v) | ItemComponent::RepairCost(v) | ItemComponent::MapId(v)
+			| ItemComponent::MapPostProcessing(v) | ItemComponent::OminousBottleAmplifier(v) | ItemComponent::BaseColor(v) => {
+				v.mc_serialize(serializer)?;
+			}
+			ItemComponent::Unbreakable { show_in_tooltip } => {
+				show_in_tooltip.mc_serialize(serializer)?;
+			}
+			ItemComponent::CustomName(text) | ItemComponent::ItemName(text) => {
+				write_text(serializer, text)?;
+			}
+			ItemComponent::Lore(lines) => {
+				VarInt(lines.len() as i32).mc_serialize(serializer)?;
+
+				for line in lines {
+					write_text(serializer, line)?;
+				}
+			}
+			ItemComponent::Rarity(rarity) => {
+				VarInt(*rarity as i32).mc_serialize(serializer)?;
+			}
+			ItemComponent::Enchantments { enchantments, show_in_tooltip } | ItemComponent::StoredEnchantments { enchantments, show_in_tooltip } => {
+				VarInt(enchantments.len() as i32).mc_serialize(serializer)?;
+
+				for enchantment in enchantments {
+					enchantment.id.mc_serialize(serializer)?;
+					enchantment.level.mc_serialize(serializer)?;
+				}
+
+				show_in_tooltip.mc_serialize(serializer)?;
+			}
+			ItemComponent::HideAdditionalTooltip | ItemComponent::HideTooltip | ItemComponent::CreativeSlotLock
+			| ItemComponent::FireResistant => {}
+			ItemComponent::IntangibleProjectile => { // always an empty compound
+				write_nbt(serializer, &NbtCompound::new::<String>(None))?;
+			}
+			ItemComponent::EnchantmentGlintOverride(b) => {
+				b.mc_serialize(serializer)?;
+			}
+			ItemComponent::DyedColor { color, show_in_tooltip } => {
+				color.mc_serialize(serializer)?;
+				show_in_tooltip.mc_serialize(serializer)?;
+			}
+			ItemComponent::MapColor(color) => {
+				color.mc_serialize(serializer)?;
+			}
+			ItemComponent::ChargedProjectiles(slots) | ItemComponent::BundleContents(slots) | ItemComponent::Container(slots) => {
+				slots.mc_serialize(serializer)?;
+			}
+			ItemComponent::NoteBlockSound(sound) => {
+				sound.mc_serialize(serializer)?;
+			}
+			ItemComponent::PotDecorations(decorations) => {
+				decorations.mc_serialize(serializer)?;
+			}
+			ItemComponent::BlockState(properties) => {
+				properties.mc_serialize(serializer)?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl McDeserialize for ItemComponent {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let type_id = VarInt::mc_deserialize(deserializer)?.0;
+
+		let component = match type_id {
+			0 => ItemComponent::CustomData(NbtCompound::from_network(deserializer)?),
+			1 => ItemComponent::MaxStackSize(VarInt::mc_deserialize(deserializer)?),
+			2 => ItemComponent::MaxDamage(VarInt::mc_deserialize(deserializer)?),
+			3 => ItemComponent::Damage(VarInt::mc_deserialize(deserializer)?),
+			4 => ItemComponent::Unbreakable { show_in_tooltip: bool::mc_deserialize(deserializer)? },
+			5 => ItemComponent::CustomName(read_text(deserializer)?),
+			6 => ItemComponent::ItemName(read_text(deserializer)?),
+			7 => ItemComponent::Lore(read_list(deserializer, read_text)?),
+			8 => ItemComponent::Rarity(Rarity::try_from(VarInt::mc_deserialize(deserializer)?.0)?),
+			9 | 23 => {
+				let enchantments = read_list(deserializer, |deserializer| Ok(Enchantment {
+					id: VarInt::mc_deserialize(deserializer)?,
+					level: VarInt::mc_deserialize(deserializer)?,
+				}))?;
+				let show_in_tooltip = bool::mc_deserialize(deserializer)?;
+
+				if type_id == 9 {
+					ItemComponent::Enchantments { enchantments, show_in_tooltip }
+				} else {
+					ItemComponent::StoredEnchantments { enchantments, show_in_tooltip }
+				}
+			}
+			13 => ItemComponent::CustomModelData(VarInt::mc_deserialize(deserializer)?),
+			14 => ItemComponent::HideAdditionalTooltip,
+			15 => ItemComponent::HideTooltip,
+			16 => ItemComponent::RepairCost(VarInt::mc_deserialize(deserializer)?),
+			17 => ItemComponent::CreativeSlotLock,
+			18 => ItemComponent::EnchantmentGlintOverride(bool::mc_deserialize(deserializer)?),
+			19 => {
+				NbtCompound::from_network(deserializer)?;
+				ItemComponent::IntangibleProjectile
+			}
+			21 => ItemComponent::FireResistant,
+			24 => ItemComponent::DyedColor {
+				color: i32::mc_deserialize(deserializer)?,
+				show_in_tooltip: bool::mc_deserialize(deserializer)?,
+			},
+			25 => ItemComponent::MapColor(i32::mc_deserialize(deserializer)?),
+			26 => ItemComponent::MapId(VarInt::mc_deserialize(deserializer)?),
+			27 => ItemComponent::MapDecorations(NbtCompound::from_network(deserializer)?),
+			28 => ItemComponent::MapPostProcessing(VarInt::mc_deserialize(deserializer)?),
+			29 => ItemComponent::ChargedProjectiles(Vec::<Slot>::mc_deserialize(deserializer)?),
+			30 => ItemComponent::BundleContents(Vec::<Slot>::mc_deserialize(deserializer)?),
+			36 => ItemComponent::DebugStickState(NbtCompound::from_network(deserializer)?),
+			37 => ItemComponent::EntityData(NbtCompound::from_network(deserializer)?),
+			38 => ItemComponent::BucketEntityData(NbtCompound::from_network(deserializer)?),
+			39 => ItemComponent::BlockEntityData(NbtCompound::from_network(deserializer)?),
+			41 => ItemComponent::OminousBottleAmplifier(VarInt::mc_deserialize(deserializer)?),
+			43 => ItemComponent::Recipes(NbtCompound::from_network(deserializer)?),
+			48 => ItemComponent::NoteBlockSound(Identifier::mc_deserialize(deserializer)?),
+			50 => ItemComponent::BaseColor(VarInt::mc_deserialize(deserializer)?),
+			51 => ItemComponent::PotDecorations(Vec::<VarInt>::mc_deserialize(deserializer)?),
+			52 => ItemComponent::Container(Vec::<Slot>::mc_deserialize(deserializer)?),
+			53 => ItemComponent::BlockState(Vec::<(String, String)>::mc_deserialize(deserializer)?),
+			55 => ItemComponent::Lock(NbtCompound::from_network(deserializer)?),
+			56 => ItemComponent::ContainerLoot(NbtCompound::from_network(deserializer)?),
+			_ => return Err(SerializingErr::UniqueFailure(format!("Item component type {} is not supported", type_id))),
+		};
+
+		Ok(component)
+	}
+}
+
+/// A slot in the format used before 1.20.5, where an item is an id, a count and an optional compound
+/// holding all of its extra data.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LegacySlot {
+	pub item: Option<LegacyItemStack>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegacyItemStack {
+	pub item_id: VarInt,
+	pub count: i8,
+	pub nbt: Option<NbtCompound>,
+}
+
+impl McSerialize for LegacySlot {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		match &self.item {
+			Some(item) => {
+				true.mc_serialize(serializer)?;
+				item.item_id.mc_serialize(serializer)?;
+				item.count.mc_serialize(serializer)?;
+
+				match &item.nbt {
+					Some(nbt) => write_nbt(serializer, nbt)?,
+					None => serializer.serialize_u8(0), // End tag
+				}
+			}
+			None => false.mc_serialize(serializer)?,
+		}
+
+		Ok(())
+	}
+}
+
+impl McDeserialize for LegacySlot {
+	/// Deserialize a slot with nameless NBT, as sent since 1.20.2
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		if !bool::mc_deserialize(deserializer)? {
+			return Ok(LegacySlot::default());
+		}
+
+		let item_id = VarInt::mc_deserialize(deserializer)?;
+		let count = i8::mc_deserialize(deserializer)?;
+
+		let nbt = match NbtTag::from_network(deserializer)? {
+			NbtTag::End => None,
+			NbtTag::Compound(compound) => Some(compound),
+			tag => return Err(SerializingErr::UniqueFailure(format!("Expected item NBT to be a compound, found {}", tag.get_name()))),
+		};
+
+		Ok(LegacySlot {
+			item: Some(LegacyItemStack {
+				item_id,
+				count,
+				nbt,
+			}),
+		})
+	}
+}
+
+fn write_nbt(serializer: &mut McSerializer, nbt: &NbtCompound) -> Result<(), SerializingErr> {
+	NbtTag::Compound(nbt.clone()).serialize_network(serializer)
+}
+
+fn write_text(serializer: &mut McSerializer, text: &TextComponent) -> Result<(), SerializingErr> {
+	text.to_nbt().map_err(|e| SerializingErr::UniqueFailure(e.to_string()))?.serialize_network(serializer)
+}
+
+fn read_text(deserializer: &mut McDeserializer) -> Result<TextComponent, SerializingErr> {
+	TextComponent::from_nbt(NbtTag::from_network(deserializer)?).map_err(|e| SerializingErr::UniqueFailure(e.to_string()))
+}
+
+fn read_length(deserializer: &mut McDeserializer) -> Result<i32, SerializingErr> {
+	let length = VarInt::mc_deserialize(deserializer)?.0;
+
+	if length < 0 {
+		return Err(SerializingErr::UniqueFailure(format!("Negative length {}", length)));
+	}
+
+	Ok(length)
+}
+
+/// Read a VarInt-prefixed list, reading each element with `read`
+fn read_list<T, F: Fn(&mut McDeserializer) -> Result<T, SerializingErr>>(deserializer: &mut McDeserializer, read: F) -> Result<Vec<T>, SerializingErr> {
+	let length = read_length(deserializer)?;
+	let mut list = vec![]; // don't trust the length for the allocation
+
+	for _ in 0..length {
+		list.push(read(deserializer)?);
+	}
+
+	Ok(list)
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
+	use crate::protocol_types::datatypes::chat::TextComponent;
+	use crate::protocol_types::datatypes::nbt::nbt::NbtCompound;
+	use crate::protocol_types::datatypes::slot::{Enchantment, ItemComponent, ItemStack, LegacyItemStack, LegacySlot, Rarity, Slot};
+	use crate::protocol_types::datatypes::var_types::VarInt;
+
+	fn round_trip<T: McSerialize + McDeserialize + PartialEq + std::fmt::Debug>(value: &T) {
+		let mut serializer = McSerializer::new();
+		value.mc_serialize(&mut serializer).unwrap();
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(&T::mc_deserialize(&mut deserializer).unwrap(), value);
+		assert!(deserializer.is_at_end());
+	}
+
+	#[test]
+	fn test_slot() {
+		let mut serializer = McSerializer::new();
+		Slot::empty().mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, vec![0]);
+
+		let mut serializer = McSerializer::new();
+		Slot::new(1, 64).mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, vec![64, 1, 0, 0]);
+
+		let mut custom_data = NbtCompound::new::<String>(None);
+		custom_data.add("owner", "sandstone");
+
+		let item = ItemStack::new(802, 1)
+			.with_component(ItemComponent::CustomName(TextComponent::new("Excalibur").color("gold").bold(true)))
+			.with_component(ItemComponent::Lore(vec![TextComponent::new("Very sharp"), TextComponent::new("")]))
+			.with_component(ItemComponent::Rarity(Rarity::Epic))
+			.with_component(ItemComponent::Enchantments { enchantments: vec![Enchantment { id: VarInt(5), level: VarInt(3) }], show_in_tooltip: true })
+			.with_component(ItemComponent::CustomData(custom_data))
+			.with_component(ItemComponent::Container(vec![Slot::new(1, 2), Slot::empty()]))
+			.with_component(ItemComponent::BlockState(vec![("facing".to_string(), "north".to_string())]))
+			.with_component(ItemComponent::HideTooltip)
+			.without_component(3);
+
+		assert_eq!(item.component(8), Some(&ItemComponent::Rarity(Rarity::Epic)));
+		round_trip(&Slot::from(item));
+		round_trip(&Slot::empty());
+	}
+
+	#[test]
+	fn test_unsupported_component() {
+		let mut deserializer = McDeserializer::new(&[1, 1, 1, 0, 10]); // food component
+		assert!(Slot::mc_deserialize(&mut deserializer).is_err());
+
+		let mut serializer = McSerializer::new();
+		assert!(ItemStack::new(1, 0).mc_serialize(&mut serializer).is_err());
+	}
+
+	#[test]
+	fn test_legacy_slot() {
+		let mut nbt = NbtCompound::new::<String>(None);
+		nbt.add("Damage", 5);
+
+		round_trip(&LegacySlot::default());
+		round_trip(&LegacySlot { item: Some(LegacyItemStack { item_id: VarInt(1), count: 3, nbt: None }) });
+		round_trip(&LegacySlot { item: Some(LegacyItemStack { item_id: VarInt(1), count: 3, nbt: Some(nbt) }) });
+	}
+}
@@ -5,6 +5,7 @@ use std::fmt;
 use std::fmt::{Display, Error, Formatter};
 use std::str::FromStr;
 
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use zerocopy::{AsBytes, FromBytes, FromZeroes};
 
@@ -22,7 +23,8 @@ pub(crate) const CONTINUE_BYTE: u8 = 0x80; // 10000000
 /// A VarInt is a packaged i32. It is represented in a more compressed (on average) byte format than
 /// a typical i32. The most significant bit of each byte is used to indicate if there are more bytes
 /// to be read, up to a max of 5.
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, AsBytes, FromBytes, FromZeroes, Clone, Copy)]
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, AsBytes, FromBytes, FromZeroes, Clone, Copy, Serialize, Deserialize)]
+#[serde(transparent)] // plain numbers outside of the protocol, such as in JSON
 #[repr(C)] // TODO: maybe remove
 pub struct VarInt(pub i32);
 
@@ -90,6 +92,48 @@ impl VarInt {
 
 		return var.to_bytes();
 	}
+
+	/// The number of bytes this VarInt takes up once serialized, from 1 to 5
+	pub const fn encoded_len(&self) -> usize {
+		let bits = 32 - (self.0 as u32).leading_zeros() as usize;
+
+		if bits == 0 {
+			1
+		} else {
+			bits.div_ceil(7)
+		}
+	}
+
+	/// Write the VarInt straight into the serializer, without building an intermediate buffer
+	pub fn write_to(&self, serializer: &mut McSerializer) {
+		let mut inner = self.0 as u32; // unsigned, so that the shift fills with zeros
+
+		while inner & !(SEGMENT_INT as u32) != 0 {
+			serializer.serialize_u8(inner as u8 | CONTINUE_BYTE);
+			inner >>= 7;
+		}
+
+		serializer.serialize_u8(inner as u8);
+	}
+
+	/// Read a VarInt one byte at a time, starting at the current index of the deserializer. The index is
+	/// only moved past the VarInt if it was read successfully.
+	pub fn read_from(deserializer: &mut McDeserializer) -> Result<Self, SerializingErr> {
+		let mut i: u32 = 0;
+
+		for pos in 0..5 {
+			let b = *deserializer.data.get(deserializer.index + pos).ok_or(SerializingErr::InvalidEndOfVarInt)?;
+
+			i |= ((b & SEGMENT_INT as u8) as u32) << (7 * pos);
+
+			if b & CONTINUE_BYTE == 0 {
+				deserializer.increment(pos + 1);
+				return Ok(VarInt(i as i32));
+			}
+		}
+
+		Err(SerializingErr::VarTypeTooLong("VarInt must be a max of 5 bytes.".to_string()))
+	}
 }
 
 impl Display for VarInt {
@@ -121,7 +165,7 @@ impl FromStr for VarInt {
 
 impl McSerialize for VarInt {
 	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
-		serializer.serialize_vec(self.to_bytes());
+		self.write_to(serializer);
 
 		Ok(())
 	}
@@ -129,34 +173,7 @@ impl McSerialize for VarInt {
 
 impl McDeserialize for VarInt {
 	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, VarInt> {
-		let mut bytes = Vec::with_capacity(5);
-
-		if deserializer.data.len() == 0 {
-			return Err(SerializingErr::InvalidEndOfVarInt);
-		}
-
-		let mut i = 0;
-
-		while deserializer.data[i + deserializer.index] & CONTINUE_BYTE == CONTINUE_BYTE {
-			if i >= 4 {
-				return Err(SerializingErr::VarTypeTooLong("VarInt must be a max of 5 bytes.".to_string()));
-			}
-
-			bytes.push(deserializer.data[i + deserializer.index]);
-			i += 1;
-		}
-
-		if i == deserializer.data.len() {
-			return Err(SerializingErr::InvalidEndOfVarInt);
-		}
-
-		bytes.push(deserializer.data[i + deserializer.index]);
-
-		deserializer.increment(i + 1);
-
-		let var = VarInt::from_slice(&bytes)?;
-
-		return Ok(var);
+		VarInt::read_from(deserializer)
 	}
 }
 
@@ -175,7 +192,8 @@ impl From<&[u8]> for VarInt {
 /// A VarLong is a packaged i64. It is represented in a more compressed (on average) byte format than
 /// a typical i64. The most significant bit of each byte is used to indicate if there are more bytes
 /// to be read, up to a max of 10.
-#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, AsBytes, FromBytes, FromZeroes, Clone, Copy)]
+#[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Hash, AsBytes, FromBytes, FromZeroes, Clone, Copy, Serialize, Deserialize)]
+#[serde(transparent)] // plain numbers outside of the protocol, such as in JSON
 #[repr(C)] // TODO: maybe remove
 pub struct VarLong(pub i64);
 
@@ -247,6 +265,48 @@ impl VarLong {
 
 		return var.to_bytes();
 	}
+
+	/// The number of bytes this VarLong takes up once serialized, from 1 to 10
+	pub const fn encoded_len(&self) -> usize {
+		let bits = 64 - (self.0 as u64).leading_zeros() as usize;
+
+		if bits == 0 {
+			1
+		} else {
+			bits.div_ceil(7)
+		}
+	}
+
+	/// Write the VarLong straight into the serializer, without building an intermediate buffer
+	pub fn write_to(&self, serializer: &mut McSerializer) {
+		let mut inner = self.0 as u64; // unsigned, so that the shift fills with zeros
+
+		while inner & !(SEGMENT_LONG as u64) != 0 {
+			serializer.serialize_u8(inner as u8 | CONTINUE_BYTE);
+			inner >>= 7;
+		}
+
+		serializer.serialize_u8(inner as u8);
+	}
+
+	/// Read a VarLong one byte at a time, starting at the current index of the deserializer. The index is
+	/// only moved past the VarLong if it was read successfully.
+	pub fn read_from(deserializer: &mut McDeserializer) -> Result<Self, SerializingErr> {
+		let mut i: u64 = 0;
+
+		for pos in 0..10 {
+			let b = *deserializer.data.get(deserializer.index + pos).ok_or(SerializingErr::InvalidEndOfVarInt)?;
+
+			i |= ((b & SEGMENT_LONG as u8) as u64) << (7 * pos);
+
+			if b & CONTINUE_BYTE == 0 {
+				deserializer.increment(pos + 1);
+				return Ok(VarLong(i as i64));
+			}
+		}
+
+		Err(SerializingErr::VarTypeTooLong("VarLong must be a max of 10 bytes.".to_string()))
+	}
 }
 
 impl Display for VarLong {
@@ -278,7 +338,7 @@ impl FromStr for VarLong {
 
 impl McSerialize for VarLong {
 	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
-		serializer.serialize_vec(self.to_bytes());
+		self.write_to(serializer);
 
 		Ok(())
 	}
@@ -286,34 +346,7 @@ impl McSerialize for VarLong {
 
 impl McDeserialize for VarLong {
 	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, VarLong> {
-		let mut bytes = Vec::with_capacity(10);
-
-		if deserializer.data.len() == 0 {
-			return Err(SerializingErr::InvalidEndOfVarInt);
-		}
-
-		let mut i = 0;
-
-		while i + deserializer.index < deserializer.data.len() && deserializer.data[i + deserializer.index] & CONTINUE_BYTE == CONTINUE_BYTE {
-			if i >= 9 {
-				return Err(SerializingErr::VarTypeTooLong("VarLong must be a max of 10 bytes.".to_string()));
-			}
-
-			bytes.push(deserializer.data[i + deserializer.index]);
-			i += 1;
-		}
-
-		if i == deserializer.data.len() {
-			return Err(SerializingErr::InvalidEndOfVarInt);
-		}
-
-		bytes.push(deserializer.data[i]);
-
-		deserializer.increment(i);
-
-		let var = VarLong::from_slice(&bytes)?;
-
-		return Ok(var);
+		VarLong::read_from(deserializer)
 	}
 }
 
@@ -350,6 +383,7 @@ impl McDeserialize for Uuid {
 #[cfg(test)]
 mod tests {
 	use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
+	use crate::protocol::serialization::serializer_error::SerializingErr;
 	use crate::protocol_types::datatypes::var_types::{VarInt, VarLong};
 
 	#[test]
@@ -441,4 +475,48 @@ mod tests {
 		assert!(VarInt::from_slice(&[255, 255, 255, 255, 15]).unwrap() == VarInt(-1));
 		assert!(VarInt::from_slice(&[128, 128, 128, 128, 8]).unwrap() == VarInt(-2147483648));
 	}
-}
\ No newline at end of file
+	
+	#[test]
+	fn test_encoded_len() {
+		for i in [0, 1, 127, 128, 25565, 2097151, 2097152, i32::MAX, -1, i32::MIN] {
+			assert_eq!(VarInt(i).encoded_len(), VarInt(i).to_bytes().len(), "{}", i);
+		}
+
+		for i in [0, 1, 127, 128, 2147483647, i64::MAX, -1, i64::MIN] {
+			assert_eq!(VarLong(i).encoded_len(), VarLong(i).to_bytes().len(), "{}", i);
+		}
+	}
+
+	#[test]
+	fn test_read_from_in_place() {
+		let mut serializer = McSerializer::new();
+		VarInt(25565).write_to(&mut serializer);
+		VarLong(-1).write_to(&mut serializer);
+		VarInt(-1).write_to(&mut serializer);
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(VarInt::read_from(&mut deserializer).unwrap(), VarInt(25565));
+		assert_eq!(VarLong::read_from(&mut deserializer).unwrap(), VarLong(-1));
+		assert_eq!(VarInt::read_from(&mut deserializer).unwrap(), VarInt(-1));
+		assert!(deserializer.is_at_end());
+
+		// a cut off VarInt is an error and leaves the index where it was
+		let mut deserializer = McDeserializer::new(&[221, 199]);
+		assert_eq!(VarInt::read_from(&mut deserializer), Err(SerializingErr::InvalidEndOfVarInt));
+		assert_eq!(deserializer.index, 0);
+
+		let mut deserializer = McDeserializer::new(&[255, 255, 255, 255, 255, 1]);
+		assert!(matches!(VarInt::read_from(&mut deserializer), Err(SerializingErr::VarTypeTooLong(_))));
+	}
+
+	#[test]
+	fn test_serde() {
+		assert_eq!(serde_json::to_string(&VarInt(-25565)).unwrap(), "-25565");
+		assert_eq!(serde_json::to_string(&VarLong(i64::MAX)).unwrap(), i64::MAX.to_string());
+
+		assert_eq!(serde_json::from_str::<VarInt>("25565").unwrap(), VarInt(25565));
+		assert_eq!(serde_json::from_str::<VarLong>("-1").unwrap(), VarLong(-1));
+		assert!(serde_json::from_str::<VarInt>("5000000000").is_err());
+		assert!(serde_json::from_str::<VarInt>("\"1\"").is_err());
+	}
+}
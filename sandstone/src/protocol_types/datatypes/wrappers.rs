@@ -0,0 +1,336 @@
+//! Wrapper types that make the encoding of a packet field explicit in its type, for the shapes that the
+//! plain Rust types can't express on their own.
+
+use std::ops::{Deref, DerefMut};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer, SerializingResult};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::nbt::nbt::NbtTag;
+use crate::protocol_types::datatypes::nbt::nbt_serde;
+
+/// A list that is prefixed by its number of elements as a VarInt. This is the same encoding as a plain
+/// `Vec<T>`, but spelling it out in the field type makes packet definitions easier to check against the
+/// protocol docs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LengthPrefixedVec<T>(pub Vec<T>);
+
+impl<T> LengthPrefixedVec<T> {
+	pub fn new(elements: Vec<T>) -> Self {
+		Self(elements)
+	}
+
+	pub fn into_inner(self) -> Vec<T> {
+		self.0
+	}
+}
+
+impl<T> Default for LengthPrefixedVec<T> {
+	fn default() -> Self {
+		Self(vec![])
+	}
+}
+
+impl<T> Deref for LengthPrefixedVec<T> {
+	type Target = Vec<T>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl<T> DerefMut for LengthPrefixedVec<T> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.0
+	}
+}
+
+impl<T> From<Vec<T>> for LengthPrefixedVec<T> {
+	fn from(elements: Vec<T>) -> Self {
+		Self(elements)
+	}
+}
+
+impl<T> From<LengthPrefixedVec<T>> for Vec<T> {
+	fn from(elements: LengthPrefixedVec<T>) -> Self {
+		elements.0
+	}
+}
+
+impl<T> FromIterator<T> for LengthPrefixedVec<T> {
+	fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+		Self(iter.into_iter().collect())
+	}
+}
+
+impl<T> IntoIterator for LengthPrefixedVec<T> {
+	type Item = T;
+	type IntoIter = std::vec::IntoIter<T>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.0.into_iter()
+	}
+}
+
+impl<T: McSerialize> McSerialize for LengthPrefixedVec<T> {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		self.0.mc_serialize(serializer)
+	}
+}
+
+impl<T: McDeserialize> McDeserialize for LengthPrefixedVec<T> {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		Ok(Self(Vec::<T>::mc_deserialize(deserializer)?))
+	}
+}
+
+/// Bytes that take up the rest of the packet, with no length prefix. This must be the last field of a
+/// packet, since reading it consumes all remaining input. Used for things like plugin message data.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ByteArrayRemainder(pub Vec<u8>);
+
+/// Another name for [ByteArrayRemainder], for fields that are an opaque tail of the packet rather than
+/// a byte array, such as data that a proxy passes through without reading it.
+pub type RemainingBytes = ByteArrayRemainder;
+
+impl ByteArrayRemainder {
+	pub fn new(bytes: Vec<u8>) -> Self {
+		Self(bytes)
+	}
+
+	pub fn into_inner(self) -> Vec<u8> {
+		self.0
+	}
+}
+
+impl Deref for ByteArrayRemainder {
+	type Target = Vec<u8>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl DerefMut for ByteArrayRemainder {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.0
+	}
+}
+
+impl From<Vec<u8>> for ByteArrayRemainder {
+	fn from(bytes: Vec<u8>) -> Self {
+		Self(bytes)
+	}
+}
+
+impl From<&[u8]> for ByteArrayRemainder {
+	fn from(bytes: &[u8]) -> Self {
+		Self(bytes.to_vec())
+	}
+}
+
+impl From<ByteArrayRemainder> for Vec<u8> {
+	fn from(bytes: ByteArrayRemainder) -> Self {
+		bytes.0
+	}
+}
+
+impl McSerialize for ByteArrayRemainder {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		serializer.serialize_bytes(&self.0);
+		Ok(())
+	}
+}
+
+impl McDeserialize for ByteArrayRemainder {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let bytes = deserializer.collect_remaining().to_vec();
+		deserializer.increment(bytes.len());
+
+		Ok(Self(bytes))
+	}
+}
+
+/// A serde type that is sent as a JSON string, prefixed by its length like any other string. The status
+/// response is the main user of this.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+	pub fn new(value: T) -> Self {
+		Self(value)
+	}
+
+	pub fn into_inner(self) -> T {
+		self.0
+	}
+}
+
+impl<T> Deref for Json<T> {
+	type Target = T;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl<T> DerefMut for Json<T> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.0
+	}
+}
+
+impl<T> From<T> for Json<T> {
+	fn from(value: T) -> Self {
+		Self(value)
+	}
+}
+
+impl<T: Serialize> McSerialize for Json<T> {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		serializer.serialize_json(&self.0)
+	}
+}
+
+impl<T: DeserializeOwned> McDeserialize for Json<T> {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		Ok(Self(deserializer.deserialize_json()?))
+	}
+}
+
+/// A serde type that is sent as network NBT, meaning a type byte followed by the tag without a root
+/// name. Structs become compounds, so this is how typed registry data and item tags fit into a packet.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Nbt<T>(pub T);
+
+impl<T> Nbt<T> {
+	pub fn new(value: T) -> Self {
+		Self(value)
+	}
+
+	pub fn into_inner(self) -> T {
+		self.0
+	}
+}
+
+impl<T> Deref for Nbt<T> {
+	type Target = T;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl<T> DerefMut for Nbt<T> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		&mut self.0
+	}
+}
+
+impl<T> From<T> for Nbt<T> {
+	fn from(value: T) -> Self {
+		Self(value)
+	}
+}
+
+impl<T: Serialize> McSerialize for Nbt<T> {
+	fn mc_serialize(&self, serializer: &mut McSerializer) -> SerializingResult<()> {
+		let tag = nbt_serde::to_nbt(&self.0).map_err(|e| SerializingErr::UniqueFailure(format!("Failed to serialize NBT: {}", e)))?;
+
+		tag.serialize_network(serializer)
+	}
+}
+
+impl<T: DeserializeOwned> McDeserialize for Nbt<T> {
+	fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
+		let tag = NbtTag::from_network(deserializer)?;
+
+		Ok(Self(nbt_serde::from_nbt(tag).map_err(|e| SerializingErr::UniqueFailure(format!("Failed to deserialize NBT: {}", e)))?))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::serialization::{McDeserialize, McDeserializer, McSerialize, McSerializer};
+	use crate::protocol_types::datatypes::var_types::VarInt;
+	use serde::{Deserialize, Serialize};
+
+	use crate::protocol_types::datatypes::wrappers::{ByteArrayRemainder, Json, LengthPrefixedVec, Nbt, RemainingBytes};
+
+	#[test]
+	fn test_length_prefixed_vec() {
+		let list: LengthPrefixedVec<VarInt> = vec![VarInt(1), VarInt(300)].into();
+
+		let mut serializer = McSerializer::new();
+		list.mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, vec![2, 1, 172, 2]);
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(LengthPrefixedVec::<VarInt>::mc_deserialize(&mut deserializer).unwrap(), list);
+
+		let mut deserializer = McDeserializer::new(&[3, 1]);
+		assert!(LengthPrefixedVec::<u8>::mc_deserialize(&mut deserializer).is_err());
+	}
+
+	#[test]
+	fn test_byte_array_remainder() {
+		let mut serializer = McSerializer::new();
+		7u8.mc_serialize(&mut serializer).unwrap();
+		ByteArrayRemainder::from(vec![1, 2, 3]).mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, vec![7, 1, 2, 3]);
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(u8::mc_deserialize(&mut deserializer).unwrap(), 7);
+		assert_eq!(ByteArrayRemainder::mc_deserialize(&mut deserializer).unwrap().as_slice(), &[1, 2, 3]);
+		assert!(deserializer.is_at_end());
+		assert!(ByteArrayRemainder::mc_deserialize(&mut deserializer).unwrap().is_empty());
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		deserializer.increment(10); // past the end
+		assert!(RemainingBytes::mc_deserialize(&mut deserializer).unwrap().is_empty());
+	}
+
+	#[test]
+	fn test_json() {
+		let value = Json(vec![1, 2, 3]);
+
+		let mut serializer = McSerializer::new();
+		value.mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output, b"\x07[1,2,3]".to_vec());
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(Json::<Vec<u8>>::mc_deserialize(&mut deserializer).unwrap(), value);
+
+		let mut deserializer = McDeserializer::new(b"\x03[1,");
+		assert!(Json::<Vec<u8>>::mc_deserialize(&mut deserializer).is_err());
+	}
+
+	#[derive(Serialize, Deserialize, Debug, PartialEq)]
+	struct DamageType {
+		message_id: String,
+		exhaustion: f32,
+	}
+
+	#[test]
+	fn test_nbt() {
+		let value = Nbt(DamageType {
+			message_id: "fall".to_string(),
+			exhaustion: 0.1,
+		});
+
+		let mut serializer = McSerializer::new();
+		value.mc_serialize(&mut serializer).unwrap();
+		assert_eq!(serializer.output[0], 10); // compound, with no root name after it
+		assert!(matches!(serializer.output[1], 5 | 8)); // first field, in whichever order the compound keeps them
+		assert_eq!(*serializer.output.last().unwrap(), 0);
+
+		let mut deserializer = McDeserializer::new(&serializer.output);
+		assert_eq!(Nbt::<DamageType>::mc_deserialize(&mut deserializer).unwrap(), value);
+		assert!(deserializer.is_at_end());
+
+		let mut deserializer = McDeserializer::new(&[8, 0, 1, 97]); // a string tag isn't a struct
+		assert!(Nbt::<DamageType>::mc_deserialize(&mut deserializer).is_err());
+	}
+}
@@ -1,19 +1,163 @@
 use crate::versions;
 
 // https://wiki.vg/Protocol_History
+// https://minecraft.wiki/w/Data_version
 versions!(ProtocolVerison, i16 => {
-    V1_8, 47, "1.8.9",
-    V1_9, 110, "1.9.4",
-    V1_10, 210, "1.10.2",
-    V1_11, 316, "1.11.2",
-    V1_12, 340, "1.12.2",
-    V1_13, 404, "1.13.2",
-    V1_14, 498, "1.14.4",
-    V1_15, 578, "1.15.2",
-    V1_16, 754, "1.16.5",
-    V1_17, 756, "1.17.1",
-    V1_18, 758, "1.18.2",
-    V1_19, 762, "1.19.4",
-    V1_20, 766, "1.20.6",
-    V1_21, -1, ""
-});
\ No newline at end of file
+    V1_7_5, 4, "1.7.5", None,
+    V1_7_10, 5, "1.7.10", None,
+    V1_8_9, 47, "1.8.9", None,
+    V1_9_0, 107, "1.9", Some(169),
+    V1_9_1, 108, "1.9.1", Some(175),
+    V1_9_2, 109, "1.9.2", Some(176),
+    V1_9_4, 110, "1.9.4", Some(184),
+    V1_10_2, 210, "1.10.2", Some(512),
+    V1_11_0, 315, "1.11", Some(819),
+    V1_11_2, 316, "1.11.2", Some(922),
+    V1_12_0, 335, "1.12", Some(1139),
+    V1_12_1, 338, "1.12.1", Some(1241),
+    V1_12_2, 340, "1.12.2", Some(1343),
+    V1_13_0, 393, "1.13", Some(1519),
+    V1_13_1, 401, "1.13.1", Some(1628),
+    V1_13_2, 404, "1.13.2", Some(1631),
+    V1_14_0, 477, "1.14", Some(1952),
+    V1_14_1, 480, "1.14.1", Some(1957),
+    V1_14_2, 485, "1.14.2", Some(1963),
+    V1_14_3, 490, "1.14.3", Some(1968),
+    V1_14_4, 498, "1.14.4", Some(1976),
+    V1_15_0, 573, "1.15", Some(2225),
+    V1_15_1, 575, "1.15.1", Some(2227),
+    V1_15_2, 578, "1.15.2", Some(2230),
+    V1_16_0, 735, "1.16", Some(2566),
+    V1_16_1, 736, "1.16.1", Some(2567),
+    V1_16_2, 751, "1.16.2", Some(2578),
+    V1_16_3, 753, "1.16.3", Some(2580),
+    V1_16_5, 754, "1.16.5", Some(2586),
+    V1_17_0, 755, "1.17", Some(2724),
+    V1_17_1, 756, "1.17.1", Some(2730),
+    V1_18_1, 757, "1.18.1", Some(2865),
+    V1_18_2, 758, "1.18.2", Some(2975),
+    V1_19_0, 759, "1.19", Some(3105),
+    V1_19_2, 760, "1.19.2", Some(3120),
+    V1_19_3, 761, "1.19.3", Some(3218),
+    V1_19_4, 762, "1.19.4", Some(3337),
+    V1_20_1, 763, "1.20.1", Some(3465),
+    V1_20_2, 764, "1.20.2", Some(3578),
+    V1_20_4, 765, "1.20.4", Some(3700),
+    V1_20_6, 766, "1.20.6", Some(3839),
+    V1_21_1, 767, "1.21.1", Some(3955),
+    V1_21_3, 768, "1.21.3", Some(4082),
+    V1_21_4, 769, "1.21.4", Some(4189),
+    V1_21_5, 770, "1.21.5", Some(4325),
+    V1_21_6, 771, "1.21.6", Some(4435),
+    V1_21_8, 772, "1.21.8", Some(4440)
+});
+
+/// Each major version is named after its last release, and releases that share a protocol number with a
+/// later one are named after it
+impl ProtocolVerison {
+	pub const V1_8: Self = Self::V1_8_9;
+	pub const V1_9: Self = Self::V1_9_4;
+	pub const V1_10: Self = Self::V1_10_2;
+	pub const V1_11: Self = Self::V1_11_2;
+	pub const V1_12: Self = Self::V1_12_2;
+	pub const V1_13: Self = Self::V1_13_2;
+	pub const V1_14: Self = Self::V1_14_4;
+	pub const V1_15: Self = Self::V1_15_2;
+	pub const V1_16: Self = Self::V1_16_5;
+	pub const V1_16_4: Self = Self::V1_16_5;
+	pub const V1_17: Self = Self::V1_17_1;
+	pub const V1_18: Self = Self::V1_18_2;
+	pub const V1_19: Self = Self::V1_19_4;
+	pub const V1_19_1: Self = Self::V1_19_2;
+	pub const V1_20: Self = Self::V1_20_6;
+	pub const V1_20_3: Self = Self::V1_20_4;
+	pub const V1_20_5: Self = Self::V1_20_6;
+	pub const V1_21: Self = Self::V1_21_8;
+}
+
+impl ProtocolVerison {
+	/// The newest version known to the library
+	pub fn latest() -> Self {
+		Self::V1_21_8
+	}
+
+	/// Whether the client has the configuration state between login and play, added in 1.20.2
+	pub fn has_configuration_state(&self) -> bool {
+		*self >= Self::V1_20_2
+	}
+
+	/// Whether chat messages can be signed by the player, added in 1.19
+	pub fn has_signed_chat(&self) -> bool {
+		*self >= Self::V1_19_0
+	}
+
+	/// Whether chat sessions are sent separately from the login, added in 1.19.3
+	pub fn has_chat_sessions(&self) -> bool {
+		*self >= Self::V1_19_3
+	}
+
+	/// Whether NBT sent over the network has no name for the root compound, since 1.20.2
+	pub fn uses_network_nbt_unnamed_root(&self) -> bool {
+		*self >= Self::V1_20_2
+	}
+
+	/// Whether text components are sent as NBT instead of JSON, since 1.20.3
+	pub fn uses_nbt_text_components(&self) -> bool {
+		*self >= Self::V1_20_3
+	}
+
+	/// Whether the server can send Login Plugin Requests, added in 1.13
+	pub fn has_login_plugin_messages(&self) -> bool {
+		*self >= Self::V1_13_0
+	}
+
+	/// Whether blocks and items use the flattened ids of 1.13, rather than ids with metadata
+	pub fn has_flattened_ids(&self) -> bool {
+		*self >= Self::V1_13_0
+	}
+
+	/// Whether the client can be transferred to another server and store cookies, added in 1.20.5
+	pub fn has_transfers(&self) -> bool {
+		*self >= Self::V1_20_5
+	}
+
+	/// Whether the client and server agree on the data packs they both know during configuration, added
+	/// in 1.20.5
+	pub fn has_known_packs(&self) -> bool {
+		*self >= Self::V1_20_5
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol_types::protocol_verison::ProtocolVerison;
+
+	#[test]
+	fn test_version_order() {
+		let versions = ProtocolVerison::get_all();
+
+		for pair in versions.windows(2) {
+			assert!(pair[0] < pair[1]);
+			assert!(pair[0].get_version_number() < pair[1].get_version_number());
+			assert!(pair[0].get_data_version() <= pair[1].get_data_version());
+		}
+
+		assert_eq!(ProtocolVerison::latest(), *versions.last().unwrap());
+		assert_eq!(ProtocolVerison::from(766), Some(ProtocolVerison::V1_20));
+		assert_eq!(ProtocolVerison::from(-1), None);
+	}
+
+	#[test]
+	fn test_capabilities() {
+		assert!(!ProtocolVerison::V1_20_1.has_configuration_state());
+		assert!(ProtocolVerison::V1_20_2.has_configuration_state());
+		assert!(ProtocolVerison::V1_20_2.uses_network_nbt_unnamed_root());
+		assert!(!ProtocolVerison::V1_20_2.uses_nbt_text_components());
+		assert!(ProtocolVerison::V1_19_0.has_signed_chat());
+		assert!(!ProtocolVerison::V1_18_2.has_signed_chat());
+		assert!(!ProtocolVerison::V1_12_2.has_login_plugin_messages());
+		assert!(ProtocolVerison::V1_20_6.has_transfers());
+		assert_eq!(ProtocolVerison::V1_8.get_data_version(), None);
+		assert_eq!(ProtocolVerison::V1_20.get_data_version(), Some(3839));
+	}
+}
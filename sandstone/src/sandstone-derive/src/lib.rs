@@ -1,81 +1,199 @@
 use proc_macro::TokenStream;
 
-use quote::quote;
-use syn::{Data, DeriveInput, Fields, Ident, parse_macro_input};
-use syn::__private::Span;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Field, Fields, Index, parse_macro_input};
+
+/// How a single field is written to and read from the network, as chosen by its attributes
+enum FieldKind {
+	/// No attribute, uses the field's own `McSerialize` and `McDeserialize` implementations
+	Plain,
+	/// `#[varint]`, an integer field that is sent as a VarInt
+	VarInt,
+	/// `#[length_prefixed]`, a `Vec<T>` that is prefixed by its length as a VarInt
+	LengthPrefixed,
+	/// `#[json]`, a serde type that is sent as a JSON string
+	Json,
+	/// `#[remainder]`, a `Vec<u8>` that takes up the rest of the packet with no length prefix
+	Remainder,
+}
+
+impl FieldKind {
+	fn from_field(field: &Field) -> Result<Self, Error> {
+		let mut kind = FieldKind::Plain;
+
+		for attr in &field.attrs {
+			let next = if attr.path().is_ident("varint") {
+				FieldKind::VarInt
+			} else if attr.path().is_ident("length_prefixed") {
+				FieldKind::LengthPrefixed
+			} else if attr.path().is_ident("json") {
+				FieldKind::Json
+			} else if attr.path().is_ident("remainder") {
+				FieldKind::Remainder
+			} else {
+				continue;
+			};
+
+			attr.meta.require_path_only()?;
+
+			if !matches!(kind, FieldKind::Plain) {
+				return Err(Error::new_spanned(attr, "Only one of #[varint], #[length_prefixed], #[json] or #[remainder] can be used on a field"));
+			}
+
+			kind = next;
+		}
+
+		Ok(kind)
+	}
+}
+
+/// The accessor used to get the field from `self` (`name` or `0`) and the local variable that holds it
+/// while deserializing
+fn field_idents(fields: &Fields) -> Vec<(TokenStream2, TokenStream2)> {
+	fields.iter().enumerate().map(|(i, field)| {
+		match &field.ident {
+			Some(ident) => (quote!(#ident), quote!(#ident)),
+			None => {
+				let index = Index::from(i);
+				let var = format_ident!("__{}", i);
+				(quote!(#index), quote!(#var))
+			}
+		}
+	}).collect()
+}
+
+fn struct_fields(input: &DeriveInput) -> Result<&Fields, Error> {
+	match &input.data {
+		Data::Struct(data) => Ok(&data.fields),
+		Data::Enum(_) => Err(Error::new_spanned(&input.ident, "Enums are not supported")),
+		Data::Union(_) => Err(Error::new_spanned(&input.ident, "Unions are not supported")),
+	}
+}
 
 /// Derive the `McSerialize` trait for a struct. This implies that all fields of the struct also
-/// implement `McSerialize`.
-#[proc_macro_derive(McSerialize)]
+/// implement `McSerialize`, unless they are marked with one of the field attributes:
+/// - `#[varint]` sends an integer field as a VarInt
+/// - `#[length_prefixed]` prefixes a `Vec<T>` with its length as a VarInt
+/// - `#[json]` sends a field that implements `serde::Serialize` as a JSON string
+/// - `#[remainder]` sends a `Vec<u8>` as the rest of the packet without a length, so it must be the last field
+#[proc_macro_derive(McSerialize, attributes(varint, length_prefixed, json, remainder))]
 pub fn derive_mc_serialize(input: TokenStream) -> TokenStream {
 	let input = parse_macro_input!(input as DeriveInput);
+
+	match expand_mc_serialize(&input) {
+		Ok(expanded) => TokenStream::from(expanded),
+		Err(e) => TokenStream::from(e.to_compile_error()),
+	}
+}
+
+fn expand_mc_serialize(input: &DeriveInput) -> Result<TokenStream2, Error> {
 	let name = &input.ident;
-	let fields = match &input.data {
-		Data::Struct(data) => match &data.fields {
-			Fields::Named(fields) => fields.named.iter().map(|field| {
-				let field_name = field.ident.as_ref().unwrap();
-				quote! {
-					self.#field_name.mc_serialize(serializer)?;
-				}
-			}).collect(),
-			Fields::Unnamed(fields) => fields.unnamed.iter().enumerate().map(|(i, _field)| {
-				let field_name = Ident::new(&format!("__{}", i), Span::call_site());
-				quote! {
-					self.#field_name.mc_serialize(serializer)?;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	let fields = struct_fields(input)?;
+
+	let mut statements = vec![];
+
+	for (field, (access, _)) in fields.iter().zip(field_idents(fields)) {
+		statements.push(match FieldKind::from_field(field)? {
+			FieldKind::Plain => quote! {
+				::sandstone::protocol::serialization::McSerialize::mc_serialize(&self.#access, serializer)?;
+			},
+			FieldKind::VarInt => quote! {
+				::sandstone::protocol::serialization::McSerialize::mc_serialize(&::sandstone::protocol_types::datatypes::var_types::VarInt(self.#access as i32), serializer)?;
+			},
+			FieldKind::LengthPrefixed => quote! {
+				::sandstone::protocol::serialization::McSerialize::mc_serialize(&::sandstone::protocol_types::datatypes::var_types::VarInt(self.#access.len() as i32), serializer)?;
+				for element in self.#access.iter() {
+					::sandstone::protocol::serialization::McSerialize::mc_serialize(element, serializer)?;
 				}
-			}).collect(),
-			Fields::Unit => vec![],
-		},
-		Data::Enum(_) => panic!("Enums are not supported"),
-		Data::Union(_) => panic!("Unions are not supported"),
-	};
-	let expanded = quote! {
-		impl McSerialize for #name {
-			fn mc_serialize(&self, serializer: &mut McSerializer) -> Result<(), SerializingErr> {
-				#(#fields)*
+			},
+			FieldKind::Json => quote! {
+				serializer.serialize_json(&self.#access)?;
+			},
+			FieldKind::Remainder => quote! {
+				serializer.serialize_bytes(&self.#access);
+			},
+		});
+	}
+
+	Ok(quote! {
+		impl #impl_generics ::sandstone::protocol::serialization::McSerialize for #name #ty_generics #where_clause {
+			fn mc_serialize(&self, serializer: &mut ::sandstone::protocol::serialization::McSerializer) -> ::sandstone::protocol::serialization::SerializingResult<()> {
+				#(#statements)*
 				Ok(())
 			}
 		}
-	};
-	TokenStream::from(expanded)
+	})
 }
 
-#[proc_macro_derive(McDeserialize)]
+/// Derive the `McDeserialize` trait for a struct. Fields are read in the order they are declared and
+/// support the same attributes as `McSerialize`.
+#[proc_macro_derive(McDeserialize, attributes(varint, length_prefixed, json, remainder))]
 pub fn derive_mc_deserialize(input: TokenStream) -> TokenStream {
 	let input = parse_macro_input!(input as DeriveInput);
+
+	match expand_mc_deserialize(&input) {
+		Ok(expanded) => TokenStream::from(expanded),
+		Err(e) => TokenStream::from(e.to_compile_error()),
+	}
+}
+
+fn expand_mc_deserialize(input: &DeriveInput) -> Result<TokenStream2, Error> {
 	let name = &input.ident;
-	let fields = match &input.data {
-		Data::Struct(data) => match &data.fields {
-			Fields::Named(fields) => fields.named.iter().map(|field| {
-				let field_name = field.ident.as_ref().unwrap();
-				let field_type = &field.ty;
-				quote! {
-					let #field_name = <#field_type>::mc_deserialize(deserializer)?;
-				}
-			}).collect(),
-			Fields::Unnamed(fields) => fields.unnamed.iter().enumerate().map(|(i, field)| {
-				let field_name = Ident::new(&format!("__{}", i), Span::call_site());
-				let field_type = &field.ty;
-				quote! {
-					let #field_name = <#field_type>::mc_deserialize(deserializer)?;
-				}
-			}).collect(),
-			Fields::Unit => vec![],
-		},
-		Data::Enum(_) => panic!("Enums are not supported"),
-		Data::Union(_) => panic!("Unions are not supported"),
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	let fields = struct_fields(input)?;
+	let idents = field_idents(fields);
+
+	let mut statements = vec![];
+
+	for (i, (field, (_, var))) in fields.iter().zip(&idents).enumerate() {
+		let ty = &field.ty;
+		let label = match &field.ident {
+			Some(ident) => format!("{}.{}", name, ident),
+			None => format!("{}.{}", name, i),
+		};
+
+		let read = match FieldKind::from_field(field)? {
+			// a length prefixed Vec<T> reads the same as a plain one
+			FieldKind::Plain | FieldKind::LengthPrefixed => quote! {
+				<#ty as ::sandstone::protocol::serialization::McDeserialize>::mc_deserialize(deserializer)
+			},
+			FieldKind::VarInt => quote! {
+				<::sandstone::protocol_types::datatypes::var_types::VarInt as ::sandstone::protocol::serialization::McDeserialize>::mc_deserialize(deserializer).map(|v| v.0 as #ty)
+			},
+			FieldKind::Json => quote! {
+				deserializer.deserialize_json::<#ty>()
+			},
+			FieldKind::Remainder => quote! {
+				<::sandstone::protocol_types::datatypes::wrappers::ByteArrayRemainder as ::sandstone::protocol::serialization::McDeserialize>::mc_deserialize(deserializer).map(|b| b.into_inner().into())
+			},
+		};
+
+		// record which field failed, so errors can be traced back to the exact spot in the input
+		statements.push(quote! {
+			let #var: #ty = #read.map_err(|e| e.while_reading(#label, deserializer))?;
+		});
+	}
+
+	let construct = match fields {
+		Fields::Named(_) => {
+			let vars = idents.iter().map(|(_, var)| var);
+			quote!(Self { #(#vars),* })
+		}
+		Fields::Unnamed(_) => {
+			let vars = idents.iter().map(|(_, var)| var);
+			quote!(Self(#(#vars),*))
+		}
+		Fields::Unit => quote!(Self),
 	};
-	let expanded = quote! {
-		impl McDeserialize for #name {
-			fn mc_deserialize<'a>(deserializer: &'a mut McDeserializer) -> SerializingResult<'a, Self> {
-				#(#fields)*
-				Ok(Self {
-					#(
-						#fields
-					)*
-				})
+
+	Ok(quote! {
+		impl #impl_generics ::sandstone::protocol::serialization::McDeserialize for #name #ty_generics #where_clause {
+			fn mc_deserialize<'a>(deserializer: &'a mut ::sandstone::protocol::serialization::McDeserializer) -> ::sandstone::protocol::serialization::SerializingResult<'a, Self> {
+				#(#statements)*
+				Ok(#construct)
 			}
 		}
-	};
-	TokenStream::from(expanded)
+	})
 }
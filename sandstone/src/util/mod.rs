@@ -1,6 +1,6 @@
+#[cfg(feature = "mojang")]
 pub mod mojang;
 mod threadpool;
-mod encryption;
 
 /*
 Useful utilities for the library such as macro helpers and enum builders
@@ -69,22 +69,23 @@ pub mod macros {
         };
     }
 
-    /// Internal Only. Creates an enum of Minecraft versions with their protocol numbers and fancy names.
-    /// Provides convenient access methods much like [enumize!]
+    /// Internal Only. Creates an enum of Minecraft versions with their protocol numbers, fancy names and
+    /// data versions. Provides convenient access methods much like [enumize!]
     #[macro_export]
     macro_rules! versions {
         ($name: ident, $y: ty => {
-                $($na: ident, $lit: expr, $fancy: literal),*
+                $($na: ident, $lit: expr, $fancy: literal, $data: expr),*
             }
         )  => {
             $crate::as_item!{
-                /// Protocol version describes each major version of Minecraft: Java Edition since 1.8.9 <br>
-                /// For each major version (ie. 1.8, 1.9, etc) the last released sub-version is used, since there
-                /// is no conceivable reason to use any of the previous sub-versions.<br>
-                /// Provided is also the protocol number associated with the last sub-version for that major version,
-                /// as well as the name typically associated with that version.
-                #[derive(Clone, Copy, PartialEq)]
-                #[allow(non_snake_case)]
+                /// Protocol version describes each release of Minecraft: Java Edition since 1.7.2 that changed the
+                /// protocol number <br>
+                /// Releases that share a protocol number are named after the last of them, since there is no
+                /// conceivable reason to use any of the previous ones.<br>
+                /// Provided is also the protocol number, the name typically associated with that version and its
+                /// data version. Versions are ordered by their protocol number.
+                #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+                #[allow(non_snake_case, non_camel_case_types)]
                 pub enum $name {
                     $($na),*,
                 }
@@ -111,6 +112,13 @@ pub mod macros {
                     }
                 }
 
+				/// The data version stored in world saves, which releases before 1.9 don't have
+				pub fn get_data_version(&self) -> Option<i32> {
+					match self {
+						$($name::$na => $data),*
+					}
+				}
+
 				pub fn get_fancy_name(&self) -> String {
 					match self {
 						$($name::$na => $fancy),*
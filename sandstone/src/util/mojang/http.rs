@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 use std::sync::atomic::AtomicBool;
+use std::time::Duration;
 
 use log::debug;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use reqwest::header::RETRY_AFTER;
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 use thiserror::Error;
@@ -12,7 +14,14 @@ use tokio::sync::Mutex;
 This file is taken from my previous project, Rustiny: https://github.com/dec4234/Rustiny/blob/master/src/api/ApiClient.rs
  */
 
-/// An HTTP client used to interact with Mojang's API
+/// How many times a request that hit the rate limit is sent again before giving up
+pub const MAX_RETRIES: u32 = 3;
+
+/// How long to wait before the first retry if the API doesn't say, doubled for each retry after that
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// An HTTP client used to interact with Mojang's API. Requests that hit the rate limit are retried after
+/// the time given by the `Retry-After` header, up to [MAX_RETRIES] times.
 // TODO: probaby can remove the struct functionality and make everything static?
 pub struct ApiClient {
     debug_mode: Mutex<AtomicBool>,
@@ -54,11 +63,7 @@ impl ApiClient {
 
     pub async fn get_params(&self, url: String, map: HashMap<&str, &str>) -> Result<String, HttpError> {
         let client = Client::new();
-        let resp = client
-            .get(&url)
-            .query(&map)
-            .send()
-            .await?;
+        let resp = self.send(client.get(&url).query(&map), &url).await?;
 
         if !resp.status().is_success() {
             debug!("Failed to get from url: {}", url);
@@ -111,13 +116,12 @@ impl ApiClient {
         let body = body.into();
         
         let client = Client::new();
-        let resp = client
+        let request = client
             .post(&url)
             .body(body.clone())
             .header("Content-Type", "application/json")
-            .query(&map)
-            .send()
-            .await?;
+            .query(&map);
+        let resp = self.send(request, &url).await?;
 
         if !resp.status().is_success() {
             debug!("Failed to get from url: {}", url);
@@ -160,6 +164,43 @@ impl ApiClient {
 
         Ok(serde_json::from_str::<T>(text.as_str())?)
     }
+
+    /// Send a request, waiting and sending it again while it is rate limited
+    async fn send(&self, request: RequestBuilder, url: &str) -> Result<Response, HttpError> {
+        let mut delay = DEFAULT_RETRY_DELAY;
+
+        for attempt in 0..=MAX_RETRIES {
+            let resp = request.try_clone().expect("request bodies are never streamed").send().await?;
+
+            if resp.status() == StatusCode::NOT_FOUND {
+                return Err(HttpError::NotFound(url.to_string()));
+            }
+
+            if resp.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(resp);
+            }
+
+            if attempt == MAX_RETRIES {
+                break;
+            }
+
+            let wait = retry_delay(resp.headers().get(RETRY_AFTER).and_then(|v| v.to_str().ok()), delay);
+            debug!("Rate limited by {}, retrying in {:?}", url, wait);
+
+            tokio::time::sleep(wait).await;
+            delay *= 2;
+        }
+
+        Err(HttpError::RateLimited(url.to_string()))
+    }
+}
+
+/// How long to wait before retrying, from the `Retry-After` header in seconds if there is one
+pub(crate) fn retry_delay(retry_after: Option<&str>, fallback: Duration) -> Duration {
+    retry_after
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(fallback)
 }
 
 /// Any sort of error that could occur while performing or processing the response of an HTTP request.
@@ -175,4 +216,10 @@ pub enum HttpError {
     Utf8Error(#[from] std::string::FromUtf8Error),
     #[error("Received error code: {0}")]
     StatusCode(String),
+    #[error("Player could not be authenticated with the session server")]
+    NotAuthenticated,
+    #[error("Still rate limited after {} retries: {0}", MAX_RETRIES)]
+    RateLimited(String),
+    #[error("Nothing was found at {0}")]
+    NotFound(String),
 }
\ No newline at end of file
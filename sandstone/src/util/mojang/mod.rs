@@ -1,18 +1,29 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+
 use base64::Engine;
 use base64::engine::general_purpose;
 use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use uuid::Uuid;
 
+pub use crate::protocol_types::datatypes::game_profile::{GameProfile, ProfileProperty};
 use crate::util::mojang::http::{ApiClient, HttpError};
 
 pub mod http;
+#[cfg(test)]
 mod mojang_testing;
 
 /*
 This file defines the Mojang API - used to get information about users, servers and encryption validation
-The rate limit is allegedly 600 requests per 10 minutes
+The rate limit is allegedly 600 requests per 10 minutes, requests that hit it are retried by the ApiClient
 Reference = https://wiki.vg/Mojang_API
+This module is only available with the `mojang` feature, which is enabled by default
 */
 
+/// The most usernames that the bulk lookup endpoint accepts in one request
+pub const MAX_BULK_LOOKUP: usize = 10;
+
 /// Get the UUID of a username
 /// This will return an error if it exceeds the rate limit or if no user with the given username exists
 pub async fn get_uuid_from_username(name: String) -> Result<UuidRequestResponse, HttpError> {
@@ -31,16 +42,47 @@ pub struct UuidRequestResponse {
 	pub demo: Option<bool>
 }
 
-/// Get the UUIDs of multiple usernames at once, in alphabetical order
+impl UuidRequestResponse {
+	/// The UUID, which the API sends without dashes
+	pub fn uuid(&self) -> Option<Uuid> {
+		Uuid::try_parse(&self.id).ok()
+	}
+}
+
+/// Get the UUIDs of multiple usernames at once, in alphabetical order. Usernames that don't exist are left
+/// out. The names are looked up [MAX_BULK_LOOKUP] at a time, so any number can be given.
 /// This will return an error if it exceeds the rate limit
 pub async fn get_uuids_from_usernames(names: Vec<String>) -> Result<Vec<UuidRequestResponse>, HttpError> {
-	let body = serde_json::to_string(&names)?;
-	
-	let responses = ApiClient::new().enable_debug_mode().await.post_parse("https://api.minecraftservices.com/minecraft/profile/lookup/bulk/byname", body.as_str(), false).await?;
-	
+	let client = ApiClient::new();
+	let mut responses = Vec::with_capacity(names.len());
+
+	for chunk in names.chunks(MAX_BULK_LOOKUP) {
+		let body = serde_json::to_string(chunk)?;
+
+		let chunk: Vec<UuidRequestResponse> = client.post_parse("https://api.minecraftservices.com/minecraft/profile/lookup/bulk/byname", body.as_str(), false).await?;
+		responses.extend(chunk);
+	}
+
+	responses.sort_by_key(|r| r.name.to_lowercase());
+
 	Ok(responses)
 }
 
+/// Get the profile of a player with their signed skin and cape, the same as the server gets when they join
+pub async fn get_profile(uuid: Uuid) -> Result<GameProfile, HttpError> {
+	let url = format!("https://sessionserver.mojang.com/session/minecraft/profile/{}?unsigned=false", uuid.simple());
+
+	ApiClient::new().get_parse(url, false).await
+}
+
+/// Look up a username and get the player's profile, see [get_profile]
+pub async fn get_profile_by_username(name: &str) -> Result<GameProfile, HttpError> {
+	let response = get_uuid_from_username(name.to_string()).await?;
+	let uuid = response.uuid().ok_or_else(|| HttpError::StatusCode(format!("Invalid UUID for {}: {}", name, response.id)))?;
+
+	get_profile(uuid).await
+}
+
 /// Get details about a given UUID such as the name of the user, a list of moderation actions against their account
 /// and most importantly, their skin base64 encoded
 pub async fn get_player_details(uuid: String) -> Result<PlayerDetailsResponse, HttpError> {
@@ -49,26 +91,120 @@ pub async fn get_player_details(uuid: String) -> Result<PlayerDetailsResponse, H
 	Ok(ApiClient::new().enable_debug_mode().await.get_parse(url, false).await?)
 }
 
+/// Get the keys that Mojang signs profile properties and player certificates with
+pub async fn get_public_keys() -> Result<PublicKeysResponse, HttpError> {
+	ApiClient::new().get_parse("https://api.minecraftservices.com/publickeys".to_string(), false).await
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PublicKeysResponse {
+	pub profilePropertyKeys: Vec<PublicKeyEntry>,
+	pub playerCertificateKeys: Vec<PublicKeyEntry>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PublicKeyEntry {
+	/// The key as base64 DER
+	pub publicKey: String,
+}
+
+/// Compute the "server hash" used for online-mode authentication. This is a SHA-1 digest of the server id,
+/// shared secret and the server's public key, formatted the same way Java formats a signed BigInteger.
+/// This means that the hash can be negative, in which case it is prefixed with a '-', and leading zeros are
+/// removed.
+///
+/// See [https://wiki.vg/Protocol_Encryption#Authentication](https://wiki.vg/Protocol_Encryption#Authentication)
+pub fn server_hash(server_id: &str, shared_secret: &[u8], public_key_der: &[u8]) -> String {
+	let mut hasher = Sha1::new();
+	hasher.update(server_id.as_bytes());
+	hasher.update(shared_secret);
+	hasher.update(public_key_der);
+	let mut digest: [u8; 20] = hasher.finalize().into();
+
+	let negative = digest[0] & 0x80 != 0;
+
+	if negative { // two's complement to get the magnitude
+		let mut carry = true;
+
+		for b in digest.iter_mut().rev() {
+			*b = !*b;
+
+			if carry {
+				let (sum, overflow) = b.overflowing_add(1);
+				*b = sum;
+				carry = overflow;
+			}
+		}
+	}
+
+	let hex = hex::encode(digest);
+	let hex = hex.trim_start_matches('0');
+
+	if negative {
+		format!("-{}", hex)
+	} else {
+		hex.to_string()
+	}
+}
+
+/// Check with the Mojang session server that the given player has joined this server, which is what
+/// verifies that they own the account they are logging in with. This should be called by the server after
+/// receiving the `EncryptionResponse`.
+///
+/// `server_hash` is computed with [server_hash]. `ip` is optional, and if provided the session server
+/// will also check that the player connected from the same IP that authenticated with Mojang.
+///
+/// Returns the player's profile, including their UUID and skin, if authentication succeeds.
+pub async fn has_joined(username: &str, server_hash: &str, ip: Option<IpAddr>) -> Result<GameProfile, HttpError> {
+	let ip = ip.map(|ip| ip.to_string());
+
+	let mut params = HashMap::new();
+	params.insert("username", username);
+	params.insert("serverId", server_hash);
+
+	if let Some(ip) = &ip {
+		params.insert("ip", ip.as_str());
+	}
+
+	let text = ApiClient::new().get_params("https://sessionserver.mojang.com/session/minecraft/hasJoined".to_string(), params).await?;
+
+	if text.is_empty() { // the session server returns 204 No Content if the player is not authenticated
+		return Err(HttpError::NotAuthenticated);
+	}
+
+	Ok(serde_json::from_str(&text)?)
+}
+
+/// Perform the full online-mode authentication step for a player, computing the server hash and
+/// checking it with the session server. `server_id` is an empty string on Notchian servers since 1.7.
+pub async fn authenticate_player(username: &str, server_id: &str, shared_secret: &[u8], public_key_der: &[u8], ip: Option<IpAddr>) -> Result<GameProfile, HttpError> {
+	let hash = server_hash(server_id, shared_secret, public_key_der);
+
+	has_joined(username, &hash, ip).await
+}
+
 #[allow(non_snake_case)]
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct PlayerDetailsResponse {
 	pub id: String,
 	pub name: String,
-	pub properties: Vec<SkinPropertyWrapper>,
+	pub properties: Vec<ProfileProperty>,
 	pub profileActions: Vec<String>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub legacy: Option<bool>,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
-pub struct SkinPropertyWrapper {
-	pub name: String,
-	pub value: String,
-	#[serde(skip_serializing_if = "Option::is_none")]
-	pub signature: Option<String>
+impl GameProfile {
+	/// Decode the player's skin and cape, if the profile has them
+	pub fn get_skin_details(&self) -> Result<Option<SkinProperty>, HttpError> {
+		self.textures().map(ProfileProperty::get_skin_details).transpose()
+	}
 }
 
-impl SkinPropertyWrapper {
+impl ProfileProperty {
+	/// Decode the value of a `textures` property
 	pub fn get_skin_details(&self) -> Result<SkinProperty, HttpError> {
 		let decoded = general_purpose::STANDARD.decode(&self.value)?;
 		let decoded = String::from_utf8(decoded)?;
@@ -1,4 +1,9 @@
-use crate::util::mojang::{get_player_details, get_uuid_from_username, get_uuids_from_usernames};
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::util::mojang::{GameProfile, get_player_details, get_profile, get_uuid_from_username, get_uuids_from_usernames, server_hash, UuidRequestResponse};
+use crate::util::mojang::http::retry_delay;
 
 /*
 These tests are ignored due to rate limit issues
@@ -78,4 +83,53 @@ pub async fn test_get_player_details() {
 	assert_eq!(response.properties[0].name, "textures");
 	
 	let _texture = response.properties[0].get_skin_details().unwrap();
+}
+
+#[ignore]
+#[tokio::test]
+pub async fn test_get_profile() {
+	let profile = get_profile(Uuid::parse_str("ef39c1973c3d4776a22622096378a966").unwrap()).await.unwrap();
+	assert_eq!(profile.name, "dec4234");
+	assert!(profile.textures().unwrap().is_signed());
+	
+	let _skin = profile.get_skin_details().unwrap().unwrap();
+}
+
+#[test]
+pub fn test_uuid_response() {
+	let response: UuidRequestResponse = serde_json::from_str(r#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch"}"#).unwrap();
+	assert_eq!(response.uuid(), Some(Uuid::parse_str("069a79f4-44e9-4726-a5be-fca90e38aaf5").unwrap()));
+}
+
+#[test]
+pub fn test_retry_delay() {
+	let fallback = Duration::from_secs(1);
+	
+	assert_eq!(retry_delay(Some("5"), fallback), Duration::from_secs(5));
+	assert_eq!(retry_delay(Some("Wed, 21 Oct 2015 07:28:00 GMT"), fallback), fallback);
+	assert_eq!(retry_delay(None, fallback), fallback);
+}
+
+#[test]
+pub fn test_server_hash() {
+	// examples from https://wiki.vg/Protocol_Encryption
+	assert_eq!(server_hash("Notch", &[], &[]), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+	assert_eq!(server_hash("jeb_", &[], &[]), "-7c9d5b0044c130109a5d7b5fb5c317c02b4e28c1");
+	assert_eq!(server_hash("simon", &[], &[]), "88e16a1019277b15d58faf0541e11910eb756f6");
+	
+	// the parts are concatenated before hashing
+	assert_eq!(server_hash("", b"No", b"tch"), "4ed1f46bbe04bc756bcb17c0c7ce3e4632f06a48");
+}
+
+#[test]
+pub fn test_game_profile_deserialization() {
+	let json = r#"{"id":"ef39c1973c3d4776a22622096378a966","name":"dec4234","properties":[{"name":"textures","value":"abc","signature":"def"}]}"#;
+	
+	let profile: GameProfile = serde_json::from_str(json).unwrap();
+	assert_eq!(profile.uuid, Uuid::parse_str("ef39c197-3c3d-4776-a226-22096378a966").unwrap());
+	assert_eq!(profile.name, "dec4234");
+	assert_eq!(profile.properties.len(), 1);
+	assert_eq!(profile.properties[0].signature, Some("def".to_string()));
+	
+	assert_eq!(serde_json::to_string(&profile).unwrap(), json);
 }
\ No newline at end of file
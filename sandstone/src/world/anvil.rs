@@ -0,0 +1,712 @@
+//! Reads and writes worlds saved in the Anvil format, which vanilla has used since 1.2. The `region` folder of a
+//! dimension holds one file for each 32x32 chunks, named `r.<x>.<z>.mca` after the region coordinates.
+//! A region file starts with a table of where each chunk is and when it was saved, both in 4 KiB sectors,
+//! followed by the chunks themselves as compressed NBT.
+//!
+//! The chunk NBT names its block states and biomes, which are turned into ids with a [GlobalPalette].
+//! Only chunks saved by 1.18 or newer can be read, since older versions lay out the sections differently.
+//! Chunks are written the same way, so anything that this crate writes can be opened by vanilla as long as
+//! the palette matches the data version.
+//! See [here](https://minecraft.wiki/w/Region_file_format) and [here](https://minecraft.wiki/w/Chunk_format)
+//! for more.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::protocol::play::chunk::ChunkSection;
+use crate::protocol_types::datatypes::identifier::Identifier;
+use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtList, NbtLongArray, NbtTag};
+use crate::protocol_types::datatypes::nbt::nbt_reader::NbtCompression;
+use crate::protocol_types::datatypes::paletted_container::{bits_for, ContainerKind, longs_needed, pack, PalettedContainer, unpack};
+use crate::protocol_types::protocol_verison::ProtocolVerison;
+use crate::world::block_entity::BlockEntity;
+use crate::world::block_state::{BlockState, GlobalPalette};
+use crate::world::column::ChunkColumn;
+use crate::world::world_error::WorldError;
+
+/// The size of a sector of a region file
+pub const SECTOR_SIZE: usize = 4096;
+/// The width of a region in chunks
+pub const REGION_WIDTH: i32 = 32;
+/// The number of chunks in a region
+pub const REGION_CHUNKS: usize = 1024;
+/// The most sectors that a chunk can take up in a region file, since the count is a single byte
+pub const MAX_CHUNK_SECTORS: usize = 255;
+
+/// Set on the compression of a chunk that is too big for the region file, which is saved in its own
+/// `c.<x>.<z>.mcc` file instead
+const EXTERNAL: u8 = 0x80;
+
+/// How a chunk in a region file is compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ChunkCompression {
+	Gzip = 1,
+	/// What vanilla uses unless told otherwise
+	#[default]
+	Zlib = 2,
+	Uncompressed = 3,
+}
+
+impl ChunkCompression {
+	fn nbt(&self) -> NbtCompression {
+		match self {
+			ChunkCompression::Gzip => NbtCompression::Gzip,
+			ChunkCompression::Zlib => NbtCompression::Zlib,
+			ChunkCompression::Uncompressed => NbtCompression::Uncompressed,
+		}
+	}
+}
+
+impl TryFrom<u8> for ChunkCompression {
+	type Error = WorldError;
+
+	/// LZ4 (4) and custom (127) compression aren't supported
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		match value {
+			1 => Ok(ChunkCompression::Gzip),
+			2 => Ok(ChunkCompression::Zlib),
+			3 => Ok(ChunkCompression::Uncompressed),
+			_ => Err(WorldError::UnsupportedCompression(value)),
+		}
+	}
+}
+
+/// The region that a chunk is in
+pub fn region_of(chunk_x: i32, chunk_z: i32) -> (i32, i32) {
+	(chunk_x.div_euclid(REGION_WIDTH), chunk_z.div_euclid(REGION_WIDTH))
+}
+
+/// The name of the file of a region
+pub fn region_file_name(region_x: i32, region_z: i32) -> String {
+	format!("r.{}.{}.mca", region_x, region_z)
+}
+
+/// The index of a chunk in the tables of its region
+fn chunk_index(chunk_x: i32, chunk_z: i32) -> usize {
+	(chunk_x.rem_euclid(REGION_WIDTH) + chunk_z.rem_euclid(REGION_WIDTH) * REGION_WIDTH) as usize
+}
+
+/// One region file, see the [module docs](self). Chunks are given in chunk coordinates, which can be
+/// relative to the region or to the world.
+#[derive(Debug)]
+pub struct RegionFile {
+	path: PathBuf,
+	file: File,
+	/// The first sector of each chunk shifted left by 8, and the number of sectors in the low byte
+	locations: Box<[u32; REGION_CHUNKS]>,
+	/// When each chunk was saved, in seconds since the epoch
+	timestamps: Box<[u32; REGION_CHUNKS]>,
+}
+
+impl RegionFile {
+	/// Open an existing region file. Files that can't be written to are opened read only, and fail when a
+	/// chunk is written.
+	pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, WorldError> {
+		let path = path.as_ref().to_path_buf();
+		let mut file = match OpenOptions::new().read(true).write(true).open(&path) {
+			Err(e) if e.kind() == ErrorKind::PermissionDenied => File::open(&path)?,
+			file => file?,
+		};
+
+		let mut header = vec![0; SECTOR_SIZE * 2];
+		file.read_exact(&mut header).map_err(|e| match e.kind() {
+			ErrorKind::UnexpectedEof => WorldError::InvalidRegion(format!("{} is too short to be a region file", path.display())),
+			_ => e.into(),
+		})?;
+
+		let mut locations = Box::new([0; REGION_CHUNKS]);
+		let mut timestamps = Box::new([0; REGION_CHUNKS]);
+
+		for i in 0..REGION_CHUNKS {
+			locations[i] = u32::from_be_bytes(header[i * 4..i * 4 + 4].try_into().unwrap());
+			timestamps[i] = u32::from_be_bytes(header[SECTOR_SIZE + i * 4..SECTOR_SIZE + i * 4 + 4].try_into().unwrap());
+		}
+
+		Ok(Self {
+			path,
+			file,
+			locations,
+			timestamps,
+		})
+	}
+
+	/// Open a region file, or create an empty one if it doesn't exist
+	pub fn open_or_create<P: AsRef<Path>>(path: P) -> Result<Self, WorldError> {
+		let path = path.as_ref();
+
+		if !path.exists() {
+			File::create(path)?.write_all(&[0; SECTOR_SIZE * 2])?;
+		}
+
+		Self::open(path)
+	}
+
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	pub fn has_chunk(&self, chunk_x: i32, chunk_z: i32) -> bool {
+		self.locations[chunk_index(chunk_x, chunk_z)] != 0
+	}
+
+	/// When a chunk was last saved, in seconds since the epoch
+	pub fn timestamp(&self, chunk_x: i32, chunk_z: i32) -> Option<u32> {
+		self.has_chunk(chunk_x, chunk_z).then(|| self.timestamps[chunk_index(chunk_x, chunk_z)])
+	}
+
+	/// The chunks in the region, relative to the region
+	pub fn chunks(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+		(0..REGION_CHUNKS)
+			.filter(|i| self.locations[*i] != 0)
+			.map(|i| (i as i32 % REGION_WIDTH, i as i32 / REGION_WIDTH))
+	}
+
+	/// Read the NBT of a chunk, or `None` if it hasn't been generated. A chunk saved in its own file has
+	/// to be given in world coordinates, since that is how the file is named.
+	pub fn read_chunk_nbt(&mut self, chunk_x: i32, chunk_z: i32) -> Result<Option<NbtCompound>, WorldError> {
+		let location = self.locations[chunk_index(chunk_x, chunk_z)];
+
+		if location == 0 {
+			return Ok(None);
+		}
+
+		let (sector, sectors) = ((location >> 8) as u64, (location & 0xFF) as usize);
+
+		if sector < 2 {
+			return Err(WorldError::InvalidRegion(format!("Chunk {}, {} overlaps the header", chunk_x, chunk_z)));
+		}
+
+		self.file.seek(SeekFrom::Start(sector * SECTOR_SIZE as u64))?;
+
+		let mut header = [0; 5];
+		self.file.read_exact(&mut header)?;
+		let length = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+		let compression = header[4];
+
+		if compression & EXTERNAL != 0 {
+			let external = self.path.with_file_name(format!("c.{}.{}.mcc", chunk_x, chunk_z));
+			let data = std::fs::read(external)?;
+
+			return Ok(Some(NbtCompound::from_bytes(&data, ChunkCompression::try_from(compression & !EXTERNAL)?.nbt())?));
+		}
+
+		if length == 0 || length + 4 > sectors * SECTOR_SIZE {
+			return Err(WorldError::InvalidRegion(format!("Chunk {}, {} has a length of {} bytes in {} sectors", chunk_x, chunk_z, length, sectors)));
+		}
+
+		let mut data = vec![0; length - 1];
+		self.file.read_exact(&mut data)?;
+
+		Ok(Some(NbtCompound::from_bytes(&data, ChunkCompression::try_from(compression)?.nbt())?))
+	}
+
+	/// Write the NBT of a chunk and set its timestamp to now. The chunk is moved to the first gap in the
+	/// file that it fits in, or to the end. Chunks too big for the region file are saved in their own file,
+	/// so like with reading they have to be given in world coordinates.
+	pub fn write_chunk_nbt(&mut self, chunk_x: i32, chunk_z: i32, nbt: &NbtCompound, compression: ChunkCompression) -> Result<(), WorldError> {
+		let data = nbt.to_bytes(compression.nbt())?;
+		let external = self.path.with_file_name(format!("c.{}.{}.mcc", chunk_x, chunk_z));
+		let mut chunk = Vec::with_capacity(data.len() + 5);
+
+		if (data.len() + 5).div_ceil(SECTOR_SIZE) > MAX_CHUNK_SECTORS {
+			std::fs::write(&external, &data)?;
+			chunk.extend_from_slice(&1u32.to_be_bytes());
+			chunk.push(compression as u8 | EXTERNAL);
+		} else {
+			if external.exists() {
+				std::fs::remove_file(&external)?;
+			}
+
+			chunk.extend_from_slice(&(data.len() as u32 + 1).to_be_bytes());
+			chunk.push(compression as u8);
+			chunk.extend_from_slice(&data);
+		}
+
+		let sectors = chunk.len().div_ceil(SECTOR_SIZE);
+		chunk.resize(sectors * SECTOR_SIZE, 0);
+
+		let index = chunk_index(chunk_x, chunk_z);
+		let sector = self.allocate(index, sectors);
+
+		self.file.seek(SeekFrom::Start((sector * SECTOR_SIZE) as u64))?;
+		self.file.write_all(&chunk)?;
+
+		let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as u32).unwrap_or(0);
+		self.set_header(index, (sector as u32) << 8 | sectors as u32, timestamp)
+	}
+
+	/// Remove a chunk, so that vanilla generates it again. Its sectors are reused by the next chunks that
+	/// are written.
+	pub fn remove_chunk(&mut self, chunk_x: i32, chunk_z: i32) -> Result<(), WorldError> {
+		let external = self.path.with_file_name(format!("c.{}.{}.mcc", chunk_x, chunk_z));
+
+		if external.exists() {
+			std::fs::remove_file(external)?;
+		}
+
+		self.set_header(chunk_index(chunk_x, chunk_z), 0, 0)
+	}
+
+	/// Find the first run of free sectors that a chunk fits in, counting the sectors it already has as free
+	fn allocate(&self, index: usize, sectors: usize) -> usize {
+		let mut used = self.locations.iter().enumerate()
+			.filter(|(i, location)| *i != index && **location != 0)
+			.map(|(_, location)| ((location >> 8) as usize, (location >> 8) as usize + (location & 0xFF) as usize))
+			.collect::<Vec<_>>();
+		used.sort_unstable();
+
+		let mut start = 2;
+
+		for (from, to) in used {
+			if from >= start + sectors {
+				break;
+			}
+
+			start = start.max(to);
+		}
+
+		start
+	}
+
+	fn set_header(&mut self, index: usize, location: u32, timestamp: u32) -> Result<(), WorldError> {
+		self.locations[index] = location;
+		self.timestamps[index] = timestamp;
+
+		self.file.seek(SeekFrom::Start(index as u64 * 4))?;
+		self.file.write_all(&location.to_be_bytes())?;
+		self.file.seek(SeekFrom::Start((SECTOR_SIZE + index * 4) as u64))?;
+		self.file.write_all(&timestamp.to_be_bytes())?;
+		self.file.flush()?;
+		Ok(())
+	}
+}
+
+/// The `region` folder of a dimension, which opens region files as they are needed
+#[derive(Debug)]
+pub struct AnvilWorld {
+	directory: PathBuf,
+	regions: HashMap<(i32, i32), RegionFile>,
+	compression: ChunkCompression,
+	data_version: i32,
+}
+
+impl AnvilWorld {
+	/// Open the region folder of a dimension, such as `world/region` for the overworld or
+	/// `world/DIM-1/region` for the nether
+	pub fn open<P: AsRef<Path>>(directory: P) -> Self {
+		Self {
+			directory: directory.as_ref().to_path_buf(),
+			regions: HashMap::new(),
+			compression: ChunkCompression::default(),
+			data_version: ProtocolVerison::V1_21_1.get_data_version().expect("1.21 has a data version"),
+		}
+	}
+
+	/// How chunks are compressed when they are written, zlib by default
+	pub fn compression(mut self, compression: ChunkCompression) -> Self {
+		self.compression = compression;
+		self
+	}
+
+	/// The `DataVersion` that chunks are written with, which is 1.21 by default. This has to be the version
+	/// that the ids of the palette are from, or vanilla will upgrade the chunks wrongly.
+	pub fn data_version(mut self, data_version: i32) -> Self {
+		self.data_version = data_version;
+		self
+	}
+
+	/// The region file that a chunk is in, or `None` if the region doesn't exist
+	pub fn region(&mut self, chunk_x: i32, chunk_z: i32) -> Result<Option<&mut RegionFile>, WorldError> {
+		let (region_x, region_z) = region_of(chunk_x, chunk_z);
+
+		if !self.regions.contains_key(&(region_x, region_z)) {
+			let path = self.directory.join(region_file_name(region_x, region_z));
+
+			if !path.exists() {
+				return Ok(None);
+			}
+
+			self.regions.insert((region_x, region_z), RegionFile::open(path)?);
+		}
+
+		Ok(self.regions.get_mut(&(region_x, region_z)))
+	}
+
+	/// The region file that a chunk is in, which is created if it doesn't exist
+	fn region_or_create(&mut self, chunk_x: i32, chunk_z: i32) -> Result<&mut RegionFile, WorldError> {
+		let region = region_of(chunk_x, chunk_z);
+
+		if !self.regions.contains_key(&region) {
+			std::fs::create_dir_all(&self.directory)?;
+			let file = RegionFile::open_or_create(self.directory.join(region_file_name(region.0, region.1)))?;
+			self.regions.insert(region, file);
+		}
+
+		Ok(self.regions.get_mut(&region).expect("the region was just opened"))
+	}
+
+	/// Read the NBT of a chunk, or `None` if it hasn't been generated
+	pub fn read_chunk_nbt(&mut self, chunk_x: i32, chunk_z: i32) -> Result<Option<NbtCompound>, WorldError> {
+		match self.region(chunk_x, chunk_z)? {
+			Some(region) => region.read_chunk_nbt(chunk_x, chunk_z),
+			None => Ok(None),
+		}
+	}
+
+	/// Read a chunk as a column of `sections`, see [read_column]
+	pub fn read_column(&mut self, chunk_x: i32, chunk_z: i32, palette: &GlobalPalette, sections: usize) -> Result<Option<ChunkColumn>, WorldError> {
+		self.read_chunk_nbt(chunk_x, chunk_z)?
+			.map(|nbt| read_column(&nbt, palette, sections))
+			.transpose()
+	}
+
+	/// Write the NBT of a chunk, creating its region file if needed
+	pub fn write_chunk_nbt(&mut self, chunk_x: i32, chunk_z: i32, nbt: &NbtCompound) -> Result<(), WorldError> {
+		let compression = self.compression;
+		self.region_or_create(chunk_x, chunk_z)?.write_chunk_nbt(chunk_x, chunk_z, nbt, compression)
+	}
+
+	/// Write a column as a chunk, see [write_column]
+	pub fn write_column(&mut self, chunk_x: i32, chunk_z: i32, column: &ChunkColumn, palette: &GlobalPalette) -> Result<(), WorldError> {
+		let nbt = write_column(column, palette, chunk_x, chunk_z, self.data_version)?;
+		self.write_chunk_nbt(chunk_x, chunk_z, &nbt)
+	}
+
+	/// Remove a chunk, if its region exists
+	pub fn remove_chunk(&mut self, chunk_x: i32, chunk_z: i32) -> Result<(), WorldError> {
+		match self.region(chunk_x, chunk_z)? {
+			Some(region) => region.remove_chunk(chunk_x, chunk_z),
+			None => Ok(()),
+		}
+	}
+}
+
+/// Turn the NBT of a chunk into a column of `sections`, which is the height of the dimension. Sections
+/// that weren't saved are air in biome 0.
+///
+/// Vanilla also saves chunks that are still being generated, which have a `Status` other than
+/// `minecraft:full`. These can be read too, but are usually missing features such as trees.
+pub fn read_column(nbt: &NbtCompound, palette: &GlobalPalette, sections: usize) -> Result<ChunkColumn, WorldError> {
+	let saved = nbt.get_list("sections")?;
+	let mut column_sections = vec![ChunkSection::empty(0); sections];
+
+	let with_blocks = saved.list.iter()
+		.filter_map(|tag| match tag {
+			NbtTag::Compound(section) if section.contains_key("block_states") => Some(section),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+
+	let min_section = match nbt.get_int("yPos") {
+		Ok(y) => y,
+		Err(_) => with_blocks.iter().map(|s| s.get_byte("Y").map(|y| y as i32)).collect::<Result<Vec<_>, _>>()?.into_iter().min().unwrap_or(0),
+	};
+
+	for section in with_blocks {
+		let y = section.get_byte("Y")? as i32;
+		let index = usize::try_from(y - min_section).ok()
+			.filter(|i| *i < sections)
+			.ok_or_else(|| WorldError::InvalidChunk(format!("Section {} is outside of the {} sections from {}", y, sections, min_section)))?;
+
+		column_sections[index] = read_section(section, palette)?;
+	}
+
+	let mut column = ChunkColumn::from_sections(min_section * 16, column_sections);
+
+	for tag in nbt.get_list("block_entities").map(|list| list.list.as_slice()).unwrap_or_default() {
+		let NbtTag::Compound(block_entity) = tag else {
+			return Err(WorldError::InvalidChunk("A block entity isn't a compound".to_string()));
+		};
+
+		let (x, y, z) = BlockEntity::position_of(block_entity)?;
+		let block_entity = BlockEntity::from_nbt(block_entity)?;
+		column.set_block_entity(x.rem_euclid(16) as usize, y, z.rem_euclid(16) as usize, block_entity);
+	}
+
+	Ok(column)
+}
+
+/// Turn a section of a chunk into a [ChunkSection]
+pub fn read_section(section: &NbtCompound, palette: &GlobalPalette) -> Result<ChunkSection, WorldError> {
+	let block_states = section.get_compound("block_states")?;
+	let states = block_states.get_list("palette")?.list.iter()
+		.map(|tag| match tag {
+			NbtTag::Compound(state) => BlockState::from_nbt(state),
+			_ => Err(WorldError::InvalidChunk("A block state palette entry isn't a compound".to_string())),
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let indices = read_indices(block_states, states.len(), ContainerKind::BLOCK_STATES)?;
+	let block_count = indices.iter().filter(|i| !states[**i as usize].is_air()).count() as i16;
+	let ids = states.iter().map(|state| palette.require_block(state)).collect::<Result<Vec<_>, _>>()?;
+
+	let biomes = match section.get_compound("biomes") {
+		Ok(biomes) => {
+			let names = biomes.get_list("palette")?.list.iter()
+				.map(|tag| match tag {
+					NbtTag::String(name) => Identifier::try_from(name.as_str()).map_err(|_| WorldError::UnknownBiome(name.clone())),
+					_ => Err(WorldError::InvalidChunk("A biome palette entry isn't a string".to_string())),
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+
+			let ids = names.iter().map(|name| palette.require_biome(name)).collect::<Result<Vec<_>, _>>()?;
+			container(read_indices(biomes, ids.len(), ContainerKind::BIOMES)?, &ids, ContainerKind::BIOMES)?
+		}
+		Err(_) => PalettedContainer::single(ContainerKind::BIOMES, 0),
+	};
+
+	Ok(ChunkSection {
+		block_count,
+		block_states: container(indices, &ids, ContainerKind::BLOCK_STATES)?,
+		biomes,
+	})
+}
+
+/// Turn a column into the NBT of a finished chunk. Light isn't saved, so vanilla lights the chunk again
+/// when it is loaded.
+pub fn write_column(column: &ChunkColumn, palette: &GlobalPalette, chunk_x: i32, chunk_z: i32, data_version: i32) -> Result<NbtCompound, WorldError> {
+	let min_section = column.min_y().div_euclid(16);
+	let sections = column.sections().iter().enumerate()
+		.map(|(i, section)| write_section(section, palette, (min_section + i as i32) as i8).map(NbtTag::Compound))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let mut nbt = NbtCompound::new(Some(""));
+	nbt.add("DataVersion", NbtTag::Int(data_version));
+	nbt.add("xPos", NbtTag::Int(chunk_x));
+	nbt.add("yPos", NbtTag::Int(min_section));
+	nbt.add("zPos", NbtTag::Int(chunk_z));
+	nbt.add("Status", NbtTag::String("minecraft:full".to_string()));
+	nbt.add("LastUpdate", NbtTag::Long(0));
+	nbt.add("InhabitedTime", NbtTag::Long(0));
+	nbt.add("isLightOn", NbtTag::Byte(0));
+	let block_entities = column.block_entities()
+		.map(|((x, y, z), block_entity)| NbtTag::Compound(block_entity.to_nbt(chunk_x * 16 + x as i32, y, chunk_z * 16 + z as i32)))
+		.collect();
+
+	nbt.add("sections", NbtList::from_vec(sections)?);
+	nbt.add("block_entities", NbtList::from_vec(block_entities)?);
+	Ok(nbt)
+}
+
+/// Turn a [ChunkSection] into a section of a chunk, at section `y`
+pub fn write_section(section: &ChunkSection, palette: &GlobalPalette, y: i8) -> Result<NbtCompound, WorldError> {
+	let block_states = write_container(&section.block_states, ContainerKind::BLOCK_STATES, |id| {
+		palette.block_state(id)
+			.map(|state| NbtTag::Compound(state.to_nbt()))
+			.ok_or_else(|| WorldError::UnknownBlockState(format!("id {}", id)))
+	})?;
+
+	let biomes = write_container(&section.biomes, ContainerKind::BIOMES, |id| {
+		palette.biome(id)
+			.map(|biome| NbtTag::String(biome.to_string()))
+			.ok_or_else(|| WorldError::UnknownBiome(format!("id {}", id)))
+	})?;
+
+	let mut nbt = NbtCompound::new::<String>(None);
+	nbt.add("Y", NbtTag::Byte(y));
+	nbt.add("block_states", block_states);
+	nbt.add("biomes", biomes);
+	Ok(nbt)
+}
+
+/// Write the palette of a container as names, and the indices into it if there is more than one
+fn write_container<F: Fn(u32) -> Result<NbtTag, WorldError>>(container: &PalettedContainer, kind: ContainerKind, name: F) -> Result<NbtCompound, WorldError> {
+	let ids = container.palette();
+	let mut nbt = NbtCompound::new::<String>(None);
+	nbt.add("palette", NbtList::from_vec(ids.iter().map(|id| name(*id)).collect::<Result<Vec<_>, _>>()?)?);
+
+	if ids.len() > 1 {
+		let indices = ids.iter().enumerate().map(|(i, id)| (*id, i as u32)).collect::<HashMap<_, _>>();
+		let values = container.values().iter().map(|id| indices[id]).collect::<Vec<_>>();
+		nbt.add("data", NbtLongArray::new(pack(&values, storage_bits(ids.len(), kind))));
+	}
+
+	Ok(nbt)
+}
+
+/// The bits of each index in a saved container. Block states use at least 4 bits like on the network,
+/// while biomes use as few as they need.
+fn storage_bits(palette_len: usize, kind: ContainerKind) -> u8 {
+	if kind == ContainerKind::BLOCK_STATES { bits_for(palette_len).max(4) } else { bits_for(palette_len) }
+}
+
+/// Unpack the palette indices of a container
+fn read_indices(container: &NbtCompound, palette_len: usize, kind: ContainerKind) -> Result<Vec<u32>, WorldError> {
+	if palette_len == 0 {
+		return Err(WorldError::InvalidChunk("Empty palette".to_string()));
+	}
+
+	let data = match container.get_long_array("data") {
+		Ok(data) if palette_len > 1 => data,
+		_ => return Ok(vec![0; kind.entries()]),
+	};
+
+	let bits = storage_bits(palette_len, kind);
+
+	if data.len() != longs_needed(kind.entries(), bits) {
+		return Err(WorldError::InvalidChunk(format!("Expected {} longs of {} bit indices, got {}", longs_needed(kind.entries(), bits), bits, data.len())));
+	}
+
+	let indices = unpack(&data.list, bits, kind.entries());
+
+	if indices.iter().any(|i| *i as usize >= palette_len) {
+		return Err(WorldError::InvalidChunk("A palette index is out of bounds".to_string()));
+	}
+
+	Ok(indices)
+}
+
+fn container(indices: Vec<u32>, ids: &[u32], kind: ContainerKind) -> Result<PalettedContainer, WorldError> {
+	Ok(PalettedContainer::from_values(kind, indices.into_iter().map(|i| ids[i as usize]).collect())?)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Write;
+	use std::str::FromStr;
+
+	use crate::protocol_types::datatypes::identifier::Identifier;
+	use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtList, NbtLongArray, NbtTag};
+	use crate::protocol_types::datatypes::nbt::nbt_reader::NbtCompression;
+	use crate::protocol_types::datatypes::paletted_container::pack;
+	use crate::world::anvil::{AnvilWorld, chunk_index, ChunkCompression, region_file_name, region_of, RegionFile, SECTOR_SIZE};
+	use crate::world::block_entity::BlockEntity;
+	use crate::world::block_state::{BlockState, GlobalPalette};
+	use crate::world::column::ChunkColumn;
+
+	fn palette() -> GlobalPalette {
+		let mut palette = GlobalPalette::new();
+		palette.insert_block_state(0, BlockState::from_str("air").unwrap());
+		palette.insert_block_state(1, BlockState::from_str("stone").unwrap());
+		palette.insert_block_state(2, BlockState::from_str("oak_log[axis=y]").unwrap());
+		palette.insert_biome(0, Identifier::minecraft("plains").unwrap());
+		palette.insert_biome(1, Identifier::minecraft("desert").unwrap());
+		palette
+	}
+
+	fn string_list(values: &[&str]) -> NbtList {
+		NbtList::from_vec(values.iter().map(|v| NbtTag::String(v.to_string())).collect()).unwrap()
+	}
+
+	/// A chunk like vanilla saves, with stone at the bottom of section -4 and a log above it
+	fn chunk_nbt() -> NbtCompound {
+		let mut indices = vec![0; 4096];
+		indices[..256].fill(1);
+		indices[256 + 17] = 2;
+
+		let states = [BlockState::from_str("air").unwrap(), BlockState::from_str("stone").unwrap(), BlockState::from_str("oak_log[axis=y]").unwrap()];
+		let mut block_states = NbtCompound::new::<String>(None);
+		block_states.add("palette", NbtList::from_vec(states.iter().map(|s| NbtTag::Compound(s.to_nbt())).collect()).unwrap());
+		block_states.add("data", NbtLongArray::new(pack(&indices, 4)));
+
+		let mut biome_indices = vec![0; 64];
+		biome_indices[63] = 1;
+		let mut biomes = NbtCompound::new::<String>(None);
+		biomes.add("palette", string_list(&["minecraft:plains", "minecraft:desert"]));
+		biomes.add("data", NbtLongArray::new(pack(&biome_indices, 1)));
+
+		let mut section = NbtCompound::new::<String>(None);
+		section.add("Y", NbtTag::Byte(-4));
+		section.add("block_states", block_states);
+		section.add("biomes", biomes);
+
+		let mut light = NbtCompound::new::<String>(None);
+		light.add("Y", NbtTag::Byte(-5));
+
+		let mut chunk = NbtCompound::new(Some(""));
+		chunk.add("yPos", NbtTag::Int(-4));
+		chunk.add("Status", NbtTag::String("minecraft:full".to_string()));
+		chunk.add("sections", NbtList::from_vec(vec![NbtTag::Compound(light), NbtTag::Compound(section)]).unwrap());
+		chunk
+	}
+
+	#[test]
+	fn test_read_region() {
+		let directory = std::env::temp_dir().join(format!("sandstone-anvil-{}", std::process::id()));
+		std::fs::create_dir_all(&directory).unwrap();
+
+		// chunk -31, 2 is at 1, 2 in region -1, 0
+		assert_eq!(region_of(-31, 2), (-1, 0));
+		let data = chunk_nbt().to_bytes(NbtCompression::Zlib).unwrap();
+		let mut file = vec![0; SECTOR_SIZE * 3];
+		let index = 1 + 2 * 32;
+		file[index * 4..index * 4 + 4].copy_from_slice(&(2u32 << 8 | 1).to_be_bytes());
+		file[SECTOR_SIZE + index * 4..SECTOR_SIZE + index * 4 + 4].copy_from_slice(&1_700_000_000u32.to_be_bytes());
+		file[SECTOR_SIZE * 2..SECTOR_SIZE * 2 + 4].copy_from_slice(&(data.len() as u32 + 1).to_be_bytes());
+		file[SECTOR_SIZE * 2 + 4] = 2;
+		file.splice(SECTOR_SIZE * 2 + 5..SECTOR_SIZE * 2 + 5 + data.len(), data);
+		std::fs::File::create(directory.join(region_file_name(-1, 0))).unwrap().write_all(&file).unwrap();
+
+		let mut world = AnvilWorld::open(&directory);
+		let region = world.region(-31, 2).unwrap().unwrap();
+		assert_eq!(region.chunks().collect::<Vec<_>>(), vec![(1, 2)]);
+		assert_eq!(region.timestamp(-31, 2), Some(1_700_000_000));
+		assert!(world.read_chunk_nbt(-32, 2).unwrap().is_none());
+		assert!(world.read_chunk_nbt(100, 100).unwrap().is_none());
+
+		let column = world.read_column(-31, 2, &palette(), 24).unwrap().unwrap();
+		assert_eq!(column.min_y(), -64);
+		assert_eq!(column.block(5, -64, 5), 1);
+		assert_eq!(column.block(1, -63, 1), 2);
+		assert_eq!(column.block(1, -62, 1), 0);
+		assert_eq!(column.block_count(), 257);
+		assert_eq!(column.sections()[0].biome(15, 15, 15), 1);
+		assert_eq!(column.sections()[0].biome(0, 0, 0), 0);
+
+		// a block that the palette doesn't know
+		assert!(world.read_column(-31, 2, &GlobalPalette::new(), 24).is_err());
+
+		std::fs::remove_dir_all(&directory).unwrap();
+	}
+
+	#[test]
+	fn test_write_region() {
+		let directory = std::env::temp_dir().join(format!("sandstone-anvil-write-{}", std::process::id()));
+		let palette = palette();
+
+		let mut column = ChunkColumn::overworld(0);
+		column.fill_layers(-64, -60, 1);
+		column.set_block(3, 70, 9, 2);
+		column.fill_biome(-64, 0, 1);
+		column.set_biome(1, 300, 3, 1);
+		column.set_block_entity(15, -60, 0, BlockEntity::banner([("minecraft:creeper", "lime")]));
+
+		let mut world = AnvilWorld::open(&directory).compression(ChunkCompression::Gzip);
+		world.write_column(-1, -1, &column, &palette).unwrap();
+		world.write_column(-2, -1, &column, &palette).unwrap();
+		world.write_column(0, 0, &ChunkColumn::overworld(1), &palette).unwrap();
+		assert_eq!(world.read_column(-1, -1, &palette, 24).unwrap().unwrap(), column);
+
+		// growing the first chunk moves it past the second, and its sector is reused
+		let mut noisy = column.clone();
+		let mut seed = 1u32;
+		for i in 0..16 * 16 * 128 {
+			seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+			noisy.set_block(i % 16, (i / 256) as i32, (i / 16) % 16, (seed >> 16) % 3);
+		}
+		world.write_column(-1, -1, &noisy, &palette).unwrap();
+		world.write_column(-1, -2, &column, &palette).unwrap();
+		drop(world);
+
+		let mut region = RegionFile::open(directory.join(region_file_name(-1, -1))).unwrap();
+		assert!(region.timestamp(-1, -1).unwrap() > 0);
+		assert_eq!(region.chunks().count(), 3);
+		assert_eq!(region.locations[chunk_index(-1, -2)] >> 8, 2);
+		assert_eq!(region.locations[chunk_index(-1, -1)] >> 8, 4);
+		assert!(region.locations[chunk_index(-1, -1)] & 0xFF > 1);
+		assert_eq!(std::fs::metadata(region.path()).unwrap().len() % SECTOR_SIZE as u64, 0);
+
+		let mut world = AnvilWorld::open(&directory);
+		assert_eq!(world.read_column(-1, -1, &palette, 24).unwrap().unwrap(), noisy);
+		assert_eq!(world.read_column(-1, -2, &palette, 24).unwrap().unwrap(), column);
+		assert_eq!(world.read_column(0, 0, &palette, 24).unwrap().unwrap(), ChunkColumn::overworld(1));
+
+		region.remove_chunk(-1, -2).unwrap();
+		assert!(region.read_chunk_nbt(-1, -2).unwrap().is_none());
+
+		std::fs::remove_dir_all(&directory).unwrap();
+	}
+}
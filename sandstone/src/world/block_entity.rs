@@ -0,0 +1,135 @@
+//! Block entities, the extra data that some blocks such as chests, signs and banners keep. Worlds save them
+//! with their type and position, while the chunk packet sends the type as an id from the
+//! `block_entity_type` registry, see [GlobalPalette::block_entity_type_id](crate::world::block_state::GlobalPalette::block_entity_type_id).
+//!
+//! The data is sent to clients as is. Vanilla leaves out data that clients don't need, such as the items
+//! in a chest, but sending it doesn't break anything.
+
+use std::str::FromStr;
+
+use crate::protocol_types::datatypes::chat::TextComponent;
+use crate::protocol_types::datatypes::identifier::Identifier;
+use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtList, NbtTag};
+use crate::world::world_error::WorldError;
+
+/// The keys that worlds save next to the data of a block entity
+const SAVED_KEYS: [&str; 5] = ["id", "x", "y", "z", "keepPacked"];
+
+/// A block entity without its position
+///
+/// ```
+/// # use sandstone::protocol_types::datatypes::chat::TextComponent;
+/// # use sandstone::world::block_entity::BlockEntity;
+/// # use sandstone::world::column::ChunkColumn;
+/// let mut column = ChunkColumn::overworld(0);
+/// column.set_block_entity(0, 64, 0, BlockEntity::sign(["Welcome".into(), TextComponent::default(), TextComponent::default(), TextComponent::default()]));
+/// column.set_block_entity(1, 64, 0, BlockEntity::banner([("minecraft:stripe_top", "red")]));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockEntity {
+	/// The type, such as `minecraft:chest`
+	pub kind: Identifier,
+	pub data: NbtCompound,
+}
+
+impl BlockEntity {
+	pub fn new(kind: Identifier) -> Self {
+		Self::with_data(kind, NbtCompound::new::<String>(None))
+	}
+
+	pub fn with_data(kind: Identifier, data: NbtCompound) -> Self {
+		Self {
+			kind,
+			data,
+		}
+	}
+
+	/// A sign with a message on the front, one component for each line
+	pub fn sign(lines: [TextComponent; 4]) -> Self {
+		let messages = lines.iter()
+			.map(|line| NbtTag::String(serde_json::to_string(line).expect("text components serialize to JSON")))
+			.collect();
+
+		let mut front = NbtCompound::new::<String>(None);
+		front.add("messages", NbtList::from_vec(messages).expect("every message is a string"));
+		front.add("color", NbtTag::String("black".to_string()));
+		front.add("has_glowing_text", NbtTag::Byte(0));
+
+		let mut data = NbtCompound::new::<String>(None);
+		data.add("front_text", front);
+		data.add("is_waxed", NbtTag::Byte(0));
+
+		Self::with_data(Identifier::minecraft("sign").expect("valid identifier"), data)
+	}
+
+	/// A banner with patterns, each a pattern such as `minecraft:stripe_top` and a dye color such as `red`,
+	/// from the bottom layer up. The base color of a banner is part of its block state.
+	pub fn banner<P: Into<String>, C: Into<String>, I: IntoIterator<Item = (P, C)>>(patterns: I) -> Self {
+		let patterns = patterns.into_iter()
+			.map(|(pattern, color)| {
+				let mut layer = NbtCompound::new::<String>(None);
+				layer.add("pattern", NbtTag::String(pattern.into()));
+				layer.add("color", NbtTag::String(color.into()));
+				NbtTag::Compound(layer)
+			})
+			.collect();
+
+		let mut data = NbtCompound::new::<String>(None);
+		data.add("patterns", NbtList::from_vec(patterns).expect("every pattern is a compound"));
+
+		Self::with_data(Identifier::minecraft("banner").expect("valid identifier"), data)
+	}
+
+	/// Set the name that containers show at the top of their screen, and that banners show on maps
+	pub fn custom_name(mut self, name: TextComponent) -> Self {
+		self.data.add("CustomName", NbtTag::String(serde_json::to_string(&name).expect("text components serialize to JSON")));
+		self
+	}
+
+	/// Read a block entity as worlds save it, with its type in `id`. The position is left out, see
+	/// [BlockEntity::position_of].
+	pub fn from_nbt(nbt: &NbtCompound) -> Result<Self, WorldError> {
+		let id = nbt.get_string("id")?;
+		let kind = Identifier::from_str(id).map_err(|_| WorldError::UnknownBlockEntity(id.to_string()))?;
+
+		let mut data = nbt.clone();
+		data.root_name = None;
+		SAVED_KEYS.iter().for_each(|key| data.remove(*key));
+
+		Ok(Self::with_data(kind, data))
+	}
+
+	/// The position in world coordinates of a block entity as worlds save it
+	pub fn position_of(nbt: &NbtCompound) -> Result<(i32, i32, i32), WorldError> {
+		Ok((nbt.get_int("x")?, nbt.get_int("y")?, nbt.get_int("z")?))
+	}
+
+	/// Write the block entity the way that worlds save it, at a position in world coordinates
+	pub fn to_nbt(&self, x: i32, y: i32, z: i32) -> NbtCompound {
+		let mut nbt = self.data.clone();
+		nbt.add("id", NbtTag::String(self.kind.to_string()));
+		nbt.add("x", NbtTag::Int(x));
+		nbt.add("y", NbtTag::Int(y));
+		nbt.add("z", NbtTag::Int(z));
+		nbt.add("keepPacked", NbtTag::Byte(0));
+		nbt
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol_types::datatypes::chat::TextComponent;
+	use crate::world::block_entity::BlockEntity;
+
+	#[test]
+	fn test_block_entity_nbt() {
+		let sign = BlockEntity::sign(["a".into(), "b".into(), "c".into(), TextComponent::default()]).custom_name("Sign".into());
+		let nbt = sign.to_nbt(-5, 70, 12);
+		assert_eq!(nbt.get_string("id").unwrap(), "minecraft:sign");
+
+		let read = BlockEntity::from_nbt(&nbt).unwrap();
+		assert_eq!(BlockEntity::position_of(&nbt).unwrap(), (-5, 70, 12));
+		assert_eq!(read, sign);
+		assert!(!read.data.contains_key("keepPacked"));
+	}
+}
@@ -0,0 +1,337 @@
+//! Block states by name, the way that worlds and structures save them, and the [GlobalPalette] that maps
+//! them to the ids sent to clients. The ids of block states aren't part of any registry that the server
+//! sends, they are fixed for each version. The data generator of the vanilla server lists them, see
+//! [GlobalPalette::from_blocks_report].
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::protocol::configuration::registry::RegistryDataBuilder;
+use crate::protocol_types::datatypes::identifier::Identifier;
+use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtTag};
+use crate::world::world_error::WorldError;
+
+/// A block and the values of its properties, written as `minecraft:oak_log[axis=y]`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockState {
+	name: Identifier,
+	properties: BTreeMap<String, String>,
+}
+
+impl BlockState {
+	pub fn new(name: Identifier) -> Self {
+		Self {
+			name,
+			properties: BTreeMap::new(),
+		}
+	}
+
+	/// Set a property
+	pub fn with<K: Into<String>, V: Into<String>>(mut self, property: K, value: V) -> Self {
+		self.properties.insert(property.into(), value.into());
+		self
+	}
+
+	pub fn name(&self) -> &Identifier {
+		&self.name
+	}
+
+	pub fn properties(&self) -> &BTreeMap<String, String> {
+		&self.properties
+	}
+
+	pub fn property(&self, property: &str) -> Option<&str> {
+		self.properties.get(property).map(|s| s.as_str())
+	}
+
+	/// Whether this is one of the kinds of air, which the client doesn't count as blocks
+	pub fn is_air(&self) -> bool {
+		self.name.namespace() == "minecraft" && matches!(self.name.path(), "air" | "cave_air" | "void_air")
+	}
+
+	/// Read a block state from the `Name` and `Properties` of a compound, as in the palette of a chunk
+	/// section or a structure
+	pub fn from_nbt(nbt: &NbtCompound) -> Result<Self, WorldError> {
+		let name = nbt.get_string("Name")?;
+		let mut state = Self::new(Identifier::from_str(name).map_err(|_| WorldError::InvalidBlockState(name.to_string()))?);
+
+		if let Ok(properties) = nbt.get_compound("Properties") {
+			for key in properties.keys() {
+				state.properties.insert(key.clone(), properties.get_string(key)?.to_string());
+			}
+		}
+
+		Ok(state)
+	}
+
+	/// Write the block state as a compound with `Name` and, if there are any, `Properties`
+	pub fn to_nbt(&self) -> NbtCompound {
+		let mut nbt = NbtCompound::new::<String>(None);
+		nbt.add("Name", NbtTag::String(self.name.to_string()));
+
+		if !self.properties.is_empty() {
+			let mut properties = NbtCompound::new::<String>(None);
+
+			for (key, value) in &self.properties {
+				properties.add(key.as_str(), NbtTag::String(value.clone()));
+			}
+
+			nbt.add("Properties", properties);
+		}
+
+		nbt
+	}
+}
+
+impl Display for BlockState {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.name)?;
+
+		if !self.properties.is_empty() {
+			let properties = self.properties.iter().map(|(k, v)| format!("{}={}", k, v)).collect::<Vec<_>>();
+			write!(f, "[{}]", properties.join(","))?;
+		}
+
+		Ok(())
+	}
+}
+
+impl FromStr for BlockState {
+	type Err = WorldError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid = || WorldError::InvalidBlockState(s.to_string());
+
+		let (name, properties) = match s.split_once('[') {
+			Some((name, rest)) => (name, Some(rest.strip_suffix(']').ok_or_else(invalid)?)),
+			None => (s, None),
+		};
+
+		let mut state = Self::new(Identifier::from_str(name).map_err(|_| invalid())?);
+
+		for property in properties.iter().flat_map(|p| p.split(',')).filter(|p| !p.is_empty()) {
+			let (key, value) = property.split_once('=').ok_or_else(invalid)?;
+			state.properties.insert(key.trim().to_string(), value.trim().to_string());
+		}
+
+		Ok(state)
+	}
+}
+
+/// The format of `reports/blocks.json`
+#[derive(Deserialize)]
+struct BlockReport {
+	states: Vec<StateReport>,
+}
+
+#[derive(Deserialize)]
+struct StateReport {
+	id: u32,
+	#[serde(default)]
+	default: bool,
+	#[serde(default)]
+	properties: BTreeMap<String, String>,
+}
+
+/// One registry in the format of `reports/registries.json`
+#[derive(Deserialize)]
+struct RegistryReport {
+	entries: HashMap<String, EntryReport>,
+}
+
+#[derive(Deserialize)]
+struct EntryReport {
+	protocol_id: u32,
+}
+
+/// Maps block states, biomes and block entity types to the ids that clients know them by, and back
+///
+/// ```
+/// # use std::str::FromStr;
+/// # use sandstone::protocol_types::datatypes::identifier::Identifier;
+/// # use sandstone::world::block_state::{BlockState, GlobalPalette};
+/// let mut palette = GlobalPalette::new();
+/// palette.insert_block_state(0, BlockState::from_str("minecraft:air").unwrap());
+/// palette.insert_block_state(1, BlockState::from_str("minecraft:stone").unwrap());
+/// palette.insert_biome(0, Identifier::minecraft("plains").unwrap());
+///
+/// assert_eq!(palette.block_id(&BlockState::from_str("stone").unwrap()), Some(1));
+/// assert_eq!(palette.block_state(0).unwrap().to_string(), "minecraft:air");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GlobalPalette {
+	block_ids: HashMap<BlockState, u32>,
+	block_states: HashMap<u32, BlockState>,
+	/// The default state of each block, used when a state is given without its properties
+	default_states: HashMap<Identifier, u32>,
+	biome_ids: HashMap<Identifier, u32>,
+	biomes: HashMap<u32, Identifier>,
+	block_entity_types: HashMap<Identifier, u32>,
+}
+
+impl GlobalPalette {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Load the block states from the `reports/blocks.json` made by the vanilla server's data generator,
+	/// with `java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports`
+	pub fn from_blocks_report(json: &str) -> Result<Self, WorldError> {
+		let report: HashMap<String, BlockReport> = serde_json::from_str(json)
+			.map_err(|e| WorldError::InvalidReport(format!("blocks.json: {}", e)))?;
+
+		let mut palette = Self::new();
+
+		for (name, block) in report {
+			let name = Identifier::from_str(&name).map_err(|_| WorldError::InvalidBlockState(name.clone()))?;
+
+			for state in block.states {
+				let default = state.default;
+				palette.insert_block_state(state.id, BlockState { name: name.clone(), properties: state.properties });
+
+				if default {
+					palette.default_states.insert(name.clone(), state.id);
+				}
+			}
+		}
+
+		Ok(palette)
+	}
+
+	/// Load the block entity types from the `reports/registries.json` made by the data generator, see
+	/// [GlobalPalette::from_blocks_report]
+	pub fn with_registries_report(mut self, json: &str) -> Result<Self, WorldError> {
+		let mut report: HashMap<String, RegistryReport> = serde_json::from_str(json)
+			.map_err(|e| WorldError::InvalidReport(format!("registries.json: {}", e)))?;
+
+		for (name, entry) in report.remove("minecraft:block_entity_type").map(|r| r.entries).unwrap_or_default() {
+			let name = Identifier::from_str(&name).map_err(|_| WorldError::UnknownBlockEntity(name.clone()))?;
+			self.insert_block_entity_type(entry.protocol_id, name);
+		}
+
+		Ok(self)
+	}
+
+	/// Add a block state with its id. The first state added for a block is its default state, unless the
+	/// blocks report says otherwise.
+	pub fn insert_block_state(&mut self, id: u32, state: BlockState) {
+		self.default_states.entry(state.name.clone()).or_insert(id);
+		self.block_ids.insert(state.clone(), id);
+		self.block_states.insert(id, state);
+	}
+
+	/// Add a biome with its id in the `worldgen/biome` registry
+	pub fn insert_biome(&mut self, id: u32, biome: Identifier) {
+		self.biome_ids.insert(biome.clone(), id);
+		self.biomes.insert(id, biome);
+	}
+
+	/// Add a block entity type with its id in the `block_entity_type` registry
+	pub fn insert_block_entity_type(&mut self, id: u32, kind: Identifier) {
+		self.block_entity_types.insert(kind, id);
+	}
+
+	/// Use the biomes of the `worldgen/biome` registry that is sent to clients, so that the ids match
+	pub fn with_biomes(mut self, registries: &RegistryDataBuilder) -> Self {
+		let registry = Identifier::minecraft("worldgen/biome").expect("valid identifier");
+
+		for (id, entry) in registries.entries(&registry).unwrap_or_default().iter().enumerate() {
+			self.insert_biome(id as u32, entry.id.clone());
+		}
+
+		self
+	}
+
+	/// The id of a block state. A state without any properties gets the default state of its block.
+	pub fn block_id(&self, state: &BlockState) -> Option<u32> {
+		match self.block_ids.get(state) {
+			Some(id) => Some(*id),
+			None if state.properties.is_empty() => self.default_states.get(&state.name).copied(),
+			None => None,
+		}
+	}
+
+	pub fn block_state(&self, id: u32) -> Option<&BlockState> {
+		self.block_states.get(&id)
+	}
+
+	/// The id of the default state of a block
+	pub fn default_state(&self, block: &Identifier) -> Option<u32> {
+		self.default_states.get(block).copied()
+	}
+
+	pub fn biome_id(&self, biome: &Identifier) -> Option<u32> {
+		self.biome_ids.get(biome).copied()
+	}
+
+	pub fn biome(&self, id: u32) -> Option<&Identifier> {
+		self.biomes.get(&id)
+	}
+
+	pub fn block_entity_type_id(&self, kind: &Identifier) -> Option<u32> {
+		self.block_entity_types.get(kind).copied()
+	}
+
+	/// The id of a block state, or [WorldError::UnknownBlockState]
+	pub fn require_block(&self, state: &BlockState) -> Result<u32, WorldError> {
+		self.block_id(state).ok_or_else(|| WorldError::UnknownBlockState(state.to_string()))
+	}
+
+	/// The id of a biome, or [WorldError::UnknownBiome]
+	pub fn require_biome(&self, biome: &Identifier) -> Result<u32, WorldError> {
+		self.biome_id(biome).ok_or_else(|| WorldError::UnknownBiome(biome.to_string()))
+	}
+
+	/// The id of a block entity type, or [WorldError::UnknownBlockEntity]
+	pub fn require_block_entity_type(&self, kind: &Identifier) -> Result<u32, WorldError> {
+		self.block_entity_type_id(kind).ok_or_else(|| WorldError::UnknownBlockEntity(kind.to_string()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use crate::protocol_types::datatypes::identifier::Identifier;
+	use crate::world::block_state::{BlockState, GlobalPalette};
+
+	#[test]
+	fn test_block_states() {
+		let state = BlockState::from_str("minecraft:oak_log[axis=y]").unwrap();
+		assert_eq!(state.property("axis"), Some("y"));
+		assert_eq!(state.to_string(), "minecraft:oak_log[axis=y]");
+		assert_eq!(BlockState::from_nbt(&state.to_nbt()).unwrap(), state);
+		assert!(BlockState::from_str("minecraft:oak_log[axis").is_err());
+		assert!(BlockState::from_str("cave_air").unwrap().is_air());
+
+		let json = r#"{
+			"minecraft:air": {"states": [{"id": 0, "default": true}]},
+			"minecraft:oak_log": {
+				"properties": {"axis": ["x", "y", "z"]},
+				"states": [
+					{"id": 136, "properties": {"axis": "x"}},
+					{"id": 137, "default": true, "properties": {"axis": "y"}},
+					{"id": 138, "properties": {"axis": "z"}}
+				]
+			}
+		}"#;
+
+		let palette = GlobalPalette::from_blocks_report(json).unwrap();
+		assert_eq!(palette.block_id(&state), Some(137));
+		assert_eq!(palette.block_id(&BlockState::from_str("oak_log").unwrap()), Some(137));
+		assert_eq!(palette.block_id(&BlockState::from_str("oak_log[axis=w]").unwrap()), None);
+		assert_eq!(palette.block_state(138).unwrap().property("axis"), Some("z"));
+
+		let json = r#"{
+			"minecraft:block_entity_type": {"protocol_id": 10, "entries": {"minecraft:chest": {"protocol_id": 1}}},
+			"minecraft:item": {"protocol_id": 6, "entries": {"minecraft:stone": {"protocol_id": 1}}}
+		}"#;
+
+		let palette = palette.with_registries_report(json).unwrap();
+		assert_eq!(palette.block_entity_type_id(&Identifier::minecraft("chest").unwrap()), Some(1));
+		assert!(palette.require_block_entity_type(&Identifier::minecraft("stone").unwrap()).is_err());
+	}
+}
@@ -0,0 +1,336 @@
+//! A column of chunk sections with world coordinates, so blocks can be set without working out which
+//! section they are in. Blocks are block state ids from the `block_state` registry, see
+//! [ChunkSection] for how they are stored. Block entities are kept next to the sections by position.
+
+use std::collections::BTreeMap;
+
+use crate::protocol::play::chunk::{AIR, ChunkDataBuilder, ChunkSection, OVERWORLD_SECTIONS, read_sections};
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::world::block_entity::BlockEntity;
+use crate::world::block_state::GlobalPalette;
+use crate::world::world_error::WorldError;
+
+/// The lowest block of the overworld
+pub const OVERWORLD_MIN_Y: i32 = -64;
+
+/// A 16 block wide column of sections, from `min_y` up
+///
+/// ```
+/// # use sandstone::world::column::ChunkColumn;
+/// let mut column = ChunkColumn::overworld(0);
+/// column.fill_layers(-64, -61, 1); // stone
+/// column.set_block(3, 100, 5, 2);
+///
+/// assert_eq!(column.block(3, 100, 5), 2);
+/// assert_eq!(column.block_count(), 4 * 256 + 1);
+///
+/// let packet = column.builder(0, 0).packet().unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkColumn {
+	min_y: i32,
+	sections: Vec<ChunkSection>,
+	/// By `x`, `y` and `z` like blocks
+	block_entities: BTreeMap<(usize, i32, usize), BlockEntity>,
+}
+
+impl ChunkColumn {
+	/// A column of air in one biome, where `min_y` is a multiple of 16
+	pub fn new(min_y: i32, sections: usize, biome: u32) -> Self {
+		Self::from_sections(min_y, vec![ChunkSection::empty(biome); sections])
+	}
+
+	/// An empty overworld column, from y = -64 up to y = 320
+	pub fn overworld(biome: u32) -> Self {
+		Self::new(OVERWORLD_MIN_Y, OVERWORLD_SECTIONS, biome)
+	}
+
+	pub fn from_sections(min_y: i32, sections: Vec<ChunkSection>) -> Self {
+		Self {
+			min_y,
+			sections,
+			block_entities: BTreeMap::new(),
+		}
+	}
+
+	/// Read a column back out of the `data` of a chunk packet, see [read_sections]
+	pub fn read(min_y: i32, data: &[u8], sections: usize) -> Result<Self, SerializingErr> {
+		Ok(Self::from_sections(min_y, read_sections(data, sections)?))
+	}
+
+	/// The lowest y in the column
+	pub fn min_y(&self) -> i32 {
+		self.min_y
+	}
+
+	/// The height of the column in blocks
+	pub fn height(&self) -> u32 {
+		self.sections.len() as u32 * 16
+	}
+
+	/// One above the highest y in the column
+	pub fn max_y(&self) -> i32 {
+		self.min_y + self.height() as i32
+	}
+
+	pub fn sections(&self) -> &[ChunkSection] {
+		&self.sections
+	}
+
+	pub fn sections_mut(&mut self) -> &mut [ChunkSection] {
+		&mut self.sections
+	}
+
+	pub fn into_sections(self) -> Vec<ChunkSection> {
+		self.sections
+	}
+
+	/// The section that contains a y, along with the y inside of it
+	fn locate(&self, y: i32) -> Option<(usize, usize)> {
+		if y < self.min_y || y >= self.max_y() {
+			return None;
+		}
+
+		let offset = (y - self.min_y) as usize;
+		Some((offset / 16, offset % 16))
+	}
+
+	/// The section that contains a y
+	pub fn section_at(&self, y: i32) -> Option<&ChunkSection> {
+		self.locate(y).map(|(section, _)| &self.sections[section])
+	}
+
+	/// The block state at a position, with `x` and `z` inside the column and `y` in world coordinates.
+	/// Everything above and below the column is air.
+	pub fn block(&self, x: usize, y: i32, z: usize) -> u32 {
+		match self.locate(y) {
+			Some((section, y)) => self.sections[section].block(x, y, z),
+			None => AIR,
+		}
+	}
+
+	/// Set the block state at a position, returning the state that was there. Nothing is set above or below
+	/// the column, which returns `None`.
+	pub fn set_block(&mut self, x: usize, y: i32, z: usize, block_state: u32) -> Option<u32> {
+		let (section, y) = self.locate(y)?;
+		Some(self.sections[section].set_block(x, y, z, block_state))
+	}
+
+	/// Set every block from `from_y` up to and including `to_y`, such as the layers of a flat world
+	pub fn fill_layers(&mut self, from_y: i32, to_y: i32, block_state: u32) {
+		for y in from_y.max(self.min_y)..=to_y.min(self.max_y() - 1) {
+			let (section, local_y) = self.locate(y).expect("y is inside of the column");
+
+			if local_y == 0 && y + 15 <= to_y {
+				let biomes = self.sections[section].biomes.clone();
+				self.sections[section] = ChunkSection { biomes, ..ChunkSection::filled(block_state, 0) };
+				continue;
+			} else if self.sections[section].block_states.single_value() == Some(block_state) {
+				continue;
+			}
+
+			for z in 0..16 {
+				for x in 0..16 {
+					self.sections[section].set_block(x, local_y, z, block_state);
+				}
+			}
+		}
+	}
+
+	/// The number of blocks that aren't air, from the counts that the sections keep
+	pub fn block_count(&self) -> usize {
+		self.sections.iter().map(|section| section.block_count.max(0) as usize).sum()
+	}
+
+	/// Count the blocks of every section again, see [ChunkSection::recount]
+	pub fn recount<F: Fn(u32) -> bool>(&mut self, is_air: F) {
+		for section in &mut self.sections {
+			section.recount(&is_air);
+		}
+	}
+
+	/// The biome at a position, with `y` in world coordinates. Like on the client, positions above and below
+	/// the column get the biome of the highest or lowest cell.
+	pub fn biome(&self, x: usize, y: i32, z: usize) -> u32 {
+		let (section, y) = self.locate(y.clamp(self.min_y, self.max_y() - 1)).expect("y is inside of the column");
+		self.sections[section].biome(x, y, z)
+	}
+
+	/// Set the biome of the 4x4x4 cell that a position is in, returning the biome that was there. Nothing is
+	/// set above or below the column, which returns `None`.
+	pub fn set_biome(&mut self, x: usize, y: i32, z: usize, biome: u32) -> Option<u32> {
+		let (section, y) = self.locate(y)?;
+		Some(self.sections[section].set_biome(x, y, z, biome))
+	}
+
+	/// Set the biome of every cell from the one containing `from_y` up to the one containing `to_y`, such as
+	/// to give the surface a different biome than caves. Biome ids are the indices of the `worldgen/biome`
+	/// registry, see [GlobalPalette::biome_id](crate::world::block_state::GlobalPalette::biome_id).
+	pub fn fill_biome(&mut self, from_y: i32, to_y: i32, biome: u32) {
+		let from_y = from_y.max(self.min_y);
+		let from_y = from_y - from_y.rem_euclid(4);
+		let to_y = (to_y - to_y.rem_euclid(4) + 3).min(self.max_y() - 1);
+
+		for y in (from_y..=to_y).step_by(4) {
+			let (section, local_y) = self.locate(y).expect("y is inside of the column");
+
+			if local_y == 0 && y + 15 <= to_y {
+				self.sections[section].fill_biome(biome);
+				continue;
+			}
+
+			for z in (0..16).step_by(4) {
+				for x in (0..16).step_by(4) {
+					self.sections[section].set_biome(x, local_y, z, biome);
+				}
+			}
+		}
+	}
+
+	/// The height of the highest block in each column that `counts`, in `z * 16 + x` order and counted up
+	/// from `min_y`, or 0 if a column has none. See [Heightmaps](crate::world::heightmap::Heightmaps) for
+	/// the ones that the client uses.
+	pub fn heightmap<F: Fn(u32) -> bool>(&self, counts: F) -> [u32; 256] {
+		let mut heights = [0; 256];
+		let mut remaining = heights.len();
+
+		for (i, section) in self.sections.iter().enumerate().rev() {
+			match section.block_states.single_value() {
+				Some(state) if !counts(state) => continue,
+				Some(_) => {
+					heights.iter_mut().filter(|h| **h == 0).for_each(|h| *h = i as u32 * 16 + 16);
+					break;
+				}
+				None => {}
+			}
+
+			for y in (0..16).rev() {
+				for (column, height) in heights.iter_mut().enumerate().filter(|(_, h)| **h == 0) {
+					if counts(section.block(column % 16, y, column / 16)) {
+						*height = (i * 16 + y) as u32 + 1;
+						remaining -= 1;
+					}
+				}
+			}
+
+			if remaining == 0 {
+				break;
+			}
+		}
+
+		heights
+	}
+
+	pub fn block_entity(&self, x: usize, y: i32, z: usize) -> Option<&BlockEntity> {
+		self.block_entities.get(&(x, y, z))
+	}
+
+	pub fn block_entity_mut(&mut self, x: usize, y: i32, z: usize) -> Option<&mut BlockEntity> {
+		self.block_entities.get_mut(&(x, y, z))
+	}
+
+	/// Attach a block entity to a position, returning the one that was there. The block has to be set
+	/// separately, and setting another block doesn't remove its block entity. Nothing is attached above or
+	/// below the column.
+	pub fn set_block_entity(&mut self, x: usize, y: i32, z: usize, block_entity: BlockEntity) -> Option<BlockEntity> {
+		if x >= 16 || z >= 16 || self.locate(y).is_none() {
+			return None;
+		}
+
+		self.block_entities.insert((x, y, z), block_entity)
+	}
+
+	pub fn remove_block_entity(&mut self, x: usize, y: i32, z: usize) -> Option<BlockEntity> {
+		self.block_entities.remove(&(x, y, z))
+	}
+
+	/// Every block entity with its position
+	pub fn block_entities(&self) -> impl Iterator<Item = ((usize, i32, usize), &BlockEntity)> {
+		self.block_entities.iter().map(|(position, block_entity)| (*position, block_entity))
+	}
+
+	/// A [ChunkDataBuilder] with the sections of this column, which still needs heightmaps and light. Block
+	/// entities are left out, see [ChunkColumn::builder_with_block_entities].
+	pub fn builder(&self, chunk_x: i32, chunk_z: i32) -> ChunkDataBuilder {
+		ChunkDataBuilder::new(chunk_x, chunk_z).sections(self.sections.clone())
+	}
+
+	/// A [ChunkDataBuilder] with the sections and block entities of this column. The type of each block
+	/// entity is sent as an id, so it has to be in the palette.
+	pub fn builder_with_block_entities(&self, chunk_x: i32, chunk_z: i32, palette: &GlobalPalette) -> Result<ChunkDataBuilder, WorldError> {
+		let mut builder = self.builder(chunk_x, chunk_z);
+
+		for ((x, y, z), block_entity) in &self.block_entities {
+			let kind = palette.require_block_entity_type(&block_entity.kind)?;
+			builder = builder.block_entity(*x as u8, *y as i16, *z as u8, kind as i32, block_entity.data.clone());
+		}
+
+		Ok(builder)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::play::chunk::{AIR, OVERWORLD_SECTIONS};
+	use crate::protocol_types::datatypes::identifier::Identifier;
+	use crate::world::block_entity::BlockEntity;
+	use crate::world::block_state::GlobalPalette;
+	use crate::world::column::ChunkColumn;
+
+	#[test]
+	fn test_chunk_column() {
+		let mut column = ChunkColumn::overworld(0);
+		assert_eq!((column.min_y(), column.max_y(), column.height()), (-64, 320, 384));
+
+		assert_eq!(column.set_block(0, -64, 0, 5), Some(AIR));
+		assert_eq!(column.set_block(15, 319, 15, 6), Some(AIR));
+		assert_eq!(column.set_block(0, 320, 0, 6), None);
+		assert_eq!(column.set_block(0, -65, 0, 6), None);
+		assert_eq!(column.block(0, -64, 0), 5);
+		assert_eq!(column.block(0, 400, 0), AIR);
+		assert_eq!(column.sections()[OVERWORLD_SECTIONS - 1].block_count, 1);
+
+		// a whole section and a few layers of the next
+		column.fill_layers(-64, -45, 1);
+		assert_eq!(column.block_count(), 20 * 256 + 1);
+		assert_eq!(column.sections()[0].block_states.single_value(), Some(1));
+		assert_eq!(column.block(7, -45, 7), 1);
+		assert_eq!(column.block(7, -44, 7), AIR);
+
+		// biomes are set in cells of 4, so this covers -64 up to -45
+		column.fill_biome(-62, -46, 3);
+		column.set_biome(15, 200, 15, 4);
+		assert_eq!((column.biome(0, -64, 0), column.biome(0, -45, 0), column.biome(0, -44, 0)), (3, 3, 0));
+		assert_eq!(column.biome(12, 203, 13), 4);
+		assert_eq!(column.biome(0, -100, 0), 3);
+		assert_eq!(column.sections()[0].biomes.single_value(), Some(3));
+		assert_eq!(column.set_biome(0, 400, 0, 4), None);
+
+		column.recount(|state| state == AIR || state == 6);
+		assert_eq!(column.block_count(), 20 * 256);
+
+		let spec = column.builder(0, 0).build().unwrap();
+		assert_eq!(ChunkColumn::read(-64, &spec.data, OVERWORLD_SECTIONS).unwrap(), column);
+	}
+
+	#[test]
+	fn test_column_block_entities() {
+		let chest = Identifier::minecraft("chest").unwrap();
+		let mut column = ChunkColumn::overworld(0);
+		assert_eq!(column.set_block_entity(3, -10, 4, BlockEntity::new(chest.clone())), None);
+		assert_eq!(column.set_block_entity(3, 320, 4, BlockEntity::new(chest.clone())), None);
+		assert_eq!(column.set_block_entity(16, 0, 4, BlockEntity::new(chest.clone())), None);
+		assert_eq!(column.block_entities().count(), 1);
+
+		let mut palette = GlobalPalette::new();
+		assert!(column.builder_with_block_entities(1, 2, &palette).is_err());
+
+		palette.insert_block_entity_type(1, chest.clone());
+		let spec = column.builder_with_block_entities(1, 2, &palette).unwrap().build().unwrap();
+		assert_eq!(spec.block_entities.len(), 1);
+		assert_eq!((spec.block_entities[0].packed_xz, spec.block_entities[0].y, spec.block_entities[0].block_entity_type.0), (0x34, -10, 1));
+
+		assert_eq!(column.remove_block_entity(3, -10, 4).unwrap().kind, chest);
+		assert!(column.block_entity(3, -10, 4).is_none());
+	}
+}
@@ -0,0 +1,99 @@
+//! The heightmaps that the chunk packet needs, found from the blocks of a [ChunkColumn]. A heightmap holds
+//! one height for each column of the chunk, which is one above its highest block of some kind counted up
+//! from the bottom of the world, or 0 if it has none.
+//!
+//! The ids of block states don't say whether a block is air or blocks motion, so that is up to the caller.
+
+use crate::protocol::play::chunk::{MOTION_BLOCKING, pack_heightmap, unpack_heightmap, WORLD_SURFACE};
+use crate::protocol_types::datatypes::nbt::nbt::NbtCompound;
+use crate::world::column::ChunkColumn;
+use crate::world::world_error::WorldError;
+
+/// The [MOTION_BLOCKING] and [WORLD_SURFACE] heightmaps of a column, in `z * 16 + x` order
+///
+/// ```
+/// # use sandstone::world::column::ChunkColumn;
+/// # use sandstone::world::heightmap::Heightmaps;
+/// let mut column = ChunkColumn::overworld(0);
+/// column.fill_layers(-64, -61, 1); // stone
+/// column.set_block(0, 10, 0, 2); // a flower
+///
+/// let heightmaps = Heightmaps::compute(&column, |state| state == 0, |state| state == 1);
+/// assert_eq!(heightmaps.world_surface[0], 75);
+/// assert_eq!(heightmaps.motion_blocking[0], 4);
+///
+/// let packet = column.builder(0, 0).heightmaps(heightmaps.to_nbt().unwrap()).packet().unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Heightmaps {
+	/// The highest block that blocks motion or holds a fluid, which the client uses for rain and snow
+	pub motion_blocking: [u32; 256],
+	/// The highest block that isn't air
+	pub world_surface: [u32; 256],
+	world_height: u32,
+}
+
+impl Heightmaps {
+	/// Find both heightmaps of a column, with `is_air` for [WORLD_SURFACE] and `blocks_motion` for
+	/// [MOTION_BLOCKING]. Water, lava and waterlogged blocks count as blocking motion.
+	pub fn compute<A: Fn(u32) -> bool, M: Fn(u32) -> bool>(column: &ChunkColumn, is_air: A, blocks_motion: M) -> Self {
+		Self {
+			motion_blocking: column.heightmap(blocks_motion),
+			world_surface: column.heightmap(|state| !is_air(state)),
+			world_height: column.height(),
+		}
+	}
+
+	/// Read the heightmaps of a chunk packet or a saved chunk. Heightmaps that are missing are all 0.
+	pub fn from_nbt(nbt: &NbtCompound, world_height: u32) -> Result<Self, WorldError> {
+		let read = |name: &str| match nbt.get_long_array(name) {
+			Ok(packed) => Ok(unpack_heightmap(packed, world_height)?),
+			Err(_) => Ok::<_, WorldError>([0; 256]),
+		};
+
+		Ok(Self {
+			motion_blocking: read(MOTION_BLOCKING)?,
+			world_surface: read(WORLD_SURFACE)?,
+			world_height,
+		})
+	}
+
+	/// Pack both heightmaps into the compound that the chunk packet and saved chunks use, see
+	/// [pack_heightmap]. This fails for a column without any sections.
+	pub fn to_nbt(&self) -> Result<NbtCompound, WorldError> {
+		let mut nbt = NbtCompound::new::<String>(None);
+		nbt.add(MOTION_BLOCKING, pack_heightmap(&self.motion_blocking, self.world_height)?);
+		nbt.add(WORLD_SURFACE, pack_heightmap(&self.world_surface, self.world_height)?);
+		Ok(nbt)
+	}
+
+	/// The height of the world that the heightmaps are packed for
+	pub fn world_height(&self) -> u32 {
+		self.world_height
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::protocol::play::chunk::AIR;
+	use crate::world::column::ChunkColumn;
+	use crate::world::heightmap::Heightmaps;
+
+	#[test]
+	fn test_heightmaps() {
+		let mut column = ChunkColumn::overworld(0);
+		column.fill_layers(-64, 0, 1);
+		column.set_block(5, 319, 7, 1);
+		column.set_block(6, 100, 7, 3);
+		column.set_block(6, 20, 7, 1);
+
+		let heightmaps = Heightmaps::compute(&column, |state| state == AIR, |state| state == 1);
+		assert_eq!(heightmaps.world_surface[0], 65);
+		assert_eq!(heightmaps.world_surface[7 * 16 + 5], 384);
+		assert_eq!(heightmaps.world_surface[7 * 16 + 6], 165);
+		assert_eq!(heightmaps.motion_blocking[7 * 16 + 6], 85);
+		assert_eq!(Heightmaps::compute(&ChunkColumn::overworld(0), |state| state == AIR, |_| true).world_surface, [0; 256]);
+
+		assert_eq!(Heightmaps::from_nbt(&heightmaps.to_nbt().unwrap(), 384).unwrap(), heightmaps);
+	}
+}
@@ -0,0 +1,11 @@
+//! Worlds held in memory. These are made of the same [ChunkSection](crate::protocol::play::chunk::ChunkSection)s
+//! that the chunk packet sends, so a column can be sent to a client without converting it first.
+
+#[cfg(feature = "anvil")]
+pub mod anvil;
+pub mod block_entity;
+pub mod block_state;
+pub mod column;
+pub mod heightmap;
+pub mod schematic;
+pub mod world_error;
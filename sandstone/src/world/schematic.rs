@@ -0,0 +1,342 @@
+//! Loads structures saved as Sponge schematics (`.schem`), the format that WorldEdit and most other tools
+//! export, so that a lobby or map can be built in a client and pasted into [ChunkColumn]s without a whole
+//! world. Versions 2 and 3 of the format are supported. Biomes and entities in the schematic are ignored.
+//!
+//! Blocks are named in the schematic, and are turned into ids with a [GlobalPalette] when it is loaded.
+//! See [here](https://github.com/SpongePowered/Schematic-Specification) for more.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::protocol::serialization::McDeserializer;
+use crate::protocol_types::datatypes::nbt::nbt::{NbtCompound, NbtTag};
+use crate::protocol_types::datatypes::var_types::VarInt;
+use crate::world::block_entity::BlockEntity;
+use crate::world::block_state::{BlockState, GlobalPalette};
+use crate::world::column::ChunkColumn;
+use crate::world::world_error::WorldError;
+
+/// A box of blocks and their block entities, with positions relative to its lowest corner
+///
+/// ```no_run
+/// # use sandstone::world::block_state::GlobalPalette;
+/// # use sandstone::world::column::ChunkColumn;
+/// # use sandstone::world::schematic::Schematic;
+/// let palette = GlobalPalette::from_blocks_report(&std::fs::read_to_string("blocks.json").unwrap()).unwrap();
+/// let lobby = Schematic::read_from_file("lobby.schem", &palette).unwrap();
+///
+/// // every chunk that the lobby covers, with its lowest corner at 0, 64, 0
+/// let columns = lobby.columns(0, 64, 0, &ChunkColumn::overworld(0));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schematic {
+	width: usize,
+	height: usize,
+	length: usize,
+	offset: (i32, i32, i32),
+	/// In `(y * length + z) * width + x` order
+	blocks: Vec<u32>,
+	block_entities: BTreeMap<(usize, usize, usize), BlockEntity>,
+}
+
+impl Schematic {
+	/// Read a schematic file, which is usually compressed with gzip
+	pub fn read_from_file<P: AsRef<Path>>(path: P, palette: &GlobalPalette) -> Result<Self, WorldError> {
+		Self::from_nbt(&NbtCompound::read_from_file(path)?, palette)
+	}
+
+	/// Read a schematic from its root compound. Version 3 puts everything inside of a `Schematic` compound,
+	/// while version 2 names the root compound `Schematic` instead.
+	pub fn from_nbt(nbt: &NbtCompound, palette: &GlobalPalette) -> Result<Self, WorldError> {
+		let schematic = nbt.get_compound("Schematic").unwrap_or(nbt);
+		let version = schematic.get_int("Version")?;
+
+		// version 3 moved the blocks into their own compound and the data of block entities into `Data`
+		let (blocks, block_data, block_entities) = match version {
+			2 => (schematic, "BlockData", "BlockEntities"),
+			3 => (schematic.get_compound("Blocks")?, "Data", "BlockEntities"),
+			_ => return Err(WorldError::InvalidSchematic(format!("Version {} isn't supported", version))),
+		};
+
+		let size = |name: &str| schematic.get_short(name).map(|s| s as u16 as usize);
+		let (width, height, length) = (size("Width")?, size("Height")?, size("Length")?);
+
+		let offset = match schematic.get_int_array("Offset") {
+			Ok(offset) if offset.len() == 3 => (offset.list[0], offset.list[1], offset.list[2]),
+			_ => (0, 0, 0),
+		};
+
+		let names = blocks.get_compound("Palette")?;
+		let mut ids = vec![None; names.len()];
+
+		for name in names.keys() {
+			let index = names.get_int(name)? as usize;
+			let state = BlockState::from_str(name)?;
+
+			*ids.get_mut(index).ok_or_else(|| WorldError::InvalidSchematic(format!("{} has the index {} in a palette of {}", name, index, names.len())))? = Some(palette.require_block(&state)?);
+		}
+
+		let data = blocks.get_byte_array(block_data)?.iter().map(|b| *b as u8).collect::<Vec<_>>();
+		let volume = width * height * length;
+
+		// each block takes at least one byte, so this is checked before trusting the size enough to allocate
+		if volume > data.len() {
+			return Err(WorldError::InvalidSchematic(format!("A {}x{}x{} schematic can't fit in {} bytes of blocks", width, height, length, data.len())));
+		}
+
+		let mut deserializer = McDeserializer::new(&data);
+		let mut block_ids = Vec::with_capacity(volume);
+
+		while !deserializer.is_at_end() {
+			let index = VarInt::read_from(&mut deserializer)?.0 as usize;
+			block_ids.push(ids.get(index).copied().flatten().ok_or_else(|| WorldError::InvalidSchematic(format!("The palette has no index {}", index)))?);
+		}
+
+		if block_ids.len() != volume {
+			return Err(WorldError::InvalidSchematic(format!("Expected {} blocks in a {}x{}x{} schematic, got {}", volume, width, height, length, block_ids.len())));
+		}
+
+		let mut schematic = Self {
+			width,
+			height,
+			length,
+			offset,
+			blocks: block_ids,
+			block_entities: BTreeMap::new(),
+		};
+
+		for tag in blocks.get_list(block_entities).map(|list| list.list.as_slice()).unwrap_or_default() {
+			let NbtTag::Compound(block_entity) = tag else {
+				return Err(WorldError::InvalidSchematic("A block entity isn't a compound".to_string()));
+			};
+
+			let (position, block_entity) = read_block_entity(block_entity, version)?;
+
+			if !schematic.contains(position.0, position.1, position.2) {
+				return Err(WorldError::InvalidSchematic(format!("{} at {:?} is outside of the schematic", block_entity.kind, position)));
+			}
+
+			schematic.block_entities.insert(position, block_entity);
+		}
+
+		Ok(schematic)
+	}
+
+	pub fn width(&self) -> usize {
+		self.width
+	}
+
+	pub fn height(&self) -> usize {
+		self.height
+	}
+
+	pub fn length(&self) -> usize {
+		self.length
+	}
+
+	/// Where the tool that saved the schematic would paste it, relative to the player
+	pub fn offset(&self) -> (i32, i32, i32) {
+		self.offset
+	}
+
+	fn contains(&self, x: usize, y: usize, z: usize) -> bool {
+		x < self.width && y < self.height && z < self.length
+	}
+
+	/// The block state at a position, or `None` outside of the schematic
+	pub fn block(&self, x: usize, y: usize, z: usize) -> Option<u32> {
+		self.contains(x, y, z).then(|| self.blocks[(y * self.length + z) * self.width + x])
+	}
+
+	pub fn block_entity(&self, x: usize, y: usize, z: usize) -> Option<&BlockEntity> {
+		self.block_entities.get(&(x, y, z))
+	}
+
+	/// The chunks that the schematic covers when its lowest corner is at `x`, `z`
+	pub fn chunks(&self, x: i32, z: i32) -> impl Iterator<Item = (i32, i32)> {
+		let (max_x, max_z) = (x + self.width.max(1) as i32 - 1, z + self.length.max(1) as i32 - 1);
+		(z >> 4..=max_z >> 4).flat_map(move |chunk_z| (x >> 4..=max_x >> 4).map(move |chunk_x| (chunk_x, chunk_z)))
+	}
+
+	/// Paste the part of the schematic that is inside of a column, with the lowest corner of the schematic
+	/// at `x`, `y`, `z` in world coordinates. Every block is set including air, and the block entities in
+	/// the pasted area are replaced by the ones from the schematic. Blocks above or below the column are
+	/// left out.
+	pub fn paste(&self, column: &mut ChunkColumn, chunk_x: i32, chunk_z: i32, x: i32, y: i32, z: i32) {
+		let clamp = |from: i32, size: usize, chunk: i32| {
+			let start = (chunk * 16 - from).clamp(0, size as i32) as usize;
+			let end = (chunk * 16 + 16 - from).clamp(0, size as i32) as usize;
+			start..end
+		};
+
+		for local_y in 0..self.height {
+			let world_y = y + local_y as i32;
+
+			for local_z in clamp(z, self.length, chunk_z) {
+				for local_x in clamp(x, self.width, chunk_x) {
+					let (column_x, column_z) = ((x + local_x as i32).rem_euclid(16) as usize, (z + local_z as i32).rem_euclid(16) as usize);
+
+					if column.set_block(column_x, world_y, column_z, self.blocks[(local_y * self.length + local_z) * self.width + local_x]).is_none() {
+						continue;
+					}
+
+					match self.block_entities.get(&(local_x, local_y, local_z)) {
+						Some(block_entity) => column.set_block_entity(column_x, world_y, column_z, block_entity.clone()),
+						None => column.remove_block_entity(column_x, world_y, column_z),
+					};
+				}
+			}
+		}
+	}
+
+	/// Paste the schematic into copies of `empty`, one for each chunk that it covers, see [Schematic::paste]
+	pub fn columns(&self, x: i32, y: i32, z: i32, empty: &ChunkColumn) -> BTreeMap<(i32, i32), ChunkColumn> {
+		self.chunks(x, z)
+			.map(|(chunk_x, chunk_z)| {
+				let mut column = empty.clone();
+				self.paste(&mut column, chunk_x, chunk_z, x, y, z);
+				((chunk_x, chunk_z), column)
+			})
+			.collect()
+	}
+}
+
+/// Read a block entity of a schematic, whose position is an int array in `Pos`. Version 2 keeps the
+/// data next to `Id` and `Pos`, while version 3 keeps it in `Data`.
+fn read_block_entity(nbt: &NbtCompound, version: i32) -> Result<((usize, usize, usize), BlockEntity), WorldError> {
+	let position = match nbt.get_int_array("Pos")?.list.as_slice() {
+		[x, y, z] if *x >= 0 && *y >= 0 && *z >= 0 => (*x as usize, *y as usize, *z as usize),
+		pos => return Err(WorldError::InvalidSchematic(format!("Invalid block entity position {:?}", pos))),
+	};
+
+	let mut data = match version {
+		3 => nbt.get_compound("Data").cloned().unwrap_or_else(|_| NbtCompound::new::<String>(None)),
+		_ => nbt.clone(),
+	};
+
+	data.root_name = None;
+	data.remove("Id");
+	data.remove("Pos");
+	data.add("id", NbtTag::String(nbt.get_string("Id")?.to_string()));
+
+	Ok((position, BlockEntity::from_nbt(&data)?))
+}
+
+#[cfg(test)]
+mod tests {
+	use std::str::FromStr;
+
+	use crate::protocol_types::datatypes::identifier::Identifier;
+	use crate::protocol_types::datatypes::nbt::nbt::{NbtByteArray, NbtCompound, NbtIntArray, NbtList, NbtTag};
+	use crate::world::block_entity::BlockEntity;
+	use crate::world::block_state::{BlockState, GlobalPalette};
+	use crate::world::column::ChunkColumn;
+	use crate::world::schematic::Schematic;
+	use crate::world::world_error::WorldError;
+
+	fn palette() -> GlobalPalette {
+		let mut palette = GlobalPalette::new();
+		palette.insert_block_state(0, BlockState::from_str("air").unwrap());
+		palette.insert_block_state(1, BlockState::from_str("stone").unwrap());
+		palette.insert_block_state(2, BlockState::from_str("chest[facing=north]").unwrap());
+		palette
+	}
+
+	/// A 3x2x2 schematic with stone on the bottom, a chest at 2, 1, 1 and air elsewhere
+	fn blocks(version: i32) -> (NbtCompound, NbtList) {
+		let mut names = NbtCompound::new::<String>(None);
+		names.add("minecraft:air", NbtTag::Int(0));
+		names.add("minecraft:stone", NbtTag::Int(1));
+		names.add("minecraft:chest[facing=north]", NbtTag::Int(2));
+
+		let mut data = vec![1; 6];
+		data.extend([0, 0, 0, 0, 0, 2]);
+
+		let mut items = NbtCompound::new::<String>(None);
+		items.add("Lock", NbtTag::String("key".to_string()));
+
+		let mut chest = NbtCompound::new::<String>(None);
+		chest.add("Id", NbtTag::String("minecraft:chest".to_string()));
+		chest.add("Pos", NbtIntArray::new(vec![2, 1, 1]));
+
+		if version == 3 {
+			chest.add("Data", items);
+		} else {
+			chest.add("Lock", NbtTag::String("key".to_string()));
+		}
+
+		let mut blocks = NbtCompound::new::<String>(None);
+		blocks.add("Palette", names);
+		blocks.add(if version == 3 { "Data" } else { "BlockData" }, NbtByteArray::new(data));
+		(blocks, NbtList::from_vec(vec![NbtTag::Compound(chest)]).unwrap())
+	}
+
+	fn schematic(version: i32) -> NbtCompound {
+		let (mut blocks, block_entities) = blocks(version);
+		let mut schematic = NbtCompound::new(Some(if version == 3 { "" } else { "Schematic" }));
+		let mut body = NbtCompound::new::<String>(None);
+		let target = if version == 3 { &mut body } else { &mut schematic };
+
+		target.add("Version", NbtTag::Int(version));
+		target.add("Width", NbtTag::Short(3));
+		target.add("Height", NbtTag::Short(2));
+		target.add("Length", NbtTag::Short(2));
+		target.add("Offset", NbtIntArray::new(vec![-1, 0, -1]));
+
+		if version == 3 {
+			blocks.add("BlockEntities", block_entities);
+			body.add("Blocks", blocks);
+			schematic.add("Schematic", body);
+		} else {
+			for key in ["Palette", "BlockData"] {
+				schematic.add(key, blocks.get(key).unwrap().clone());
+			}
+
+			schematic.add("BlockEntities", block_entities);
+		}
+
+		schematic
+	}
+
+	#[test]
+	fn test_schematic() {
+		let v2 = Schematic::from_nbt(&schematic(2), &palette()).unwrap();
+		let v3 = Schematic::from_nbt(&schematic(3), &palette()).unwrap();
+		assert_eq!(v2, v3);
+
+		assert_eq!((v3.width(), v3.height(), v3.length(), v3.offset()), (3, 2, 2, (-1, 0, -1)));
+		assert_eq!(v3.block(1, 0, 1), Some(1));
+		assert_eq!(v3.block(2, 1, 1), Some(2));
+		assert_eq!(v3.block(3, 0, 0), None);
+		assert_eq!(v3.block_entity(2, 1, 1).unwrap().data.get_string("Lock").unwrap(), "key");
+
+		// a block the palette doesn't know
+		assert!(Schematic::from_nbt(&schematic(3), &GlobalPalette::new()).is_err());
+
+		// across the corner of four chunks
+		let mut empty = ChunkColumn::overworld(0);
+		empty.set_block_entity(14, 65, 15, BlockEntity::new(Identifier::minecraft("sign").unwrap()));
+		let columns = v3.columns(-2, 64, -1, &empty);
+		assert_eq!(columns.keys().copied().collect::<Vec<_>>(), vec![(-1, -1), (-1, 0), (0, -1), (0, 0)]);
+		assert_eq!(columns[&(-1, -1)].block(14, 64, 15), 1);
+		assert_eq!(columns[&(0, 0)].block(0, 65, 0), 2);
+		assert_eq!(columns[&(0, 0)].block_entity(0, 65, 0).unwrap().kind.to_string(), "minecraft:chest");
+		assert_eq!(columns[&(-1, -1)].block_count(), 2);
+
+		// air is pasted over the sign too
+		assert!(columns[&(-1, -1)].block_entity(14, 65, 15).is_none());
+		assert!(columns[&(0, -1)].block_entity(14, 65, 15).is_some());
+	}
+
+	#[test]
+	fn test_oversized_schematic() {
+		// a header that claims 65535 blocks on each side, with the data of only 12
+		let mut nbt = schematic(2);
+		for key in ["Width", "Height", "Length"] {
+			nbt.add(key, NbtTag::Short(-1));
+		}
+
+		assert!(matches!(Schematic::from_nbt(&nbt, &palette()), Err(WorldError::InvalidSchematic(_))));
+	}
+}
@@ -0,0 +1,36 @@
+use std::io;
+
+use thiserror::Error;
+
+use crate::protocol::serialization::serializer_error::SerializingErr;
+use crate::protocol_types::datatypes::nbt::nbt_error::NbtError;
+
+/// An error while loading or saving a world
+#[derive(Error, Debug)]
+pub enum WorldError {
+	#[error("Unknown block state {0}")]
+	UnknownBlockState(String),
+	#[error("Unknown biome {0}")]
+	UnknownBiome(String),
+	#[error("Unknown block entity type {0}")]
+	UnknownBlockEntity(String),
+	#[error("Invalid block state {0}")]
+	InvalidBlockState(String),
+	#[error("Invalid data generator report: {0}")]
+	InvalidReport(String),
+	#[error("Invalid region file: {0}")]
+	InvalidRegion(String),
+	#[error("Invalid chunk: {0}")]
+	InvalidChunk(String),
+	#[error("Invalid schematic: {0}")]
+	InvalidSchematic(String),
+	#[error("Unsupported chunk compression {0}")]
+	UnsupportedCompression(u8),
+
+	#[error(transparent)]
+	Nbt(#[from] NbtError),
+	#[error(transparent)]
+	SerializingErr(#[from] SerializingErr),
+	#[error(transparent)]
+	IOError(#[from] io::Error),
+}